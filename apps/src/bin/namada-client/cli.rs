@@ -18,6 +18,9 @@ pub async fn main() -> Result<()> {
                 Sub::TxTransfer(TxTransfer(args)) => {
                     tx::submit_transfer(ctx, args).await;
                 }
+                Sub::TxMultiTransfer(TxMultiTransfer(args)) => {
+                    tx::submit_multi_transfer(ctx, args).await;
+                }
                 Sub::TxIbcTransfer(TxIbcTransfer(args)) => {
                     tx::submit_ibc_transfer(ctx, args).await;
                 }
@@ -30,6 +33,12 @@ pub async fn main() -> Result<()> {
                 Sub::TxInitValidator(TxInitValidator(args)) => {
                     tx::submit_init_validator(ctx, args).await;
                 }
+                Sub::TxChangeConsensusKey(TxChangeConsensusKey(args)) => {
+                    tx::submit_change_consensus_key(ctx, args).await;
+                }
+                Sub::TxUnjailValidator(TxUnjailValidator(args)) => {
+                    tx::submit_unjail_validator(ctx, args).await;
+                }
                 Sub::TxInitProposal(TxInitProposal(args)) => {
                     tx::submit_init_proposal(ctx, args).await;
                 }
@@ -39,6 +48,9 @@ pub async fn main() -> Result<()> {
                 Sub::TxRevealPk(TxRevealPk(args)) => {
                     tx::submit_reveal_pk(ctx, args).await;
                 }
+                Sub::TxSigned(TxSigned(args)) => {
+                    tx::submit_signed_tx(ctx, args).await;
+                }
                 Sub::Bond(Bond(args)) => {
                     tx::submit_bond(ctx, args).await;
                 }
@@ -48,6 +60,10 @@ pub async fn main() -> Result<()> {
                 Sub::Withdraw(Withdraw(args)) => {
                     tx::submit_withdraw(ctx, args).await;
                 }
+                #[cfg(feature = "dev")]
+                Sub::TxFaucetWithdrawal(TxFaucetWithdrawal(args)) => {
+                    tx::submit_faucet_withdrawal(ctx, args).await;
+                }
                 // Ledger queries
                 Sub::QueryEpoch(QueryEpoch(args)) => {
                     rpc::query_epoch(args).await;
@@ -82,6 +98,15 @@ pub async fn main() -> Result<()> {
                 Sub::QueryRawBytes(QueryRawBytes(args)) => {
                     rpc::query_raw_bytes(ctx, args).await;
                 }
+                Sub::DumpStorage(DumpStorage(args)) => {
+                    rpc::dump_storage(ctx, args).await;
+                }
+                Sub::QueryAccountVp(QueryAccountVp(args)) => {
+                    rpc::query_account_vp(ctx, args).await;
+                }
+                Sub::QueryIbcTimedOutPackets(QueryIbcTimedOutPackets(args)) => {
+                    rpc::query_ibc_timed_out_packets(args).await;
+                }
 
                 Sub::QueryProposal(QueryProposal(args)) => {
                     rpc::query_proposal(ctx, args).await;
@@ -108,6 +133,9 @@ pub async fn main() -> Result<()> {
             Utils::InitGenesisValidator(InitGenesisValidator(args)) => {
                 utils::init_genesis_validator(global_args, args)
             }
+            Utils::DecodeTx(DecodeTx(args)) => {
+                utils::decode_tx(global_args, args)
+            }
         },
     }
     Ok(())
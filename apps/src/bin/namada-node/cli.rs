@@ -2,18 +2,28 @@
 
 use eyre::{Context, Result};
 use namada_apps::cli::{self, cmds};
+use namada_apps::logging;
 use namada_apps::node::ledger;
+use tracing_subscriber::filter::LevelFilter;
 
 pub fn main() -> Result<()> {
     let (cmd, mut ctx) = cli::namada_node_cli()?;
     if let Some(mode) = ctx.global_args.mode.clone() {
         ctx.config.ledger.tendermint.tendermint_mode = mode;
     }
+
+    // init logging, with the OpenTelemetry export if it's configured. The
+    // chain ID was logged with the config before the subscriber was set.
+    let _telemetry = logging::init_node_from_env_or(
+        LevelFilter::INFO,
+        &ctx.config.ledger.shell,
+    )?;
+    tracing::info!("Chain ID: {}", ctx.global_config.default_chain_id);
     match cmd {
         cmds::NamadaNode::Ledger(sub) => match sub {
             cmds::Ledger::Run(_) => {
-                let wasm_dir = ctx.wasm_dir();
-                ledger::run(ctx.config.ledger, wasm_dir);
+                let wasm_dirs = ctx.wasm_dirs();
+                ledger::run(ctx.config.ledger, wasm_dirs);
             }
             cmds::Ledger::Reset(_) => {
                 ledger::reset(ctx.config.ledger)
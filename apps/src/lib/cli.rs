@@ -149,19 +149,27 @@ pub mod cmds {
 
     impl Cmd for NamadaClient {
         fn add_sub(app: App) -> App {
+            // Development transactions
+            #[cfg(feature = "dev")]
+            let app =
+                app.subcommand(TxFaucetWithdrawal::def().display_order(4));
             app
                 // Simple transactions
                 .subcommand(TxCustom::def().display_order(1))
                 .subcommand(TxTransfer::def().display_order(1))
+                .subcommand(TxMultiTransfer::def().display_order(1))
                 .subcommand(TxIbcTransfer::def().display_order(1))
                 .subcommand(TxUpdateVp::def().display_order(1))
                 .subcommand(TxInitAccount::def().display_order(1))
                 .subcommand(TxRevealPk::def().display_order(1))
+                .subcommand(TxSigned::def().display_order(1))
                 // Proposal transactions
                 .subcommand(TxInitProposal::def().display_order(1))
                 .subcommand(TxVoteProposal::def().display_order(1))
                 // PoS transactions
                 .subcommand(TxInitValidator::def().display_order(2))
+                .subcommand(TxChangeConsensusKey::def().display_order(2))
+                .subcommand(TxUnjailValidator::def().display_order(2))
                 .subcommand(Bond::def().display_order(2))
                 .subcommand(Unbond::def().display_order(2))
                 .subcommand(Withdraw::def().display_order(2))
@@ -176,6 +184,9 @@ pub mod cmds {
                 .subcommand(QuerySlashes::def().display_order(3))
                 .subcommand(QueryResult::def().display_order(3))
                 .subcommand(QueryRawBytes::def().display_order(3))
+                .subcommand(DumpStorage::def().display_order(3))
+                .subcommand(QueryAccountVp::def().display_order(3))
+                .subcommand(QueryIbcTimedOutPackets::def().display_order(3))
                 .subcommand(QueryProposal::def().display_order(3))
                 .subcommand(QueryProposalResult::def().display_order(3))
                 .subcommand(QueryProtocolParameters::def().display_order(3))
@@ -187,12 +198,19 @@ pub mod cmds {
             use NamadaClientWithContext::*;
             let tx_custom = Self::parse_with_ctx(matches, TxCustom);
             let tx_transfer = Self::parse_with_ctx(matches, TxTransfer);
+            let tx_multi_transfer =
+                Self::parse_with_ctx(matches, TxMultiTransfer);
             let tx_ibc_transfer = Self::parse_with_ctx(matches, TxIbcTransfer);
             let tx_update_vp = Self::parse_with_ctx(matches, TxUpdateVp);
             let tx_init_account = Self::parse_with_ctx(matches, TxInitAccount);
             let tx_init_validator =
                 Self::parse_with_ctx(matches, TxInitValidator);
+            let tx_change_consensus_key =
+                Self::parse_with_ctx(matches, TxChangeConsensusKey);
+            let tx_unjail_validator =
+                Self::parse_with_ctx(matches, TxUnjailValidator);
             let tx_reveal_pk = Self::parse_with_ctx(matches, TxRevealPk);
+            let tx_signed = Self::parse_with_ctx(matches, TxSigned);
             let tx_init_proposal =
                 Self::parse_with_ctx(matches, TxInitProposal);
             let tx_vote_proposal =
@@ -212,21 +230,35 @@ pub mod cmds {
             let query_slashes = Self::parse_with_ctx(matches, QuerySlashes);
             let query_result = Self::parse_with_ctx(matches, QueryResult);
             let query_raw_bytes = Self::parse_with_ctx(matches, QueryRawBytes);
+            let dump_storage = Self::parse_with_ctx(matches, DumpStorage);
+            let query_account_vp =
+                Self::parse_with_ctx(matches, QueryAccountVp);
+            let query_ibc_timed_out_packets =
+                Self::parse_with_ctx(matches, QueryIbcTimedOutPackets);
             let query_proposal = Self::parse_with_ctx(matches, QueryProposal);
             let query_proposal_result =
                 Self::parse_with_ctx(matches, QueryProposalResult);
             let query_protocol_parameters =
                 Self::parse_with_ctx(matches, QueryProtocolParameters);
+            #[cfg(feature = "dev")]
+            let tx_faucet_withdrawal =
+                Self::parse_with_ctx(matches, TxFaucetWithdrawal);
+            #[cfg(not(feature = "dev"))]
+            let tx_faucet_withdrawal = None;
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
             tx_custom
                 .or(tx_transfer)
+                .or(tx_multi_transfer)
                 .or(tx_ibc_transfer)
                 .or(tx_update_vp)
                 .or(tx_init_account)
                 .or(tx_reveal_pk)
+                .or(tx_signed)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
                 .or(tx_init_validator)
+                .or(tx_change_consensus_key)
+                .or(tx_unjail_validator)
                 .or(bond)
                 .or(unbond)
                 .or(withdraw)
@@ -240,9 +272,13 @@ pub mod cmds {
                 .or(query_slashes)
                 .or(query_result)
                 .or(query_raw_bytes)
+                .or(dump_storage)
+                .or(query_account_vp)
+                .or(query_ibc_timed_out_packets)
                 .or(query_proposal)
                 .or(query_proposal_result)
                 .or(query_protocol_parameters)
+                .or(tx_faucet_withdrawal)
                 .or(utils)
         }
     }
@@ -281,14 +317,18 @@ pub mod cmds {
         // Ledger cmds
         TxCustom(TxCustom),
         TxTransfer(TxTransfer),
+        TxMultiTransfer(TxMultiTransfer),
         TxIbcTransfer(TxIbcTransfer),
         QueryResult(QueryResult),
         TxUpdateVp(TxUpdateVp),
         TxInitAccount(TxInitAccount),
         TxInitValidator(TxInitValidator),
+        TxChangeConsensusKey(TxChangeConsensusKey),
+        TxUnjailValidator(TxUnjailValidator),
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
         TxRevealPk(TxRevealPk),
+        TxSigned(TxSigned),
         Bond(Bond),
         Unbond(Unbond),
         Withdraw(Withdraw),
@@ -302,9 +342,14 @@ pub mod cmds {
         QueryCommissionRate(QueryCommissionRate),
         QuerySlashes(QuerySlashes),
         QueryRawBytes(QueryRawBytes),
+        DumpStorage(DumpStorage),
+        QueryAccountVp(QueryAccountVp),
+        QueryIbcTimedOutPackets(QueryIbcTimedOutPackets),
         QueryProposal(QueryProposal),
         QueryProposalResult(QueryProposalResult),
         QueryProtocolParameters(QueryProtocolParameters),
+        #[cfg(feature = "dev")]
+        TxFaucetWithdrawal(TxFaucetWithdrawal),
     }
 
     #[allow(clippy::large_enum_variant)]
@@ -977,6 +1022,28 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxMultiTransfer(pub args::TxMultiTransfer);
+
+    impl SubCmd for TxMultiTransfer {
+        const CMD: &'static str = "multi-transfer";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxMultiTransfer(args::TxMultiTransfer::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to transfer tokens from a \
+                     single source to many targets at once.",
+                )
+                .add_args::<args::TxMultiTransfer>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxIbcTransfer(pub args::TxIbcTransfer);
 
@@ -1062,6 +1129,50 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxChangeConsensusKey(pub args::TxChangeConsensusKey);
+
+    impl SubCmd for TxChangeConsensusKey {
+        const CMD: &'static str = "change-consensus-key";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxChangeConsensusKey(args::TxChangeConsensusKey::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to change a validator's \
+                     consensus key, which takes effect at the pipeline offset.",
+                )
+                .add_args::<args::TxChangeConsensusKey>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxUnjailValidator(pub args::TxUnjailValidator);
+
+    impl SubCmd for TxUnjailValidator {
+        const CMD: &'static str = "unjail-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxUnjailValidator(args::TxUnjailValidator::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to unjail a validator once its \
+                     jailing period is over.",
+                )
+                .add_args::<args::TxUnjailValidator>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct Bond(pub args::Bond);
 
@@ -1138,6 +1249,29 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct QueryIbcTimedOutPackets(pub args::Query);
+
+    impl SubCmd for QueryIbcTimedOutPackets {
+        const CMD: &'static str = "ibc-timed-out-packets";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryIbcTimedOutPackets(args::Query::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the IBC packets sent from the chain that the node \
+                     has marked as timed-out locally, because they have not \
+                     been acknowledged in time.",
+                )
+                .add_args::<args::Query>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryConversions(pub args::QueryConversions);
 
@@ -1312,6 +1446,47 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct DumpStorage(pub args::DumpStorage);
+
+    impl SubCmd for DumpStorage {
+        const CMD: &'static str = "dump-storage";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| DumpStorage(args::DumpStorage::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Dump the hex-encoded raw bytes of all the storage keys \
+                     under a given prefix. Intended for debugging only.",
+                )
+                .add_args::<args::DumpStorage>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryAccountVp(pub args::QueryAccountVp);
+
+    impl SubCmd for QueryAccountVp {
+        const CMD: &'static str = "query-account-vp";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryAccountVp(args::QueryAccountVp::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the hash of the validity predicate of an account")
+                .add_args::<args::QueryAccountVp>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxInitProposal(pub args::InitProposal);
 
@@ -1356,6 +1531,29 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxSigned(pub args::TxSigned);
+
+    impl SubCmd for TxSigned {
+        const CMD: &'static str = "signed-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxSigned(args::TxSigned::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Submit a transaction that has already been signed, e.g. \
+                     with an offline or a hardware wallet. The transaction is \
+                     broadcast as is, without being signed again.",
+                )
+                .add_args::<args::TxSigned>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxRevealPk(pub args::RevealPk);
 
@@ -1386,12 +1584,37 @@ pub mod cmds {
         }
     }
 
+    #[cfg(feature = "dev")]
+    #[derive(Clone, Debug)]
+    pub struct TxFaucetWithdrawal(pub args::FaucetWithdrawal);
+
+    #[cfg(feature = "dev")]
+    impl SubCmd for TxFaucetWithdrawal {
+        const CMD: &'static str = "request-funds";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxFaucetWithdrawal(args::FaucetWithdrawal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Request funds from a testnet faucet. A target may \
+                     withdraw a capped amount at most once per epoch.",
+                )
+                .add_args::<args::FaucetWithdrawal>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Utils {
         JoinNetwork(JoinNetwork),
         FetchWasms(FetchWasms),
         InitNetwork(InitNetwork),
         InitGenesisValidator(InitGenesisValidator),
+        DecodeTx(DecodeTx),
     }
 
     impl SubCmd for Utils {
@@ -1406,10 +1629,12 @@ pub mod cmds {
                     SubCmd::parse(matches).map(Self::InitNetwork);
                 let init_genesis =
                     SubCmd::parse(matches).map(Self::InitGenesisValidator);
+                let decode_tx = SubCmd::parse(matches).map(Self::DecodeTx);
                 join_network
                     .or(fetch_wasms)
                     .or(init_network)
                     .or(init_genesis)
+                    .or(decode_tx)
             })
         }
 
@@ -1420,6 +1645,7 @@ pub mod cmds {
                 .subcommand(FetchWasms::def())
                 .subcommand(InitNetwork::def())
                 .subcommand(InitGenesisValidator::def())
+                .subcommand(DecodeTx::def())
                 .setting(AppSettings::SubcommandRequiredElseHelp)
         }
     }
@@ -1503,6 +1729,28 @@ pub mod cmds {
                 .add_args::<args::InitGenesisValidator>()
         }
     }
+
+    #[derive(Clone, Debug)]
+    pub struct DecodeTx(pub args::DecodeTx);
+
+    impl SubCmd for DecodeTx {
+        const CMD: &'static str = "decode-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::DecodeTx::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Decode a serialized transaction or transaction data and \
+                     print it in a human-readable form.",
+                )
+                .add_args::<args::DecodeTx>()
+        }
+    }
 }
 
 pub mod args {
@@ -1516,6 +1764,7 @@ pub mod args {
     use namada::types::address::Address;
     use namada::types::chain::{ChainId, ChainIdPrefix};
     use namada::types::governance::ProposalVote;
+    use namada::types::hash::Hash;
     use namada::types::key::*;
     use namada::types::masp::MaspValue;
     use namada::types::storage::{self, Epoch};
@@ -1526,6 +1775,7 @@ pub mod args {
     use super::context::*;
     use super::utils::*;
     use super::{ArgGroup, ArgMatches};
+    use crate::client::decode_tx::TxDataType;
     use crate::client::types::{ParsedTxArgs, ParsedTxTransferArgs};
     use crate::config;
     use crate::config::TendermintMode;
@@ -1564,6 +1814,9 @@ pub mod args {
     const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
     const DRY_RUN_TX: ArgFlag = flag("dry-run");
     const EPOCH: ArgOpt<Epoch> = arg_opt("epoch");
+    #[cfg(feature = "dev")]
+    const FAUCET: ArgDefaultFromCtx<WalletAddress> =
+        arg_default_from_ctx("faucet", DefaultFn(|| "faucet".into()));
     const FORCE: ArgFlag = flag("force");
     const DONT_PREFETCH_WASM: ArgFlag = flag("dont-prefetch-wasm");
     const GAS_AMOUNT: ArgDefault<token::Amount> =
@@ -1588,6 +1841,7 @@ pub mod args {
     const MASP_VALUE: Arg<MaspValue> = arg("value");
     const MAX_COMMISSION_RATE_CHANGE: Arg<Decimal> =
         arg("max-commission-rate-change");
+    const MEMO: ArgOpt<String> = arg_opt("memo");
     const MODE: ArgOpt<String> = arg_opt("mode");
     const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
     const NO_CONVERSIONS: ArgFlag = flag("no-conversions");
@@ -1617,13 +1871,17 @@ pub mod args {
     const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
     const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
     const SUB_PREFIX: ArgOpt<String> = arg_opt("sub-prefix");
+    #[cfg(feature = "dev")]
+    const TARGET: Arg<WalletAddress> = arg("target");
     const TIMEOUT_HEIGHT: ArgOpt<u64> = arg_opt("timeout-height");
     const TIMEOUT_SEC_OFFSET: ArgOpt<u64> = arg_opt("timeout-sec-offset");
     const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
     const TOKEN: Arg<WalletAddress> = arg("token");
     const TRANSFER_SOURCE: Arg<WalletTransferSource> = arg("source");
     const TRANSFER_TARGET: Arg<WalletTransferTarget> = arg("target");
+    const TX_DATA_TYPE: ArgOpt<TxDataType> = arg_opt("type");
     const TX_HASH: Arg<String> = arg("tx-hash");
+    const TX_PATH: Arg<PathBuf> = arg("tx-path");
     const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
     const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
     const VALIDATOR: Arg<WalletAddress> = arg("validator");
@@ -1635,6 +1893,7 @@ pub mod args {
     const VALIDATOR_CODE_PATH: ArgOpt<PathBuf> = arg_opt("validator-code-path");
     const VALUE: ArgOpt<String> = arg_opt("value");
     const VIEWING_KEY: Arg<WalletViewingKey> = arg("key");
+    const VP_HASH_OPT: ArgOpt<Hash> = arg_opt("vp");
     const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
     const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
 
@@ -1749,6 +2008,42 @@ pub mod args {
         }
     }
 
+    /// Pre-signed transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxSigned {
+        /// The address of the ledger node as host:port
+        pub ledger_address: TendermintAddress,
+        /// Path to the file with the Borsh-serialized signed wrapper tx
+        pub tx_path: PathBuf,
+        /// Do not wait for the transaction to be applied
+        pub broadcast_only: bool,
+    }
+
+    impl Args for TxSigned {
+        fn parse(matches: &ArgMatches) -> Self {
+            let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
+            let tx_path = TX_PATH.parse(matches);
+            let broadcast_only = BROADCAST_ONLY.parse(matches);
+            Self {
+                ledger_address,
+                tx_path,
+                broadcast_only,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
+                .arg(TX_PATH.def().about(
+                    "The path to a file containing a Borsh-serialized wrapper \
+                     transaction, signed by the fee payer.",
+                ))
+                .arg(BROADCAST_ONLY.def().about(
+                    "Do not wait for the transaction to be applied. This will \
+                     return once the transaction is added to the mempool.",
+                ))
+        }
+    }
+
     /// Transfer transaction arguments
     #[derive(Clone, Debug)]
     pub struct TxTransfer {
@@ -1764,6 +2059,8 @@ pub mod args {
         pub sub_prefix: Option<String>,
         /// Transferred token amount
         pub amount: token::Amount,
+        /// Transfer memo
+        pub memo: Option<String>,
     }
 
     impl TxTransfer {
@@ -1789,6 +2086,7 @@ pub mod args {
             let token = TOKEN.parse(matches);
             let sub_prefix = SUB_PREFIX.parse(matches);
             let amount = AMOUNT.parse(matches);
+            let memo = MEMO.parse(matches);
             Self {
                 tx,
                 source,
@@ -1796,6 +2094,7 @@ pub mod args {
                 token,
                 sub_prefix,
                 amount,
+                memo,
             }
         }
 
@@ -1812,6 +2111,47 @@ pub mod args {
                 .arg(TOKEN.def().about("The transfer token."))
                 .arg(SUB_PREFIX.def().about("The token's sub prefix."))
                 .arg(AMOUNT.def().about("The amount to transfer in decimal."))
+                .arg(MEMO.def().about(
+                    "An optional memo for the transfer, e.g. an identifier \
+                     for the recipient, of up to 256 bytes.",
+                ))
+        }
+    }
+
+    /// Multi-recipient transfer transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxMultiTransfer {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Transfer source address
+        pub source: WalletAddress,
+        /// The path to the JSON file with the transfer outputs
+        pub outputs_path: PathBuf,
+    }
+
+    impl Args for TxMultiTransfer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let outputs_path = DATA_PATH.parse(matches);
+            Self {
+                tx,
+                source,
+                outputs_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account address. The source's key is used to \
+                     produce the signature.",
+                ))
+                .arg(DATA_PATH.def().about(
+                    "The data path file (json) with the list of the transfer \
+                     outputs, each with a `target` address, a `token` address \
+                     and an `amount` in decimal.",
+                ))
         }
     }
 
@@ -1898,6 +2238,9 @@ pub mod args {
         pub source: WalletAddress,
         /// Path to the VP WASM code file for the new account
         pub vp_code_path: Option<PathBuf>,
+        /// Hash of the VP WASM code for the new account, to be found among
+        /// the WASM artifacts
+        pub vp_code_hash: Option<Hash>,
         /// Public key for the new account
         pub public_key: WalletPublicKey,
     }
@@ -1907,11 +2250,13 @@ pub mod args {
             let tx = Tx::parse(matches);
             let source = SOURCE.parse(matches);
             let vp_code_path = CODE_PATH_OPT.parse(matches);
+            let vp_code_hash = VP_HASH_OPT.parse(matches);
             let public_key = PUBLIC_KEY.parse(matches);
             Self {
                 tx,
                 source,
                 vp_code_path,
+                vp_code_hash,
                 public_key,
             }
         }
@@ -1924,8 +2269,21 @@ pub mod args {
                 .arg(CODE_PATH_OPT.def().about(
                     "The path to the validity predicate WASM code to be used \
                      for the new account. Uses the default user VP if none \
-                     specified.",
+                     specified. The VP must be in the chain's VP whitelist, \
+                     if any.",
                 ))
+                .arg(
+                    VP_HASH_OPT
+                        .def()
+                        .about(
+                            "The hash of the validity predicate WASM code to \
+                             be used for the new account, in upper-case \
+                             hexadecimal encoding. The code is found among \
+                             the WASM artifacts and the hash must be in the \
+                             chain's VP whitelist, if any.",
+                        )
+                        .conflicts_with(CODE_PATH_OPT.name),
+                )
                 .arg(PUBLIC_KEY.def().about(
                     "A public key to be used for the new account in \
                      hexadecimal encoding.",
@@ -2019,6 +2377,74 @@ pub mod args {
         }
     }
 
+    /// Transaction to change a validator's consensus key arguments
+    #[derive(Clone, Debug)]
+    pub struct TxChangeConsensusKey {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address (should be self)
+        pub validator: WalletAddress,
+        /// The new consensus key, generated if none given
+        pub consensus_key: Option<WalletKeypair>,
+        /// Don't encrypt the generated keypair
+        pub unsafe_dont_encrypt: bool,
+    }
+
+    impl Args for TxChangeConsensusKey {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let consensus_key = VALIDATOR_CONSENSUS_KEY.parse(matches);
+            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                consensus_key,
+                unsafe_dont_encrypt,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about(
+                    "The validator's address whose consensus key to change.",
+                ))
+                .arg(VALIDATOR_CONSENSUS_KEY.def().about(
+                    "The new consensus key for the validator. A new one will \
+                     be generated if none given.",
+                ))
+                .arg(UNSAFE_DONT_ENCRYPT.def().about(
+                    "UNSAFE: Do not encrypt the generated keypair. Do not use \
+                     this for keys used in a live network.",
+                ))
+        }
+    }
+
+    /// Transaction to unjail a validator arguments
+    #[derive(Clone, Debug)]
+    pub struct TxUnjailValidator {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address (should be self)
+        pub validator: WalletAddress,
+    }
+
+    impl Args for TxUnjailValidator {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            Self { tx, validator }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>().arg(
+                VALIDATOR
+                    .def()
+                    .about("The address of the jailed validator to unjail."),
+            )
+        }
+    }
+
     /// Transaction to update a VP arguments
     #[derive(Clone, Debug)]
     pub struct TxUpdateVp {
@@ -2262,6 +2688,51 @@ pub mod args {
         }
     }
 
+    /// Withdrawal from a testnet faucet arguments
+    #[cfg(feature = "dev")]
+    #[derive(Clone, Debug)]
+    pub struct FaucetWithdrawal {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The faucet address
+        pub faucet: WalletAddress,
+        /// The address credited with the withdrawn tokens
+        pub target: WalletAddress,
+        /// The withdrawn token address
+        pub token: WalletAddress,
+        /// The withdrawn amount
+        pub amount: token::Amount,
+    }
+
+    #[cfg(feature = "dev")]
+    impl Args for FaucetWithdrawal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let faucet = FAUCET.parse(matches);
+            let target = TARGET.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                faucet,
+                target,
+                token,
+                amount,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(FAUCET.def().about("The faucet address."))
+                .arg(TARGET.def().about(
+                    "The address credited with the withdrawn tokens. The \
+                     target's key is used to produce the signature.",
+                ))
+                .arg(TOKEN.def().about("The withdrawn token."))
+                .arg(AMOUNT.def().about("The amount to withdraw."))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryProposal {
         /// Common query args
@@ -2705,6 +3176,54 @@ pub mod args {
                 .arg(STORAGE_KEY.def().about("Storage key"))
         }
     }
+
+    /// Dump the raw bytes of all the storage keys under a given prefix
+    #[derive(Clone, Debug)]
+    pub struct DumpStorage {
+        /// The storage key prefix to dump
+        pub prefix: storage::Key,
+        /// Common query args
+        pub query: Query,
+    }
+
+    impl Args for DumpStorage {
+        fn parse(matches: &ArgMatches) -> Self {
+            let prefix = STORAGE_KEY.parse(matches);
+            let query = Query::parse(matches);
+            Self { prefix, query }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(STORAGE_KEY.def().about("Storage key prefix"))
+        }
+    }
+
+    /// Query the hash of the validity predicate of an account
+    #[derive(Clone, Debug)]
+    pub struct QueryAccountVp {
+        /// Common query args
+        pub query: Query,
+        /// Address of the account
+        pub address: WalletAddress,
+    }
+
+    impl Args for QueryAccountVp {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let address = ADDRESS.parse(matches);
+            Self { query, address }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                ADDRESS
+                    .def()
+                    .about("The account's address whose VP hash to query."),
+            )
+        }
+    }
+
     /// Common transaction arguments
     #[derive(Clone, Debug)]
     pub struct Tx {
@@ -3397,6 +3916,39 @@ pub mod args {
                 ))
         }
     }
+
+    /// Decode tx arguments
+    #[derive(Clone, Debug)]
+    pub struct DecodeTx {
+        /// Path to the file with the serialized tx or tx data
+        pub data_path: PathBuf,
+        /// The type of the data, detected when not set
+        pub data_type: Option<TxDataType>,
+    }
+
+    impl Args for DecodeTx {
+        fn parse(matches: &ArgMatches) -> Self {
+            let data_path = DATA_PATH.parse(matches);
+            let data_type = TX_DATA_TYPE.parse(matches);
+            Self {
+                data_path,
+                data_type,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(DATA_PATH.def().about(
+                "The path to a file containing a Protobuf-serialized \
+                 transaction or Borsh-serialized transaction data.",
+            ))
+            .arg(TX_DATA_TYPE.def().about(
+                "The type of the data: tx, signed-tx-data, transfer, \
+                 update-vp, init-account, init-validator, bond, withdraw, \
+                 init-proposal or vote-proposal. When not set, it's detected \
+                 from the data.",
+            ))
+        }
+    }
 }
 
 pub fn namada_cli() -> (cmds::Namada, String) {
@@ -17,7 +17,7 @@ use crate::config::genesis::genesis_config;
 use crate::config::global::GlobalConfig;
 use crate::config::{self, Config};
 use crate::wallet::Wallet;
-use crate::wasm_loader;
+use crate::wasm_loader::WasmDirs;
 
 /// Env. var to set chain ID
 const ENV_VAR_CHAIN_ID: &str = "NAMADA_CHAIN_ID";
@@ -183,9 +183,24 @@ impl Context {
         wasm_dir
     }
 
-    /// Read the given WASM file from the WASM directory or an absolute path.
+    /// Get the wasm directory configured for the chain together with the
+    /// configured search directories.
+    pub fn wasm_dirs(&self) -> WasmDirs {
+        let chain_dir = self.config.ledger.chain_dir();
+        WasmDirs {
+            search: self
+                .config
+                .wasm_search_dirs
+                .iter()
+                .map(|dir| chain_dir.join(dir))
+                .collect(),
+            writable: self.wasm_dir(),
+        }
+    }
+
+    /// Read the given WASM file from the WASM directories or an absolute path.
     pub fn read_wasm(&self, file_name: impl AsRef<Path>) -> Vec<u8> {
-        wasm_loader::read_wasm_or_exit(self.wasm_dir(), file_name)
+        self.wasm_dirs().read_wasm_or_exit(file_name)
     }
 }
 
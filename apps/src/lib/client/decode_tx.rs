@@ -0,0 +1,289 @@
+//! Decoding of serialized txs and tx data, to inspect them in a
+//! human-readable form.
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use borsh::BorshDeserialize;
+use namada::proto::{SignedTxData, Tx};
+use namada::types::hash::Hash;
+use namada::types::token;
+use namada::types::transaction::governance::{
+    InitProposalData, VoteProposalData,
+};
+use namada::types::transaction::{pos, InitAccount, InitValidator, UpdateVp};
+use thiserror::Error;
+
+/// The types of the txs and tx data that can be decoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxDataType {
+    /// A Protobuf-encoded tx, as submitted to the ledger
+    Tx,
+    /// Tx data signed by an account
+    SignedTxData,
+    /// A token transfer
+    Transfer,
+    /// An update of an account's validity predicate
+    UpdateVp,
+    /// An initialization of an account
+    InitAccount,
+    /// An initialization of a validator
+    InitValidator,
+    /// A bond or an unbond of tokens
+    Bond,
+    /// A withdrawal of unbonded tokens
+    Withdraw,
+    /// An initialization of a governance proposal
+    InitProposal,
+    /// A vote on a governance proposal
+    VoteProposal,
+}
+
+impl TxDataType {
+    /// All the types, in the order in which they're tried when the type of
+    /// the data is detected
+    pub const ALL: [TxDataType; 10] = [
+        TxDataType::SignedTxData,
+        TxDataType::Transfer,
+        TxDataType::InitProposal,
+        TxDataType::VoteProposal,
+        TxDataType::InitValidator,
+        TxDataType::InitAccount,
+        TxDataType::UpdateVp,
+        TxDataType::Bond,
+        TxDataType::Withdraw,
+        TxDataType::Tx,
+    ];
+
+    /// The name of the type, as accepted by the `--type` argument
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxDataType::Tx => "tx",
+            TxDataType::SignedTxData => "signed-tx-data",
+            TxDataType::Transfer => "transfer",
+            TxDataType::UpdateVp => "update-vp",
+            TxDataType::InitAccount => "init-account",
+            TxDataType::InitValidator => "init-validator",
+            TxDataType::Bond => "bond",
+            TxDataType::Withdraw => "withdraw",
+            TxDataType::InitProposal => "init-proposal",
+            TxDataType::VoteProposal => "vote-proposal",
+        }
+    }
+}
+
+impl Display for TxDataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for TxDataType {
+    type Err = DecodeTxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TxDataType::ALL
+            .into_iter()
+            .find(|data_type| data_type.name() == s)
+            .ok_or_else(|| DecodeTxError::UnknownType(s.to_owned()))
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum DecodeTxError {
+    #[error("Unknown tx data type {0}, expected one of: {}", type_names())]
+    UnknownType(String),
+    #[error("Failed to decode the data as {0}: {1}")]
+    InvalidData(TxDataType, String),
+    #[error(
+        "The data could not be decoded as any of the known types: {}",
+        type_names()
+    )]
+    Unrecognized,
+}
+
+/// The names of all the types, separated by commas
+fn type_names() -> String {
+    TxDataType::ALL
+        .iter()
+        .map(TxDataType::name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A decoded tx or tx data
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub enum DecodedTxData {
+    Tx(Tx),
+    SignedTxData(SignedTxData),
+    Transfer(token::Transfer),
+    UpdateVp(UpdateVp),
+    InitAccount(InitAccount),
+    InitValidator(InitValidator),
+    Bond(pos::Bond),
+    Withdraw(pos::Withdraw),
+    InitProposal(InitProposalData),
+    VoteProposal(VoteProposalData),
+}
+
+/// Decode the given bytes as a tx or tx data of the given type. When the
+/// type is not given, it's detected by trying all the known types.
+pub fn decode_tx_data(
+    bytes: &[u8],
+    data_type: Option<TxDataType>,
+) -> Result<DecodedTxData, DecodeTxError> {
+    match data_type {
+        Some(data_type) => decode_as(bytes, data_type)
+            .map_err(|err| DecodeTxError::InvalidData(data_type, err)),
+        None => TxDataType::ALL
+            .into_iter()
+            .find_map(|data_type| decode_as(bytes, data_type).ok())
+            .ok_or(DecodeTxError::Unrecognized),
+    }
+}
+
+/// Decode the given bytes as the given type
+fn decode_as(
+    bytes: &[u8],
+    data_type: TxDataType,
+) -> Result<DecodedTxData, String> {
+    fn borsh<T: BorshDeserialize>(bytes: &[u8]) -> Result<T, String> {
+        T::try_from_slice(bytes).map_err(|err| err.to_string())
+    }
+    Ok(match data_type {
+        TxDataType::Tx => DecodedTxData::Tx(
+            Tx::try_from(bytes).map_err(|err| err.to_string())?,
+        ),
+        TxDataType::SignedTxData => DecodedTxData::SignedTxData(borsh(bytes)?),
+        TxDataType::Transfer => DecodedTxData::Transfer(borsh(bytes)?),
+        TxDataType::UpdateVp => DecodedTxData::UpdateVp(borsh(bytes)?),
+        TxDataType::InitAccount => DecodedTxData::InitAccount(borsh(bytes)?),
+        TxDataType::InitValidator => {
+            DecodedTxData::InitValidator(borsh(bytes)?)
+        }
+        TxDataType::Bond => DecodedTxData::Bond(borsh(bytes)?),
+        TxDataType::Withdraw => DecodedTxData::Withdraw(borsh(bytes)?),
+        TxDataType::InitProposal => DecodedTxData::InitProposal(borsh(bytes)?),
+        TxDataType::VoteProposal => DecodedTxData::VoteProposal(borsh(bytes)?),
+    })
+}
+
+/// Write the inner data of a tx or of signed tx data, decoded if its type is
+/// recognized
+fn fmt_inner_data(
+    f: &mut fmt::Formatter<'_>,
+    data: &Option<Vec<u8>>,
+) -> fmt::Result {
+    match data {
+        Some(data) => match decode_tx_data(data, None) {
+            Ok(decoded) => write!(f, "data: {}", decoded),
+            Err(_) => write!(f, "data: {} undecodable bytes", data.len()),
+        },
+        None => write!(f, "data: none"),
+    }
+}
+
+impl Display for DecodedTxData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedTxData::Tx(tx) => {
+                writeln!(f, "tx")?;
+                writeln!(f, "code hash: {}", Hash::sha256(&tx.code))?;
+                writeln!(f, "timestamp: {}", tx.timestamp)?;
+                fmt_inner_data(f, &tx.data)
+            }
+            DecodedTxData::SignedTxData(signed) => {
+                writeln!(f, "signed-tx-data")?;
+                writeln!(f, "signature: {:?}", signed.sig)?;
+                fmt_inner_data(f, &signed.data)
+            }
+            DecodedTxData::Transfer(data) => write!(f, "transfer {:#?}", data),
+            DecodedTxData::UpdateVp(data) => {
+                write!(f, "update-vp {:#?}", data)
+            }
+            DecodedTxData::InitAccount(data) => {
+                write!(f, "init-account {:#?}", data)
+            }
+            DecodedTxData::InitValidator(data) => {
+                write!(f, "init-validator {:#?}", data)
+            }
+            DecodedTxData::Bond(data) => write!(f, "bond {:#?}", data),
+            DecodedTxData::Withdraw(data) => write!(f, "withdraw {:#?}", data),
+            DecodedTxData::InitProposal(data) => {
+                write!(f, "init-proposal {:#?}", data)
+            }
+            DecodedTxData::VoteProposal(data) => {
+                write!(f, "vote-proposal {:#?}", data)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use borsh::BorshSerialize;
+    use namada::types::address;
+
+    use super::*;
+
+    fn transfer() -> token::Transfer {
+        token::Transfer {
+            source: address::testing::established_address_1(),
+            target: address::testing::established_address_2(),
+            token: address::nam(),
+            sub_prefix: None,
+            amount: token::Amount::whole(10),
+            key: None,
+            shielded: None,
+            memo: Some("memo".to_owned()),
+        }
+    }
+
+    /// Test that tx data written to a file is decoded back, with its type
+    /// detected or given.
+    #[test]
+    fn test_decode_tx_data_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tx.data");
+        fs::write(&path, transfer().try_to_vec().unwrap()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        for data_type in [None, Some(TxDataType::Transfer)] {
+            let decoded = decode_tx_data(&bytes, data_type).unwrap();
+            assert!(matches!(
+                decoded,
+                DecodedTxData::Transfer(decoded) if decoded == transfer()
+            ));
+        }
+        // The wrong type hint is rejected
+        assert!(matches!(
+            decode_tx_data(&bytes, Some(TxDataType::Bond)),
+            Err(DecodeTxError::InvalidData(TxDataType::Bond, _))
+        ));
+    }
+
+    /// Test that undecodable data is rejected.
+    #[test]
+    fn test_decode_tx_data_unrecognized() {
+        assert!(matches!(
+            decode_tx_data(&[0xff; 7], None),
+            Err(DecodeTxError::Unrecognized)
+        ));
+    }
+
+    /// Test that the type names are parsed back.
+    #[test]
+    fn test_tx_data_type_names() {
+        for data_type in TxDataType::ALL {
+            assert_eq!(
+                data_type.name().parse::<TxDataType>().unwrap(),
+                data_type
+            );
+        }
+        assert!("unknown".parse::<TxDataType>().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod decode_tx;
 pub mod rpc;
 pub mod signing;
 pub mod tendermint_rpc_types;
@@ -26,15 +26,18 @@ use namada::ledger::events::Event;
 use namada::ledger::governance::parameters::GovParams;
 use namada::ledger::governance::storage as gov_storage;
 use namada::ledger::native_vp::governance::utils::Votes;
-use namada::ledger::parameters::{storage as param_storage, EpochDuration};
+use namada::ledger::parameters::{
+    storage as param_storage, EpochDuration, FeeDistribution,
+};
 use namada::ledger::pos::types::{decimal_mult_u64, WeightedValidator};
 use namada::ledger::pos::{
     self, is_validator_slashes_key, BondId, Bonds, PosParams, Slash, Unbonds,
 };
-use namada::ledger::queries::{self, RPC};
+use namada::ledger::queries::{self, MAX_PREFIX_PAGE_LIMIT, RPC};
 use namada::ledger::storage::ConversionState;
 use namada::proto::{SignedTxData, Tx};
 use namada::types::address::{masp, tokens, Address};
+use namada::types::chain::ChainId;
 use namada::types::governance::{
     OfflineProposal, OfflineVote, ProposalResult, ProposalVote, TallyResult,
     VotePower,
@@ -50,6 +53,7 @@ use namada::types::transaction::{
     process_tx, AffineCurve, DecryptedTx, EllipticCurve, PairingEngine, TxType,
     WrapperTx,
 };
+use namada::types::validity_predicate::VpCodeHash;
 use namada::types::{address, storage, token};
 use rust_decimal::Decimal;
 use tokio::time::{Duration, Instant};
@@ -59,6 +63,10 @@ use crate::client::tendermint_rpc_types::TxResponse;
 use crate::client::tx::{
     Conversions, PinnedBalanceError, TransactionDelta, TransferDelta,
 };
+use crate::config::genesis::{
+    Genesis, GenesisPage, GenesisPageRequest, GENESIS_QUERY_PATH,
+    MAX_GENESIS_PAGE_VALIDATORS,
+};
 use crate::facade::tendermint::merkle::proof::Proof;
 use crate::facade::tendermint_config::net::Address as TendermintAddress;
 use crate::facade::tendermint_rpc::error::Error as TError;
@@ -126,6 +134,29 @@ pub async fn query_epoch(args: args::Query) -> Epoch {
     epoch
 }
 
+/// Query the IBC packets sent from the chain that the node has marked as
+/// timed-out locally
+pub async fn query_ibc_timed_out_packets(args: args::Query) {
+    let client = HttpClient::new(args.ledger_address).unwrap();
+    let packets = unwrap_client_response(
+        RPC.shell().ibc_timed_out_packets(&client).await,
+    );
+    if packets.is_empty() {
+        println!("No IBC packets are timed-out locally");
+    }
+    for (packet_id, packet) in packets {
+        println!(
+            "IBC packet {}/{}/{} sent at height {} and time {} is timed-out \
+             locally",
+            packet_id.port_id,
+            packet_id.channel_id,
+            packet_id.sequence,
+            packet.height,
+            packet.sent_at
+        );
+    }
+}
+
 /// Query the last committed block
 pub async fn query_block(
     args: args::Query,
@@ -221,7 +252,12 @@ pub async fn query_tx_deltas(
                         .expect("Ill-formed Tx");
                     let mut wrapper = None;
                     let mut transfer = None;
-                    extract_payload(tx, &mut wrapper, &mut transfer);
+                    extract_payload(
+                        tx,
+                        &ctx.global_config.default_chain_id,
+                        &mut wrapper,
+                        &mut transfer,
+                    );
                     // Epoch data is not needed for transparent transactions
                     let epoch = wrapper.map(|x| x.epoch).unwrap_or_default();
                     if let Some(transfer) = transfer {
@@ -381,10 +417,11 @@ pub async fn query_transfers(mut ctx: Context, args: args::QueryTransfers) {
 /// Extract the payload from the given Tx object
 fn extract_payload(
     tx: Tx,
+    chain_id: &ChainId,
     wrapper: &mut Option<WrapperTx>,
     transfer: &mut Option<Transfer>,
 ) {
-    match process_tx(tx) {
+    match process_tx(tx, chain_id) {
         Ok(TxType::Wrapper(wrapper_tx)) => {
             let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
             extract_payload(
@@ -392,6 +429,7 @@ fn extract_payload(
                     Ok(tx) => DecryptedTx::Decrypted(tx),
                     _ => DecryptedTx::Undecryptable(wrapper_tx.clone()),
                 }),
+                chain_id,
                 wrapper,
                 transfer,
             );
@@ -424,6 +462,71 @@ pub async fn query_raw_bytes(_ctx: Context, args: args::QueryRawBytes) {
     }
 }
 
+/// Dump the raw bytes of all the storage keys under a given prefix, for
+/// debugging
+pub async fn dump_storage(_ctx: Context, args: args::DumpStorage) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let mut offset = 0;
+    let mut height = None;
+    let mut count = 0;
+    loop {
+        let page = unwrap_client_response(
+            RPC.shell()
+                .storage_dump_prefix(
+                    &client,
+                    None,
+                    height,
+                    false,
+                    &args.prefix,
+                    &offset,
+                    &MAX_PREFIX_PAGE_LIMIT,
+                )
+                .await,
+        )
+        .data;
+        for entry in &page.entries {
+            println!("{}: 0x{}", entry.key, entry.value);
+        }
+        count += page.entries.len();
+        match page.next_offset {
+            Some(next_offset) => {
+                offset = next_offset;
+                height = Some(page.height);
+            }
+            None => {
+                println!(
+                    "Dumped {} keys under {} at height {}",
+                    count, args.prefix, page.height
+                );
+                break;
+            }
+        }
+    }
+}
+
+/// Query the hash of the validity predicate of an account
+pub async fn query_account_vp(ctx: Context, args: args::QueryAccountVp) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let address = ctx.get(&args.address);
+    match unwrap_client_response(
+        RPC.shell().vp_code_hash(&client, &address).await,
+    ) {
+        VpCodeHash::Code(hash) => {
+            println!("VP hash of {}: {}", address, hash)
+        }
+        VpCodeHash::Default => println!("No VP found for {}", address),
+    }
+}
+
+/// Query the whitelist of the hashes of the VPs allowed on chain. An empty
+/// whitelist allows any VP.
+pub async fn query_vp_whitelist(client: &HttpClient) -> Vec<String> {
+    let key = param_storage::get_vp_whitelist_storage_key();
+    query_storage_value::<Vec<String>>(client, &key)
+        .await
+        .expect("Parameter should be definied.")
+}
+
 /// Query token balance(s)
 pub async fn query_balance(mut ctx: Context, args: args::QueryBalance) {
     // Query the balances of shielded or transparent account types depending on
@@ -1316,10 +1419,16 @@ pub async fn query_protocol_parameters(
         .expect("Parameter should be definied.");
     println!("{:4}Max. block duration: {}", "", max_block_duration);
 
-    let key = param_storage::get_tx_whitelist_storage_key();
-    let vp_whitelist = query_storage_value::<Vec<String>>(&client, &key)
+    let key = param_storage::get_max_ibc_packet_data_bytes_key();
+    let max_ibc_packet_data_bytes = query_storage_value::<u64>(&client, &key)
         .await
         .expect("Parameter should be definied.");
+    println!(
+        "{:4}Max. IBC packet data size: {} bytes",
+        "", max_ibc_packet_data_bytes
+    );
+
+    let vp_whitelist = query_vp_whitelist(&client).await;
     println!("{:4}VP whitelist: {:?}", "", vp_whitelist);
 
     let key = param_storage::get_tx_whitelist_storage_key();
@@ -1328,6 +1437,62 @@ pub async fn query_protocol_parameters(
         .expect("Parameter should be definied.");
     println!("{:4}Transactions whitelist: {:?}", "", tx_whitelist);
 
+    let key = param_storage::get_fee_distribution_key();
+    let fee_distribution =
+        query_storage_value::<FeeDistribution>(&client, &key)
+            .await
+            .expect("Parameter should be definied.");
+    println!("{:4}Fee burn rate: {}", "", fee_distribution.burn_rate);
+    println!(
+        "{:4}Fee block proposer rate: {}",
+        "", fee_distribution.proposer_rate
+    );
+    println!(
+        "{:4}Fee community pool rate: {} (paid to {})",
+        "",
+        fee_distribution.community_pool_rate,
+        fee_distribution.community_pool
+    );
+
+    let key = param_storage::get_block_gas_limit_key();
+    let block_gas_limit = query_storage_value::<u64>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}Block gas limit: {}", "", block_gas_limit);
+
+    let key = param_storage::get_max_verifiers_key();
+    let max_verifiers = query_storage_value::<Option<u64>>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    match max_verifiers {
+        Some(max_verifiers) => {
+            println!("{:4}Max verifiers per tx: {}", "", max_verifiers)
+        }
+        None => println!("{:4}Max verifiers per tx: unlimited", ""),
+    }
+
+    let key = param_storage::get_fee_tokens_key();
+    let fee_tokens =
+        query_storage_value::<BTreeMap<Address, Decimal>>(&client, &key)
+            .await
+            .unwrap_or_default();
+    println!("{:4}Fee tokens:", "");
+    for (token, rate) in fee_tokens {
+        println!("{:8}{}: {}", "", token, rate);
+    }
+
+    let key = param_storage::get_vp_wasm_memory_max_pages_key();
+    let vp_max_pages = query_storage_value::<u32>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}VP WASM memory max pages: {}", "", vp_max_pages);
+
+    let key = param_storage::get_tx_wasm_memory_max_pages_key();
+    let tx_max_pages = query_storage_value::<u32>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}Tx WASM memory max pages: {}", "", tx_max_pages);
+
     println!("PoS parameters");
     let key = pos::params_key();
     let pos_params = query_storage_value::<PosParams>(&client, &key)
@@ -1353,8 +1518,21 @@ pub async fn query_protocol_parameters(
         "{:4}Max. validator slots: {}",
         "", pos_params.max_validator_slots
     );
+    println!(
+        "{:4}Min. inflation rate: {}",
+        "", pos_params.min_inflation_rate
+    );
+    println!(
+        "{:4}Max. inflation rate: {}",
+        "", pos_params.max_inflation_rate
+    );
+    println!(
+        "{:4}Target staked ratio: {}",
+        "", pos_params.target_staked_ratio
+    );
     println!("{:4}Pipeline length: {}", "", pos_params.pipeline_len);
     println!("{:4}Unbonding length: {}", "", pos_params.unbonding_len);
+    println!("{:4}Jail length: {}", "", pos_params.jail_len);
     println!("{:4}Votes per token: {}", "", pos_params.tm_votes_per_token);
 }
 
@@ -1745,9 +1923,9 @@ pub async fn query_bonded_stake(ctx: Context, args: args::QueryBondedStake) {
                     };
                     let is_active = validator_set.active.contains(&weighted);
                     if !is_active {
-                        debug_assert!(
-                            validator_set.inactive.contains(&weighted)
-                        );
+                        debug_assert!(validator_set
+                            .inactive
+                            .contains(&weighted));
                     }
                     println!(
                         "Validator {} is {}, bonded stake: {}",
@@ -2285,11 +2463,34 @@ pub async fn query_storage_prefix<T>(
 where
     T: BorshDeserialize,
 {
-    let values = unwrap_client_response(
-        RPC.shell()
-            .storage_prefix(client, None, None, false, key)
-            .await,
-    );
+    // Fetch the values in pages to avoid large responses
+    let mut values = vec![];
+    let mut offset = 0;
+    let mut height = None;
+    loop {
+        let page = unwrap_client_response(
+            RPC.shell()
+                .storage_prefix_page(
+                    client,
+                    None,
+                    height,
+                    false,
+                    key,
+                    &offset,
+                    &MAX_PREFIX_PAGE_LIMIT,
+                )
+                .await,
+        )
+        .data;
+        values.extend(page.values);
+        match page.next_offset {
+            Some(next_offset) => {
+                offset = next_offset;
+                height = Some(page.height);
+            }
+            None => break,
+        }
+    }
     let decode =
         |PrefixValue { key, value }: PrefixValue| match T::try_from_slice(
             &value[..],
@@ -2303,10 +2504,50 @@ where
             }
             Ok(value) => Some((key, value)),
         };
-    if values.data.is_empty() {
+    if values.is_empty() {
         None
     } else {
-        Some(values.data.into_iter().filter_map(decode))
+        Some(values.into_iter().filter_map(decode))
+    }
+}
+
+/// Query the genesis that the node started from, along with its chain ID.
+/// The validators are fetched in pages to avoid large responses.
+pub async fn query_genesis(client: &HttpClient) -> (ChainId, Genesis) {
+    let mut validators = vec![];
+    loop {
+        let request = GenesisPageRequest {
+            offset: validators.len() as u64,
+            limit: MAX_GENESIS_PAGE_VALIDATORS,
+        };
+        let response = unwrap_client_response(
+            queries::Client::request(
+                client,
+                GENESIS_QUERY_PATH.to_owned(),
+                Some(request.try_to_vec().unwrap()),
+                None,
+                false,
+            )
+            .await,
+        );
+        let page = GenesisPage::try_from_slice(&response.data[..])
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to decode the genesis: {}", err);
+                cli::safe_exit(1)
+            });
+        let GenesisPage {
+            chain_id,
+            total_validators,
+            mut genesis,
+        } = page;
+        let is_last_page = genesis.validators.is_empty()
+            || validators.len() as u64 + genesis.validators.len() as u64
+                >= total_validators;
+        validators.append(&mut genesis.validators);
+        if is_last_page {
+            genesis.validators = validators;
+            return (chain_id, genesis);
+        }
     }
 }
 
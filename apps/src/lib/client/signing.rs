@@ -147,7 +147,7 @@ pub async fn sign_tx(
     default: TxSigningKey,
 ) -> (Context, TxBroadcastData) {
     let keypair = tx_signer(&mut ctx, args, default).await;
-    let tx = tx.sign(&keypair);
+    let tx = tx.sign(&keypair, &ctx.global_config.default_chain_id);
 
     let epoch = rpc::query_epoch(args::Query {
         ledger_address: args.ledger_address.clone(),
@@ -193,7 +193,7 @@ pub async fn sign_wrapper(
     let decrypted_hash = tx.tx_hash.to_string();
     TxBroadcastData::Wrapper {
         tx: tx
-            .sign(keypair)
+            .sign(keypair, &ctx.global_config.default_chain_id)
             .expect("Wrapper tx signing keypair should be correct"),
         wrapper_hash,
         decrypted_hash,
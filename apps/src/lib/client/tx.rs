@@ -39,11 +39,15 @@ use namada::ibc_proto::cosmos::base::v1beta1::Coin;
 use namada::ledger::governance::storage as gov_storage;
 use namada::ledger::masp;
 use namada::ledger::pos::{BondId, Bonds, CommissionRates, Unbonds};
+#[cfg(feature = "dev")]
+use namada::ledger::testnet_faucet;
 use namada::proto::Tx;
 use namada::types::address::{masp, masp_tx_key, Address};
+use namada::types::chain::ChainId;
 use namada::types::governance::{
     OfflineProposal, OfflineVote, Proposal, ProposalVote,
 };
+use namada::types::hash::Hash;
 use namada::types::key::*;
 use namada::types::masp::{PaymentAddress, TransferTarget};
 use namada::types::storage::{
@@ -56,7 +60,9 @@ use namada::types::token::{
 use namada::types::transaction::governance::{
     InitProposalData, VoteProposalData,
 };
-use namada::types::transaction::{pos, InitAccount, InitValidator, UpdateVp};
+use namada::types::transaction::{
+    hash_tx, pos, tx_types, InitAccount, InitValidator, TxType, UpdateVp,
+};
 use namada::types::{storage, token};
 use namada::{ledger, vm};
 use rand_core::{CryptoRng, OsRng, RngCore};
@@ -85,12 +91,17 @@ const TX_VOTE_PROPOSAL: &str = "tx_vote_proposal.wasm";
 const TX_REVEAL_PK: &str = "tx_reveal_pk.wasm";
 const TX_UPDATE_VP_WASM: &str = "tx_update_vp.wasm";
 const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+const TX_MULTI_TRANSFER_WASM: &str = "tx_multi_transfer.wasm";
 const TX_IBC_WASM: &str = "tx_ibc.wasm";
 const VP_USER_WASM: &str = "vp_user.wasm";
 const TX_BOND_WASM: &str = "tx_bond.wasm";
 const TX_UNBOND_WASM: &str = "tx_unbond.wasm";
 const TX_WITHDRAW_WASM: &str = "tx_withdraw.wasm";
 const TX_CHANGE_COMMISSION_WASM: &str = "tx_change_validator_commission.wasm";
+const TX_CHANGE_CONSENSUS_KEY_WASM: &str = "tx_change_consensus_key.wasm";
+const TX_UNJAIL_VALIDATOR_WASM: &str = "tx_unjail_validator.wasm";
+#[cfg(feature = "dev")]
+const TX_FAUCET_WITHDRAWAL_WASM: &str = "tx_faucet_withdrawal.wasm";
 
 /// Timeout for requests to the `/accepted` and `/applied`
 /// ABCI query endpoints.
@@ -112,6 +123,64 @@ pub async fn submit_custom(ctx: Context, args: args::TxCustom) {
     save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
 }
 
+/// Submit a tx that has been signed elsewhere, e.g. with an offline or a
+/// hardware wallet. The tx is read from a file containing a Borsh-serialized
+/// wrapper [`Tx`] and it is broadcast as is.
+pub async fn submit_signed_tx(ctx: Context, args: args::TxSigned) {
+    let tx_path = args.tx_path.to_string_lossy();
+    let tx_bytes = match std::fs::read(&args.tx_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("Couldn't read the transaction at {}: {}", tx_path, err);
+            safe_exit(1)
+        }
+    };
+    let to_broadcast = match signed_tx_broadcast_data(
+        &tx_bytes,
+        &ctx.global_config.default_chain_id,
+    ) {
+        Ok(to_broadcast) => to_broadcast,
+        Err(err) => {
+            eprintln!("Invalid signed transaction at {}: {}", tx_path, err);
+            safe_exit(1)
+        }
+    };
+    let result = if args.broadcast_only {
+        broadcast_tx(args.ledger_address, &to_broadcast)
+            .await
+            .map(|_| ())
+    } else {
+        submit_tx(args.ledger_address, to_broadcast)
+            .await
+            .map(|_| ())
+    };
+    if let Err(err) = result {
+        eprintln!("Encountered error while broadcasting transaction: {}", err);
+        safe_exit(1)
+    }
+}
+
+/// Decode a signed wrapper tx and check its signature for the given chain.
+/// Returns the tx along with the hashes needed for monitoring it on chain.
+fn signed_tx_broadcast_data(
+    tx_bytes: &[u8],
+    chain_id: &ChainId,
+) -> std::result::Result<TxBroadcastData, String> {
+    let tx = Tx::try_from_slice(tx_bytes).map_err(|err| {
+        format!("Failed to deserialize the transaction: {}", err)
+    })?;
+    match tx_types::process_tx(tx.clone(), chain_id)
+        .map_err(|err| err.to_string())?
+    {
+        TxType::Wrapper(wrapper) => Ok(TxBroadcastData::Wrapper {
+            tx,
+            wrapper_hash: hash_tx(&wrapper.try_to_vec().unwrap()).to_string(),
+            decrypted_hash: wrapper.tx_hash.to_string(),
+        }),
+        _ => Err("Expected a signed wrapper transaction".to_string()),
+    }
+}
+
 pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
     let addr = ctx.get(&args.addr);
 
@@ -168,10 +237,23 @@ pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
 
 pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
     let public_key = ctx.get_cached(&args.public_key);
-    let vp_code = args
-        .vp_code_path
-        .map(|path| ctx.read_wasm(path))
-        .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
+    let vp_code = match (args.vp_code_path, args.vp_code_hash) {
+        (Some(path), _) => ctx.read_wasm(path),
+        (None, Some(hash)) => {
+            match ctx.wasm_dirs().read_wasm_by_hash(&hash.to_string()) {
+                Some(vp_code) => vp_code,
+                None => {
+                    eprintln!(
+                        "No validity predicate with hash {} was found among \
+                         the WASM artifacts",
+                        hash
+                    );
+                    safe_exit(1)
+                }
+            }
+        }
+        (None, None) => ctx.read_wasm(VP_USER_WASM),
+    };
     // Validate the VP code
     if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
         eprintln!("Validity predicate code validation failed with {}", err);
@@ -179,6 +261,21 @@ pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
             safe_exit(1)
         }
     }
+    // Check that the VP is allowed on chain, before submitting the tx that
+    // would be rejected
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let vp_whitelist = rpc::query_vp_whitelist(&client).await;
+    let vp_hash = Hash::sha256(&vp_code).to_string();
+    if !vp_whitelist.is_empty() && !vp_whitelist.contains(&vp_hash) {
+        eprintln!(
+            "The validity predicate with hash {} is not in the VP whitelist \
+             of the chain",
+            vp_hash
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
 
     let tx_code = ctx.read_wasm(TX_INIT_ACCOUNT_WASM);
     let data = InitAccount {
@@ -1497,6 +1594,20 @@ pub async fn submit_transfer(mut ctx: Context, args: args::TxTransfer) {
             safe_exit(1)
         }
     }
+    // Check that the memo isn't too long, which would be rejected on chain
+    if let Some(memo) = &args.memo {
+        if !token::is_valid_memo(memo) {
+            eprintln!(
+                "The memo is {} bytes long, which exceeds the maximum of {} \
+                 bytes.",
+                memo.len(),
+                token::MAX_MEMO_LENGTH
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
     // Check that the token address exists on chain
     let token_exists =
         rpc::known_address(&parsed_args.token, args.tx.ledger_address.clone())
@@ -1598,6 +1709,7 @@ pub async fn submit_transfer(mut ctx: Context, args: args::TxTransfer) {
         sub_prefix,
         amount,
         key,
+        memo: args.memo.clone(),
         shielded: {
             let spending_key = parsed_args.source.spending_key();
             let payment_address = parsed_args.target.payment_address();
@@ -1652,6 +1764,65 @@ pub async fn submit_transfer(mut ctx: Context, args: args::TxTransfer) {
     process_tx(ctx, &args.tx, tx, signing_address).await;
 }
 
+pub async fn submit_multi_transfer(ctx: Context, args: args::TxMultiTransfer) {
+    let source = ctx.get(&args.source);
+    let file = File::open(&args.outputs_path).expect("File must exist.");
+    let outputs: Vec<token::TransferOutput> =
+        serde_json::from_reader(file).expect("JSON was not well-formatted");
+    let transfer = token::MultiTransfer {
+        source: source.clone(),
+        outputs,
+    };
+    // Check that the outputs are valid, which would be rejected on chain
+    let totals = match transfer.totals() {
+        Ok(totals) => totals,
+        Err(err) => {
+            eprintln!("Invalid transfer outputs: {}", err);
+            safe_exit(1)
+        }
+    };
+    // Check that the source address exists on chain
+    let source_exists =
+        rpc::known_address(&source, args.tx.ledger_address.clone()).await;
+    if !source_exists {
+        eprintln!("The source address {} doesn't exist on chain.", source);
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    // Check that the source's balance of every token covers its total, as
+    // the whole transfer is rejected otherwise
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    for (token, total) in totals {
+        let balance_key = token::balance_key(&token, &source);
+        let balance =
+            rpc::query_storage_value::<token::Amount>(&client, &balance_key)
+                .await
+                .unwrap_or_default();
+        if balance < total {
+            eprintln!(
+                "The balance of the source {} of token {} is lower than the \
+                 total amount to be transferred. Total amount to transfer is \
+                 {} and the balance is {}.",
+                source, token, total, balance
+            );
+            if !args.tx.force {
+                safe_exit(1)
+            }
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_MULTI_TRANSFER_WASM);
+    tracing::debug!("Multi transfer data {:?}", transfer);
+    let data = transfer
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, TxSigningKey::WalletAddress(args.source))
+        .await;
+}
+
 pub async fn submit_ibc_transfer(ctx: Context, args: args::TxIbcTransfer) {
     let source = ctx.get(&args.source);
     // Check that the source address exists on chain
@@ -2447,6 +2618,60 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
     .await;
 }
 
+/// Submit a tx to withdraw tokens from a testnet faucet
+#[cfg(feature = "dev")]
+pub async fn submit_faucet_withdrawal(
+    ctx: Context,
+    args: args::FaucetWithdrawal,
+) {
+    let faucet = ctx.get(&args.faucet);
+    let target = ctx.get(&args.target);
+    let token = ctx.get(&args.token);
+
+    // Check that the withdrawal is within the faucet's limits
+    if args.amount > testnet_faucet::MAX_WITHDRAWAL {
+        eprintln!(
+            "The faucet allows to withdraw at most {} tokens at once.",
+            testnet_faucet::MAX_WITHDRAWAL
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: args.tx.ledger_address.clone(),
+    })
+    .await;
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let last_withdrawal_key =
+        testnet_faucet::last_withdrawal_epoch_key(&faucet, &target);
+    let last_withdrawal =
+        rpc::query_storage_value::<Epoch>(&client, &last_withdrawal_key).await;
+    if last_withdrawal == Some(epoch) {
+        eprintln!(
+            "The target {} has already withdrawn from the faucet in the \
+             current epoch {}.",
+            target, epoch
+        );
+        if !args.tx.force {
+            safe_exit(1)
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_FAUCET_WITHDRAWAL_WASM);
+    let data = testnet_faucet::Withdrawal {
+        faucet,
+        target,
+        token,
+        amount: args.amount,
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, TxSigningKey::WalletAddress(args.target))
+        .await;
+}
+
 pub async fn submit_validator_commission_change(
     ctx: Context,
     args: args::TxCommissionRateChange,
@@ -2529,6 +2754,105 @@ pub async fn submit_validator_commission_change(
     .await;
 }
 
+pub async fn submit_change_consensus_key(
+    mut ctx: Context,
+    args::TxChangeConsensusKey {
+        tx: tx_args,
+        validator,
+        consensus_key,
+        unsafe_dont_encrypt,
+    }: args::TxChangeConsensusKey,
+) {
+    let epoch = rpc::query_epoch(args::Query {
+        ledger_address: tx_args.ledger_address.clone(),
+    })
+    .await;
+
+    let validator_address = ctx.get(&validator);
+    if !rpc::is_validator(&validator_address, tx_args.ledger_address.clone())
+        .await
+    {
+        eprintln!("The given address {validator_address} is not a validator.");
+        if !tx_args.force {
+            safe_exit(1)
+        }
+    }
+
+    let alias = ctx
+        .wallet
+        .find_alias(&validator_address)
+        .map(|alias| alias.to_string())
+        .unwrap_or_else(|| "validator".to_string());
+    let consensus_key_alias = format!("{}-consensus-key-{}", alias, epoch.0);
+    let consensus_key = ctx
+        .get_opt_cached(&consensus_key)
+        .map(|key| match key {
+            common::SecretKey::Ed25519(_) => key,
+            common::SecretKey::Secp256k1(_) => {
+                eprintln!("Consensus key can only be ed25519");
+                safe_exit(1)
+            }
+        })
+        .unwrap_or_else(|| {
+            println!("Generating a new consensus key...");
+            let key = ctx
+                .wallet
+                .gen_key(
+                    // Note that TM only allows ed25519 for consensus key
+                    SchemeType::Ed25519,
+                    Some(consensus_key_alias.clone()),
+                    unsafe_dont_encrypt,
+                )
+                .1;
+            ctx.wallet.save().unwrap_or_else(|err| eprintln!("{}", err));
+            key
+        });
+
+    let tx_code = ctx.read_wasm(TX_CHANGE_CONSENSUS_KEY_WASM);
+    let data = pos::ConsensusKeyChange {
+        validator: validator_address,
+        consensus_key: consensus_key.ref_to(),
+    };
+    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &tx_args, tx, TxSigningKey::WalletAddress(validator)).await;
+    if !tx_args.dry_run {
+        println!(
+            "The new consensus key will take effect at the pipeline offset \
+             from the current epoch {}. The node's Tendermint validator key \
+             must be replaced with the new key at that epoch.",
+            epoch
+        );
+    }
+}
+
+pub async fn submit_unjail_validator(
+    ctx: Context,
+    args::TxUnjailValidator {
+        tx: tx_args,
+        validator,
+    }: args::TxUnjailValidator,
+) {
+    let validator_address = ctx.get(&validator);
+    if !rpc::is_validator(&validator_address, tx_args.ledger_address.clone())
+        .await
+    {
+        eprintln!("The given address {validator_address} is not a validator.");
+        if !tx_args.force {
+            safe_exit(1)
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_UNJAIL_VALIDATOR_WASM);
+    let data = validator_address
+        .try_to_vec()
+        .expect("Encoding tx data shouldn't fail");
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &tx_args, tx, TxSigningKey::WalletAddress(validator)).await;
+}
+
 /// Submit transaction and wait for result. Returns a list of addresses
 /// initialized in the transaction if any. In dry run, this is always empty.
 async fn process_tx(
@@ -22,6 +22,7 @@ use sha2::{Digest, Sha256};
 
 use crate::cli::context::ENV_VAR_WASM_DIR;
 use crate::cli::{self, args};
+use crate::client::decode_tx;
 use crate::config::genesis::genesis_config::{
     self, HexString, ValidatorPreGenesisConfig,
 };
@@ -357,7 +358,11 @@ pub async fn fetch_wasms_aux(base_dir: &Path, chain_id: &ChainId) {
         path.push("wasm");
         path
     };
-    wasm_loader::pre_fetch_wasm(&wasm_dir).await;
+    wasm_loader::pre_fetch_wasm(
+        &wasm_loader::WasmDirs::new(wasm_dir),
+        wasm_loader::PreFetchOptions::default(),
+    )
+    .await;
 }
 
 /// Length of a Tendermint Node ID in bytes
@@ -871,6 +876,25 @@ fn init_established_account(
     }
 }
 
+/// Decode a serialized tx or tx data from a file and print it.
+pub fn decode_tx(_global_args: args::Global, args: args::DecodeTx) {
+    let bytes = fs::read(&args.data_path).unwrap_or_else(|err| {
+        eprintln!(
+            "Failed to read the data from {}: {}",
+            args.data_path.to_string_lossy(),
+            err
+        );
+        cli::safe_exit(1)
+    });
+    match decode_tx::decode_tx_data(&bytes, args.data_type) {
+        Ok(decoded) => println!("{}", decoded),
+        Err(err) => {
+            eprintln!("{}", err);
+            cli::safe_exit(1)
+        }
+    }
+}
+
 /// Initialize genesis validator's address, consensus key and validator account
 /// key and use it in the ledger's node.
 pub fn init_genesis_validator(
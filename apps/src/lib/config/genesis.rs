@@ -1,41 +1,46 @@
 //! The parameters used for the chain's genesis
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 #[cfg(not(feature = "dev"))]
 use std::path::Path;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use derivative::Derivative;
 use namada::ledger::governance::parameters::GovParams;
-use namada::ledger::parameters::EpochDuration;
+use namada::ledger::parameters::{
+    EpochDuration, EpochDurationError, FeeDistribution, FeeDistributionError,
+};
 use namada::ledger::pos::{GenesisValidator, PosParams};
 use namada::types::address::Address;
-#[cfg(not(feature = "dev"))]
 use namada::types::chain::ChainId;
 use namada::types::key::dkg_session_keys::DkgPublicKey;
 use namada::types::key::*;
 use namada::types::time::{DateTimeUtc, DurationSecs};
 use namada::types::{storage, token};
 use rust_decimal::Decimal;
+use thiserror::Error;
 
 /// Genesis configuration file format
 pub mod genesis_config {
     use std::array::TryFromSliceError;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::convert::TryInto;
     use std::path::Path;
     use std::str::FromStr;
 
     use data_encoding::HEXLOWER;
     use eyre::Context;
+    use namada::ledger::gas::BLOCK_GAS_LIMIT;
     use namada::ledger::governance::parameters::GovParams;
-    use namada::ledger::parameters::EpochDuration;
+    use namada::ledger::parameters::{EpochDuration, FeeDistribution};
     use namada::ledger::pos::{GenesisValidator, PosParams};
     use namada::types::address::Address;
+    use namada::types::ibc::DEFAULT_MAX_PACKET_DATA_BYTES;
     use namada::types::key::dkg_session_keys::DkgPublicKey;
     use namada::types::key::*;
     use namada::types::time::Rfc3339String;
     use namada::types::{storage, token};
+    use namada::vm::wasm::memory;
     use rust_decimal::Decimal;
     use serde::{Deserialize, Serialize};
     use thiserror::Error;
@@ -104,7 +109,8 @@ pub mod genesis_config {
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct GenesisConfig {
-        // Genesis timestamp
+        // Genesis timestamp, either an RFC 3339 date-time or a Unix
+        // timestamp in seconds
         pub genesis_time: Rfc3339String,
         // Name of the native token - this must one of the tokens included in
         // the `token` field
@@ -228,6 +234,9 @@ pub mod genesis_config {
         // Maximum duration per block (in seconds).
         // TODO: this is i64 because datetime wants it
         pub max_expected_time_per_block: i64,
+        /// Maximum size of the data of an IBC packet in bytes. When not set,
+        /// it defaults to `DEFAULT_MAX_PACKET_DATA_BYTES`.
+        pub max_ibc_packet_data_bytes: Option<u64>,
         // Hashes of whitelisted vps array. `None` value or an empty array
         // disables whitelisting.
         pub vp_whitelist: Option<Vec<String>>,
@@ -238,10 +247,47 @@ pub mod genesis_config {
         pub implicit_vp: String,
         /// Expected number of epochs per year
         pub epochs_per_year: u64,
+        /// Minimum duration per epoch (in seconds). When not set, it's
+        /// derived from the expected number of epochs per year.
+        pub min_duration: Option<u64>,
         /// PoS gain p
         pub pos_gain_p: Decimal,
         /// PoS gain d
         pub pos_gain_d: Decimal,
+        /// Distribution of the fees paid by the txs of a block. When not set,
+        /// the fees are paid entirely to the block proposer.
+        pub fee_distribution: Option<FeeDistributionConfig>,
+        /// Maximum sum of the gas limits of the wrapper txs included in a
+        /// block. When not set, it defaults to `BLOCK_GAS_LIMIT`.
+        pub block_gas_limit: Option<u64>,
+        /// Maximum number of verifiers that a tx may trigger. Txs triggering
+        /// more are rejected before their VPs run. When not set, the
+        /// verifiers are not capped.
+        pub max_verifiers: Option<u64>,
+        /// The tokens other than the native token that are accepted for the
+        /// fees, with their conversion rates, i.e. the amount of the native
+        /// token that one unit of the token is worth. When not set, only the
+        /// native token is accepted.
+        pub fee_tokens: Option<BTreeMap<Address, Decimal>>,
+        /// Maximum memory of VP WASM execution in 64 KiB WASM pages. When
+        /// not set, it defaults to `VP_MEMORY_MAX_PAGES`.
+        pub vp_wasm_memory_max_pages: Option<u32>,
+        /// Maximum memory of tx WASM execution in 64 KiB WASM pages. When
+        /// not set, it defaults to `TX_MEMORY_MAX_PAGES`.
+        pub tx_wasm_memory_max_pages: Option<u32>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct FeeDistributionConfig {
+        /// Rate of the fees that is burned
+        pub burn_rate: Decimal,
+        /// Rate of the fees that is paid to the block proposer
+        pub proposer_rate: Decimal,
+        /// Rate of the fees that is paid to the community pool
+        pub community_pool_rate: Decimal,
+        /// Address of the community pool. When not set, it's the governance
+        /// treasury.
+        pub community_pool: Option<String>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -264,6 +310,9 @@ pub mod genesis_config {
         // Reward for voting on a block.
         // XXX: u64 doesn't work with toml-rs!
         pub block_vote_reward: Decimal,
+        // Minimum staking APY
+        // XXX: u64 doesn't work with toml-rs!
+        pub min_inflation_rate: Decimal,
         // Maximum staking APY
         // XXX: u64 doesn't work with toml-rs!
         pub max_inflation_rate: Decimal,
@@ -277,6 +326,9 @@ pub mod genesis_config {
         // light client attack.
         // XXX: u64 doesn't work with toml-rs!
         pub light_client_attack_min_slash_rate: Decimal,
+        // Number of epochs for which a slashed validator is jailed.
+        // XXX: u64 doesn't work with toml-rs!
+        pub jail_len: u64,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -285,6 +337,19 @@ pub mod genesis_config {
         pub sha256: Option<HexString>,
     }
 
+    fn load_fee_distribution(config: FeeDistributionConfig) -> FeeDistribution {
+        let default = FeeDistribution::default();
+        FeeDistribution {
+            burn_rate: config.burn_rate,
+            proposer_rate: config.proposer_rate,
+            community_pool_rate: config.community_pool_rate,
+            community_pool: config
+                .community_pool
+                .map(|address| Address::decode(address).unwrap())
+                .unwrap_or(default.community_pool),
+        }
+    }
+
     fn load_validator(
         config: &ValidatorConfig,
         wasm: &HashMap<String, WasmConfig>,
@@ -559,21 +624,25 @@ pub mod genesis_config {
             .to_sha256_bytes()
             .unwrap();
 
-        let min_duration: i64 =
-            60 * 60 * 24 * 365 / (parameters.epochs_per_year as i64);
+        // An invalid duration is reported by the genesis validation
+        let min_duration: u64 = parameters.min_duration.unwrap_or_else(|| {
+            (60 * 60 * 24 * 365_u64)
+                .checked_div(parameters.epochs_per_year)
+                .unwrap_or_default()
+        });
         let parameters = Parameters {
             epoch_duration: EpochDuration {
                 min_num_of_blocks: parameters.min_num_of_blocks,
-                min_duration: namada::types::time::Duration::seconds(
-                    min_duration,
-                )
-                .into(),
+                min_duration: DurationSecs(min_duration),
             },
             max_expected_time_per_block:
                 namada::types::time::Duration::seconds(
                     parameters.max_expected_time_per_block,
                 )
                 .into(),
+            max_ibc_packet_data_bytes: parameters
+                .max_ibc_packet_data_bytes
+                .unwrap_or(DEFAULT_MAX_PACKET_DATA_BYTES),
             vp_whitelist: parameters.vp_whitelist.unwrap_or_default(),
             tx_whitelist: parameters.tx_whitelist.unwrap_or_default(),
             implicit_vp_code_path,
@@ -583,6 +652,21 @@ pub mod genesis_config {
             pos_gain_d: parameters.pos_gain_d,
             staked_ratio: Decimal::ZERO,
             pos_inflation_amount: 0,
+            fee_distribution: parameters
+                .fee_distribution
+                .map(load_fee_distribution)
+                .unwrap_or_default(),
+            block_gas_limit: parameters
+                .block_gas_limit
+                .unwrap_or(BLOCK_GAS_LIMIT),
+            max_verifiers: parameters.max_verifiers,
+            fee_tokens: parameters.fee_tokens.unwrap_or_default(),
+            vp_wasm_memory_max_pages: parameters
+                .vp_wasm_memory_max_pages
+                .unwrap_or(memory::VP_MEMORY_MAX_PAGES),
+            tx_wasm_memory_max_pages: parameters
+                .tx_wasm_memory_max_pages
+                .unwrap_or(memory::TX_MEMORY_MAX_PAGES),
         };
 
         let GovernanceParamsConfig {
@@ -609,10 +693,12 @@ pub mod genesis_config {
             tm_votes_per_token,
             block_proposer_reward,
             block_vote_reward,
+            min_inflation_rate,
             max_inflation_rate,
             target_staked_ratio,
             duplicate_vote_min_slash_rate,
             light_client_attack_min_slash_rate,
+            jail_len,
         } = pos_params;
         let pos_params = PosParams {
             max_validator_slots,
@@ -621,14 +707,19 @@ pub mod genesis_config {
             tm_votes_per_token,
             block_proposer_reward,
             block_vote_reward,
+            min_inflation_rate,
             max_inflation_rate,
             target_staked_ratio,
             duplicate_vote_min_slash_rate,
             light_client_attack_min_slash_rate,
+            jail_len,
         };
 
         let mut genesis = Genesis {
-            genesis_time: genesis_time.try_into().unwrap(),
+            genesis_time: genesis_time
+                .0
+                .parse()
+                .expect("expected RFC3339 or Unix timestamp genesis_time"),
             native_token,
             validators: validators.into_values().collect(),
             token_accounts,
@@ -673,7 +764,7 @@ pub mod genesis_config {
     }
 }
 
-#[derive(Debug, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
 #[borsh_init(init)]
 pub struct Genesis {
     pub genesis_time: DateTimeUtc,
@@ -695,6 +786,175 @@ impl Genesis {
         self.established_accounts.sort();
         self.implicit_accounts.sort();
     }
+
+    /// Check the invariants of the genesis that `init_chain` relies on: the
+    /// epoch duration and the fee distribution are valid, the native token is
+    /// well-formed, every account is defined only once, every
+    /// validator has some stake and the total supply of every token fits in a
+    /// [`token::Amount`].
+    pub fn validate(&self) -> Result<(), GenesisError> {
+        self.parameters.epoch_duration.validate()?;
+        self.parameters.fee_distribution.validate()?;
+        self.validate_native_token()?;
+
+        let mut accounts = HashSet::new();
+        let addresses = self
+            .validators
+            .iter()
+            .map(|validator| validator.pos_data.address.clone())
+            .chain(
+                self.established_accounts
+                    .iter()
+                    .map(|account| account.address.clone()),
+            )
+            .chain(
+                self.implicit_accounts
+                    .iter()
+                    .map(|account| Address::from(&account.public_key)),
+            )
+            .chain(
+                self.token_accounts
+                    .iter()
+                    .map(|account| account.address.clone()),
+            );
+        for address in addresses {
+            if !accounts.insert(address.clone()) {
+                return Err(GenesisError::DuplicateAccount(address));
+            }
+        }
+
+        if let Some(validator) = self
+            .validators
+            .iter()
+            .find(|validator| validator.pos_data.tokens == 0.into())
+        {
+            return Err(GenesisError::ZeroStake(
+                validator.pos_data.address.clone(),
+            ));
+        }
+
+        let max_supply = token::Amount::max().change();
+        for token in &self.token_accounts {
+            let mut amounts: Vec<token::Amount> =
+                token.balances.values().copied().collect();
+            // The validators' stakes and non-staked balances are in the
+            // native token
+            if token.address == self.native_token {
+                for validator in &self.validators {
+                    amounts.push(validator.pos_data.tokens);
+                    amounts.push(validator.non_staked_balance);
+                }
+            }
+            let supply = amounts.iter().try_fold(0, |supply, amount| {
+                token::Change::checked_add(supply, amount.change())
+                    .filter(|supply| *supply <= max_supply)
+            });
+            if supply.is_none() {
+                return Err(GenesisError::SupplyOverflow(
+                    token.address.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the native token, in which the fees and the stakes are
+    /// paid, is an established address declared as a token account with a
+    /// VP.
+    pub fn validate_native_token(&self) -> Result<(), GenesisError> {
+        if !matches!(self.native_token, Address::Established(_)) {
+            return Err(GenesisError::NativeTokenNotEstablished(
+                self.native_token.clone(),
+            ));
+        }
+        match self
+            .token_accounts
+            .iter()
+            .find(|token| token.address == self.native_token)
+        {
+            Some(token) if token.vp_code_path.is_empty() => Err(
+                GenesisError::NativeTokenWithoutVp(self.native_token.clone()),
+            ),
+            Some(_) => Ok(()),
+            None => Err(GenesisError::NativeTokenNotDeclared(
+                self.native_token.clone(),
+            )),
+        }
+    }
+}
+
+/// A violation of the genesis invariants
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GenesisError {
+    #[error("The account {0} is defined more than once")]
+    DuplicateAccount(Address),
+    #[error("The genesis validator {0} has no stake")]
+    ZeroStake(Address),
+    #[error("The total supply of the token {0} overflows the maximum amount")]
+    SupplyOverflow(Address),
+    #[error("Invalid epoch duration: {0}")]
+    InvalidEpochDuration(#[from] EpochDurationError),
+    #[error("Invalid fee distribution: {0}")]
+    InvalidFeeDistribution(#[from] FeeDistributionError),
+    #[error("The native token {0} is not an established address")]
+    NativeTokenNotEstablished(Address),
+    #[error("The native token {0} is not declared in the genesis tokens")]
+    NativeTokenNotDeclared(Address),
+    #[error("The native token {0} has no validity predicate")]
+    NativeTokenWithoutVp(Address),
+}
+
+/// The path of the query for the genesis of a running node
+pub const GENESIS_QUERY_PATH: &str = "/genesis";
+
+/// The maximum number of validators in a page of the genesis query
+pub const MAX_GENESIS_PAGE_VALIDATORS: u64 = 100;
+
+/// A request for a page of the genesis of a running node. The validator set
+/// may be too large for a single response, so the validators are paginated.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct GenesisPageRequest {
+    /// The index of the first validator in the page
+    pub offset: u64,
+    /// The maximum number of validators in the page, capped by
+    /// [`MAX_GENESIS_PAGE_VALIDATORS`]
+    pub limit: u64,
+}
+
+/// A page of the genesis of a running node
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct GenesisPage {
+    /// The chain ID of the node
+    pub chain_id: ChainId,
+    /// The total number of validators in the genesis
+    pub total_validators: u64,
+    /// The genesis, which only contains the requested page of validators
+    pub genesis: Genesis,
+}
+
+impl GenesisPage {
+    /// Take the requested page of the genesis' validators
+    pub fn new(
+        chain_id: ChainId,
+        mut genesis: Genesis,
+        request: &GenesisPageRequest,
+    ) -> Self {
+        let total_validators = genesis.validators.len() as u64;
+        let offset = request.offset.min(total_validators) as usize;
+        let limit = request.limit.min(MAX_GENESIS_PAGE_VALIDATORS) as usize;
+        genesis.validators = genesis
+            .validators
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .collect();
+        Self {
+            chain_id,
+            total_validators,
+            genesis,
+        }
+    }
 }
 
 #[derive(
@@ -800,6 +1060,8 @@ pub struct Parameters {
     pub epoch_duration: EpochDuration,
     /// Maximum expected time per block
     pub max_expected_time_per_block: DurationSecs,
+    /// Maximum size of the data of an IBC packet in bytes
+    pub max_ibc_packet_data_bytes: u64,
     /// Whitelisted validity predicate hashes
     pub vp_whitelist: Vec<String>,
     /// Whitelisted tx hashes
@@ -818,6 +1080,19 @@ pub struct Parameters {
     pub staked_ratio: Decimal,
     /// PoS inflation amount from the last epoch (read + write for every epoch)
     pub pos_inflation_amount: u64,
+    /// Distribution of the fees paid by the txs of a block
+    pub fee_distribution: FeeDistribution,
+    /// Maximum sum of the gas limits of the wrapper txs included in a block
+    pub block_gas_limit: u64,
+    /// Maximum number of verifiers that a tx may trigger, if any
+    pub max_verifiers: Option<u64>,
+    /// The tokens other than the native token that are accepted for the
+    /// fees, with their conversion rates to the native token
+    pub fee_tokens: BTreeMap<Address, Decimal>,
+    /// Maximum memory of VP WASM execution in 64 KiB WASM pages
+    pub vp_wasm_memory_max_pages: u32,
+    /// Maximum memory of tx WASM execution in 64 KiB WASM pages
+    pub tx_wasm_memory_max_pages: u32,
 }
 
 #[cfg(not(feature = "dev"))]
@@ -827,9 +1102,23 @@ pub fn genesis(base_dir: impl AsRef<Path>, chain_id: &ChainId) -> Genesis {
         .join(format!("{}.toml", chain_id.as_str()));
     genesis_config::read_genesis_config(path)
 }
+#[cfg(not(feature = "dev"))]
+/// Read the genesis from its config file, returning an error rather than
+/// panicking if the file cannot be read or parsed.
+pub fn try_genesis(
+    base_dir: impl AsRef<Path>,
+    chain_id: &ChainId,
+) -> color_eyre::eyre::Result<Genesis> {
+    let path = base_dir
+        .as_ref()
+        .join(format!("{}.toml", chain_id.as_str()));
+    genesis_config::open_genesis_config(path)
+        .map(genesis_config::load_genesis_config)
+}
 #[cfg(feature = "dev")]
 pub fn genesis() -> Genesis {
     use namada::types::address;
+    use namada::vm::wasm::memory;
     use rust_decimal_macros::dec;
 
     use crate::wallet;
@@ -867,6 +1156,8 @@ pub fn genesis() -> Genesis {
             min_duration: namada::types::time::Duration::seconds(600).into(),
         },
         max_expected_time_per_block: namada::types::time::DurationSecs(30),
+        max_ibc_packet_data_bytes:
+            namada::types::ibc::DEFAULT_MAX_PACKET_DATA_BYTES,
         vp_whitelist: vec![],
         tx_whitelist: vec![],
         implicit_vp_code_path: vp_implicit_path.into(),
@@ -877,6 +1168,12 @@ pub fn genesis() -> Genesis {
         pos_gain_d: dec!(0.1),
         staked_ratio: dec!(0.0),
         pos_inflation_amount: 0,
+        fee_distribution: FeeDistribution::default(),
+        block_gas_limit: namada::ledger::gas::BLOCK_GAS_LIMIT,
+        max_verifiers: None,
+        fee_tokens: BTreeMap::new(),
+        vp_wasm_memory_max_pages: memory::VP_MEMORY_MAX_PAGES,
+        tx_wasm_memory_max_pages: memory::TX_MEMORY_MAX_PAGES,
     };
     let albert = EstablishedAccount {
         address: wallet::defaults::albert_address(),
@@ -958,11 +1255,14 @@ pub fn genesis() -> Genesis {
 #[cfg(test)]
 pub mod tests {
     use borsh::BorshSerialize;
+    use namada::types::address;
     use namada::types::address::testing::gen_established_address;
     use namada::types::key::*;
     use rand::prelude::ThreadRng;
     use rand::thread_rng;
+    use rust_decimal_macros::dec;
 
+    use super::*;
     use crate::wallet;
 
     /// Run `cargo test gen_genesis_validator -- --nocapture` to generate a
@@ -981,4 +1281,182 @@ pub mod tests {
         println!("protocol_keypair: {:?}", protocol_keypair);
         println!("dkg_keypair: {:?}", dkg_keypair.try_to_vec().unwrap());
     }
+
+    /// Test that the dev genesis is valid.
+    #[test]
+    fn test_validate_dev_genesis() {
+        assert_eq!(genesis().validate(), Ok(()));
+    }
+
+    /// Test that an account defined more than once is rejected, including
+    /// when it's defined by accounts of different kinds.
+    #[test]
+    fn test_validate_duplicate_account() {
+        let mut genesis = genesis();
+        let account = genesis.established_accounts[0].clone();
+        genesis.established_accounts.push(account.clone());
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::DuplicateAccount(account.address))
+        );
+
+        let mut genesis = super::genesis();
+        let mut account = genesis.established_accounts[0].clone();
+        account.address = genesis.validators[0].pos_data.address.clone();
+        genesis.established_accounts.push(account.clone());
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::DuplicateAccount(account.address))
+        );
+    }
+
+    /// Test that a validator with zero stake is rejected.
+    #[test]
+    fn test_validate_zero_stake() {
+        let mut genesis = genesis();
+        genesis.validators[0].pos_data.tokens = token::Amount::from(0);
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::ZeroStake(
+                genesis.validators[0].pos_data.address.clone()
+            ))
+        );
+    }
+
+    /// Test that an epoch duration without a minimum number of blocks or a
+    /// minimum duration is rejected.
+    #[test]
+    fn test_validate_epoch_duration() {
+        let mut zero_blocks = genesis();
+        zero_blocks.parameters.epoch_duration.min_num_of_blocks = 0;
+        assert_eq!(
+            zero_blocks.validate(),
+            Err(GenesisError::InvalidEpochDuration(
+                EpochDurationError::ZeroNumOfBlocks
+            ))
+        );
+
+        let mut zero_duration = genesis();
+        zero_duration.parameters.epoch_duration.min_duration = DurationSecs(0);
+        assert_eq!(
+            zero_duration.validate(),
+            Err(GenesisError::InvalidEpochDuration(
+                EpochDurationError::ZeroDuration
+            ))
+        );
+    }
+
+    /// Test that a fee distribution whose rates don't sum up to 1 or with a
+    /// rate out of bounds is rejected.
+    #[test]
+    fn test_validate_fee_distribution() {
+        let mut genesis = genesis();
+        genesis.parameters.fee_distribution = FeeDistribution {
+            burn_rate: dec!(0.5),
+            proposer_rate: dec!(0.25),
+            community_pool_rate: dec!(0.25),
+            ..FeeDistribution::default()
+        };
+        assert_eq!(genesis.validate(), Ok(()));
+
+        genesis.parameters.fee_distribution.community_pool_rate = dec!(0.2);
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::InvalidFeeDistribution(
+                FeeDistributionError::RatesSum(dec!(0.95))
+            ))
+        );
+
+        genesis.parameters.fee_distribution = FeeDistribution {
+            burn_rate: dec!(1.5),
+            proposer_rate: dec!(-0.5),
+            community_pool_rate: dec!(0),
+            ..FeeDistribution::default()
+        };
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::InvalidFeeDistribution(
+                FeeDistributionError::InvalidRate(dec!(1.5))
+            ))
+        );
+    }
+
+    /// Test that a balance that overflows the total supply of a token is
+    /// rejected, counting the validators' tokens in the native token supply.
+    #[test]
+    fn test_validate_supply_overflow() {
+        let mut genesis = genesis();
+        let native_token = genesis.native_token.clone();
+        let token = genesis
+            .token_accounts
+            .iter_mut()
+            .find(|token| token.address != native_token)
+            .unwrap();
+        let owner = address::testing::established_address_1();
+        token.balances.insert(owner, token::Amount::max());
+        let token = token.address.clone();
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::SupplyOverflow(token))
+        );
+
+        let mut genesis = super::genesis();
+        let validator_tokens = genesis.validators[0].pos_data.tokens;
+        let native_token = genesis
+            .token_accounts
+            .iter_mut()
+            .find(|token| token.address == native_token)
+            .unwrap();
+        native_token.balances = HashMap::from([(
+            address::testing::established_address_1(),
+            token::Amount::max().saturating_sub(&validator_tokens),
+        )]);
+        assert_eq!(
+            genesis.validate(),
+            Err(GenesisError::SupplyOverflow(genesis.native_token.clone()))
+        );
+    }
+
+    /// Test that a native token that's missing from the genesis tokens, that
+    /// has no VP or that's not an established address is rejected.
+    #[test]
+    fn test_validate_native_token() {
+        let mut genesis = genesis();
+        let native_token = genesis.native_token.clone();
+        genesis
+            .token_accounts
+            .retain(|token| token.address != native_token);
+        let error = genesis.validate().unwrap_err();
+        assert_eq!(
+            error,
+            GenesisError::NativeTokenNotDeclared(native_token.clone())
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The native token {} is not declared in the genesis tokens",
+                native_token
+            )
+        );
+
+        let mut genesis = super::genesis();
+        genesis
+            .token_accounts
+            .iter_mut()
+            .find(|token| token.address == native_token)
+            .unwrap()
+            .vp_code_path = String::new();
+        assert_eq!(
+            genesis.validate_native_token(),
+            Err(GenesisError::NativeTokenWithoutVp(native_token))
+        );
+
+        let mut genesis = super::genesis();
+        let implicit = address::testing::gen_implicit_address();
+        genesis.native_token = implicit.clone();
+        assert_eq!(
+            genesis.validate_native_token(),
+            Err(GenesisError::NativeTokenNotEstablished(implicit))
+        );
+    }
 }
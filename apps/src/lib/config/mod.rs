@@ -10,8 +10,10 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use namada::types::address::Address;
 use namada::types::chain::ChainId;
-use namada::types::time::Rfc3339String;
+use namada::types::time::{DateTimeUtc, Duration, Rfc3339String};
+use namada::types::token;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -32,10 +34,20 @@ pub const FILENAME: &str = "config.toml";
 pub const TENDERMINT_DIR: &str = "tendermint";
 /// Chain-specific Namada DB. Nested in chain dirs.
 pub const DB_DIR: &str = "db";
+/// A genesis time further in the future than this many hours from the node's
+/// start-up triggers a warning.
+pub const GENESIS_TIME_FAR_FUTURE_HOURS: i64 = 24;
+/// The smallest RocksDB memtable size that may be configured.
+pub const DB_MIN_WRITE_BUFFER_SIZE_BYTES: u64 = 64 * 1024;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     pub wasm_dir: PathBuf,
+    /// Directories, possibly read-only, that are searched in order for the
+    /// WASM artifacts before the `wasm_dir`. Relative paths are resolved
+    /// from the chain directory.
+    #[serde(default)]
+    pub wasm_search_dirs: Vec<PathBuf>,
     pub ledger: Ledger,
 }
 
@@ -67,6 +79,25 @@ impl From<String> for TendermintMode {
     }
 }
 
+/// The retention of the historical state of the committed blocks, beyond
+/// which it's pruned from the DB
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PruningPolicy {
+    /// The state of all the blocks is retained
+    KeepAll,
+    /// The state of the blocks of the current epoch and of this many
+    /// preceding epochs is retained
+    KeepRecent(u64),
+    /// Only the state of the last committed block is retained
+    KeepNothingButLatest,
+}
+
+impl Default for PruningPolicy {
+    fn default() -> Self {
+        Self::KeepAll
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ledger {
     pub genesis_time: Rfc3339String,
@@ -91,6 +122,158 @@ pub struct Shell {
     /// When set, will limit the how many block heights in the past can the
     /// storage be queried for reading values.
     pub storage_read_past_height_limit: Option<u64>,
+    /// When set, the results of up to this many recent queries are cached in
+    /// memory, keyed by their path, data and height. The queries that execute
+    /// txs are never cached. When not set, the queries are not cached.
+    #[serde(default)]
+    pub query_cache_size: Option<usize>,
+    /// RocksDB memtable (write buffer) size in bytes.
+    /// When not set, defaults to RocksDB's default of 64 MiB.
+    #[serde(default)]
+    pub db_write_buffer_size_bytes: Option<u64>,
+    /// RocksDB maximum number of concurrent background jobs (compactions and
+    /// flushes). Must be at least 1.
+    /// When not set, defaults to the number of RocksDB compaction threads.
+    #[serde(default)]
+    pub db_max_background_jobs: Option<u32>,
+    /// RocksDB maximum total size of the write-ahead log in bytes.
+    /// When not set, it's derived by RocksDB from the memtables' size.
+    #[serde(default)]
+    pub db_max_total_wal_size_bytes: Option<u64>,
+    /// When set, RocksDB incrementally syncs the write-ahead log to disk every
+    /// this many bytes written.
+    #[serde(default)]
+    pub db_wal_bytes_per_sync: Option<u64>,
+    /// When set, wrapper txs offering a fee per unit of gas lower than this
+    /// amount of the native token are rejected from the mempool. The fees
+    /// paid in the fee tokens of the protocol parameters are converted to
+    /// the native token with their rates to be compared.
+    #[serde(default)]
+    pub min_gas_price: Option<token::Amount>,
+    /// The number of blocks for which the results of the applied txs can be
+    /// queried by tx hash.
+    /// When not set, defaults to 1000 blocks.
+    #[serde(default)]
+    pub tx_results_retention_blocks: Option<u64>,
+    /// The number of blocks for which the events emitted by a block can be
+    /// queried by its height.
+    /// When not set, defaults to 1000 blocks.
+    #[serde(default)]
+    pub block_events_retention_blocks: Option<u64>,
+    /// The duration in seconds after which an IBC packet sent from this chain
+    /// and still awaiting an acknowledgement is marked as timed-out in the
+    /// node's local tracking, for cleanup. It never overrides the timeout
+    /// height and timestamp of the packet on-chain.
+    /// When not set, defaults to 1 day.
+    #[serde(default)]
+    pub ibc_ack_timeout_secs: Option<u64>,
+    /// The duration in seconds for which an IBC packet marked as timed-out
+    /// locally keeps being tracked and can be queried, after which it's
+    /// forgotten.
+    /// When not set, defaults to 7 days.
+    #[serde(default)]
+    pub ibc_timed_out_retention_secs: Option<u64>,
+    /// When not empty, only wrapper txs whose fee payer is in this list are
+    /// admitted to the mempool.
+    #[serde(default)]
+    pub mempool_allowlist: Vec<Address>,
+    /// Wrapper txs whose fee payer is in this list are rejected from the
+    /// mempool, even when the fee payer is also in the allowlist.
+    #[serde(default)]
+    pub mempool_denylist: Vec<Address>,
+    /// The maximum number of protocol txs waiting to be broadcast. When the
+    /// queue is full, the shell waits for room rather than dropping the tx.
+    /// When not set, defaults to 1024.
+    #[serde(default)]
+    pub broadcaster_queue_capacity: Option<usize>,
+    /// The maximum number of consensus requests from Tendermint queued for
+    /// the shell. Unlike the mempool and info requests, consensus requests
+    /// are never dropped when the queue is full, Tendermint waits for room
+    /// instead. When not set, defaults to 100.
+    #[serde(default)]
+    pub consensus_buffer_size: Option<usize>,
+    /// The maximum number of WASMs that are fetched concurrently on start-up.
+    /// When not set, defaults to 8.
+    #[serde(default)]
+    pub wasm_prefetch_max_concurrent: Option<usize>,
+    /// When set, all the WASMs are attempted to be fetched on start-up even
+    /// when some of them fail, before the first failure is reported.
+    /// Otherwise, the first failure aborts the other fetches.
+    #[serde(default)]
+    pub wasm_prefetch_continue_on_error: bool,
+    /// The number of threads of the pool that runs the VPs of txs, separate
+    /// from the global Rayon pool. Each thread runs one VP at a time, so this
+    /// is also the maximum number of VP instances, each with its own WASM
+    /// memory, that are live at once. Set it to 1 to run the VPs serially,
+    /// e.g. for debugging. When not set, defaults to the number of threads of
+    /// the global Rayon pool.
+    #[serde(default)]
+    pub vp_threads: Option<usize>,
+    /// When set, the liveness and readiness probes are served over HTTP on
+    /// this address, at `/healthz` and `/readyz` respectively.
+    #[serde(default)]
+    pub health_check_address: Option<SocketAddr>,
+    /// When set, the summaries of the committed blocks are streamed to the
+    /// subscribers connecting over TCP to this address, as JSON lines.
+    #[serde(default)]
+    pub block_stream_address: Option<SocketAddr>,
+    /// When set, a panic in the shell aborts the process immediately after
+    /// it's logged, e.g. to be restarted sooner by a process supervisor.
+    /// Otherwise, the node shuts down its other tasks cleanly before the
+    /// panic is propagated, which can take a while. It can also be set with
+    /// the env var `NAMADA_LEDGER__SHELL__ABORT_ON_PANIC`.
+    #[serde(default)]
+    pub abort_on_panic: bool,
+    /// The retention of the historical state of the committed blocks, which
+    /// is pruned after each block is committed. Queries at the pruned
+    /// heights are rejected. Defaults to keeping the state of all the blocks.
+    #[serde(default)]
+    pub pruning_policy: PruningPolicy,
+    /// Log 1 in this many of the ABCI requests of each type that's not
+    /// consensus-critical, e.g. `Query` and `CheckTx`, at the debug level.
+    /// The `InitChain`, `PrepareProposal`, `ProcessProposal`,
+    /// `FinalizeBlock` and `Commit` requests are always logged. When not set
+    /// or set to 0, none of the other requests are logged.
+    #[serde(default)]
+    pub abci_request_log_sample_rate: Option<u64>,
+    /// The maximum time in milliseconds that a query waits for the shell's
+    /// response, beyond which it's aborted with a timeout response that can
+    /// be retried, so that a slow query doesn't hold a slot of the info
+    /// requests' buffer. When not set, defaults to 10 seconds.
+    /// Note that this doesn't bound the shell's work: a query that the shell
+    /// already started keeps running until it completes, only the queries
+    /// that timed out before the shell got to them are skipped.
+    #[serde(default)]
+    pub query_timeout_ms: Option<u64>,
+    /// Whether to serve the snapshots of the state to the nodes that state
+    /// sync from this node. When disabled, `ListSnapshots` returns no
+    /// snapshots and `LoadSnapshotChunk` no chunk, without reaching the shell.
+    /// This node can still state sync from other nodes. When not set,
+    /// defaults to `true`.
+    #[serde(default)]
+    pub serve_snapshots: Option<bool>,
+    /// The interval in seconds at which the time left until the genesis time
+    /// is logged, while a node started before the genesis time waits for it.
+    /// When not set, defaults to 1 minute.
+    #[serde(default)]
+    pub genesis_wait_log_interval_secs: Option<u64>,
+    /// When set, the traces and the metrics of the node are exported to the
+    /// OpenTelemetry collector at this OTLP gRPC endpoint, e.g.
+    /// `http://localhost:4317`. It can also be set with the env var
+    /// `NAMADA_LEDGER__SHELL__OTLP_ENDPOINT`.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// The ratio of the traces that are exported to the OpenTelemetry
+    /// collector, between `0` and `1`. It can also be set with the env var
+    /// `NAMADA_LEDGER__SHELL__OTLP_SAMPLING_RATE`. When not set, defaults to
+    /// exporting all the traces.
+    #[serde(default)]
+    pub otlp_sampling_rate: Option<f64>,
+    /// Dev only: when set, every epoch ends after this many blocks,
+    /// regardless of the epoch duration protocol parameters.
+    #[cfg(feature = "dev")]
+    #[serde(default)]
+    pub dev_epoch_num_of_blocks: Option<u64>,
     /// Use the [`Ledger::db_dir()`] method to read the value.
     db_dir: PathBuf,
     /// Use the [`Ledger::tendermint_dir()`] method to read the value.
@@ -140,6 +323,35 @@ impl Ledger {
                 tx_wasm_compilation_cache_bytes: None,
                 // Default corresponds to 1 hour of past blocks at 1 block/sec
                 storage_read_past_height_limit: Some(3600),
+                query_cache_size: None,
+                db_write_buffer_size_bytes: None,
+                db_max_background_jobs: None,
+                db_max_total_wal_size_bytes: None,
+                db_wal_bytes_per_sync: None,
+                min_gas_price: None,
+                tx_results_retention_blocks: None,
+                block_events_retention_blocks: None,
+                ibc_ack_timeout_secs: None,
+                ibc_timed_out_retention_secs: None,
+                mempool_allowlist: vec![],
+                mempool_denylist: vec![],
+                broadcaster_queue_capacity: None,
+                consensus_buffer_size: None,
+                wasm_prefetch_max_concurrent: None,
+                wasm_prefetch_continue_on_error: false,
+                vp_threads: None,
+                health_check_address: None,
+                block_stream_address: None,
+                abort_on_panic: false,
+                pruning_policy: PruningPolicy::default(),
+                abci_request_log_sample_rate: None,
+                query_timeout_ms: None,
+                serve_snapshots: None,
+                genesis_wait_log_interval_secs: None,
+                otlp_endpoint: None,
+                otlp_sampling_rate: None,
+                #[cfg(feature = "dev")]
+                dev_epoch_num_of_blocks: None,
                 db_dir: DB_DIR.into(),
                 tendermint_dir: TENDERMINT_DIR.into(),
             },
@@ -168,6 +380,16 @@ impl Ledger {
         }
     }
 
+    /// Parse the configured genesis time.
+    pub fn parsed_genesis_time(&self) -> Result<DateTimeUtc> {
+        DateTimeUtc::try_from(self.genesis_time.clone()).map_err(|err| {
+            Error::InvalidGenesisTime(
+                self.genesis_time.0.clone(),
+                err.to_string(),
+            )
+        })
+    }
+
     /// Get the chain directory path
     pub fn chain_dir(&self) -> PathBuf {
         self.shell.base_dir.join(self.chain_id.as_str())
@@ -196,6 +418,29 @@ impl Shell {
             .join(chain_id.as_str())
             .join(&self.tendermint_dir)
     }
+
+    /// Check that the configured RocksDB options are in their valid ranges.
+    pub fn validate_db_options(&self) -> Result<()> {
+        if let Some(size) = self.db_write_buffer_size_bytes {
+            if size < DB_MIN_WRITE_BUFFER_SIZE_BYTES {
+                return Err(Error::InvalidDbOption(
+                    "db_write_buffer_size_bytes",
+                    size,
+                    "must be at least 64 KiB",
+                ));
+            }
+        }
+        if let Some(jobs) = self.db_max_background_jobs {
+            if jobs < 1 {
+                return Err(Error::InvalidDbOption(
+                    "db_max_background_jobs",
+                    jobs as u64,
+                    "must be at least 1",
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -215,10 +460,27 @@ pub enum Error {
          {{protocol}}/{{ip}}/tcp/{{port}}/p2p/{{peerid}}"
     )]
     BadBootstrapPeerFormat(String),
+    #[error(
+        "Invalid `ledger.genesis_time` \"{0}\": {1}. Expected an RFC3339 \
+         timestamp, e.g. \"2022-12-31T12:00:00Z\""
+    )]
+    InvalidGenesisTime(String, String),
+    #[error("Invalid `ledger.shell.{0}` value {1}: {2}")]
+    InvalidDbOption(&'static str, u64, &'static str),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Check if the given genesis time is further in the future than
+/// [`GENESIS_TIME_FAR_FUTURE_HOURS`] from `now`. The chain won't start
+/// producing blocks until its genesis time.
+pub fn is_genesis_time_far_future(
+    genesis_time: DateTimeUtc,
+    now: DateTimeUtc,
+) -> bool {
+    genesis_time.0 - now.0 > Duration::hours(GENESIS_TIME_FAR_FUTURE_HOURS)
+}
+
 #[derive(Error, Debug)]
 pub enum SerdeError {
     // This is needed for serde https://serde.rs/error-handling.html
@@ -239,6 +501,7 @@ impl Config {
     ) -> Self {
         Self {
             wasm_dir: DEFAULT_WASM_DIR.into(),
+            wasm_search_dirs: vec![],
             ledger: Ledger::new(base_dir, chain_id, mode),
         }
     }
@@ -298,7 +561,11 @@ impl Config {
                 )
             })
             .map_err(Error::ReadError)?;
-        config.try_into().map_err(Error::DeserializationError)
+        let config: Self =
+            config.try_into().map_err(Error::DeserializationError)?;
+        config.ledger.parsed_genesis_time()?;
+        config.ledger.shell.validate_db_options()?;
+        Ok(config)
     }
 
     /// Generate configuration and write it to a file.
@@ -370,3 +637,109 @@ And this is correct
        nested:Nested,
     }
 "#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Test that a malformed genesis time is rejected when the config is read.
+    #[test]
+    fn test_malformed_genesis_time() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let mut config = Config::new(
+            base_dir.path(),
+            chain_id.clone(),
+            TendermintMode::Full,
+        );
+        config.ledger.genesis_time =
+            Rfc3339String("2022-12-31 12:00:00".to_owned());
+        config.write(base_dir.path(), &chain_id, true).unwrap();
+
+        let result = Config::read(base_dir.path(), &chain_id, None);
+        match result {
+            Err(Error::InvalidGenesisTime(genesis_time, _)) => {
+                assert_eq!(genesis_time, "2022-12-31 12:00:00")
+            }
+            _ => panic!("Expected invalid genesis time, got {:?}", result),
+        }
+    }
+
+    /// Test that the RocksDB options survive a write and read of the config.
+    #[test]
+    fn test_db_options_round_trip() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let mut config = Config::new(
+            base_dir.path(),
+            chain_id.clone(),
+            TendermintMode::Full,
+        );
+        config.ledger.shell.db_write_buffer_size_bytes =
+            Some(128 * 1024 * 1024);
+        config.ledger.shell.db_max_background_jobs = Some(4);
+        config.ledger.shell.db_max_total_wal_size_bytes =
+            Some(1024 * 1024 * 1024);
+        config.ledger.shell.db_wal_bytes_per_sync = Some(1024 * 1024);
+        config.write(base_dir.path(), &chain_id, true).unwrap();
+
+        let read = Config::read(base_dir.path(), &chain_id, None).unwrap();
+        let shell = read.ledger.shell;
+        assert_eq!(shell.db_write_buffer_size_bytes, Some(128 * 1024 * 1024));
+        assert_eq!(shell.db_max_background_jobs, Some(4));
+        assert_eq!(shell.db_max_total_wal_size_bytes, Some(1024 * 1024 * 1024));
+        assert_eq!(shell.db_wal_bytes_per_sync, Some(1024 * 1024));
+    }
+
+    /// Test that out of range RocksDB options are rejected when the config is
+    /// read.
+    #[test]
+    fn test_invalid_db_options() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let mut config = Config::new(
+            base_dir.path(),
+            chain_id.clone(),
+            TendermintMode::Full,
+        );
+        config.ledger.shell.db_max_background_jobs = Some(0);
+        config.write(base_dir.path(), &chain_id, true).unwrap();
+        let result = Config::read(base_dir.path(), &chain_id, None);
+        match result {
+            Err(Error::InvalidDbOption("db_max_background_jobs", 0, _)) => {}
+            _ => panic!("Expected invalid DB option, got {:?}", result),
+        }
+
+        config.ledger.shell.db_max_background_jobs = None;
+        config.ledger.shell.db_write_buffer_size_bytes = Some(1024);
+        config.write(base_dir.path(), &chain_id, true).unwrap();
+        let result = Config::read(base_dir.path(), &chain_id, None);
+        match result {
+            Err(Error::InvalidDbOption(
+                "db_write_buffer_size_bytes",
+                1024,
+                _,
+            )) => {}
+            _ => panic!("Expected invalid DB option, got {:?}", result),
+        }
+    }
+
+    /// Test that a valid genesis time far in the future is detected.
+    #[test]
+    fn test_far_future_genesis_time() {
+        let now = DateTimeUtc::now();
+        let far_future = DateTimeUtc(
+            now.0 + Duration::hours(GENESIS_TIME_FAR_FUTURE_HOURS + 1),
+        );
+        let ledger = Ledger {
+            genesis_time: far_future.into(),
+            ..Ledger::new("", ChainId::default(), TendermintMode::Full)
+        };
+        let genesis_time = ledger.parsed_genesis_time().unwrap();
+        assert!(is_genesis_time_far_future(genesis_time, now));
+
+        let soon = DateTimeUtc(now.0 + Duration::minutes(1));
+        assert!(!is_genesis_time_far_future(soon, now));
+        assert!(!is_genesis_time_far_future(now, now));
+    }
+}
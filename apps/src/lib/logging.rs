@@ -3,9 +3,16 @@ use std::env;
 
 use color_eyre::eyre::Result;
 use eyre::WrapErr;
+use opentelemetry::sdk::trace::Tracer;
+use tracing::Level;
 use tracing_log::LogTracer;
-use tracing_subscriber::filter::{Directive, EnvFilter};
+use tracing_subscriber::filter::{Directive, EnvFilter, LevelFilter, Targets};
 use tracing_subscriber::fmt::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+
+use crate::config;
+use crate::telemetry::{self, Telemetry};
 
 pub const ENV_KEY: &str = "NAMADA_LOG";
 
@@ -18,27 +25,186 @@ pub fn init_from_env_or(default: impl Into<Directive>) -> Result<()> {
     init_log_tracer()
 }
 
+/// Like [`init_from_env_or`], but also export the traces and the metrics to
+/// OpenTelemetry, if it's configured in the given ledger's config. An
+/// invalid configuration is logged as a warning and the node continues
+/// without the export. The returned exporters must be kept alive while the
+/// node runs.
+pub fn init_node_from_env_or(
+    default: impl Into<Directive>,
+    config: &config::Shell,
+) -> Result<Option<Telemetry>> {
+    let filter = filter_from_env_or(default);
+    let telemetry =
+        telemetry::OtlpConfig::from_config(config).and_then(|config| {
+            config.map(|config| telemetry::install(&config)).transpose()
+        });
+    let (telemetry, error) = match telemetry {
+        Ok(Some((tracer, telemetry))) => {
+            set_subscriber_with_otlp(filter, tracer)?;
+            (Some(telemetry), None)
+        }
+        Ok(None) => {
+            set_subscriber(filter)?;
+            (None, None)
+        }
+        Err(err) => {
+            set_subscriber(filter)?;
+            (None, Some(err))
+        }
+    };
+    init_log_tracer()?;
+    if let Some(err) = error {
+        tracing::warn!("{}. Continuing without the OpenTelemetry export.", err);
+    }
+    Ok(telemetry)
+}
+
+/// Build a filter from the `RUST_LOG`-style directives in [`ENV_KEY`] env var,
+/// e.g. `NAMADA_LOG=namada::ledger::ethereum_node=debug,info`. When the env
+/// var is unset or it only contains module-specific directives, the given
+/// default level is applied to everything else.
 pub fn filter_from_env_or(default: impl Into<Directive>) -> EnvFilter {
-    env::var(ENV_KEY)
-        .map(EnvFilter::new)
-        .unwrap_or_else(|_| EnvFilter::default().add_directive(default.into()))
+    filter_from_directives_or(env::var(ENV_KEY).ok(), default)
 }
 
-pub fn set_subscriber(filter: EnvFilter) -> Result<()> {
-    let with_color = if let Ok(val) = env::var(COLOR_ENV_KEY) {
-        val.to_ascii_lowercase() != "false"
-    } else {
-        true
-    };
+fn filter_from_directives_or(
+    directives: Option<String>,
+    default: impl Into<Directive>,
+) -> EnvFilter {
+    match directives {
+        Some(directives) => {
+            let has_global_level = directives.split(',').any(|directive| {
+                let directive = directive.trim();
+                !directive.is_empty()
+                    && !directive.contains('=')
+                    && directive.parse::<LevelFilter>().is_ok()
+            });
+            let filter = EnvFilter::new(directives);
+            if has_global_level {
+                filter
+            } else {
+                filter.add_directive(default.into())
+            }
+        }
+        None => EnvFilter::default().add_directive(default.into()),
+    }
+}
 
+pub fn set_subscriber(filter: EnvFilter) -> Result<()> {
     let my_collector = Subscriber::builder()
-        .with_ansi(with_color)
+        .with_ansi(with_color())
         .with_env_filter(filter)
         .finish();
     tracing::subscriber::set_global_default(my_collector)
         .wrap_err("Failed to set log subscriber")
 }
 
+/// Set a subscriber that also exports the spans with the given tracer. The
+/// spans and events of Namada are exported down to the debug level, e.g. the
+/// spans of the VPs, regardless of the log filter.
+fn set_subscriber_with_otlp(filter: EnvFilter, tracer: Tracer) -> Result<()> {
+    let otlp_filter = Targets::new()
+        .with_target("namada", Level::DEBUG)
+        .with_target("namada_apps", Level::DEBUG);
+    let subscriber = tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(with_color())
+                .with_filter(filter),
+        )
+        .with(
+            tracing_opentelemetry::layer()
+                .with_tracer(tracer)
+                .with_filter(otlp_filter),
+        );
+    tracing::subscriber::set_global_default(subscriber)
+        .wrap_err("Failed to set log subscriber")
+}
+
 pub fn init_log_tracer() -> Result<()> {
     LogTracer::init().wrap_err("Failed to initialize log adapter")
 }
+
+/// Check if the logs should be colored, which they are unless disabled by the
+/// [`COLOR_ENV_KEY`] env var
+fn with_color() -> bool {
+    if let Ok(val) = env::var(COLOR_ENV_KEY) {
+        val.to_ascii_lowercase() != "false"
+    } else {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Emit a debug and an info event from two modules with a filter built
+    /// from the given directives and return the captured output.
+    fn capture(directives: Option<&str>) -> String {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let filter = filter_from_directives_or(
+            directives.map(ToString::to_string),
+            LevelFilter::INFO,
+        );
+        let subscriber = Subscriber::builder()
+            .with_ansi(false)
+            .with_env_filter(filter)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(
+                target: "namada::ledger::ethereum_node",
+                "oracle debug"
+            );
+            tracing::info!(target: "namada::ledger::ethereum_node", "oracle info");
+            tracing::debug!(target: "namada::ledger::shell", "shell debug");
+            tracing::info!(target: "namada::ledger::shell", "shell info");
+        });
+        let output = buffer.0.lock().unwrap();
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    /// Test that a module-specific directive raises the verbosity of only that
+    /// module, while the default level applies to everything else.
+    #[test]
+    fn test_module_specific_filter() {
+        // Without any directives, the default level applies
+        let output = capture(None);
+        assert!(!output.contains("oracle debug"), "{}", output);
+        assert!(output.contains("oracle info"), "{}", output);
+        assert!(!output.contains("shell debug"), "{}", output);
+        assert!(output.contains("shell info"), "{}", output);
+
+        // A module directive with the default level for the rest
+        let output = capture(Some("namada::ledger::ethereum_node=debug"));
+        assert!(output.contains("oracle debug"), "{}", output);
+        assert!(output.contains("oracle info"), "{}", output);
+        assert!(!output.contains("shell debug"), "{}", output);
+        assert!(output.contains("shell info"), "{}", output);
+
+        // A module directive with an explicit global level
+        let output = capture(Some("namada::ledger::ethereum_node=debug,warn"));
+        assert!(output.contains("oracle debug"), "{}", output);
+        assert!(!output.contains("shell debug"), "{}", output);
+        assert!(!output.contains("shell info"), "{}", output);
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt::{self, Display};
 use std::future::Future;
 use std::pin::Pin;
 
@@ -11,8 +12,8 @@ pub type AbortingTask = &'static str;
 /// An [`AbortableSpawner`] will spawn abortable tasks into the asynchronous
 /// runtime.
 pub struct AbortableSpawner {
-    abort_send: UnboundedSender<AbortingTask>,
-    abort_recv: UnboundedReceiver<AbortingTask>,
+    abort_send: UnboundedSender<AbortReason>,
+    abort_recv: UnboundedReceiver<AbortReason>,
     cleanup_jobs: Vec<Pin<Box<dyn Future<Output = ()>>>>,
 }
 
@@ -66,18 +67,21 @@ impl AbortableSpawner {
     /// This future will resolve when:
     ///
     ///   1. A user sends a shutdown signal (e.g. SIGINT), or...
-    ///   2. One of the child processes of the ledger terminates,
-    ///      which generates a notification upon dropping an [`Aborter`].
+    ///   2. One of the child processes of the ledger terminates, which
+    ///      generates a notification upon dropping an [`Aborter`], or...
+    ///   3. One of the child processes of the ledger fails with an internal
+    ///      error, reported with [`Aborter::abort_with_error`].
     ///
-    /// These two scenarios are represented by the [`AborterStatus`] enum.
-    pub async fn wait_for_abort(self) -> AborterStatus {
-        let status = wait_for_abort(self.abort_recv).await;
+    /// These scenarios are represented by the [`AbortReason`] enum. When
+    /// several child processes abort, the first abort is reported.
+    pub async fn wait_for_abort(self) -> AbortReason {
+        let reason = wait_for_abort(self.abort_recv).await;
 
         for job in self.cleanup_jobs {
             job.await;
         }
 
-        status
+        reason
     }
 
     /// This method is responsible for actually spawning the async task into the
@@ -95,6 +99,8 @@ impl AbortableSpawner {
         let abort = Aborter {
             who,
             sender: self.abort_send.clone(),
+            error: None,
+            detached: false,
         };
         tokio::spawn(abortable(abort))
     }
@@ -129,129 +135,291 @@ impl<'a, A> WithCleanup<'a, A> {
 /// A panic-proof handle for aborting a future. Will abort during stack
 /// unwinding and its drop method sends abort message with `who` inside it.
 pub struct Aborter {
-    sender: mpsc::UnboundedSender<AbortingTask>,
+    sender: mpsc::UnboundedSender<AbortReason>,
     who: AbortingTask,
+    /// The internal error of the task, if it failed
+    error: Option<String>,
+    /// Whether the task's termination is not to be reported
+    detached: bool,
+}
+
+impl Aborter {
+    /// Abort because the task failed with an internal error, which is
+    /// reported instead of the task's termination.
+    pub fn abort_with_error(mut self, error: impl Display) {
+        self.error = Some(error.to_string());
+    }
+
+    /// Detach the task, whose termination is then not reported, so that the
+    /// other tasks keep running. Used by the tasks that the ledger doesn't
+    /// depend on, once they're done.
+    pub fn detach(mut self) {
+        self.detached = true;
+    }
 }
 
 impl Drop for Aborter {
     fn drop(&mut self) {
+        if self.detached {
+            return;
+        }
+        let reason = match self.error.take() {
+            Some(error) => AbortReason::Internal(self.who, error),
+            None => AbortReason::ChildTerminated(Some(self.who)),
+        };
         // Send abort message, ignore result
-        let _ = self.sender.send(self.who);
+        let _ = self.sender.send(reason);
     }
 }
 
 #[cfg(unix)]
 async fn wait_for_abort(
-    mut abort_recv: UnboundedReceiver<AbortingTask>,
-) -> AborterStatus {
+    mut abort_recv: UnboundedReceiver<AbortReason>,
+) -> AbortReason {
     use tokio::signal::unix::{signal, SignalKind};
     let mut sigterm = signal(SignalKind::terminate()).unwrap();
     let mut sighup = signal(SignalKind::hangup()).unwrap();
     let mut sigpipe = signal(SignalKind::pipe()).unwrap();
     tokio::select! {
         signal = tokio::signal::ctrl_c() => {
-            match signal {
-                Ok(()) => tracing::info!("Received interrupt signal, exiting..."),
-                Err(err) => tracing::error!("Failed to listen for CTRL+C signal: {}", err),
+            if let Err(err) = signal {
+                tracing::error!("Failed to listen for CTRL+C signal: {}", err);
             }
+            AbortReason::Signal(Signal::Interrupt)
         },
         signal = sigterm.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received termination signal, exiting..."),
-                None => tracing::error!("Termination signal cannot be caught anymore, exiting..."),
+            if signal.is_none() {
+                tracing::error!("Termination signal cannot be caught anymore");
             }
+            AbortReason::Signal(Signal::Terminate)
         },
         signal = sighup.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received hangup signal, exiting..."),
-                None => tracing::error!("Hangup signal cannot be caught anymore, exiting..."),
+            if signal.is_none() {
+                tracing::error!("Hangup signal cannot be caught anymore");
             }
+            AbortReason::Signal(Signal::Hangup)
         },
         signal = sigpipe.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received pipe signal, exiting..."),
-                None => tracing::error!("Pipe signal cannot be caught anymore, exiting..."),
+            if signal.is_none() {
+                tracing::error!("Pipe signal cannot be caught anymore");
             }
+            AbortReason::Signal(Signal::Pipe)
         },
         msg = abort_recv.recv() => {
             // When the msg is `None`, there are no more abort senders, so both
             // Tendermint and the shell must have already exited
-            if let Some(who) = msg {
-                 tracing::info!("{} has exited, shutting down...", who);
-            }
-            return AborterStatus::ChildProcessTerminated;
+            msg.unwrap_or(AbortReason::ChildTerminated(None))
         }
-    };
-    AborterStatus::UserShutdownLedger
+    }
 }
 
 #[cfg(windows)]
 async fn wait_for_abort(
-    mut abort_recv: UnboundedReceiver<AbortingTask>,
-) -> AborterStatus {
+    mut abort_recv: UnboundedReceiver<AbortReason>,
+) -> AbortReason {
     let mut sigbreak = tokio::signal::windows::ctrl_break().unwrap();
-    let _ = tokio::select! {
+    tokio::select! {
         signal = tokio::signal::ctrl_c() => {
-            match signal {
-                Ok(()) => tracing::info!("Received interrupt signal, exiting..."),
-                Err(err) => tracing::error!("Failed to listen for CTRL+C signal: {}", err),
+            if let Err(err) = signal {
+                tracing::error!("Failed to listen for CTRL+C signal: {}", err);
             }
+            AbortReason::Signal(Signal::Interrupt)
         },
         signal = sigbreak.recv() => {
-            match signal {
-                Some(()) => tracing::info!("Received break signal, exiting..."),
-                None => tracing::error!("Break signal cannot be caught anymore, exiting..."),
+            if signal.is_none() {
+                tracing::error!("Break signal cannot be caught anymore");
             }
+            AbortReason::Signal(Signal::Break)
         },
         msg = abort_recv.recv() => {
             // When the msg is `None`, there are no more abort senders, so both
             // Tendermint and the shell must have already exited
-            if let Some(who) = msg {
-                 tracing::info!("{} has exited, shutting down...", who);
-            }
-            return AborterStatus::ChildProcessTerminated;
+            msg.unwrap_or(AbortReason::ChildTerminated(None))
         }
-    };
-    AborterStatus::UserShutdownLedger
+    }
 }
 
 #[cfg(not(any(unix, windows)))]
 async fn wait_for_abort(
-    mut abort_recv: UnboundedReceiver<AbortingTask>,
-) -> AborterStatus {
-    let _ = tokio::select! {
+    mut abort_recv: UnboundedReceiver<AbortReason>,
+) -> AbortReason {
+    tokio::select! {
         signal = tokio::signal::ctrl_c() => {
-            match signal {
-                Ok(()) => tracing::info!("Received interrupt signal, exiting..."),
-                Err(err) => tracing::error!("Failed to listen for CTRL+C signal: {}", err),
+            if let Err(err) = signal {
+                tracing::error!("Failed to listen for CTRL+C signal: {}", err);
             }
+            AbortReason::Signal(Signal::Interrupt)
         },
         msg = abort_recv.recv() => {
             // When the msg is `None`, there are no more abort senders, so both
             // Tendermint and the shell must have already exited
-            if let Some(who) = msg {
-                 tracing::info!("{} has exited, shutting down...", who);
-            }
-            return AborterStatus::ChildProcessTerminated;
+            msg.unwrap_or(AbortReason::ChildTerminated(None))
         }
-    };
-    AborterStatus::UserShutdownLedger
+    }
 }
 
-/// An [`AborterStatus`] represents one of two possible causes that resulted
-/// in shutting down the ledger.
+/// A shutdown signal received by the ledger process
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum AborterStatus {
+pub enum Signal {
+    /// SIGINT, or CTRL+C
+    Interrupt,
+    /// SIGTERM
+    Terminate,
+    /// SIGHUP
+    Hangup,
+    /// SIGPIPE
+    Pipe,
+    /// CTRL+BREAK on Windows
+    Break,
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Signal::Interrupt => "interrupt",
+            Signal::Terminate => "termination",
+            Signal::Hangup => "hangup",
+            Signal::Pipe => "pipe",
+            Signal::Break => "break",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An [`AbortReason`] represents the cause that resulted in shutting down
+/// the ledger.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AbortReason {
     /// The ledger process received a shutdown signal.
-    UserShutdownLedger,
+    Signal(Signal),
     /// One of the ledger's child processes terminated, signaling the
-    /// [`AbortableSpawner`].
-    ChildProcessTerminated,
+    /// [`AbortableSpawner`]. The task is unknown when all the child processes
+    /// have already terminated.
+    ChildTerminated(Option<AbortingTask>),
+    /// One of the ledger's child processes failed with an internal error.
+    Internal(AbortingTask, String),
 }
 
-impl AborterStatus {
-    /// Checks if the reason for aborting was a child process terminating.
-    pub fn child_terminated(self) -> bool {
-        matches!(self, AborterStatus::ChildProcessTerminated)
+impl AbortReason {
+    /// Checks if the reason for aborting was a child process terminating,
+    /// including when it failed.
+    pub fn child_terminated(&self) -> bool {
+        !matches!(self, AbortReason::Signal(_))
+    }
+
+    /// Log the reason for aborting
+    pub fn log(&self) {
+        match self {
+            AbortReason::Signal(signal) => {
+                tracing::info!("Received {} signal, exiting...", signal)
+            }
+            AbortReason::ChildTerminated(Some(who)) => {
+                tracing::info!("{} has exited, shutting down...", who)
+            }
+            AbortReason::ChildTerminated(None) => {
+                tracing::info!("All the tasks have exited, shutting down...")
+            }
+            AbortReason::Internal(who, error) => {
+                tracing::error!("{} failed, shutting down: {}", who, error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the termination of a task is reported as the abort reason.
+    #[tokio::test]
+    async fn test_abort_child_terminated() {
+        let mut spawner = AbortableSpawner::new();
+        spawner
+            .spawn_abortable("Task", |aborter| async move {
+                drop(aborter);
+            })
+            .with_no_cleanup();
+        assert_eq!(
+            spawner.wait_for_abort().await,
+            AbortReason::ChildTerminated(Some("Task"))
+        );
+    }
+
+    /// Test that the internal error of a task is reported as the abort
+    /// reason, after the cleanup jobs.
+    #[tokio::test]
+    async fn test_abort_internal_error() {
+        let mut spawner = AbortableSpawner::new();
+        let (cleanup_send, mut cleanup_recv) = mpsc::unbounded_channel();
+        spawner
+            .spawn_abortable("Task", |aborter| async move {
+                aborter.abort_with_error("failure");
+            })
+            .with_cleanup(async move {
+                cleanup_send.send(()).unwrap();
+            });
+        assert_eq!(
+            spawner.wait_for_abort().await,
+            AbortReason::Internal("Task", "failure".into())
+        );
+        assert!(cleanup_recv.try_recv().is_ok());
+    }
+
+    /// Test that the first of several racing aborts is reported.
+    #[tokio::test]
+    async fn test_abort_first_reported() {
+        let spawner = AbortableSpawner::new();
+        let first = Aborter {
+            who: "First",
+            sender: spawner.abort_send.clone(),
+            error: None,
+            detached: false,
+        };
+        let second = Aborter {
+            who: "Second",
+            sender: spawner.abort_send.clone(),
+            error: None,
+            detached: false,
+        };
+        second.abort_with_error("failure");
+        drop(first);
+        assert_eq!(
+            spawner.wait_for_abort().await,
+            AbortReason::Internal("Second", "failure".into())
+        );
+    }
+
+    /// Test that the termination of a detached task is not reported.
+    #[tokio::test]
+    async fn test_abort_detached() {
+        let mut spawner = AbortableSpawner::new();
+        spawner
+            .spawn_abortable("Detached", |aborter| async move {
+                aborter.detach();
+            })
+            .with_no_cleanup()
+            .await
+            .unwrap();
+        spawner
+            .spawn_abortable("Task", |aborter| async move {
+                drop(aborter);
+            })
+            .with_no_cleanup();
+        assert_eq!(
+            spawner.wait_for_abort().await,
+            AbortReason::ChildTerminated(Some("Task"))
+        );
+    }
+
+    /// Test that a shutdown signal is reported as the abort reason.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_abort_signal() {
+        let spawner = AbortableSpawner::new();
+        let abort = tokio::spawn(spawner.wait_for_abort());
+        // Let the signal handlers be registered before raising the signal
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        assert_eq!(abort.await.unwrap(), AbortReason::Signal(Signal::Hangup));
     }
 }
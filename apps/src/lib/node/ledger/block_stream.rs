@@ -0,0 +1,234 @@
+//! A stream of the summaries of the committed blocks, e.g. for indexers to
+//! follow the chain without polling for new blocks.
+//!
+//! A subscriber connects over TCP and receives a JSON message on a new line
+//! for each block committed from then on. Committing a block never waits for
+//! the subscribers: when a subscriber falls behind by more than the capacity
+//! of the stream, the oldest summaries are dropped for it and it receives a
+//! gap message with the number of missed blocks instead.
+
+use std::collections::BTreeMap;
+
+use data_encoding::HEXUPPER;
+use namada::ledger::events::{Event, EventType};
+use namada::types::storage::{BlockHash, BlockHeight};
+use namada::types::time::DateTimeUtc;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot};
+
+/// The number of block summaries buffered for each subscriber, beyond which
+/// the oldest ones are dropped
+const STREAM_CAPACITY: usize = 100;
+
+/// The sending side of the stream, used by the shell to publish the
+/// summaries of the committed blocks
+pub type Sender = broadcast::Sender<BlockSummary>;
+
+/// Create a new stream of block summaries
+pub fn channel() -> Sender {
+    let (sender, _receiver) = broadcast::channel(STREAM_CAPACITY);
+    sender
+}
+
+/// A compact summary of a committed block
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    /// The height of the block
+    pub height: u64,
+    /// The hash of the block, hex-encoded
+    pub hash: String,
+    /// The time of the block, in RFC 3339 format
+    pub time: Option<String>,
+    /// The number of txs applied in the block
+    pub num_txs: u64,
+    /// The events emitted when the block was finalized
+    pub events: Vec<EventSummary>,
+}
+
+/// An event emitted when a block was finalized
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSummary {
+    /// The type of the event
+    pub event_type: String,
+    /// The attributes of the event
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl BlockSummary {
+    /// Summarize a committed block from its events
+    pub fn new(
+        height: BlockHeight,
+        hash: &BlockHash,
+        time: Option<DateTimeUtc>,
+        events: &[Event],
+    ) -> Self {
+        let num_txs = events
+            .iter()
+            .filter(|event| event.event_type == EventType::Applied)
+            .count() as u64;
+        Self {
+            height: height.0,
+            hash: HEXUPPER.encode(&hash.0),
+            time: time.map(|time| time.to_rfc3339()),
+            num_txs,
+            events: events
+                .iter()
+                .map(|event| EventSummary {
+                    event_type: event.event_type.to_string(),
+                    attributes: event.attributes.clone().into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A message sent to a subscriber
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamMessage {
+    /// The summary of a committed block
+    Block(BlockSummary),
+    /// The subscriber fell behind and missed the summaries of this many
+    /// blocks
+    Gap(u64),
+}
+
+/// Stream the block summaries to the subscribers connecting to the given
+/// listener, until a message is received from `abort_recv`.
+pub async fn serve(
+    listener: TcpListener,
+    sender: Sender,
+    abort_recv: oneshot::Receiver<()>,
+) -> std::io::Result<()> {
+    tokio::select! {
+        res = accept_subscribers(listener, sender) => res,
+        _ = abort_recv => {
+            tracing::info!("Shutting down the block stream server...");
+            Ok(())
+        }
+    }
+}
+
+/// Accept the subscriber connections, each of them is streamed to in its own
+/// task
+async fn accept_subscribers(
+    listener: TcpListener,
+    sender: Sender,
+) -> std::io::Result<()> {
+    loop {
+        let (stream, address) = listener.accept().await?;
+        let receiver = sender.subscribe();
+        tokio::spawn(async move {
+            if let Err(err) = stream_blocks(stream, receiver).await {
+                tracing::debug!(
+                    "Stopped streaming blocks to {}: {}",
+                    address,
+                    err
+                );
+            }
+        });
+    }
+}
+
+/// Write the block summaries received from the shell to a subscriber
+async fn stream_blocks(
+    mut stream: TcpStream,
+    mut receiver: broadcast::Receiver<BlockSummary>,
+) -> std::io::Result<()> {
+    loop {
+        let message = match receiver.recv().await {
+            Ok(summary) => StreamMessage::Block(summary),
+            Err(RecvError::Lagged(missed)) => StreamMessage::Gap(missed),
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        stream.write_all(&line).await?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use namada::ledger::events::EventLevel;
+    use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+
+    use super::*;
+
+    /// Read the next message from a subscriber's stream
+    async fn next_message(
+        lines: &mut Lines<BufReader<TcpStream>>,
+    ) -> StreamMessage {
+        let line = lines.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn summary(height: u64) -> BlockSummary {
+        BlockSummary::new(
+            BlockHeight(height),
+            &BlockHash([height as u8; 32]),
+            None,
+            &[],
+        )
+    }
+
+    /// Test that only the applied txs are counted, not the accepted wrapper
+    /// txs nor the other events of the txs.
+    #[test]
+    fn test_num_txs() {
+        let event = |event_type: EventType| Event {
+            event_type,
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        let events = [
+            event(EventType::Accepted),
+            event(EventType::Applied),
+            event(EventType::Vp("vp_event".to_owned())),
+            event(EventType::Ibc("send_packet".to_owned())),
+            event(EventType::Applied),
+        ];
+        let summary = BlockSummary::new(
+            BlockHeight(1),
+            &BlockHash([1; 32]),
+            None,
+            &events,
+        );
+        assert_eq!(summary.num_txs, 2);
+        assert_eq!(summary.events.len(), events.len());
+    }
+
+    /// Test that a subscriber that falls behind receives a gap message for
+    /// the dropped summaries and then the following ones.
+    #[tokio::test]
+    async fn test_slow_subscriber_gap() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let sender = channel();
+        let (_abort_send, abort_recv) = oneshot::channel();
+        tokio::spawn(serve(listener, sender.clone(), abort_recv));
+
+        let stream = TcpStream::connect(address).await.unwrap();
+        while sender.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        // Publishing never waits for the subscriber
+        let published = STREAM_CAPACITY as u64 + 2;
+        for height in 1..=published {
+            sender.send(summary(height)).unwrap();
+        }
+
+        let mut lines = BufReader::new(stream).lines();
+        assert_eq!(next_message(&mut lines).await, StreamMessage::Gap(2));
+        for height in 3..=published {
+            assert_eq!(
+                next_message(&mut lines).await,
+                StreamMessage::Block(summary(height))
+            );
+        }
+    }
+}
@@ -1,19 +1,32 @@
-use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::{self, Receiver, Sender};
 
 use crate::facade::tendermint_rpc::{Client, HttpClient};
 
+/// The default capacity of the queue of txs to be broadcast
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+/// Create a queue of txs to be broadcast with the given capacity. A capacity
+/// of 0 is treated as 1.
+///
+/// When the queue is full, the senders are blocked until there's room for the
+/// tx rather than dropping it, because the protocol txs are needed for the
+/// ledger's operation.
+pub fn channel(capacity: usize) -> (Sender<Vec<u8>>, Receiver<Vec<u8>>) {
+    mpsc::channel(capacity.max(1))
+}
+
 /// A service for broadcasting txs via an HTTP client.
 /// The receiver is for receiving message payloads for other services
 /// to be broadcast.
 pub struct Broadcaster {
     client: HttpClient,
-    receiver: UnboundedReceiver<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
 }
 
 impl Broadcaster {
     /// Create a new broadcaster that will send Http messages
     /// over the given url.
-    pub fn new(url: &str, receiver: UnboundedReceiver<Vec<u8>>) -> Self {
+    pub fn new(url: &str, receiver: Receiver<Vec<u8>>) -> Self {
         Self {
             client: HttpClient::new(format!("http://{}", url).as_str())
                 .unwrap(),
@@ -57,3 +70,34 @@ impl Broadcaster {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use tokio::sync::mpsc::error::TrySendError;
+
+    use super::*;
+
+    /// Test that the queue is bounded by the configured capacity and that
+    /// the room is made by the consumer.
+    #[test]
+    fn test_queue_capacity() {
+        let (sender, mut receiver) = channel(2);
+        sender.try_send(vec![0]).unwrap();
+        sender.try_send(vec![1]).unwrap();
+        assert!(matches!(
+            sender.try_send(vec![2]),
+            Err(TrySendError::Full(_))
+        ));
+
+        assert_eq!(receiver.blocking_recv(), Some(vec![0]));
+        sender.try_send(vec![2]).unwrap();
+
+        // A capacity of 0 is treated as 1
+        let (sender, _receiver) = channel(0);
+        sender.try_send(vec![0]).unwrap();
+        assert!(matches!(
+            sender.try_send(vec![1]),
+            Err(TrySendError::Full(_))
+        ));
+    }
+}
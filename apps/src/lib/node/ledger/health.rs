@@ -0,0 +1,228 @@
+//! HTTP endpoints for probing the liveness and the readiness of the ledger,
+//! e.g. by an orchestrator. These are distinct from the Prometheus metrics
+//! served by Tendermint.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// The path of the liveness probe
+pub const LIVENESS_PATH: &str = "/healthz";
+
+/// The path of the readiness probe
+pub const READINESS_PATH: &str = "/readyz";
+
+/// How long to wait for a service to accept a connection when checking the
+/// readiness
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The maximum number of bytes read from a probe request
+const MAX_REQUEST_BYTES: usize = 1024;
+
+/// The services that must be reachable for the ledger to be ready
+#[derive(Clone, Debug)]
+pub struct Readiness {
+    /// The address of the ABCI server
+    pub abci_address: SocketAddr,
+    /// The address of Tendermint's RPC server
+    pub tendermint_rpc_address: SocketAddr,
+}
+
+impl Readiness {
+    /// Get the names of the services that are not reachable. The ledger is
+    /// ready when there are none.
+    pub async fn unavailable(&self) -> Vec<&'static str> {
+        let (abci, tendermint) = tokio::join!(
+            is_reachable(self.abci_address),
+            is_reachable(self.tendermint_rpc_address),
+        );
+        let mut unavailable = vec![];
+        if !abci {
+            unavailable.push("ABCI server");
+        }
+        if !tendermint {
+            unavailable.push("Tendermint");
+        }
+        unavailable
+    }
+}
+
+/// Check that a service accepts connections at the given address
+async fn is_reachable(address: SocketAddr) -> bool {
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(address)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Answer the probes received by the given listener, until a message is
+/// received from `abort_recv`.
+pub async fn serve(
+    listener: TcpListener,
+    readiness: Readiness,
+    abort_recv: oneshot::Receiver<()>,
+) -> std::io::Result<()> {
+    tokio::select! {
+        res = accept_probes(listener, readiness) => res,
+        _ = abort_recv => {
+            tracing::info!("Shutting down the health check server...");
+            Ok(())
+        }
+    }
+}
+
+/// Accept the probe connections, each of them is answered in its own task
+async fn accept_probes(
+    listener: TcpListener,
+    readiness: Readiness,
+) -> std::io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let readiness = readiness.clone();
+        tokio::spawn(async move {
+            if let Err(err) = answer_probe(stream, &readiness).await {
+                tracing::debug!("Failed to answer a health check: {}", err);
+            }
+        });
+    }
+}
+
+/// Read a probe request from the stream and write back the response
+async fn answer_probe(
+    mut stream: TcpStream,
+    readiness: &Readiness,
+) -> std::io::Result<()> {
+    let mut request = [0; MAX_REQUEST_BYTES];
+    let len = stream.read(&mut request).await?;
+    let response = probe_response(&request[..len], readiness).await;
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Build the HTTP response to a probe request. The liveness probe is
+/// answered as soon as the request is handled, which shows that the event
+/// loop is responsive.
+async fn probe_response(request: &[u8], readiness: &Readiness) -> String {
+    let request = String::from_utf8_lossy(request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some(LIVENESS_PATH)) => ("200 OK", "OK".to_owned()),
+        (Some("GET"), Some(READINESS_PATH)) => {
+            let unavailable = readiness.unavailable().await;
+            if unavailable.is_empty() {
+                ("200 OK", "OK".to_owned())
+            } else {
+                (
+                    "503 Service Unavailable",
+                    format!("Unavailable: {}", unavailable.join(", ")),
+                )
+            }
+        }
+        (Some("GET"), _) => ("404 Not Found", "Not found".to_owned()),
+        _ => ("405 Method Not Allowed", "Method not allowed".to_owned()),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: \
+         {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Send a GET request for the given path and return the response
+    async fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(
+                format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path)
+                    .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    /// Get an address on which nothing is listening
+    async fn closed_address() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    /// Start serving the probes and return the server's address along with
+    /// the sender to stop it
+    async fn start(readiness: Readiness) -> (SocketAddr, oneshot::Sender<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (abort_send, abort_recv) = oneshot::channel();
+        tokio::spawn(serve(listener, readiness, abort_recv));
+        (address, abort_send)
+    }
+
+    /// Test that both probes succeed when all the services are reachable.
+    #[tokio::test]
+    async fn test_probes_when_healthy() {
+        let abci = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let tendermint = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (address, _abort_send) = start(Readiness {
+            abci_address: abci.local_addr().unwrap(),
+            tendermint_rpc_address: tendermint.local_addr().unwrap(),
+        })
+        .await;
+
+        let response = get(address, LIVENESS_PATH).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        let response = get(address, READINESS_PATH).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        let response = get(address, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    }
+
+    /// Test that the readiness probe fails, while the liveness probe still
+    /// succeeds, when a service is not reachable.
+    #[tokio::test]
+    async fn test_probes_when_degraded() {
+        let abci = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (address, _abort_send) = start(Readiness {
+            abci_address: abci.local_addr().unwrap(),
+            tendermint_rpc_address: closed_address().await,
+        })
+        .await;
+
+        let response = get(address, LIVENESS_PATH).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        let response = get(address, READINESS_PATH).await;
+        assert!(
+            response.starts_with("HTTP/1.1 503 Service Unavailable"),
+            "{}",
+            response
+        );
+        assert!(
+            response.ends_with("Unavailable: Tendermint"),
+            "{}",
+            response
+        );
+
+        // Once the ABCI server is down too, both services are reported
+        drop(abci);
+        let response = get(address, READINESS_PATH).await;
+        assert!(
+            response.ends_with("Unavailable: ABCI server, Tendermint"),
+            "{}",
+            response
+        );
+    }
+}
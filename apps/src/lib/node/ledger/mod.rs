@@ -1,24 +1,29 @@
 mod abortable;
+pub mod block_stream;
 mod broadcaster;
+pub mod health;
+mod request_log;
 mod shell;
 mod shims;
 pub mod storage;
 pub mod tendermint_node;
 
-use std::convert::TryInto;
 use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::str::FromStr;
 use std::thread;
 
 use byte_unit::Byte;
 use futures::future::TryFutureExt;
 use namada::ledger::governance::storage as gov_storage;
-use namada::types::storage::Key;
+use namada::types::storage::{DbKeySeg, Key};
+use namada::types::time::DateTimeUtc;
 use once_cell::unsync::Lazy;
 use sysinfo::{RefreshKind, System, SystemExt};
 use tokio::task;
-use tower::ServiceBuilder;
+use tower::buffer::Buffer;
+use tower::limit::RateLimit;
+use tower::load_shed::LoadShed;
+use tower::{BoxError, Service, ServiceBuilder};
 
 use self::abortable::AbortableSpawner;
 use self::shims::abcipp_shim::AbciService;
@@ -26,12 +31,13 @@ use crate::config::utils::num_of_threads;
 use crate::config::TendermintMode;
 use crate::facade::tendermint_proto::abci::CheckTxType;
 use crate::facade::tower_abci::{response, split, Server};
-use crate::node::ledger::broadcaster::Broadcaster;
+use crate::node::ledger::broadcaster::{self, Broadcaster};
 use crate::node::ledger::config::genesis;
 use crate::node::ledger::shell::{Error, MempoolTxType, Shell};
 use crate::node::ledger::shims::abcipp_shim::AbcippShim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::{Request, Response};
-use crate::{config, wasm_loader};
+use crate::wasm_loader::WasmDirs;
+use crate::{cli, config, wasm_loader};
 
 /// Env. var to set a number of Tokio RT worker threads
 const ENV_VAR_TOKIO_THREADS: &str = "NAMADA_TOKIO_THREADS";
@@ -39,6 +45,33 @@ const ENV_VAR_TOKIO_THREADS: &str = "NAMADA_TOKIO_THREADS";
 /// Env. var to set a number of Rayon global worker threads
 const ENV_VAR_RAYON_THREADS: &str = "NAMADA_RAYON_THREADS";
 
+/// The default maximum number of queued consensus requests
+const DEFAULT_CONSENSUS_BUFFER_SIZE: usize = 100;
+
+/// The maximum number of queued info requests, beyond which they are shed
+const INFO_BUFFER_SIZE: usize = 100;
+
+/// The name of the shell's thread
+const SHELL_THREAD_NAME: &str = "ledger-shell";
+
+/// The default interval at which the time left until the genesis time is
+/// logged
+const DEFAULT_GENESIS_WAIT_LOG_INTERVAL: std::time::Duration =
+    std::time::Duration::from_secs(60);
+
+/// The least available memory that's considered a plausible reading, below
+/// which the caches that are not configured fall back to their default sizes
+/// rather than to a share of it
+const MIN_AVAILABLE_MEMORY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// The default size of each of the WASM compilation caches, when the available
+/// memory is unknown
+const DEFAULT_WASM_COMPILATION_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The default size of the RocksDB block cache, when the available memory is
+/// unknown
+const DEFAULT_BLOCK_CACHE_BYTES: u64 = 128 * 1024 * 1024;
+
 // Until ABCI++ is ready, the shim provides the service implementation.
 // We will add this part back in once the shim is no longer needed.
 //```
@@ -60,24 +93,22 @@ impl Shell {
         let proposals_key = gov_storage::get_commiting_proposals_prefix(
             self.storage.last_epoch.0,
         );
+        // NOTE: `iter_prefix` iterate over the matching prefix. In this case
+        // a proposal with grace_epoch 110 will be matched by prefixes 1, 11
+        // and 110. The pattern only matches the keys of the desired epoch.
+        let proposals_pattern = gov_storage::get_commiting_proposals_pattern(
+            self.storage.last_epoch.0,
+        );
 
         let (proposal_iter, _) = self.storage.iter_prefix(&proposals_key);
         for (key, _, _) in proposal_iter {
             let key =
                 Key::from_str(key.as_str()).expect("Key should be parsable");
-            if gov_storage::get_commit_proposal_epoch(&key).unwrap()
-                != self.storage.last_epoch.0
-            {
-                // NOTE: `iter_prefix` iterate over the matching prefix. In this
-                // case  a proposal with grace_epoch 110 will be
-                // matched by prefixes  1, 11 and 110. Thus we
-                // have to skip to the next iteration of
-                //  the cycle for all the prefixes that don't actually match
-                //  the desired epoch.
-                continue;
-            }
-
-            let proposal_id = gov_storage::get_commit_proposal_id(&key);
+            let proposal_id =
+                match key.matches_pattern(&proposals_pattern).as_deref() {
+                    Some([DbKeySeg::StringSeg(id)]) => id.parse::<u64>().ok(),
+                    _ => continue,
+                };
             if let Some(id) = proposal_id {
                 self.proposal_data.insert(id);
             }
@@ -85,22 +116,42 @@ impl Shell {
     }
 
     fn call(&mut self, req: Request) -> Result<Response, Error> {
+        let request_type = request_log::request_type(&req);
+        let height =
+            request_log::request_height(&req, self.storage.last_height);
+        request_log::in_request_span(request_type, height, || {
+            self.handle_request(request_type, req)
+        })
+    }
+
+    fn handle_request(
+        &mut self,
+        request_type: &'static str,
+        req: Request,
+    ) -> Result<Response, Error> {
+        if self.request_log_sampler.should_log(request_type) {
+            tracing::debug!("Request {}", request_type);
+        }
+        if let Some(response) = self.reject_before_init_chain(&req) {
+            tracing::warn!(
+                "Rejected a {} request received before InitChain",
+                request_type
+            );
+            return response;
+        }
         match req {
             Request::InitChain(init) => {
-                tracing::debug!("Request InitChain");
                 self.init_chain(init).map(Response::InitChain)
             }
             Request::Info(_) => Ok(Response::Info(self.last_state())),
             Request::Query(query) => Ok(Response::Query(self.query(query))),
             Request::PrepareProposal(block) => {
-                tracing::debug!("Request PrepareProposal");
                 Ok(Response::PrepareProposal(self.prepare_proposal(block)))
             }
             Request::VerifyHeader(_req) => {
                 Ok(Response::VerifyHeader(self.verify_header(_req)))
             }
             Request::ProcessProposal(block) => {
-                tracing::debug!("Request ProcessProposal");
                 Ok(Response::ProcessProposal(self.process_proposal(block)))
             }
             Request::RevertProposal(_req) => {
@@ -111,21 +162,14 @@ impl Shell {
                 Ok(Response::ExtendVote(self.extend_vote(_req)))
             }
             #[cfg(feature = "abcipp")]
-            Request::VerifyVoteExtension(_req) => {
-                tracing::debug!("Request VerifyVoteExtension");
-                Ok(Response::VerifyVoteExtension(
-                    self.verify_vote_extension(_req),
-                ))
-            }
+            Request::VerifyVoteExtension(_req) => Ok(
+                Response::VerifyVoteExtension(self.verify_vote_extension(_req)),
+            ),
             Request::FinalizeBlock(finalize) => {
-                tracing::debug!("Request FinalizeBlock");
                 self.load_proposals();
                 self.finalize_block(finalize).map(Response::FinalizeBlock)
             }
-            Request::Commit(_) => {
-                tracing::debug!("Request Commit");
-                Ok(Response::Commit(self.commit()))
-            }
+            Request::Commit(_) => Ok(Response::Commit(self.commit())),
             Request::Flush(_) => Ok(Response::Flush(Default::default())),
             Request::Echo(msg) => Ok(Response::Echo(response::Echo {
                 message: msg.message,
@@ -137,7 +181,9 @@ impl Shell {
                     CheckTxType::New => MempoolTxType::NewTransaction,
                     CheckTxType::Recheck => MempoolTxType::RecheckTransaction,
                 };
-                Ok(Response::CheckTx(self.mempool_validate(&tx.tx, r#type)))
+                let response = self.mempool_validate(&tx.tx, r#type.clone());
+                self.track_mempool_tx(&tx.tx, &r#type, &response);
+                Ok(Response::CheckTx(response))
             }
             Request::ListSnapshots(_) => {
                 Ok(Response::ListSnapshots(Default::default()))
@@ -156,7 +202,7 @@ impl Shell {
 }
 
 /// Run the ledger with an async runtime
-pub fn run(config: config::Ledger, wasm_dir: PathBuf) {
+pub fn run(config: config::Ledger, wasm_dirs: WasmDirs) {
     let logical_cores = num_cpus::get();
     tracing::info!("Available logical cores: {}", logical_cores);
 
@@ -174,8 +220,9 @@ pub fn run(config: config::Ledger, wasm_dir: PathBuf) {
     );
     tracing::info!("Using {} threads for Tokio.", tokio_threads);
 
-    // Configure number of threads for rayon (used in `par_iter` when running
-    // VPs)
+    // Configure number of threads for rayon (used in `par_iter`). The VPs run
+    // on their own pool, which is as large as this one, unless the
+    // `vp_threads` config is set.
     rayon::ThreadPoolBuilder::new()
         .num_threads(rayon_threads)
         .thread_name(|i| format!("ledger-rayon-worker-{}", i))
@@ -190,7 +237,7 @@ pub fn run(config: config::Ledger, wasm_dir: PathBuf) {
         .enable_all()
         .build()
         .unwrap()
-        .block_on(run_aux(config, wasm_dir));
+        .block_on(run_aux(config, wasm_dirs));
 }
 
 /// Resets the tendermint_node state and removes database files
@@ -207,8 +254,13 @@ pub fn reset(config: config::Ledger) -> Result<(), shell::Error> {
 ///   - A [`Broadcaster`], for the ledger to submit txs to Tendermint's mempool.
 ///
 /// All must be alive for correct functioning.
-async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
-    let setup_data = run_aux_setup(&config, &wasm_dir).await;
+///
+/// A panic in the shell is propagated once the other tasks have shut down,
+/// unless the config `abort_on_panic` is set.
+async fn run_aux(config: config::Ledger, wasm_dirs: WasmDirs) {
+    set_shell_panic_hook(&config.shell);
+
+    let setup_data = run_aux_setup(&config, &wasm_dirs).await;
 
     // Create an `AbortableSpawner` for signalling shut down from the shell or
     // from Tendermint
@@ -217,23 +269,44 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
     // Start Tendermint node
     let tendermint_node = start_tendermint(&mut spawner, &config);
 
+    // Log the time left until the genesis time, if it's not reached yet
+    let genesis_wait = start_genesis_wait(&mut spawner, &config);
+
+    // Start serving the health checks, if configured
+    let health_check = start_health_check(&mut spawner, &config);
+
+    // Start streaming the summaries of the committed blocks, if configured
+    let block_stream = block_stream::channel();
+    let block_stream_server =
+        start_block_stream(&mut spawner, &config, block_stream.clone());
+
     // Start ABCI server and broadcaster (the latter only if we are a validator
     // node)
     let (abci, broadcaster, shell_handler) = start_abci_broadcaster_shell(
         &mut spawner,
-        wasm_dir,
+        wasm_dirs,
         setup_data,
         config,
+        block_stream,
     );
 
     // Wait for interrupt signal or abort message
-    let aborted = spawner.wait_for_abort().await.child_terminated();
+    let reason = spawner.wait_for_abort().await;
+    reason.log();
+    let aborted = reason.child_terminated();
 
     // Wait for all managed tasks to finish.
-    let res = tokio::try_join!(tendermint_node, abci, broadcaster);
+    let res = tokio::try_join!(
+        tendermint_node,
+        abci,
+        broadcaster,
+        health_check,
+        block_stream_server,
+        genesis_wait
+    );
 
     match res {
-        Ok((tendermint_res, abci_res, _)) => {
+        Ok((tendermint_res, abci_res, _, _, _, _)) => {
             // we ignore errors on user-initiated shutdown
             if aborted {
                 if let Err(err) = tendermint_res {
@@ -261,8 +334,36 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
     }
 }
 
+/// When the config `abort_on_panic` is set, install a panic hook that aborts
+/// the process on a panic in the shell. Returns whether the hook was
+/// installed.
+fn set_shell_panic_hook(config: &config::Shell) -> bool {
+    if !config.abort_on_panic {
+        return false;
+    }
+    install_shell_panic_hook(|| std::process::abort());
+    true
+}
+
+/// Install a panic hook that calls `abort` after the previous hook, when the
+/// panic happens in the shell's thread.
+fn install_shell_panic_hook<F>(abort: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    let prev_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        prev_hook(info);
+        if thread::current().name() == Some(SHELL_THREAD_NAME) {
+            tracing::error!("The shell panicked, aborting: {}", info);
+            abort();
+        }
+    }));
+}
+
 /// A [`RunAuxSetup`] stores some variables used to start child
 /// processes of the ledger.
+#[derive(Debug, PartialEq, Eq)]
 struct RunAuxSetup {
     vp_wasm_compilation_cache: u64,
     tx_wasm_compilation_cache: u64,
@@ -272,40 +373,67 @@ struct RunAuxSetup {
 /// Return some variables used to start child processes of the ledger.
 async fn run_aux_setup(
     config: &config::Ledger,
-    wasm_dir: &PathBuf,
+    wasm_dirs: &WasmDirs,
 ) -> RunAuxSetup {
     // Prefetch needed wasm artifacts
-    wasm_loader::pre_fetch_wasm(wasm_dir).await;
+    wasm_loader::pre_fetch_wasm(
+        wasm_dirs,
+        wasm_loader::PreFetchOptions::from(&config.shell),
+    )
+    .await;
 
-    // Find the system available memory
-    let available_memory_bytes = Lazy::new(|| {
+    cache_sizes(&config.shell, || {
         let sys = System::new_with_specifics(RefreshKind::new().with_memory());
-        let available_memory_bytes = sys.available_memory() * 1024;
+        sys.available_memory() * 1024
+    })
+}
+
+/// Find the sizes of the caches. The caches that are not configured are
+/// sized as a share of the available memory, which is only read when needed.
+/// Some platforms report no or implausibly low available memory, in which
+/// case these caches fall back to their default sizes rather than being
+/// sized down to nothing.
+fn cache_sizes(
+    config: &config::Shell,
+    read_available_memory_bytes: impl FnOnce() -> u64,
+) -> RunAuxSetup {
+    // Find the system available memory, if the reading is plausible
+    let available_memory_bytes = Lazy::new(|| {
+        let available_memory_bytes = read_available_memory_bytes();
         tracing::info!(
             "Available memory: {}",
             Byte::from_bytes(available_memory_bytes as u128)
                 .get_appropriate_unit(true)
         );
-        available_memory_bytes
+        if available_memory_bytes < MIN_AVAILABLE_MEMORY_BYTES {
+            tracing::warn!(
+                "The available memory reading is implausibly low, using the \
+                 default sizes of the caches that are not configured."
+            );
+            None
+        } else {
+            Some(available_memory_bytes)
+        }
     });
 
     // Find the VP WASM compilation cache size
-    let vp_wasm_compilation_cache =
-        match config.shell.vp_wasm_compilation_cache_bytes {
-            Some(vp_wasm_compilation_cache) => {
-                tracing::info!(
-                    "VP WASM compilation cache size set from the configuration"
-                );
-                vp_wasm_compilation_cache
-            }
-            None => {
-                tracing::info!(
-                    "VP WASM compilation cache size not configured, using 1/6 \
-                     of available memory."
-                );
-                *available_memory_bytes / 6
-            }
-        };
+    let vp_wasm_compilation_cache = match config.vp_wasm_compilation_cache_bytes
+    {
+        Some(vp_wasm_compilation_cache) => {
+            tracing::info!(
+                "VP WASM compilation cache size set from the configuration"
+            );
+            vp_wasm_compilation_cache
+        }
+        None => {
+            tracing::info!(
+                "VP WASM compilation cache size not configured, using 1/6 of \
+                 available memory."
+            );
+            available_memory_bytes
+                .map_or(DEFAULT_WASM_COMPILATION_CACHE_BYTES, |bytes| bytes / 6)
+        }
+    };
     tracing::info!(
         "VP WASM compilation cache size: {}",
         Byte::from_bytes(vp_wasm_compilation_cache as u128)
@@ -313,22 +441,23 @@ async fn run_aux_setup(
     );
 
     // Find the tx WASM compilation cache size
-    let tx_wasm_compilation_cache =
-        match config.shell.tx_wasm_compilation_cache_bytes {
-            Some(tx_wasm_compilation_cache) => {
-                tracing::info!(
-                    "Tx WASM compilation cache size set from the configuration"
-                );
-                tx_wasm_compilation_cache
-            }
-            None => {
-                tracing::info!(
-                    "Tx WASM compilation cache size not configured, using 1/6 \
-                     of available memory."
-                );
-                *available_memory_bytes / 6
-            }
-        };
+    let tx_wasm_compilation_cache = match config.tx_wasm_compilation_cache_bytes
+    {
+        Some(tx_wasm_compilation_cache) => {
+            tracing::info!(
+                "Tx WASM compilation cache size set from the configuration"
+            );
+            tx_wasm_compilation_cache
+        }
+        None => {
+            tracing::info!(
+                "Tx WASM compilation cache size not configured, using 1/6 of \
+                 available memory."
+            );
+            available_memory_bytes
+                .map_or(DEFAULT_WASM_COMPILATION_CACHE_BYTES, |bytes| bytes / 6)
+        }
+    };
     tracing::info!(
         "Tx WASM compilation cache size: {}",
         Byte::from_bytes(tx_wasm_compilation_cache as u128)
@@ -336,7 +465,7 @@ async fn run_aux_setup(
     );
 
     // Find the RocksDB block cache size
-    let db_block_cache_size_bytes = match config.shell.block_cache_bytes {
+    let db_block_cache_size_bytes = match config.block_cache_bytes {
         Some(block_cache_bytes) => {
             tracing::info!("Block cache set from the configuration.");
             block_cache_bytes
@@ -346,7 +475,8 @@ async fn run_aux_setup(
                 "Block cache size not configured, using 1/3 of available \
                  memory."
             );
-            *available_memory_bytes / 3
+            available_memory_bytes
+                .map_or(DEFAULT_BLOCK_CACHE_BYTES, |bytes| bytes / 3)
         }
     };
     tracing::info!(
@@ -370,9 +500,10 @@ async fn run_aux_setup(
 /// Lastly, this function executes an ABCI shell on a new OS thread.
 fn start_abci_broadcaster_shell(
     spawner: &mut AbortableSpawner,
-    wasm_dir: PathBuf,
+    wasm_dirs: WasmDirs,
     setup_data: RunAuxSetup,
     config: config::Ledger,
+    block_stream: block_stream::Sender,
 ) -> (
     task::JoinHandle<shell::Result<()>>,
     task::JoinHandle<()>,
@@ -387,8 +518,12 @@ fn start_abci_broadcaster_shell(
 
     // Channels for validators to send protocol txs to be broadcast to the
     // broadcaster service
-    let (broadcaster_sender, broadcaster_receiver) =
-        tokio::sync::mpsc::unbounded_channel();
+    let (broadcaster_sender, broadcaster_receiver) = broadcaster::channel(
+        config
+            .shell
+            .broadcaster_queue_capacity
+            .unwrap_or(broadcaster::DEFAULT_QUEUE_CAPACITY),
+    );
 
     // Start broadcaster
     let broadcaster = if matches!(
@@ -424,14 +559,25 @@ fn start_abci_broadcaster_shell(
     // Construct our ABCI application.
     let tendermint_mode = config.tendermint.tendermint_mode.clone();
     let ledger_address = config.shell.ledger_address;
+    let consensus_buffer_size = config
+        .shell
+        .consensus_buffer_size
+        .unwrap_or(DEFAULT_CONSENSUS_BUFFER_SIZE);
     #[cfg(not(feature = "dev"))]
     let genesis = genesis::genesis(&config.shell.base_dir, &config.chain_id);
     #[cfg(feature = "dev")]
     let genesis = genesis::genesis();
+    // Fail fast on a malformed native token, on which the fees and the
+    // staking rely
+    if let Err(err) = genesis.validate_native_token() {
+        eprintln!("Invalid genesis: {}", err);
+        cli::safe_exit(1)
+    }
     let (shell, abci_service) = AbcippShim::new(
         config,
-        wasm_dir,
+        wasm_dirs,
         broadcaster_sender,
+        block_stream,
         &db_cache,
         vp_wasm_compilation_cache,
         tx_wasm_compilation_cache,
@@ -444,8 +590,13 @@ fn start_abci_broadcaster_shell(
     // Start the ABCI server
     let abci = spawner
         .spawn_abortable("ABCI", move |aborter| async move {
-            let res =
-                run_abci(abci_service, ledger_address, abci_abort_recv).await;
+            let res = run_abci(
+                abci_service,
+                ledger_address,
+                consensus_buffer_size,
+                abci_abort_recv,
+            )
+            .await;
 
             drop(aborter);
             res
@@ -455,7 +606,7 @@ fn start_abci_broadcaster_shell(
         });
 
     // Start the shell in a new OS thread
-    let thread_builder = thread::Builder::new().name("ledger-shell".into());
+    let thread_builder = thread::Builder::new().name(SHELL_THREAD_NAME.into());
     let shell_handler = thread_builder
         .spawn(move || {
             tracing::info!("Namada ledger node started.");
@@ -479,6 +630,7 @@ fn start_abci_broadcaster_shell(
 async fn run_abci(
     abci_service: AbciService,
     ledger_address: SocketAddr,
+    consensus_buffer_size: usize,
     abort_recv: tokio::sync::oneshot::Receiver<()>,
 ) -> shell::Result<()> {
     // Split it into components.
@@ -487,7 +639,7 @@ async fn run_abci(
     // Hand those components to the ABCI server, but customize request behavior
     // for each category
     let server = Server::builder()
-        .consensus(consensus)
+        .consensus(consensus_service(consensus, consensus_buffer_size))
         .snapshot(snapshot)
         .mempool(
             ServiceBuilder::new()
@@ -495,13 +647,7 @@ async fn run_abci(
                 .buffer(1024)
                 .service(mempool),
         )
-        .info(
-            ServiceBuilder::new()
-                .load_shed()
-                .buffer(100)
-                .rate_limit(50, std::time::Duration::from_secs(1))
-                .service(info),
-        )
+        .info(info_service(info))
         .finish()
         .unwrap();
 
@@ -525,6 +671,144 @@ async fn run_abci(
     }
 }
 
+/// Wrap the consensus service with a buffer of the given size.
+///
+/// Consensus requests must never be shed. Tendermint expects a response to
+/// every consensus request, in order, and dropping any of them would halt the
+/// node. When the buffer is full, Tendermint waits for room in it instead.
+fn consensus_service<S, R>(consensus: S, buffer_size: usize) -> Buffer<S, R>
+where
+    S: Service<R> + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError> + Send + Sync,
+    R: Send + 'static,
+{
+    ServiceBuilder::new().buffer(buffer_size).service(consensus)
+}
+
+/// Wrap the info service with a rate limit and a buffer, and shed the
+/// requests that don't fit into the buffer. Info requests are only queries,
+/// so they are shed to keep the node responsive under load.
+fn info_service<S, R>(info: S) -> LoadShed<Buffer<RateLimit<S>, R>>
+where
+    S: Service<R> + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError> + Send + Sync,
+    R: Send + 'static,
+{
+    ServiceBuilder::new()
+        .load_shed()
+        .buffer(INFO_BUFFER_SIZE)
+        .rate_limit(50, std::time::Duration::from_secs(1))
+        .service(info)
+}
+
+/// Launches a new task serving the liveness and readiness probes into the
+/// asynchronous runtime, if a health check address is configured, and returns
+/// its [`task::JoinHandle`].
+fn start_health_check(
+    spawner: &mut AbortableSpawner,
+    config: &config::Ledger,
+) -> task::JoinHandle<()> {
+    let address = match config.shell.health_check_address {
+        Some(address) => address,
+        None => return spawn_dummy_task(()),
+    };
+    let readiness = health::Readiness {
+        abci_address: config.shell.ledger_address,
+        tendermint_rpc_address: config.tendermint.rpc_address,
+    };
+
+    // Channel for signalling shut down to the health check server
+    let (health_abort_send, health_abort_recv) =
+        tokio::sync::oneshot::channel();
+
+    spawner
+        .spawn_abortable("Health check", move |aborter| async move {
+            match tokio::net::TcpListener::bind(address).await {
+                Ok(listener) => {
+                    tracing::info!("Serving health checks on {}", address);
+                    match health::serve(listener, readiness, health_abort_recv)
+                        .await
+                    {
+                        Ok(()) => drop(aborter),
+                        Err(err) => {
+                            // The ledger doesn't depend on the health checks,
+                            // so it keeps running without them
+                            tracing::warn!(
+                                "Health check server error, the health checks \
+                                 are no longer served: {}",
+                                err
+                            );
+                            aborter.detach();
+                        }
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to bind the health check server to {}, the \
+                         ledger will run without the health checks: {}",
+                        address,
+                        err
+                    );
+                    aborter.detach();
+                }
+            }
+        })
+        .with_cleanup(async move {
+            let _ = health_abort_send.send(());
+        })
+}
+
+/// Launches a new task streaming the summaries of the committed blocks
+/// published by the shell into the asynchronous runtime, if a block stream
+/// address is configured, and returns its [`task::JoinHandle`].
+fn start_block_stream(
+    spawner: &mut AbortableSpawner,
+    config: &config::Ledger,
+    sender: block_stream::Sender,
+) -> task::JoinHandle<()> {
+    let address = match config.shell.block_stream_address {
+        Some(address) => address,
+        None => return spawn_dummy_task(()),
+    };
+
+    // Channel for signalling shut down to the block stream server
+    let (stream_abort_send, stream_abort_recv) =
+        tokio::sync::oneshot::channel();
+
+    spawner
+        .spawn_abortable("Block stream", move |aborter| async move {
+            match tokio::net::TcpListener::bind(address).await {
+                Ok(listener) => {
+                    tracing::info!("Streaming blocks on {}", address);
+                    match block_stream::serve(
+                        listener,
+                        sender,
+                        stream_abort_recv,
+                    )
+                    .await
+                    {
+                        Ok(()) => drop(aborter),
+                        Err(err) => aborter.abort_with_error(format!(
+                            "Block stream server error: {}",
+                            err
+                        )),
+                    }
+                }
+                Err(err) => {
+                    aborter.abort_with_error(format!(
+                        "Failed to bind the block stream server to {}: {}",
+                        address, err
+                    ));
+                }
+            }
+        })
+        .with_cleanup(async move {
+            let _ = stream_abort_send.send(());
+        })
+}
+
 /// Launches a new task managing a Tendermint process into the asynchronous
 /// runtime, and returns its [`task::JoinHandle`].
 fn start_tendermint(
@@ -536,10 +820,15 @@ fn start_tendermint(
     let ledger_address = config.shell.ledger_address.to_string();
     let tendermint_config = config.tendermint.clone();
     let genesis_time = config
-        .genesis_time
-        .clone()
-        .try_into()
-        .expect("expected RFC3339 genesis_time");
+        .parsed_genesis_time()
+        .expect("The genesis time is validated when the config is loaded");
+    if config::is_genesis_time_far_future(genesis_time, DateTimeUtc::now()) {
+        tracing::warn!(
+            "The genesis time {} is far in the future, the chain won't start \
+             producing blocks until then.",
+            genesis_time.to_rfc3339()
+        );
+    }
 
     // Channel for signalling shut down to Tendermint process
     let (tm_abort_send, tm_abort_recv) =
@@ -559,9 +848,9 @@ fn start_tendermint(
             .await;
             tracing::info!("Tendermint node is no longer running.");
 
-            drop(aborter);
-            if res.is_err() {
-                tracing::error!("{:?}", &res);
+            match &res {
+                Ok(()) => drop(aborter),
+                Err(err) => aborter.abort_with_error(err),
             }
             res
         })
@@ -587,8 +876,335 @@ fn start_tendermint(
         })
 }
 
+/// Launches a new task logging the time left until the genesis time, while
+/// the node started before it waits for it. The task is aborted with the
+/// other tasks, e.g. on an interrupt signal, and it's detached once the
+/// genesis time is reached, so that the other tasks keep running.
+fn start_genesis_wait(
+    spawner: &mut AbortableSpawner,
+    config: &config::Ledger,
+) -> task::JoinHandle<()> {
+    let genesis_time = config
+        .parsed_genesis_time()
+        .expect("The genesis time is validated when the config is loaded");
+    let log_interval = config
+        .shell
+        .genesis_wait_log_interval_secs
+        .map(|secs| std::time::Duration::from_secs(secs.max(1)))
+        .unwrap_or(DEFAULT_GENESIS_WAIT_LOG_INTERVAL);
+    spawn_genesis_wait(spawner, genesis_time, log_interval)
+}
+
+fn spawn_genesis_wait(
+    spawner: &mut AbortableSpawner,
+    genesis_time: DateTimeUtc,
+    log_interval: std::time::Duration,
+) -> task::JoinHandle<()> {
+    if genesis_time <= DateTimeUtc::now() {
+        return spawn_dummy_task(());
+    }
+
+    // Channel for signalling shut down to the genesis wait
+    let (wait_abort_send, wait_abort_recv) = tokio::sync::oneshot::channel();
+
+    spawner
+        .spawn_abortable("Genesis wait", move |aborter| async move {
+            tokio::select! {
+                () = wait_for_genesis(genesis_time, log_interval) => {
+                    tracing::info!(
+                        "Reached the genesis time {}",
+                        genesis_time.to_rfc3339()
+                    );
+                    aborter.detach()
+                }
+                _ = wait_abort_recv => drop(aborter),
+            }
+        })
+        .with_cleanup(async move {
+            let _ = wait_abort_send.send(());
+        })
+}
+
+/// Wait until the genesis time, logging the time left every `log_interval`.
+/// Returns immediately when the genesis time has already passed.
+async fn wait_for_genesis(
+    genesis_time: DateTimeUtc,
+    log_interval: std::time::Duration,
+) {
+    loop {
+        let left = match (genesis_time.0 - DateTimeUtc::now().0).to_std() {
+            Ok(left) if !left.is_zero() => left,
+            // The genesis time has passed
+            _ => return,
+        };
+        tracing::info!(
+            "Waiting {} until genesis at {}",
+            format_time_left(left),
+            genesis_time.to_rfc3339()
+        );
+        tokio::time::sleep(left.min(log_interval)).await;
+    }
+}
+
+/// Format the time left until the genesis time in minutes, or in seconds when
+/// it's less than a minute
+fn format_time_left(left: std::time::Duration) -> String {
+    let secs = left.as_secs();
+    if secs >= 60 {
+        format!("{} minutes", secs / 60)
+    } else {
+        format!("{} seconds", secs)
+    }
+}
+
 /// Spawn a dummy asynchronous task into the runtime,
 /// which will resolve instantly.
 fn spawn_dummy_task<T: Send + 'static>(ready: T) -> task::JoinHandle<T> {
     tokio::spawn(async { std::future::ready(ready).await })
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use futures::future::{self, Ready};
+    use namada::types::time::Duration as TimeDuration;
+    use tower::load_shed::error::Overloaded;
+
+    use super::*;
+    use crate::node::ledger::abortable::AbortReason;
+
+    /// A service that is never ready, as if the shell was stuck processing
+    /// a request
+    struct StalledService;
+
+    impl Service<()> for StalledService {
+        type Error = BoxError;
+        type Future = Ready<Result<(), BoxError>>;
+        type Response = ();
+
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Poll::Pending
+        }
+
+        fn call(&mut self, _req: ()) -> Self::Future {
+            unreachable!("A stalled service is never ready")
+        }
+    }
+
+    /// Test that when the shell stalls, the info requests that don't fit
+    /// into the buffer are shed, while the consensus requests wait for room.
+    #[tokio::test]
+    async fn test_consensus_requests_are_never_shed() {
+        const CONSENSUS_BUFFER_SIZE: usize = 2;
+
+        let mut info = info_service(StalledService);
+        let mut pending_info = vec![];
+        for _ in 0..INFO_BUFFER_SIZE {
+            future::poll_fn(|cx| info.poll_ready(cx)).await.unwrap();
+            pending_info.push(info.call(()));
+        }
+        future::poll_fn(|cx| info.poll_ready(cx)).await.unwrap();
+        let err = info.call(()).await.unwrap_err();
+        assert!(err.is::<Overloaded>());
+
+        let mut consensus =
+            consensus_service(StalledService, CONSENSUS_BUFFER_SIZE);
+        let mut pending_consensus = vec![];
+        for _ in 0..CONSENSUS_BUFFER_SIZE {
+            future::poll_fn(|cx| consensus.poll_ready(cx))
+                .await
+                .unwrap();
+            pending_consensus.push(consensus.call(()));
+        }
+        // The consensus service is not ready, but it doesn't fail either
+        let ready = tokio::time::timeout(
+            Duration::from_millis(100),
+            future::poll_fn(|cx| consensus.poll_ready(cx)),
+        )
+        .await;
+        assert!(ready.is_err());
+    }
+
+    /// Test that the panic hook is only installed when configured and that
+    /// it only aborts on a panic in the shell's thread.
+    #[test]
+    fn test_shell_panic_hook() {
+        let config =
+            config::Ledger::new("", Default::default(), TendermintMode::Full);
+        assert!(!config.shell.abort_on_panic);
+        assert!(!set_shell_panic_hook(&config.shell));
+
+        let aborted = Arc::new(AtomicBool::new(false));
+        let hook_aborted = aborted.clone();
+        install_shell_panic_hook(move || {
+            hook_aborted.store(true, Ordering::SeqCst)
+        });
+        let panic_in_thread = |name: &str| {
+            thread::Builder::new()
+                .name(name.into())
+                .spawn(|| panic!("Test panic"))
+                .unwrap()
+                .join()
+                .unwrap_err();
+        };
+        panic_in_thread("other");
+        assert!(!aborted.load(Ordering::SeqCst));
+        panic_in_thread(SHELL_THREAD_NAME);
+        assert!(aborted.load(Ordering::SeqCst));
+        // Restore the default hook
+        let _ = std::panic::take_hook();
+    }
+
+    /// Test that the caches that are not configured fall back to their
+    /// default sizes when the available memory reading is zero or implausibly
+    /// low, rather than being sized down to nothing.
+    #[test]
+    fn test_cache_sizes_fallback() {
+        let mut config =
+            config::Ledger::new("", Default::default(), TendermintMode::Full)
+                .shell;
+        let defaults = RunAuxSetup {
+            vp_wasm_compilation_cache: DEFAULT_WASM_COMPILATION_CACHE_BYTES,
+            tx_wasm_compilation_cache: DEFAULT_WASM_COMPILATION_CACHE_BYTES,
+            db_block_cache_size_bytes: DEFAULT_BLOCK_CACHE_BYTES,
+        };
+        // A zero reading
+        assert_eq!(cache_sizes(&config, || 0), defaults);
+        // A very small, but non-zero reading
+        assert_eq!(cache_sizes(&config, || 1024 * 1024), defaults);
+        assert_eq!(
+            cache_sizes(&config, || MIN_AVAILABLE_MEMORY_BYTES - 1),
+            defaults
+        );
+
+        // A plausible reading is shared between the caches
+        let available = 6 * MIN_AVAILABLE_MEMORY_BYTES;
+        assert_eq!(
+            cache_sizes(&config, || available),
+            RunAuxSetup {
+                vp_wasm_compilation_cache: available / 6,
+                tx_wasm_compilation_cache: available / 6,
+                db_block_cache_size_bytes: available / 3,
+            }
+        );
+
+        // The configured sizes are used as they are, without reading the
+        // available memory
+        config.vp_wasm_compilation_cache_bytes = Some(1);
+        config.tx_wasm_compilation_cache_bytes = Some(2);
+        config.block_cache_bytes = Some(3);
+        assert_eq!(
+            cache_sizes(&config, || unreachable!()),
+            RunAuxSetup {
+                vp_wasm_compilation_cache: 1,
+                tx_wasm_compilation_cache: 2,
+                db_block_cache_size_bytes: 3,
+            }
+        );
+    }
+
+    /// A buffer of the logs written in a test
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl LogBuffer {
+        fn contains(&self, pattern: &str) -> bool {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).contains(pattern)
+        }
+    }
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Test that a node started shortly before the genesis time logs the time
+    /// left until it, without aborting the other tasks once it's reached,
+    /// that the wait is aborted with the other tasks and that there's no wait
+    /// once the genesis time has passed.
+    #[tokio::test]
+    async fn test_genesis_wait() {
+        let logs = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let log_interval = Duration::from_millis(10);
+
+        // The genesis time has already passed
+        let genesis_time =
+            DateTimeUtc(DateTimeUtc::now().0 - TimeDuration::seconds(1));
+        tokio::time::timeout(
+            Duration::from_millis(100),
+            wait_for_genesis(genesis_time, log_interval),
+        )
+        .await
+        .expect("There must be no wait");
+        assert!(!logs.contains("until genesis"));
+
+        // A near-future genesis time
+        let genesis_time =
+            DateTimeUtc(DateTimeUtc::now().0 + TimeDuration::seconds(1));
+        let mut spawner = AbortableSpawner::new();
+        let wait = spawn_genesis_wait(&mut spawner, genesis_time, log_interval);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(logs.contains(&format!(
+            "seconds until genesis at {}",
+            genesis_time.to_rfc3339()
+        )));
+        tokio::time::timeout(Duration::from_secs(2), wait)
+            .await
+            .expect("The wait must end at the genesis time")
+            .unwrap();
+        assert!(logs.contains("Reached the genesis time"));
+        // The end of the wait doesn't abort the other tasks
+        spawner
+            .spawn_abortable("Task", |aborter| async move {
+                drop(aborter);
+            })
+            .with_no_cleanup();
+        assert_eq!(
+            spawner.wait_for_abort().await,
+            AbortReason::ChildTerminated(Some("Task"))
+        );
+
+        // The wait for a far-future genesis time is aborted with the other
+        // tasks
+        let genesis_time =
+            DateTimeUtc(DateTimeUtc::now().0 + TimeDuration::hours(1));
+        let mut spawner = AbortableSpawner::new();
+        let wait = spawn_genesis_wait(&mut spawner, genesis_time, log_interval);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(logs.contains("Waiting 59 minutes until genesis"));
+        spawner
+            .spawn_abortable("Task", |aborter| async move {
+                drop(aborter);
+            })
+            .with_no_cleanup();
+        assert_eq!(
+            spawner.wait_for_abort().await,
+            AbortReason::ChildTerminated(Some("Task"))
+        );
+        tokio::time::timeout(Duration::from_secs(1), wait)
+            .await
+            .expect("The wait must be aborted")
+            .unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+//! Sampling of the logs of the ABCI requests, so that the frequent requests,
+//! like `Query` and `CheckTx`, don't flood the logs at scale, and the tracing
+//! spans of the requests, so that the time spent handling them can be
+//! attributed by the request type.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use namada::types::storage::BlockHeight;
+
+use crate::node::ledger::shims::abcipp_shim_types::shim::Request;
+use crate::telemetry;
+
+/// The types of the consensus-critical requests, which are always logged
+const ALWAYS_LOGGED: [&str; 6] = [
+    "InitChain",
+    "PrepareProposal",
+    "ProcessProposal",
+    "VerifyVoteExtension",
+    "FinalizeBlock",
+    "Commit",
+];
+
+/// Get the type of a request, as logged
+pub fn request_type(req: &Request) -> &'static str {
+    match req {
+        Request::InitChain(_) => "InitChain",
+        Request::Info(_) => "Info",
+        Request::Query(_) => "Query",
+        Request::PrepareProposal(_) => "PrepareProposal",
+        Request::VerifyHeader(_) => "VerifyHeader",
+        Request::ProcessProposal(_) => "ProcessProposal",
+        Request::RevertProposal(_) => "RevertProposal",
+        #[cfg(feature = "abcipp")]
+        Request::ExtendVote(_) => "ExtendVote",
+        #[cfg(feature = "abcipp")]
+        Request::VerifyVoteExtension(_) => "VerifyVoteExtension",
+        Request::FinalizeBlock(_) => "FinalizeBlock",
+        Request::Commit(_) => "Commit",
+        Request::Flush(_) => "Flush",
+        Request::Echo(_) => "Echo",
+        Request::CheckTx(_) => "CheckTx",
+        Request::ListSnapshots(_) => "ListSnapshots",
+        Request::OfferSnapshot(_) => "OfferSnapshot",
+        Request::LoadSnapshotChunk(_) => "LoadSnapshotChunk",
+        Request::ApplySnapshotChunk(_) => "ApplySnapshotChunk",
+    }
+}
+
+/// Get the height of the block a request is for, if it's applicable. The
+/// block-level requests are for the block following the last committed one,
+/// while a query for the height 0 is for the last committed block.
+pub fn request_height(req: &Request, last_height: BlockHeight) -> Option<u64> {
+    match req {
+        Request::Query(query) => match u64::try_from(query.height) {
+            Ok(0) => Some(last_height.0),
+            Ok(height) => Some(height),
+            Err(_) => None,
+        },
+        Request::PrepareProposal(_)
+        | Request::ProcessProposal(_)
+        | Request::FinalizeBlock(_)
+        | Request::Commit(_) => Some(last_height.0 + 1),
+        _ => None,
+    }
+}
+
+/// Handle a request in a span with its type and the height of its block, if
+/// it's applicable. The time it took to handle the request is recorded in the
+/// span's `duration_us` field, in microseconds, and in the exported metrics,
+/// if any. The spans entered while the request is handled, like the spans of
+/// the VPs, are nested in it.
+pub fn in_request_span<T>(
+    request_type: &'static str,
+    height: Option<u64>,
+    handle: impl FnOnce() -> T,
+) -> T {
+    let span = tracing::info_span!(
+        "abci_request",
+        request = request_type,
+        height = tracing::field::Empty,
+        duration_us = tracing::field::Empty,
+    );
+    if let Some(height) = height {
+        span.record("height", height);
+    }
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = handle();
+    let duration = start.elapsed();
+    span.record("duration_us", duration.as_micros() as u64);
+    telemetry::record_request(request_type, duration);
+    result
+}
+
+/// A sampler of the logged requests. The consensus-critical requests are
+/// always logged, while only 1 in every `sample_rate` of the other requests
+/// of each type is logged.
+#[derive(Debug)]
+pub struct RequestLogSampler {
+    /// Log 1 in this many requests of each sampled type, or none if 0
+    sample_rate: u64,
+    /// The number of requests received by type
+    counters: HashMap<&'static str, u64>,
+}
+
+impl RequestLogSampler {
+    /// Create a sampler logging 1 in `sample_rate` of the requests of each
+    /// type that's not always logged, or none of them if the rate is 0
+    pub fn new(sample_rate: u64) -> Self {
+        Self {
+            sample_rate,
+            counters: HashMap::new(),
+        }
+    }
+
+    /// Count a received request of the given type and check if it should be
+    /// logged
+    pub fn should_log(&mut self, request_type: &'static str) -> bool {
+        if ALWAYS_LOGGED.contains(&request_type) {
+            return true;
+        }
+        if self.sample_rate == 0 {
+            return false;
+        }
+        let counter = self.counters.entry(request_type).or_default();
+        let should_log = *counter % self.sample_rate == 0;
+        *counter = counter.wrapping_add(1);
+        should_log
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::{span, Subscriber};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    use super::*;
+    use crate::facade::tendermint_proto::abci::RequestQuery;
+
+    /// The sequence of request types received in the tests
+    const REQUESTS: [&str; 10] = [
+        "Query",
+        "CheckTx",
+        "Query",
+        "FinalizeBlock",
+        "Query",
+        "CheckTx",
+        "Commit",
+        "Query",
+        "CheckTx",
+        "Query",
+    ];
+
+    fn sample(sample_rate: u64) -> Vec<bool> {
+        let mut sampler = RequestLogSampler::new(sample_rate);
+        REQUESTS
+            .into_iter()
+            .map(|request_type| sampler.should_log(request_type))
+            .collect()
+    }
+
+    /// Test that 1 in every N requests is logged for each sampled type, while
+    /// the consensus-critical requests are always logged.
+    #[test]
+    fn test_request_log_sampling() {
+        // The 1st and 4th Query and the 1st CheckTx are logged
+        assert_eq!(
+            sample(3),
+            [true, true, false, true, false, false, true, true, false, false]
+        );
+        // All the requests are logged
+        assert_eq!(sample(1), [true; 10]);
+        // Only the always logged requests are logged
+        assert_eq!(
+            sample(0),
+            [
+                false, false, false, true, false, false, true, false, false,
+                false
+            ]
+        );
+    }
+
+    /// A closed span, with its recorded fields
+    #[derive(Debug)]
+    struct RecordedSpan {
+        name: &'static str,
+        parent: Option<&'static str>,
+        fields: HashMap<&'static str, String>,
+    }
+
+    /// A layer recording the spans, once they're closed
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        open: Arc<Mutex<HashMap<span::Id, RecordedSpan>>>,
+        closed: Arc<Mutex<Vec<RecordedSpan>>>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<&'static str, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name(), format!("{:?}", value));
+        }
+    }
+
+    impl<S> Layer<S> for SpanRecorder
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &span::Attributes<'_>,
+            id: &span::Id,
+            ctx: Context<'_, S>,
+        ) {
+            let mut span = RecordedSpan {
+                name: attrs.metadata().name(),
+                parent: ctx
+                    .span(id)
+                    .and_then(|span| span.parent())
+                    .map(|parent| parent.name()),
+                fields: HashMap::new(),
+            };
+            attrs.record(&mut FieldVisitor(&mut span.fields));
+            self.open.lock().unwrap().insert(id.clone(), span);
+        }
+
+        fn on_record(
+            &self,
+            id: &span::Id,
+            values: &span::Record<'_>,
+            _ctx: Context<'_, S>,
+        ) {
+            if let Some(span) = self.open.lock().unwrap().get_mut(id) {
+                values.record(&mut FieldVisitor(&mut span.fields));
+            }
+        }
+
+        fn on_close(&self, id: span::Id, _ctx: Context<'_, S>) {
+            if let Some(span) = self.open.lock().unwrap().remove(&id) {
+                self.closed.lock().unwrap().push(span);
+            }
+        }
+    }
+
+    /// Test that a request is handled in a span with its type, height and
+    /// duration, in which the spans entered while handling it are nested.
+    #[test]
+    fn test_request_span() {
+        let recorder = SpanRecorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            in_request_span("FinalizeBlock", Some(2), || {
+                tracing::debug_span!("vp").in_scope(|| {})
+            });
+            in_request_span("Info", None, || {});
+        });
+
+        let closed = recorder.closed.lock().unwrap();
+        let names: Vec<_> = closed.iter().map(|span| span.name).collect();
+        assert_eq!(names, ["vp", "abci_request", "abci_request"]);
+        assert_eq!(closed[0].parent, Some("abci_request"));
+
+        let finalize_block = &closed[1].fields;
+        assert_eq!(finalize_block["request"], "\"FinalizeBlock\"");
+        assert_eq!(finalize_block["height"], "2");
+        assert!(finalize_block["duration_us"].parse::<u64>().is_ok());
+
+        let info = &closed[2].fields;
+        assert_eq!(info["request"], "\"Info\"");
+        assert!(!info.contains_key("height"));
+        assert!(info.contains_key("duration_us"));
+    }
+
+    /// Test that the block-level requests are for the block following the
+    /// last committed one and that a query for the height 0 is for the last
+    /// committed block.
+    #[test]
+    fn test_request_height() {
+        let last_height = BlockHeight(5);
+        let query = |height| {
+            Request::Query(RequestQuery {
+                height,
+                ..Default::default()
+            })
+        };
+        assert_eq!(request_height(&query(0), last_height), Some(5));
+        assert_eq!(request_height(&query(3), last_height), Some(3));
+        assert_eq!(request_height(&query(-1), last_height), None);
+        assert_eq!(
+            request_height(&Request::Commit(Default::default()), last_height),
+            Some(6)
+        );
+        assert_eq!(
+            request_height(&Request::Info(Default::default()), last_height),
+            None
+        );
+    }
+}
@@ -1,8 +1,13 @@
 //! Implementation of the `FinalizeBlock` ABCI++ method for the Shell
 
+use std::collections::{BTreeMap, HashMap};
+
+use namada::ledger::events::tx_results::TxChanges;
+use namada::ledger::inflation::{self, InflationController};
 use namada::ledger::pos::types::into_tm_voting_power;
-use namada::ledger::protocol;
-use namada::types::storage::{BlockHash, BlockResults, Header};
+use namada::ledger::storage::write_log::StorageModification;
+use namada::ledger::{parameters, pos, protocol, storage_api, token_supply};
+use namada::types::storage::{BlockHash, BlockResults, DbKeySeg, Header};
 
 use super::governance::execute_governance_proposals;
 use super::*;
@@ -25,7 +30,8 @@ where
     /// included in the next `Shell::prepare_proposal` call.
     ///
     /// Incoming wrapper txs need no further validation. They
-    /// are added to the block.
+    /// are added to the block and their fees are charged, to be distributed
+    /// at the end of the block by the fee distribution parameter.
     ///
     /// Error codes:
     ///   0: Ok
@@ -34,10 +40,19 @@ where
     ///   3: Wasm runtime error
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
+    ///
+    /// A block whose time is before the last committed block's time is
+    /// rejected with an error before any state is updated.
     pub fn finalize_block(
         &mut self,
         req: shim::request::FinalizeBlock,
     ) -> Result<shim::response::FinalizeBlock> {
+        self.check_block_time(req.header.time)?;
+        let block_time = req.header.time;
+        let proposer = self.storage.read_validator_address_raw_hash(
+            tm_raw_hash_to_string(&req.proposer_address),
+        );
+
         // reset gas meter before we start
         self.gas_meter.reset();
 
@@ -45,6 +60,15 @@ where
         // begin the next block and check if a new epoch began
         let (height, new_epoch) =
             self.update_state(req.header, req.hash, req.byzantine_validators);
+        if new_epoch {
+            self.log_token_supply();
+            self.apply_inflation();
+        }
+        // apply the slashes for the evidence of misbehavior, if any
+        response.events.extend(self.slash());
+        // the WASM memory limits of the block are the parameters committed
+        // before it
+        self.update_wasm_memory_max_pages();
 
         if new_epoch {
             let _proposals_result =
@@ -53,7 +77,14 @@ where
 
         // Tracks the accepted transactions
         self.storage.block.results = BlockResults::default();
+        // The storage changes and the verifiers of the txs, by the hash of
+        // their tx
+        let mut tx_changes = HashMap::new();
+        // The fees charged to the wrapper txs, by token
+        let mut fees = BTreeMap::new();
         for (tx_index, processed_tx) in req.txs.iter().enumerate() {
+            // The txs in the block are no longer in the mempool
+            self.mempool.remove(&hash_tx(&processed_tx.tx));
             let tx = if let Ok(tx) = Tx::try_from(processed_tx.tx.as_ref()) {
                 tx
             } else {
@@ -70,7 +101,10 @@ where
             if ErrorCodes::from_u32(processed_tx.result.code).unwrap()
                 == ErrorCodes::InvalidSig
             {
-                let mut tx_event = match process_tx(tx.clone()) {
+                let mut tx_event = match process_tx(
+                    tx.clone(),
+                    &self.chain_id,
+                ) {
                     Ok(tx @ TxType::Wrapper(_))
                     | Ok(tx @ TxType::Protocol(_)) => {
                         Event::new_tx_event(&tx, height.0)
@@ -99,7 +133,7 @@ where
                 continue;
             }
 
-            let tx_type = if let Ok(tx_type) = process_tx(tx) {
+            let tx_type = if let Ok(tx_type) = process_tx(tx, &self.chain_id) {
                 tx_type
             } else {
                 tracing::error!(
@@ -128,8 +162,9 @@ where
             }
 
             let mut tx_event = match &tx_type {
-                TxType::Wrapper(_wrapper) => {
-                    self.storage.tx_queue.push(_wrapper.clone());
+                TxType::Wrapper(wrapper) => {
+                    self.storage.tx_queue.push(wrapper.clone());
+                    self.charge_fee(wrapper, &mut fees);
                     Event::new_tx_event(&tx_type, height.0)
                 }
                 TxType::Decrypted(inner) => {
@@ -159,22 +194,25 @@ where
                 }
             };
 
-            match protocol::apply_tx(
-                tx_type,
-                tx_length,
-                TxIndex(
-                    tx_index
-                        .try_into()
-                        .expect("transaction index out of bounds"),
-                ),
-                &mut self.gas_meter,
-                &mut self.write_log,
-                &self.storage,
-                &mut self.vp_wasm_cache,
-                &mut self.tx_wasm_cache,
-            )
-            .map_err(Error::TxApply)
-            {
+            let max_verifiers = self.max_verifiers();
+            let result = install_in_current_span(&self.vp_thread_pool, || {
+                protocol::apply_tx(
+                    tx_type,
+                    tx_length,
+                    TxIndex(
+                        tx_index
+                            .try_into()
+                            .expect("transaction index out of bounds"),
+                    ),
+                    &mut self.gas_meter,
+                    &mut self.write_log,
+                    &self.storage,
+                    &mut self.vp_wasm_cache,
+                    &mut self.tx_wasm_cache,
+                    max_verifiers,
+                )
+            });
+            match result.map_err(Error::TxApply) {
                 Ok(result) => {
                     if result.is_accepted() {
                         tracing::info!(
@@ -193,6 +231,16 @@ where
                             let event = Event::from(ibc_event.clone());
                             response.events.push(event);
                         }
+                        // Add the events emitted by the VPs besides the
+                        // tx_event
+                        for (vp, event) in &result.vps_result.events {
+                            response.events.push(Event::new_vp_event(
+                                vp,
+                                event.clone(),
+                                &tx_event["hash"],
+                                height.0,
+                            ));
+                        }
                         match serde_json::to_string(
                             &result.initialized_accounts,
                         ) {
@@ -218,6 +266,10 @@ where
                         self.write_log.drop_tx();
                         tx_event["code"] = ErrorCodes::InvalidTx.into();
                     }
+                    tx_changes.insert(
+                        tx_event["hash"].clone(),
+                        TxChanges::from(&result),
+                    );
                     tx_event["gas_used"] = result.gas_used.to_string();
                     tx_event["info"] = result.to_string();
                 }
@@ -233,11 +285,19 @@ where
                         .get_current_transaction_gas()
                         .to_string();
                     tx_event["info"] = msg.to_string();
-                    tx_event["code"] = ErrorCodes::WasmRuntimeError.into();
+                    tx_event["code"] = match msg {
+                        Error::TxApply(protocol::Error::TooManyVerifiers {
+                            ..
+                        }) => ErrorCodes::InvalidTx,
+                        _ => ErrorCodes::WasmRuntimeError,
+                    }
+                    .into();
                 }
             }
             response.events.push(tx_event);
         }
+        self.distribute_fees(proposer.as_ref(), fees);
+        self.update_token_supply();
 
         if new_epoch {
             self.update_epoch(&mut response);
@@ -248,14 +308,152 @@ where
             .finalize_transaction()
             .map_err(|_| Error::GasOverflow)?;
 
+        self.tx_results
+            .log_block(height, &response.events, tx_changes);
+        self.block_event_log.log_block(height, &response.events);
+        self.ibc_packets
+            .log_block(height, block_time, &response.events);
         self.event_log_mut().log_events(response.events.clone());
+        if self.block_stream.receiver_count() > 0 {
+            self.block_events = response.events.clone();
+        }
 
         Ok(response)
     }
 
+    /// Check that the time of a new block is not before the time of the last
+    /// committed block, which is recorded in its committed header. Equal
+    /// times are allowed and the first block has no predecessor to check.
+    fn check_block_time(&self, time: DateTimeUtc) -> Result<()> {
+        if self.storage.last_height.0 == 0 {
+            return Ok(());
+        }
+        let last_header = self
+            .storage
+            .db
+            .read_block_header(self.storage.last_height)
+            .expect("Must be able to read the last committed block's header");
+        match last_header {
+            Some(last_header) if time < last_header.time => {
+                Err(Error::NonMonotonicBlockTime(time, last_header.time))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Charge the fee of a wrapper tx to its payer and add it to the fees of
+    /// the block. The payer's balance was checked by `ProcessProposal`, but
+    /// it may have been spent by the previous txs of the block, in which case
+    /// only the remaining balance is charged.
+    fn charge_fee(
+        &mut self,
+        wrapper: &WrapperTx,
+        fees: &mut BTreeMap<Address, token::Amount>,
+    ) {
+        if wrapper.fee.amount == token::Amount::default() {
+            return;
+        }
+        let fee_payer = wrapper_fee_payer(wrapper);
+        let balance_key = token::balance_key(&wrapper.fee.token, &fee_payer);
+        let mut balance = self.read_balance(&balance_key);
+        let fee = if wrapper.fee.amount > balance {
+            tracing::error!(
+                "The fee payer {} has a balance of {} left, which cannot pay \
+                 the fee of {} of the wrapper tx {}",
+                fee_payer,
+                balance,
+                wrapper.fee.amount,
+                wrapper.tx_hash
+            );
+            balance
+        } else {
+            wrapper.fee.amount
+        };
+        balance.spend(&fee);
+        self.write_balance(&balance_key, balance);
+        fees.entry(wrapper.fee.token.clone())
+            .or_default()
+            .receive(&fee);
+    }
+
+    /// Distribute the fees charged in the block by the fee distribution
+    /// parameter. The burned part is credited to no one, the proposer's part
+    /// is paid to the block proposer and the rest to the community pool. When
+    /// the proposer is unknown, its part is paid to the community pool too.
+    fn distribute_fees(
+        &mut self,
+        proposer: Option<&Address>,
+        fees: BTreeMap<Address, token::Amount>,
+    ) {
+        if fees.is_empty() {
+            return;
+        }
+        let (fee_distribution, _gas) =
+            parameters::read_fee_distribution_parameter(&self.storage)
+                .expect("The fee distribution parameter must be set");
+        for (token, fee) in fees {
+            let split = fee_distribution.split(fee);
+            let mut community_pool_fee = split.community_pool;
+            match proposer {
+                Some(proposer) => {
+                    self.credit_balance(&token, proposer, split.proposer)
+                }
+                None => community_pool_fee.receive(&split.proposer),
+            }
+            self.credit_balance(
+                &token,
+                &fee_distribution.community_pool,
+                community_pool_fee,
+            );
+        }
+    }
+
+    /// Credit an amount to a balance
+    fn credit_balance(
+        &mut self,
+        token: &Address,
+        owner: &Address,
+        amount: token::Amount,
+    ) {
+        if amount == token::Amount::default() {
+            return;
+        }
+        let balance_key = token::balance_key(token, owner);
+        let mut balance = self.read_balance(&balance_key);
+        balance.receive(&amount);
+        self.write_balance(&balance_key, balance);
+    }
+
+    /// Read a balance with the changes of the txs applied so far in the block
+    fn read_balance(&self, balance_key: &Key) -> token::Amount {
+        match self.write_log.read(balance_key).0 {
+            Some(StorageModification::Write { value }) => {
+                token::Amount::try_from_slice(value).unwrap_or_default()
+            }
+            Some(_) => token::Amount::default(),
+            None => storage_api::StorageRead::read(&self.storage, balance_key)
+                .expect("Storage read in the protocol must not fail")
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Write a balance to the block's write log, so that it's committed with
+    /// the changes of the txs of the block
+    fn write_balance(&mut self, balance_key: &Key, balance: token::Amount) {
+        self.write_log
+            .write(
+                balance_key,
+                balance
+                    .try_to_vec()
+                    .expect("Encoding a balance shouldn't fail"),
+            )
+            .expect("Writing a balance to the write log shouldn't fail");
+        self.write_log.commit_tx();
+    }
+
     /// Sets the metadata necessary for a new block, including
     /// the hash, height, validator changes, and evidence of
-    /// byzantine behavior. Applies slashes if necessary.
+    /// byzantine behavior, whose slashes are to be applied next.
     /// Returns a bool indicating if a new epoch began and
     /// the height of the new block.
     fn update_state(
@@ -279,20 +477,212 @@ where
 
         self.byzantine_validators = byzantine_validators;
 
+        #[cfg(feature = "dev")]
+        self.shorten_dev_epoch(header_time);
+
         let new_epoch = self
             .storage
             .update_epoch(height, header_time)
             .expect("Must be able to update epoch");
 
-        self.slash();
         (height, new_epoch)
     }
 
+    /// In dev mode with `dev_epoch_num_of_blocks` set, bring forward the
+    /// end of the current epoch so that it lasts only the configured number
+    /// of blocks and ignores the epoch's minimum duration.
+    #[cfg(feature = "dev")]
+    fn shorten_dev_epoch(&mut self, header_time: DateTimeUtc) {
+        let num_of_blocks = match self.dev_epoch_num_of_blocks {
+            Some(num_of_blocks) => num_of_blocks,
+            None => return,
+        };
+        let epoch_start_height = self
+            .storage
+            .block
+            .pred_epochs
+            .last_epoch_first_height()
+            .unwrap_or_default();
+        let next_epoch_min_start_height = epoch_start_height + num_of_blocks;
+        if next_epoch_min_start_height
+            < self.storage.next_epoch_min_start_height
+        {
+            self.storage.next_epoch_min_start_height =
+                next_epoch_min_start_height;
+        }
+        if header_time < self.storage.next_epoch_min_start_time {
+            self.storage.next_epoch_min_start_time = header_time;
+        }
+    }
+
+    /// Record the total supply of the known tokens at the end of the epoch
+    /// of the last committed block, which just ended, and delete the supplies
+    /// of the epoch that's past the retention period.
+    fn log_token_supply(&mut self) {
+        let ended_epoch = self.storage.last_epoch;
+        let expired_epoch = token_supply::expired_epoch(ended_epoch);
+        for token in address::tokens().into_keys() {
+            let supply =
+                token_supply::read_total_supply(&self.storage, &token)
+                    .expect("Storage read in the protocol must not fail");
+            self.write_log
+                .write(
+                    &token_supply::epoch_total_supply_key(&token, ended_epoch),
+                    supply
+                        .try_to_vec()
+                        .expect("Encoding a supply shouldn't fail"),
+                )
+                .expect("Writing a supply to the write log shouldn't fail");
+            if let Some(expired_epoch) = expired_epoch {
+                self.write_log
+                    .delete(&token_supply::epoch_total_supply_key(
+                        &token,
+                        expired_epoch,
+                    ))
+                    .expect(
+                        "Deleting a supply from the write log shouldn't fail",
+                    );
+            }
+        }
+        self.write_log.commit_tx();
+    }
+
+    /// Update the total supply of the known tokens by the changes of their
+    /// balances in the block, so that the balances don't have to be summed
+    /// up again.
+    fn update_token_supply(&mut self) {
+        let tokens = address::tokens();
+        let mut deltas: BTreeMap<Address, token::Change> = BTreeMap::new();
+        for key in self.write_log.get_block_keys() {
+            let token = match key.segments.first() {
+                Some(DbKeySeg::AddressSeg(token))
+                    if tokens.contains_key(token) =>
+                {
+                    token
+                }
+                _ => continue,
+            };
+            if token::is_balance_key(token, &key).is_none() {
+                continue;
+            }
+            let pre: token::Amount =
+                storage_api::StorageRead::read(&self.storage, &key)
+                    .expect("Storage read in the protocol must not fail")
+                    .unwrap_or_default();
+            let post = self.read_balance(&key);
+            *deltas.entry(token.clone()).or_default() +=
+                post.change() - pre.change();
+        }
+        for (token, delta) in deltas {
+            if delta == 0 {
+                continue;
+            }
+            let supply = token_supply::read_total_supply(&self.storage, &token)
+                .expect("Storage read in the protocol must not fail");
+            let supply =
+                token::Amount::from_change((supply.change() + delta).max(0));
+            self.write_log
+                .write(
+                    &token_supply::total_supply_key(&token),
+                    supply
+                        .try_to_vec()
+                        .expect("Encoding a supply shouldn't fail"),
+                )
+                .expect("Writing a supply to the write log shouldn't fail");
+        }
+        self.write_log.commit_tx();
+    }
+
+    /// Mint the inflation of the epoch that just began, as set by the
+    /// inflation controller from the state at the end of the last epoch, and
+    /// pay it to the owners of the bonds, proportionally to their amounts. The
+    /// bonds to the jailed validators are not paid.
+    fn apply_inflation(&mut self) {
+        let (current_epoch, _gas) = self.storage.get_current_epoch();
+        let staking_token = self.storage.staking_token_address();
+        let pos_params = self.storage.read_pos_params();
+        let (params, _gas) = parameters::read(&self.storage)
+            .expect("The protocol parameters must be set");
+
+        let bond_ids: Vec<_> =
+            storage_api::iter_prefix_bytes(&self.storage, &pos::bonds_prefix())
+                .expect("Storage read in the protocol must not fail")
+                .filter_map(|entry| {
+                    let (key, _value) = entry
+                        .expect("Storage read in the protocol must not fail");
+                    pos::is_bond_key(&key)
+                })
+                .collect();
+        let mut staked = token::Amount::default();
+        let mut bonds = Vec::with_capacity(bond_ids.len());
+        for bond_id in bond_ids {
+            // Read through `PosReadOnly`, as `PosBase` has no bond amounts
+            let amount = pos::namada_proof_of_stake::PosReadOnly::bond_amount(
+                &self.storage,
+                &bond_id,
+                current_epoch,
+            )
+            .expect("Storage read in the protocol must not fail");
+            staked.receive(&amount);
+            // The bonds to a jailed validator are not paid any rewards
+            let is_jailed = matches!(
+                self.storage
+                    .read_validator_state(&bond_id.validator)
+                    .and_then(|states| states.get(current_epoch).copied()),
+                Some(pos::types::ValidatorState::Jailed)
+            );
+            if !is_jailed {
+                bonds.push((bond_id.source, amount));
+            }
+        }
+        let total_supply =
+            token_supply::read_total_supply(&self.storage, &staking_token)
+                .expect("Storage read in the protocol must not fail");
+
+        let inflation = InflationController {
+            total_supply,
+            staked,
+            last_staked_ratio: params.staked_ratio,
+            last_inflation: token::Amount::from(params.pos_inflation_amount),
+            target_staked_ratio: pos_params.target_staked_ratio,
+            min_inflation_rate: pos_params.min_inflation_rate,
+            max_inflation_rate: pos_params.max_inflation_rate,
+            p_gain: params.pos_gain_p,
+            d_gain: params.pos_gain_d,
+            epochs_per_year: params.epochs_per_year,
+        }
+        .run();
+        let mut minted = token::Amount::default();
+        for (owner, share) in inflation::distribute(inflation.amount, bonds) {
+            self.credit_balance(&staking_token, &owner, share);
+            minted.receive(&share);
+        }
+        tracing::info!(
+            "Minted {} of inflation in epoch {} at a staked ratio of {}",
+            minted,
+            current_epoch,
+            inflation.staked_ratio
+        );
+
+        parameters::update_staked_ratio_parameter(
+            &mut self.storage,
+            &inflation.staked_ratio,
+        )
+        .expect("Writing a protocol parameter shouldn't fail");
+        parameters::update_pos_inflation_amount_parameter(
+            &mut self.storage,
+            &u64::from(inflation.amount),
+        )
+        .expect("Writing a protocol parameter shouldn't fail");
+    }
+
     /// If a new epoch begins, we update the response to include
     /// changes to the validator sets and consensus parameters
-    fn update_epoch(&self, response: &mut shim::response::FinalizeBlock) {
-        // Apply validator set update
+    fn update_epoch(&mut self, response: &mut shim::response::FinalizeBlock) {
         let (current_epoch, _gas) = self.storage.get_current_epoch();
+        // Re-select the active validators of the upcoming epochs
+        self.storage.rebalance_validator_sets(current_epoch);
+        // Apply validator set update
         let pos_params = self.storage.read_pos_params();
         // TODO ABCI validator updates on block H affects the validator set
         // on block H+2, do we need to update a block earlier?
@@ -330,10 +720,27 @@ where
 /// are covered by the e2e tests.
 #[cfg(test)]
 mod test_finalize_block {
+    use data_encoding::{HEXLOWER, HEXUPPER};
+    use namada::ledger::events::block_events::{
+        BlockEventLog, BlockEventsResult,
+    };
+    use namada::ledger::events::tx_results::{self, TxResultOrigin};
+    use namada::ledger::events::EventType;
+    use namada::ledger::parameters::FeeDistribution;
+    use namada::ledger::queries::RPC;
+    use namada::ledger::token_supply::EpochSupply;
+    use namada::types::hash::Hash;
+    use namada::types::key::tm_consensus_key_raw_hash;
     use namada::types::storage::Epoch;
+    use namada::types::time::Duration;
     use namada::types::transaction::{EncryptionKey, Fee};
+    use namada::vm::wasm::memory;
+    use rust_decimal_macros::dec;
 
     use super::*;
+    use crate::facade::tendermint_proto::abci::{
+        MisbehaviorType as EvidenceType, Validator,
+    };
     use crate::node::ledger::shell::test_utils::*;
     use crate::node::ledger::shims::abcipp_shim_types::shim::request::{
         FinalizeBlock, ProcessedTx,
@@ -365,7 +772,9 @@ mod test_finalize_block {
                 raw_tx.clone(),
                 Default::default(),
             );
-            let tx = wrapper.sign(&keypair).expect("Test failed");
+            let tx = wrapper
+                .sign(&keypair, &shell.chain_id)
+                .expect("Test failed");
             if i > 1 {
                 processed_txs.push(ProcessedTx {
                     tx: tx.to_bytes(),
@@ -585,7 +994,9 @@ mod test_finalize_block {
                 raw_tx.clone(),
                 Default::default(),
             );
-            let wrapper = wrapper_tx.sign(&keypair).expect("Test failed");
+            let wrapper = wrapper_tx
+                .sign(&keypair, &shell.chain_id)
+                .expect("Test failed");
             valid_txs.push(wrapper_tx);
             processed_txs.push(ProcessedTx {
                 tx: wrapper.to_bytes(),
@@ -642,4 +1053,866 @@ mod test_finalize_block {
         }
         assert_eq!(counter, 2);
     }
+
+    /// Make a duplicate vote evidence at block height 1 for the genesis
+    /// validator and return it along with the validator's address
+    fn genesis_validator_evidence(shell: &TestShell) -> (Address, Evidence) {
+        let (current_epoch, _) = shell.storage.get_current_epoch();
+        let validator = shell
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .expect("Test failed")
+            .active
+            .iter()
+            .next()
+            .expect("Test failed")
+            .address
+            .clone();
+        let consensus_key = shell
+            .storage
+            .read_validator_consensus_key(&validator)
+            .expect("Test failed")
+            .get(current_epoch)
+            .expect("Test failed")
+            .clone();
+        let address = HEXUPPER
+            .decode(tm_consensus_key_raw_hash(&consensus_key).as_bytes())
+            .expect("Test failed");
+        let evidence = Evidence {
+            r#type: EvidenceType::DuplicateVote as i32,
+            validator: Some(Validator {
+                address,
+                ..Default::default()
+            }),
+            height: 1,
+            ..Default::default()
+        };
+        (validator, evidence)
+    }
+
+    /// Test that evidence of misbehavior delivered in a block slashes the
+    /// validator's stake and emits a slash event, and that the same evidence
+    /// delivered again is ignored
+    #[test]
+    fn test_slash_evidence() {
+        let (mut shell, _) = setup();
+        let (validator, evidence) = genesis_validator_evidence(&shell);
+        let params = shell.storage.read_pos_params();
+        let pipeline_epoch = shell.storage.block.epoch + params.pipeline_len;
+        let stake_at_pipeline = |shell: &TestShell| {
+            shell
+                .storage
+                .read_validator_deltas(&validator)
+                .expect("Test failed")
+                .get(pipeline_epoch)
+                .expect("Test failed")
+        };
+        let stake_before = stake_at_pipeline(&shell);
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                byzantine_validators: vec![evidence.clone()],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        shell.commit();
+        let slash_events: Vec<_> = events
+            .iter()
+            .filter(|event| event.event_type == EventType::Slash)
+            .collect();
+        assert_eq!(slash_events.len(), 1);
+        assert_eq!(slash_events[0]["validator"], validator.encode());
+        let stake_after = stake_at_pipeline(&shell);
+        assert!(stake_after < stake_before);
+        assert_eq!(shell.storage.read_validator_slashes(&validator).len(), 1);
+
+        // The same evidence delivered again is ignored
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                byzantine_validators: vec![evidence],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        assert!(!events
+            .iter()
+            .any(|event| event.event_type == EventType::Slash));
+        assert_eq!(stake_at_pipeline(&shell), stake_after);
+        assert_eq!(shell.storage.read_validator_slashes(&validator).len(), 1);
+    }
+
+    /// Test that a changed consensus key of a validator is only replaced in
+    /// the validator set updates at the pipeline epoch, by deactivating the
+    /// old key and activating the new key.
+    #[test]
+    fn test_consensus_key_change_validator_updates() {
+        let (mut shell, _) = setup();
+        let (validator, _) = genesis_validator_evidence(&shell);
+        let (current_epoch, _) = shell.storage.get_current_epoch();
+        let params = shell.storage.read_pos_params();
+        let mut consensus_keys = shell
+            .storage
+            .read_validator_consensus_key(&validator)
+            .expect("Test failed");
+        let old_key = consensus_keys
+            .get(current_epoch)
+            .expect("Test failed")
+            .clone();
+        let new_key = gen_keypair().ref_to();
+        consensus_keys.set(new_key.clone(), current_epoch, &params);
+        shell
+            .storage
+            .write_validator_consensus_key(&validator, &consensus_keys);
+        shell
+            .storage
+            .write_validator_address_raw_hash(&validator, &new_key);
+
+        let validator_updates = |shell: &TestShell| {
+            let (epoch, _) = shell.storage.get_current_epoch();
+            let mut updates = vec![];
+            shell
+                .storage
+                .validator_set_update(epoch, |update| updates.push(update));
+            updates
+        };
+        // Before the pipeline epoch, the validator is not updated
+        for _ in 1..params.pipeline_len {
+            advance_epoch(&mut shell);
+            assert!(validator_updates(&shell).is_empty());
+        }
+        // At the pipeline epoch, the old key is replaced with the new key
+        advance_epoch(&mut shell);
+        match &validator_updates(&shell)[..] {
+            [
+                ValidatorSetUpdate::Deactivated(deactivated),
+                ValidatorSetUpdate::Active(active),
+            ] => {
+                assert_eq!(deactivated, &old_key);
+                assert_eq!(active.consensus_key, new_key);
+            }
+            updates => panic!("Unexpected validator updates {:?}", updates),
+        }
+        // The validator is not updated again after the change
+        advance_epoch(&mut shell);
+        assert!(validator_updates(&shell).is_empty());
+    }
+
+    /// Test that a slashed validator is jailed from the pipeline epoch, when
+    /// it's deactivated in the validator set updates.
+    #[cfg(feature = "dev")]
+    #[test]
+    fn test_slash_evidence_jails_validator() {
+        let (mut shell, _) = setup();
+        shell.dev_epoch_num_of_blocks = Some(2);
+        let (validator, evidence) = genesis_validator_evidence(&shell);
+        let (current_epoch, _) = shell.storage.get_current_epoch();
+        let params = shell.storage.read_pos_params();
+        let pipeline_epoch = current_epoch + params.pipeline_len;
+        let consensus_key = shell
+            .storage
+            .read_validator_consensus_key(&validator)
+            .expect("Test failed")
+            .get(current_epoch)
+            .expect("Test failed")
+            .clone();
+
+        shell
+            .finalize_block(FinalizeBlock {
+                byzantine_validators: vec![evidence],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        shell.commit();
+        assert_eq!(
+            shell.storage.read_validator_jailed_epoch(&validator),
+            Some(current_epoch)
+        );
+        let states = shell
+            .storage
+            .read_validator_state(&validator)
+            .expect("Test failed");
+        assert_eq!(
+            states.get(current_epoch),
+            Some(&pos::types::ValidatorState::Candidate)
+        );
+        assert_eq!(
+            states.get(pipeline_epoch),
+            Some(&pos::types::ValidatorState::Jailed)
+        );
+        let validator_set = shell
+            .storage
+            .read_validator_set()
+            .get(pipeline_epoch)
+            .expect("Test failed")
+            .clone();
+        assert!(!validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+            .any(|weighted| weighted.address == validator));
+
+        // At the pipeline epoch, the jailed validator is deactivated
+        for _ in 0..params.pipeline_len {
+            advance_epoch(&mut shell);
+        }
+        let (epoch, _) = shell.storage.get_current_epoch();
+        assert_eq!(epoch, pipeline_epoch);
+        let mut updates = vec![];
+        shell
+            .storage
+            .validator_set_update(epoch, |update| updates.push(update));
+        assert!(updates.iter().any(|update| matches!(
+            update,
+            ValidatorSetUpdate::Deactivated(key) if key == &consensus_key
+        )));
+    }
+
+    /// Query the result of the tx with the given hash
+    fn query_tx_result(
+        shell: &TestShell,
+        tx_hash: &Hash,
+    ) -> Option<tx_results::TxResult> {
+        let response = shell.query(request::Query {
+            path: RPC.shell().tx_result_path(tx_hash),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        BorshDeserialize::try_from_slice(&response.value).expect("Test failed")
+    }
+
+    /// Test that the result of an applied tx can be queried by its hash
+    /// until it expires, and that an unknown hash has no result.
+    #[test]
+    fn test_query_tx_result() {
+        let (mut shell, _) = setup();
+        shell.tx_results = TxResultLog::new(1);
+        let keypair = gen_keypair();
+        let mut wasm_path = top_level_directory();
+        wasm_path.push("wasm_for_tests/tx_no_op.wasm");
+        let tx_code = std::fs::read(wasm_path)
+            .expect("Expected a file at given code path");
+        let raw_tx =
+            Tx::new(tx_code, Some("transaction data".as_bytes().to_owned()));
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: shell.storage.native_token.clone(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx.clone(),
+            Default::default(),
+        );
+        shell.enqueue_tx(wrapper);
+        let tx_hash = hash_tx(&raw_tx.to_bytes());
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![ProcessedTx {
+                    tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(
+                        raw_tx,
+                    )))
+                    .to_bytes(),
+                    result: TxResult {
+                        code: ErrorCodes::Ok.into(),
+                        info: "".into(),
+                    },
+                }],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        let result = query_tx_result(&shell, &tx_hash).expect("Test failed");
+        assert!(result.is_accepted());
+        assert_eq!(
+            result.origin,
+            TxResultOrigin::Block(shell.storage.last_height + 1)
+        );
+        assert_eq!(
+            result.gas_used.to_string(),
+            events[0].attributes["gas_used"]
+        );
+        assert!(result.rejected_vps.is_empty());
+
+        // An unknown hash has no result
+        assert!(query_tx_result(&shell, &Hash([0; 32])).is_none());
+
+        // The result expires after the retention period
+        shell.commit();
+        shell
+            .finalize_block(FinalizeBlock::default())
+            .expect("Test failed");
+        assert!(query_tx_result(&shell, &tx_hash).is_none());
+    }
+
+    /// Test that the same wrapper tx has a result of the same shape from the
+    /// mempool validation and from its application in a block, but that only
+    /// the latter is committed.
+    #[test]
+    fn test_tx_result_mempool_and_block() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let raw_tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: shell.storage.native_token.clone(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx,
+            Default::default(),
+        );
+        let wrapper_hash = hash_tx(&wrapper.try_to_vec().expect("Test failed"));
+        let tx = wrapper
+            .sign(&keypair, &shell.chain_id)
+            .expect("Test failed")
+            .to_bytes();
+
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+        let check_tx_result: tx_results::TxResult =
+            BorshDeserialize::try_from_slice(
+                &HEXLOWER
+                    .decode(response.info.as_bytes())
+                    .expect("Test failed"),
+            )
+            .expect("Test failed");
+        assert_eq!(check_tx_result.origin, TxResultOrigin::CheckTx);
+        assert!(check_tx_result.is_accepted());
+        assert!(!check_tx_result.is_committed());
+        assert_eq!(check_tx_result.info, response.log);
+        // The mempool doesn't execute the tx, nor commit any events
+        assert_eq!(check_tx_result.gas_used, 0);
+        assert!(check_tx_result.changed_keys.is_empty());
+        assert!(check_tx_result.events.is_empty());
+        assert!(response.events.is_empty());
+
+        shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![ProcessedTx {
+                    tx,
+                    result: TxResult {
+                        code: ErrorCodes::Ok.into(),
+                        info: "".into(),
+                    },
+                }],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        let block_result =
+            query_tx_result(&shell, &wrapper_hash).expect("Test failed");
+        assert_eq!(
+            block_result.origin,
+            TxResultOrigin::Block(shell.storage.last_height + 1)
+        );
+        assert!(block_result.is_accepted());
+        assert!(block_result.is_committed());
+        assert_eq!(block_result.code, check_tx_result.code);
+        assert!(block_result.rejected_vps.is_empty());
+    }
+
+    /// Query the events emitted by the block at the given height
+    fn query_block_events(
+        shell: &TestShell,
+        height: BlockHeight,
+    ) -> BlockEventsResult {
+        let response = shell.query(request::Query {
+            path: RPC.shell().block_events_path(&height),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        BorshDeserialize::try_from_slice(&response.value).expect("Test failed")
+    }
+
+    /// Test that the events emitted by a block can be queried by its height,
+    /// grouped by their source, until they expire, and that they cannot be
+    /// queried for a block that's not committed.
+    #[test]
+    fn test_query_block_events() {
+        let (mut shell, _) = setup();
+        shell.block_event_log = BlockEventLog::new(1);
+        let (validator, evidence) = genesis_validator_evidence(&shell);
+        let keypair = gen_keypair();
+        let mut wasm_path = top_level_directory();
+        wasm_path.push("wasm_for_tests/tx_no_op.wasm");
+        let tx_code = std::fs::read(wasm_path)
+            .expect("Expected a file at given code path");
+        let raw_tx =
+            Tx::new(tx_code, Some("transaction data".as_bytes().to_owned()));
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: shell.storage.native_token.clone(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx.clone(),
+            Default::default(),
+        );
+        shell.enqueue_tx(wrapper);
+        let tx_hash = hash_tx(&raw_tx.to_bytes());
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![ProcessedTx {
+                    tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(
+                        raw_tx,
+                    )))
+                    .to_bytes(),
+                    result: TxResult {
+                        code: ErrorCodes::Ok.into(),
+                        info: "".into(),
+                    },
+                }],
+                byzantine_validators: vec![evidence],
+                ..Default::default()
+            })
+            .expect("Test failed");
+        let height = shell.storage.last_height + 1;
+        // The block is not committed yet
+        let response = shell.query(request::Query {
+            path: RPC.shell().block_events_path(&height),
+            ..Default::default()
+        });
+        assert_ne!(response.code, 0);
+        shell.commit();
+
+        let block_events = match query_block_events(&shell, height) {
+            BlockEventsResult::Available(block_events) => block_events,
+            BlockEventsResult::Unavailable => panic!("Test failed"),
+        };
+        assert_eq!(block_events.begin_block.len(), 1);
+        assert_eq!(block_events.begin_block[0].event_type, EventType::Slash);
+        assert_eq!(
+            block_events.begin_block[0]["validator"],
+            validator.encode()
+        );
+        assert_eq!(block_events.txs.len(), 1);
+        assert_eq!(block_events.txs[0].hash, tx_hash.to_string());
+        assert_eq!(
+            block_events.txs[0].events.last().map(|e| &e.event_type),
+            Some(&EventType::Applied)
+        );
+        assert!(block_events.end_block.is_empty());
+        // All the events of the block are returned
+        let num_events = block_events.begin_block.len()
+            + block_events
+                .txs
+                .iter()
+                .map(|tx| tx.events.len())
+                .sum::<usize>();
+        assert_eq!(num_events, events.len());
+
+        // The events expire after the retention period
+        shell
+            .finalize_block(FinalizeBlock::default())
+            .expect("Test failed");
+        shell.commit();
+        assert_eq!(
+            query_block_events(&shell, height),
+            BlockEventsResult::Unavailable
+        );
+        assert_eq!(
+            query_block_events(&shell, shell.storage.last_height),
+            BlockEventsResult::Available(Default::default())
+        );
+    }
+
+    /// Test that with `dev_epoch_num_of_blocks` set, a new epoch begins
+    /// every given number of blocks, even though the genesis epoch duration
+    /// parameters are much longer.
+    #[cfg(feature = "dev")]
+    #[test]
+    fn test_dev_epoch_num_of_blocks() {
+        let (mut shell, _) = setup();
+        shell.dev_epoch_num_of_blocks = Some(2);
+
+        let mut new_epochs = 0;
+        for _ in 0..6 {
+            let epoch = shell.storage.block.epoch;
+            shell
+                .finalize_block(FinalizeBlock::default())
+                .expect("Test failed");
+            shell.commit();
+            if shell.storage.block.epoch != epoch {
+                assert_eq!(shell.storage.block.epoch, epoch.next());
+                new_epochs += 1;
+            }
+        }
+        assert_eq!(new_epochs, 3);
+    }
+
+    /// Query the total supply of a token in the given epoch
+    #[cfg(feature = "dev")]
+    fn query_total_supply(
+        shell: &TestShell,
+        token: &Address,
+        epoch: Epoch,
+    ) -> EpochSupply {
+        let response = shell.query(request::Query {
+            path: RPC.shell().total_supply_path(token, &epoch),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        BorshDeserialize::try_from_slice(&response.value).expect("Test failed")
+    }
+
+    /// Finalize and commit a block that begins a new epoch, by bringing
+    /// forward the end of the current epoch
+    fn advance_epoch(shell: &mut TestShell) {
+        let epoch = shell.storage.block.epoch;
+        shell.storage.next_epoch_min_start_height = BlockHeight::default();
+        shell.storage.next_epoch_min_start_time = DateTimeUtc::now();
+        shell
+            .finalize_block(FinalizeBlock::default())
+            .expect("Test failed");
+        shell.commit();
+        assert_eq!(shell.storage.block.epoch, epoch.next());
+    }
+
+    /// Test that the total supply of a token is updated from the balances
+    /// changed in a block, that it's recorded at the end of each epoch and
+    /// that it can't be queried for a future epoch.
+    #[cfg(feature = "dev")]
+    #[test]
+    fn test_query_total_supply() {
+        let (mut shell, _) = setup();
+        shell.dev_epoch_num_of_blocks = Some(2);
+        // Not the staking token, whose supply is inflated at every epoch
+        let token = address::btc();
+        let genesis_supply =
+            token_supply::read_total_supply(&shell.storage, &token)
+                .expect("Test failed");
+        // Mint in a tx of a new block
+        let mint = |shell: &mut TestShell, owner: &Address, amount: u64| {
+            let key = token::balance_key(&token, owner);
+            let amount = token::Amount::from(amount);
+            shell
+                .write_log
+                .write(&key, amount.try_to_vec().expect("Test failed"))
+                .expect("Test failed");
+            shell.write_log.commit_tx();
+            shell
+                .finalize_block(FinalizeBlock::default())
+                .expect("Test failed");
+            shell.commit();
+        };
+        let supply = |amount: u64| {
+            EpochSupply::Available(genesis_supply + token::Amount::from(amount))
+        };
+
+        mint(&mut shell, &address::testing::established_address_1(), 100);
+        let first_epoch = shell.storage.last_epoch;
+        // The supply is persisted rather than summed up from the balances
+        let (persisted, _gas) = shell
+            .storage
+            .read(&token_supply::total_supply_key(&token))
+            .expect("Test failed");
+        assert!(persisted.is_some());
+        assert_eq!(
+            query_total_supply(&shell, &token, first_epoch),
+            supply(100)
+        );
+
+        advance_epoch(&mut shell);
+        mint(&mut shell, &address::testing::established_address_2(), 50);
+        let second_epoch = shell.storage.last_epoch;
+        assert_eq!(second_epoch, first_epoch.next());
+        assert_eq!(
+            query_total_supply(&shell, &token, first_epoch),
+            supply(100)
+        );
+        assert_eq!(
+            query_total_supply(&shell, &token, second_epoch),
+            supply(150)
+        );
+
+        // The supply cannot be queried for a future epoch
+        let response = shell.query(request::Query {
+            path: RPC.shell().total_supply_path(&token, &second_epoch.next()),
+            ..Default::default()
+        });
+        assert_ne!(response.code, 0);
+    }
+
+    /// Finalize a block with the given time
+    fn finalize_block_at(
+        shell: &mut TestShell,
+        time: DateTimeUtc,
+    ) -> Result<shim::response::FinalizeBlock> {
+        let mut req = FinalizeBlock::default();
+        req.header.time = time;
+        shell.finalize_block(req)
+    }
+
+    /// Test that a block may not be finalized with a time before the last
+    /// committed block's time, while the first block and a block with the
+    /// same time as the last committed block are accepted.
+    #[test]
+    fn test_non_monotonic_block_time() {
+        let (mut shell, _) = setup();
+        let time = DateTimeUtc::now();
+
+        // The first block has no predecessor
+        finalize_block_at(&mut shell, time).expect("Test failed");
+        shell.commit();
+
+        // A block with an equal time is accepted
+        finalize_block_at(&mut shell, time).expect("Test failed");
+        shell.commit();
+        let last_height = shell.storage.last_height;
+
+        // A block with an earlier time is rejected without updating the state
+        let result = finalize_block_at(&mut shell, time - Duration::seconds(1));
+        assert!(matches!(result, Err(Error::NonMonotonicBlockTime(_, _))));
+        assert_eq!(shell.storage.block.height, last_height);
+        assert!(shell.storage.header.is_none());
+
+        // A later block is accepted
+        finalize_block_at(&mut shell, time + Duration::seconds(1))
+            .expect("Test failed");
+        shell.commit();
+        assert_eq!(shell.storage.last_height, last_height + 1);
+    }
+
+    /// Test that the WASM memory limits of a block are the parameters
+    /// committed before it, regardless of the node's config.
+    #[test]
+    fn test_wasm_memory_max_pages_parameters() {
+        let (mut shell, _) = setup();
+        let time = DateTimeUtc::now();
+
+        finalize_block_at(&mut shell, time).expect("Test failed");
+        shell.commit();
+        assert_eq!(
+            shell.vp_wasm_cache.memory_max_pages(),
+            memory::VP_MEMORY_MAX_PAGES
+        );
+        assert_eq!(
+            shell.tx_wasm_cache.memory_max_pages(),
+            memory::TX_MEMORY_MAX_PAGES
+        );
+
+        parameters::update_vp_wasm_memory_max_pages_parameter(
+            &mut shell.storage,
+            &100,
+        )
+        .expect("Test failed");
+        parameters::update_tx_wasm_memory_max_pages_parameter(
+            &mut shell.storage,
+            &400,
+        )
+        .expect("Test failed");
+        finalize_block_at(&mut shell, time).expect("Test failed");
+        shell.commit();
+        assert_eq!(shell.vp_wasm_cache.memory_max_pages(), 100);
+        assert_eq!(shell.tx_wasm_cache.memory_max_pages(), 400);
+    }
+
+    /// Test that a subscriber to the block stream receives the summaries of
+    /// the committed blocks in order.
+    #[test]
+    fn test_block_stream_summaries() {
+        let (mut shell, _) = setup();
+        let mut subscriber = shell.block_stream.subscribe();
+        let time = DateTimeUtc::now();
+
+        for seconds in 0..2 {
+            finalize_block_at(&mut shell, time + Duration::seconds(seconds))
+                .expect("Test failed");
+            shell.commit();
+        }
+
+        let first = subscriber.try_recv().expect("Test failed");
+        let second = subscriber.try_recv().expect("Test failed");
+        assert!(subscriber.try_recv().is_err());
+        assert_eq!(second.height, first.height + 1);
+        assert_eq!(second.height, shell.storage.last_height.0);
+        assert_eq!(first.time, Some(time.to_rfc3339()));
+        assert_eq!(second.hash, HEXUPPER.encode(&shell.storage.block.hash.0));
+    }
+
+    /// Test that the fees of the wrapper txs included in a block are charged
+    /// to their payers and split between burning, the block proposer and the
+    /// community pool, with the rounding remainder paid to the pool.
+    #[test]
+    fn test_fee_distribution() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let token = shell.storage.native_token.clone();
+        let community_pool = address::testing::established_address_1();
+        parameters::update_fee_distribution_parameter(
+            &mut shell.storage,
+            &FeeDistribution {
+                burn_rate: dec!(0.5),
+                proposer_rate: dec!(0.3),
+                community_pool_rate: dec!(0.2),
+                community_pool: community_pool.clone(),
+            },
+        )
+        .expect("Test failed");
+        let balance = |shell: &TestShell, owner: &Address| -> token::Amount {
+            storage_api::StorageRead::read(
+                &shell.storage,
+                &token::balance_key(&token, owner),
+            )
+            .expect("Test failed")
+            .unwrap_or_default()
+        };
+        let payer = Address::from(&keypair.ref_to());
+        shell
+            .storage
+            .write(
+                &token::balance_key(&token, &payer),
+                token::Amount::from(100).try_to_vec().expect("Test failed"),
+            )
+            .expect("Test failed");
+        let proposer_address = genesis_proposer_address(&shell);
+        let proposer = shell
+            .storage
+            .read_validator_address_raw_hash(tm_raw_hash_to_string(
+                &proposer_address,
+            ))
+            .expect("Test failed");
+        let proposer_balance = balance(&shell, &proposer);
+
+        let txs = [7, 4]
+            .into_iter()
+            .map(|fee: u64| {
+                let wrapper = WrapperTx::new(
+                    Fee {
+                        amount: fee.into(),
+                        token: token.clone(),
+                    },
+                    &keypair,
+                    Epoch(0),
+                    0.into(),
+                    Tx::new("wasm_code".as_bytes().to_owned(), None),
+                    Default::default(),
+                );
+                ProcessedTx {
+                    tx: wrapper
+                        .sign(&keypair, &shell.chain_id)
+                        .expect("Test failed")
+                        .to_bytes(),
+                    result: TxResult {
+                        code: ErrorCodes::Ok.into(),
+                        info: "".into(),
+                    },
+                }
+            })
+            .collect();
+        shell
+            .finalize_block(FinalizeBlock {
+                txs,
+                proposer_address,
+                ..Default::default()
+            })
+            .expect("Test failed");
+        shell.commit();
+
+        // Of the 11 charged, 5 are burned, 3 are paid to the proposer and the
+        // rest to the community pool
+        assert_eq!(balance(&shell, &payer), token::Amount::from(89));
+        assert_eq!(
+            balance(&shell, &proposer),
+            proposer_balance + token::Amount::from(3)
+        );
+        assert_eq!(balance(&shell, &community_pool), token::Amount::from(3));
+    }
+
+    /// Test that the inflation of a new epoch is minted at the configured
+    /// rate and paid to the owners of the bonds, proportionally to their
+    /// amounts.
+    #[cfg(feature = "dev")]
+    #[test]
+    fn test_epoch_inflation() {
+        let (mut shell, _) = setup();
+        let token = shell.storage.native_token.clone();
+        // Without any control, the inflation is at the minimum rate
+        let pos_params = pos::PosParams {
+            min_inflation_rate: dec!(0.05),
+            max_inflation_rate: dec!(0.1),
+            ..shell.storage.read_pos_params()
+        };
+        shell.storage.write_pos_params(&pos_params);
+        parameters::update_pos_gain_p_parameter(&mut shell.storage, &dec!(0))
+            .expect("Test failed");
+        parameters::update_pos_gain_d_parameter(&mut shell.storage, &dec!(0))
+            .expect("Test failed");
+        parameters::update_epochs_per_year_parameter(&mut shell.storage, &100)
+            .expect("Test failed");
+
+        // Delegate as much as the genesis validator's self-bond
+        let (current_epoch, _) = shell.storage.get_current_epoch();
+        let validator = shell
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .expect("Test failed")
+            .active
+            .iter()
+            .next()
+            .expect("Test failed")
+            .address
+            .clone();
+        let self_bond = pos::namada_proof_of_stake::PosReadOnly::bond_amount(
+            &shell.storage,
+            &pos::BondId {
+                source: validator.clone(),
+                validator: validator.clone(),
+            },
+            current_epoch,
+        )
+        .expect("Test failed");
+        let delegator = address::testing::established_address_1();
+        shell.storage.write_bond(
+            &pos::BondId {
+                source: delegator.clone(),
+                validator: validator.clone(),
+            },
+            &pos::Bonds::init_at_genesis(
+                pos::Bond {
+                    pos_deltas: HashMap::from([(current_epoch, self_bond)]),
+                    neg_deltas: Default::default(),
+                },
+                current_epoch,
+            ),
+        );
+
+        let balance = |shell: &TestShell, owner: &Address| -> token::Amount {
+            storage_api::StorageRead::read(
+                &shell.storage,
+                &token::balance_key(&token, owner),
+            )
+            .expect("Test failed")
+            .unwrap_or_default()
+        };
+        let supply = |shell: &TestShell| {
+            token_supply::read_total_supply(&shell.storage, &token)
+                .expect("Test failed")
+        };
+        let total_supply = supply(&shell);
+        let validator_balance = balance(&shell, &validator);
+
+        advance_epoch(&mut shell);
+
+        // 5% per annum for one of the 100 epochs in a year, split in half
+        let inflation =
+            total_supply.mul_floor(dec!(0.0005)).expect("Test failed");
+        let share = inflation.mul_floor(dec!(0.5)).expect("Test failed");
+        assert_eq!(balance(&shell, &delegator), share);
+        assert_eq!(balance(&shell, &validator), validator_balance + share);
+        assert_eq!(supply(&shell), total_supply + share + share);
+        let (params, _) =
+            parameters::read(&shell.storage).expect("Test failed");
+        assert_eq!(params.pos_inflation_amount, u64::from(inflation));
+    }
 }
@@ -0,0 +1,162 @@
+//! Export of the state of the chain into a genesis definition, which can be
+//! used to seed a new chain on a hard fork. This is unrelated to the state
+//! sync snapshots.
+use std::collections::HashMap;
+
+use namada::ledger::storage_api;
+
+use super::*;
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Export the state of the chain at the given height into a genesis
+    /// definition. The accounts, validators and parameters of the chain's
+    /// genesis are carried over with their state as of the given height:
+    /// - the balances of all the owners of the genesis tokens
+    /// - the validators' balances that are not staked
+    /// - the public keys of the established accounts
+    ///
+    /// Bonds and governance proposals made after genesis are not exported.
+    /// The tokens bonded in the chain's genesis are bonded again when the
+    /// exported genesis is initialized, so the PoS account's balance is left
+    /// out.
+    ///
+    /// Only the state at the last committed height can be exported.
+    pub fn export_genesis_state(
+        &self,
+        at_height: BlockHeight,
+    ) -> Result<genesis::Genesis> {
+        if at_height != self.storage.last_height {
+            return Err(Error::ExportHeight(
+                at_height,
+                self.storage.last_height,
+            ));
+        }
+        #[cfg(not(feature = "dev"))]
+        let mut genesis =
+            genesis::genesis(&self.base_dir, &self.storage.chain_id);
+        #[cfg(feature = "dev")]
+        let mut genesis = genesis::genesis();
+
+        for token_account in genesis.token_accounts.iter_mut() {
+            token_account.balances =
+                self.read_token_balances(&token_account.address)?;
+        }
+        for validator in genesis.validators.iter_mut() {
+            let balance_key = token::balance_key(
+                &self.storage.native_token,
+                &validator.pos_data.address,
+            );
+            validator.non_staked_balance =
+                storage_api::StorageRead::read(&self.storage, &balance_key)
+                    .map_err(Error::ExportState)?
+                    .unwrap_or_default();
+        }
+        for account in genesis.established_accounts.iter_mut() {
+            account.public_key = storage_api::StorageRead::read(
+                &self.storage,
+                &pk_key(&account.address),
+            )
+            .map_err(Error::ExportState)?;
+        }
+        Ok(genesis)
+    }
+
+    /// Read the balances of all the owners of the given token, except for
+    /// the PoS account.
+    fn read_token_balances(
+        &self,
+        token: &Address,
+    ) -> Result<HashMap<Address, token::Amount>> {
+        let prefix = token::balance_prefix(token);
+        let mut balances = HashMap::new();
+        for entry in storage_api::iter_prefix(&self.storage, &prefix)
+            .map_err(Error::ExportState)?
+        {
+            let (key, amount): (Key, token::Amount) =
+                entry.map_err(Error::ExportState)?;
+            match token::is_balance_key(token, &key) {
+                Some(owner) if *owner != pos::ADDRESS => {
+                    balances.insert(owner.clone(), amount);
+                }
+                _ => {}
+            }
+        }
+        Ok(balances)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use namada::types::storage::BlockHash;
+
+    use super::super::test_utils::{gen_keypair, setup, TestShell};
+    use super::*;
+    use crate::facade::tendermint_proto::abci::RequestInitChain;
+    use crate::facade::tendermint_proto::google::protobuf::Timestamp;
+
+    /// Test that the exported state can seed a fresh chain with the same
+    /// balances, and that only the last committed height can be exported.
+    #[test]
+    fn test_export_genesis_state() {
+        let (mut shell, _) = setup();
+        let native_token = shell.storage.native_token.clone();
+
+        // Credit a new account, which isn't in the genesis, and commit
+        let owner = Address::from(&gen_keypair().ref_to());
+        let balance = token::Amount::from(1_000_000);
+        shell
+            .storage
+            .write(
+                &token::balance_key(&native_token, &owner),
+                balance.try_to_vec().unwrap(),
+            )
+            .unwrap();
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        shell.storage.commit().unwrap();
+
+        // A height that hasn't been committed cannot be exported
+        assert!(matches!(
+            shell.export_genesis_state(BlockHeight(2)),
+            Err(Error::ExportHeight(BlockHeight(2), BlockHeight(1)))
+        ));
+
+        let genesis = shell
+            .export_genesis_state(BlockHeight(1))
+            .expect("Exporting the last committed height must succeed");
+
+        // Seed a fresh chain with the exported genesis
+        let (mut fresh, _) = TestShell::new();
+        fresh
+            .init_chain_with_genesis(
+                RequestInitChain {
+                    time: Some(Timestamp {
+                        seconds: 0,
+                        nanos: 0,
+                    }),
+                    chain_id: ChainId::default().to_string(),
+                    ..Default::default()
+                },
+                genesis,
+            )
+            .expect("Initializing the exported genesis must succeed");
+
+        let fresh_balance: Option<token::Amount> =
+            storage_api::StorageRead::read(
+                &fresh.storage,
+                &token::balance_key(&native_token, &owner),
+            )
+            .unwrap();
+        assert_eq!(fresh_balance, Some(balance));
+        assert_eq!(
+            shell.read_token_balances(&native_token).unwrap(),
+            fresh.read_token_balances(&native_token).unwrap()
+        );
+    }
+}
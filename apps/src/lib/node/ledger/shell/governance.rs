@@ -81,17 +81,25 @@ where
                             .storage
                             .write(&pending_execution_key, "")
                             .expect("Should be able to write to storage.");
-                        let tx_result = protocol::apply_tx(
-                            tx_type,
-                            0, /*  this is used to compute the fee
-                                * based on the code size. We dont
-                                * need it here. */
-                            TxIndex::default(),
-                            &mut BlockGasMeter::default(),
-                            &mut shell.write_log,
-                            &shell.storage,
-                            &mut shell.vp_wasm_cache,
-                            &mut shell.tx_wasm_cache,
+                        let tx_result = install_in_current_span(
+                            &shell.vp_thread_pool,
+                            || {
+                                protocol::apply_tx(
+                                    tx_type,
+                                    0, /*  this is used to compute the fee
+                                        * based on the code size. We dont
+                                        * need it here. */
+                                    TxIndex::default(),
+                                    &mut BlockGasMeter::default(),
+                                    &mut shell.write_log,
+                                    &shell.storage,
+                                    &mut shell.vp_wasm_cache,
+                                    &mut shell.tx_wasm_cache,
+                                    // The proposal code was approved by
+                                    // governance, so it isn't capped
+                                    None,
+                                )
+                            },
                         );
                         shell
                             .storage
@@ -2,7 +2,7 @@
 use std::collections::HashMap;
 use std::hash::Hash;
 
-use namada::ledger::parameters::Parameters;
+use namada::ledger::parameters::{self, Parameters};
 use namada::ledger::pos::into_tm_voting_power;
 use namada::types::key::*;
 #[cfg(not(feature = "dev"))]
@@ -12,7 +12,6 @@ use super::*;
 use crate::facade::tendermint_proto::abci;
 use crate::facade::tendermint_proto::crypto::PublicKey as TendermintPublicKey;
 use crate::facade::tendermint_proto::google::protobuf;
-use crate::wasm_loader;
 
 impl<D, H> Shell<D, H>
 where
@@ -26,7 +25,6 @@ where
         &mut self,
         init: request::InitChain,
     ) -> Result<response::InitChain> {
-        let mut response = response::InitChain::default();
         let (current_chain_id, _) = self.storage.get_chain_id();
         if current_chain_id != init.chain_id {
             return Err(Error::ChainId(format!(
@@ -50,6 +48,25 @@ where
         #[cfg(feature = "dev")]
         let genesis = genesis::genesis();
 
+        self.init_chain_with_genesis(init, genesis)
+    }
+
+    /// Initialize the chain's storage from the given genesis. The chain ID of
+    /// the request is expected to have been checked already.
+    ///
+    /// The genesis may only be initialized once. Its completion is recorded
+    /// last in the merkle tree, which isn't persisted before the first
+    /// commit, so an initialization interrupted by a restart is run again.
+    pub(super) fn init_chain_with_genesis(
+        &mut self,
+        init: request::InitChain,
+        genesis: genesis::Genesis,
+    ) -> Result<response::InitChain> {
+        if self.is_chain_initialized() {
+            return Err(Error::ChainAlreadyInitialized);
+        }
+        genesis.validate().map_err(Error::InvalidGenesis)?;
+        let mut response = response::InitChain::default();
         let ts: protobuf::Timestamp = init.time.expect("Missing genesis time");
         let initial_height = init
             .initial_height
@@ -66,6 +83,7 @@ where
         let genesis::Parameters {
             epoch_duration,
             max_expected_time_per_block,
+            max_ibc_packet_data_bytes,
             vp_whitelist,
             tx_whitelist,
             implicit_vp_code_path,
@@ -75,12 +93,19 @@ where
             pos_gain_d,
             staked_ratio,
             pos_inflation_amount,
+            fee_distribution,
+            block_gas_limit,
+            max_verifiers,
+            fee_tokens,
+            vp_wasm_memory_max_pages,
+            tx_wasm_memory_max_pages,
         } = genesis.parameters;
         // borrow necessary for release build, annoys clippy on dev build
         #[allow(clippy::needless_borrow)]
-        let implicit_vp =
-            wasm_loader::read_wasm(&self.wasm_dir, &implicit_vp_code_path)
-                .map_err(Error::ReadingWasm)?;
+        let implicit_vp = self
+            .wasm_dirs
+            .read_wasm(&implicit_vp_code_path)
+            .map_err(Error::ReadingWasm)?;
         // In dev, we don't check the hash
         #[cfg(feature = "dev")]
         let _ = implicit_vp_sha256;
@@ -99,6 +124,7 @@ where
         let parameters = Parameters {
             epoch_duration,
             max_expected_time_per_block,
+            max_ibc_packet_data_bytes,
             vp_whitelist,
             tx_whitelist,
             implicit_vp,
@@ -107,8 +133,15 @@ where
             pos_gain_d,
             staked_ratio,
             pos_inflation_amount,
+            fee_distribution,
+            block_gas_limit,
+            max_verifiers,
+            fee_tokens,
+            vp_wasm_memory_max_pages,
+            tx_wasm_memory_max_pages,
         };
         parameters.init_storage(&mut self.storage);
+        self.update_wasm_memory_max_pages();
 
         // Initialize governance parameters
         genesis.gov_params.init_storage(&mut self.storage);
@@ -133,9 +166,10 @@ where
             let vp_code = match vp_code_cache.get(&vp_code_path).cloned() {
                 Some(vp_code) => vp_code,
                 None => {
-                    let wasm =
-                        wasm_loader::read_wasm(&self.wasm_dir, &vp_code_path)
-                            .map_err(Error::ReadingWasm)?;
+                    let wasm = self
+                        .wasm_dirs
+                        .read_wasm(&vp_code_path)
+                        .map_err(Error::ReadingWasm)?;
                     vp_code_cache.insert(vp_code_path.clone(), wasm.clone());
                     wasm
                 }
@@ -191,10 +225,9 @@ where
             balances,
         } in genesis.token_accounts
         {
-            let vp_code =
-                vp_code_cache.get_or_insert_with(vp_code_path.clone(), || {
-                    wasm_loader::read_wasm(&self.wasm_dir, &vp_code_path)
-                        .unwrap()
+            let vp_code = vp_code_cache
+                .get_or_insert_with(vp_code_path.clone(), || {
+                    self.wasm_dirs.read_wasm(&vp_code_path).unwrap()
                 });
 
             // In dev, we don't check the hash
@@ -232,11 +265,9 @@ where
             let vp_code = vp_code_cache.get_or_insert_with(
                 validator.validator_vp_code_path.clone(),
                 || {
-                    wasm_loader::read_wasm(
-                        &self.wasm_dir,
-                        &validator.validator_vp_code_path,
-                    )
-                    .unwrap()
+                    self.wasm_dirs
+                        .read_wasm(&validator.validator_vp_code_path)
+                        .unwrap()
                 },
             );
 
@@ -327,6 +358,12 @@ where
             );
             response.validators.push(abci_validator);
         }
+
+        let initialized_key =
+            parameters::storage::get_genesis_initialized_key();
+        self.storage
+            .write(&initialized_key, true.try_to_vec().unwrap())
+            .expect("Unable to record the genesis initialization");
         Ok(response)
     }
 }
@@ -354,3 +391,77 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use namada::ledger::storage_api::StorageRead;
+
+    use super::super::test_utils::{gen_keypair, TestShell};
+    use super::*;
+    use crate::facade::tendermint_proto::abci::RequestInitChain;
+    use crate::facade::tendermint_proto::google::protobuf::Timestamp;
+
+    fn init_chain_request() -> RequestInitChain {
+        RequestInitChain {
+            time: Some(Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Test that a second `InitChain` is rejected without re-applying the
+    /// genesis, while an interrupted initialization can be completed.
+    #[test]
+    fn test_init_chain_only_once() {
+        let (mut shell, _) = TestShell::new();
+        // A partially initialized chain, as left by an interrupted
+        // initialization, can be initialized
+        let owner = address::Address::from(&gen_keypair().ref_to());
+        let balance_key =
+            token::balance_key(&shell.storage.native_token, &owner);
+        shell
+            .storage
+            .write(&balance_key, token::Amount::from(1).try_to_vec().unwrap())
+            .unwrap();
+        shell
+            .shell
+            .init_chain(init_chain_request())
+            .expect("Initializing the chain must succeed");
+
+        // Change the state after the genesis
+        let balance = token::Amount::from(123);
+        shell
+            .storage
+            .write(&balance_key, balance.try_to_vec().unwrap())
+            .unwrap();
+        let root = shell.storage.merkle_root();
+
+        // The genesis is not applied again
+        assert!(matches!(
+            shell.shell.init_chain(init_chain_request()),
+            Err(Error::ChainAlreadyInitialized)
+        ));
+        let read_balance: Option<token::Amount> =
+            StorageRead::read(&shell.storage, &balance_key).unwrap();
+        assert_eq!(read_balance, Some(balance));
+        assert_eq!(shell.storage.merkle_root(), root);
+    }
+
+    /// Test that an invalid genesis is rejected before it's applied.
+    #[test]
+    fn test_init_chain_invalid_genesis() {
+        let (mut shell, _) = TestShell::new();
+        let mut genesis = genesis::genesis();
+        genesis.validators[0].pos_data.tokens = token::Amount::from(0);
+        assert!(matches!(
+            shell.init_chain_with_genesis(init_chain_request(), genesis),
+            Err(Error::InvalidGenesis(genesis::GenesisError::ZeroStake(_)))
+        ));
+        let initialized_key =
+            parameters::storage::get_genesis_initialized_key();
+        assert!(!shell.storage.has_key(&initialized_key).unwrap().0);
+    }
+}
@@ -0,0 +1,284 @@
+//! Tracking of the txs admitted to the local mempool, for diagnostics only.
+//! Tendermint doesn't expose its mempool to the application, so the shell
+//! keeps the txs that passed `CheckTx` until they're included in a block or
+//! fail a re-check. This is not consensus-critical: the admission times and
+//! the order in which the tracked txs are forgotten are local to the node,
+//! so they must never be used to select the txs of a proposal.
+
+use std::collections::{BTreeMap, HashMap};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada::types::hash::Hash;
+
+use super::*;
+
+/// The path of the query for the txs admitted to the local mempool
+pub const MEMPOOL_QUERY_PATH: &str = "/mempool";
+
+/// The maximum number of txs in a page of the mempool query
+pub const MAX_MEMPOOL_PAGE_TXS: u64 = 100;
+
+/// The maximum number of tracked txs. Tendermint may evict txs without
+/// notifying the shell, e.g. when its mempool is full, so the oldest tracked
+/// txs are forgotten beyond this number.
+const MAX_TRACKED_TXS: usize = 10_000;
+
+/// A request for a page of the txs admitted to the local mempool
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct MempoolPageRequest {
+    /// The index of the first tx in the page
+    pub offset: u64,
+    /// The maximum number of txs in the page, capped by
+    /// [`MAX_MEMPOOL_PAGE_TXS`]
+    pub limit: u64,
+}
+
+/// A tx admitted to the local mempool
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MempoolTx {
+    /// The hash of the tx
+    pub hash: Hash,
+    /// When the tx was first admitted
+    pub admitted_at: DateTimeUtc,
+    /// The gas limit of the tx, which is zero for txs other than wrappers
+    pub gas_limit: u64,
+}
+
+/// A page of the txs admitted to the local mempool, ordered by admission
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct MempoolPage {
+    /// The total number of tracked txs
+    pub total: u64,
+    /// The requested page of txs
+    pub txs: Vec<MempoolTx>,
+}
+
+/// The txs admitted to the local mempool
+#[derive(Debug, Default)]
+pub struct MempoolTracker {
+    /// The txs by their admission sequence number
+    txs: BTreeMap<u64, MempoolTx>,
+    /// The admission sequence numbers of the txs by their hash
+    seqs: HashMap<Hash, u64>,
+    /// The sequence number of the next admitted tx
+    next_seq: u64,
+}
+
+impl MempoolTracker {
+    /// Track an admitted tx. A tx that is already tracked keeps its first
+    /// admission time.
+    pub fn admit(&mut self, hash: Hash, gas_limit: u64) {
+        if self.seqs.contains_key(&hash) {
+            return;
+        }
+        if self.txs.len() >= MAX_TRACKED_TXS {
+            let oldest = self.txs.keys().next().copied();
+            if let Some(oldest) = oldest.and_then(|seq| self.txs.remove(&seq)) {
+                self.seqs.remove(&oldest.hash);
+            }
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.seqs.insert(hash.clone(), seq);
+        self.txs.insert(
+            seq,
+            MempoolTx {
+                hash,
+                admitted_at: DateTimeUtc::now(),
+                gas_limit,
+            },
+        );
+    }
+
+    /// Stop tracking a tx, if it's tracked
+    pub fn remove(&mut self, hash: &Hash) {
+        if let Some(seq) = self.seqs.remove(hash) {
+            self.txs.remove(&seq);
+        }
+    }
+
+    /// Get the requested page of the tracked txs
+    pub fn page(&self, request: &MempoolPageRequest) -> MempoolPage {
+        let offset = request.offset.min(self.txs.len() as u64) as usize;
+        let limit = request.limit.min(MAX_MEMPOOL_PAGE_TXS) as usize;
+        MempoolPage {
+            total: self.txs.len() as u64,
+            txs: self
+                .txs
+                .values()
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Update the tracked mempool txs with the response to a `CheckTx`
+    /// request. Txs that are accepted are tracked and txs that fail a
+    /// re-check, which Tendermint evicts, are forgotten.
+    pub fn track_mempool_tx(
+        &mut self,
+        tx_bytes: &[u8],
+        r#type: &MempoolTxType,
+        response: &response::CheckTx,
+    ) {
+        let hash = hash_tx(tx_bytes);
+        match (r#type, response.code == u32::from(ErrorCodes::Ok)) {
+            (MempoolTxType::NewTransaction, true) => {
+                let gas_limit =
+                    u64::try_from(response.gas_wanted).unwrap_or_default();
+                self.mempool.admit(hash, gas_limit)
+            }
+            (MempoolTxType::RecheckTransaction, false) => {
+                self.mempool.remove(&hash)
+            }
+            _ => {}
+        }
+    }
+
+    /// Query a page of the txs admitted to the local mempool. The data of the
+    /// query must be a borsh encoded [`MempoolPageRequest`].
+    pub(super) fn query_mempool(&self, data: &[u8]) -> response::Query {
+        let request = match MempoolPageRequest::try_from_slice(data) {
+            Ok(request) => request,
+            Err(err) => {
+                return response::Query {
+                    code: 1,
+                    info: format!("Invalid mempool query: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        let page = self.mempool.page(&request);
+        response::Query {
+            value: page
+                .try_to_vec()
+                .expect("Encoding the mempool page shouldn't fail"),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mempool {
+    use namada::types::storage::Epoch;
+    use namada::types::transaction::{Fee, GasLimit};
+
+    use super::super::test_utils::{gen_keypair, setup, TestShell};
+    use super::*;
+
+    /// Make a signed wrapper tx with the given gas limit
+    fn signed_wrapper(gas_limit: u64) -> Vec<u8> {
+        let keypair = gen_keypair();
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: address::nam(),
+            },
+            &keypair,
+            Epoch(0),
+            GasLimit::from(gas_limit),
+            tx,
+            Default::default(),
+        )
+        .sign(&keypair, &ChainId::default())
+        .expect("Test failed")
+        .to_bytes()
+    }
+
+    /// Query a page of the tracked mempool txs
+    fn query(shell: &TestShell, offset: u64, limit: u64) -> MempoolPage {
+        let data = MempoolPageRequest { offset, limit }
+            .try_to_vec()
+            .expect("Test failed");
+        let response = shell.query_mempool(&data);
+        assert_eq!(response.code, 0, "{}", response.info);
+        MempoolPage::try_from_slice(&response.value).expect("Test failed")
+    }
+
+    /// Test that the admitted txs appear in the mempool query, paginated and
+    /// ordered by admission, and that they're forgotten when a re-check
+    /// fails.
+    #[test]
+    fn test_query_mempool() {
+        let (mut shell, _) = setup();
+        let first = signed_wrapper(1_000);
+        let second = signed_wrapper(2_000);
+        for tx in [&first, &second] {
+            let response =
+                shell.mempool_validate(tx, MempoolTxType::NewTransaction);
+            shell.track_mempool_tx(
+                tx,
+                &MempoolTxType::NewTransaction,
+                &response,
+            );
+        }
+
+        let page = query(&shell, 0, 10);
+        assert_eq!(page.total, 2);
+        let txs: Vec<_> = page
+            .txs
+            .iter()
+            .map(|tx| (tx.hash.clone(), tx.gas_limit))
+            .collect();
+        assert_eq!(
+            txs,
+            vec![(hash_tx(&first), 1_000), (hash_tx(&second), 2_000)]
+        );
+
+        // The pages are capped and past the end they are empty
+        let page = query(&shell, 1, 1);
+        assert_eq!(page.total, 2);
+        assert_eq!(page.txs.len(), 1);
+        assert_eq!(page.txs[0].hash, hash_tx(&second));
+        assert!(query(&shell, 2, 10).txs.is_empty());
+
+        // A failed re-check forgets the tx
+        let failed = response::CheckTx {
+            code: ErrorCodes::InvalidTx.into(),
+            ..Default::default()
+        };
+        shell.track_mempool_tx(
+            &first,
+            &MempoolTxType::RecheckTransaction,
+            &failed,
+        );
+        let page = query(&shell, 0, 10);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.txs[0].hash, hash_tx(&second));
+
+        // A malformed request is rejected
+        let response = shell.query_mempool(b"malformed");
+        assert_eq!(response.code, 1);
+    }
+
+    /// Test that the oldest txs are forgotten beyond the maximum number of
+    /// tracked txs.
+    #[test]
+    fn test_mempool_tracker_capacity() {
+        let mut tracker = MempoolTracker::default();
+        for i in 0..=MAX_TRACKED_TXS {
+            let mut hash = [0; 32];
+            hash[..8].copy_from_slice(&(i as u64).to_le_bytes());
+            tracker.admit(Hash(hash), 0);
+        }
+        let page = tracker.page(&MempoolPageRequest {
+            offset: 0,
+            limit: 1,
+        });
+        assert_eq!(page.total, MAX_TRACKED_TXS as u64);
+        let mut second = [0; 32];
+        second[..8].copy_from_slice(&1_u64.to_le_bytes());
+        assert_eq!(page.txs[0].hash, Hash(second));
+    }
+}
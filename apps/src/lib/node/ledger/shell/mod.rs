@@ -6,51 +6,70 @@
 //! (unless we can simply overwrite them in the next block).
 //! More info in <https://github.com/anoma/namada/issues/362>.
 mod finalize_block;
+mod genesis_export;
 mod governance;
 mod init_chain;
+pub mod mempool;
 mod prepare_proposal;
 mod process_proposal;
 mod queries;
+pub mod replay;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::{TryFrom, TryInto};
+use std::fmt::Debug;
 use std::mem;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 #[allow(unused_imports)]
 use std::rc::Rc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use data_encoding::HEXLOWER;
+use namada::ledger::events::block_events::{
+    BlockEventLog, DEFAULT_BLOCK_EVENTS_RETENTION_BLOCKS,
+};
+use namada::ledger::events::ibc_packets::{
+    IbcPacketTracker, DEFAULT_IBC_ACK_TIMEOUT_SECS,
+    DEFAULT_IBC_TIMED_OUT_RETENTION_SECS,
+};
 use namada::ledger::events::log::EventLog;
+use namada::ledger::events::tx_results::{
+    self, TxResultLog, DEFAULT_TX_RESULTS_RETENTION_BLOCKS,
+};
 use namada::ledger::events::Event;
-use namada::ledger::gas::BlockGasMeter;
+use namada::ledger::gas::{BlockGasMeter, BLOCK_GAS_LIMIT};
 use namada::ledger::pos::namada_proof_of_stake::types::{
     ActiveValidator, ValidatorSetUpdate,
 };
 use namada::ledger::pos::namada_proof_of_stake::PosBase;
+use namada::ledger::queries::cache::QueryCache;
 use namada::ledger::storage::write_log::WriteLog;
 use namada::ledger::storage::{
     DBIter, Sha256Hasher, Storage, StorageHasher, DB,
 };
-use namada::ledger::{ibc, pos, protocol};
+use namada::ledger::{ibc, parameters, pos, protocol};
 use namada::proto::{self, Tx};
 use namada::types::address::{masp, masp_tx_key, Address};
 use namada::types::chain::ChainId;
 use namada::types::key::*;
-use namada::types::storage::{BlockHeight, Key, TxIndex};
-use namada::types::time::{DateTimeUtc, TimeZone, Utc};
+use namada::types::storage::{BlockHeight, Epoch, Key, TxIndex};
+use namada::types::time::{DateTimeUtc, DurationSecs, TimeZone, Utc};
 use namada::types::transaction::{
     hash_tx, process_tx, verify_decrypted_correctly, AffineCurve, DecryptedTx,
-    EllipticCurve, PairingEngine, TxType, WrapperTx,
+    EllipticCurve, Fee, PairingEngine, TxFeeInfo, TxType, WrapperTx,
 };
 use namada::types::{address, token};
-use namada::vm::wasm::{TxCache, VpCache};
+use namada::vm::wasm::{memory, TxCache, VpCache};
 use namada::vm::WasmCacheRwAccess;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
+use once_cell::unsync::OnceCell;
+use rust_decimal::Decimal;
 use thiserror::Error;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::Sender;
 
-use crate::config::{genesis, TendermintMode};
+use crate::config::{genesis, PruningPolicy, TendermintMode};
 #[cfg(feature = "abcipp")]
 use crate::facade::tendermint_proto::abci::response_verify_vote_extension::VerifyStatus;
 use crate::facade::tendermint_proto::abci::{
@@ -58,11 +77,14 @@ use crate::facade::tendermint_proto::abci::{
 };
 use crate::facade::tendermint_proto::crypto::public_key;
 use crate::facade::tower_abci::{request, response};
+use crate::node::ledger::block_stream::{self, BlockSummary};
+use crate::node::ledger::request_log::RequestLogSampler;
 use crate::node::ledger::shims::abcipp_shim_types::shim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::response::TxResult;
 use crate::node::ledger::{storage, tendermint_node};
 #[allow(unused_imports)]
 use crate::wallet::ValidatorData;
+use crate::wasm_loader::WasmDirs;
 use crate::{config, wallet};
 
 fn key_to_tendermint(
@@ -84,6 +106,12 @@ pub enum Error {
     RemoveDB(std::io::Error),
     #[error("chain ID mismatch: {0}")]
     ChainId(String),
+    #[error("The chain's genesis has already been initialized")]
+    ChainAlreadyInitialized,
+    #[error("The chain has not been initialized by `InitChain` yet")]
+    ChainNotInitialized,
+    #[error("Invalid genesis: {0}")]
+    InvalidGenesis(genesis::GenesisError),
     #[error("Error decoding a transaction from bytes: {0}")]
     TxDecoding(proto::Error),
     #[error("Error trying to apply a transaction: {0}")]
@@ -91,7 +119,7 @@ pub enum Error {
     #[error("Gas limit exceeding while applying transactions in block")]
     GasOverflow,
     #[error("{0}")]
-    Tendermint(tendermint_node::Error),
+    Tendermint(tendermint_node::TendermintError),
     #[error("Server error: {0}")]
     TowerServer(String),
     #[error("{0}")]
@@ -100,6 +128,38 @@ pub enum Error {
     BadProposal(u64, String),
     #[error("Error reading wasm: {0}")]
     ReadingWasm(#[from] eyre::Error),
+    #[error(
+        "The offered fee of {0} per unit of gas is below the minimum gas \
+         price of {1}"
+    )]
+    FeeTooLow(token::Amount, token::Amount),
+    #[error(
+        "Fees must be paid in the native token {0} or in one of the accepted \
+         fee tokens, but {1} was offered"
+    )]
+    FeeTokenNotAccepted(Address, Address),
+    #[error("The fee token {0} has a zero conversion rate")]
+    FeeTokenZeroRate(Address),
+    #[error("Txs from {0} are not permitted in the mempool")]
+    TxNotPermitted(Address),
+    #[error(
+        "Cannot export the state at height {0}, only the last committed \
+         height {1} can be exported"
+    )]
+    ExportHeight(BlockHeight, BlockHeight),
+    #[error("Error reading the state to export: {0}")]
+    ExportState(namada::ledger::storage_api::Error),
+    #[error("Block time {0} is before the last committed block's time {1}")]
+    NonMonotonicBlockTime(DateTimeUtc, DateTimeUtc),
+    #[error(
+        "Cannot replay the blocks from height {0}, the next height of the \
+         state is {1}"
+    )]
+    ReplayHeight(BlockHeight, BlockHeight),
+    #[error("The block to replay at height {0} is missing")]
+    ReplayMissing(BlockHeight),
+    #[error("The proposal of the replayed block at height {0} was rejected")]
+    ReplayRejected(BlockHeight),
 }
 
 impl From<Error> for TxResult {
@@ -123,6 +183,13 @@ pub enum ErrorCodes {
     InvalidOrder = 4,
     ExtraTxs = 5,
     Undecryptable = 6,
+    FeeTooLow = 7,
+    NotPermitted = 8,
+    BlockGasLimitExceeded = 9,
+    /// Rejected by a custom mempool check, see [`MempoolCheck`]
+    Custom = 10,
+    /// The chain has not been initialized by `InitChain` yet
+    ChainNotInitialized = 11,
 }
 
 impl From<ErrorCodes> for u32 {
@@ -137,6 +204,20 @@ impl From<ErrorCodes> for String {
     }
 }
 
+/// A custom check of the txs submitted to the mempool, which can be
+/// registered with [`Shell::register_mempool_check`] to extend the built-in
+/// validation, e.g. in a fork with additional rules. The custom checks run
+/// after the built-in ones have passed.
+pub trait MempoolCheck: Debug + Send {
+    /// The name of the check, reported as the codespace of the `CheckTx`
+    /// response of the txs that it rejects
+    fn name(&self) -> &str;
+
+    /// Check a tx submitted to the mempool, giving the reason of its
+    /// rejection, if any
+    fn check(&self, tx: &Tx) -> std::result::Result<(), String>;
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub fn reset(config: config::Ledger) -> Result<()> {
@@ -152,12 +233,52 @@ pub fn reset(config: config::Ledger) -> Result<()> {
     Ok(())
 }
 
+/// Build the thread pool on which the VPs of txs run. When the number of
+/// threads is not given, the pool is as large as the global Rayon pool. With
+/// a single thread, the VPs run serially. The number of threads bounds the
+/// number of VPs that run concurrently, and so the memory of their instances.
+fn build_vp_thread_pool(num_threads: Option<usize>) -> rayon::ThreadPool {
+    let num_threads = num_threads.unwrap_or_else(rayon::current_num_threads);
+    tracing::info!("Using {} threads for VPs.", num_threads);
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|i| format!("ledger-vp-worker-{}", i))
+        .build()
+        .expect("Building the VP thread pool should not fail")
+}
+
+/// Run the given function on the VP thread pool in the current span, which
+/// the pool's threads don't inherit, so that the spans of the VPs are nested
+/// in the span of the request that applies the tx
+fn install_in_current_span<R: Send>(
+    pool: &rayon::ThreadPool,
+    f: impl FnOnce() -> R + Send,
+) -> R {
+    let span = tracing::Span::current();
+    pool.install(|| span.in_scope(f))
+}
+
+/// The fees that the wrapper txs included in a block pay, keyed by the fee
+/// token and the fee payer
+pub(crate) type BlockFees = BTreeMap<(Address, Address), token::Amount>;
+
+/// Get the payer of the fee of a wrapper tx. The MASP pays the fees of the
+/// txs signed with its sentinel key, otherwise the payer is derived from the
+/// public key.
+pub(crate) fn wrapper_fee_payer(wrapper: &WrapperTx) -> Address {
+    if wrapper.pk != masp_tx_key().ref_to() {
+        wrapper.fee_payer()
+    } else {
+        masp()
+    }
+}
+
 #[derive(Debug)]
 #[allow(dead_code, clippy::large_enum_variant)]
 pub(super) enum ShellMode {
     Validator {
         data: ValidatorData,
-        broadcast_sender: UnboundedSender<Vec<u8>>,
+        broadcast_sender: Sender<Vec<u8>>,
     },
     Full,
     Seed,
@@ -204,8 +325,11 @@ where
     /// Path to the base directory with DB data and configs
     #[allow(dead_code)]
     base_dir: PathBuf,
-    /// Path to the WASM directory for files used in the genesis block.
-    wasm_dir: PathBuf,
+    /// The genesis that the node started from, read from its config file
+    /// once, on the first genesis query
+    genesis: OnceCell<genesis::Genesis>,
+    /// The WASM directories for files used in the genesis block.
+    wasm_dirs: WasmDirs,
     /// Information about the running shell instance
     #[allow(dead_code)]
     mode: ShellMode,
@@ -213,14 +337,60 @@ where
     vp_wasm_cache: VpCache<WasmCacheRwAccess>,
     /// Tx WASM compilation cache
     tx_wasm_cache: TxCache<WasmCacheRwAccess>,
+    /// The txs are applied on this pool, so that their VPs run on it rather
+    /// than on the global Rayon pool. Sized by the config `vp_threads`.
+    vp_thread_pool: rayon::ThreadPool,
     /// Taken from config `storage_read_past_height_limit`. When set, will
     /// limit the how many block heights in the past can the storage be
     /// queried for reading values.
     storage_read_past_height_limit: Option<u64>,
+    /// The cache of the results of the recent queries, sized by the config
+    /// `query_cache_size`. The queries are not cached when it's not set.
+    query_cache: Option<QueryCache>,
+    /// Taken from config `min_gas_price`. When set, wrapper txs offering a
+    /// lower fee per unit of gas are rejected from the mempool.
+    min_gas_price: Option<token::Amount>,
+    /// Taken from config `mempool_allowlist`. When not empty, only wrapper
+    /// txs whose fee payer is listed are admitted to the mempool.
+    mempool_allowlist: Vec<Address>,
+    /// Taken from config `mempool_denylist`. Wrapper txs whose fee payer is
+    /// listed are rejected from the mempool.
+    mempool_denylist: Vec<Address>,
+    /// The custom checks of the txs submitted to the mempool, in the order in
+    /// which they were registered
+    mempool_checks: Vec<Box<dyn MempoolCheck>>,
+    /// Taken from config `dev_epoch_num_of_blocks`. When set, epochs end
+    /// after this many blocks, regardless of the epoch duration parameters.
+    #[cfg(feature = "dev")]
+    dev_epoch_num_of_blocks: Option<u64>,
     /// Proposal execution tracking
     pub proposal_data: HashSet<u64>,
     /// Log of events emitted by `FinalizeBlock` ABCI calls.
     event_log: EventLog,
+    /// Log of the results of the txs applied in the last blocks. Its
+    /// retention is taken from config `tx_results_retention_blocks`.
+    tx_results: TxResultLog,
+    /// Log of the events emitted by the last blocks. Its retention is taken
+    /// from config `block_events_retention_blocks`.
+    block_event_log: BlockEventLog,
+    /// The IBC packets sent from this chain awaiting an acknowledgement. The
+    /// bound after which they're marked as timed-out locally is taken from
+    /// config `ibc_ack_timeout_secs` and their retention once timed-out from
+    /// config `ibc_timed_out_retention_secs`.
+    ibc_packets: IbcPacketTracker,
+    /// The txs admitted to the local mempool, for diagnostics
+    mempool: mempool::MempoolTracker,
+    /// The summaries of the committed blocks are published on this stream
+    block_stream: block_stream::Sender,
+    /// The events of the last finalized block, kept until it's committed
+    /// only while the block stream has subscribers
+    block_events: Vec<Event>,
+    /// Taken from config `pruning_policy`. The historical state of the blocks
+    /// outside of its retention is pruned after each commit.
+    pruning_policy: PruningPolicy,
+    /// Samples the logged ABCI requests, with the rate taken from config
+    /// `abci_request_log_sample_rate`
+    pub(super) request_log_sampler: RequestLogSampler,
 }
 
 impl<D, H> Shell<D, H>
@@ -232,9 +402,11 @@ where
     /// up the database with this data and tries to load the last state.
     pub fn new(
         config: config::Ledger,
-        wasm_dir: PathBuf,
-        broadcast_sender: UnboundedSender<Vec<u8>>,
+        wasm_dirs: WasmDirs,
+        broadcast_sender: Sender<Vec<u8>>,
+        block_stream: block_stream::Sender,
         db_cache: Option<&D::Cache>,
+        db_options: Option<&D::Options>,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
         native_token: Address,
@@ -245,13 +417,62 @@ where
         let mode = config.tendermint.tendermint_mode;
         let storage_read_past_height_limit =
             config.shell.storage_read_past_height_limit;
+        let query_cache = config
+            .shell
+            .query_cache_size
+            .and_then(NonZeroUsize::new)
+            .map(QueryCache::new);
+        let min_gas_price = config.shell.min_gas_price;
+        let pruning_policy = config.shell.pruning_policy;
+        let request_log_sampler = RequestLogSampler::new(
+            config
+                .shell
+                .abci_request_log_sample_rate
+                .unwrap_or_default(),
+        );
+        let tx_results = TxResultLog::new(
+            config
+                .shell
+                .tx_results_retention_blocks
+                .unwrap_or(DEFAULT_TX_RESULTS_RETENTION_BLOCKS),
+        );
+        let block_event_log = BlockEventLog::new(
+            config
+                .shell
+                .block_events_retention_blocks
+                .unwrap_or(DEFAULT_BLOCK_EVENTS_RETENTION_BLOCKS),
+        );
+        let ibc_packets = IbcPacketTracker::new(
+            DurationSecs(
+                config
+                    .shell
+                    .ibc_ack_timeout_secs
+                    .unwrap_or(DEFAULT_IBC_ACK_TIMEOUT_SECS),
+            ),
+            DurationSecs(
+                config
+                    .shell
+                    .ibc_timed_out_retention_secs
+                    .unwrap_or(DEFAULT_IBC_TIMED_OUT_RETENTION_SECS),
+            ),
+        );
+        let mempool_allowlist = config.shell.mempool_allowlist;
+        let mempool_denylist = config.shell.mempool_denylist;
+        let vp_thread_pool = build_vp_thread_pool(config.shell.vp_threads);
+        #[cfg(feature = "dev")]
+        let dev_epoch_num_of_blocks = config.shell.dev_epoch_num_of_blocks;
         if !Path::new(&base_dir).is_dir() {
             std::fs::create_dir(&base_dir)
                 .expect("Creating directory for Namada should not fail");
         }
         // load last state from storage
-        let mut storage =
-            Storage::open(db_path, chain_id.clone(), native_token, db_cache);
+        let mut storage = Storage::open(
+            db_path,
+            chain_id.clone(),
+            native_token,
+            db_cache,
+            db_options,
+        );
         storage
             .load_last_state()
             .map_err(|e| {
@@ -312,14 +533,15 @@ where
             TendermintMode::Seed => ShellMode::Seed,
         };
 
-        Self {
+        let mut shell = Self {
             chain_id,
             storage,
             gas_meter: BlockGasMeter::default(),
             write_log: WriteLog::default(),
             byzantine_validators: vec![],
             base_dir,
-            wasm_dir,
+            genesis: OnceCell::new(),
+            wasm_dirs,
             mode,
             vp_wasm_cache: VpCache::new(
                 vp_wasm_cache_dir,
@@ -329,11 +551,29 @@ where
                 tx_wasm_cache_dir,
                 tx_wasm_compilation_cache as usize,
             ),
+            vp_thread_pool,
             storage_read_past_height_limit,
+            query_cache,
+            min_gas_price,
+            mempool_allowlist,
+            mempool_denylist,
+            mempool_checks: vec![],
+            #[cfg(feature = "dev")]
+            dev_epoch_num_of_blocks,
             proposal_data: HashSet::new(),
             // TODO: config event log params
             event_log: EventLog::default(),
-        }
+            tx_results,
+            block_event_log,
+            ibc_packets,
+            mempool: mempool::MempoolTracker::default(),
+            block_stream,
+            block_events: vec![],
+            pruning_policy,
+            request_log_sampler,
+        };
+        shell.update_wasm_memory_max_pages();
+        shell
     }
 
     /// Return a reference to the [`EventLog`].
@@ -381,6 +621,60 @@ where
         response
     }
 
+    /// Check if the chain's genesis has been initialized, either by
+    /// `InitChain` or by restoring a state synced from a snapshot
+    pub fn is_chain_initialized(&self) -> bool {
+        let (initialized, _gas) = self
+            .storage
+            .has_key(&parameters::storage::get_genesis_initialized_key())
+            .expect("Reading the storage shouldn't fail");
+        initialized
+    }
+
+    /// Get the response to a request received before the chain has been
+    /// initialized, which may not read the chain's empty state. Only the
+    /// requests that Tendermint sends before `InitChain`, for its handshake
+    /// or to state sync the node, are served then, for which `None` is
+    /// returned. A `Query` or a `CheckTx` gets a response with the
+    /// [`ErrorCodes::ChainNotInitialized`] code, while the other requests
+    /// fail.
+    pub fn reject_before_init_chain(
+        &self,
+        req: &shim::Request,
+    ) -> Option<Result<shim::Response>> {
+        use shim::{Request, Response};
+
+        if matches!(
+            req,
+            Request::InitChain(_)
+                | Request::Info(_)
+                | Request::Echo(_)
+                | Request::Flush(_)
+                | Request::ListSnapshots(_)
+                | Request::OfferSnapshot(_)
+                | Request::LoadSnapshotChunk(_)
+                | Request::ApplySnapshotChunk(_)
+        ) || self.is_chain_initialized()
+        {
+            return None;
+        }
+        let code = ErrorCodes::ChainNotInitialized.into();
+        let info = Error::ChainNotInitialized.to_string();
+        Some(match req {
+            Request::Query(_) => Ok(Response::Query(response::Query {
+                code,
+                info,
+                ..Default::default()
+            })),
+            Request::CheckTx(_) => Ok(Response::CheckTx(response::CheckTx {
+                code,
+                log: info,
+                ..Default::default()
+            })),
+            _ => Err(Error::ChainNotInitialized),
+        })
+    }
+
     /// Read the value for a storage key dropping any error
     pub fn read_storage_key<T>(&self, key: &Key) -> Option<T>
     where
@@ -410,8 +704,12 @@ where
         }
     }
 
-    /// Apply PoS slashes from the evidence
-    fn slash(&mut self) {
+    /// Apply PoS slashes from the evidence, at the rates set in the PoS
+    /// parameters, and jail the slashed validators. Evidence for a validator
+    /// that is no longer bonded or that has already been slashed for the same
+    /// misbehavior is ignored. Returns an event for each applied slash.
+    fn slash(&mut self) -> Vec<Event> {
+        let mut events = vec![];
         if !self.byzantine_validators.is_empty() {
             let byzantine_validators =
                 mem::take(&mut self.byzantine_validators);
@@ -498,25 +796,71 @@ where
                         continue;
                     }
                 };
+                let current_stake = self
+                    .storage
+                    .read_validator_deltas(&validator)
+                    .and_then(|deltas| deltas.get(current_epoch))
+                    .unwrap_or_default();
+                if current_stake <= 0 {
+                    tracing::info!(
+                        "Skipping evidence for validator {} that is no longer \
+                         bonded",
+                        validator
+                    );
+                    continue;
+                }
+                let slash = pos::types::Slash {
+                    epoch: evidence_epoch,
+                    block_height: evidence_height,
+                    rate: slash_type.get_slash_rate(&pos_params),
+                    r#type: slash_type,
+                };
+                let already_slashed =
+                    self.storage.read_validator_slashes(&validator).iter().any(
+                        |applied| {
+                            applied.epoch == slash.epoch
+                                && applied.block_height == slash.block_height
+                                && applied.r#type == slash.r#type
+                        },
+                    );
+                if already_slashed {
+                    tracing::info!(
+                        "Skipping evidence for validator {} that has already \
+                         been slashed for it",
+                        validator
+                    );
+                    continue;
+                }
                 tracing::info!(
                     "Slashing {} for {} in epoch {}, block height {}",
                     validator,
-                    slash_type,
+                    slash.r#type,
                     evidence_epoch,
                     evidence_height
                 );
-                if let Err(err) = self.storage.slash(
+                match self.storage.slash(
                     &pos_params,
                     current_epoch,
                     evidence_epoch,
                     evidence_height,
-                    slash_type,
+                    slash.r#type.clone(),
                     &validator,
                 ) {
-                    tracing::error!("Error in slashing: {}", err);
+                    Ok(()) => {
+                        self.storage.jail_validator(current_epoch, &validator);
+                        events.push(Event::new_slash_event(
+                            &validator,
+                            &slash,
+                            self.storage.block.height.0,
+                        ))
+                    }
+                    Err(err) => {
+                        tracing::error!("Error in slashing: {}", err)
+                    }
                 }
             }
         }
+        events
     }
 
     /// INVARIANT: This method must be stateless.
@@ -547,6 +891,8 @@ where
         self.write_log
             .commit_block(&mut self.storage)
             .expect("Expected committing block write log success");
+        // the header is cleared once the block is committed
+        let block_time = self.storage.header.as_ref().map(|header| header.time);
         // store the block's data in DB
         self.storage.commit().unwrap_or_else(|e| {
             tracing::error!(
@@ -555,33 +901,344 @@ where
             )
         });
 
+        self.prune_storage();
+
         let root = self.storage.merkle_root();
         tracing::info!(
-            "Committed block hash: {}, height: {}",
+            "Committed block hash: {}, algorithm: {}, height: {}",
             root,
+            self.storage.hash_algorithm(),
             self.storage.last_height,
         );
+        if self.block_stream.receiver_count() > 0 {
+            let summary = BlockSummary::new(
+                self.storage.last_height,
+                &self.storage.block.hash,
+                block_time,
+                &mem::take(&mut self.block_events),
+            );
+            // Publishing only fails when all the subscribers are gone
+            let _ = self.block_stream.send(summary);
+        }
         response.data = root.0;
         response
     }
 
+    /// Prune the historical state of the blocks outside of the retention of
+    /// the configured pruning policy
+    fn prune_storage(&mut self) {
+        let oldest_retained_height = match self.pruning_policy {
+            PruningPolicy::KeepAll => return,
+            PruningPolicy::KeepRecent(num_epochs) => {
+                let oldest_epoch =
+                    match self.storage.last_epoch.0.checked_sub(num_epochs) {
+                        Some(epoch) => Epoch(epoch),
+                        None => return,
+                    };
+                // The first heights of the epochs that ended long ago are not
+                // known, in which case nothing is pruned
+                match self
+                    .storage
+                    .block
+                    .pred_epochs
+                    .get_first_block_height(oldest_epoch)
+                {
+                    Some(height) => height,
+                    None => return,
+                }
+            }
+            PruningPolicy::KeepNothingButLatest => self.storage.last_height,
+        };
+        if let Err(err) = self.storage.prune(oldest_retained_height) {
+            tracing::error!(
+                "Failed to prune the storage below the height {}: {}",
+                oldest_retained_height,
+                err
+            );
+        }
+    }
+
     /// Validate a transaction request. On success, the transaction will
     /// included in the mempool and propagated to peers, otherwise it will be
     /// rejected.
+    ///
+    /// The response's `info` carries the hex-encoded [`tx_results::TxResult`],
+    /// in the same shape as the result of a tx applied in a block. The
+    /// mempool doesn't execute the tx, so the result has no storage
+    /// changes, verifiers or events.
     pub fn mempool_validate(
         &self,
         tx_bytes: &[u8],
         r#_type: MempoolTxType,
     ) -> response::CheckTx {
         let mut response = response::CheckTx::default();
-        match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
-            Ok(_) => response.log = String::from("Mempool validation passed"),
+        let result = self.mempool_tx_result(tx_bytes, &mut response);
+        response.code = result.code;
+        response.log = result.info.clone();
+        response.info = HEXLOWER.encode(
+            &result
+                .try_to_vec()
+                .expect("Serializing the tx result shouldn't fail"),
+        );
+        response
+    }
+
+    /// Get the result of the mempool validation of a tx. The gas limit, the
+    /// fee info and the codespace of a rejection by a custom check are set on
+    /// the given response.
+    fn mempool_tx_result(
+        &self,
+        tx_bytes: &[u8],
+        response: &mut response::CheckTx,
+    ) -> tx_results::TxResult {
+        let tx = match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
+            Ok(tx) => tx,
             Err(msg) => {
-                response.code = 1;
-                response.log = msg.to_string();
+                return tx_results::TxResult::check_tx(1, msg.to_string())
+            }
+        };
+        let wrapper = match process_tx(tx.clone(), &self.chain_id) {
+            Ok(TxType::Wrapper(wrapper)) => Some(wrapper),
+            _ => None,
+        };
+
+        // Check the operator's lists before any further validation
+        if let Some(Err(err)) = wrapper
+            .as_ref()
+            .map(|wrapper| self.check_mempool_lists(wrapper))
+        {
+            return tx_results::TxResult::check_tx(
+                ErrorCodes::NotPermitted.into(),
+                err.to_string(),
+            );
+        }
+
+        // Report the gas limit and fee so that clients can display them
+        let fee_info = self.tx_fee_info(wrapper.as_ref());
+        response.gas_wanted =
+            i64::try_from(fee_info.gas_limit).unwrap_or(i64::MAX);
+        response.data = fee_info
+            .try_to_vec()
+            .expect("Serializing the fee info shouldn't fail");
+
+        match wrapper
+            .map_or(Ok(()), |wrapper| self.check_min_gas_price(&wrapper))
+        {
+            Ok(()) => {}
+            Err(err @ Error::FeeTooLow(..)) => {
+                return tx_results::TxResult::check_tx(
+                    ErrorCodes::FeeTooLow.into(),
+                    err.to_string(),
+                );
+            }
+            Err(msg) => {
+                return tx_results::TxResult::check_tx(1, msg.to_string())
+            }
+        }
+
+        // The custom checks run last, the first rejection is reported
+        for check in &self.mempool_checks {
+            if let Err(reason) = check.check(&tx) {
+                response.codespace = check.name().to_owned();
+                return tx_results::TxResult::check_tx(
+                    ErrorCodes::Custom.into(),
+                    reason,
+                );
+            }
+        }
+        tx_results::TxResult::check_tx(
+            ErrorCodes::Ok.into(),
+            "Mempool validation passed",
+        )
+    }
+
+    /// Register a custom check of the txs submitted to the mempool. The checks
+    /// run in the order in which they were registered.
+    pub fn register_mempool_check(
+        &mut self,
+        check: impl MempoolCheck + 'static,
+    ) {
+        self.mempool_checks.push(Box::new(check));
+    }
+
+    /// Check that the fee payer of a wrapper tx is permitted by the
+    /// configured mempool lists. A fee payer that is in the denylist is
+    /// rejected, even if it's also in the allowlist. An empty allowlist
+    /// permits any fee payer.
+    fn check_mempool_lists(&self, wrapper: &WrapperTx) -> Result<()> {
+        let fee_payer = wrapper.fee_payer();
+        if self.mempool_denylist.contains(&fee_payer)
+            || (!self.mempool_allowlist.is_empty()
+                && !self.mempool_allowlist.contains(&fee_payer))
+        {
+            return Err(Error::TxNotPermitted(fee_payer));
+        }
+        Ok(())
+    }
+
+    /// Get the gas limit and fee of a tx. Txs other than wrappers carry no
+    /// fee, for which a zero gas limit and fee in the native token are
+    /// reported.
+    fn tx_fee_info(&self, wrapper: Option<&WrapperTx>) -> TxFeeInfo {
+        match wrapper {
+            Some(wrapper) => TxFeeInfo {
+                gas_limit: u64::from(&wrapper.gas_limit),
+                fee: wrapper.fee.clone(),
+            },
+            None => TxFeeInfo {
+                gas_limit: 0,
+                fee: Fee {
+                    amount: token::Amount::default(),
+                    token: self.storage.native_token.clone(),
+                },
+            },
+        }
+    }
+
+    /// Get the conversion rate of a fee token to the native token. The fees
+    /// may only be paid in the native token or in one of the fee tokens of
+    /// the protocol parameters, the other tokens and the fee tokens with a
+    /// zero rate are rejected, as there is no way to price them.
+    pub(crate) fn fee_token_rate(&self, token: &Address) -> Result<Decimal> {
+        if *token == self.storage.native_token {
+            return Ok(Decimal::ONE);
+        }
+        let (fee_tokens, _gas) =
+            parameters::read_fee_tokens_parameter(&self.storage)
+                .expect("Couldn't read the fee tokens parameter");
+        let rate = fee_tokens.get(token).copied().ok_or_else(|| {
+            Error::FeeTokenNotAccepted(
+                self.storage.native_token.clone(),
+                token.clone(),
+            )
+        })?;
+        if rate.is_zero() {
+            return Err(Error::FeeTokenZeroRate(token.clone()));
+        }
+        Ok(rate)
+    }
+
+    /// Get the total fee that the payer of a wrapper tx pays in the wrapper's
+    /// fee token with the wrapper included in a block, along with the token
+    /// and payer, given the fees of the wrappers already included in the
+    /// block. `None` is returned when the payer's balance doesn't cover the
+    /// total fee.
+    pub(crate) fn wrapper_block_fee(
+        &self,
+        wrapper: &WrapperTx,
+        block_fees: &BlockFees,
+    ) -> Option<((Address, Address), token::Amount)> {
+        let token_and_payer =
+            (wrapper.fee.token.clone(), wrapper_fee_payer(wrapper));
+        let total = block_fees
+            .get(&token_and_payer)
+            .copied()
+            .unwrap_or_default()
+            .checked_add(&wrapper.fee.amount)?;
+        let (token, payer) = &token_and_payer;
+        (total <= self.get_balance(token, payer))
+            .then_some((token_and_payer, total))
+    }
+
+    /// Get the fee of a wrapper tx converted to the native token with the
+    /// rate of its fee token. The fee must be paid in an accepted fee token.
+    pub(crate) fn native_fee(&self, wrapper: &WrapperTx) -> Result<u64> {
+        let rate = self.fee_token_rate(&wrapper.fee.token)?;
+        let fee = u64::from(wrapper.fee.amount);
+        if wrapper.fee.token == self.storage.native_token {
+            return Ok(fee);
+        }
+        // A fee worth more than the maximum amount saturates
+        Ok(Decimal::from(fee)
+            .checked_mul(rate)
+            .map_or(Some(u64::MAX), |native_fee| native_fee.to_u64())
+            .unwrap_or(u64::MAX))
+    }
+
+    /// Check that a wrapper tx pays its fee in an accepted fee token and that
+    /// it offers at least the configured minimum gas price. The fees paid in
+    /// a token other than the native token are converted to the native token
+    /// with the rate of the fee token.
+    fn check_min_gas_price(&self, wrapper: &WrapperTx) -> Result<()> {
+        let native_fee = self.native_fee(wrapper)?;
+        let min_gas_price = match self.min_gas_price {
+            Some(min_gas_price) => min_gas_price,
+            None => return Ok(()),
+        };
+        // A zero gas limit cannot pay for anything, so it's priced at zero
+        let gas_price: token::Amount = native_fee
+            .checked_div(u64::from(&wrapper.gas_limit))
+            .unwrap_or_default()
+            .into();
+        if gas_price < min_gas_price {
+            return Err(Error::FeeTooLow(gas_price, min_gas_price));
+        }
+        Ok(())
+    }
+
+    /// Read the block gas limit parameter, which the sum of the gas limits
+    /// of a block's wrapper txs may not exceed. A sum equal to the limit
+    /// fits. Until the parameter is initialized at genesis, the limit is the
+    /// protocol's [`BLOCK_GAS_LIMIT`].
+    pub(crate) fn block_gas_limit(&self) -> u64 {
+        match parameters::read_block_gas_limit_parameter(&self.storage) {
+            Ok((block_gas_limit, _gas)) => block_gas_limit,
+            Err(parameters::ReadError::ParametersMissing) => BLOCK_GAS_LIMIT,
+            Err(err) => {
+                panic!("Couldn't read the block gas limit parameter: {}", err)
+            }
+        }
+    }
+
+    /// Read the max verifiers parameter. When it's set, txs triggering more
+    /// verifiers are rejected before their VPs run.
+    pub(crate) fn max_verifiers(&self) -> Option<usize> {
+        let (max_verifiers, _gas) =
+            parameters::read_max_verifiers_parameter(&self.storage)
+                .expect("Couldn't read the max verifiers parameter");
+        max_verifiers.map(|max| max as usize)
+    }
+
+    /// Read the maximum memory of VP WASM execution parameter. Until it's
+    /// initialized at genesis, it's the default
+    /// [`memory::VP_MEMORY_MAX_PAGES`].
+    fn vp_wasm_memory_max_pages(&self) -> u32 {
+        match parameters::read_vp_wasm_memory_max_pages_parameter(&self.storage)
+        {
+            Ok((max_pages, _gas)) => max_pages,
+            Err(parameters::ReadError::ParametersMissing) => {
+                memory::VP_MEMORY_MAX_PAGES
+            }
+            Err(err) => {
+                panic!("Couldn't read the VP WASM memory parameter: {}", err)
+            }
+        }
+    }
+
+    /// Read the maximum memory of tx WASM execution parameter. Until it's
+    /// initialized at genesis, it's the default
+    /// [`memory::TX_MEMORY_MAX_PAGES`].
+    fn tx_wasm_memory_max_pages(&self) -> u32 {
+        match parameters::read_tx_wasm_memory_max_pages_parameter(&self.storage)
+        {
+            Ok((max_pages, _gas)) => max_pages,
+            Err(parameters::ReadError::ParametersMissing) => {
+                memory::TX_MEMORY_MAX_PAGES
+            }
+            Err(err) => {
+                panic!("Couldn't read the tx WASM memory parameter: {}", err)
             }
         }
-        response
+    }
+
+    /// Limit the memory of the WASM execution to the current parameters. It
+    /// has to be called whenever the parameters may have changed, so that
+    /// every node runs the txs and VPs of a block with the same limits.
+    pub(crate) fn update_wasm_memory_max_pages(&mut self) {
+        let vp_max_pages = self.vp_wasm_memory_max_pages();
+        let tx_max_pages = self.tx_wasm_memory_max_pages();
+        self.vp_wasm_cache.set_memory_max_pages(vp_max_pages);
+        self.tx_wasm_cache.set_memory_max_pages(tx_max_pages);
     }
 
     #[allow(dead_code)]
@@ -592,21 +1249,25 @@ where
         let mut write_log = WriteLog::default();
         let mut vp_wasm_cache = self.vp_wasm_cache.read_only();
         let mut tx_wasm_cache = self.tx_wasm_cache.read_only();
+        let max_verifiers = self.max_verifiers();
         match Tx::try_from(tx_bytes) {
             Ok(tx) => {
                 let tx = TxType::Decrypted(DecryptedTx::Decrypted(tx));
-                match protocol::apply_tx(
-                    tx,
-                    tx_bytes.len(),
-                    TxIndex::default(),
-                    &mut gas_meter,
-                    &mut write_log,
-                    &self.storage,
-                    &mut vp_wasm_cache,
-                    &mut tx_wasm_cache,
-                )
-                .map_err(Error::TxApply)
-                {
+                let result =
+                    install_in_current_span(&self.vp_thread_pool, || {
+                        protocol::apply_tx(
+                            tx,
+                            tx_bytes.len(),
+                            TxIndex::default(),
+                            &mut gas_meter,
+                            &mut write_log,
+                            &self.storage,
+                            &mut vp_wasm_cache,
+                            &mut tx_wasm_cache,
+                            max_verifiers,
+                        )
+                    });
+                match result.map_err(Error::TxApply) {
                     Ok(result) => response.info = result.to_string(),
                     Err(error) => {
                         response.code = 1;
@@ -666,6 +1327,7 @@ mod test_utils {
     use std::ops::{Deref, DerefMut};
     use std::path::PathBuf;
 
+    use data_encoding::HEXUPPER;
     use namada::ledger::storage::mockdb::MockDB;
     use namada::ledger::storage::{BlockStateWrite, MerkleTree, Sha256Hasher};
     use namada::types::address::EstablishedAddressGen;
@@ -675,13 +1337,14 @@ mod test_utils {
     use namada::types::storage::{BlockHash, BlockResults, Epoch, Header};
     use namada::types::transaction::Fee;
     use tempfile::tempdir;
-    use tokio::sync::mpsc::UnboundedReceiver;
+    use tokio::sync::mpsc::Receiver;
 
     use super::*;
     use crate::facade::tendermint_proto::abci::{
         RequestInitChain, RequestProcessProposal,
     };
     use crate::facade::tendermint_proto::google::protobuf::Timestamp;
+    use crate::node::ledger::broadcaster;
     use crate::node::ledger::shims::abcipp_shim_types::shim::request::{
         FinalizeBlock, ProcessedTx,
     };
@@ -748,8 +1411,9 @@ mod test_utils {
     impl TestShell {
         /// Returns a new shell paired with a broadcast receiver, which will
         /// receives any protocol txs sent by the shell.
-        pub fn new() -> (Self, UnboundedReceiver<Vec<u8>>) {
-            let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        pub fn new() -> (Self, Receiver<Vec<u8>>) {
+            let (sender, receiver) =
+                broadcaster::channel(broadcaster::DEFAULT_QUEUE_CAPACITY);
             let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
             let vp_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
             let tx_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
@@ -761,8 +1425,10 @@ mod test_utils {
                             Default::default(),
                             TendermintMode::Validator,
                         ),
-                        top_level_directory().join("wasm"),
+                        WasmDirs::new(top_level_directory().join("wasm")),
                         sender,
+                        block_stream::channel(),
+                        None,
                         None,
                         vp_wasm_compilation_cache,
                         tx_wasm_compilation_cache,
@@ -829,7 +1495,7 @@ mod test_utils {
     /// Start a new test shell and initialize it. Returns the shell paired with
     /// a broadcast receiver, which will receives any protocol txs sent by the
     /// shell.
-    pub(super) fn setup() -> (TestShell, UnboundedReceiver<Vec<u8>>) {
+    pub(super) fn setup() -> (TestShell, Receiver<Vec<u8>>) {
         let (mut test, receiver) = TestShell::new();
         test.init_chain(RequestInitChain {
             time: Some(Timestamp {
@@ -842,6 +1508,32 @@ mod test_utils {
         (test, receiver)
     }
 
+    /// Get the Tendermint raw hash of the genesis validator's consensus key
+    pub(super) fn genesis_proposer_address(shell: &TestShell) -> Vec<u8> {
+        let (current_epoch, _) = shell.storage.get_current_epoch();
+        let validator = shell
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .expect("Test failed")
+            .active
+            .iter()
+            .next()
+            .expect("Test failed")
+            .address
+            .clone();
+        let consensus_key = shell
+            .storage
+            .read_validator_consensus_key(&validator)
+            .expect("Test failed")
+            .get(current_epoch)
+            .expect("Test failed")
+            .clone();
+        HEXUPPER
+            .decode(tm_consensus_key_raw_hash(&consensus_key).as_bytes())
+            .expect("Test failed")
+    }
+
     /// This is just to be used in testing. It is not
     /// a meaningful default.
     impl Default for FinalizeBlock {
@@ -855,6 +1547,7 @@ mod test_utils {
                 },
                 byzantine_validators: vec![],
                 txs: vec![],
+                proposer_address: vec![],
             }
         }
     }
@@ -865,7 +1558,8 @@ mod test_utils {
     fn test_tx_queue_persistence() {
         let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
         // we have to use RocksDB for this test
-        let (sender, _) = tokio::sync::mpsc::unbounded_channel();
+        let (sender, _receiver) =
+            broadcaster::channel(broadcaster::DEFAULT_QUEUE_CAPACITY);
         let vp_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
         let tx_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
         let native_token = address::nam();
@@ -875,8 +1569,10 @@ mod test_utils {
                 Default::default(),
                 TendermintMode::Validator,
             ),
-            top_level_directory().join("wasm"),
+            WasmDirs::new(top_level_directory().join("wasm")),
             sender.clone(),
+            block_stream::channel(),
+            None,
             None,
             vp_wasm_compilation_cache,
             tx_wasm_compilation_cache,
@@ -935,8 +1631,10 @@ mod test_utils {
                 Default::default(),
                 TendermintMode::Validator,
             ),
-            top_level_directory().join("wasm"),
+            WasmDirs::new(top_level_directory().join("wasm")),
             sender,
+            block_stream::channel(),
+            None,
             None,
             vp_wasm_compilation_cache,
             tx_wasm_compilation_cache,
@@ -944,4 +1642,492 @@ mod test_utils {
         );
         assert!(!shell.storage.tx_queue.is_empty());
     }
+
+    /// Test that the historical state outside of the retention of the
+    /// pruning policy is pruned, while the last committed block is retained.
+    #[test]
+    fn test_pruning_policy() {
+        let (mut shell, _) = TestShell::new();
+        // The epochs 0, 1 and 2 start at the heights 0, 10 and 20
+        for height in [10, 20] {
+            shell
+                .storage
+                .block
+                .pred_epochs
+                .new_epoch(BlockHeight(height), u64::MAX);
+        }
+        shell.storage.last_epoch = Epoch(2);
+        shell.storage.last_height = BlockHeight(25);
+
+        let prune = |shell: &mut TestShell, policy| {
+            shell.pruning_policy = policy;
+            shell.prune_storage();
+            shell.storage.oldest_retained_height
+        };
+        assert_eq!(prune(&mut shell, PruningPolicy::KeepAll), BlockHeight(0));
+        // There are fewer epochs than the retained ones
+        assert_eq!(
+            prune(&mut shell, PruningPolicy::KeepRecent(3)),
+            BlockHeight(0)
+        );
+        assert_eq!(
+            prune(&mut shell, PruningPolicy::KeepRecent(1)),
+            BlockHeight(10)
+        );
+        // The pruned state cannot be retained again
+        assert_eq!(
+            prune(&mut shell, PruningPolicy::KeepRecent(2)),
+            BlockHeight(10)
+        );
+        assert_eq!(
+            prune(&mut shell, PruningPolicy::KeepNothingButLatest),
+            BlockHeight(25)
+        );
+    }
+
+    /// Test that a `Query` received before `InitChain` gets a response with
+    /// the not-initialized code rather than reading the empty state, while
+    /// `Info` is served for Tendermint's handshake.
+    #[test]
+    fn test_reject_before_init_chain() {
+        let (mut shell, _) = TestShell::new();
+        let query = shim::Request::Query(request::Query::default());
+        match shell.reject_before_init_chain(&query) {
+            Some(Ok(shim::Response::Query(response))) => {
+                assert_eq!(
+                    response.code,
+                    u32::from(ErrorCodes::ChainNotInitialized)
+                );
+                assert_eq!(
+                    response.info,
+                    Error::ChainNotInitialized.to_string()
+                );
+            }
+            _ => panic!("Test failed"),
+        }
+        let info = shim::Request::Info(Default::default());
+        assert!(shell.reject_before_init_chain(&info).is_none());
+        let commit = shim::Request::Commit(Default::default());
+        assert!(matches!(
+            shell.reject_before_init_chain(&commit),
+            Some(Err(Error::ChainNotInitialized))
+        ));
+
+        shell.init_chain(RequestInitChain {
+            time: Some(Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        });
+        assert!(shell.reject_before_init_chain(&query).is_none());
+        assert!(shell.reject_before_init_chain(&commit).is_none());
+    }
+}
+
+#[cfg(test)]
+mod test_mempool_validate {
+    use std::collections::BTreeMap;
+
+    use namada::types::storage::Epoch;
+    use namada::types::transaction::{Fee, GasLimit};
+
+    use super::test_utils::*;
+    use super::*;
+
+    /// Make a signed wrapper tx with the given fee amount and token and a
+    /// gas limit of `gas_limit`
+    fn signed_wrapper(
+        keypair: &common::SecretKey,
+        amount: u64,
+        token: Address,
+        gas_limit: u64,
+    ) -> Vec<u8> {
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        WrapperTx::new(
+            Fee {
+                amount: amount.into(),
+                token,
+            },
+            keypair,
+            Epoch(0),
+            GasLimit::from(gas_limit),
+            tx,
+            Default::default(),
+        )
+        .sign(keypair, &ChainId::default())
+        .expect("Test failed")
+        .to_bytes()
+    }
+
+    /// Test that a wrapper tx offering less than the minimum gas price is
+    /// rejected with the `FeeTooLow` code, while wrappers offering exactly
+    /// or more than the minimum are accepted.
+    #[test]
+    fn test_min_gas_price() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let gas_limit = 1_000_000;
+        shell.min_gas_price = Some(token::Amount::from(2));
+        let native_token = shell.storage.native_token.clone();
+
+        let below = signed_wrapper(
+            &keypair,
+            2 * gas_limit - 1,
+            native_token.clone(),
+            gas_limit,
+        );
+        let response =
+            shell.mempool_validate(&below, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::FeeTooLow));
+        assert!(
+            response.log.contains(&token::Amount::from(2).to_string()),
+            "The log should contain the required minimum: {}",
+            response.log
+        );
+
+        let at = signed_wrapper(
+            &keypair,
+            2 * gas_limit,
+            native_token.clone(),
+            gas_limit,
+        );
+        let response =
+            shell.mempool_validate(&at, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+
+        let above =
+            signed_wrapper(&keypair, 3 * gas_limit, native_token, gas_limit);
+        let response =
+            shell.mempool_validate(&above, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+    }
+
+    /// Test that while a minimum gas price is set, a wrapper tx paying its
+    /// fee in a token other than the native token is rejected, and that it's
+    /// accepted when no minimum is set.
+    #[test]
+    fn test_min_gas_price_non_native_token() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let gas_limit = 1_000_000;
+        let tx = signed_wrapper(&keypair, gas_limit, address::btc(), gas_limit);
+
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+
+        shell.min_gas_price = Some(token::Amount::from(1));
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::InvalidTx));
+    }
+
+    /// Test that a fee paid in an accepted fee token is converted to the
+    /// native token to be checked against the minimum gas price, while the
+    /// tokens that aren't accepted or that have a zero rate are rejected.
+    #[test]
+    fn test_min_gas_price_fee_tokens() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let gas_limit = 1_000_000;
+        shell.min_gas_price = Some(token::Amount::from(2));
+        // One BTC is worth a half of the native token
+        parameters::update_fee_tokens_parameter(
+            &mut shell.storage,
+            &BTreeMap::from([
+                (address::btc(), Decimal::new(5, 1)),
+                (address::dot(), Decimal::ZERO),
+            ]),
+        )
+        .expect("Test failed");
+
+        let at =
+            signed_wrapper(&keypair, 4 * gas_limit, address::btc(), gas_limit);
+        let response =
+            shell.mempool_validate(&at, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+
+        let below = signed_wrapper(
+            &keypair,
+            4 * gas_limit - 1,
+            address::btc(),
+            gas_limit,
+        );
+        let response =
+            shell.mempool_validate(&below, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::FeeTooLow));
+
+        // A token that is not accepted
+        let not_accepted =
+            signed_wrapper(&keypair, 4 * gas_limit, address::eth(), gas_limit);
+        let response = shell
+            .mempool_validate(&not_accepted, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::InvalidTx));
+        assert!(
+            response.log.contains("accepted fee tokens"),
+            "{}",
+            response.log
+        );
+
+        // A token with a zero rate
+        let zero_rate =
+            signed_wrapper(&keypair, 4 * gas_limit, address::dot(), gas_limit);
+        let response =
+            shell.mempool_validate(&zero_rate, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::InvalidTx));
+        assert!(
+            response.log.contains("zero conversion rate"),
+            "{}",
+            response.log
+        );
+    }
+
+    /// Test that the mempool lists permit allow-listed fee payers and reject
+    /// deny-listed and unlisted ones, with the denylist taking precedence.
+    #[test]
+    fn test_mempool_lists() {
+        let (mut shell, _) = setup();
+        let native_token = shell.storage.native_token.clone();
+        let allowed = gen_keypair();
+        let denied = gen_keypair();
+        let unlisted = gen_keypair();
+        let wrapper = |keypair: &common::SecretKey| {
+            signed_wrapper(keypair, 100, native_token.clone(), 1_000_000)
+        };
+
+        // Empty lists permit any fee payer
+        for keypair in [&allowed, &denied, &unlisted] {
+            let response = shell.mempool_validate(
+                &wrapper(keypair),
+                MempoolTxType::NewTransaction,
+            );
+            assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+        }
+
+        // A deny-listed fee payer is rejected, even if also allow-listed
+        let allowed_addr = Address::from(&allowed.ref_to());
+        let denied_addr = Address::from(&denied.ref_to());
+        shell.mempool_allowlist = vec![allowed_addr, denied_addr.clone()];
+        shell.mempool_denylist = vec![denied_addr];
+        let response = shell.mempool_validate(
+            &wrapper(&allowed),
+            MempoolTxType::NewTransaction,
+        );
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+        let response = shell
+            .mempool_validate(&wrapper(&denied), MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::NotPermitted));
+
+        // An unlisted fee payer is rejected when there is an allowlist
+        let response = shell.mempool_validate(
+            &wrapper(&unlisted),
+            MempoolTxType::NewTransaction,
+        );
+        assert_eq!(response.code, u32::from(ErrorCodes::NotPermitted));
+
+        // An unlisted fee payer is permitted when there's only a denylist
+        shell.mempool_allowlist = vec![];
+        let response = shell.mempool_validate(
+            &wrapper(&unlisted),
+            MempoolTxType::NewTransaction,
+        );
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+    }
+
+    /// A custom mempool check rejecting the txs with the given code
+    #[derive(Debug)]
+    struct RejectCode(Vec<u8>);
+
+    impl MempoolCheck for RejectCode {
+        fn name(&self) -> &str {
+            "reject-code"
+        }
+
+        fn check(&self, tx: &Tx) -> std::result::Result<(), String> {
+            if tx.code == self.0 {
+                Err("The tx code is not allowed on this node".to_owned())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Test that the rejection reason of a custom mempool check reaches the
+    /// `CheckTx` response, while the txs that it accepts pass.
+    #[test]
+    fn test_custom_mempool_check() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let native_token = shell.storage.native_token.clone();
+        let tx = signed_wrapper(&keypair, 100, native_token, 1_000_000);
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+
+        // The wrapper tx carries no code, it's rejected
+        shell.register_mempool_check(RejectCode(vec![]));
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Custom));
+        assert_eq!(response.codespace, "reject-code");
+        assert_eq!(response.log, "The tx code is not allowed on this node");
+
+        // Another code is accepted
+        shell.mempool_checks = vec![Box::new(RejectCode(vec![1]))];
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+    }
+
+    /// Test that the response of an accepted wrapper tx carries its gas limit
+    /// and fee.
+    #[test]
+    fn test_fee_info_in_response() {
+        let (shell, _) = setup();
+        let keypair = gen_keypair();
+        let native_token = shell.storage.native_token.clone();
+        let tx = signed_wrapper(&keypair, 100, native_token.clone(), 2_000_000);
+
+        let response =
+            shell.mempool_validate(&tx, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+        assert_eq!(response.gas_wanted, 2_000_000);
+        let fee_info =
+            TxFeeInfo::try_from_slice(&response.data).expect("Test failed");
+        assert_eq!(
+            fee_info,
+            TxFeeInfo {
+                gas_limit: 2_000_000,
+                fee: Fee {
+                    amount: 100.into(),
+                    token: native_token,
+                },
+            }
+        );
+    }
+
+    /// Test that a tx without a fee reports a zero gas limit and fee.
+    #[test]
+    fn test_fee_info_in_response_without_fee() {
+        let (shell, _) = setup();
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+
+        let response = shell
+            .mempool_validate(&tx.to_bytes(), MempoolTxType::NewTransaction);
+        assert_eq!(response.gas_wanted, 0);
+        let fee_info =
+            TxFeeInfo::try_from_slice(&response.data).expect("Test failed");
+        assert_eq!(
+            fee_info,
+            TxFeeInfo {
+                gas_limit: 0,
+                fee: Fee {
+                    amount: token::Amount::default(),
+                    token: shell.storage.native_token.clone(),
+                },
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_vp_thread_pool {
+    use std::collections::BTreeSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    use super::test_utils::*;
+    use super::*;
+
+    /// Get the names of the threads that run a parallel iterator started on
+    /// the given pool, the same way as the VPs are run.
+    fn par_iter_thread_names(pool: &rayon::ThreadPool) -> BTreeSet<String> {
+        pool.install(|| {
+            (0..64)
+                .into_par_iter()
+                .map(|_| {
+                    std::thread::current().name().unwrap_or_default().to_owned()
+                })
+                .collect()
+        })
+    }
+
+    /// Get the maximum number of the closures of a parallel iterator started
+    /// on the given pool that were live at once, the same way as the VPs are
+    /// run.
+    fn par_iter_max_live(pool: &rayon::ThreadPool) -> usize {
+        let live = AtomicUsize::new(0);
+        let max_live = AtomicUsize::new(0);
+        pool.install(|| {
+            (0..64).into_par_iter().for_each(|_| {
+                let now_live = live.fetch_add(1, Ordering::SeqCst) + 1;
+                max_live.fetch_max(now_live, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                live.fetch_sub(1, Ordering::SeqCst);
+            })
+        });
+        max_live.into_inner()
+    }
+
+    /// Test that no more VPs than the threads of the pool are live at once,
+    /// both when the pool is smaller and larger than the global Rayon pool.
+    #[test]
+    fn test_max_live_vps() {
+        let global_threads = rayon::current_num_threads();
+        for num_threads in [1, 2, global_threads + 2] {
+            let pool = build_vp_thread_pool(Some(num_threads));
+            let max_live = par_iter_max_live(&pool);
+            assert!(max_live >= 1);
+            assert!(
+                max_live <= num_threads,
+                "{} VPs were live at once with {} threads",
+                max_live,
+                num_threads
+            );
+        }
+    }
+
+    /// Test that the VPs run on the dedicated pool rather than on the global
+    /// Rayon pool.
+    #[test]
+    fn test_vps_run_on_dedicated_pool() {
+        let (shell, _) = setup();
+        assert_eq!(
+            shell.vp_thread_pool.current_num_threads(),
+            rayon::current_num_threads()
+        );
+        let names = par_iter_thread_names(&shell.vp_thread_pool);
+        assert!(!names.is_empty());
+        assert!(names
+            .iter()
+            .all(|name| name.starts_with("ledger-vp-worker-")));
+
+        let pool = build_vp_thread_pool(Some(3));
+        assert_eq!(pool.current_num_threads(), 3);
+        let names = par_iter_thread_names(&pool);
+        assert!(names.len() <= 3);
+        assert!(names
+            .iter()
+            .all(|name| name.starts_with("ledger-vp-worker-")));
+    }
+
+    /// Test that with a single thread in the pool, the VPs run serially.
+    #[test]
+    fn test_vps_run_serially_on_single_thread() {
+        let pool = build_vp_thread_pool(Some(1));
+        assert_eq!(pool.current_num_threads(), 1);
+        assert_eq!(
+            par_iter_thread_names(&pool),
+            BTreeSet::from(["ledger-vp-worker-0".to_owned()])
+        );
+    }
 }
@@ -1,25 +1,24 @@
 //! Implementation of the [`RequestPrepareProposal`] ABCI++ method for the Shell
 
+use std::cmp::Ordering;
+
+use namada::ledger::consensus_params::HALF_MAX_PROPOSAL_SIZE;
 use namada::ledger::storage::{DBIter, StorageHasher, DB};
 use namada::proto::Tx;
+use namada::types::hash::Hash;
 use namada::types::transaction::tx_types::TxType;
 use namada::types::transaction::wrapper::wrapper_tx::PairingEngine;
-use namada::types::transaction::{AffineCurve, DecryptedTx, EllipticCurve};
+use namada::types::transaction::{
+    hash_tx, AffineCurve, DecryptedTx, EllipticCurve, WrapperTx,
+};
 
 use super::super::*;
 use crate::facade::tendermint_proto::abci::RequestPrepareProposal;
 #[cfg(feature = "abcipp")]
 use crate::facade::tendermint_proto::abci::{tx_record::TxAction, TxRecord};
-use crate::node::ledger::shell::{process_tx, ShellMode};
+use crate::node::ledger::shell::{process_tx, BlockFees, ShellMode};
 use crate::node::ledger::shims::abcipp_shim_types::shim::TxBytes;
 
-// TODO: remove this hard-coded value; Tendermint, and thus
-// Namada uses 20 MiB max block sizes by default; 16 MiB leaves
-// plenty of room for header data, evidence and protobuf serialization
-// overhead
-const MAX_PROPOSAL_SIZE: usize = 16 << 20;
-const HALF_MAX_PROPOSAL_SIZE: usize = MAX_PROPOSAL_SIZE / 2;
-
 impl<D, H> Shell<D, H>
 where
     D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
@@ -32,6 +31,19 @@ where
     /// with decryptions of the wrapper txs from the previously
     /// committed block.
     ///
+    /// The new wrapper txs are included by decreasing fee per unit of gas,
+    /// with the fees converted to the native token with the rates of their
+    /// fee tokens and ties broken by their hash, so that the proposal is
+    /// deterministic. The wrapper txs paying their fee in a token that isn't
+    /// accepted are skipped.
+    /// No more wrapper txs are added once the sum of their gas limits would
+    /// exceed the block gas limit. The selection only depends on the txs
+    /// themselves, never on when or in which order the node received them
+    /// (see [`order_by_priority`]). A wrapper tx is skipped if its payer's
+    /// balance doesn't cover its fee on top of the fees of the payer's
+    /// wrapper txs already included, as [`Shell::process_proposal`] would
+    /// reject it.
+    ///
     /// INVARIANT: Any changes applied in this method must be reverted if
     /// the proposal is rejected (unless we can simply overwrite
     /// them in the next block).
@@ -45,56 +57,73 @@ where
 
             // TODO: Craft the Ethereum state update tx
             // filter in half of the new txs from Tendermint, only keeping
-            // wrappers
-            let mut total_proposal_size = 0;
+            // wrappers, ordered by their fee per unit of gas
+            let mut wrappers = vec![];
             #[cfg(feature = "abcipp")]
-            let mut txs: Vec<TxRecord> = req
-                .txs
+            let mut removed = vec![];
+            for tx_bytes in req.txs {
+                // the wrappers paying their fee in a token that isn't
+                // accepted would be rejected by process proposal
+                let wrapper = match Tx::try_from(tx_bytes.as_slice())
+                    .map(|tx| process_tx(tx, &self.chain_id))
+                {
+                    Ok(Ok(TxType::Wrapper(wrapper))) => self
+                        .native_fee(&wrapper)
+                        .ok()
+                        .map(|native_fee| (wrapper, native_fee)),
+                    _ => None,
+                };
+                match wrapper {
+                    Some((wrapper, native_fee)) => wrappers.push((
+                        hash_tx(&tx_bytes),
+                        tx_bytes,
+                        wrapper,
+                        native_fee,
+                    )),
+                    #[cfg(feature = "abcipp")]
+                    None => removed.push(record::remove(tx_bytes)),
+                    #[cfg(not(feature = "abcipp"))]
+                    None => {}
+                }
+            }
+            order_by_priority(&mut wrappers);
+
+            let block_gas_limit = self.block_gas_limit();
+            let mut total_proposal_size = 0;
+            let mut total_proposal_gas: u64 = 0;
+            let mut block_fees = BlockFees::default();
+            let txs = wrappers
                 .into_iter()
-                .map(|tx_bytes| {
-                    if let Ok(Ok(TxType::Wrapper(_))) =
-                        Tx::try_from(tx_bytes.as_slice()).map(process_tx)
-                    {
-                        record::keep(tx_bytes)
-                    } else {
-                        record::remove(tx_bytes)
+                .filter(|(_, _, wrapper, _)| {
+                    match self.wrapper_block_fee(wrapper, &block_fees) {
+                        Some((token_and_payer, total)) => {
+                            block_fees.insert(token_and_payer, total);
+                            true
+                        }
+                        None => false,
                     }
                 })
-                .take_while(|tx_record| {
-                    let new_size = total_proposal_size + tx_record.tx.len();
+                .take_while(|(_, tx_bytes, wrapper, _)| {
+                    let new_size = total_proposal_size + tx_bytes.len();
+                    let new_gas = total_proposal_gas
+                        .saturating_add(u64::from(&wrapper.gas_limit));
                     if new_size > HALF_MAX_PROPOSAL_SIZE
-                        || tx_record.action != TxAction::Unmodified as i32
+                        || new_gas > block_gas_limit
                     {
                         false
                     } else {
                         total_proposal_size = new_size;
+                        total_proposal_gas = new_gas;
                         true
                     }
                 })
-                .collect();
+                .map(|(_, tx_bytes, _, _)| tx_bytes);
+            #[cfg(feature = "abcipp")]
+            let mut txs: Vec<TxRecord> = txs.map(record::keep).collect();
+            #[cfg(feature = "abcipp")]
+            txs.append(&mut removed);
             #[cfg(not(feature = "abcipp"))]
-            let mut txs: Vec<TxBytes> = req
-                .txs
-                .into_iter()
-                .filter_map(|tx_bytes| {
-                    if let Ok(Ok(TxType::Wrapper(_))) =
-                        Tx::try_from(tx_bytes.as_slice()).map(process_tx)
-                    {
-                        Some(tx_bytes)
-                    } else {
-                        None
-                    }
-                })
-                .take_while(|tx_bytes| {
-                    let new_size = total_proposal_size + tx_bytes.len();
-                    if new_size > HALF_MAX_PROPOSAL_SIZE {
-                        false
-                    } else {
-                        total_proposal_size = new_size;
-                        true
-                    }
-                })
-                .collect();
+            let mut txs: Vec<TxBytes> = txs.collect();
 
             // decrypt the wrapper txs included in the previous block
             let decrypted_txs = self.storage.tx_queue.iter().map(|tx| {
@@ -130,6 +159,38 @@ where
     }
 }
 
+/// Order the wrapper txs from the mempool, given with their fee converted to
+/// the native token, by their priority of inclusion in a proposal, i.e. by
+/// decreasing fee per unit of gas and then by their hash. The order only
+/// depends on data that's part of the txs and on the committed fee token
+/// rates, so that every node selects the same txs from the same mempool txs,
+/// regardless of the order in which they were admitted to its mempool. The
+/// arrival times of the txs must never be used here.
+fn order_by_priority(wrappers: &mut [(Hash, TxBytes, WrapperTx, u64)]) {
+    wrappers.sort_by(|(hash_a, _, a, fee_a), (hash_b, _, b, fee_b)| {
+        compare_fee_per_gas((*fee_b, b), (*fee_a, a))
+            .then_with(|| hash_a.0.cmp(&hash_b.0))
+    });
+}
+
+/// Compare the fees per unit of gas offered by two wrapper txs, given with
+/// their fee converted to the native token, so that fees paid in different
+/// tokens are compared by their worth. A zero gas limit is priced at zero,
+/// like in the minimum gas price check.
+fn compare_fee_per_gas(
+    (fee_a, a): (u64, &WrapperTx),
+    (fee_b, b): (u64, &WrapperTx),
+) -> Ordering {
+    let price =
+        |fee: u64, wrapper: &WrapperTx| match u64::from(&wrapper.gas_limit) {
+            0 => (0, 1),
+            gas => (u128::from(fee), u128::from(gas)),
+        };
+    let (amount_a, gas_a) = price(fee_a, a);
+    let (amount_b, gas_b) = price(fee_b, b);
+    (amount_a * gas_b).cmp(&(amount_b * gas_a))
+}
+
 /// Functions for creating the appropriate TxRecord given the
 /// numeric code
 #[cfg(feature = "abcipp")]
@@ -165,9 +226,16 @@ pub(super) mod record {
 
 #[cfg(test)]
 mod test_prepare_proposal {
+    use std::collections::BTreeMap;
+
     use borsh::BorshSerialize;
+    use namada::ledger::parameters;
+    use namada::types::address::{self, Address};
+    use namada::types::key::common;
     use namada::types::storage::Epoch;
+    use namada::types::token;
     use namada::types::transaction::{Fee, WrapperTx};
+    use rust_decimal_macros::dec;
 
     use super::*;
     use crate::node::ledger::shell::test_utils::{gen_keypair, TestShell};
@@ -244,7 +312,7 @@ mod test_prepare_proposal {
 
     /// Test that the decrypted txs are included
     /// in the proposal in the same order as their
-    /// corresponding wrappers
+    /// corresponding wrappers in the previous block
     #[test]
     fn test_decrypted_txs_in_correct_order() {
         let (mut shell, _) = TestShell::new();
@@ -277,11 +345,16 @@ mod test_prepare_proposal {
                 tx,
                 Default::default(),
             );
-            let wrapper = wrapper_tx.sign(&keypair).expect("Test failed");
+            let wrapper = wrapper_tx
+                .sign(&keypair, &shell.chain_id)
+                .expect("Test failed");
             shell.enqueue_tx(wrapper_tx);
             expected_wrapper.push(wrapper.clone());
             req.txs.push(wrapper.to_bytes());
         }
+        // the new wrappers offer the same fee, so they're ordered by their
+        // hash
+        expected_wrapper.sort_by_key(|tx| hash_tx(&tx.to_bytes()).0);
         // we extract the inner data from the txs for testing
         // equality since otherwise changes in timestamps would
         // fail the test
@@ -336,4 +409,273 @@ mod test_prepare_proposal {
             assert_eq!(received, expected_txs);
         }
     }
+
+    /// Test that the proposal stops including wrapper txs from the mempool
+    /// once the sum of their gas limits would exceed the block gas limit,
+    /// and that the txs reaching exactly the limit are included
+    #[test]
+    fn test_prepare_proposal_block_gas_limit() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let gas_limit = 1_000;
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &(2 * gas_limit),
+        )
+        .expect("Test failed");
+        let mut wrappers: Vec<TxBytes> = (0..3)
+            .map(|i| {
+                let tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some(format!("transaction data: {}", i).into_bytes()),
+                );
+                WrapperTx::new(
+                    Fee {
+                        amount: 0.into(),
+                        token: shell.storage.native_token.clone(),
+                    },
+                    &keypair,
+                    Epoch(0),
+                    gas_limit.into(),
+                    tx,
+                    Default::default(),
+                )
+                .sign(&keypair, &shell.chain_id)
+                .expect("Test failed")
+                .to_bytes()
+            })
+            .collect();
+        let req = RequestPrepareProposal {
+            txs: wrappers.clone(),
+            max_tx_bytes: 0,
+            ..Default::default()
+        };
+        // the wrappers offer the same fee, so they're included by their hash
+        wrappers.sort_by_key(|tx_bytes| hash_tx(tx_bytes).0);
+        #[cfg(feature = "abcipp")]
+        assert_eq!(
+            shell.prepare_proposal(req).tx_records,
+            vec![
+                record::keep(wrappers[0].clone()),
+                record::keep(wrappers[1].clone())
+            ]
+        );
+        #[cfg(not(feature = "abcipp"))]
+        assert_eq!(shell.prepare_proposal(req).txs, wrappers[..2].to_vec());
+    }
+
+    /// Make a signed wrapper tx with the given fee amount and gas limit
+    fn wrapper_with_fee(
+        shell: &TestShell,
+        keypair: &common::SecretKey,
+        amount: u64,
+        gas_limit: u64,
+    ) -> TxBytes {
+        let token = shell.storage.native_token.clone();
+        wrapper_with_fee_in(shell, keypair, token, amount, gas_limit)
+    }
+
+    /// Make a signed wrapper tx with the given fee amount in the given token
+    /// and gas limit
+    fn wrapper_with_fee_in(
+        shell: &TestShell,
+        keypair: &common::SecretKey,
+        token: Address,
+        amount: u64,
+        gas_limit: u64,
+    ) -> TxBytes {
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some(format!("transaction data: {}", amount).into_bytes()),
+        );
+        WrapperTx::new(
+            Fee {
+                amount: amount.into(),
+                token,
+            },
+            keypair,
+            Epoch(0),
+            gas_limit.into(),
+            tx,
+            Default::default(),
+        )
+        .sign(keypair, &shell.chain_id)
+        .expect("Test failed")
+        .to_bytes()
+    }
+
+    /// Credit the given amount of the native token to the address of the
+    /// given key
+    fn credit(shell: &mut TestShell, keypair: &common::SecretKey, amount: u64) {
+        let token = shell.storage.native_token.clone();
+        credit_in(shell, keypair, &token, amount)
+    }
+
+    /// Credit the given amount of the given token to the address of the
+    /// given key
+    fn credit_in(
+        shell: &mut TestShell,
+        keypair: &common::SecretKey,
+        token: &Address,
+        amount: u64,
+    ) {
+        let owner = Address::from(&keypair.ref_to());
+        shell
+            .storage
+            .write(
+                &token::balance_key(token, &owner),
+                token::Amount::from(amount)
+                    .try_to_vec()
+                    .expect("Test failed"),
+            )
+            .expect("Test failed");
+    }
+
+    /// Get the txs of a proposal that are kept from the mempool
+    fn proposed_txs(shell: &TestShell, txs: Vec<TxBytes>) -> Vec<TxBytes> {
+        let req = RequestPrepareProposal {
+            txs,
+            max_tx_bytes: 0,
+            ..Default::default()
+        };
+        #[cfg(feature = "abcipp")]
+        {
+            shell
+                .prepare_proposal(req)
+                .tx_records
+                .into_iter()
+                .filter(|record| record.action == TxAction::Unmodified as i32)
+                .map(|record| record.tx)
+                .collect()
+        }
+        #[cfg(not(feature = "abcipp"))]
+        {
+            shell.prepare_proposal(req).txs
+        }
+    }
+
+    /// Test that when two wrapper txs don't both fit in the block, the one
+    /// offering the higher fee per unit of gas is included, regardless of
+    /// the order in which they're received
+    #[test]
+    fn test_prepare_proposal_orders_by_fee_per_gas() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let gas_limit = 1_000;
+        credit(&mut shell, &keypair, 3 * gas_limit);
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &gas_limit,
+        )
+        .expect("Test failed");
+        let low = wrapper_with_fee(&shell, &keypair, gas_limit, gas_limit);
+        let high = wrapper_with_fee(&shell, &keypair, 2 * gas_limit, gas_limit);
+
+        assert_eq!(
+            proposed_txs(&shell, vec![low.clone(), high.clone()]),
+            vec![high.clone()]
+        );
+        assert_eq!(proposed_txs(&shell, vec![high.clone(), low]), vec![high]);
+    }
+
+    /// Test that the fees paid in different tokens are compared by their worth
+    /// in the native token, so that a larger amount of a token worth less
+    /// doesn't outrank a smaller but more valuable native fee
+    #[test]
+    fn test_prepare_proposal_orders_fee_tokens_by_rate() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let gas_limit = 1_000;
+        let fee_token = address::testing::established_address_2();
+        parameters::update_fee_tokens_parameter(
+            &mut shell.storage,
+            &BTreeMap::from([(fee_token.clone(), dec!(0.01))]),
+        )
+        .expect("Test failed");
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &gas_limit,
+        )
+        .expect("Test failed");
+        credit(&mut shell, &keypair, gas_limit);
+        credit_in(&mut shell, &keypair, &fee_token, 50 * gas_limit);
+        // 50 units of the fee token per unit of gas are worth 0.5 units of
+        // the native token
+        let token_fee = wrapper_with_fee_in(
+            &shell,
+            &keypair,
+            fee_token,
+            50 * gas_limit,
+            gas_limit,
+        );
+        let native_fee =
+            wrapper_with_fee(&shell, &keypair, gas_limit, gas_limit);
+
+        assert_eq!(
+            proposed_txs(&shell, vec![token_fee.clone(), native_fee.clone()]),
+            vec![native_fee.clone()]
+        );
+        assert_eq!(
+            proposed_txs(&shell, vec![native_fee.clone(), token_fee]),
+            vec![native_fee]
+        );
+    }
+
+    /// Test that wrapper txs offering equal fees per unit of gas are ordered
+    /// by their hash, regardless of the order in which they're received
+    #[test]
+    fn test_prepare_proposal_equal_fees_ordered_by_hash() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        credit(&mut shell, &keypair, 3_000);
+        // Both offer a fee of 1 per unit of gas
+        let a = wrapper_with_fee(&shell, &keypair, 1_000, 1_000);
+        let b = wrapper_with_fee(&shell, &keypair, 2_000, 2_000);
+        let mut expected = vec![a.clone(), b.clone()];
+        expected.sort_by_key(|tx_bytes| hash_tx(tx_bytes).0);
+
+        assert_eq!(proposed_txs(&shell, vec![a.clone(), b.clone()]), expected);
+        assert_eq!(proposed_txs(&shell, vec![b, a]), expected);
+    }
+
+    /// Test that two independent shells select the same txs, in the same
+    /// order, from a batch of wrapper txs offering equal fees that don't all
+    /// fit in the block, even when they're received in different orders
+    #[test]
+    fn test_prepare_proposal_equal_fees_deterministic_across_shells() {
+        let (mut shell_a, _) = TestShell::new();
+        let (mut shell_b, _) = TestShell::new();
+        let gas_limit = 1_000;
+        // Equal fees from different signers, so that only their hash differs
+        let keypairs: Vec<_> = (0..8).map(|_| gen_keypair()).collect();
+        for shell in [&mut shell_a, &mut shell_b] {
+            parameters::update_block_gas_limit_parameter(
+                &mut shell.storage,
+                &(4 * gas_limit),
+            )
+            .expect("Test failed");
+            for keypair in &keypairs {
+                credit(shell, keypair, gas_limit);
+            }
+        }
+        let batch: Vec<TxBytes> = keypairs
+            .iter()
+            .map(|keypair| {
+                wrapper_with_fee(&shell_a, keypair, gas_limit, gas_limit)
+            })
+            .collect();
+        let mut reordered = batch.clone();
+        reordered.reverse();
+        reordered.rotate_left(3);
+
+        let proposal_a = proposed_txs(&shell_a, batch.clone());
+        let proposal_b = proposed_txs(&shell_b, reordered);
+        assert_eq!(proposal_a, proposal_b);
+
+        // The txs with the lowest hashes fill the block
+        let mut expected = batch;
+        expected.sort_by_key(|tx_bytes| hash_tx(tx_bytes).0);
+        expected.truncate(4);
+        assert_eq!(proposal_a, expected);
+    }
 }
@@ -1,28 +1,134 @@
 //! Implementation of the ['VerifyHeader`], [`ProcessProposal`],
 //! and [`RevertProposal`] ABCI++ methods for the Shell
 
+use namada::types::time::DurationSecs;
+
 use super::*;
 use crate::facade::tendermint_proto::abci::response_process_proposal::ProposalStatus;
 use crate::facade::tendermint_proto::abci::RequestProcessProposal;
 use crate::node::ledger::shims::abcipp_shim_types::shim::response::ProcessProposal;
 
+/// The maximum number of seconds that a proposed block's time may be ahead
+/// of the local clock
+pub const MAX_HEADER_TIME_DRIFT_SECS: u64 = 10;
+
 impl<D, H> Shell<D, H>
 where
     D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
     H: StorageHasher + Sync + 'static,
 {
+    /// Check that a proposed block's header is consistent with the last
+    /// committed block. The header is rejected if:
+    /// - its app hash doesn't match the merkle root of the last committed block
+    /// - its proposer is not in the active validator set of the current epoch
+    /// - its time is not after the last committed block's time, or it is more
+    ///   than [`MAX_HEADER_TIME_DRIFT_SECS`] ahead of the local clock
+    ///
+    /// The first block after genesis has no predecessor, so only its proposer
+    /// and drift from the local clock are checked.
+    ///
+    /// With ABCI, this is called with the header of every decided block at
+    /// `BeginBlock`, where a rejection can only be reported.
+    ///
     /// INVARIANT: This method must be stateless.
     pub fn verify_header(
         &self,
-        _req: shim::request::VerifyHeader,
+        req: shim::request::VerifyHeader,
     ) -> shim::response::VerifyHeader {
-        Default::default()
+        match self.check_header(&req) {
+            Ok(()) => shim::response::VerifyHeader {
+                status: ProposalStatus::Accept as i32,
+                info: String::default(),
+            },
+            Err(info) => {
+                tracing::info!("Rejecting block header: {}", info);
+                shim::response::VerifyHeader {
+                    status: ProposalStatus::Reject as i32,
+                    info,
+                }
+            }
+        }
+    }
+
+    /// Check the given header, returning the reason for its rejection, if
+    /// any.
+    fn check_header(
+        &self,
+        req: &shim::request::VerifyHeader,
+    ) -> std::result::Result<(), String> {
+        if self.storage.last_height.0 > 0 {
+            let app_hash = self.storage.merkle_root().0;
+            if req.app_hash != app_hash {
+                return Err(format!(
+                    "App hash {} doesn't match the last committed block's app \
+                     hash {}",
+                    tm_raw_hash_to_string(&req.app_hash),
+                    tm_raw_hash_to_string(app_hash),
+                ));
+            }
+            let last_header = self
+                .storage
+                .db
+                .read_block_header(self.storage.last_height)
+                .map_err(|err| {
+                    format!(
+                        "Failed to read the last committed block's header: {}",
+                        err
+                    )
+                })?;
+            if let Some(last_header) = last_header {
+                if req.time <= last_header.time {
+                    return Err(format!(
+                        "Block time {} is not after the last committed \
+                         block's time {}",
+                        req.time.to_rfc3339(),
+                        last_header.time.to_rfc3339(),
+                    ));
+                }
+            }
+        }
+
+        let max_time =
+            DateTimeUtc::now() + DurationSecs(MAX_HEADER_TIME_DRIFT_SECS);
+        if req.time > max_time {
+            return Err(format!(
+                "Block time {} is too far ahead of the local time",
+                req.time.to_rfc3339(),
+            ));
+        }
+
+        let proposer_raw_hash = tm_raw_hash_to_string(&req.proposer_address);
+        let proposer = self
+            .storage
+            .read_validator_address_raw_hash(&proposer_raw_hash)
+            .ok_or_else(|| {
+                format!("Unknown proposer with raw hash {}", proposer_raw_hash)
+            })?;
+        let (current_epoch, _) = self.storage.get_current_epoch();
+        let is_active = self
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .map(|validators| {
+                validators
+                    .active
+                    .iter()
+                    .any(|validator| validator.address == proposer)
+            })
+            .unwrap_or_default();
+        if !is_active {
+            return Err(format!(
+                "Proposer {} is not in the active validator set",
+                proposer
+            ));
+        }
+        Ok(())
     }
 
     /// Check all the txs in a block. Some txs may be incorrect,
     /// but we only reject the entire block if the order of the
     /// included txs violates the order decided upon in the previous
-    /// block.
+    /// block, or if its wrapper txs exceed the block gas limit.
     pub fn process_proposal(
         &self,
         req: RequestProcessProposal,
@@ -42,15 +148,23 @@ where
     /// Check all the given txs.
     pub fn process_txs(&self, txs: &[Vec<u8>]) -> Vec<TxResult> {
         let mut tx_queue_iter = self.storage.tx_queue.iter();
+        let mut block_gas = 0;
+        let mut block_fees = BlockFees::default();
         txs.iter()
             .map(|tx_bytes| {
-                self.process_single_tx(tx_bytes, &mut tx_queue_iter)
+                self.process_single_tx(
+                    tx_bytes,
+                    &mut tx_queue_iter,
+                    &mut block_gas,
+                    &mut block_fees,
+                )
             })
             .collect()
     }
 
-    /// Checks if the Tx can be deserialized from bytes. Checks the fees and
-    /// signatures of the fee payer for a transaction if it is a wrapper tx.
+    /// Checks if the Tx can be deserialized from bytes. Checks the fee token,
+    /// the fees and signatures of the fee payer for a transaction if it is a
+    /// wrapper tx.
     ///
     /// Checks validity of a decrypted tx or that a tx marked un-decryptable
     /// is in fact so. Also checks that decrypted txs were submitted in
@@ -63,6 +177,13 @@ where
     ///   3: Wasm runtime error
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
+    ///   9. The block gas limit is exceeded
+    ///
+    /// The gas limits of the accepted wrapper txs are added to `block_gas`.
+    /// The decrypted txs are not counted, as their inclusion was decided in
+    /// the previous block. Likewise, the fees of the accepted wrapper txs are
+    /// added to `block_fees`, so that the fees of all the wrappers of a payer
+    /// in the block must be covered by its balance.
     ///
     /// INVARIANT: Any changes applied in this method must be reverted if the
     /// proposal is rejected (unless we can simply overwrite them in the
@@ -71,6 +192,8 @@ where
         &self,
         tx_bytes: &[u8],
         tx_queue_iter: &mut impl Iterator<Item = &'a WrapperTx>,
+        block_gas: &mut u64,
+        block_fees: &mut BlockFees,
     ) -> TxResult {
         let tx = match Tx::try_from(tx_bytes) {
             Ok(tx) => tx,
@@ -85,7 +208,7 @@ where
         // TODO: This should not be hardcoded
         let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
 
-        match process_tx(tx) {
+        match process_tx(tx, &self.chain_id) {
             // This occurs if the wrapper / protocol tx signature is invalid
             Err(err) => TxResult {
                 code: ErrorCodes::InvalidSig.into(),
@@ -149,33 +272,42 @@ where
                             ),
                         }
                     } else {
-                        // If the public key corresponds to the MASP sentinel
-                        // transaction key, then the fee payer is effectively
-                        // the MASP, otherwise derive
-                        // they payer from public key.
-                        let fee_payer = if tx.pk != masp_tx_key().ref_to() {
-                            tx.fee_payer()
-                        } else {
-                            masp()
-                        };
                         // check that the fee payer has sufficient balance
-                        let balance =
-                            self.get_balance(&tx.fee.token, &fee_payer);
+                        // for the fees of all its wrappers in the block
+                        let block_fee = self.wrapper_block_fee(&tx, block_fees);
 
-                        if tx.fee.amount <= balance {
+                        let new_block_gas =
+                            block_gas.saturating_add(u64::from(&tx.gas_limit));
+                        if let Err(err) = self.fee_token_rate(&tx.fee.token) {
                             TxResult {
-                                code: ErrorCodes::Ok.into(),
-                                info: "Process proposal accepted this \
-                                       transaction"
-                                    .into(),
+                                code: ErrorCodes::InvalidTx.into(),
+                                info: err.to_string(),
                             }
-                        } else {
+                        } else if block_fee.is_none() {
                             TxResult {
                                 code: ErrorCodes::InvalidTx.into(),
                                 info: "The address given does not have \
                                        sufficient balance to pay fee"
                                     .into(),
                             }
+                        } else if new_block_gas > self.block_gas_limit() {
+                            TxResult {
+                                code: ErrorCodes::BlockGasLimitExceeded.into(),
+                                info: format!(
+                                    "The wrapper txs' gas limits sum up to \
+                                     {}, which exceeds the block gas limit",
+                                    new_block_gas
+                                ),
+                            }
+                        } else {
+                            *block_gas = new_block_gas;
+                            block_fees.extend(block_fee);
+                            TxResult {
+                                code: ErrorCodes::Ok.into(),
+                                info: "Process proposal accepted this \
+                                       transaction"
+                                    .into(),
+                            }
                         }
                     }
                 }
@@ -195,11 +327,15 @@ where
 /// are covered by the e2e tests.
 #[cfg(test)]
 mod test_process_proposal {
+    use std::str::FromStr;
+
     use borsh::BorshDeserialize;
     use namada::proto::SignedTxData;
+    use namada::types::chain::ChainIdPrefix;
     use namada::types::hash::Hash;
     use namada::types::key::*;
-    use namada::types::storage::Epoch;
+    use namada::types::storage::{BlockHash, Epoch, Header};
+    use namada::types::time::Duration;
     use namada::types::token::Amount;
     use namada::types::transaction::encrypted::EncryptedTx;
     use namada::types::transaction::{EncryptionKey, Fee};
@@ -208,8 +344,108 @@ mod test_process_proposal {
     use crate::facade::tendermint_proto::abci::RequestInitChain;
     use crate::facade::tendermint_proto::google::protobuf::Timestamp;
     use crate::node::ledger::shell::test_utils::{
-        gen_keypair, ProcessProposal, TestError, TestShell,
+        gen_keypair, genesis_proposer_address, setup, ProcessProposal,
+        TestError, TestShell,
     };
+    use crate::node::ledger::shims::abcipp_shim_types::shim::request::VerifyHeader;
+
+    /// Commit a block at height 1 with the given time
+    fn commit_block(shell: &mut TestShell, time: DateTimeUtc) {
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .expect("Test failed");
+        shell
+            .storage
+            .set_header(Header {
+                hash: Hash([0; 32]),
+                time,
+                next_validators_hash: Hash([0; 32]),
+            })
+            .expect("Test failed");
+        shell.storage.commit().expect("Test failed");
+    }
+
+    /// Test that a header that follows the last committed block is accepted
+    #[test]
+    fn test_verify_header_valid() {
+        let (mut shell, _) = setup();
+        let now = DateTimeUtc::now();
+        commit_block(&mut shell, now - Duration::seconds(5));
+        let response = shell.verify_header(VerifyHeader {
+            app_hash: shell.storage.merkle_root().0.to_vec(),
+            proposer_address: genesis_proposer_address(&shell),
+            time: now,
+        });
+        assert_eq!(response.status, ProposalStatus::Accept as i32);
+    }
+
+    /// Test that the first block after genesis, which has no predecessor, is
+    /// accepted without checking its app hash
+    #[test]
+    fn test_verify_header_first_block() {
+        let (shell, _) = setup();
+        let response = shell.verify_header(VerifyHeader {
+            app_hash: vec![],
+            proposer_address: genesis_proposer_address(&shell),
+            time: DateTimeUtc::now(),
+        });
+        assert_eq!(response.status, ProposalStatus::Accept as i32);
+    }
+
+    /// Test that a header whose time is not after the last committed block's
+    /// time, or is too far ahead of the local clock, is rejected
+    #[test]
+    fn test_verify_header_non_monotonic_time() {
+        let (mut shell, _) = setup();
+        let last_time = DateTimeUtc::now();
+        commit_block(&mut shell, last_time);
+        let app_hash = shell.storage.merkle_root().0.to_vec();
+        let proposer_address = genesis_proposer_address(&shell);
+
+        for time in [
+            last_time,
+            last_time - Duration::seconds(1),
+            DateTimeUtc::now() + DurationSecs(2 * MAX_HEADER_TIME_DRIFT_SECS),
+        ] {
+            let response = shell.verify_header(VerifyHeader {
+                app_hash: app_hash.clone(),
+                proposer_address: proposer_address.clone(),
+                time,
+            });
+            assert_eq!(response.status, ProposalStatus::Reject as i32);
+        }
+    }
+
+    /// Test that a header with a proposer that isn't a validator is rejected
+    #[test]
+    fn test_verify_header_unknown_proposer() {
+        let (mut shell, _) = setup();
+        let now = DateTimeUtc::now();
+        commit_block(&mut shell, now - Duration::seconds(5));
+        let response = shell.verify_header(VerifyHeader {
+            app_hash: shell.storage.merkle_root().0.to_vec(),
+            proposer_address: vec![0; 20],
+            time: now,
+        });
+        assert_eq!(response.status, ProposalStatus::Reject as i32);
+        assert!(response.info.contains("Unknown proposer"));
+    }
+
+    /// Test that a header with an app hash that doesn't match the last
+    /// committed block is rejected
+    #[test]
+    fn test_verify_header_wrong_app_hash() {
+        let (mut shell, _) = setup();
+        let now = DateTimeUtc::now();
+        commit_block(&mut shell, now - Duration::seconds(5));
+        let response = shell.verify_header(VerifyHeader {
+            app_hash: vec![0; 32],
+            proposer_address: genesis_proposer_address(&shell),
+            time: now,
+        });
+        assert_eq!(response.status, ProposalStatus::Reject as i32);
+    }
 
     /// Test that if a wrapper tx is not signed, it is rejected
     /// by [`process_proposal`].
@@ -279,7 +515,7 @@ mod test_process_proposal {
             tx,
             Default::default(),
         )
-        .sign(&keypair)
+        .sign(&keypair, &shell.chain_id)
         .expect("Test failed");
         let new_tx = if let Some(Ok(SignedTxData {
             data: Some(data),
@@ -340,6 +576,48 @@ mod test_process_proposal {
         );
     }
 
+    /// Test that a wrapper tx signed for another chain is rejected, so that it
+    /// cannot be replayed on this one
+    #[test]
+    fn test_wrapper_other_chain_rejected() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let other_chain = ChainIdPrefix::from_str("other")
+            .expect("Test failed")
+            .temp_chain_id();
+        assert_ne!(other_chain, shell.chain_id);
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: shell.storage.native_token.clone(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            tx,
+            Default::default(),
+        )
+        .sign(&keypair, &other_chain)
+        .expect("Test failed");
+        let request = ProcessProposal {
+            txs: vec![wrapper.to_bytes()],
+        };
+        let response = if let [response] = shell
+            .process_proposal(request)
+            .expect("Test failed")
+            .as_slice()
+        {
+            response.clone()
+        } else {
+            panic!("Test failed")
+        };
+        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidSig));
+    }
+
     /// Test that if the account submitting the tx is not known and the fee is
     /// non-zero, [`process_proposal`] rejects that tx
     #[test]
@@ -361,7 +639,7 @@ mod test_process_proposal {
             tx,
             Default::default(),
         )
-        .sign(&keypair)
+        .sign(&keypair, &shell.chain_id)
         .expect("Test failed");
         let request = ProcessProposal {
             txs: vec![wrapper.to_bytes()],
@@ -414,7 +692,7 @@ mod test_process_proposal {
             tx,
             Default::default(),
         )
-        .sign(&keypair)
+        .sign(&keypair, &shell.chain_id)
         .expect("Test failed");
 
         let request = ProcessProposal {
@@ -439,6 +717,118 @@ mod test_process_proposal {
         );
     }
 
+    /// Test that if the fees of the wrapper txs of a payer in a block sum up to
+    /// more than its balance, [`process_proposal`] rejects the wrapper tx that
+    /// exceeds it, even though each fee is covered by the balance on its own
+    #[test]
+    fn test_wrapper_insufficient_balance_for_block_fees() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        shell
+            .storage
+            .write(
+                &token::balance_key(&shell.storage.native_token, &payer),
+                Amount::whole(100).try_to_vec().expect("Test failed"),
+            )
+            .expect("Test failed");
+        let txs = (0..2)
+            .map(|_| {
+                let tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some("transaction data".as_bytes().to_owned()),
+                );
+                WrapperTx::new(
+                    Fee {
+                        amount: Amount::whole(60),
+                        token: shell.storage.native_token.clone(),
+                    },
+                    &keypair,
+                    Epoch(0),
+                    0.into(),
+                    tx,
+                    Default::default(),
+                )
+                .sign(&keypair, &shell.chain_id)
+                .expect("Test failed")
+                .to_bytes()
+            })
+            .collect::<Vec<_>>();
+
+        let response = shell
+            .process_proposal(ProcessProposal {
+                txs: txs[..1].to_vec(),
+            })
+            .expect("Test failed");
+        assert_eq!(response[0].result.code, u32::from(ErrorCodes::Ok));
+
+        let response = shell
+            .process_proposal(ProcessProposal { txs })
+            .expect("Test failed");
+        assert_eq!(response[0].result.code, u32::from(ErrorCodes::Ok));
+        assert_eq!(response[1].result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(
+            response[1].result.info,
+            String::from(
+                "The address given does not have sufficient balance to pay fee"
+            )
+        );
+    }
+
+    /// Test that a wrapper tx paying its fee in a token that isn't one of the
+    /// fee tokens of the protocol parameters is rejected.
+    #[test]
+    fn test_wrapper_fee_token_not_accepted() {
+        let (mut shell, _) = TestShell::new();
+        shell.init_chain(RequestInitChain {
+            time: Some(Timestamp {
+                seconds: 0,
+                nanos: 0,
+            }),
+            chain_id: ChainId::default().to_string(),
+            ..Default::default()
+        });
+        let keypair = crate::wallet::defaults::daewon_keypair();
+
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 1.into(),
+                token: address::btc(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            tx,
+            Default::default(),
+        )
+        .sign(&keypair, &shell.chain_id)
+        .expect("Test failed");
+
+        let request = ProcessProposal {
+            txs: vec![wrapper.to_bytes()],
+        };
+
+        let response = if let [resp] = shell
+            .process_proposal(request)
+            .expect("Test failed")
+            .as_slice()
+        {
+            resp.clone()
+        } else {
+            panic!("Test failed")
+        };
+        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert!(
+            response.result.info.contains("accepted fee tokens"),
+            "{}",
+            response.result.info
+        );
+    }
+
     /// Test that if the expected order of decrypted txs is
     /// validated, [`process_proposal`] rejects it
     #[test]
@@ -724,4 +1114,67 @@ mod test_process_proposal {
             ),
         );
     }
+
+    /// Make a signed wrapper tx without fee with the given gas limit
+    fn wrapper_with_gas_limit(shell: &TestShell, gas_limit: u64) -> Vec<u8> {
+        let keypair = gen_keypair();
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: shell.storage.native_token.clone(),
+            },
+            &keypair,
+            Epoch(0),
+            gas_limit.into(),
+            tx,
+            Default::default(),
+        )
+        .sign(&keypair, &shell.chain_id)
+        .expect("Test failed")
+        .to_bytes()
+    }
+
+    /// Test that a proposal whose wrapper txs' gas limits sum up to exactly
+    /// the block gas limit is accepted, while one unit over it is rejected
+    #[test]
+    fn test_block_gas_limit() {
+        let (mut shell, _) = TestShell::new();
+        let gas_limit = 1_000;
+        let txs = vec![
+            wrapper_with_gas_limit(&shell, gas_limit),
+            wrapper_with_gas_limit(&shell, gas_limit),
+        ];
+
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &(2 * gas_limit),
+        )
+        .expect("Test failed");
+        let response = shell
+            .process_proposal(ProcessProposal { txs: txs.clone() })
+            .expect("Test failed");
+        assert!(response
+            .iter()
+            .all(|tx| tx.result.code == u32::from(ErrorCodes::Ok)));
+
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &(2 * gas_limit - 1),
+        )
+        .expect("Test failed");
+        match shell.process_proposal(ProcessProposal { txs }) {
+            Err(TestError::RejectProposal(response)) => {
+                assert_eq!(response[0].result.code, u32::from(ErrorCodes::Ok));
+                assert_eq!(
+                    response[1].result.code,
+                    u32::from(ErrorCodes::BlockGasLimitExceeded)
+                );
+            }
+            _ => panic!("Test failed"),
+        }
+    }
 }
@@ -22,9 +22,21 @@ where
     /// the default if `path` is not a supported string.
     /// INVARIANT: This method must be stateless.
     pub fn query(&self, query: request::Query) -> response::Query {
+        // The genesis isn't in storage, so it's not handled by the RPC router
+        if query.path == genesis::GENESIS_QUERY_PATH {
+            return self.query_genesis(&query.data);
+        }
+        // The mempool is node-local, so it's not handled by the RPC router
+        if query.path == mempool::MEMPOOL_QUERY_PATH {
+            return self.query_mempool(&query.data);
+        }
+
         let ctx = RequestCtx {
             storage: &self.storage,
             event_log: self.event_log(),
+            tx_results: &self.tx_results,
+            block_events: &self.block_event_log,
+            ibc_packets: &self.ibc_packets,
             vp_wasm_cache: self.vp_wasm_cache.read_only(),
             tx_wasm_cache: self.tx_wasm_cache.read_only(),
             storage_read_past_height_limit: self.storage_read_past_height_limit,
@@ -46,7 +58,14 @@ where
         };
 
         // Invoke the root RPC handler - returns borsh-encoded data on success
-        let result = namada::ledger::queries::handle_path(ctx, &request);
+        let result = match &self.query_cache {
+            Some(cache) => {
+                cache.get_or_compute(&request, self.storage.last_height, || {
+                    namada::ledger::queries::handle_path(ctx, &request)
+                })
+            }
+            None => namada::ledger::queries::handle_path(ctx, &request),
+        };
         match result {
             Ok(ResponseQuery { data, info, proof }) => response::Query {
                 value: data,
@@ -62,6 +81,57 @@ where
         }
     }
 
+    /// Query a page of the genesis that the node started from, along with
+    /// the node's chain ID. The data of the query must be a borsh encoded
+    /// [`genesis::GenesisPageRequest`].
+    fn query_genesis(&self, data: &[u8]) -> response::Query {
+        let request = match genesis::GenesisPageRequest::try_from_slice(data) {
+            Ok(request) => request,
+            Err(err) => {
+                return response::Query {
+                    code: 1,
+                    info: format!("Invalid genesis query: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        let genesis = match self.genesis() {
+            Ok(genesis) => genesis,
+            Err(err) => {
+                return response::Query {
+                    code: 1,
+                    info: format!("Failed to read the genesis: {}", err),
+                    ..Default::default()
+                };
+            }
+        };
+        let page = genesis::GenesisPage::new(
+            self.storage.chain_id.clone(),
+            genesis.clone(),
+            &request,
+        );
+        response::Query {
+            value: page.try_to_vec().expect("Encoding genesis shouldn't fail"),
+            ..Default::default()
+        }
+    }
+
+    /// Get the genesis that the node started from. Its config file is read
+    /// only once.
+    fn genesis(&self) -> std::result::Result<&genesis::Genesis, String> {
+        self.genesis.get_or_try_init(|| {
+            #[cfg(not(feature = "dev"))]
+            {
+                genesis::try_genesis(&self.base_dir, &self.storage.chain_id)
+                    .map_err(|err| err.to_string())
+            }
+            #[cfg(feature = "dev")]
+            {
+                Ok(genesis::genesis())
+            }
+        })
+    }
+
     /// Simple helper function for the ledger to get balances
     /// of the specified token at the specified address
     pub fn get_balance(
@@ -134,3 +204,236 @@ where
             })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroUsize;
+
+    use namada::ledger::consensus_params::{
+        ConsensusParams, HALF_MAX_PROPOSAL_SIZE,
+    };
+    use namada::ledger::gas::{BlockGasMeter, GasSchedule, VpGasMeter};
+    use namada::ledger::queries::cache::{QueryCache, QueryCacheStats};
+    use namada::ledger::queries::RPC;
+    use namada::ledger::{gas, parameters};
+    use namada::types::storage::Key;
+    use namada::types::time::DurationSecs;
+
+    use super::super::test_utils::{setup, TestShell};
+    use super::*;
+
+    /// Query the genesis with the given request
+    fn query_genesis_page(
+        shell: &TestShell,
+        request: genesis::GenesisPageRequest,
+    ) -> genesis::GenesisPage {
+        let response = shell.query(request::Query {
+            path: genesis::GENESIS_QUERY_PATH.to_owned(),
+            data: request.try_to_vec().unwrap(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        genesis::GenesisPage::try_from_slice(&response.value).unwrap()
+    }
+
+    /// Test that the genesis query returns the node's chain ID and that the
+    /// validators are paginated.
+    #[test]
+    fn test_query_genesis() {
+        let (shell, _) = setup();
+        let page = query_genesis_page(
+            &shell,
+            genesis::GenesisPageRequest {
+                offset: 0,
+                limit: u64::MAX,
+            },
+        );
+        assert_eq!(page.chain_id, shell.storage.chain_id);
+        assert_eq!(page.genesis.native_token, shell.storage.native_token);
+        let total_validators = page.total_validators;
+        assert!(total_validators > 0);
+        assert_eq!(
+            page.genesis.validators.len() as u64,
+            total_validators.min(genesis::MAX_GENESIS_PAGE_VALIDATORS)
+        );
+
+        // A page past the last validator is empty
+        let page = query_genesis_page(
+            &shell,
+            genesis::GenesisPageRequest {
+                offset: total_validators,
+                limit: 1,
+            },
+        );
+        assert_eq!(page.chain_id, shell.storage.chain_id);
+        assert_eq!(page.total_validators, total_validators);
+        assert!(page.genesis.validators.is_empty());
+
+        // A malformed request is rejected
+        let response = shell.query(request::Query {
+            path: genesis::GENESIS_QUERY_PATH.to_owned(),
+            data: b"arbitrary data".to_vec(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 1);
+    }
+
+    /// Query the consensus parameters enforced by the node
+    fn query_consensus_params(shell: &TestShell) -> ConsensusParams {
+        let response = shell.query(request::Query {
+            path: RPC.shell().consensus_params_path(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        ConsensusParams::try_from_slice(&response.value).unwrap()
+    }
+
+    /// Test that the consensus parameters query reflects the configured
+    /// limits and the parameters currently set by governance.
+    #[test]
+    fn test_query_consensus_params() {
+        let (mut shell, _) = setup();
+        let params = query_consensus_params(&shell);
+        assert_eq!(params.block_gas_limit, gas::BLOCK_GAS_LIMIT);
+        assert_eq!(params.max_verifiers, None);
+
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &1_000,
+        )
+        .unwrap();
+        parameters::update_max_verifiers_parameter(
+            &mut shell.storage,
+            &Some(3),
+        )
+        .unwrap();
+        let params = query_consensus_params(&shell);
+        assert_eq!(params.block_gas_limit, 1_000);
+        assert_eq!(params.tx_gas_limit, gas::TRANSACTION_GAS_LIMIT);
+        assert_eq!(params.max_verifiers, Some(3));
+        assert_eq!(params.max_wrapper_txs_bytes, HALF_MAX_PROPOSAL_SIZE as u64);
+
+        // A parameter changed by governance is reflected
+        let max_expected_time_per_block =
+            DurationSecs(params.max_expected_time_per_block.0 + 1);
+        parameters::update_max_expected_time_per_block_parameter(
+            &mut shell.storage,
+            &max_expected_time_per_block,
+        )
+        .unwrap();
+        let params = query_consensus_params(&shell);
+        assert_eq!(
+            params.max_expected_time_per_block,
+            max_expected_time_per_block
+        );
+
+        let max_ibc_packet_data_bytes = params.max_ibc_packet_data_bytes + 1;
+        parameters::update_max_ibc_packet_data_bytes_parameter(
+            &mut shell.storage,
+            &max_ibc_packet_data_bytes,
+        )
+        .unwrap();
+        let params = query_consensus_params(&shell);
+        assert_eq!(params.max_ibc_packet_data_bytes, max_ibc_packet_data_bytes);
+    }
+
+    /// Test that the gas schedule query returns the costs that the node's gas
+    /// meters charge and the effective block gas limit.
+    #[test]
+    fn test_query_gas_schedule() {
+        let (mut shell, _) = setup();
+        let response = shell.query(request::Query {
+            path: RPC.shell().gas_schedule_path(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        let schedule = GasSchedule::try_from_slice(&response.value).unwrap();
+        assert_eq!(schedule, GasSchedule::current());
+        assert_eq!(schedule.version, gas::GAS_SCHEDULE_VERSION);
+        assert_eq!(schedule.transaction_gas_limit, gas::TRANSACTION_GAS_LIMIT);
+        assert_eq!(schedule.block_gas_limit, gas::BLOCK_GAS_LIMIT);
+        assert_eq!(schedule.verify_tx_sig_gas, gas::VERIFY_TX_SIG_GAS_COST);
+
+        // The meters charge the costs of the schedule
+        let mut meter = BlockGasMeter::default();
+        meter.add_base_transaction_fee(0).unwrap();
+        assert_eq!(
+            meter.get_current_transaction_gas(),
+            schedule.base_transaction_fee
+        );
+        meter.add_compiling_fee(100).unwrap();
+        assert_eq!(
+            meter.get_current_transaction_gas(),
+            schedule.base_transaction_fee + 100 * schedule.compile_gas_per_byte
+        );
+        let mut vp_meter = VpGasMeter::new(0);
+        vp_meter.add_compiling_fee(100).unwrap();
+        assert_eq!(vp_meter.current_gas, 100 * schedule.compile_gas_per_byte);
+
+        // The storage charges the accessed bytes
+        let key = Key::parse("gas_schedule").unwrap();
+        let value = vec![0_u8; 10];
+        let (write_gas, _) = shell.storage.write(&key, &value).unwrap();
+        let (_, read_gas) = shell.storage.read(&key).unwrap();
+        let accessed_bytes = (key.len() + value.len()) as u64;
+        assert_eq!(
+            write_gas,
+            accessed_bytes * schedule.storage_access_gas_per_byte
+        );
+        assert_eq!(read_gas, write_gas);
+
+        // The block gas limit parameter lowers the schedule's limit
+        parameters::update_block_gas_limit_parameter(
+            &mut shell.storage,
+            &1_000,
+        )
+        .unwrap();
+        let response = shell.query(request::Query {
+            path: RPC.shell().gas_schedule_path(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.info);
+        let schedule = GasSchedule::try_from_slice(&response.value).unwrap();
+        assert_eq!(schedule.block_gas_limit, 1_000);
+        assert_eq!(
+            schedule,
+            GasSchedule {
+                block_gas_limit: 1_000,
+                ..GasSchedule::current()
+            }
+        );
+    }
+
+    /// Test that with the query cache enabled, a query repeated at the same
+    /// height is served from the cache and that it's computed again once a
+    /// new block is committed.
+    #[test]
+    fn test_query_cache() {
+        let (mut shell, _) = setup();
+        shell.query_cache =
+            Some(QueryCache::new(NonZeroUsize::new(10).unwrap()));
+        let query_epoch = |shell: &TestShell| {
+            let response = shell.query(request::Query {
+                path: RPC.shell().epoch_path(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            Epoch::try_from_slice(&response.value).unwrap()
+        };
+        let stats =
+            |shell: &TestShell| shell.query_cache.as_ref().unwrap().stats();
+
+        let epoch = query_epoch(&shell);
+        assert_eq!(stats(&shell), QueryCacheStats { hits: 0, misses: 1 });
+        assert_eq!(query_epoch(&shell), epoch);
+        assert_eq!(stats(&shell), QueryCacheStats { hits: 1, misses: 1 });
+
+        // The query is computed again at a new height
+        shell
+            .finalize_block(shim::request::FinalizeBlock::default())
+            .expect("Test failed");
+        shell.commit();
+        query_epoch(&shell);
+        assert_eq!(stats(&shell), QueryCacheStats { hits: 1, misses: 2 });
+    }
+}
@@ -0,0 +1,218 @@
+//! Replay of a range of committed blocks against a copy of the state, to debug
+//! a divergence of the state by comparing the app hashes at each height.
+//!
+//! The ledger doesn't store the txs of the blocks, so the blocks to replay
+//! are taken from a [`BlockSource`], e.g. recorded from Tendermint's block
+//! store.
+
+use std::collections::BTreeMap;
+
+use namada::types::storage::BlockHash;
+
+use super::*;
+use crate::facade::tendermint_proto::abci::response_process_proposal::ProposalStatus;
+use crate::facade::tendermint_proto::abci::RequestProcessProposal;
+use crate::node::ledger::shims::abcipp_shim_types::shim::request::{
+    FinalizeBlock, ProcessedTx,
+};
+use crate::node::ledger::shims::abcipp_shim_types::shim::TxBytes;
+
+/// A committed block, as recorded to be replayed
+#[derive(Clone, Debug)]
+pub struct RecordedBlock {
+    /// The hash of the block
+    pub hash: BlockHash,
+    /// The header of the block
+    pub header: namada::types::storage::Header,
+    /// The evidence of misbehavior in the block
+    pub byzantine_validators: Vec<Evidence>,
+    /// The txs of the block
+    pub txs: Vec<TxBytes>,
+    /// Tendermint raw hash of the proposer's consensus key
+    pub proposer_address: Vec<u8>,
+    /// The app hash committed for the block
+    pub app_hash: Vec<u8>,
+}
+
+/// A source of the committed blocks to replay
+pub trait BlockSource {
+    /// Get the block committed at the given height, if any
+    fn block(&self, height: BlockHeight) -> Option<RecordedBlock>;
+}
+
+impl BlockSource for BTreeMap<BlockHeight, RecordedBlock> {
+    fn block(&self, height: BlockHeight) -> Option<RecordedBlock> {
+        self.get(&height).cloned()
+    }
+}
+
+/// The app hash of a replayed block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayedBlock {
+    /// The height of the block
+    pub height: BlockHeight,
+    /// The app hash committed when the block was replayed
+    pub app_hash: Vec<u8>,
+    /// The app hash originally committed for the block
+    pub original_app_hash: Vec<u8>,
+}
+
+/// The outcome of a replay of a range of blocks
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// The replayed blocks, in order. The replay stops at the first block
+    /// whose app hash diverges from the original one.
+    pub blocks: Vec<ReplayedBlock>,
+    /// The first height at which the app hash diverged, if any
+    pub first_divergence: Option<BlockHeight>,
+}
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Replay the committed blocks in the range `from..=to` from the given
+    /// source, re-applying each of them with the same code paths as a
+    /// running node: `ProcessProposal`, `FinalizeBlock` and `Commit`. The
+    /// shell must hold a copy of the state as of the block before `from`.
+    ///
+    /// The app hash committed at each height is compared with the original
+    /// one and the replay stops at the first divergence.
+    pub fn replay_blocks(
+        &mut self,
+        source: &impl BlockSource,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> Result<ReplayReport> {
+        let next_height = self.storage.last_height.next_height();
+        if from != next_height {
+            return Err(Error::ReplayHeight(from, next_height));
+        }
+        let mut report = ReplayReport::default();
+        for height in from.0..=to.0 {
+            let height = BlockHeight(height);
+            let block =
+                source.block(height).ok_or(Error::ReplayMissing(height))?;
+
+            let proposal = self.process_proposal(RequestProcessProposal {
+                txs: block.txs.clone(),
+                ..Default::default()
+            });
+            if proposal.status != ProposalStatus::Accept as i32 {
+                return Err(Error::ReplayRejected(height));
+            }
+            let txs = proposal
+                .tx_results
+                .into_iter()
+                .zip(block.txs)
+                .map(|(result, tx)| ProcessedTx { tx, result })
+                .collect();
+            self.finalize_block(FinalizeBlock {
+                hash: block.hash,
+                header: block.header,
+                byzantine_validators: block.byzantine_validators,
+                txs,
+                proposer_address: block.proposer_address,
+            })?;
+            let app_hash = self.commit().data;
+
+            let diverged = app_hash != block.app_hash;
+            report.blocks.push(ReplayedBlock {
+                height,
+                app_hash,
+                original_app_hash: block.app_hash,
+            });
+            if diverged {
+                tracing::warn!(
+                    "The app hash of the replayed block at height {} diverged",
+                    height
+                );
+                report.first_divergence = Some(height);
+                break;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use namada::types::time::Duration;
+
+    use super::super::test_utils::*;
+    use super::*;
+
+    /// Commit a few empty blocks and record them
+    fn record_blocks(
+        shell: &mut TestShell,
+        num_blocks: u64,
+    ) -> BTreeMap<BlockHeight, RecordedBlock> {
+        let time = DateTimeUtc::now();
+        (0..num_blocks)
+            .map(|i| {
+                let height = shell.storage.last_height.next_height();
+                let mut block = RecordedBlock {
+                    hash: BlockHash([i as u8; 32]),
+                    header: FinalizeBlock::default().header,
+                    byzantine_validators: vec![],
+                    txs: vec![],
+                    proposer_address: vec![],
+                    app_hash: vec![],
+                };
+                block.header.time = time + Duration::seconds(i as i64);
+                shell
+                    .finalize_block(FinalizeBlock {
+                        hash: block.hash.clone(),
+                        header: block.header.clone(),
+                        byzantine_validators: vec![],
+                        txs: vec![],
+                        proposer_address: vec![],
+                    })
+                    .expect("Test failed");
+                block.app_hash = shell.commit().data;
+                (height, block)
+            })
+            .collect()
+    }
+
+    /// Test that replaying the recorded blocks against a fresh state commits
+    /// the same app hashes.
+    #[test]
+    fn test_replay_blocks() {
+        let (mut shell, _) = setup();
+        let blocks = record_blocks(&mut shell, 3);
+
+        let (mut fresh, _) = setup();
+        let report = fresh
+            .replay_blocks(&blocks, BlockHeight(1), BlockHeight(3))
+            .expect("Test failed");
+        assert_eq!(report.first_divergence, None);
+        assert_eq!(report.blocks.len(), 3);
+        for replayed in &report.blocks {
+            assert_eq!(replayed.app_hash, blocks[&replayed.height].app_hash);
+            assert_eq!(replayed.app_hash, replayed.original_app_hash);
+        }
+        assert_eq!(fresh.storage.last_height, shell.storage.last_height);
+    }
+
+    /// Test that the first height at which the app hash diverges is reported
+    /// and that the replay must start from the next height of the state.
+    #[test]
+    fn test_replay_blocks_divergence() {
+        let (mut shell, _) = setup();
+        let mut blocks = record_blocks(&mut shell, 3);
+        blocks.get_mut(&BlockHeight(2)).unwrap().app_hash = vec![0; 32];
+
+        let (mut fresh, _) = setup();
+        assert!(matches!(
+            fresh.replay_blocks(&blocks, BlockHeight(2), BlockHeight(3)),
+            Err(Error::ReplayHeight(BlockHeight(2), BlockHeight(1)))
+        ));
+        let report = fresh
+            .replay_blocks(&blocks, BlockHeight(1), BlockHeight(3))
+            .expect("Test failed");
+        assert_eq!(report.first_divergence, Some(BlockHeight(2)));
+        assert_eq!(report.blocks.len(), 2);
+    }
+}
@@ -1,10 +1,11 @@
 use std::convert::TryFrom;
 use std::future::Future;
-use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use futures::future::FutureExt;
+use namada::ledger::queries::QUERY_TIMEOUT_CODE;
 use namada::types::address::Address;
 #[cfg(not(feature = "abcipp"))]
 use namada::types::hash::Hash;
@@ -12,7 +13,7 @@ use namada::types::hash::Hash;
 use namada::types::storage::BlockHash;
 #[cfg(not(feature = "abcipp"))]
 use namada::types::transaction::hash_tx;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::Sender;
 use tower::Service;
 
 use super::super::Shell;
@@ -22,8 +23,18 @@ use super::abcipp_shim_types::shim::TxBytes;
 use super::abcipp_shim_types::shim::{Error, Request, Response};
 use crate::config;
 #[cfg(not(feature = "abcipp"))]
+use crate::facade::tendermint_proto::abci::response_process_proposal::ProposalStatus;
+#[cfg(not(feature = "abcipp"))]
 use crate::facade::tendermint_proto::abci::RequestBeginBlock;
+use crate::facade::tendermint_proto::abci::ResponseQuery;
 use crate::facade::tower_abci::{BoxError, Request as Req, Response as Resp};
+use crate::node::ledger::block_stream;
+use crate::node::ledger::storage::PersistentDBOptions;
+use crate::wasm_loader::WasmDirs;
+
+/// The default maximum time that a query waits for the shell's response. It
+/// doesn't bound the time that the shell spends on a query it already started.
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// The shim wraps the shell, which implements ABCI++.
 /// The shim makes a crude translation between the ABCI interface currently used
@@ -46,8 +57,9 @@ impl AbcippShim {
     /// shell.
     pub fn new(
         config: config::Ledger,
-        wasm_dir: PathBuf,
-        broadcast_sender: UnboundedSender<Vec<u8>>,
+        wasm_dirs: WasmDirs,
+        broadcast_sender: Sender<Vec<u8>>,
+        block_stream: block_stream::Sender,
         db_cache: &rocksdb::Cache,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
@@ -56,13 +68,22 @@ impl AbcippShim {
         // We can use an unbounded channel here, because tower-abci limits the
         // the number of requests that can come in
         let (shell_send, shell_recv) = std::sync::mpsc::channel();
+        let db_options = PersistentDBOptions::from(&config.shell);
+        let query_timeout = config
+            .shell
+            .query_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_QUERY_TIMEOUT);
+        let serve_snapshots = config.shell.serve_snapshots.unwrap_or(true);
         (
             Self {
                 service: Shell::new(
                     config,
-                    wasm_dir,
+                    wasm_dirs,
                     broadcast_sender,
+                    block_stream,
                     Some(db_cache),
+                    Some(&db_options),
                     vp_wasm_compilation_cache,
                     tx_wasm_compilation_cache,
                     native_token,
@@ -73,7 +94,11 @@ impl AbcippShim {
                 delivered_txs: vec![],
                 shell_recv,
             },
-            AbciService { shell_send },
+            AbciService {
+                shell_send,
+                query_timeout,
+                serve_snapshots,
+            },
         )
     }
 
@@ -89,6 +114,10 @@ impl AbcippShim {
     /// [`AbciService`].
     pub fn run(mut self) {
         while let Ok((req, resp_sender)) = self.shell_recv.recv() {
+            // Skip the queries that timed out while waiting for the shell
+            if matches!(req, Req::Query(_)) && resp_sender.is_closed() {
+                continue;
+            }
             let resp = match req {
                 Req::ProcessProposal(proposal) => self
                     .service
@@ -126,6 +155,22 @@ impl AbcippShim {
                 }
                 #[cfg(not(feature = "abcipp"))]
                 Req::BeginBlock(block) => {
+                    // The block has already been decided, so its header
+                    // cannot be rejected anymore. A header that fails the
+                    // verification shows that this node's state has diverged.
+                    let verified = self.service.verify_header((&block).into());
+                    if verified.status != ProposalStatus::Accept as i32 {
+                        tracing::error!(
+                            "The header of the decided block at height {} \
+                             failed the verification: {}",
+                            block
+                                .header
+                                .as_ref()
+                                .map(|header| header.height)
+                                .unwrap_or_default(),
+                            verified.info,
+                        );
+                    }
                     // we save this data to be forwarded to finalize later
                     self.begin_block_request = Some(block);
                     Ok(Resp::BeginBlock(Default::default()))
@@ -187,6 +232,11 @@ pub struct AbciService {
         Req,
         tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
     )>,
+    /// The maximum time that a query waits for the shell's response. The
+    /// shell is not interrupted when it elapses.
+    query_timeout: Duration,
+    /// Whether to serve the snapshots of the state to other nodes
+    serve_snapshots: bool,
 }
 
 /// The ABCI tower service implementation sends and receives messages to and
@@ -206,6 +256,28 @@ impl Service<Req> for AbciService {
     }
 
     fn call(&mut self, req: Req) -> Self::Future {
+        if !self.serve_snapshots {
+            // Serving snapshots is disabled, so there's nothing to list or to
+            // load and the shell is not involved. Offering and applying
+            // snapshots, used to state sync this node, still reach the shell.
+            match req {
+                Req::ListSnapshots(_) => {
+                    return async {
+                        Ok(Resp::ListSnapshots(Default::default()))
+                    }
+                    .boxed();
+                }
+                Req::LoadSnapshotChunk(_) => {
+                    return async {
+                        Ok(Resp::LoadSnapshotChunk(Default::default()))
+                    }
+                    .boxed();
+                }
+                _ => {}
+            }
+        }
+        let query_timeout =
+            matches!(req, Req::Query(_)).then_some(self.query_timeout);
         let (resp_send, recv) = tokio::sync::oneshot::channel();
         let result = self.shell_send.send((req, resp_send));
         Box::pin(
@@ -214,7 +286,24 @@ impl Service<Req> for AbciService {
                     // The shell has shut-down
                     return Err(err.into());
                 }
-                match recv.await {
+                let resp = match query_timeout {
+                    Some(query_timeout) => {
+                        match tokio::time::timeout(query_timeout, recv).await {
+                            Ok(resp) => resp,
+                            Err(_elapsed) => {
+                                tracing::info!(
+                                    "A query timed out after {:?}",
+                                    query_timeout
+                                );
+                                return Ok(query_timeout_response(
+                                    query_timeout,
+                                ));
+                            }
+                        }
+                    }
+                    None => recv.await,
+                };
+                match resp {
                     Ok(resp) => resp,
                     Err(err) => {
                         tracing::info!("ABCI response channel didn't respond");
@@ -226,3 +315,144 @@ impl Service<Req> for AbciService {
         )
     }
 }
+
+/// The response to a query that timed out, which can be retried
+fn query_timeout_response(query_timeout: Duration) -> Resp {
+    Resp::Query(ResponseQuery {
+        code: QUERY_TIMEOUT_CODE,
+        info: format!(
+            "The query timed out after {} ms, it can be retried",
+            query_timeout.as_millis()
+        ),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::facade::tendermint_proto::abci::{
+        RequestListSnapshots, RequestOfferSnapshot, RequestQuery,
+        ResponseListSnapshots, Snapshot,
+    };
+
+    /// Create a service whose requests are answered by a mock shell, which
+    /// responds to each of them with the given function
+    fn mock_shell_service(
+        query_timeout: Duration,
+        serve_snapshots: bool,
+        respond: impl Fn(Req) -> Resp + Send + 'static,
+    ) -> AbciService {
+        let (shell_send, shell_recv) = std::sync::mpsc::channel::<(
+            Req,
+            tokio::sync::oneshot::Sender<Result<Resp, BoxError>>,
+        )>();
+        thread::spawn(move || {
+            while let Ok((req, resp_sender)) = shell_recv.recv() {
+                let _ = resp_sender.send(Ok(respond(req)));
+            }
+        });
+        AbciService {
+            shell_send,
+            query_timeout,
+            serve_snapshots,
+        }
+    }
+
+    /// Create a service whose queries are answered by a slow shell, which
+    /// takes the given time to respond to each of them
+    fn slow_shell_service(
+        query_timeout: Duration,
+        response_time: Duration,
+    ) -> AbciService {
+        mock_shell_service(query_timeout, true, move |_req| {
+            thread::sleep(response_time);
+            Resp::Query(Default::default())
+        })
+    }
+
+    /// Create a service whose shell has a snapshot to serve
+    fn snapshot_shell_service(serve_snapshots: bool) -> AbciService {
+        mock_shell_service(DEFAULT_QUERY_TIMEOUT, serve_snapshots, |req| {
+            match req {
+                Req::ListSnapshots(_) => {
+                    Resp::ListSnapshots(ResponseListSnapshots {
+                        snapshots: vec![Snapshot {
+                            height: 1,
+                            ..Default::default()
+                        }],
+                    })
+                }
+                Req::OfferSnapshot(_) => {
+                    Resp::OfferSnapshot(Default::default())
+                }
+                _ => panic!("Unexpected request {:?}", req),
+            }
+        })
+    }
+
+    /// Test that a query that the shell responds to just under the timeout
+    /// succeeds, while a query that takes just over the timeout is aborted
+    /// with a timeout response.
+    #[tokio::test]
+    async fn test_query_timeout() {
+        let query_timeout = Duration::from_millis(300);
+        let margin = Duration::from_millis(100);
+
+        let mut service =
+            slow_shell_service(query_timeout, query_timeout - margin);
+        let resp = service
+            .call(Req::Query(RequestQuery::default()))
+            .await
+            .unwrap();
+        match resp {
+            Resp::Query(resp) => assert_eq!(resp.code, 0),
+            _ => panic!("Unexpected response {:?}", resp),
+        }
+
+        let mut service =
+            slow_shell_service(query_timeout, query_timeout + margin);
+        let resp = service
+            .call(Req::Query(RequestQuery::default()))
+            .await
+            .unwrap();
+        match resp {
+            Resp::Query(resp) => {
+                assert_eq!(resp.code, QUERY_TIMEOUT_CODE);
+                assert!(resp.info.contains("timed out"), "{}", resp.info);
+            }
+            _ => panic!("Unexpected response {:?}", resp),
+        }
+    }
+    /// Test that no snapshots are listed when serving them is disabled, while
+    /// the snapshots offered to state sync this node still reach the shell.
+    #[tokio::test]
+    async fn test_snapshot_serving_disabled() {
+        let mut service = snapshot_shell_service(true);
+        let resp = service
+            .call(Req::ListSnapshots(RequestListSnapshots::default()))
+            .await
+            .unwrap();
+        match resp {
+            Resp::ListSnapshots(resp) => assert_eq!(resp.snapshots.len(), 1),
+            _ => panic!("Unexpected response {:?}", resp),
+        }
+
+        let mut service = snapshot_shell_service(false);
+        let resp = service
+            .call(Req::ListSnapshots(RequestListSnapshots::default()))
+            .await
+            .unwrap();
+        match resp {
+            Resp::ListSnapshots(resp) => assert!(resp.snapshots.is_empty()),
+            _ => panic!("Unexpected response {:?}", resp),
+        }
+        let resp = service
+            .call(Req::OfferSnapshot(RequestOfferSnapshot::default()))
+            .await
+            .unwrap();
+        assert!(matches!(resp, Resp::OfferSnapshot(_)));
+    }
+}
@@ -205,10 +205,34 @@ pub mod shim {
             Misbehavior as Evidence, RequestFinalizeBlock,
         };
 
-        pub struct VerifyHeader;
+        /// The parts of a proposed block's header that are checked when
+        /// verifying it
+        #[derive(Debug, Clone)]
+        pub struct VerifyHeader {
+            /// The app hash that the proposer claims the previous block
+            /// committed to
+            pub app_hash: Vec<u8>,
+            /// Tendermint raw hash of the proposer's consensus key
+            pub proposer_address: Vec<u8>,
+            /// Timestamp of the proposed block
+            pub time: DateTimeUtc,
+        }
 
         pub struct RevertProposal;
 
+        #[cfg(not(feature = "abcipp"))]
+        impl From<&RequestBeginBlock> for VerifyHeader {
+            fn from(req: &RequestBeginBlock) -> VerifyHeader {
+                let header = req.header.as_ref().unwrap();
+                VerifyHeader {
+                    app_hash: header.app_hash.clone(),
+                    proposer_address: header.proposer_address.clone(),
+                    time: DateTimeUtc::try_from(header.time.clone().unwrap())
+                        .unwrap(),
+                }
+            }
+        }
+
         /// A Tx and the result of calling Process Proposal on it
         #[derive(Debug, Clone)]
         pub struct ProcessedTx {
@@ -221,6 +245,8 @@ pub mod shim {
             pub header: Header,
             pub byzantine_validators: Vec<Evidence>,
             pub txs: Vec<ProcessedTx>,
+            /// Tendermint raw hash of the proposer's consensus key
+            pub proposer_address: Vec<u8>,
         }
 
         #[cfg(feature = "abcipp")]
@@ -238,6 +264,7 @@ pub mod shim {
                     },
                     byzantine_validators: req.byzantine_validators,
                     txs: vec![],
+                    proposer_address: req.proposer_address,
                 }
             }
         }
@@ -260,6 +287,7 @@ pub mod shim {
                     },
                     byzantine_validators: req.byzantine_validators,
                     txs: vec![],
+                    proposer_address: header.proposer_address,
                 }
             }
         }
@@ -283,7 +311,10 @@ pub mod shim {
         };
 
         #[derive(Debug, Default)]
-        pub struct VerifyHeader;
+        pub struct VerifyHeader {
+            pub status: i32,
+            pub info: String,
+        }
 
         #[derive(Debug, Default, Clone)]
         pub struct TxResult {
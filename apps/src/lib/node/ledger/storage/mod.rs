@@ -10,12 +10,15 @@ use arse_merkle_tree::traits::Hasher;
 use arse_merkle_tree::H256;
 use blake2b_rs::{Blake2b, Blake2bBuilder};
 use namada::ledger::storage::{Storage, StorageHasher};
+use namada::types::hash::HashAlgorithm;
 
 #[derive(Default)]
 pub struct PersistentStorageHasher(Blake2bHasher);
 
 pub type PersistentDB = rocksdb::RocksDB;
 
+pub type PersistentDBOptions = rocksdb::RocksDBOptions;
+
 pub type PersistentStorage = Storage<PersistentDB, PersistentStorageHasher>;
 
 impl Hasher for PersistentStorageHasher {
@@ -29,6 +32,8 @@ impl Hasher for PersistentStorageHasher {
 }
 
 impl StorageHasher for PersistentStorageHasher {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake2b;
+
     fn hash(value: impl AsRef<[u8]>) -> H256 {
         let mut buf = [0u8; 32];
         let mut hasher = new_blake2b();
@@ -50,10 +55,12 @@ fn new_blake2b() -> Blake2b {
 
 #[cfg(test)]
 mod tests {
-    use namada::ledger::storage::types;
+    use namada::ledger::storage::{types, DB};
     use namada::types::address;
     use namada::types::chain::ChainId;
-    use namada::types::storage::{BlockHash, BlockHeight, Key};
+    use namada::types::hash::Hash;
+    use namada::types::storage::{BlockHash, BlockHeight, Header, Key};
+    use namada::types::time::DateTimeUtc;
     use proptest::collection::vec;
     use proptest::prelude::*;
     use proptest::test_runner::Config;
@@ -70,6 +77,7 @@ mod tests {
             ChainId::default(),
             address::nam(),
             None,
+            None,
         );
         let key = Key::parse("key").expect("cannot parse the key string");
         let value: u64 = 1;
@@ -117,6 +125,7 @@ mod tests {
             ChainId::default(),
             address::nam(),
             None,
+            None,
         );
         storage
             .begin_block(BlockHash::default(), BlockHeight(100))
@@ -143,6 +152,7 @@ mod tests {
             ChainId::default(),
             address::nam(),
             None,
+            None,
         );
         storage
             .load_last_state()
@@ -166,6 +176,7 @@ mod tests {
             ChainId::default(),
             address::nam(),
             None,
+            None,
         );
         storage
             .begin_block(BlockHash::default(), BlockHeight(100))
@@ -201,6 +212,77 @@ mod tests {
         }
     }
 
+    /// Test that the key-vals under a prefix are read as they were at an older
+    /// height, after some of them have been updated, deleted or added.
+    #[test]
+    fn test_iter_prefix_with_height() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage = PersistentStorage::open(
+            db_path.path(),
+            ChainId::default(),
+            address::nam(),
+            None,
+            None,
+        );
+        let prefix = Key::parse("prefix").expect("cannot parse the key string");
+        let key = |i: u64| {
+            prefix
+                .push(&format!("{}", i))
+                .expect("cannot push the key segment")
+        };
+
+        // At height 1, write the keys 0..5
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .expect("begin_block failed");
+        for i in 0..5_u64 {
+            storage
+                .write(&key(i), types::encode(&i))
+                .expect("write failed");
+        }
+        storage.commit().expect("commit failed");
+        let expected: Vec<(String, Vec<u8>)> = (0..5_u64)
+            .map(|i| (key(i).to_string(), types::encode(&i)))
+            .collect();
+
+        // At height 2, update the key 1, delete the key 3 and add the key 7
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(2))
+            .expect("begin_block failed");
+        storage
+            .write(&key(1), types::encode(&100_u64))
+            .expect("write failed");
+        storage.delete(&key(3)).expect("delete failed");
+        storage
+            .write(&key(7), types::encode(&7_u64))
+            .expect("write failed");
+        storage.commit().expect("commit failed");
+
+        let (kvs, _gas) = storage
+            .iter_prefix_with_height(&prefix, BlockHeight(1))
+            .expect("iter_prefix_with_height failed");
+        assert_eq!(kvs, expected);
+
+        // At the last height, it reads the latest state
+        let (kvs, _gas) = storage
+            .iter_prefix_with_height(&prefix, BlockHeight(2))
+            .expect("iter_prefix_with_height failed");
+        let latest: Vec<(String, Vec<u8>)> = storage
+            .iter_prefix(&prefix)
+            .0
+            .map(|(key, val, _gas)| (key, val))
+            .collect();
+        assert_eq!(kvs, latest);
+        assert_eq!(
+            kvs.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>(),
+            [0, 1, 2, 4, 7]
+                .into_iter()
+                .map(|i| key(i).to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_validity_predicate() {
         let db_path =
@@ -210,6 +292,7 @@ mod tests {
             ChainId::default(),
             address::nam(),
             None,
+            None,
         );
         storage
             .begin_block(BlockHash::default(), BlockHeight(100))
@@ -268,6 +351,7 @@ mod tests {
             ChainId::default(),
             address::nam(),
             None,
+            None,
         );
 
         // 1. For each `blocks_write_value`, write the current block height if
@@ -344,4 +428,122 @@ mod tests {
 
         Ok(())
     }
+
+    /// Test that the historical state of the blocks committed before the
+    /// oldest retained height is pruned, while the state at and after it
+    /// remains readable, also after the DB is reopened.
+    #[test]
+    fn test_prune() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let open = || {
+            let mut storage = PersistentStorage::open(
+                db_path.path(),
+                ChainId::default(),
+                address::nam(),
+                None,
+                None,
+            );
+            storage.load_last_state().expect("Test failed");
+            storage
+        };
+        let mut storage = open();
+
+        // Write the height at each height
+        let key = Key::parse("key").expect("cannot parse the key string");
+        for height in 0..10 {
+            let height = BlockHeight(height);
+            storage
+                .begin_block(BlockHash::default(), height)
+                .expect("Test failed");
+            storage
+                .write(&key, types::encode(&height))
+                .expect("Test failed");
+            storage.commit().expect("Test failed");
+        }
+
+        let oldest_retained_height = BlockHeight(5);
+        storage.prune(oldest_retained_height).expect("Test failed");
+        drop(storage);
+        let storage = open();
+        assert_eq!(storage.oldest_retained_height, oldest_retained_height);
+
+        // The state at the oldest retained height remains
+        for height in 5..10 {
+            let height = BlockHeight(height);
+            let (value, _gas) =
+                storage.read_with_height(&key, height).expect("Test failed");
+            let value: BlockHeight = types::decode(value.unwrap()).unwrap();
+            assert_eq!(value, height);
+            assert!(storage.db.read_block_hash(height).unwrap().is_some());
+        }
+        // The state before it is pruned
+        let pruned_height = BlockHeight(4);
+        assert!(matches!(
+            storage.read_with_height(&key, pruned_height),
+            Err(namada::ledger::storage::Error::PrunedHeight {
+                height,
+                oldest_retained_height: BlockHeight(5),
+            }) if height == pruned_height
+        ));
+        assert!(storage.db.read_block_hash(pruned_height).unwrap().is_none());
+        assert!(storage
+            .db
+            .read_merkle_tree_stores(pruned_height)
+            .unwrap()
+            .is_none());
+    }
+
+    /// Test that the headers of the pruned blocks, which the IBC VP reads,
+    /// are kept across repeated pruning.
+    #[test]
+    fn test_prune_keeps_block_headers() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage = PersistentStorage::open(
+            db_path.path(),
+            ChainId::default(),
+            address::nam(),
+            None,
+            None,
+        );
+        let header = |height: u64| Header {
+            hash: Hash::sha256(height.to_le_bytes()),
+            time: DateTimeUtc::now(),
+            next_validators_hash: Hash::default(),
+        };
+        for height in 0..10 {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .expect("Test failed");
+            storage.set_header(header(height)).expect("Test failed");
+            storage.commit().expect("Test failed");
+        }
+
+        storage.prune(BlockHeight(3)).expect("Test failed");
+        storage.prune(BlockHeight(6)).expect("Test failed");
+
+        // The last committed block's header is served from memory, which no
+        // longer holds it after the commit
+        for height in 0..9 {
+            let (read, _gas) = storage
+                .get_block_header(Some(BlockHeight(height)))
+                .expect("Test failed");
+            assert_eq!(
+                read.expect("The header must be kept").hash,
+                header(height).hash
+            );
+        }
+        // The rest of the pruned blocks' state is removed
+        assert!(storage
+            .db
+            .read_block_hash(BlockHeight(2))
+            .unwrap()
+            .is_none());
+        assert!(storage
+            .db
+            .read_block_hash(BlockHeight(5))
+            .unwrap()
+            .is_none());
+    }
 }
@@ -39,7 +39,8 @@ use namada::ledger::storage::{
 };
 use namada::types::internal::TxQueue;
 use namada::types::storage::{
-    BlockHeight, BlockResults, Header, Key, KeySeg, KEY_SEGMENT_SEPARATOR,
+    BlockHash, BlockHeight, BlockResults, Header, Key, KeySeg,
+    KEY_SEGMENT_SEPARATOR,
 };
 use namada::types::time::DateTimeUtc;
 use rocksdb::{
@@ -47,6 +48,7 @@ use rocksdb::{
     ReadOptions, SliceTransform, WriteBatch, WriteOptions,
 };
 
+use crate::config;
 use crate::config::utils::num_of_threads;
 
 // TODO the DB schema will probably need some kind of versioning
@@ -63,10 +65,39 @@ pub struct RocksDB(rocksdb::DB);
 #[derive(Default)]
 pub struct RocksDBWriteBatch(WriteBatch);
 
+/// RocksDB tunable options. The options that are not set fall back to the
+/// defaults used in [`open`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RocksDBOptions {
+    /// Size of a single memtable (write buffer) in bytes
+    pub write_buffer_size_bytes: Option<u64>,
+    /// Maximum number of concurrent background jobs (compactions and
+    /// flushes)
+    pub max_background_jobs: Option<u32>,
+    /// Maximum total size of the write-ahead log files in bytes, after which
+    /// the column families backed by the oldest log file are flushed
+    pub max_total_wal_size_bytes: Option<u64>,
+    /// Incrementally sync the write-ahead log to disk every this many bytes
+    /// written
+    pub wal_bytes_per_sync: Option<u64>,
+}
+
+impl From<&config::Shell> for RocksDBOptions {
+    fn from(config: &config::Shell) -> Self {
+        Self {
+            write_buffer_size_bytes: config.db_write_buffer_size_bytes,
+            max_background_jobs: config.db_max_background_jobs,
+            max_total_wal_size_bytes: config.db_max_total_wal_size_bytes,
+            wal_bytes_per_sync: config.db_wal_bytes_per_sync,
+        }
+    }
+}
+
 /// Open RocksDB for the DB
 pub fn open(
     path: impl AsRef<Path>,
     cache: Option<&rocksdb::Cache>,
+    options: Option<&RocksDBOptions>,
 ) -> Result<RocksDB> {
     let logical_cores = num_cpus::get();
     let compaction_threads = num_of_threads(
@@ -90,6 +121,22 @@ pub fn open(
     cf_opts.set_bytes_per_sync(1048576);
     set_max_open_files(&mut cf_opts);
 
+    if let Some(options) = options {
+        if let Some(size) = options.write_buffer_size_bytes {
+            cf_opts.set_write_buffer_size(size as usize);
+        }
+        if let Some(jobs) = options.max_background_jobs {
+            tracing::info!("Using {} background jobs for RocksDB.", jobs);
+            cf_opts.set_max_background_jobs(jobs as i32);
+        }
+        if let Some(size) = options.max_total_wal_size_bytes {
+            cf_opts.set_max_total_wal_size(size);
+        }
+        if let Some(bytes) = options.wal_bytes_per_sync {
+            cf_opts.set_wal_bytes_per_sync(bytes);
+        }
+    }
+
     cf_opts.set_compression_type(rocksdb::DBCompressionType::Zstd);
     cf_opts.set_compression_options(0, 0, 0, 1024 * 1024);
     // TODO the recommended default `options.compaction_pri =
@@ -242,13 +289,15 @@ impl RocksDB {
 
 impl DB for RocksDB {
     type Cache = rocksdb::Cache;
+    type Options = RocksDBOptions;
     type WriteBatch = RocksDBWriteBatch;
 
     fn open(
         db_path: impl AsRef<std::path::Path>,
         cache: Option<&Self::Cache>,
+        options: Option<&Self::Options>,
     ) -> Self {
-        open(db_path, cache).expect("cannot open the DB")
+        open(db_path, cache, options).expect("cannot open the DB")
     }
 
     fn flush(&self, wait: bool) -> Result<()> {
@@ -577,6 +626,24 @@ impl DB for RocksDB {
         }
     }
 
+    fn read_block_hash(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<BlockHash>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"hash".to_owned())
+            .map_err(Error::KeyError)?;
+        let value = self
+            .0
+            .get(key.to_string())
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        match value {
+            Some(v) => Ok(Some(types::decode(v).map_err(Error::CodingError)?)),
+            None => Ok(None),
+        }
+    }
+
     fn read_merkle_tree_stores(
         &self,
         height: BlockHeight,
@@ -794,6 +861,55 @@ impl DB for RocksDB {
         Ok(prev_len)
     }
 
+    fn prune(&mut self, oldest_retained_height: BlockHeight) -> Result<()> {
+        let pruned_height = self
+            .read_oldest_retained_height()?
+            .unwrap_or(BlockHeight(0));
+        let mut batch = WriteBatch::default();
+        // The keys are ordered by their height by the `key_comparator`, so
+        // the range covers all the keys of the blocks below the height that
+        // haven't been pruned yet
+        batch.delete_range(
+            format!("{}/", pruned_height.raw()),
+            format!("{}/", oldest_retained_height.raw()),
+        );
+        // The block headers are written back after the range deletion. They
+        // are kept, because the IBC VP reads the headers of past blocks to
+        // validate the host's consensus states.
+        for height in pruned_height.0..oldest_retained_height.0 {
+            let key = Key::from(BlockHeight(height).to_db_key())
+                .push(&"header".to_owned())
+                .map_err(Error::KeyError)?
+                .to_string();
+            if let Some(header) = self
+                .0
+                .get(&key)
+                .map_err(|e| Error::DBError(e.into_string()))?
+            {
+                batch.put(key, header);
+            }
+        }
+        batch.put(
+            "oldest_retained_height",
+            types::encode(&oldest_retained_height),
+        );
+        self.exec_batch(batch)?;
+        self.flush(false)
+    }
+
+    fn read_oldest_retained_height(&self) -> Result<Option<BlockHeight>> {
+        match self
+            .0
+            .get("oldest_retained_height")
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
     fn batch() -> Self::WriteBatch {
         RocksDBWriteBatch::default()
     }
@@ -891,11 +1007,11 @@ impl<'iter> DBIter<'iter> for RocksDB {
         &'iter self,
         prefix: &Key,
     ) -> PersistentPrefixIterator<'iter> {
-        iter_prefix(self, prefix, Direction::Forward)
+        iter_prefix(self, "subspace/".to_owned(), prefix, Direction::Forward)
     }
 
     fn rev_iter_prefix(&'iter self, prefix: &Key) -> Self::PrefixIter {
-        iter_prefix(self, prefix, Direction::Reverse)
+        iter_prefix(self, "subspace/".to_owned(), prefix, Direction::Reverse)
     }
 
     fn iter_results(&'iter self) -> PersistentPrefixIterator<'iter> {
@@ -917,14 +1033,23 @@ impl<'iter> DBIter<'iter> for RocksDB {
         );
         PersistentPrefixIterator(PrefixIterator::new(iter, db_prefix))
     }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> PersistentPrefixIterator<'iter> {
+        let db_prefix = format!("{}/diffs/old/", height.to_db_key());
+        iter_prefix(self, db_prefix, prefix, Direction::Forward)
+    }
 }
 
 fn iter_prefix<'iter>(
     db: &'iter RocksDB,
+    db_prefix: String,
     prefix: &Key,
     direction: Direction,
 ) -> PersistentPrefixIterator<'iter> {
-    let db_prefix = "subspace/".to_owned();
     let prefix = format!("{}{}", db_prefix, prefix);
 
     let mut read_opts = ReadOptions::default();
@@ -1064,7 +1189,7 @@ mod test {
     #[test]
     fn test_load_state() {
         let dir = tempdir().unwrap();
-        let mut db = open(dir.path(), None).unwrap();
+        let mut db = open(dir.path(), None, None).unwrap();
 
         let mut batch = RocksDB::batch();
         let last_height = BlockHeight::default();
@@ -1113,7 +1238,7 @@ mod test {
     #[test]
     fn test_read() {
         let dir = tempdir().unwrap();
-        let mut db = open(dir.path(), None).unwrap();
+        let mut db = open(dir.path(), None, None).unwrap();
 
         let key = Key::parse("test").unwrap();
 
@@ -1148,4 +1273,45 @@ mod test {
             db.read_subspace_val(&key).expect("read should succeed");
         assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 2]));
     }
+
+    /// Test that the tunable options are applied when the DB is opened. RocksDB
+    /// persists the options it was opened with in an `OPTIONS-*` file.
+    #[test]
+    fn test_open_with_options() {
+        let dir = tempdir().unwrap();
+        let options = RocksDBOptions {
+            write_buffer_size_bytes: Some(8 * 1024 * 1024),
+            max_background_jobs: Some(3),
+            max_total_wal_size_bytes: Some(64 * 1024 * 1024),
+            wal_bytes_per_sync: Some(512 * 1024),
+        };
+        let db = open(dir.path(), None, Some(&options)).unwrap();
+        drop(db);
+
+        let options_file = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(|name| name.starts_with("OPTIONS-"))
+                    .unwrap_or_default()
+            })
+            .max()
+            .expect("RocksDB should have written its options file");
+        let persisted = std::fs::read_to_string(options_file).unwrap();
+        let persisted: Vec<&str> = persisted.lines().map(str::trim).collect();
+        for expected in [
+            "write_buffer_size=8388608",
+            "max_background_jobs=3",
+            "max_total_wal_size=67108864",
+            "wal_bytes_per_sync=524288",
+        ] {
+            assert!(
+                persisted.contains(&expected),
+                "Expected \"{}\" in the persisted options",
+                expected
+            );
+        }
+    }
 }
@@ -1,7 +1,9 @@
 use std::env;
+use std::net::{SocketAddr, TcpListener};
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
 use std::str::FromStr;
+use std::time::Duration;
 
 use borsh::BorshSerialize;
 use namada::types::chain::ChainId;
@@ -11,39 +13,92 @@ use serde_json::json;
 use thiserror::Error;
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 use tokio::process::Command;
 
 use crate::config;
+use crate::config::TendermintMode;
 use crate::facade::tendermint::Genesis;
 use crate::facade::tendermint_config::net::Address as TendermintAddress;
 use crate::facade::tendermint_config::{
-    Error as TendermintError, TendermintConfig,
+    Error as TendermintConfigError, TendermintConfig,
 };
 
 /// Env. var to output Tendermint log to stdout
 pub const ENV_VAR_TM_STDOUT: &str = "NAMADA_TM_STDOUT";
 
+/// The major and minor version of the supported Tendermint release series
+const TM_SUPPORTED_MAJOR_MINOR: (u64, u64) = (0, 1);
+
+/// The version suffix of the supported Tendermint releases, which depends on
+/// the ABCI flavour this node is built with
+#[cfg(feature = "abcipp")]
+const TM_SUPPORTED_SUFFIX: &str = "abcipp";
+#[cfg(not(feature = "abcipp"))]
+const TM_SUPPORTED_SUFFIX: &str = "abciplus";
+
+/// The maximum number of persistent peers that are probed for reachability
+/// on start-up
+const MAX_PROBED_PEERS: usize = 3;
+
+/// How long to wait for a probed persistent peer to accept a connection
+const PEER_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Error, Debug)]
-pub enum Error {
+pub enum TendermintError {
+    #[error(
+        "Tendermint binary not found at \"{0}\". Install Tendermint or set \
+         the TENDERMINT env var to its path."
+    )]
+    BinaryNotFound(String),
+    #[error("Failed to query the Tendermint version: {0}")]
+    Version(std::io::Error),
+    #[error("Couldn't parse the Tendermint version from \"{0}\"")]
+    UnparseableVersion(String),
+    #[error(
+        "Incompatible Tendermint version {0}, this node requires version {1}"
+    )]
+    IncompatibleVersion(String, String),
     #[error("Failed to initialize Tendermint: {0}")]
     Init(std::io::Error),
+    #[error("Tendermint init exited with {0}: {1}")]
+    InitExited(ExitStatus, String),
     #[error("Failed to load Tendermint config file: {0}")]
-    LoadConfig(TendermintError),
-    #[error("Failed to open Tendermint config for writing: {0}")]
-    OpenWriteConfig(std::io::Error),
+    LoadConfig(TendermintConfigError),
     #[error("Failed to serialize Tendermint config TOML to string: {0}")]
     ConfigSerializeToml(toml::ser::Error),
-    #[error("Failed to write Tendermint config: {0}")]
-    WriteConfig(std::io::Error),
+    #[error("Failed to write Tendermint config to {0}: {1}")]
+    WriteConfig(PathBuf, std::io::Error),
+    #[error("Failed to bind Tendermint RPC address {0}: {1}")]
+    RpcBind(SocketAddr, std::io::Error),
     #[error("Failed to start up Tendermint node: {0}")]
     StartUp(std::io::Error),
-    #[error("{0}")]
-    Runtime(String),
+    #[error("Tendermint process exited with {0}")]
+    ProcessExited(ExitStatus),
+    #[error("Failed to wait for the Tendermint process: {0}")]
+    Wait(std::io::Error),
     #[error("Failed to convert to String: {0:?}")]
     TendermintPath(std::ffi::OsString),
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = std::result::Result<T, TendermintError>;
+
+/// A problem with the configured persistent peers. These are not fatal, but a
+/// node with bad peers may start without ever gossiping.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PeerError {
+    #[error(
+        "No persistent peers are configured, the node may not be able to find \
+         any peers"
+    )]
+    NoPeers,
+    #[error("The persistent peer {0} is missing a node ID")]
+    MissingNodeId(String),
+    #[error("The persistent peer {0} is not a TCP address")]
+    NotTcp(String),
+    #[error("The persistent peer {0} has an empty host or a zero port")]
+    Unroutable(String),
+}
 
 /// Check if the TENDERMINT env var has been set and use that as the
 /// location of the tendermint binary. Otherwise, assume it is on path
@@ -57,7 +112,7 @@ fn from_env_or_default() -> Result<String> {
         }
         Err(std::env::VarError::NotPresent) => Ok(String::from("tendermint")),
         Err(std::env::VarError::NotUnicode(msg)) => {
-            Err(Error::TendermintPath(msg))
+            Err(TendermintError::TendermintPath(msg))
         }
     }
 }
@@ -84,15 +139,11 @@ pub async fn run(
         Path::new(&path).exists()
     };
 
+    // check that the binary is compatible before we touch the home dir
+    check_version(&tendermint_path).await?;
+
     // init and run a tendermint node child process
-    let output = Command::new(&tendermint_path)
-        .args(["init", &mode, "--home", &home_dir_string])
-        .output()
-        .await
-        .map_err(Error::Init)?;
-    if !output.status.success() {
-        panic!("Tendermint failed to initialize with {:#?}", output);
-    }
+    init(&tendermint_path, &mode, &home_dir_string).await?;
 
     #[cfg(feature = "dev")]
     {
@@ -107,7 +158,11 @@ pub async fn run(
     #[cfg(not(feature = "abcipp"))]
     write_tm_genesis(&home_dir, chain_id, genesis_time).await;
 
+    validate_persistent_peers(&config).await;
+
+    let rpc_address = config.rpc_address;
     update_tendermint_config(&home_dir, config).await?;
+    check_rpc_address_available(rpc_address)?;
 
     let mut tendermint_node = Command::new(&tendermint_path);
     tendermint_node.args([
@@ -129,7 +184,7 @@ pub async fn run(
     let mut tendermint_node = tendermint_node
         .kill_on_drop(true)
         .spawn()
-        .map_err(Error::StartUp)?;
+        .map_err(|err| spawn_error(&tendermint_path, err))?;
     tracing::info!("Tendermint node started");
 
     tokio::select! {
@@ -139,11 +194,11 @@ pub async fn run(
                     if status.success() {
                         Ok(())
                     } else {
-                        Err(Error::Runtime(status.to_string()))
+                        Err(TendermintError::ProcessExited(status))
                     }
                 },
                 Err(err) => {
-                    Err(Error::Runtime(err.to_string()))
+                    Err(TendermintError::Wait(err))
                 }
             }
         },
@@ -165,6 +220,176 @@ pub async fn run(
     }
 }
 
+/// Warn about the configured persistent peers that are malformed, and about
+/// the first few well-formed ones that are not reachable. This never fails,
+/// as the peers may simply not be up yet.
+async fn validate_persistent_peers(config: &config::Tendermint) {
+    let mut well_formed_peers = vec![];
+    for result in check_persistent_peers(
+        &config.tendermint_mode,
+        &config.p2p_persistent_peers,
+    ) {
+        match result {
+            Ok(peer) => well_formed_peers.push(peer),
+            Err(err) => tracing::warn!("{}", err),
+        }
+    }
+    let probes = well_formed_peers.into_iter().take(MAX_PROBED_PEERS).map(
+        |(host, port)| async move {
+            if !probe_peer(host, port).await {
+                tracing::warn!(
+                    "The persistent peer {}:{} is not reachable",
+                    host,
+                    port
+                );
+            }
+        },
+    );
+    futures::future::join_all(probes).await;
+}
+
+/// Check that the persistent peers are well-formed, returning the host and
+/// port of each well-formed peer. A seed node or a validator may have no
+/// persistent peers, but a full node without them may never find any.
+fn check_persistent_peers<'a>(
+    mode: &TendermintMode,
+    peers: &'a [TendermintAddress],
+) -> Vec<std::result::Result<(&'a str, u16), PeerError>> {
+    if peers.is_empty() {
+        return match mode {
+            TendermintMode::Full => vec![Err(PeerError::NoPeers)],
+            TendermintMode::Validator | TendermintMode::Seed => vec![],
+        };
+    }
+    peers.iter().map(check_peer).collect()
+}
+
+/// Check that a persistent peer is a routable TCP address with a node ID,
+/// e.g. `tcp://<node_id>@<host>:<port>`.
+fn check_peer(
+    peer: &TendermintAddress,
+) -> std::result::Result<(&str, u16), PeerError> {
+    match peer {
+        TendermintAddress::Tcp { peer_id: None, .. } => {
+            Err(PeerError::MissingNodeId(peer.to_string()))
+        }
+        TendermintAddress::Tcp { host, port, .. }
+            if host.is_empty() || *port == 0 =>
+        {
+            Err(PeerError::Unroutable(peer.to_string()))
+        }
+        TendermintAddress::Tcp { host, port, .. } => Ok((host, *port)),
+        TendermintAddress::Unix { .. } => {
+            Err(PeerError::NotTcp(peer.to_string()))
+        }
+    }
+}
+
+/// Check if a peer accepts a TCP connection within [`PEER_PROBE_TIMEOUT`].
+async fn probe_peer(host: &str, port: u16) -> bool {
+    matches!(
+        tokio::time::timeout(
+            PEER_PROBE_TIMEOUT,
+            TcpStream::connect((host, port))
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
+/// Check that the version reported by the Tendermint binary is from the
+/// supported release series.
+async fn check_version(tendermint_path: &str) -> Result<()> {
+    let output = Command::new(tendermint_path)
+        .arg("version")
+        .output()
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => {
+                TendermintError::BinaryNotFound(tendermint_path.to_owned())
+            }
+            _ => TendermintError::Version(err),
+        })?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    let (major, minor, suffix) = parse_version(&version)
+        .ok_or_else(|| TendermintError::UnparseableVersion(version.clone()))?;
+    let (supported_major, supported_minor) = TM_SUPPORTED_MAJOR_MINOR;
+    if (major, minor) != TM_SUPPORTED_MAJOR_MINOR
+        || !suffix.starts_with(TM_SUPPORTED_SUFFIX)
+    {
+        return Err(TendermintError::IncompatibleVersion(
+            version,
+            format!(
+                "{supported_major}.{supported_minor}.x-{TM_SUPPORTED_SUFFIX}"
+            ),
+        ));
+    }
+    tracing::debug!("Using Tendermint version {}", version);
+    Ok(())
+}
+
+/// Parse a Tendermint version string, e.g. `v0.1.4-abciplus`, into its major
+/// and minor version and a suffix following the patch version, if any.
+fn parse_version(version: &str) -> Option<(u64, u64, &str)> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let (numbers, suffix) = match version.split_once('-') {
+        Some((numbers, suffix)) => (numbers, suffix),
+        None => (version, ""),
+    };
+    let mut numbers = numbers.split('.').map(str::parse::<u64>);
+    let major = numbers.next()?.ok()?;
+    let minor = numbers.next()?.ok()?;
+    let _patch = numbers.next()?.ok()?;
+    if numbers.next().is_some() {
+        return None;
+    }
+    Some((major, minor, suffix))
+}
+
+/// Run `tendermint init` in the given home directory.
+async fn init(
+    tendermint_path: &str,
+    mode: &str,
+    home_dir_string: &str,
+) -> Result<()> {
+    let output = Command::new(tendermint_path)
+        .args(["init", mode, "--home", home_dir_string])
+        .output()
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => {
+                TendermintError::BinaryNotFound(tendermint_path.to_owned())
+            }
+            _ => TendermintError::Init(err),
+        })?;
+    if !output.status.success() {
+        return Err(TendermintError::InitExited(
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Map an error from spawning the Tendermint process, distinguishing a
+/// missing binary.
+fn spawn_error(tendermint_path: &str, err: std::io::Error) -> TendermintError {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => {
+            TendermintError::BinaryNotFound(tendermint_path.to_owned())
+        }
+        _ => TendermintError::StartUp(err),
+    }
+}
+
+/// Check that the Tendermint RPC address can be bound before starting the
+/// node, so that we can report it instead of an opaque process exit.
+fn check_rpc_address_available(rpc_address: SocketAddr) -> Result<()> {
+    TcpListener::bind(rpc_address)
+        .map(drop)
+        .map_err(|err| TendermintError::RpcBind(rpc_address, err))
+}
+
 pub fn reset(tendermint_dir: impl AsRef<Path>) -> Result<()> {
     let tendermint_path = from_env_or_default()?;
     let tendermint_dir = tendermint_dir.as_ref().to_string_lossy();
@@ -302,8 +527,8 @@ async fn update_tendermint_config(
 ) -> Result<()> {
     let home_dir = home_dir.as_ref();
     let path = home_dir.join("config").join("config.toml");
-    let mut config =
-        TendermintConfig::load_toml_file(&path).map_err(Error::LoadConfig)?;
+    let mut config = TendermintConfig::load_toml_file(&path)
+        .map_err(TendermintError::LoadConfig)?;
 
     config.p2p.laddr =
         TendermintAddress::from_str(&tendermint_config.p2p_address.to_string())
@@ -342,17 +567,28 @@ async fn update_tendermint_config(
             tendermint_config.consensus_timeout_commit;
     }
 
-    let mut file = OpenOptions::new()
+    let config_str = toml::to_string(&config)
+        .map_err(TendermintError::ConfigSerializeToml)?;
+    write_tendermint_config(path, config_str).await
+}
+
+/// Overwrite an existing Tendermint config file with the given TOML string.
+async fn write_tendermint_config(
+    path: PathBuf,
+    config_str: String,
+) -> Result<()> {
+    let mut file = match OpenOptions::new()
         .write(true)
         .truncate(true)
-        .open(path)
+        .open(&path)
         .await
-        .map_err(Error::OpenWriteConfig)?;
-    let config_str =
-        toml::to_string(&config).map_err(Error::ConfigSerializeToml)?;
+    {
+        Ok(file) => file,
+        Err(err) => return Err(TendermintError::WriteConfig(path, err)),
+    };
     file.write_all(config_str.as_bytes())
         .await
-        .map_err(Error::WriteConfig)
+        .map_err(|err| TendermintError::WriteConfig(path, err))
 }
 
 async fn write_tm_genesis(
@@ -403,3 +639,198 @@ async fn write_tm_genesis(
         .await
         .expect("Couldn't write the Tendermint genesis file");
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Test that a missing Tendermint binary is reported as such.
+    #[tokio::test]
+    async fn test_init_binary_not_found() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let tendermint_path = home_dir
+            .path()
+            .join("no-tendermint-here")
+            .to_string_lossy()
+            .to_string();
+        let home_dir_string = home_dir.path().to_string_lossy().to_string();
+
+        let result = init(&tendermint_path, "full", &home_dir_string).await;
+        match result {
+            Err(TendermintError::BinaryNotFound(path)) => {
+                assert_eq!(path, tendermint_path)
+            }
+            _ => panic!("Expected binary not found error, got {:?}", result),
+        }
+    }
+
+    /// Create an executable script in the given dir that prints the given
+    /// version, to stand in for a Tendermint binary.
+    #[cfg(unix)]
+    fn stub_tendermint(dir: &Path, version: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("tendermint");
+        std::fs::write(&path, format!("#!/bin/sh\necho '{version}'\n"))
+            .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// Test that a failed `tendermint init` is reported with its stderr.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_init_exited() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tendermint");
+        std::fs::write(&path, "#!/bin/sh\necho 'init failed' >&2\nexit 1\n")
+            .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        let tendermint_path = path.to_string_lossy().to_string();
+        let home_dir_string = dir.path().to_string_lossy().to_string();
+
+        let result = init(&tendermint_path, "full", &home_dir_string).await;
+        match result {
+            Err(TendermintError::InitExited(status, stderr)) => {
+                assert_eq!(status.code(), Some(1));
+                assert_eq!(stderr, "init failed");
+            }
+            _ => panic!("Expected init exited error, got {:?}", result),
+        }
+    }
+
+    /// Test the version check against a stub binary reporting a supported, an
+    /// unsupported and an unparseable version.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_check_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let supported = format!("0.1.4-{TM_SUPPORTED_SUFFIX}");
+        let tendermint_path = stub_tendermint(dir.path(), &supported);
+        check_version(&tendermint_path).await.unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let tendermint_path = stub_tendermint(dir.path(), "0.34.21");
+        let result = check_version(&tendermint_path).await;
+        assert!(matches!(
+            result,
+            Err(TendermintError::IncompatibleVersion(version, _))
+                if version == "0.34.21"
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let tendermint_path = stub_tendermint(dir.path(), "not a version");
+        let result = check_version(&tendermint_path).await;
+        assert!(matches!(
+            result,
+            Err(TendermintError::UnparseableVersion(version))
+                if version == "not a version"
+        ));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v0.1.4-abciplus"), Some((0, 1, "abciplus")));
+        assert_eq!(parse_version("0.1.1-abcipp"), Some((0, 1, "abcipp")));
+        assert_eq!(parse_version("0.34.21"), Some((0, 34, "")));
+        assert_eq!(parse_version("0.34"), None);
+        assert_eq!(parse_version("0.34.x"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    /// Test that a failure to write the Tendermint config is reported with
+    /// the config's path.
+    #[tokio::test]
+    async fn test_write_config_failed() {
+        let home_dir = tempfile::tempdir().unwrap();
+        // The config directory doesn't exist, so the write must fail
+        let path = home_dir.path().join("config").join("config.toml");
+
+        let result =
+            write_tendermint_config(path.clone(), "".to_string()).await;
+        match result {
+            Err(TendermintError::WriteConfig(err_path, _)) => {
+                assert_eq!(err_path, path)
+            }
+            _ => panic!("Expected config write error, got {:?}", result),
+        }
+    }
+
+    /// Test the checks of well-formed and malformed persistent peers.
+    #[test]
+    fn test_check_peer() {
+        let node_id = "a".repeat(40);
+        let peer = |addr: &str| TendermintAddress::from_str(addr).unwrap();
+
+        let well_formed = peer(&format!("tcp://{node_id}@127.0.0.1:26656"));
+        assert_eq!(check_peer(&well_formed), Ok(("127.0.0.1", 26656)));
+        let well_formed = peer(&format!("{node_id}@example.com:26656"));
+        assert_eq!(check_peer(&well_formed), Ok(("example.com", 26656)));
+
+        let no_node_id = peer("tcp://127.0.0.1:26656");
+        assert!(matches!(
+            check_peer(&no_node_id),
+            Err(PeerError::MissingNodeId(_))
+        ));
+        let zero_port = peer(&format!("tcp://{node_id}@127.0.0.1:0"));
+        assert!(matches!(
+            check_peer(&zero_port),
+            Err(PeerError::Unroutable(_))
+        ));
+        let unix = peer("unix:///tmp/tendermint.sock");
+        assert!(matches!(check_peer(&unix), Err(PeerError::NotTcp(_))));
+
+        // A node ID that isn't 20 hex-encoded bytes is rejected already when
+        // the config is parsed
+        assert!(TendermintAddress::from_str(
+            "tcp://not-a-node-id@127.0.0.1:26656"
+        )
+        .is_err());
+        assert!(TendermintAddress::from_str(&format!(
+            "tcp://{}@127.0.0.1:26656",
+            "a".repeat(39)
+        ))
+        .is_err());
+    }
+
+    /// Test that only a full node is warned about having no persistent peers.
+    #[test]
+    fn test_check_no_persistent_peers() {
+        assert_eq!(
+            check_persistent_peers(&TendermintMode::Full, &[]),
+            vec![Err(PeerError::NoPeers)]
+        );
+        assert!(check_persistent_peers(&TendermintMode::Seed, &[]).is_empty());
+        assert!(
+            check_persistent_peers(&TendermintMode::Validator, &[]).is_empty()
+        );
+    }
+
+    /// Test the probing of a listening and a closed peer address.
+    #[tokio::test]
+    async fn test_probe_peer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(probe_peer("127.0.0.1", port).await);
+
+        drop(listener);
+        assert!(!probe_peer("127.0.0.1", port).await);
+    }
+
+    /// Test that an RPC address that's already taken is reported.
+    #[test]
+    fn test_rpc_address_taken() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let rpc_address = listener.local_addr().unwrap();
+
+        let result = check_rpc_address_available(rpc_address);
+        assert!(matches!(
+            result,
+            Err(TendermintError::RpcBind(addr, _)) if addr == rpc_address
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+//! An optional export of the traces and the metrics of the node to an
+//! OpenTelemetry collector, with OTLP over gRPC.
+//!
+//! The export is configured by the `otlp_endpoint` and `otlp_sampling_rate`
+//! settings of the ledger's config, read when the tracing subscriber is
+//! installed. When the endpoint is unset nothing is installed, so the export
+//! has no overhead. The traces are made of the spans of the ABCI requests and
+//! of the VPs, while the metrics count the requests and record the time it
+//! took to handle them.
+//!
+//! The exporters run in their own runtime, as the subscriber is installed
+//! before the node starts its runtime. A collector that's unreachable doesn't
+//! prevent the node from starting, the failed exports are logged as warnings.
+
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use opentelemetry::metrics::{
+    Counter, MeterProvider as _, MetricsError, ValueRecorder,
+};
+use opentelemetry::sdk::metrics::PushController;
+use opentelemetry::sdk::trace::{self, Sampler, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::TraceError;
+use opentelemetry::{global, runtime, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use thiserror::Error;
+use tokio::runtime::Runtime;
+
+use crate::config;
+
+/// The name of the service the traces and the metrics are attributed to
+const SERVICE_NAME: &str = "namada";
+
+/// How often the metrics are exported
+const METRICS_EXPORT_PERIOD: Duration = Duration::from_secs(10);
+
+/// The instruments of the metrics, set when the export is installed
+static METRICS: OnceCell<Metrics> = OnceCell::new();
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "Invalid OTLP sampling rate {0}, expected a number between 0 and 1"
+    )]
+    InvalidSamplingRate(f64),
+    #[error("Failed to start the runtime of the OTLP exporters: {0}")]
+    Runtime(std::io::Error),
+    #[error("Failed to install the OTLP traces exporter: {0}")]
+    Traces(TraceError),
+    #[error("Failed to install the OTLP metrics exporter: {0}")]
+    Metrics(MetricsError),
+}
+
+/// The configuration of the export
+#[derive(Clone, Debug, PartialEq)]
+pub struct OtlpConfig {
+    /// The endpoint of the collector
+    pub endpoint: String,
+    /// The ratio of the traces that are exported, between `0` and `1`
+    pub sampling_rate: f64,
+}
+
+impl OtlpConfig {
+    /// Read the configuration from the ledger's config, or `None` if the
+    /// export is not enabled
+    pub fn from_config(config: &config::Shell) -> Result<Option<Self>, Error> {
+        Self::parse(config.otlp_endpoint.clone(), config.otlp_sampling_rate)
+    }
+
+    fn parse(
+        endpoint: Option<String>,
+        sampling_rate: Option<f64>,
+    ) -> Result<Option<Self>, Error> {
+        let endpoint = match endpoint {
+            Some(endpoint) if !endpoint.trim().is_empty() => endpoint,
+            _ => return Ok(None),
+        };
+        let sampling_rate = match sampling_rate {
+            Some(rate) if (0.0..=1.0).contains(&rate) => rate,
+            Some(rate) => return Err(Error::InvalidSamplingRate(rate)),
+            None => 1.0,
+        };
+        Ok(Some(Self {
+            endpoint,
+            sampling_rate,
+        }))
+    }
+}
+
+/// The installed exporters. The pending traces and metrics are exported and
+/// the exporters are shut down when it's dropped.
+pub struct Telemetry {
+    _controller: PushController,
+    // Dropped last, as the exporters run in it
+    _runtime: Runtime,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        global::shutdown_tracer_provider();
+    }
+}
+
+impl std::fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Telemetry").finish()
+    }
+}
+
+/// Install the exporters of the traces and the metrics. Returns the tracer
+/// to be used by the tracing subscriber's OpenTelemetry layer and the
+/// exporters, which must be kept alive while the node runs.
+pub fn install(config: &OtlpConfig) -> Result<(Tracer, Telemetry), Error> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("otlp-exporter")
+        .enable_all()
+        .build()
+        .map_err(Error::Runtime)?;
+    let (tracer, controller) = {
+        // The exporters are spawned in the runtime
+        let _entered = runtime.enter();
+        install_exporters(config)?
+    };
+    Ok((
+        tracer,
+        Telemetry {
+            _controller: controller,
+            _runtime: runtime,
+        },
+    ))
+}
+
+fn install_exporters(
+    config: &OtlpConfig,
+) -> Result<(Tracer, PushController), Error> {
+    // The failed exports are reported to the global handler
+    let _ = global::set_error_handler(|err| {
+        tracing::warn!("Failed to export to OpenTelemetry: {}", err);
+    });
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(config.endpoint.clone()),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(Sampler::ParentBased(Box::new(
+                    Sampler::TraceIdRatioBased(config.sampling_rate),
+                )))
+                .with_resource(Resource::new([KeyValue::new(
+                    "service.name",
+                    SERVICE_NAME,
+                )])),
+        )
+        .install_batch(runtime::Tokio)
+        .map_err(Error::Traces)?;
+
+    let controller = opentelemetry_otlp::new_pipeline()
+        .metrics(tokio::spawn, opentelemetry::util::tokio_interval_stream)
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(config.endpoint.clone()),
+        )
+        .with_period(METRICS_EXPORT_PERIOD)
+        .build()
+        .map_err(Error::Metrics)?;
+    let meter = controller.provider().meter(SERVICE_NAME, None);
+    let _ = METRICS.set(Metrics {
+        requests: meter
+            .u64_counter("abci_requests")
+            .with_description("The number of ABCI requests handled")
+            .init(),
+        request_duration: meter
+            .f64_value_recorder("abci_request_duration_seconds")
+            .with_description("The time it took to handle an ABCI request")
+            .init(),
+    });
+    Ok((tracer, controller))
+}
+
+/// The instruments of the metrics
+struct Metrics {
+    /// The number of the handled requests, by their type
+    requests: Counter<u64>,
+    /// The time it took to handle the requests, by their type
+    request_duration: ValueRecorder<f64>,
+}
+
+/// Record the metrics of a handled ABCI request of the given type, if the
+/// export is installed
+pub fn record_request(request_type: &'static str, duration: Duration) {
+    if let Some(metrics) = METRICS.get() {
+        let attributes = [KeyValue::new("request", request_type)];
+        metrics.requests.add(1, &attributes);
+        metrics
+            .request_duration
+            .record(duration.as_secs_f64(), &attributes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    /// The path of the gRPC method exporting the traces
+    const EXPORT_TRACES_PATH: &str =
+        "/opentelemetry.proto.collector.trace.v1.TraceService/Export";
+
+    /// The requests received by the mock receiver, by their path
+    type Received = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    /// The exporters are installed globally, so the tests installing them
+    /// must not run concurrently
+    static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Start a mock OTLP receiver recording the requests it receives. It
+    /// answers them with an OK status, without a message.
+    fn start_mock_receiver(runtime: &Runtime) -> (SocketAddr, Received) {
+        let received = Received::default();
+        let recorder = received.clone();
+        let make_service = make_service_fn(move |_conn| {
+            let recorder = recorder.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let recorder = recorder.clone();
+                    async move {
+                        let path = req.uri().path().to_owned();
+                        let body = hyper::body::to_bytes(req.into_body())
+                            .await
+                            .unwrap_or_default();
+                        recorder.lock().unwrap().push((path, body.to_vec()));
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .header("content-type", "application/grpc")
+                                .header("grpc-status", "0")
+                                .body(Body::empty())
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+        let _entered = runtime.enter();
+        let server = Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(true)
+            .serve(make_service);
+        let address = server.local_addr();
+        runtime.spawn(server);
+        (address, received)
+    }
+
+    /// Test that the configuration is only set with an endpoint and that the
+    /// sampling rate must be a ratio.
+    #[test]
+    fn test_otlp_config() {
+        assert_eq!(OtlpConfig::parse(None, Some(0.5)).unwrap(), None);
+        assert_eq!(OtlpConfig::parse(Some(" ".into()), None).unwrap(), None);
+        let endpoint = "http://localhost:4317".to_string();
+        assert_eq!(
+            OtlpConfig::parse(Some(endpoint.clone()), None).unwrap(),
+            Some(OtlpConfig {
+                endpoint: endpoint.clone(),
+                sampling_rate: 1.0,
+            })
+        );
+        assert_eq!(
+            OtlpConfig::parse(Some(endpoint.clone()), Some(0.25)).unwrap(),
+            Some(OtlpConfig {
+                endpoint: endpoint.clone(),
+                sampling_rate: 0.25,
+            })
+        );
+        for rate in [1.5, -0.1, f64::NAN] {
+            assert!(matches!(
+                OtlpConfig::parse(Some(endpoint.clone()), Some(rate)),
+                Err(Error::InvalidSamplingRate(_))
+            ));
+        }
+    }
+
+    /// Test that the exporters are installed for an unreachable collector,
+    /// so that the node can start without it.
+    #[test]
+    fn test_install_unreachable_endpoint() {
+        let _lock = INSTALL_LOCK.lock().unwrap();
+        let config = OtlpConfig {
+            endpoint: "http://127.0.0.1:1".into(),
+            sampling_rate: 1.0,
+        };
+        let (_tracer, telemetry) =
+            install(&config).expect("An unreachable collector is not fatal");
+        drop(telemetry);
+    }
+
+    /// Test that the spans are exported to the collector.
+    #[test]
+    fn test_spans_exported() {
+        let _lock = INSTALL_LOCK.lock().unwrap();
+        let runtime = Runtime::new().unwrap();
+        let (address, received) = start_mock_receiver(&runtime);
+        let config = OtlpConfig {
+            endpoint: format!("http://{}", address),
+            sampling_rate: 1.0,
+        };
+        let (tracer, telemetry) = install(&config).unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("abci_request", request = "FinalizeBlock")
+                .in_scope(|| ());
+        });
+        // The pending spans are exported when the exporters are dropped
+        drop(telemetry);
+
+        let received = received.lock().unwrap();
+        let (_, body) = received
+            .iter()
+            .find(|(path, _)| path == EXPORT_TRACES_PATH)
+            .expect("The traces should be exported");
+        // The name of the span is encoded as is in the protobuf message
+        assert!(body
+            .windows(b"abci_request".len())
+            .any(|window| window == b"abci_request"));
+    }
+}
@@ -2,15 +2,18 @@
 use core::borrow::Borrow;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use data_encoding::HEXLOWER;
 use eyre::{eyre, WrapErr};
-use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 use tokio::io::AsyncReadExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 use crate::cli::safe_exit;
 use crate::config::DEFAULT_WASM_CHECKSUMS_FILE;
@@ -25,6 +28,10 @@ pub enum Error {
     WasmNotFound(String),
     #[error("Error while downloading {0}: {1}")]
     ServerError(String, String),
+    #[error("Error reading {0}: {1}")]
+    FileRead(String, std::io::Error),
+    #[error("Checksum mismatch for WASM {0}: expected {1}, got {2}")]
+    ChecksumMismatch(String, String, String),
 }
 
 /// A hash map where keys are simple file names and values their full file name
@@ -101,14 +108,163 @@ impl Checksums {
     }
 }
 
+/// The directories to load the WASM artifacts from.
+#[derive(Clone, Debug)]
+pub struct WasmDirs {
+    /// Directories, possibly read-only, that are searched in order for the
+    /// WASM artifacts before the writable directory. An artifact is only
+    /// loaded from a search directory if its checksum matches.
+    pub search: Vec<PathBuf>,
+    /// The directory with the WASM checksums, into which any artifacts that
+    /// are not found in the search directories are fetched.
+    pub writable: PathBuf,
+}
+
+impl WasmDirs {
+    /// WASM directories without any search directories.
+    pub fn new(writable: impl Into<PathBuf>) -> Self {
+        Self {
+            search: vec![],
+            writable: writable.into(),
+        }
+    }
+
+    /// Read the given WASM file from the first search directory that contains
+    /// it with a matching checksum, or from the writable directory or an
+    /// absolute path.
+    pub fn read_wasm(
+        &self,
+        file_path: impl AsRef<Path>,
+    ) -> eyre::Result<Vec<u8>> {
+        if !self.search.is_empty() {
+            let checksums = Checksums::read_checksums(&self.writable);
+            let name = file_path
+                .as_ref()
+                .file_name()
+                .and_then(|os_name| os_name.to_str());
+            if let Some(full_name) = name.and_then(|name| checksums.0.get(name))
+            {
+                if let Some(bytes) = find_wasm(&self.search, full_name) {
+                    return Ok(bytes);
+                }
+            }
+        }
+        read_wasm(&self.writable, file_path)
+    }
+
+    /// Read the WASM artifact whose SHA256 hash is the given hex-encoded one,
+    /// if it's one of the artifacts of the checksums.
+    pub fn read_wasm_by_hash(&self, hash: &str) -> Option<Vec<u8>> {
+        let hash = hash.to_lowercase();
+        let checksums = Checksums::read_checksums(&self.writable);
+        let name = checksums.0.iter().find_map(|(name, full_name)| {
+            (full_name.split('.').nth(1) == Some(hash.as_str()))
+                .then(|| name.clone())
+        })?;
+        let bytes = self.read_wasm(name).ok()?;
+        (wasm_hash(&bytes) == hash).then_some(bytes)
+    }
+
+    /// Read the given WASM file like [`WasmDirs::read_wasm`] or exit on
+    /// failure.
+    pub fn read_wasm_or_exit(&self, file_path: impl AsRef<Path>) -> Vec<u8> {
+        match self.read_wasm(file_path) {
+            Ok(wasm) => wasm,
+            Err(err) => {
+                eprintln!("Error reading wasm: {}", err);
+                safe_exit(1);
+            }
+        }
+    }
+}
+
+/// Find the WASM artifact with the given full file name, including its SHA256
+/// hash, in the first of the given directories that contains it with a
+/// matching hash. Artifacts with a mismatching hash are skipped.
+pub fn find_wasm(dirs: &[PathBuf], full_name: &str) -> Option<Vec<u8>> {
+    dirs.iter().find_map(|dir| {
+        let wasm_path = dir.join(full_name);
+        let bytes = fs::read(&wasm_path).ok()?;
+        match verify_wasm(full_name, &bytes) {
+            Ok(()) => Some(bytes),
+            Err(err) => {
+                tracing::warn!(
+                    "Skipping {}: {}",
+                    wasm_path.to_string_lossy(),
+                    err
+                );
+                None
+            }
+        }
+    })
+}
+
+/// Get the hex-encoded SHA256 hash of the given WASM bytes.
+fn wasm_hash(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    HEXLOWER.encode(&hasher.finalize())
+}
+
+/// Verify that the SHA256 hash in the given full file name of a WASM artifact,
+/// e.g. `tx_transfer.<hash>.wasm`, matches its bytes.
+fn verify_wasm(full_name: &str, bytes: &[u8]) -> Result<(), Error> {
+    let expected = full_name.split('.').nth(1).unwrap_or_default();
+    let actual = wasm_hash(bytes);
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(Error::ChecksumMismatch(
+            full_name.to_owned(),
+            expected.to_owned(),
+            actual,
+        ))
+    }
+}
+
+/// The default maximum number of WASMs that are fetched concurrently
+pub const DEFAULT_MAX_CONCURRENT_FETCHES: usize = 8;
+
+/// Options for [`pre_fetch_wasm`].
+#[derive(Clone, Copy, Debug)]
+pub struct PreFetchOptions {
+    /// The maximum number of WASMs that are fetched concurrently
+    pub max_concurrent: usize,
+    /// When set, the first WASM that fails to be fetched aborts the other
+    /// fetches. Otherwise, all the WASMs are attempted before the first
+    /// failure is reported.
+    pub fail_fast: bool,
+}
+
+impl Default for PreFetchOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT_FETCHES,
+            fail_fast: true,
+        }
+    }
+}
+
+impl From<&crate::config::Shell> for PreFetchOptions {
+    fn from(config: &crate::config::Shell) -> Self {
+        Self {
+            max_concurrent: config
+                .wasm_prefetch_max_concurrent
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_FETCHES),
+            fail_fast: !config.wasm_prefetch_continue_on_error,
+        }
+    }
+}
+
 /// Download all the pre-built wasms, or if they're already downloaded, verify
-/// their checksums.
-pub async fn pre_fetch_wasm(wasm_directory: impl AsRef<Path>) {
+/// their checksums. The wasms that are found in any of the search directories
+/// are not downloaded.
+pub async fn pre_fetch_wasm(wasm_dirs: &WasmDirs, options: PreFetchOptions) {
+    let wasm_directory = &wasm_dirs.writable;
     #[cfg(feature = "dev")]
     {
-        let checksums_path = wasm_directory
-            .as_ref()
-            .join(crate::config::DEFAULT_WASM_CHECKSUMS_FILE);
+        let checksums_path =
+            wasm_directory.join(crate::config::DEFAULT_WASM_CHECKSUMS_FILE);
         // If the checksums file doesn't exists ...
         if tokio::fs::canonicalize(&checksums_path).await.is_err() {
             tokio::fs::create_dir_all(&wasm_directory).await.unwrap();
@@ -132,129 +288,132 @@ pub async fn pre_fetch_wasm(wasm_directory: impl AsRef<Path>) {
     // load json with wasm hashes
     let checksums = Checksums::read_checksums_async(&wasm_directory).await;
 
-    join_all(checksums.0.into_iter().map(|(name, full_name)| {
-        let wasm_directory = wasm_directory.as_ref().to_owned();
+    let full_names = checksums.0.into_values().collect();
+    if let Err(err) = fetch_all_wasm(
+        wasm_directory,
+        &wasm_dirs.search,
+        full_names,
+        options,
+        download_wasm,
+    )
+    .await
+    {
+        eprintln!("Error fetching wasm: {}", err);
+        safe_exit(1);
+    }
+}
+
+/// Fetch the WASMs with the given full file names with [`fetch_wasm`], with
+/// at most [`PreFetchOptions::max_concurrent`] fetches at a time. The WASMs
+/// that are found in any of the search directories are not fetched.
+async fn fetch_all_wasm<F, Fut>(
+    wasm_directory: &Path,
+    search_dirs: &[PathBuf],
+    full_names: Vec<String>,
+    options: PreFetchOptions,
+    fetch: F,
+) -> Result<(), Error>
+where
+    F: FnOnce(String) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Result<Vec<u8>, Error>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(options.max_concurrent.max(1)));
+    let mut tasks = JoinSet::new();
+    for full_name in full_names {
+        let wasm_directory = wasm_directory.to_owned();
+        let search_dirs = search_dirs.to_owned();
+        let semaphore = semaphore.clone();
+        let fetch = fetch.clone();
 
         // Async check and download (if needed) each file
-        tokio::spawn(async move {
-            let wasm_path = wasm_directory.join(&full_name);
-            match tokio::fs::read(&wasm_path).await {
-                // if the file exist, first check the hash. If not matching
-                // download it again.
-                Ok(bytes) => {
-                    let mut hasher = Sha256::new();
-                    hasher.update(bytes);
-                    let result = HEXLOWER.encode(&hasher.finalize());
-                    let derived_name = format!(
-                        "{}.{}.wasm",
-                        &name.split('.').collect::<Vec<&str>>()[0],
-                        result
-                    );
-                    if full_name == derived_name {
-                        return;
-                    }
-                    tracing::info!(
-                        "WASM checksum mismatch: Got {}, expected {}. \
-                         Fetching new version...",
-                        &derived_name,
-                        &full_name
-                    );
-                    #[cfg(feature = "dev")]
-                    {
-                        // try to copy built file from the Namada WASM root dir
-                        if tokio::fs::copy(
-                            std::env::current_dir()
-                                .unwrap()
-                                .join(crate::config::DEFAULT_WASM_DIR)
-                                .join(&full_name),
-                            wasm_directory.join(&full_name),
-                        )
-                        .await
-                        .is_ok()
-                        {
-                            tracing::info!(
-                                "File {} copied from WASM root dir.",
-                                full_name
-                            );
-                            return;
-                        }
-                    }
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("The semaphore is never closed");
+            // The search directories are read-only, so a file that's found in
+            // one of them doesn't have to be fetched
+            if find_wasm(&search_dirs, &full_name).is_some() {
+                return Ok(());
+            }
+            fetch_wasm(&wasm_directory, &full_name, fetch).await
+        });
+    }
 
-                    let url = format!("{}/{}", S3_URL, full_name);
-                    match download_wasm(url).await {
-                        Ok(bytes) => {
-                            if let Err(e) =
-                                tokio::fs::write(wasm_path, &bytes).await
-                            {
-                                eprintln!(
-                                    "Error while creating file for {}: {}",
-                                    &name, e
-                                );
-                                safe_exit(1);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Error downloading wasm: {}", e);
-                            safe_exit(1);
-                        }
-                    }
-                }
-                // if the doesn't file exist, download it.
-                Err(err) => match err.kind() {
-                    std::io::ErrorKind::NotFound => {
-                        #[cfg(feature = "dev")]
-                        {
-                            // try to copy built file from the Namada WASM root
-                            // dir
-                            if tokio::fs::copy(
-                                std::env::current_dir()
-                                    .unwrap()
-                                    .join(crate::config::DEFAULT_WASM_DIR)
-                                    .join(&full_name),
-                                wasm_directory.join(&full_name),
-                            )
-                            .await
-                            .is_ok()
-                            {
-                                tracing::info!(
-                                    "File {} copied from WASM root dir.",
-                                    full_name
-                                );
-                                return;
-                            }
-                        }
-
-                        let url = format!("{}/{}", S3_URL, full_name);
-                        match download_wasm(url).await {
-                            Ok(bytes) => {
-                                if let Err(e) =
-                                    tokio::fs::write(wasm_path, &bytes).await
-                                {
-                                    eprintln!(
-                                        "Error while creating file for {}: {}",
-                                        &name, e
-                                    );
-                                    safe_exit(1);
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("Error downloading wasm: {}", e);
-                                safe_exit(1);
-                            }
-                        }
-                    }
-                    _ => {
-                        eprintln!(
-                            "Can't read {}.",
-                            wasm_path.as_os_str().to_string_lossy()
-                        );
-                        safe_exit(1);
-                    }
-                },
+    let mut first_error = None;
+    while let Some(result) = tasks.join_next().await {
+        let result = result
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+        if let Err(err) = result {
+            if options.fail_fast {
+                // Dropping the set aborts the remaining fetches
+                return Err(err);
             }
-        })
-    }))
-    .await;
+            tracing::error!("{}", err);
+            first_error.get_or_insert(err);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Make sure that the WASM with the given full file name is in the given
+/// directory and that its checksum matches. A missing or corrupted file is
+/// fetched again with the given `fetch` function from its URL. A fetched file
+/// whose checksum doesn't match is refused.
+async fn fetch_wasm<F, Fut>(
+    wasm_directory: &Path,
+    full_name: &str,
+    fetch: F,
+) -> Result<(), Error>
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, Error>>,
+{
+    let wasm_path = wasm_directory.join(full_name);
+    match tokio::fs::read(&wasm_path).await {
+        // if the file exist, first check the hash. If not matching
+        // download it again.
+        Ok(bytes) => match verify_wasm(full_name, &bytes) {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                tracing::info!("{}. Fetching new version...", err);
+            }
+        },
+        // if the file doesn't exist, download it.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(Error::FileRead(
+                wasm_path.to_string_lossy().into_owned(),
+                err,
+            ));
+        }
+    }
+
+    #[cfg(feature = "dev")]
+    {
+        // try to copy built file from the Namada WASM root dir
+        if tokio::fs::copy(
+            std::env::current_dir()
+                .unwrap()
+                .join(crate::config::DEFAULT_WASM_DIR)
+                .join(full_name),
+            &wasm_path,
+        )
+        .await
+        .is_ok()
+        {
+            tracing::info!("File {} copied from WASM root dir.", full_name);
+            return Ok(());
+        }
+    }
+
+    let url = format!("{}/{}", S3_URL, full_name);
+    let bytes = fetch(url).await?;
+    // Refuse to use a downloaded file that doesn't match its checksum
+    verify_wasm(full_name, &bytes)?;
+    tokio::fs::write(&wasm_path, &bytes)
+        .await
+        .map_err(|_| Error::FileWrite(wasm_path.to_string_lossy().into_owned()))
 }
 
 pub fn read_wasm(
@@ -326,3 +485,303 @@ async fn download_wasm(url: String) -> Result<Vec<u8>, Error> {
         Err(e) => Err(Error::Download(url, e)),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::future::BoxFuture;
+    use futures::FutureExt;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    /// Get the full file name of a WASM with the given bytes
+    fn full_name(bytes: &[u8]) -> String {
+        format!("tx_test.{}.wasm", wasm_hash(bytes))
+    }
+
+    /// Test that the first search directory with the WASM is used.
+    #[test]
+    fn test_find_wasm_in_second_dir() {
+        let (first, second) = (tempdir().unwrap(), tempdir().unwrap());
+        let dirs = vec![first.path().to_owned(), second.path().to_owned()];
+        let wasm = b"wasm".to_vec();
+        let full_name = full_name(&wasm);
+
+        assert_eq!(find_wasm(&dirs, &full_name), None);
+
+        fs::write(second.path().join(&full_name), &wasm).unwrap();
+        assert_eq!(find_wasm(&dirs, &full_name), Some(wasm));
+    }
+
+    /// Test that a WASM with a mismatching checksum in an earlier search
+    /// directory is skipped.
+    #[test]
+    fn test_find_wasm_skips_stale_artifact() {
+        let (first, second) = (tempdir().unwrap(), tempdir().unwrap());
+        let dirs = vec![first.path().to_owned(), second.path().to_owned()];
+        let wasm = b"wasm".to_vec();
+        let full_name = full_name(&wasm);
+
+        fs::write(first.path().join(&full_name), b"stale").unwrap();
+        assert_eq!(find_wasm(&dirs, &full_name), None);
+
+        fs::write(second.path().join(&full_name), &wasm).unwrap();
+        assert_eq!(find_wasm(&dirs, &full_name), Some(wasm));
+    }
+
+    /// Test that the WASM is read from the search directories via the
+    /// checksums in the writable directory, falling back to the writable
+    /// directory.
+    #[test]
+    fn test_read_wasm_from_search_dirs() {
+        let (first, second) = (tempdir().unwrap(), tempdir().unwrap());
+        let writable = tempdir().unwrap();
+        let wasm = b"wasm".to_vec();
+        let full_name = full_name(&wasm);
+        let checksums = Checksums(HashMap::from_iter([(
+            "tx_test.wasm".to_owned(),
+            full_name.clone(),
+        )]));
+        fs::write(
+            writable.path().join(DEFAULT_WASM_CHECKSUMS_FILE),
+            serde_json::to_vec(&checksums).unwrap(),
+        )
+        .unwrap();
+        let wasm_dirs = WasmDirs {
+            search: vec![first.path().to_owned(), second.path().to_owned()],
+            writable: writable.path().to_owned(),
+        };
+
+        // Not found anywhere
+        assert!(wasm_dirs.read_wasm("tx_test.wasm").is_err());
+
+        // Found in the writable dir
+        fs::write(writable.path().join(&full_name), b"writable").unwrap();
+        assert_eq!(
+            wasm_dirs.read_wasm("tx_test.wasm").unwrap(),
+            b"writable".to_vec()
+        );
+
+        // A stale artifact in the first dir is skipped in favor of the second
+        fs::write(first.path().join(&full_name), b"stale").unwrap();
+        fs::write(second.path().join(&full_name), &wasm).unwrap();
+        assert_eq!(wasm_dirs.read_wasm("tx_test.wasm").unwrap(), wasm);
+    }
+
+    /// Test that a WASM is read by its hash only if its bytes match it.
+    #[test]
+    fn test_read_wasm_by_hash() {
+        let writable = tempdir().unwrap();
+        let wasm = b"wasm".to_vec();
+        let hash = wasm_hash(&wasm);
+        let full_name = full_name(&wasm);
+        let checksums = Checksums(HashMap::from_iter([(
+            "tx_test.wasm".to_owned(),
+            full_name.clone(),
+        )]));
+        fs::write(
+            writable.path().join(DEFAULT_WASM_CHECKSUMS_FILE),
+            serde_json::to_vec(&checksums).unwrap(),
+        )
+        .unwrap();
+        let wasm_dirs = WasmDirs::new(writable.path());
+
+        // Unknown hash
+        assert_eq!(wasm_dirs.read_wasm_by_hash(&wasm_hash(b"other")), None);
+
+        // Known hash, but the artifact doesn't match it
+        fs::write(writable.path().join(&full_name), b"corrupted").unwrap();
+        assert_eq!(wasm_dirs.read_wasm_by_hash(&hash), None);
+
+        // The hash may be upper-case
+        fs::write(writable.path().join(&full_name), &wasm).unwrap();
+        assert_eq!(
+            wasm_dirs.read_wasm_by_hash(&hash.to_uppercase()),
+            Some(wasm)
+        );
+    }
+
+    /// Test that a cached WASM with a mismatching checksum is fetched again.
+    #[tokio::test]
+    async fn test_fetch_wasm_replaces_corrupted_file() {
+        let wasm_dir = tempdir().unwrap();
+        let wasm = b"wasm".to_vec();
+        let full_name = full_name(&wasm);
+        let wasm_path = wasm_dir.path().join(&full_name);
+
+        // A valid cached file is not fetched
+        fs::write(&wasm_path, &wasm).unwrap();
+        fetch_wasm(wasm_dir.path(), &full_name, |url| async {
+            Err(Error::WasmNotFound(url))
+        })
+        .await
+        .unwrap();
+
+        // A corrupted cached file is fetched again
+        fs::write(&wasm_path, b"corrupted").unwrap();
+        let fetched = wasm.clone();
+        fetch_wasm(
+            wasm_dir.path(),
+            &full_name,
+            |_url| async move { Ok(fetched) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read(&wasm_path).unwrap(), wasm);
+    }
+
+    /// Test that a fetched WASM with a mismatching checksum is refused.
+    #[tokio::test]
+    async fn test_fetch_wasm_refuses_remote_mismatch() {
+        let wasm_dir = tempdir().unwrap();
+        let full_name = full_name(b"wasm");
+
+        let result = fetch_wasm(wasm_dir.path(), &full_name, |_url| async {
+            Ok(b"tampered".to_vec())
+        })
+        .await;
+        match result {
+            Err(Error::ChecksumMismatch(name, expected, actual)) => {
+                assert_eq!(name, full_name);
+                assert_eq!(expected, wasm_hash(b"wasm"));
+                assert_eq!(actual, wasm_hash(b"tampered"));
+            }
+            _ => panic!("Expected a checksum mismatch, got {:?}", result),
+        }
+        assert!(!wasm_dir.path().join(&full_name).exists());
+    }
+
+    /// A mock fetcher of the given WASMs that records the highest number of
+    /// concurrent fetches
+    #[derive(Clone)]
+    struct MockFetcher {
+        wasms: Arc<HashMap<String, Vec<u8>>>,
+        current: Arc<AtomicUsize>,
+        max: Arc<AtomicUsize>,
+    }
+
+    impl MockFetcher {
+        fn new(wasms: &[Vec<u8>]) -> Self {
+            Self {
+                wasms: Arc::new(
+                    wasms
+                        .iter()
+                        .map(|wasm| (full_name(wasm), wasm.clone()))
+                        .collect(),
+                ),
+                current: Default::default(),
+                max: Default::default(),
+            }
+        }
+
+        fn fetch(
+            self,
+        ) -> impl FnOnce(String) -> BoxFuture<'static, Result<Vec<u8>, Error>>
+               + Clone
+               + Send
+               + 'static {
+            move |url| {
+                async move {
+                    let current = self.current.fetch_add(1, Ordering::SeqCst);
+                    self.max.fetch_max(current + 1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    self.current.fetch_sub(1, Ordering::SeqCst);
+                    let full_name = url.rsplit('/').next().unwrap();
+                    self.wasms
+                        .get(full_name)
+                        .cloned()
+                        .ok_or(Error::WasmNotFound(url))
+                }
+                .boxed()
+            }
+        }
+    }
+
+    /// Test that the WASMs are fetched concurrently, but never more than the
+    /// configured maximum at a time.
+    #[tokio::test]
+    async fn test_fetch_all_wasm_bounded_concurrency() {
+        let wasm_dir = tempdir().unwrap();
+        let wasms: Vec<Vec<u8>> = (0..10)
+            .map(|i| format!("wasm {}", i).into_bytes())
+            .collect();
+        let fetcher = MockFetcher::new(&wasms);
+        let options = PreFetchOptions {
+            max_concurrent: 3,
+            fail_fast: true,
+        };
+
+        fetch_all_wasm(
+            wasm_dir.path(),
+            &[],
+            wasms.iter().map(|wasm| full_name(wasm)).collect(),
+            options,
+            fetcher.clone().fetch(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(fetcher.max.load(Ordering::SeqCst), 3);
+        for wasm in &wasms {
+            let path = wasm_dir.path().join(full_name(wasm));
+            assert_eq!(&fs::read(path).unwrap(), wasm);
+        }
+    }
+
+    /// Test that when a WASM fails to be fetched, the error is reported and
+    /// the other WASMs are still fetched unless failing fast.
+    #[tokio::test]
+    async fn test_fetch_all_wasm_failure() {
+        let wasms: Vec<Vec<u8>> =
+            (0..5).map(|i| format!("wasm {}", i).into_bytes()).collect();
+        let fetcher = MockFetcher::new(&wasms);
+        // The first WASM is unknown to the fetcher
+        let missing = full_name(b"missing");
+        let full_names: Vec<String> = std::iter::once(missing.clone())
+            .chain(wasms.iter().map(|wasm| full_name(wasm)))
+            .collect();
+
+        // Continue on error
+        let wasm_dir = tempdir().unwrap();
+        let options = PreFetchOptions {
+            max_concurrent: 1,
+            fail_fast: false,
+        };
+        let result = fetch_all_wasm(
+            wasm_dir.path(),
+            &[],
+            full_names.clone(),
+            options,
+            fetcher.clone().fetch(),
+        )
+        .await;
+        assert!(
+            matches!(&result, Err(Error::WasmNotFound(url)) if url.ends_with(&missing))
+        );
+        for wasm in &wasms {
+            assert!(wasm_dir.path().join(full_name(wasm)).exists());
+        }
+
+        // Fail fast
+        let wasm_dir = tempdir().unwrap();
+        let options = PreFetchOptions {
+            max_concurrent: 1,
+            fail_fast: true,
+        };
+        let result = fetch_all_wasm(
+            wasm_dir.path(),
+            &[],
+            full_names,
+            options,
+            fetcher.fetch(),
+        )
+        .await;
+        assert!(
+            matches!(&result, Err(Error::WasmNotFound(url)) if url.ends_with(&missing))
+        );
+        assert!(!wasm_dir.path().join(full_name(&wasms[4])).exists());
+    }
+}
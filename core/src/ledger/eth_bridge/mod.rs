@@ -0,0 +1,9 @@
+//! Ethereum bridge library code
+
+use crate::types::address::{Address, InternalAddress};
+
+/// Internal address of the Ethereum bridge
+pub const ADDRESS: Address = Address::Internal(InternalAddress::EthBridge);
+
+pub mod storage;
+pub mod transfer;
@@ -0,0 +1,185 @@
+//! storage helpers
+use super::transfer::TransferToEthereum;
+use super::ADDRESS;
+use crate::ledger::storage_api::{self, StorageRead, StorageWrite};
+use crate::types::storage::{DbKeySeg, Key, KeySeg};
+
+const QUEUE_STORAGE_KEY: &str = "queue";
+
+/// Get the key corresponding to @EthBridge/queue
+pub fn queue_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&QUEUE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given key is in the storage of the Ethereum bridge
+pub fn is_eth_bridge_key(key: &Key) -> bool {
+    matches!(key.segments.get(0), Some(DbKeySeg::AddressSeg(addr)) if addr == &ADDRESS)
+}
+
+/// Check if the given key is the key of the bridge's transfer queue
+pub fn is_queue_key(key: &Key) -> bool {
+    key == &queue_key()
+}
+
+/// Read all the transfers in the queue, from the oldest one. A malformed
+/// queue value is reported as an error.
+pub fn read_queue<S>(
+    storage: &S,
+) -> storage_api::Result<Vec<TransferToEthereum>>
+where
+    S: for<'iter> StorageRead<'iter>,
+{
+    Ok(storage.read(&queue_key())?.unwrap_or_default())
+}
+
+/// Get the oldest transfer in the queue without removing it. Returns
+/// `Ok(None)` if the queue is empty.
+pub fn peek_queue<S>(
+    storage: &S,
+) -> storage_api::Result<Option<TransferToEthereum>>
+where
+    S: for<'iter> StorageRead<'iter>,
+{
+    Ok(read_queue(storage)?.into_iter().next())
+}
+
+/// Add a transfer to the end of the queue.
+pub fn push_to_queue<S>(
+    storage: &mut S,
+    transfer: TransferToEthereum,
+) -> storage_api::Result<()>
+where
+    S: StorageWrite + for<'iter> StorageRead<'iter>,
+{
+    let mut queue = read_queue(storage)?;
+    queue.push(transfer);
+    storage.write(&queue_key(), queue)
+}
+
+/// Remove the oldest transfer from the queue and return it. Returns `Ok(None)`
+/// if the queue is empty.
+pub fn pop_from_queue<S>(
+    storage: &mut S,
+) -> storage_api::Result<Option<TransferToEthereum>>
+where
+    S: StorageWrite + for<'iter> StorageRead<'iter>,
+{
+    let mut queue = read_queue(storage)?;
+    if queue.is_empty() {
+        return Ok(None);
+    }
+    let transfer = queue.remove(0);
+    if queue.is_empty() {
+        storage.delete(&queue_key())?;
+    } else {
+        storage.write(&queue_key(), queue)?;
+    }
+    Ok(Some(transfer))
+}
+
+/// Remove a pending transfer from the queue, to cancel it. If the same
+/// transfer is queued more than once, only the oldest one is removed. Returns
+/// `Ok(false)` if the transfer is not in the queue, because it's no longer
+/// pending.
+pub fn remove_from_queue<S>(
+    storage: &mut S,
+    transfer: &TransferToEthereum,
+) -> storage_api::Result<bool>
+where
+    S: StorageWrite + for<'iter> StorageRead<'iter>,
+{
+    let mut queue = read_queue(storage)?;
+    let index = match queue.iter().position(|queued| queued == transfer) {
+        Some(index) => index,
+        None => return Ok(false),
+    };
+    queue.remove(index);
+    if queue.is_empty() {
+        storage.delete(&queue_key())?;
+    } else {
+        storage.write(&queue_key(), queue)?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::eth_bridge::transfer::EthAddress;
+    use crate::ledger::storage::testing::TestStorage;
+    use crate::types::{address, token};
+
+    fn transfer(amount: u64) -> TransferToEthereum {
+        TransferToEthereum {
+            sender: address::testing::established_address_1(),
+            recipient: EthAddress([1; 20]),
+            asset: address::eth(),
+            amount: token::Amount::from(amount),
+            fee: token::Amount::default(),
+        }
+    }
+
+    /// Test that queued transfers are popped in the order they were pushed.
+    #[test]
+    fn test_queue_round_trip() {
+        let mut storage = TestStorage::default();
+        assert_eq!(peek_queue(&storage).unwrap(), None);
+        assert_eq!(pop_from_queue(&mut storage).unwrap(), None);
+
+        push_to_queue(&mut storage, transfer(1)).unwrap();
+        push_to_queue(&mut storage, transfer(2)).unwrap();
+        assert_eq!(
+            read_queue(&storage).unwrap(),
+            vec![transfer(1), transfer(2)]
+        );
+        assert_eq!(peek_queue(&storage).unwrap(), Some(transfer(1)));
+
+        assert_eq!(pop_from_queue(&mut storage).unwrap(), Some(transfer(1)));
+        assert_eq!(peek_queue(&storage).unwrap(), Some(transfer(2)));
+        assert_eq!(pop_from_queue(&mut storage).unwrap(), Some(transfer(2)));
+        assert_eq!(pop_from_queue(&mut storage).unwrap(), None);
+        assert!(!StorageRead::has_key(&storage, &queue_key()).unwrap());
+    }
+
+    /// Test that a pending transfer can be removed from anywhere in the
+    /// queue, but not once it has been popped.
+    #[test]
+    fn test_remove_from_queue() {
+        let mut storage = TestStorage::default();
+        for amount in [1, 2, 3] {
+            push_to_queue(&mut storage, transfer(amount)).unwrap();
+        }
+
+        assert!(remove_from_queue(&mut storage, &transfer(2)).unwrap());
+        assert_eq!(
+            read_queue(&storage).unwrap(),
+            vec![transfer(1), transfer(3)]
+        );
+        assert!(!remove_from_queue(&mut storage, &transfer(2)).unwrap());
+
+        assert_eq!(pop_from_queue(&mut storage).unwrap(), Some(transfer(1)));
+        assert!(!remove_from_queue(&mut storage, &transfer(1)).unwrap());
+        assert!(remove_from_queue(&mut storage, &transfer(3)).unwrap());
+        assert!(!StorageRead::has_key(&storage, &queue_key()).unwrap());
+    }
+
+    /// Test that a malformed queue value is reported as an error.
+    #[test]
+    fn test_malformed_queue() {
+        let mut storage = TestStorage::default();
+        StorageWrite::write_bytes(
+            &mut storage,
+            &queue_key(),
+            b"arbitrary value",
+        )
+        .unwrap();
+
+        assert!(read_queue(&storage).is_err());
+        assert!(peek_queue(&storage).is_err());
+        assert!(pop_from_queue(&mut storage).is_err());
+        assert!(push_to_queue(&mut storage, transfer(1)).is_err());
+        assert!(remove_from_queue(&mut storage, &transfer(1)).is_err());
+    }
+}
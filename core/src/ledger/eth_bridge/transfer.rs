@@ -0,0 +1,211 @@
+//! Transfers from Namada to Ethereum
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use data_encoding::HEXLOWER_PERMISSIVE;
+use thiserror::Error;
+
+use crate::types::address::{self, Address};
+use crate::types::token;
+
+/// The prefix of a hex-encoded Ethereum address
+const ETH_ADDRESS_PREFIX: &str = "0x";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("Malformed Ethereum address {0:?}: {1}")]
+    MalformedEthAddress(String, String),
+    #[error("The transferred amount must not be zero")]
+    ZeroAmount,
+    #[error("The Ethereum recipient must not be the zero address")]
+    ZeroRecipient,
+    #[error("The asset {0} is not supported by the bridge")]
+    UnsupportedAsset(Address),
+}
+
+/// Result of a transfer to Ethereum construction or validation
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An Ethereum address
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct EthAddress(pub [u8; 20]);
+
+impl EthAddress {
+    /// Check if this is the zero address, to which transfers would be lost
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 20]
+    }
+}
+
+impl Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            ETH_ADDRESS_PREFIX,
+            HEXLOWER_PERMISSIVE.encode(&self.0)
+        )
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = Error;
+
+    /// Parse a hex-encoded Ethereum address, prefixed by `0x`
+    fn from_str(s: &str) -> Result<Self> {
+        let malformed = |reason: &str| {
+            Error::MalformedEthAddress(s.to_owned(), reason.into())
+        };
+        let hex = s
+            .strip_prefix(ETH_ADDRESS_PREFIX)
+            .ok_or_else(|| malformed("missing the 0x prefix"))?;
+        let bytes = HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|err| malformed(&err.to_string()))?;
+        let bytes: [u8; 20] = bytes
+            .try_into()
+            .map_err(|_| malformed("expected 20 bytes"))?;
+        Ok(Self(bytes))
+    }
+}
+
+/// A transfer of an asset from Namada to Ethereum
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TransferToEthereum {
+    /// The sender of the transfer on Namada
+    pub sender: Address,
+    /// The Ethereum address of the recipient
+    pub recipient: EthAddress,
+    /// The transferred asset
+    pub asset: Address,
+    /// The transferred amount
+    pub amount: token::Amount,
+    /// The fee paid to the relayer of the transfer, in the transferred asset
+    pub fee: token::Amount,
+}
+
+impl TransferToEthereum {
+    /// Construct a new transfer to Ethereum, checking that it's well-formed
+    pub fn new(
+        sender: Address,
+        recipient: EthAddress,
+        asset: Address,
+        amount: token::Amount,
+        fee: token::Amount,
+    ) -> Result<Self> {
+        let transfer = Self {
+            sender,
+            recipient,
+            asset,
+            amount,
+            fee,
+        };
+        transfer.validate()?;
+        Ok(transfer)
+    }
+
+    /// Check that the transfer is well-formed: it must transfer a non-zero
+    /// amount of a supported asset to a non-zero Ethereum address.
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == token::Amount::default() {
+            return Err(Error::ZeroAmount);
+        }
+        if self.recipient.is_zero() {
+            return Err(Error::ZeroRecipient);
+        }
+        if !is_supported_asset(&self.asset) {
+            return Err(Error::UnsupportedAsset(self.asset.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Check if the given asset may be transferred to Ethereum. Only the known
+/// tokens are supported.
+pub fn is_supported_asset(asset: &Address) -> bool {
+    address::tokens().contains_key(asset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "0x6b175474e89094c44da98b954eedeac495271d0f";
+
+    fn transfer(amount: u64) -> Result<TransferToEthereum> {
+        TransferToEthereum::new(
+            address::testing::established_address_1(),
+            RECIPIENT.parse()?,
+            address::eth(),
+            token::Amount::from(amount),
+            token::Amount::from(1),
+        )
+    }
+
+    /// Test that Ethereum addresses round-trip through their string
+    /// representation and that malformed addresses are rejected.
+    #[test]
+    fn test_eth_address_parsing() {
+        let recipient: EthAddress = RECIPIENT.parse().unwrap();
+        assert_eq!(recipient.to_string(), RECIPIENT);
+        // The hex digits may be upper case, as in checksummed addresses
+        let checksummed: EthAddress =
+            "0x6B175474E89094C44Da98b954EedeAC495271d0F"
+                .parse()
+                .unwrap();
+        assert_eq!(checksummed, recipient);
+
+        for malformed in [
+            "6b175474e89094c44da98b954eedeac495271d0f",
+            "0x6b175474e89094c44da98b954eedeac495271d0",
+            "0x6b175474e89094c44da98b954eedeac495271d0f00",
+            "0x6b175474e89094c44da98b954eedeac495271d0g",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    malformed.parse::<EthAddress>(),
+                    Err(Error::MalformedEthAddress(..))
+                ),
+                "{malformed}"
+            );
+        }
+    }
+
+    /// Test that only well-formed transfers can be constructed.
+    #[test]
+    fn test_transfer_validation() {
+        let valid = transfer(10).unwrap();
+        let decoded =
+            TransferToEthereum::try_from_slice(&valid.try_to_vec().unwrap())
+                .unwrap();
+        assert_eq!(decoded, valid);
+
+        assert_eq!(transfer(0), Err(Error::ZeroAmount));
+
+        let mut zero_recipient = valid.clone();
+        zero_recipient.recipient = EthAddress([0; 20]);
+        assert_eq!(zero_recipient.validate(), Err(Error::ZeroRecipient));
+
+        let mut unsupported = valid;
+        unsupported.asset = address::testing::established_address_2();
+        assert!(matches!(
+            unsupported.validate(),
+            Err(Error::UnsupportedAsset(_))
+        ));
+    }
+}
@@ -21,10 +21,26 @@ const COMPILE_GAS_PER_BYTE: u64 = 1;
 const BASE_TRANSACTION_FEE: u64 = 2;
 const PARALLEL_GAS_DIVIDER: u64 = 10;
 
-/// The maximum value should be less or equal to i64::MAX
-/// to avoid the gas overflow when sending this to ABCI
-const BLOCK_GAS_LIMIT: u64 = 10_000_000_000_000;
-const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
+/// The gas cost of a verification of a tx's signature in a VP
+pub const VERIFY_TX_SIG_GAS_COST: u64 = 1000;
+/// The gas cost per byte of a validation of untrusted wasm code
+pub const WASM_VALIDATION_GAS_PER_BYTE: u64 = 1;
+/// The gas cost of a wasm instruction
+pub const WASM_INSTRUCTION_GAS: u32 = 1;
+/// The gas cost per page of a growth of the wasm memory
+pub const WASM_MEMORY_GROW_GAS_PER_PAGE: u32 = 1;
+/// The gas cost per byte of the keys and of the values accessed in storage
+pub const STORAGE_ACCESS_GAS_PER_BYTE: u64 = 1;
+
+/// The version of the [`GasSchedule`], which must be incremented whenever any
+/// of its costs changes
+pub const GAS_SCHEDULE_VERSION: u32 = 1;
+
+/// The maximum gas of a block. The maximum value should be less or equal to
+/// i64::MAX to avoid the gas overflow when sending this to ABCI
+pub const BLOCK_GAS_LIMIT: u64 = 10_000_000_000_000;
+/// The maximum gas of a transaction
+pub const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
 
 /// The minimum gas cost for accessing the storage
 pub const MIN_STORAGE_GAS: u64 = 1;
@@ -32,6 +48,64 @@ pub const MIN_STORAGE_GAS: u64 = 1;
 /// Gas module result for functions that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The costs per operation charged by the gas meters, e.g. for clients to
+/// estimate the gas of a tx offline. The costs are fixed by the protocol, so
+/// a change of any of them comes with a new version.
+#[derive(
+    Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct GasSchedule {
+    /// The version of the schedule
+    pub version: u32,
+    /// The gas charged for every tx before it's applied
+    pub base_transaction_fee: u64,
+    /// The gas per byte of the wasm code of a tx or a VP, charged when it's
+    /// compiled
+    pub compile_gas_per_byte: u64,
+    /// The gas per byte of untrusted wasm code, charged when it's validated
+    pub wasm_validation_gas_per_byte: u64,
+    /// The gas of a wasm instruction
+    pub wasm_instruction_gas: u64,
+    /// The gas per page of a growth of the wasm memory
+    pub wasm_memory_grow_gas_per_page: u64,
+    /// The gas per byte of the keys and the values read from or written to
+    /// storage
+    pub storage_access_gas_per_byte: u64,
+    /// The minimum gas of an access to the storage
+    pub min_storage_gas: u64,
+    /// The gas of a verification of a tx's signature
+    pub verify_tx_sig_gas: u64,
+    /// The divider of the gas of the VPs run in parallel, other than the one
+    /// with the highest gas, which is charged in full
+    pub parallel_gas_divider: u64,
+    /// The maximum gas of a transaction
+    pub transaction_gas_limit: u64,
+    /// The maximum gas of a block. The block gas limit protocol parameter may
+    /// lower it below [`BLOCK_GAS_LIMIT`].
+    pub block_gas_limit: u64,
+}
+
+impl GasSchedule {
+    /// The schedule currently charged by the gas meters, with the protocol's
+    /// maximum gas of a block
+    pub fn current() -> Self {
+        Self {
+            version: GAS_SCHEDULE_VERSION,
+            base_transaction_fee: BASE_TRANSACTION_FEE,
+            compile_gas_per_byte: COMPILE_GAS_PER_BYTE,
+            wasm_validation_gas_per_byte: WASM_VALIDATION_GAS_PER_BYTE,
+            wasm_instruction_gas: WASM_INSTRUCTION_GAS as u64,
+            wasm_memory_grow_gas_per_page: WASM_MEMORY_GROW_GAS_PER_PAGE as u64,
+            storage_access_gas_per_byte: STORAGE_ACCESS_GAS_PER_BYTE,
+            min_storage_gas: MIN_STORAGE_GAS,
+            verify_tx_sig_gas: VERIFY_TX_SIG_GAS_COST,
+            parallel_gas_divider: PARALLEL_GAS_DIVIDER,
+            transaction_gas_limit: TRANSACTION_GAS_LIMIT,
+            block_gas_limit: BLOCK_GAS_LIMIT,
+        }
+    }
+}
+
 /// Gas metering in a block. Tracks the gas in a current block and a current
 /// transaction.
 #[derive(Debug, Default, Clone)]
@@ -226,6 +300,37 @@ mod tests {
 
     use super::*;
 
+    /// Test that the costs of the schedule are pinned to its version, so that
+    /// a change of any of them without a new version fails.
+    #[test]
+    fn test_gas_schedule_version() {
+        let expected = match GAS_SCHEDULE_VERSION {
+            1 => GasSchedule {
+                version: 1,
+                base_transaction_fee: 2,
+                compile_gas_per_byte: 1,
+                wasm_validation_gas_per_byte: 1,
+                wasm_instruction_gas: 1,
+                wasm_memory_grow_gas_per_page: 1,
+                storage_access_gas_per_byte: 1,
+                min_storage_gas: 1,
+                verify_tx_sig_gas: 1000,
+                parallel_gas_divider: 10,
+                transaction_gas_limit: 10_000_000_000,
+                block_gas_limit: 10_000_000_000_000,
+            },
+            version => panic!(
+                "Pin the costs of the gas schedule version {} in this test",
+                version
+            ),
+        };
+        assert_eq!(
+            GasSchedule::current(),
+            expected,
+            "The gas schedule changed, increment GAS_SCHEDULE_VERSION"
+        );
+    }
+
     proptest! {
         #[test]
         fn test_vp_gas_meter_add(gas in 0..TRANSACTION_GAS_LIMIT) {
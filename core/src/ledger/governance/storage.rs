@@ -1,6 +1,6 @@
 use crate::ledger::governance::ADDRESS;
 use crate::types::address::Address;
-use crate::types::storage::{DbKeySeg, Key, KeySeg};
+use crate::types::storage::{DbKeySeg, Key, KeyPattern, KeySeg};
 
 const PROPOSAL_PREFIX: &str = "proposal";
 const PROPOSAL_VOTE: &str = "vote";
@@ -388,6 +388,12 @@ pub fn get_commiting_proposals_prefix(epoch: u64) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the pattern of the committing proposal keys at the given epoch, which
+/// captures the proposal id
+pub fn get_commiting_proposals_pattern(epoch: u64) -> KeyPattern {
+    KeyPattern::exact(&get_commiting_proposals_prefix(epoch)).push_any()
+}
+
 /// Get the committing proposal key
 pub fn get_committing_proposals_key(id: u64, epoch: u64) -> Key {
     get_commiting_proposals_prefix(epoch)
@@ -1,7 +1,9 @@
 //! Functions to handle IBC modules
 
 use std::str::FromStr;
+use std::time::Duration;
 
+use borsh::BorshDeserialize;
 use sha2::Digest;
 use thiserror::Error;
 
@@ -73,11 +75,15 @@ use crate::ledger::ibc::data::{
     PacketReceipt,
 };
 use crate::ledger::ibc::storage;
+use crate::ledger::parameters::storage::get_max_ibc_packet_data_bytes_key;
 use crate::ledger::storage_api;
 use crate::tendermint::Time;
 use crate::tendermint_proto::{Error as ProtoError, Protobuf};
 use crate::types::address::{Address, InternalAddress};
-use crate::types::ibc::IbcEvent as NamadaIbcEvent;
+use crate::types::ibc::{
+    check_packet_data_size, IbcEvent as NamadaIbcEvent, PacketDataError,
+    DEFAULT_MAX_PACKET_DATA_BYTES,
+};
 use crate::types::storage::{BlockHeight, Key};
 use crate::types::time::Rfc3339String;
 use crate::types::token::{self, Amount};
@@ -117,6 +123,24 @@ pub enum Error {
     ReceivingToken(String),
     #[error("IBC storage error: {0}")]
     IbcStorage(storage::Error),
+    #[error("Packet data error: {0}")]
+    PacketData(PacketDataError),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClientStatusError {
+    #[error("The client is frozen: ID {client_id}, frozen height {height}")]
+    ClientFrozen { client_id: ClientId, height: Height },
+    #[error(
+        "The client has expired: ID {client_id}, latest consensus state at \
+         {timestamp}, trusting period {trusting_period:?}"
+    )]
+    ClientExpired {
+        client_id: ClientId,
+        timestamp: Timestamp,
+        trusting_period: Duration,
+    },
 }
 
 // This is needed to use `ibc::Handler::Error` with `IbcActions` in
@@ -629,6 +653,19 @@ pub trait IbcActions {
         timeout_height: Height,
         timeout_timestamp: Timestamp,
     ) -> std::result::Result<(), Self::Error> {
+        // check the size of the packet data before anything is written
+        let max_bytes_key = get_max_ibc_packet_data_bytes_key();
+        let max_bytes = match self.read_ibc_data(&max_bytes_key)? {
+            Some(value) => u64::try_from_slice(&value[..]).map_err(|e| {
+                Error::Channel(format!(
+                    "Decoding the max packet data size failed: {}",
+                    e
+                ))
+            })?,
+            None => DEFAULT_MAX_PACKET_DATA_BYTES,
+        };
+        check_packet_data_size(&data, max_bytes).map_err(Error::PacketData)?;
+
         // get and increment the next sequence send
         let seq_key = storage::next_sequence_send_key(&port_channel_id);
         let sequence = self.get_and_inc_sequence(&seq_key)?;
@@ -1213,6 +1250,42 @@ pub fn update_client(
     }
 }
 
+/// Check that a client is active to accept a packet or an update, i.e. that
+/// it isn't frozen and that its trusting period hasn't elapsed since the
+/// timestamp of its latest consensus state at the given time. The client is
+/// expired from the exact end of its trusting period.
+pub fn check_client_status(
+    client_id: &ClientId,
+    client_state: &AnyClientState,
+    consensus_state: &AnyConsensusState,
+    now: Timestamp,
+) -> std::result::Result<(), ClientStatusError> {
+    if let Some(height) = client_state.frozen_height() {
+        return Err(ClientStatusError::ClientFrozen {
+            client_id: client_id.clone(),
+            height,
+        });
+    }
+    let trusting_period = match client_state {
+        AnyClientState::Tendermint(cs) => cs.trusting_period,
+        // A mock client never expires
+        #[cfg(any(feature = "ibc-mocks-abcipp", feature = "ibc-mocks"))]
+        AnyClientState::Mock(_) => return Ok(()),
+    };
+    let timestamp = consensus_state.timestamp();
+    let expires_at = timestamp
+        .nanoseconds()
+        .saturating_add(trusting_period.as_nanos() as u64);
+    if now.nanoseconds() >= expires_at {
+        return Err(ClientStatusError::ClientExpired {
+            client_id: client_id.clone(),
+            timestamp,
+            trusting_period,
+        });
+    }
+    Ok(())
+}
+
 /// Returns a new client ID
 pub fn client_id(client_type: ClientType, counter: u64) -> Result<ClientId> {
     ClientId::new(client_type, counter).map_err(Error::ClientId)
@@ -1603,3 +1676,121 @@ pub fn make_timeout_event(packet: Packet) -> IbcEvent {
         packet,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ibc::clients::ics07_tendermint::client_state::{
+        AllowUpdate, ClientState as TmClientState,
+    };
+    use crate::ibc::core::ics02_client::trust_threshold::TrustThreshold;
+    use crate::ibc::core::ics23_commitment::commitment::CommitmentRoot;
+    use crate::ibc::core::ics24_host::identifier::ChainId;
+    use crate::ledger::storage::ics23_specs::ibc_proof_specs;
+    use crate::ledger::storage::traits::Sha256Hasher;
+
+    /// The trusting period of the client in the tests
+    const TRUSTING_PERIOD: Duration = Duration::from_secs(100);
+    /// The UNIX time of the latest consensus state in the tests, in seconds
+    const CONSENSUS_TIME: u64 = 1_000_000;
+
+    fn client_id() -> ClientId {
+        ClientId::from_str("07-tendermint-0").unwrap()
+    }
+
+    fn tm_client_state() -> TmClientState {
+        TmClientState::new(
+            ChainId::from_str("test-chain").unwrap(),
+            TrustThreshold::default(),
+            TRUSTING_PERIOD,
+            TRUSTING_PERIOD * 2,
+            Duration::from_secs(60),
+            Height::new(0, 10),
+            ibc_proof_specs::<Sha256Hasher>().into(),
+            vec!["upgrade".to_string(), "upgradedIBCState".to_string()],
+            AllowUpdate {
+                after_expiry: true,
+                after_misbehaviour: true,
+            },
+        )
+        .unwrap()
+    }
+
+    fn consensus_state() -> AnyConsensusState {
+        TmConsensusState::new(
+            CommitmentRoot::from_bytes(&[]),
+            Time::from_unix_timestamp(CONSENSUS_TIME as i64, 0).unwrap(),
+            crate::tendermint::Hash::None,
+        )
+        .wrap_any()
+    }
+
+    /// Get the timestamp at the given number of seconds after the latest
+    /// consensus state
+    fn after_consensus(secs: u64) -> Timestamp {
+        Timestamp::from_nanoseconds((CONSENSUS_TIME + secs) * 1_000_000_000)
+            .unwrap()
+    }
+
+    /// Test that a client is active until the exact end of its trusting
+    /// period, after which it's expired.
+    #[test]
+    fn test_client_status_expiry() {
+        let client_id = client_id();
+        let client_state = tm_client_state().wrap_any();
+        let consensus_state = consensus_state();
+
+        // A fresh client is accepted
+        for secs in [0, TRUSTING_PERIOD.as_secs() - 1] {
+            assert_eq!(
+                check_client_status(
+                    &client_id,
+                    &client_state,
+                    &consensus_state,
+                    after_consensus(secs),
+                ),
+                Ok(())
+            );
+        }
+        // A client is expired from the exact end of its trusting period
+        for secs in [TRUSTING_PERIOD.as_secs(), TRUSTING_PERIOD.as_secs() + 1] {
+            assert_eq!(
+                check_client_status(
+                    &client_id,
+                    &client_state,
+                    &consensus_state,
+                    after_consensus(secs),
+                ),
+                Err(ClientStatusError::ClientExpired {
+                    client_id: client_id.clone(),
+                    timestamp: consensus_state.timestamp(),
+                    trusting_period: TRUSTING_PERIOD,
+                })
+            );
+        }
+    }
+
+    /// Test that a frozen client is rejected, even within its trusting
+    /// period.
+    #[test]
+    fn test_client_status_frozen() {
+        let client_id = client_id();
+        let frozen_height = Height::new(0, 5);
+        let mut client_state = tm_client_state();
+        client_state.frozen_height = Some(frozen_height);
+        let client_state = client_state.wrap_any();
+
+        assert_eq!(
+            check_client_status(
+                &client_id,
+                &client_state,
+                &consensus_state(),
+                after_consensus(0),
+            ),
+            Err(ClientStatusError::ClientFrozen {
+                client_id,
+                height: frozen_height,
+            })
+        );
+    }
+}
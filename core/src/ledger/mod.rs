@@ -1,5 +1,6 @@
 //! The ledger modules
 
+pub mod eth_bridge;
 pub mod gas;
 pub mod governance;
 #[cfg(any(feature = "abciplus", feature = "abcipp"))]
@@ -8,5 +9,6 @@ pub mod parameters;
 pub mod slash_fund;
 pub mod storage;
 pub mod storage_api;
+pub mod testnet_faucet;
 pub mod tx_env;
 pub mod vp_env;
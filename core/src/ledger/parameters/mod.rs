@@ -1,6 +1,8 @@
 //! Protocol parameters
 pub mod storage;
 
+use std::collections::BTreeMap;
+
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use rust_decimal::Decimal;
 use thiserror::Error;
@@ -11,6 +13,7 @@ use crate::ledger::storage::{self as ledger_storage};
 use crate::types::address::{Address, InternalAddress};
 use crate::types::storage::Key;
 use crate::types::time::DurationSecs;
+use crate::types::token;
 
 const ADDRESS: Address = Address::Internal(InternalAddress::Parameters);
 
@@ -32,6 +35,8 @@ pub struct Parameters {
     pub epoch_duration: EpochDuration,
     /// Maximum expected time per block (read only)
     pub max_expected_time_per_block: DurationSecs,
+    /// Maximum size of the data of an IBC packet in bytes (read only)
+    pub max_ibc_packet_data_bytes: u64,
     /// Whitelisted validity predicate hashes (read only)
     pub vp_whitelist: Vec<String>,
     /// Whitelisted tx hashes (read only)
@@ -48,6 +53,20 @@ pub struct Parameters {
     pub staked_ratio: Decimal,
     /// PoS inflation amount from the last epoch (read + write for every epoch)
     pub pos_inflation_amount: u64,
+    /// Distribution of the fees paid by the txs of a block (read only)
+    pub fee_distribution: FeeDistribution,
+    /// Maximum sum of the gas limits of the wrapper txs included in a block
+    /// (read only)
+    pub block_gas_limit: u64,
+    /// Maximum number of verifiers that a tx may trigger, if any (read only)
+    pub max_verifiers: Option<u64>,
+    /// The tokens other than the native token that are accepted for the
+    /// fees, with their conversion rates to the native token (read only)
+    pub fee_tokens: BTreeMap<Address, Decimal>,
+    /// Maximum memory of VP WASM execution in 64 KiB WASM pages (read only)
+    pub vp_wasm_memory_max_pages: u32,
+    /// Maximum memory of tx WASM execution in 64 KiB WASM pages (read only)
+    pub tx_wasm_memory_max_pages: u32,
 }
 
 /// Epoch duration. A new epoch begins as soon as both the `min_num_of_blocks`
@@ -71,6 +90,126 @@ pub struct EpochDuration {
     pub min_duration: DurationSecs,
 }
 
+impl EpochDuration {
+    /// Check that both the minimum number of blocks and the minimum duration
+    /// of an epoch are positive. Otherwise, one of them would never hold back
+    /// a new epoch.
+    pub fn validate(&self) -> Result<(), EpochDurationError> {
+        if self.min_num_of_blocks == 0 {
+            return Err(EpochDurationError::ZeroNumOfBlocks);
+        }
+        if self.min_duration.0 == 0 {
+            return Err(EpochDurationError::ZeroDuration);
+        }
+        Ok(())
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EpochDurationError {
+    #[error("The minimum number of blocks of an epoch must be positive")]
+    ZeroNumOfBlocks,
+    #[error("The minimum duration of an epoch must be positive")]
+    ZeroDuration,
+}
+
+/// Distribution of the fees paid by the txs of a block. The fees are split
+/// between a burned part, a part paid to the block proposer and a part paid to
+/// the community pool, by the given rates.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct FeeDistribution {
+    /// The rate of the fees that is burned
+    pub burn_rate: Decimal,
+    /// The rate of the fees that is paid to the block proposer
+    pub proposer_rate: Decimal,
+    /// The rate of the fees that is paid to the community pool
+    pub community_pool_rate: Decimal,
+    /// The address of the community pool
+    pub community_pool: Address,
+}
+
+/// The parts of a fee split by a [`FeeDistribution`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeeSplit {
+    /// The burned part
+    pub burned: token::Amount,
+    /// The part paid to the block proposer
+    pub proposer: token::Amount,
+    /// The part paid to the community pool
+    pub community_pool: token::Amount,
+}
+
+impl Default for FeeDistribution {
+    /// The fees are entirely paid to the block proposer, with the governance
+    /// treasury as the community pool.
+    fn default() -> Self {
+        Self {
+            burn_rate: Decimal::ZERO,
+            proposer_rate: Decimal::ONE,
+            community_pool_rate: Decimal::ZERO,
+            community_pool: Address::Internal(InternalAddress::SlashFund),
+        }
+    }
+}
+
+impl FeeDistribution {
+    /// Check that the rates are between 0 and 1 and that they sum up to
+    /// exactly 1, so that the fees are distributed entirely.
+    pub fn validate(&self) -> Result<(), FeeDistributionError> {
+        let rates =
+            [self.burn_rate, self.proposer_rate, self.community_pool_rate];
+        if let Some(rate) = rates
+            .iter()
+            .find(|rate| **rate < Decimal::ZERO || **rate > Decimal::ONE)
+        {
+            return Err(FeeDistributionError::InvalidRate(*rate));
+        }
+        let sum: Decimal = rates.iter().sum();
+        if sum != Decimal::ONE {
+            return Err(FeeDistributionError::RatesSum(sum));
+        }
+        Ok(())
+    }
+
+    /// Split a fee by the rates. The burned part and the proposer's part are
+    /// rounded down and the community pool gets the rest, so that the parts
+    /// always add up to the fee and the indivisible remainder of the rounding
+    /// goes to the community pool. The distribution must be valid.
+    pub fn split(&self, fee: token::Amount) -> FeeSplit {
+        let burned = fee.mul_floor(self.burn_rate).unwrap_or_default();
+        let proposer = fee.mul_floor(self.proposer_rate).unwrap_or_default();
+        let mut community_pool = fee;
+        community_pool.spend(&burned);
+        community_pool.spend(&proposer);
+        FeeSplit {
+            burned,
+            proposer,
+            community_pool,
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FeeDistributionError {
+    #[error("The fee distribution rates must be between 0 and 1, got {0}")]
+    InvalidRate(Decimal),
+    #[error("The fee distribution rates must sum up to 1, got {0}")]
+    RatesSum(Decimal),
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum ReadError {
@@ -101,6 +240,7 @@ impl Parameters {
         let Self {
             epoch_duration,
             max_expected_time_per_block,
+            max_ibc_packet_data_bytes,
             vp_whitelist,
             tx_whitelist,
             implicit_vp,
@@ -109,6 +249,12 @@ impl Parameters {
             pos_gain_d,
             staked_ratio,
             pos_inflation_amount,
+            fee_distribution,
+            block_gas_limit,
+            max_verifiers,
+            fee_tokens,
+            vp_wasm_memory_max_pages,
+            tx_wasm_memory_max_pages,
         } = self;
 
         // write epoch parameters
@@ -147,6 +293,20 @@ impl Parameters {
                  the genesis block",
             );
 
+        // write max IBC packet data size parameter
+        let max_ibc_packet_data_bytes_key =
+            storage::get_max_ibc_packet_data_bytes_key();
+        let max_ibc_packet_data_bytes_value = encode(max_ibc_packet_data_bytes);
+        storage
+            .write(
+                &max_ibc_packet_data_bytes_key,
+                max_ibc_packet_data_bytes_value,
+            )
+            .expect(
+                "Max IBC packet data size parameter must be initialized in \
+                 the genesis block",
+            );
+
         // write implicit vp parameter
         let implicit_vp_key = storage::get_implicit_vp_key();
         storage.write(&implicit_vp_key, implicit_vp).expect(
@@ -187,6 +347,48 @@ impl Parameters {
             "PoS inflation rate parameter must be initialized in the genesis \
              block",
         );
+
+        let fee_distribution_key = storage::get_fee_distribution_key();
+        let fee_distribution_val = encode(fee_distribution);
+        storage
+            .write(&fee_distribution_key, fee_distribution_val)
+            .expect(
+                "Fee distribution parameter must be initialized in the \
+                 genesis block",
+            );
+
+        let block_gas_limit_key = storage::get_block_gas_limit_key();
+        let block_gas_limit_val = encode(block_gas_limit);
+        storage
+            .write(&block_gas_limit_key, block_gas_limit_val)
+            .expect(
+                "Block gas limit parameter must be initialized in the genesis \
+                 block",
+            );
+
+        let max_verifiers_key = storage::get_max_verifiers_key();
+        let max_verifiers_val = encode(max_verifiers);
+        storage.write(&max_verifiers_key, max_verifiers_val).expect(
+            "Max verifiers parameter must be initialized in the genesis block",
+        );
+
+        let fee_tokens_key = storage::get_fee_tokens_key();
+        let fee_tokens_val = encode(fee_tokens);
+        storage.write(&fee_tokens_key, fee_tokens_val).expect(
+            "Fee tokens parameter must be initialized in the genesis block",
+        );
+
+        let vp_memory_key = storage::get_vp_wasm_memory_max_pages_key();
+        let vp_memory_val = encode(vp_wasm_memory_max_pages);
+        storage.write(&vp_memory_key, vp_memory_val).expect(
+            "VP WASM memory parameter must be initialized in the genesis block",
+        );
+
+        let tx_memory_key = storage::get_tx_wasm_memory_max_pages_key();
+        let tx_memory_val = encode(tx_wasm_memory_max_pages);
+        storage.write(&tx_memory_key, tx_memory_val).expect(
+            "Tx WASM memory parameter must be initialized in the genesis block",
+        );
     }
 }
 /// Update the max_expected_time_per_block parameter in storage. Returns the
@@ -203,6 +405,37 @@ where
     update(storage, value, key)
 }
 
+/// Update the max_ibc_packet_data_bytes parameter in storage. Returns the gas
+/// cost.
+pub fn update_max_ibc_packet_data_bytes_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &u64,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_ibc_packet_data_bytes_key();
+    update(storage, value, key)
+}
+
+/// Read the the max IBC packet data size parameter from store
+pub fn read_max_ibc_packet_data_bytes_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(u64, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_ibc_packet_data_bytes_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let max_ibc_packet_data_bytes: u64 =
+        decode(value.ok_or(ReadError::ParametersMissing)?)
+            .map_err(ReadError::StorageTypeError)?;
+
+    Ok((max_ibc_packet_data_bytes, gas))
+}
+
 /// Update the vp whitelist parameter in storage. Returns the parameters and gas
 /// cost.
 pub fn update_vp_whitelist_parameter<DB, H>(
@@ -249,7 +482,7 @@ where
 /// gas cost.
 pub fn update_epochs_per_year_parameter<DB, H>(
     storage: &mut Storage<DB, H>,
-    value: &EpochDuration,
+    value: &u64,
 ) -> std::result::Result<u64, WriteError>
 where
     DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
@@ -263,7 +496,7 @@ where
 /// cost.
 pub fn update_pos_gain_p_parameter<DB, H>(
     storage: &mut Storage<DB, H>,
-    value: &EpochDuration,
+    value: &Decimal,
 ) -> std::result::Result<u64, WriteError>
 where
     DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
@@ -277,7 +510,7 @@ where
 /// cost.
 pub fn update_pos_gain_d_parameter<DB, H>(
     storage: &mut Storage<DB, H>,
-    value: &EpochDuration,
+    value: &Decimal,
 ) -> std::result::Result<u64, WriteError>
 where
     DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
@@ -291,7 +524,7 @@ where
 /// gas cost.
 pub fn update_staked_ratio_parameter<DB, H>(
     storage: &mut Storage<DB, H>,
-    value: &EpochDuration,
+    value: &Decimal,
 ) -> std::result::Result<u64, WriteError>
 where
     DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
@@ -305,7 +538,7 @@ where
 /// and gas cost.
 pub fn update_pos_inflation_amount_parameter<DB, H>(
     storage: &mut Storage<DB, H>,
-    value: &EpochDuration,
+    value: &u64,
 ) -> std::result::Result<u64, WriteError>
 where
     DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
@@ -315,6 +548,191 @@ where
     update(storage, value, key)
 }
 
+/// Update the fee distribution parameter in storage. Returns the gas cost.
+pub fn update_fee_distribution_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &FeeDistribution,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_fee_distribution_key();
+    update(storage, value, key)
+}
+
+/// Read the fee distribution parameter from store
+pub fn read_fee_distribution_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(FeeDistribution, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_fee_distribution_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let fee_distribution: FeeDistribution =
+        decode(value.ok_or(ReadError::ParametersMissing)?)
+            .map_err(ReadError::StorageTypeError)?;
+
+    Ok((fee_distribution, gas))
+}
+
+/// Update the block gas limit parameter in storage. Returns the gas cost.
+pub fn update_block_gas_limit_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &u64,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_block_gas_limit_key();
+    update(storage, value, key)
+}
+
+/// Read the block gas limit parameter from store
+pub fn read_block_gas_limit_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(u64, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_block_gas_limit_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let block_gas_limit: u64 =
+        decode(value.ok_or(ReadError::ParametersMissing)?)
+            .map_err(ReadError::StorageTypeError)?;
+
+    Ok((block_gas_limit, gas))
+}
+
+/// Update the max verifiers parameter in storage. Returns the gas cost.
+pub fn update_max_verifiers_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &Option<u64>,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_verifiers_key();
+    update(storage, value, key)
+}
+
+/// Read the max verifiers parameter from store. The verifiers of a tx are not
+/// capped when it's `None`, which is also the case until the parameter is
+/// initialized at genesis.
+pub fn read_max_verifiers_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(Option<u64>, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_max_verifiers_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let max_verifiers: Option<u64> = match value {
+        Some(value) => decode(value).map_err(ReadError::StorageTypeError)?,
+        None => None,
+    };
+
+    Ok((max_verifiers, gas))
+}
+
+/// Update the fee tokens parameter in storage. Returns the gas cost.
+pub fn update_fee_tokens_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &BTreeMap<Address, Decimal>,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_fee_tokens_key();
+    update(storage, value, key)
+}
+
+/// Read the fee tokens parameter from store. Only the native token is
+/// accepted for the fees until the parameter is initialized at genesis.
+pub fn read_fee_tokens_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(BTreeMap<Address, Decimal>, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_fee_tokens_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let fee_tokens: BTreeMap<Address, Decimal> = match value {
+        Some(value) => decode(value).map_err(ReadError::StorageTypeError)?,
+        None => BTreeMap::new(),
+    };
+
+    Ok((fee_tokens, gas))
+}
+
+/// Update the VP WASM memory max pages parameter in storage. Returns the gas
+/// cost.
+pub fn update_vp_wasm_memory_max_pages_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &u32,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_vp_wasm_memory_max_pages_key();
+    update(storage, value, key)
+}
+
+/// Read the VP WASM memory max pages parameter from store
+pub fn read_vp_wasm_memory_max_pages_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(u32, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_vp_wasm_memory_max_pages_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let max_pages: u32 = decode(value.ok_or(ReadError::ParametersMissing)?)
+        .map_err(ReadError::StorageTypeError)?;
+
+    Ok((max_pages, gas))
+}
+
+/// Update the tx WASM memory max pages parameter in storage. Returns the gas
+/// cost.
+pub fn update_tx_wasm_memory_max_pages_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &u32,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_tx_wasm_memory_max_pages_key();
+    update(storage, value, key)
+}
+
+/// Read the tx WASM memory max pages parameter from store
+pub fn read_tx_wasm_memory_max_pages_parameter<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(u32, u64), ReadError>
+where
+    DB: ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: ledger_storage::StorageHasher,
+{
+    let key = storage::get_tx_wasm_memory_max_pages_key();
+    let (value, gas) = storage.read(&key).map_err(ReadError::StorageError)?;
+    let max_pages: u32 = decode(value.ok_or(ReadError::ParametersMissing)?)
+        .map_err(ReadError::StorageTypeError)?;
+
+    Ok((max_pages, gas))
+}
+
 /// Update the implicit VP parameter in storage. Return the gas cost.
 pub fn update_implicit_vp<DB, H>(
     storage: &mut Storage<DB, H>,
@@ -413,6 +831,9 @@ where
         decode(value.ok_or(ReadError::ParametersMissing)?)
             .map_err(ReadError::StorageTypeError)?;
 
+    let (max_ibc_packet_data_bytes, gas_ibc_packet) =
+        read_max_ibc_packet_data_bytes_parameter(storage)?;
+
     let implicit_vp_key = storage::get_implicit_vp_key();
     let (value, gas_implicit_vp) = storage
         .read(&implicit_vp_key)
@@ -464,10 +885,28 @@ where
         decode(value.ok_or(ReadError::ParametersMissing)?)
             .map_err(ReadError::StorageTypeError)?;
 
+    let (fee_distribution, gas_fees) =
+        read_fee_distribution_parameter(storage)?;
+
+    let (block_gas_limit, gas_block_gas_limit) =
+        read_block_gas_limit_parameter(storage)?;
+
+    let (max_verifiers, gas_max_verifiers) =
+        read_max_verifiers_parameter(storage)?;
+
+    let (fee_tokens, gas_fee_tokens) = read_fee_tokens_parameter(storage)?;
+
+    let (vp_wasm_memory_max_pages, gas_vp_memory) =
+        read_vp_wasm_memory_max_pages_parameter(storage)?;
+
+    let (tx_wasm_memory_max_pages, gas_tx_memory) =
+        read_tx_wasm_memory_max_pages_parameter(storage)?;
+
     Ok((
         Parameters {
             epoch_duration,
             max_expected_time_per_block,
+            max_ibc_packet_data_bytes,
             vp_whitelist,
             tx_whitelist,
             implicit_vp,
@@ -476,16 +915,29 @@ where
             pos_gain_d,
             staked_ratio,
             pos_inflation_amount,
+            fee_distribution,
+            block_gas_limit,
+            max_verifiers,
+            fee_tokens,
+            vp_wasm_memory_max_pages,
+            tx_wasm_memory_max_pages,
         },
         gas_epoch
             + gas_tx
             + gas_vp
             + gas_time
+            + gas_ibc_packet
             + gas_implicit_vp
             + gas_epy
             + gas_gain_p
             + gas_gain_d
             + gas_staked
-            + gas_reward,
+            + gas_reward
+            + gas_fees
+            + gas_block_gas_limit
+            + gas_max_verifiers
+            + gas_fee_tokens
+            + gas_vp_memory
+            + gas_tx_memory,
     ))
 }
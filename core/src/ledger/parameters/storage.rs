@@ -6,12 +6,20 @@ const EPOCH_DURATION_KEY: &str = "epoch_duration";
 const VP_WHITELIST_KEY: &str = "vp_whitelist";
 const TX_WHITELIST_KEY: &str = "tx_whitelist";
 const MAX_EXPECTED_TIME_PER_BLOCK_KEY: &str = "max_expected_time_per_block";
+const MAX_IBC_PACKET_DATA_BYTES_KEY: &str = "max_ibc_packet_data_bytes";
 const IMPLICIT_VP_KEY: &str = "implicit_vp";
 const EPOCHS_PER_YEAR_KEY: &str = "epochs_per_year";
 const POS_GAIN_P_KEY: &str = "pos_gain_p";
 const POS_GAIN_D_KEY: &str = "pos_gain_d";
 const STAKED_RATIO_KEY: &str = "staked_ratio_key";
 const POS_INFLATION_AMOUNT_KEY: &str = "pos_inflation_amount_key";
+const FEE_DISTRIBUTION_KEY: &str = "fee_distribution";
+const BLOCK_GAS_LIMIT_KEY: &str = "block_gas_limit";
+const MAX_VERIFIERS_KEY: &str = "max_verifiers";
+const FEE_TOKENS_KEY: &str = "fee_tokens";
+const VP_WASM_MEMORY_MAX_PAGES_KEY: &str = "vp_wasm_memory_max_pages";
+const TX_WASM_MEMORY_MAX_PAGES_KEY: &str = "tx_wasm_memory_max_pages";
+const GENESIS_INITIALIZED_KEY: &str = "genesis_initialized";
 
 /// Returns if the key is a parameter key.
 pub fn is_parameter_key(key: &Key) -> bool {
@@ -22,6 +30,13 @@ pub fn is_parameter_key(key: &Key) -> bool {
 pub fn is_protocol_parameter_key(key: &Key) -> bool {
     is_epoch_duration_storage_key(key)
         || is_max_expected_time_per_block_key(key)
+        || is_max_ibc_packet_data_bytes_key(key)
+        || is_fee_distribution_key(key)
+        || is_block_gas_limit_key(key)
+        || is_max_verifiers_key(key)
+        || is_fee_tokens_key(key)
+        || is_vp_wasm_memory_max_pages_key(key)
+        || is_tx_wasm_memory_max_pages_key(key)
         || is_tx_whitelist_key(key)
         || is_vp_whitelist_key(key)
 }
@@ -42,6 +57,14 @@ pub fn is_max_expected_time_per_block_key(key: &Key) -> bool {
     ] if addr == &ADDRESS && max_expected_time_per_block == MAX_EXPECTED_TIME_PER_BLOCK_KEY)
 }
 
+/// Returns if the key is the max_ibc_packet_data_bytes key.
+pub fn is_max_ibc_packet_data_bytes_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(max_ibc_packet_data_bytes),
+    ] if addr == &ADDRESS && max_ibc_packet_data_bytes == MAX_IBC_PACKET_DATA_BYTES_KEY)
+}
+
 /// Returns if the key is the tx_whitelist key.
 pub fn is_tx_whitelist_key(key: &Key) -> bool {
     matches!(&key.segments[..], [
@@ -106,6 +129,54 @@ pub fn is_pos_inflation_amount_key(key: &Key) -> bool {
     ] if addr == &ADDRESS && pos_inflation_amount == POS_INFLATION_AMOUNT_KEY)
 }
 
+/// Returns if the key is the fee distribution key.
+pub fn is_fee_distribution_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(fee_distribution),
+    ] if addr == &ADDRESS && fee_distribution == FEE_DISTRIBUTION_KEY)
+}
+
+/// Returns if the key is the block gas limit key.
+pub fn is_block_gas_limit_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(block_gas_limit),
+    ] if addr == &ADDRESS && block_gas_limit == BLOCK_GAS_LIMIT_KEY)
+}
+
+/// Returns if the key is the max verifiers key.
+pub fn is_max_verifiers_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(max_verifiers),
+    ] if addr == &ADDRESS && max_verifiers == MAX_VERIFIERS_KEY)
+}
+
+/// Returns if the key is the fee tokens key.
+pub fn is_fee_tokens_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(fee_tokens),
+    ] if addr == &ADDRESS && fee_tokens == FEE_TOKENS_KEY)
+}
+
+/// Returns if the key is the VP WASM memory max pages key.
+pub fn is_vp_wasm_memory_max_pages_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(max_pages),
+    ] if addr == &ADDRESS && max_pages == VP_WASM_MEMORY_MAX_PAGES_KEY)
+}
+
+/// Returns if the key is the tx WASM memory max pages key.
+pub fn is_tx_wasm_memory_max_pages_key(key: &Key) -> bool {
+    matches!(&key.segments[..], [
+        DbKeySeg::AddressSeg(addr),
+        DbKeySeg::StringSeg(max_pages),
+    ] if addr == &ADDRESS && max_pages == TX_WASM_MEMORY_MAX_PAGES_KEY)
+}
+
 /// Storage key used for epoch parameter.
 pub fn get_epoch_duration_storage_key() -> Key {
     Key {
@@ -146,6 +217,16 @@ pub fn get_max_expected_time_per_block_key() -> Key {
     }
 }
 
+/// Storage key used for max_ibc_packet_data_bytes parameter.
+pub fn get_max_ibc_packet_data_bytes_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(MAX_IBC_PACKET_DATA_BYTES_KEY.to_string()),
+        ],
+    }
+}
+
 /// Storage key used for implicit VP parameter.
 pub fn get_implicit_vp_key() -> Key {
     Key {
@@ -205,3 +286,74 @@ pub fn get_pos_inflation_amount_key() -> Key {
         ],
     }
 }
+
+/// Storage key used for fee distribution parameter.
+pub fn get_fee_distribution_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(FEE_DISTRIBUTION_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for block gas limit parameter.
+pub fn get_block_gas_limit_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(BLOCK_GAS_LIMIT_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for max verifiers parameter.
+pub fn get_max_verifiers_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(MAX_VERIFIERS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for fee tokens parameter.
+pub fn get_fee_tokens_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(FEE_TOKENS_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for VP WASM memory max pages parameter.
+pub fn get_vp_wasm_memory_max_pages_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(VP_WASM_MEMORY_MAX_PAGES_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for tx WASM memory max pages parameter.
+pub fn get_tx_wasm_memory_max_pages_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(TX_WASM_MEMORY_MAX_PAGES_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key recording that the chain's genesis has been initialized. It's
+/// not a parameter, but it's kept with them so that no tx may change it.
+pub fn get_genesis_initialized_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(ADDRESS),
+            DbKeySeg::StringSeg(GENESIS_INITIALIZED_KEY.to_string()),
+        ],
+    }
+}
@@ -17,7 +17,7 @@ use crate::bytes::ByteBuf;
 use crate::ledger::storage::ics23_specs::{self, ibc_leaf_spec};
 use crate::ledger::storage::types;
 use crate::types::address::{Address, InternalAddress};
-use crate::types::hash::Hash;
+use crate::types::hash::{Hash, HashAlgorithm};
 use crate::types::storage::{
     self, DbKeySeg, Error as StorageError, Key, StringKey, TreeBytes,
     TreeKeyError, IBC_KEY_LIMIT,
@@ -46,6 +46,8 @@ pub enum Error {
     Ics23MultiLeaf,
     #[error("A Tendermint proof can only be constructed from an ICS23 proof.")]
     TendermintProof,
+    #[error("Invalid Tendermint proof: {0}")]
+    InvalidTendermintProof(String),
 }
 
 /// Result for functions that may fail
@@ -327,6 +329,11 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         self.base.root().into()
     }
 
+    /// Get the algorithm used to hash the tree
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        H::ALGORITHM
+    }
+
     /// Get the stores of the base and sub trees
     pub fn stores(&self) -> MerkleTreeStoresWrite {
         MerkleTreeStoresWrite {
@@ -590,6 +597,112 @@ impl From<Proof> for crate::tendermint::merkle::proof::Proof {
     }
 }
 
+#[cfg(any(feature = "tendermint", feature = "tendermint-abcipp"))]
+impl TryFrom<crate::tendermint::merkle::proof::Proof> for Proof {
+    type Error = Error;
+
+    fn try_from(
+        proof: crate::tendermint::merkle::proof::Proof,
+    ) -> Result<Self> {
+        use prost::Message;
+
+        use crate::tendermint::merkle::proof::ProofOp;
+
+        // The ProofOps are set from leaf to root
+        let [sub_proof_op, base_proof_op]: [ProofOp; 2] =
+            proof.ops.try_into().map_err(|_| {
+                Error::InvalidTendermintProof(
+                    "Expected a sub proof and a base proof".into(),
+                )
+            })?;
+        let key = std::str::from_utf8(&sub_proof_op.key)
+            .map_err(|err| Error::InvalidTendermintProof(err.to_string()))?;
+        let key = Key::parse(key).map_err(Error::InvalidKey)?;
+        let decode = |op: ProofOp| {
+            CommitmentProof::decode(op.data.as_slice())
+                .map_err(|err| Error::InvalidTendermintProof(err.to_string()))
+        };
+        Ok(Self {
+            key,
+            sub_proof: decode(sub_proof_op)?,
+            base_proof: decode(base_proof_op)?,
+        })
+    }
+}
+
+impl Proof {
+    /// Verify the proof against the root of the merkle tree, i.e. the app
+    /// hash of the block that the proof was made for. With a value, the
+    /// proof must prove that the key exists with this value, otherwise that
+    /// the key doesn't exist.
+    pub fn verify<H: StorageHasher>(
+        &self,
+        value: Option<&[u8]>,
+        root: &MerkleRoot,
+    ) -> bool {
+        let (store_type, sub_key) = match StoreType::sub_key(&self.key) {
+            Ok(sub_key) => sub_key,
+            Err(_) => return false,
+        };
+        let specs = match store_type {
+            StoreType::Ibc => ics23_specs::ibc_proof_specs::<H>(),
+            _ => ics23_specs::proof_specs::<H>(),
+        };
+        let sub_key = sub_key.to_string();
+
+        // First, the sub proof is verified against the sub root that it
+        // commits to
+        let sub_root = match (&self.sub_proof.proof, value) {
+            (Some(Ics23Proof::Exist(ep)), Some(value)) => {
+                let sub_root = match ics23::calculate_existence_root(ep) {
+                    Ok(sub_root) => sub_root,
+                    Err(_) => return false,
+                };
+                if !ics23::verify_membership(
+                    &self.sub_proof,
+                    &specs[0],
+                    &sub_root,
+                    sub_key.as_bytes(),
+                    value,
+                ) {
+                    return false;
+                }
+                sub_root
+            }
+            (Some(Ics23Proof::Nonexist(nep)), None) => {
+                let sub_root = match nep.left.as_ref().or(nep.right.as_ref()) {
+                    Some(neighbor) => {
+                        match ics23::calculate_existence_root(neighbor) {
+                            Ok(sub_root) => sub_root,
+                            Err(_) => return false,
+                        }
+                    }
+                    None => return false,
+                };
+                if !ics23::verify_non_membership(
+                    &self.sub_proof,
+                    &specs[0],
+                    &sub_root,
+                    sub_key.as_bytes(),
+                ) {
+                    return false;
+                }
+                sub_root
+            }
+            _ => return false,
+        };
+        // Next, the base proof is verified with the sub root against the
+        // given root
+        ics23::verify_membership(
+            &self.base_proof,
+            &specs[1],
+            &root.0,
+            store_type.to_string().as_bytes(),
+            &sub_root,
+        )
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
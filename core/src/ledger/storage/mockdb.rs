@@ -16,7 +16,8 @@ use crate::ledger::storage::types::{self, KVBytes, PrefixIterator};
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::internal::TxQueue;
 use crate::types::storage::{
-    BlockHeight, BlockResults, Header, Key, KeySeg, KEY_SEGMENT_SEPARATOR,
+    BlockHash, BlockHeight, BlockResults, Header, Key, KeySeg,
+    KEY_SEGMENT_SEPARATOR,
 };
 use crate::types::time::DateTimeUtc;
 
@@ -41,9 +42,15 @@ pub struct MockDBWriteBatch;
 impl DB for MockDB {
     /// There is no cache for MockDB
     type Cache = ();
+    /// There are no tunable options for MockDB
+    type Options = ();
     type WriteBatch = MockDBWriteBatch;
 
-    fn open(_db_path: impl AsRef<Path>, _cache: Option<&Self::Cache>) -> Self {
+    fn open(
+        _db_path: impl AsRef<Path>,
+        _cache: Option<&Self::Cache>,
+        _options: Option<&Self::Options>,
+    ) -> Self {
         Self::default()
     }
 
@@ -307,6 +314,21 @@ impl DB for MockDB {
         }
     }
 
+    fn read_block_hash(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<BlockHash>> {
+        let prefix_key = Key::from(height.to_db_key());
+        let key = prefix_key
+            .push(&"hash".to_owned())
+            .map_err(Error::KeyError)?;
+        let value = self.0.borrow().get(&key.to_string()).cloned();
+        match value {
+            Some(v) => Ok(Some(types::decode(v).map_err(Error::CodingError)?)),
+            None => Ok(None),
+        }
+    }
+
     fn read_merkle_tree_stores(
         &self,
         height: BlockHeight,
@@ -393,6 +415,36 @@ impl DB for MockDB {
         })
     }
 
+    fn prune(&mut self, oldest_retained_height: BlockHeight) -> Result<()> {
+        self.0.borrow_mut().retain(|key, _| {
+            let mut segments = key.split(KEY_SEGMENT_SEPARATOR);
+            let height = segments.next();
+            match height.map(str::parse::<u64>) {
+                // the block headers are kept for the IBC VP
+                Some(Ok(height)) => {
+                    height >= oldest_retained_height.0
+                        || segments.next() == Some("header")
+                }
+                // the key doesn't include the height
+                _ => true,
+            }
+        });
+        self.0.borrow_mut().insert(
+            "oldest_retained_height".to_owned(),
+            types::encode(&oldest_retained_height),
+        );
+        Ok(())
+    }
+
+    fn read_oldest_retained_height(&self) -> Result<Option<BlockHeight>> {
+        match self.0.borrow().get("oldest_retained_height") {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
     fn batch() -> Self::WriteBatch {
         MockDBWriteBatch
     }
@@ -483,6 +535,24 @@ impl<'iter> DBIter<'iter> for MockDB {
             db_prefix,
         )
     }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> MockPrefixIterator {
+        let db_prefix = format!("{}/diffs/old/", height.to_db_key());
+        let prefix = format!("{}{}", db_prefix, prefix);
+        let iter = self.0.borrow().clone().into_iter();
+        MockPrefixIterator::new(
+            MockIterator {
+                prefix,
+                iter,
+                reverse_order: false,
+            },
+            db_prefix,
+        )
+    }
 }
 
 /// A prefix iterator base for the [`MockPrefixIterator`].
@@ -8,7 +8,7 @@ pub mod traits;
 pub mod types;
 
 use core::fmt::Debug;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use masp_primitives::asset_type::AssetType;
@@ -29,7 +29,7 @@ use rayon::prelude::ParallelSlice;
 use thiserror::Error;
 pub use traits::{Sha256Hasher, StorageHasher};
 
-use crate::ledger::gas::MIN_STORAGE_GAS;
+use crate::ledger::gas::{MIN_STORAGE_GAS, STORAGE_ACCESS_GAS_PER_BYTE};
 use crate::ledger::parameters::{self, EpochDuration, Parameters};
 use crate::ledger::storage::merkle_tree::{
     Error as MerkleTreeError, MerkleRoot,
@@ -42,6 +42,7 @@ use crate::types::address::{
     masp, Address, EstablishedAddressGen, InternalAddress,
 };
 use crate::types::chain::{ChainId, CHAIN_ID_LENGTH};
+use crate::types::hash::HashAlgorithm;
 // TODO
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::internal::TxQueue;
@@ -54,6 +55,12 @@ use crate::types::token;
 
 /// A result of a function that may fail
 pub type Result<T> = std::result::Result<T, Error>;
+
+// TODO put this into PoS parameters and pass it to tendermint
+// `consensus_params` on `InitChain` and `EndBlock`
+/// The number of blocks for which evidence of misbehavior is valid
+pub const EVIDENCE_MAX_AGE_NUM_BLOCKS: u64 = 100000;
+
 /// A representation of the conversion state
 #[derive(Debug, Default, BorshSerialize, BorshDeserialize)]
 pub struct ConversionState {
@@ -88,6 +95,9 @@ where
     /// The height of the most recently committed block, or `BlockHeight(0)` if
     /// no block has been committed for this chain yet.
     pub last_height: BlockHeight,
+    /// The oldest block height whose historical state is retained. The state
+    /// of the blocks below it has been pruned.
+    pub oldest_retained_height: BlockHeight,
     /// The epoch of the most recently committed block. If it is `Epoch(0)`,
     /// then no block may have been committed for this chain yet.
     pub last_epoch: Epoch,
@@ -150,6 +160,14 @@ pub enum Error {
     BorshCodingError(std::io::Error),
     #[error("Merkle tree at the height {height} is not stored")]
     NoMerkleTree { height: BlockHeight },
+    #[error(
+        "The state at the height {height} has been pruned, the oldest \
+         retained height is {oldest_retained_height}"
+    )]
+    PrunedHeight {
+        height: BlockHeight,
+        oldest_retained_height: BlockHeight,
+    },
 }
 
 /// The block's state as stored in the database.
@@ -208,6 +226,8 @@ pub struct BlockStateWrite<'a> {
 pub trait DB: std::fmt::Debug {
     /// A DB's cache
     type Cache;
+    /// A DB's tunable options
+    type Options;
     /// A handle for batch writes
     type WriteBatch: DBWriteBatch;
 
@@ -215,6 +235,7 @@ pub trait DB: std::fmt::Debug {
     fn open(
         db_path: impl AsRef<std::path::Path>,
         cache: Option<&Self::Cache>,
+        options: Option<&Self::Options>,
     ) -> Self;
 
     /// Flush data on the memory to persistent them
@@ -229,6 +250,10 @@ pub trait DB: std::fmt::Debug {
     /// Read the block header with the given height from the DB
     fn read_block_header(&self, height: BlockHeight) -> Result<Option<Header>>;
 
+    /// Read the block hash with the given height from the DB
+    fn read_block_hash(&self, height: BlockHeight)
+        -> Result<Option<BlockHash>>;
+
     /// Read the merkle tree stores with the given height
     fn read_merkle_tree_stores(
         &self,
@@ -294,6 +319,17 @@ pub trait DB: std::fmt::Debug {
         height: BlockHeight,
         key: &Key,
     ) -> Result<i64>;
+
+    /// Delete the historical state of the blocks below the given height,
+    /// i.e. their subspace diffs, Merkle tree stores and metadata other than
+    /// the block header, and persist the height as the oldest retained one.
+    /// The block headers, which VPs may read, and the results of the blocks
+    /// are kept.
+    fn prune(&mut self, oldest_retained_height: BlockHeight) -> Result<()>;
+
+    /// Read the oldest block height whose historical state is retained, if
+    /// the DB has been pruned
+    fn read_oldest_retained_height(&self) -> Result<Option<BlockHeight>>;
 }
 
 /// A database prefix iterator.
@@ -311,6 +347,14 @@ pub trait DBIter<'iter> {
 
     /// Read results subspace key value pairs from the DB
     fn iter_results(&'iter self) -> Self::PrefixIter;
+
+    /// Read the previous values of the account subspace keys with the given
+    /// prefix that were updated or deleted at the given height.
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: &Key,
+    ) -> Self::PrefixIter;
 }
 
 /// Atomic batch write.
@@ -337,6 +381,7 @@ where
         chain_id: ChainId,
         native_token: Address,
         cache: Option<&D::Cache>,
+        options: Option<&D::Options>,
     ) -> Self {
         let block = BlockStorage {
             tree: MerkleTree::default(),
@@ -347,11 +392,12 @@ where
             results: BlockResults::default(),
         };
         Storage::<D, H> {
-            db: D::open(db_path, cache),
+            db: D::open(db_path, cache, options),
             chain_id,
             block,
             header: None,
             last_height: BlockHeight(0),
+            oldest_retained_height: BlockHeight(0),
             last_epoch: Epoch::default(),
             next_epoch_min_start_height: BlockHeight::default(),
             next_epoch_min_start_time: DateTimeUtc::now(),
@@ -394,6 +440,9 @@ where
             self.next_epoch_min_start_height = next_epoch_min_start_height;
             self.next_epoch_min_start_time = next_epoch_min_start_time;
             self.address_gen = address_gen;
+            if let Some(height) = self.db.read_oldest_retained_height()? {
+                self.oldest_retained_height = height;
+            }
             if self.last_epoch.0 > 1 {
                 // The derived conversions will be placed in MASP address space
                 let masp_addr = masp();
@@ -455,15 +504,48 @@ where
         Ok(())
     }
 
+    /// Prune the historical state of the blocks below the given height. The
+    /// last committed block is always retained.
+    pub fn prune(&mut self, oldest_retained_height: BlockHeight) -> Result<()> {
+        let oldest_retained_height =
+            oldest_retained_height.min(self.last_height);
+        if oldest_retained_height <= self.oldest_retained_height {
+            return Ok(());
+        }
+        self.db.prune(oldest_retained_height)?;
+        self.oldest_retained_height = oldest_retained_height;
+        Ok(())
+    }
+
+    /// Check that the historical state at the given height has not been
+    /// pruned
+    fn check_not_pruned(&self, height: BlockHeight) -> Result<()> {
+        if height < self.oldest_retained_height {
+            return Err(Error::PrunedHeight {
+                height,
+                oldest_retained_height: self.oldest_retained_height,
+            });
+        }
+        Ok(())
+    }
+
     /// Find the root hash of the merkle tree
     pub fn merkle_root(&self) -> MerkleRoot {
         self.block.tree.root()
     }
 
+    /// Get the algorithm used to hash the merkle tree
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.block.tree.hash_algorithm()
+    }
+
     /// Check if the given key is present in storage. Returns the result and the
     /// gas cost.
     pub fn has_key(&self, key: &Key) -> Result<(bool, u64)> {
-        Ok((self.block.tree.has_key(key)?, key.len() as _))
+        Ok((
+            self.block.tree.has_key(key)?,
+            key.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE,
+        ))
     }
 
     /// Returns a value from the specified subspace and the gas cost
@@ -477,9 +559,9 @@ where
         match self.db.read_subspace_val(key)? {
             Some(v) => {
                 let gas = key.len() + v.len();
-                Ok((Some(v), gas as _))
+                Ok((Some(v), gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE))
             }
-            None => Ok((None, key.len() as _)),
+            None => Ok((None, key.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE)),
         }
     }
 
@@ -493,6 +575,7 @@ where
         if height >= self.last_height {
             self.read(key)
         } else {
+            self.check_not_pruned(height)?;
             match self.db.read_subspace_val_with_height(
                 key,
                 height,
@@ -500,9 +583,11 @@ where
             )? {
                 Some(v) => {
                     let gas = key.len() + v.len();
-                    Ok((Some(v), gas as _))
+                    Ok((Some(v), gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE))
+                }
+                None => {
+                    Ok((None, key.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE))
                 }
-                None => Ok((None, key.len() as _)),
             }
         }
     }
@@ -512,7 +597,10 @@ where
         &self,
         prefix: &Key,
     ) -> (<D as DBIter<'_>>::PrefixIter, u64) {
-        (self.db.iter_prefix(prefix), prefix.len() as _)
+        (
+            self.db.iter_prefix(prefix),
+            prefix.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE,
+        )
     }
 
     /// Returns a prefix iterator, reverse ordered by storage keys, and the gas
@@ -521,7 +609,60 @@ where
         &self,
         prefix: &Key,
     ) -> (<D as DBIter<'_>>::PrefixIter, u64) {
-        (self.db.rev_iter_prefix(prefix), prefix.len() as _)
+        (
+            self.db.rev_iter_prefix(prefix),
+            prefix.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE,
+        )
+    }
+
+    /// Returns the key-vals with the given prefix as they were at the given
+    /// height, ordered by storage keys, and the gas cost
+    pub fn iter_prefix_with_height(
+        &self,
+        prefix: &Key,
+        height: BlockHeight,
+    ) -> Result<(Vec<(String, Vec<u8>)>, u64)> {
+        let mut gas = prefix.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE;
+        if height >= self.last_height {
+            let kvs: Vec<(String, Vec<u8>)> = self
+                .db
+                .iter_prefix(prefix)
+                .map(|(key, val, iter_gas)| {
+                    gas += iter_gas * STORAGE_ACCESS_GAS_PER_BYTE;
+                    (key, val)
+                })
+                .collect();
+            return Ok((kvs, gas));
+        }
+        self.check_not_pruned(height)?;
+        // The keys present at the given height are either still present now
+        // or they have been updated or deleted by one of the later blocks
+        let mut keys: BTreeSet<String> = self
+            .db
+            .iter_prefix(prefix)
+            .map(|(key, _val, _gas)| key)
+            .collect();
+        for raw_height in height.0 + 1..=self.last_height.0 {
+            keys.extend(
+                self.db
+                    .iter_old_diffs(BlockHeight(raw_height), prefix)
+                    .map(|(key, _val, _gas)| key),
+            );
+        }
+        let mut kvs = Vec::with_capacity(keys.len());
+        for key in keys {
+            let storage_key = Key::parse(&key).map_err(Error::KeyError)?;
+            if let Some(val) = self.db.read_subspace_val_with_height(
+                &storage_key,
+                height,
+                self.last_height,
+            )? {
+                gas += (key.len() + val.len()) as u64
+                    * STORAGE_ACCESS_GAS_PER_BYTE;
+                kvs.push((key, val));
+            }
+        }
+        Ok((kvs, gas))
     }
 
     /// Returns a prefix iterator and the gas cost
@@ -546,7 +687,7 @@ where
         let gas = key.len() + len;
         let size_diff =
             self.db.write_subspace_val(self.block.height, key, value)?;
-        Ok((gas as _, size_diff))
+        Ok((gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE, size_diff))
     }
 
     /// Delete the specified subspace and returns the gas cost and the size
@@ -561,7 +702,7 @@ where
                 self.db.delete_subspace_val(self.block.height, key)?;
         }
         let gas = key.len() + deleted_bytes_len as usize;
-        Ok((gas as _, deleted_bytes_len))
+        Ok((gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE, deleted_bytes_len))
     }
 
     /// Set the block header.
@@ -642,6 +783,7 @@ where
                 .map(Into::into)
                 .map_err(Error::MerkleTreeError)
         } else {
+            self.check_not_pruned(height)?;
             match self.db.read_merkle_tree_stores(height)? {
                 Some(stores) => {
                     let tree = MerkleTree::<H>::new(stores);
@@ -674,6 +816,7 @@ where
                 .map(Into::into)
                 .map_err(Error::MerkleTreeError)
         } else {
+            self.check_not_pruned(height)?;
             match self.db.read_merkle_tree_stores(height)? {
                 Some(stores) => MerkleTree::<H>::new(stores)
                     .get_non_existence_proof(key)
@@ -710,7 +853,7 @@ where
         self.update_epoch_in_merkle_tree()
     }
 
-    /// Get the block header
+    /// Get the block header. The headers of the pruned blocks are retained.
     pub fn get_block_header(
         &self,
         height: Option<BlockHeight>,
@@ -753,12 +896,9 @@ where
             } = parameters.epoch_duration;
             self.next_epoch_min_start_height = height + min_num_of_blocks;
             self.next_epoch_min_start_time = time + min_duration;
-            // TODO put this into PoS parameters and pass it to tendermint
-            // `consensus_params` on `InitChain` and `EndBlock`
-            let evidence_max_age_num_blocks: u64 = 100000;
             self.block
                 .pred_epochs
-                .new_epoch(height, evidence_max_age_num_blocks);
+                .new_epoch(height, EVIDENCE_MAX_AGE_NUM_BLOCKS);
             tracing::info!("Began a new epoch {}", self.block.epoch);
             self.update_allowed_conversions()?;
         }
@@ -1191,6 +1331,7 @@ pub mod testing {
                 block,
                 header: None,
                 last_height: BlockHeight(0),
+                oldest_retained_height: BlockHeight(0),
                 last_epoch: Epoch::default(),
                 next_epoch_min_start_height: BlockHeight::default(),
                 next_epoch_min_start_time: DateTimeUtc::now(),
@@ -1216,7 +1357,9 @@ mod tests {
 
     use super::testing::*;
     use super::*;
-    use crate::ledger::parameters::{self, Parameters};
+    use crate::ledger::gas::BLOCK_GAS_LIMIT;
+    use crate::ledger::parameters::{self, FeeDistribution, Parameters};
+    use crate::types::ibc::DEFAULT_MAX_PACKET_DATA_BYTES;
     use crate::types::time::{self, Duration};
 
     prop_compose! {
@@ -1286,6 +1429,7 @@ mod tests {
             let mut parameters = Parameters {
                 epoch_duration: epoch_duration.clone(),
                 max_expected_time_per_block: Duration::seconds(max_expected_time_per_block).into(),
+                max_ibc_packet_data_bytes: DEFAULT_MAX_PACKET_DATA_BYTES,
                 vp_whitelist: vec![],
                 tx_whitelist: vec![],
                 implicit_vp: vec![],
@@ -1294,6 +1438,12 @@ mod tests {
                 pos_gain_d: dec!(0.1),
                 staked_ratio: dec!(0.1),
                 pos_inflation_amount: 0,
+                fee_distribution: FeeDistribution::default(),
+                block_gas_limit: BLOCK_GAS_LIMIT,
+                max_verifiers: None,
+                fee_tokens: Default::default(),
+                vp_wasm_memory_max_pages: 200,
+                tx_wasm_memory_max_pages: 200,
             };
             parameters.init_storage(&mut storage);
 
@@ -1373,4 +1523,77 @@ mod tests {
                 time_of_update + parameters.epoch_duration.min_duration);
         }
     }
+
+    /// Produce blocks at the given interval from the start of an epoch that
+    /// lasts at least 10 blocks and 100 seconds. Returns the height of the
+    /// first block of the next epoch.
+    fn next_epoch_start_height(block_interval_secs: i64) -> BlockHeight {
+        let epoch_duration = EpochDuration {
+            min_num_of_blocks: 10,
+            min_duration: Duration::seconds(100).into(),
+        };
+        let start_time: DateTimeUtc = Utc
+            .timestamp_opt(0, 0)
+            .single()
+            .expect("expected valid timestamp")
+            .into();
+        let mut storage = TestStorage {
+            next_epoch_min_start_height: BlockHeight(
+                epoch_duration.min_num_of_blocks,
+            ),
+            next_epoch_min_start_time: start_time + epoch_duration.min_duration,
+            ..Default::default()
+        };
+        let parameters = Parameters {
+            epoch_duration,
+            max_expected_time_per_block: Duration::seconds(30).into(),
+            max_ibc_packet_data_bytes: DEFAULT_MAX_PACKET_DATA_BYTES,
+            vp_whitelist: vec![],
+            tx_whitelist: vec![],
+            implicit_vp: vec![],
+            epochs_per_year: 100,
+            pos_gain_p: dec!(0.1),
+            pos_gain_d: dec!(0.1),
+            staked_ratio: dec!(0.1),
+            pos_inflation_amount: 0,
+            fee_distribution: FeeDistribution::default(),
+            block_gas_limit: BLOCK_GAS_LIMIT,
+            max_verifiers: None,
+            fee_tokens: Default::default(),
+            vp_wasm_memory_max_pages: 200,
+            tx_wasm_memory_max_pages: 200,
+        };
+        parameters.init_storage(&mut storage);
+
+        let epoch_before = storage.block.epoch;
+        for height in 1..1000 {
+            let block_time =
+                start_time + Duration::seconds(height * block_interval_secs);
+            let height = BlockHeight(height as u64);
+            storage.update_epoch(height, block_time).unwrap();
+            if storage.block.epoch != epoch_before {
+                assert_eq!(storage.block.epoch, epoch_before.next());
+                return height;
+            }
+        }
+        panic!("A new epoch must begin")
+    }
+
+    /// Test that when the blocks are produced faster than expected, the
+    /// minimum duration of an epoch holds back the next epoch.
+    #[test]
+    fn test_update_epoch_fast_blocks() {
+        // With a block per second, the minimum number of blocks is produced
+        // after 10 seconds, but the epoch must last 100 seconds
+        assert_eq!(next_epoch_start_height(1), BlockHeight(100));
+    }
+
+    /// Test that when the blocks are produced slower than expected, the
+    /// minimum number of blocks of an epoch holds back the next epoch.
+    #[test]
+    fn test_update_epoch_slow_blocks() {
+        // With a block per minute, the minimum duration passes after 2
+        // blocks, but the epoch must last 10 blocks
+        assert_eq!(next_epoch_start_height(60), BlockHeight(10));
+    }
 }
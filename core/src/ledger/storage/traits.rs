@@ -12,7 +12,7 @@ use sha2::{Digest, Sha256};
 use super::ics23_specs;
 use super::merkle_tree::{Amt, Error, MembershipProof, Smt};
 use crate::ledger::storage::merkle_tree::StorageBytes;
-use crate::types::hash::Hash;
+use crate::types::hash::{Hash, HashAlgorithm};
 use crate::types::storage::{Key, StringKey, TreeBytes};
 
 /// Trait for reading from a merkle tree that is a sub-tree
@@ -156,6 +156,9 @@ impl<'a, H: StorageHasher + Default> SubTreeWrite for &'a mut Amt<H> {
 
 /// The storage hasher used for the merkle tree.
 pub trait StorageHasher: Hasher + Default {
+    /// The algorithm used by this hasher
+    const ALGORITHM: HashAlgorithm;
+
     /// Hash the value to store
     fn hash(value: impl AsRef<[u8]>) -> H256;
 }
@@ -184,6 +187,8 @@ impl Hasher for Sha256Hasher {
 }
 
 impl StorageHasher for Sha256Hasher {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
+
     fn hash(value: impl AsRef<[u8]>) -> H256 {
         let mut hasher = Sha256::new();
         hasher.update(value.as_ref());
@@ -0,0 +1,78 @@
+//! Withdrawals from a testnet faucet account, which anyone may draw a capped
+//! amount of tokens from without the faucet's signature, at most once per
+//! epoch for each target account.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::address::Address;
+use crate::types::storage::{DbKeySeg, Key, KeySeg};
+use crate::types::token;
+
+/// The maximum amount of a token that may be withdrawn from a faucet in a
+/// single withdrawal
+pub const MAX_WITHDRAWAL: token::Amount = token::Amount::whole(1_000);
+
+/// The storage key segment of the epochs of the last withdrawals
+const LAST_WITHDRAWAL_EPOCH_STORAGE_KEY: &str = "last_withdrawal_epoch";
+
+/// A withdrawal of tokens from a faucet
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Withdrawal {
+    /// The faucet account
+    pub faucet: Address,
+    /// The account credited with the withdrawn tokens
+    pub target: Address,
+    /// The withdrawn token
+    pub token: Address,
+    /// The withdrawn amount, at most [`MAX_WITHDRAWAL`]
+    pub amount: token::Amount,
+}
+
+/// Obtain a storage key for the epoch of the last withdrawal from the given
+/// faucet to the given target.
+pub fn last_withdrawal_epoch_key(faucet: &Address, target: &Address) -> Key {
+    Key::from(faucet.to_db_key())
+        .push(&LAST_WITHDRAWAL_EPOCH_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&target.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a key for the epoch of the last
+/// withdrawal from the given faucet. If it is, returns the target.
+pub fn is_last_withdrawal_epoch_key<'a>(
+    faucet: &Address,
+    key: &'a Key,
+) -> Option<&'a Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(target),
+        ] if key == LAST_WITHDRAWAL_EPOCH_STORAGE_KEY && addr == faucet => {
+            Some(target)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+
+    /// Test that the last withdrawal epoch keys are only recognized for
+    /// their faucet.
+    #[test]
+    fn test_last_withdrawal_epoch_key() {
+        let faucet = established_address_1();
+        let target = established_address_2();
+        let key = last_withdrawal_epoch_key(&faucet, &target);
+        assert_eq!(is_last_withdrawal_epoch_key(&faucet, &key), Some(&target));
+        assert_eq!(is_last_withdrawal_epoch_key(&target, &key), None);
+        let balance_key = token::balance_key(&faucet, &target);
+        assert_eq!(is_last_withdrawal_epoch_key(&faucet, &balance_key), None);
+    }
+}
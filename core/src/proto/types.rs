@@ -9,14 +9,13 @@ use thiserror::Error;
 use super::generated::types;
 #[cfg(any(feature = "tendermint", feature = "tendermint-abcipp"))]
 use crate::tendermint_proto::abci::ResponseDeliverTx;
+use crate::types::chain::ChainId;
 use crate::types::key::*;
 use crate::types::time::DateTimeUtc;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::token::Transfer;
 use crate::types::transaction::hash_tx;
 #[cfg(feature = "ferveo-tpke")]
-use crate::types::transaction::process_tx;
-#[cfg(feature = "ferveo-tpke")]
 use crate::types::transaction::DecryptedTx;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::transaction::TxType;
@@ -49,11 +48,17 @@ pub struct SignedTxData {
     /// The original tx data bytes, if any
     pub data: Option<Vec<u8>>,
     /// The signature is produced on the tx data concatenated with the tx code
-    /// and the timestamp.
+    /// and the timestamp, bound to the chain ID.
     pub sig: common::Signature,
 }
 
-/// A generic signed data wrapper for Borsh encode-able data.
+/// The context tag of the signatures of [`Signed`] data. It's signed along
+/// with the data, so that these signatures cannot be mistaken for signatures
+/// of other kinds of payloads.
+pub const SIGNED_DATA_CONTEXT: &str = "namada-signed-data";
+
+/// A generic signed data wrapper for Borsh encode-able data. The signature is
+/// bound to a chain ID, so that it cannot be replayed on another chain.
 #[derive(
     Clone, Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
 )]
@@ -101,27 +106,41 @@ impl<T> Signed<T>
 where
     T: BorshSerialize + BorshDeserialize,
 {
-    /// Initialize a new signed data.
-    pub fn new(keypair: &common::SecretKey, data: T) -> Self {
-        let to_sign = data
-            .try_to_vec()
-            .expect("Encoding data for signing shouldn't fail");
+    /// Initialize a new signed data for the given chain.
+    pub fn new(
+        keypair: &common::SecretKey,
+        chain_id: &ChainId,
+        data: T,
+    ) -> Self {
+        let to_sign = Self::signed_bytes(chain_id, &data);
         let sig = common::SigScheme::sign(keypair, to_sign);
         Self { data, sig }
     }
 
-    /// Verify that the data has been signed by the secret key
-    /// counterpart of the given public key.
+    /// Verify that the data has been signed for the given chain by the
+    /// secret key counterpart of the given public key.
     pub fn verify(
         &self,
         pk: &common::PublicKey,
+        chain_id: &ChainId,
     ) -> std::result::Result<(), VerifySigError> {
-        let bytes = self
-            .data
-            .try_to_vec()
-            .expect("Encoding data for verifying signature shouldn't fail");
+        let bytes = Self::signed_bytes(chain_id, &self.data);
         common::SigScheme::verify_signature_raw(pk, &bytes, &self.sig)
     }
+
+    /// Get the bytes that are signed: the [`SIGNED_DATA_CONTEXT`] tag, the
+    /// chain ID and the data.
+    fn signed_bytes(chain_id: &ChainId, data: &T) -> Vec<u8> {
+        let mut bytes = SIGNED_DATA_CONTEXT
+            .try_to_vec()
+            .expect("Encoding the context tag shouldn't fail");
+        chain_id
+            .serialize(&mut bytes)
+            .expect("Encoding the chain ID shouldn't fail");
+        data.serialize(&mut bytes)
+            .expect("Encoding data for signing shouldn't fail");
+        bytes
+    }
 }
 
 /// A Tx with its code replaced by a hash salted with the Borsh
@@ -152,9 +171,23 @@ impl SigningTx {
         hash_tx(&bytes).0
     }
 
-    /// Sign a transaction using [`SignedTxData`].
-    pub fn sign(self, keypair: &common::SecretKey) -> Self {
-        let to_sign = self.hash();
+    /// Get the hash that is signed, which binds the tx to the given chain ID,
+    /// so that its signature cannot be replayed on another chain.
+    pub fn signed_hash(&self, chain_id: &ChainId) -> [u8; 32] {
+        let mut bytes = self.hash().to_vec();
+        chain_id
+            .serialize(&mut bytes)
+            .expect("Encoding the chain ID shouldn't fail");
+        hash_tx(&bytes).0
+    }
+
+    /// Sign a transaction for the given chain using [`SignedTxData`].
+    pub fn sign(
+        self,
+        keypair: &common::SecretKey,
+        chain_id: &ChainId,
+    ) -> Self {
+        let to_sign = self.signed_hash(chain_id);
         let sig = common::SigScheme::sign(keypair, to_sign);
         let signed = SignedTxData {
             data: self.data,
@@ -169,12 +202,13 @@ impl SigningTx {
         }
     }
 
-    /// Verify that the transaction has been signed by the secret key
-    /// counterpart of the given public key.
+    /// Verify that the transaction has been signed for the given chain by the
+    /// secret key counterpart of the given public key.
     pub fn verify_sig(
         &self,
         pk: &common::PublicKey,
         sig: &common::Signature,
+        chain_id: &ChainId,
     ) -> std::result::Result<(), VerifySigError> {
         // Try to get the transaction data from decoded `SignedTxData`
         let tx_data = self.data.clone().ok_or(VerifySigError::MissingData)?;
@@ -186,7 +220,7 @@ impl SigningTx {
             data,
             timestamp: self.timestamp,
         };
-        let signed_data = tx.hash();
+        let signed_data = tx.signed_hash(chain_id);
         common::SigScheme::verify_signature_raw(pk, &signed_data, sig)
     }
 
@@ -283,7 +317,9 @@ impl From<Tx> for ResponseDeliverTx {
         fn encode_string(x: String) -> String {
             x
         }
-        match process_tx(tx) {
+        // The decrypted txs are not signed, so they're decoded without
+        // checking a signature
+        match TxType::try_from(tx) {
             Ok(TxType::Decrypted(DecryptedTx::Decrypted(tx))) => {
                 let empty_vec = vec![];
                 let tx_data = tx.data.as_ref().unwrap_or(&empty_vec);
@@ -362,23 +398,34 @@ impl Tx {
         SigningTx::from(self.clone()).code_hash
     }
 
-    /// Sign a transaction using [`SignedTxData`].
-    pub fn sign(self, keypair: &common::SecretKey) -> Self {
+    /// Get the hash that is signed for the given chain ID. See
+    /// [`SigningTx::signed_hash`].
+    pub fn signed_hash(&self, chain_id: &ChainId) -> [u8; 32] {
+        SigningTx::from(self.clone()).signed_hash(chain_id)
+    }
+
+    /// Sign a transaction for the given chain using [`SignedTxData`].
+    pub fn sign(
+        self,
+        keypair: &common::SecretKey,
+        chain_id: &ChainId,
+    ) -> Self {
         let code = self.code.clone();
         SigningTx::from(self)
-            .sign(keypair)
+            .sign(keypair, chain_id)
             .expand(code)
             .expect("code hashes to unexpected value")
     }
 
-    /// Verify that the transaction has been signed by the secret key
-    /// counterpart of the given public key.
+    /// Verify that the transaction has been signed for the given chain by the
+    /// secret key counterpart of the given public key.
     pub fn verify_sig(
         &self,
         pk: &common::PublicKey,
         sig: &common::Signature,
+        chain_id: &ChainId,
     ) -> std::result::Result<(), VerifySigError> {
-        SigningTx::from(self.clone()).verify_sig(pk, sig)
+        SigningTx::from(self.clone()).verify_sig(pk, sig, chain_id)
     }
 }
 
@@ -458,7 +505,10 @@ impl Dkg {
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use super::*;
+    use crate::types::chain::ChainIdPrefix;
 
     #[test]
     fn test_tx() {
@@ -484,6 +534,52 @@ mod tests {
         }
     }
 
+    /// Test that signed data verifies only for the chain it was signed for,
+    /// so that it cannot be replayed on another chain.
+    #[test]
+    fn test_signed_data_chain_id() {
+        let keypair = crate::types::key::testing::keypair_1();
+        let pk = keypair.ref_to();
+        let chain_a = ChainId::default();
+        let chain_b = ChainIdPrefix::from_str("other")
+            .expect("valid prefix")
+            .temp_chain_id();
+        let data = "arbitrary data".to_owned();
+
+        let signed = Signed::new(&keypair, &chain_a, data.clone());
+        assert!(signed.verify(&pk, &chain_a).is_ok());
+        assert!(signed.verify(&pk, &chain_b).is_err());
+
+        // A signature over the bare data is not accepted either
+        let bare = Signed {
+            data: data.clone(),
+            sig: common::SigScheme::sign(&keypair, data.try_to_vec().unwrap()),
+        };
+        assert!(bare.verify(&pk, &chain_a).is_err());
+    }
+
+    /// Test that a signed tx verifies only for the chain it was signed for,
+    /// so that it cannot be replayed on another chain.
+    #[test]
+    fn test_signed_tx_chain_id() {
+        let keypair = crate::types::key::testing::keypair_1();
+        let pk = keypair.ref_to();
+        let chain_a = ChainId::default();
+        let chain_b = ChainIdPrefix::from_str("other")
+            .expect("valid prefix")
+            .temp_chain_id();
+        let tx = Tx::new(
+            "wasm code".as_bytes().to_owned(),
+            Some("arbitrary data".as_bytes().to_owned()),
+        )
+        .sign(&keypair, &chain_a);
+        let signed = SignedTxData::try_from_slice(&tx.data.clone().unwrap())
+            .expect("decoding failed");
+
+        assert!(tx.verify_sig(&pk, &signed.sig, &chain_a).is_ok());
+        assert!(tx.verify_sig(&pk, &signed.sig, &chain_b).is_err());
+    }
+
     #[test]
     fn test_dkg_gossip_message() {
         let data = "arbitrary string".to_owned();
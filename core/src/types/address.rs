@@ -91,7 +91,21 @@ pub enum DecodeError {
     UnexpectedBech32Prefix(String, String),
     #[error("Unexpected Bech32m variant {0:?}, expected {BECH32M_VARIANT:?}")]
     UnexpectedBech32Variant(bech32::Variant),
-    #[error("Invalid address encoding")]
+    #[error(
+        "Invalid Bech32m checksum, the address may contain a typo or be \
+         incomplete"
+    )]
+    InvalidChecksum,
+    #[error(
+        "Unexpected address length {0}, expected {ADDRESS_LEN} characters"
+    )]
+    UnexpectedLength(usize),
+    #[error(
+        "Unknown address kind {0:?}, expected one of {PREFIX_ESTABLISHED:?}, \
+         {PREFIX_IMPLICIT:?}, {PREFIX_INTERNAL:?} or {PREFIX_IBC:?}"
+    )]
+    UnknownDiscriminant(String),
+    #[error("Invalid address encoding: {0}")]
     InvalidInnerEncoding(std::io::Error),
 }
 
@@ -132,10 +146,28 @@ impl Address {
             })
     }
 
-    /// Decode an address from Bech32m encoding
+    /// Decode an address from Bech32m encoding. The human-readable part and
+    /// the length are checked before the checksum, so that an address of
+    /// another network or an incomplete address is reported as such.
     pub fn decode(string: impl AsRef<str>) -> Result<Self> {
-        let (prefix, hash_base32, variant) = bech32::decode(string.as_ref())
-            .map_err(DecodeError::DecodeBech32)?;
+        let string = string.as_ref();
+        // The human-readable part is separated from the data by the last `1`
+        if let Some((prefix, _data)) = string.rsplit_once('1') {
+            if !prefix.eq_ignore_ascii_case(ADDRESS_HRP) {
+                return Err(DecodeError::UnexpectedBech32Prefix(
+                    prefix.to_lowercase(),
+                    ADDRESS_HRP.into(),
+                ));
+            }
+        }
+        if string.len() != ADDRESS_LEN {
+            return Err(DecodeError::UnexpectedLength(string.len()));
+        }
+        let (prefix, hash_base32, variant) =
+            bech32::decode(string).map_err(|err| match err {
+                bech32::Error::InvalidChecksum => DecodeError::InvalidChecksum,
+                err => DecodeError::DecodeBech32(err),
+            })?;
         if prefix != ADDRESS_HRP {
             return Err(DecodeError::UnexpectedBech32Prefix(
                 prefix,
@@ -148,10 +180,34 @@ impl Address {
         }
         let bytes: Vec<u8> = FromBase32::from_base32(&hash_base32)
             .map_err(DecodeError::DecodeBase32)?;
+        if let Some((discriminant, _)) = std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|string| string.split_once("::"))
+        {
+            if ![
+                PREFIX_ESTABLISHED,
+                PREFIX_IMPLICIT,
+                PREFIX_INTERNAL,
+                PREFIX_IBC,
+            ]
+            .contains(&discriminant)
+            {
+                return Err(DecodeError::UnknownDiscriminant(
+                    discriminant.to_owned(),
+                ));
+            }
+        }
         Self::try_from_fixed_len_string(&mut &bytes[..])
             .map_err(DecodeError::InvalidInnerEncoding)
     }
 
+    /// Derive the implicit address of an account from its public key. This is
+    /// how the ledger derives implicit addresses, so it can be used to get
+    /// the address of an account before it's revealed on-chain.
+    pub fn from_public_key(pk: &key::common::PublicKey) -> Self {
+        Self::Implicit(pk.into())
+    }
+
     /// Try to get a raw hash of an address, only defined for established and
     /// implicit addresses.
     pub fn raw_hash(&self) -> Option<&str> {
@@ -424,7 +480,7 @@ impl From<&key::common::PublicKey> for ImplicitAddress {
 
 impl From<&key::common::PublicKey> for Address {
     fn from(pk: &key::common::PublicKey) -> Self {
-        Self::Implicit(pk.into())
+        Self::from_public_key(pk)
     }
 }
 
@@ -628,6 +684,80 @@ pub mod tests {
         assert_eq!(encoded_address, expect);
     }
 
+    /// Test that the implicit addresses derived from known ed25519 and
+    /// secp256k1 public keys match their known addresses.
+    #[test]
+    fn test_address_from_public_key() {
+        use data_encoding::HEXLOWER;
+
+        // The Borsh encoded public keys, prefixed by their scheme
+        let known = [
+            (
+                "00d2bbc65a45539c4dc73fd03f896616e56ec326ae8e7f9de08bd4efcc3a506cb8",
+                "atest1d9khqw36g4zrj3358y6rs32zgceyvvfsxfzrz3z9xg65zv34xcer2sehgvuyvwfn8ymyys2zlnxvy4",
+            ),
+            (
+                "010284bf7562262bbd6940085748f3be6afa52ae317155181ece31b66351ccffa4b0",
+                "atest1d9khqw368qcngdphxerrws6rxaprj33kx5m5ysjrg5mrjdjpgvmnvs6r8prrqwpexv6yvd3emyxruw",
+            ),
+        ];
+        for (pk, address) in known {
+            let pk = key::common::PublicKey::try_from_slice(
+                &HEXLOWER.decode(pk.as_bytes()).unwrap(),
+            )
+            .unwrap();
+            let derived = Address::from_public_key(&pk);
+            assert!(matches!(derived, Address::Implicit(_)));
+            assert_eq!(derived.encode(), address);
+            assert_eq!(Address::decode(address).unwrap(), derived);
+            assert_eq!(Address::from(&pk), derived);
+        }
+    }
+
+    /// Test that each kind of malformed address is reported with a distinct
+    /// error.
+    #[test]
+    fn test_address_decode_errors() {
+        let address = "atest1v4ehgw36g56ngwpk8ppnzsf4xqeyvsf3xq6nxde5gseyys3nxgenvvfex5cnyd2rx9zrzwfctgx7sp";
+        assert!(Address::decode(address).is_ok());
+        // The Bech32m encoding is case-insensitive
+        assert!(Address::decode(address.to_uppercase()).is_ok());
+
+        // A typo in the data part
+        let corrupted = address.replacen("v4ehgw36", "v4ehgw37", 1);
+        assert!(matches!(
+            Address::decode(corrupted),
+            Err(DecodeError::InvalidChecksum)
+        ));
+
+        // An address of another network
+        let wrong_prefix = address.replacen("atest", "anet", 1);
+        assert!(matches!(
+            Address::decode(wrong_prefix),
+            Err(DecodeError::UnexpectedBech32Prefix(prefix, _))
+                if prefix == "anet"
+        ));
+
+        // An incomplete address
+        let truncated = &address[..address.len() - 1];
+        assert!(matches!(
+            Address::decode(truncated),
+            Err(DecodeError::UnexpectedLength(len)) if len == ADDRESS_LEN - 1
+        ));
+
+        // A well-encoded string of an unknown kind of address
+        let mut unknown = b"xyz::".to_vec();
+        unknown.resize(FIXED_LEN_STRING_BYTES, b' ');
+        let unknown =
+            bech32::encode(ADDRESS_HRP, unknown.to_base32(), BECH32M_VARIANT)
+                .unwrap();
+        assert!(matches!(
+            Address::decode(unknown),
+            Err(DecodeError::UnknownDiscriminant(discriminant))
+                if discriminant == "xyz"
+        ));
+    }
+
     proptest! {
         #[test]
         /// Check that all the address types are of the same length
@@ -111,6 +111,43 @@ impl FromStr for Hash {
     }
 }
 
+/// The algorithms that may produce a [`Hash`].
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub enum HashAlgorithm {
+    /// SHA-256, the default algorithm
+    Sha256,
+    /// BLAKE2b with a 32 bytes output, used by the ledger's persistent
+    /// storage
+    Blake2b,
+}
+
+impl Default for HashAlgorithm {
+    fn default() -> Self {
+        Self::Sha256
+    }
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+            HashAlgorithm::Blake2b => write!(f, "blake2b"),
+        }
+    }
+}
+
 impl Hash {
     /// Compute sha256 of some bytes
     pub fn sha256(data: impl AsRef<[u8]>) -> Self {
@@ -176,3 +213,25 @@ impl From<&H256> for Hash {
         Self(hash.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the default algorithm is SHA-256 and that the algorithms'
+    /// discriminants are stable.
+    #[test]
+    fn test_algorithm_discriminants() {
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Sha256);
+        for (algorithm, discriminant) in
+            [(HashAlgorithm::Sha256, 0), (HashAlgorithm::Blake2b, 1)]
+        {
+            let bytes = algorithm.try_to_vec().unwrap();
+            assert_eq!(bytes, vec![discriminant]);
+            let decoded = HashAlgorithm::try_from_slice(&bytes).unwrap();
+            assert_eq!(decoded, algorithm);
+        }
+        // An unknown discriminant is rejected
+        assert!(HashAlgorithm::try_from_slice(&[2]).is_err());
+    }
+}
@@ -3,6 +3,37 @@
 use std::collections::HashMap;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use thiserror::Error;
+
+/// The default maximum size of the data of an IBC packet in bytes, used when
+/// it's not set in the genesis parameters
+pub const DEFAULT_MAX_PACKET_DATA_BYTES: u64 = 64 * 1024;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PacketDataError {
+    #[error(
+        "The IBC packet data of {size} bytes exceeds the maximum of {max} \
+         bytes"
+    )]
+    TooLarge { size: u64, max: u64 },
+}
+
+/// Check that the data of an IBC packet doesn't exceed the maximum size in
+/// bytes. A packet with data of exactly the maximum size is accepted.
+pub fn check_packet_data_size(
+    data: &[u8],
+    max_bytes: u64,
+) -> Result<(), PacketDataError> {
+    let size = data.len() as u64;
+    if size > max_bytes {
+        return Err(PacketDataError::TooLarge {
+            size,
+            max: max_bytes,
+        });
+    }
+    Ok(())
+}
 
 /// Wrapped IbcEvent
 #[derive(
@@ -72,3 +103,24 @@ mod ibc_rs_conversion {
 
 #[cfg(any(feature = "abciplus", feature = "abcipp"))]
 pub use ibc_rs_conversion::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the packet data of exactly the maximum size is accepted and
+    /// that one byte over is rejected.
+    #[test]
+    fn test_packet_data_size_limit() {
+        let max = 100;
+        let data = vec![0_u8; max as usize];
+        assert_eq!(check_packet_data_size(&data, max), Ok(()));
+        assert_eq!(check_packet_data_size(&[], max), Ok(()));
+
+        let data = vec![0_u8; max as usize + 1];
+        assert_eq!(
+            check_packet_data_size(&data, max),
+            Err(PacketDataError::TooLarge { size: max + 1, max })
+        );
+    }
+}
@@ -37,6 +37,16 @@ pub enum PublicKey {
     Secp256k1(secp256k1::PublicKey),
 }
 
+impl PublicKey {
+    /// The signature scheme of this public key
+    pub fn scheme(&self) -> SchemeType {
+        match self {
+            PublicKey::Ed25519(_) => SchemeType::Ed25519,
+            PublicKey::Secp256k1(_) => SchemeType::Secp256k1,
+        }
+    }
+}
+
 impl super::PublicKey for PublicKey {
     const TYPE: SchemeType = SigScheme::TYPE;
 
@@ -128,7 +128,9 @@ pub trait TryFromRef<T: ?Sized>: Sized {
 }
 
 /// Type capturing signature scheme IDs
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(
+    PartialEq, Eq, Copy, Clone, Debug, BorshSerialize, BorshDeserialize,
+)]
 pub enum SchemeType {
     /// Type identifier for Ed25519 scheme
     Ed25519,
@@ -151,6 +153,32 @@ impl FromStr for SchemeType {
     }
 }
 
+/// The public key of an account, if any, with its signature scheme
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum AccountPublicKey {
+    /// No public key has been established for the account
+    Uninitialized,
+    /// The public key established for the account
+    Initialized {
+        /// The public key
+        public_key: common::PublicKey,
+        /// The signature scheme of the public key
+        scheme: SchemeType,
+    },
+}
+
+impl From<Option<common::PublicKey>> for AccountPublicKey {
+    fn from(public_key: Option<common::PublicKey>) -> Self {
+        match public_key {
+            Some(public_key) => Self::Initialized {
+                scheme: public_key.scheme(),
+                public_key,
+            },
+            None => Self::Uninitialized,
+        }
+    }
+}
+
 /// Represents a signature
 
 pub trait Signature:
@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::bytes::ByteBuf;
-use crate::types::address::{self, Address};
+use crate::types::address::{self, Address, InternalAddress};
 use crate::types::hash::Hash;
 use crate::types::time::DateTimeUtc;
 
@@ -285,6 +285,30 @@ impl Header {
     }
 }
 
+/// The metadata of a committed block
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BlockMetadata {
+    /// Height of the block
+    pub height: BlockHeight,
+    /// Hash of the block
+    pub hash: BlockHash,
+    /// Timestamp associated to the block
+    pub time: DateTimeUtc,
+}
+
+/// The boundaries of the epoch of the last committed block
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct EpochBoundaries {
+    /// Epoch of the last committed block
+    pub current_epoch: Epoch,
+    /// Height of the first block of the current epoch
+    pub first_block_of_current: BlockHeight,
+    /// Minimum block height from which the next epoch can start
+    pub next_epoch_min_start_height: BlockHeight,
+    /// Minimum block time from which the next epoch can start
+    pub next_epoch_min_start_time: DateTimeUtc,
+}
+
 /// A storage key is made of storage key segments [`DbKeySeg`], separated by
 /// [`KEY_SEGMENT_SEPARATOR`].
 #[derive(
@@ -307,6 +331,61 @@ pub struct Key {
     pub segments: Vec<DbKeySeg>,
 }
 
+/// A segment of a [`KeyPattern`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeyPatternSeg {
+    /// Matches only the given segment
+    Exact(DbKeySeg),
+    /// Matches and captures any single segment
+    Any,
+}
+
+/// A pattern of storage keys made of exact segments and wildcards, to be
+/// matched with [`Key::matches_pattern`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeyPattern {
+    segments: Vec<KeyPatternSeg>,
+    /// When `true`, the pattern also matches and captures any number of
+    /// segments following its last segment
+    any_trailing: bool,
+}
+
+impl KeyPattern {
+    /// A pattern matching exactly the given key
+    pub fn exact(key: &Key) -> Self {
+        Self {
+            segments: key
+                .segments
+                .iter()
+                .cloned()
+                .map(KeyPatternSeg::Exact)
+                .collect(),
+            any_trailing: false,
+        }
+    }
+
+    /// Returns a new pattern with segments of `Self` and the given exact
+    /// segment
+    pub fn push_exact<T: KeySeg>(mut self, seg: &T) -> Self {
+        self.segments.push(KeyPatternSeg::Exact(seg.to_db_key()));
+        self
+    }
+
+    /// Returns a new pattern with segments of `Self` and a single segment
+    /// wildcard
+    pub fn push_any(mut self) -> Self {
+        self.segments.push(KeyPatternSeg::Any);
+        self
+    }
+
+    /// Returns a new pattern that also matches any number of segments
+    /// following the segments of `Self`
+    pub fn any_trailing(mut self) -> Self {
+        self.any_trailing = true;
+        self
+    }
+}
+
 /// A [`Key`] made of borrowed key segments [`DbKeySeg`].
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct KeyRef<'a> {
@@ -524,6 +603,33 @@ impl Key {
         Some((KeyRef { segments: prefix }, last))
     }
 
+    /// Match the key against the given pattern. On a match, returns the
+    /// segments captured by the pattern's wildcards, in order.
+    pub fn matches_pattern<'a>(
+        &'a self,
+        pattern: &KeyPattern,
+    ) -> Option<Vec<&'a DbKeySeg>> {
+        let num_segments = pattern.segments.len();
+        let len_matches = if pattern.any_trailing {
+            self.segments.len() >= num_segments
+        } else {
+            self.segments.len() == num_segments
+        };
+        if !len_matches {
+            return None;
+        }
+        let mut captured = vec![];
+        for (seg, pattern_seg) in self.segments.iter().zip(&pattern.segments) {
+            match pattern_seg {
+                KeyPatternSeg::Exact(exact) if exact == seg => {}
+                KeyPatternSeg::Exact(_) => return None,
+                KeyPatternSeg::Any => captured.push(seg),
+            }
+        }
+        captured.extend(&self.segments[num_segments..]);
+        Some(captured)
+    }
+
     /// Returns a key of the validity predicate of the given address
     /// Only this function can push "?" segment for validity predicate
     pub fn validity_predicate(addr: &Address) -> Self {
@@ -545,6 +651,15 @@ impl Key {
         }
     }
 
+    /// Check if the key is in one of the namespaces reserved for the
+    /// protocol's subsystems, given by [`reserved_prefixes`]. The prefixes are
+    /// matched by whole segments.
+    pub fn is_reserved(&self) -> bool {
+        reserved_prefixes()
+            .iter()
+            .any(|prefix| self.split_prefix(prefix).is_some())
+    }
+
     /// Returns a key from the given DB key path that has the height and
     /// the space type
     pub fn parse_db_key(db_key: &str) -> Result<Self> {
@@ -623,6 +738,25 @@ impl Key {
     }
 }
 
+/// The prefixes of the storage key namespaces reserved for the protocol's
+/// subsystems. Only the subsystems write under them and the user VPs reject
+/// a write under them. The governance proposals, which are validated by the
+/// governance VP, only require a valid signature.
+///
+/// The IBC and MASP namespaces are not reserved, because the txs relayed or
+/// shielded by third parties write under them when crediting an account.
+pub fn reserved_prefixes() -> [Key; 6] {
+    [
+        InternalAddress::PoS,
+        InternalAddress::PosSlashPool,
+        InternalAddress::Parameters,
+        InternalAddress::Governance,
+        InternalAddress::SlashFund,
+        InternalAddress::EthBridge,
+    ]
+    .map(|internal| Key::from(Address::Internal(internal).to_db_key()))
+}
+
 impl Display for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let key = self
@@ -1059,6 +1193,17 @@ impl Epochs {
         self.first_block_heights.push(block_height);
     }
 
+    /// Look-up the height of the first block of the last known epoch.
+    pub fn last_epoch_first_height(&self) -> Option<BlockHeight> {
+        self.first_block_heights.last().copied()
+    }
+
+    /// Look-up the height of the first block of a given epoch.
+    pub fn get_first_block_height(&self, epoch: Epoch) -> Option<BlockHeight> {
+        let index = epoch.0.checked_sub(self.first_known_epoch.0)?;
+        self.first_block_heights.get(index as usize).copied()
+    }
+
     /// Look-up the epoch of a given block height.
     pub fn get_epoch(&self, block_height: BlockHeight) -> Option<Epoch> {
         if let Some((first_known_epoch_height, rest)) =
@@ -1090,11 +1235,102 @@ pub struct PrefixValue {
     pub value: Vec<u8>,
 }
 
+/// A page of the values of a storage prefix iterator.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct PrefixValuePage {
+    /// The values in this page
+    pub values: Vec<PrefixValue>,
+    /// The height of the block at which the values were read. The following
+    /// pages must be requested at the same height.
+    pub height: BlockHeight,
+    /// The offset of the next page, if there are any more values
+    pub next_offset: Option<u64>,
+}
+
+/// A value of a storage dump, for diagnostics.
+#[derive(
+    Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct StorageDumpEntry {
+    /// Storage key
+    pub key: Key,
+    /// Hex-encoded raw value bytes
+    pub value: String,
+}
+
+/// A page of a dump of the storage under a prefix, for diagnostics.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct StorageDumpPage {
+    /// The entries in this page
+    pub entries: Vec<StorageDumpEntry>,
+    /// The height of the block at which the entries were read. The following
+    /// pages must be requested at the same height.
+    pub height: BlockHeight,
+    /// The offset of the next page, if there are any more entries
+    pub next_offset: Option<u64>,
+}
+
 #[cfg(test)]
 mod tests {
     use proptest::prelude::*;
 
     use super::*;
+    use crate::types::token;
+
+    /// Test that the keys in the namespaces of the protocol's subsystems are
+    /// reserved and that the users' keys aren't, matching the namespaces by
+    /// whole segments.
+    #[test]
+    fn test_reserved_keys() {
+        let pos = Address::Internal(InternalAddress::PoS);
+        let governance = Address::Internal(InternalAddress::Governance);
+        let user = address::testing::established_address_1();
+
+        // The namespaces and the keys under them are reserved
+        for prefix in reserved_prefixes() {
+            assert!(prefix.is_reserved(), "{}", prefix);
+        }
+        let pos_key = Key::from(pos.to_db_key())
+            .push(&"validator".to_owned())
+            .unwrap();
+        assert!(pos_key.is_reserved());
+        let proposal_key = Key::from(governance.to_db_key())
+            .push(&"proposal".to_owned())
+            .unwrap();
+        assert!(proposal_key.is_reserved());
+
+        // The users' keys aren't reserved
+        let user_key = Key::from(user.to_db_key())
+            .push(&"data".to_owned())
+            .unwrap();
+        assert!(!user_key.is_reserved());
+        assert!(!Key::validity_predicate(&user).is_reserved());
+        assert!(!token::balance_key(&address::nam(), &user).is_reserved());
+        // A user's key that contains a reserved address after its prefix
+        assert!(!token::balance_key(&address::nam(), &pos).is_reserved());
+        assert!(!Key::from(user.to_db_key())
+            .push(&pos)
+            .unwrap()
+            .is_reserved());
+
+        // A segment that spells out a reserved address without being one is
+        // at the boundary of the namespace, but not in it
+        let look_alike = Key::from(DbKeySeg::StringSeg(pos.encode()))
+            .push(&"validator".to_owned())
+            .unwrap();
+        assert!(!look_alike.is_reserved());
+        let look_alike = Key::from(DbKeySeg::StringSeg(format!(
+            "{}{}",
+            pos.to_db_key().raw(),
+            "x"
+        )));
+        assert!(!look_alike.is_reserved());
+
+        // IBC and MASP aren't reserved
+        let ibc = Address::Internal(InternalAddress::Ibc);
+        assert!(!Key::from(ibc.to_db_key()).is_reserved());
+        assert!(!Key::from(address::masp().to_db_key()).is_reserved());
+    }
 
     proptest! {
         /// Tests that any key that doesn't contain reserved prefixes is valid.
@@ -1170,6 +1406,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_key_matches_pattern() {
+        let addr = address::testing::established_address_1();
+        let key = Key::from(addr.to_db_key())
+            .push(&"a".to_owned())
+            .and_then(|key| key.push(&"b".to_owned()))
+            .expect("cannot push the segment");
+
+        let pattern = KeyPattern::exact(&key);
+        assert_eq!(key.matches_pattern(&pattern), Some(vec![]));
+
+        // A mid-key wildcard
+        let pattern = KeyPattern::default()
+            .push_exact(&addr)
+            .push_any()
+            .push_exact(&"b".to_owned());
+        assert_eq!(
+            key.matches_pattern(&pattern),
+            Some(vec![&DbKeySeg::StringSeg("a".to_owned())])
+        );
+        let pattern = KeyPattern::default()
+            .push_exact(&addr)
+            .push_any()
+            .push_exact(&"c".to_owned());
+        assert_eq!(key.matches_pattern(&pattern), None);
+
+        // A trailing wildcard
+        let pattern = KeyPattern::default().push_exact(&addr).any_trailing();
+        assert_eq!(
+            key.matches_pattern(&pattern),
+            Some(vec![
+                &DbKeySeg::StringSeg("a".to_owned()),
+                &DbKeySeg::StringSeg("b".to_owned())
+            ])
+        );
+        let pattern = KeyPattern::exact(&key).any_trailing();
+        assert_eq!(key.matches_pattern(&pattern), Some(vec![]));
+
+        // Patterns with a different number of segments don't match
+        let pattern = KeyPattern::default().push_exact(&addr).push_any();
+        assert_eq!(key.matches_pattern(&pattern), None);
+        let pattern = KeyPattern::exact(&key).push_any();
+        assert_eq!(key.matches_pattern(&pattern), None);
+    }
+
+    #[test]
+    fn test_key_matches_governance_pattern() {
+        use crate::ledger::governance::storage as gov_storage;
+
+        // The pattern of the committing proposals of epoch 1 must not match
+        // those of epochs 11 and 110 that share its prefix
+        let pattern = gov_storage::get_commiting_proposals_pattern(1);
+        let key = gov_storage::get_committing_proposals_key(7, 1);
+        assert_eq!(
+            key.matches_pattern(&pattern),
+            Some(vec![&DbKeySeg::StringSeg("7".to_owned())])
+        );
+        for epoch in [11, 110] {
+            let key = gov_storage::get_committing_proposals_key(7, epoch);
+            assert_eq!(key.matches_pattern(&pattern), None);
+        }
+
+        // Capture the voter's address of any proposal's vote
+        let delegator = address::testing::established_address_1();
+        let voter = address::testing::established_address_2();
+        let key =
+            gov_storage::get_vote_proposal_key(3, voter.clone(), delegator);
+        let pattern =
+            KeyPattern::exact(&gov_storage::get_proposal_vote_prefix_key(3))
+                .any_trailing();
+        let captured = key.matches_pattern(&pattern).unwrap();
+        assert_eq!(captured.len(), 2);
+        assert!(captured.contains(&&DbKeySeg::AddressSeg(voter)));
+    }
+
     #[test]
     fn test_predecessor_epochs() {
         let mut epochs = Epochs::default();
@@ -1205,6 +1516,16 @@ mod tests {
         assert_eq!(epochs.get_epoch(BlockHeight(20)), Some(Epoch(2)));
         assert_eq!(epochs.get_epoch(BlockHeight(100)), Some(Epoch(2)));
         assert_eq!(epochs.get_epoch(BlockHeight(200)), Some(Epoch(3)));
+        assert_eq!(epochs.get_first_block_height(Epoch(1)), None);
+        assert_eq!(
+            epochs.get_first_block_height(Epoch(2)),
+            Some(BlockHeight(20))
+        );
+        assert_eq!(
+            epochs.get_first_block_height(Epoch(3)),
+            Some(BlockHeight(200))
+        );
+        assert_eq!(epochs.get_first_block_height(Epoch(4)), None);
 
         // increase the limit
         max_age_num_blocks = 200;
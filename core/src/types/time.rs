@@ -3,9 +3,11 @@
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Display;
 use std::ops::{Add, Sub};
+use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 pub use chrono::{DateTime, Duration, TimeZone, Utc};
+use thiserror::Error;
 
 /// Check if the given `duration` has passed since the given `start.
 pub fn duration_passed(
@@ -109,6 +111,47 @@ impl DateTimeUtc {
     }
 }
 
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseDateTimeError {
+    #[error("Invalid RFC 3339 date-time: {0}")]
+    Rfc3339(chrono::ParseError),
+    #[error("The Unix timestamp {0} is out of range")]
+    UnixTimestampOutOfRange(String),
+}
+
+impl FromStr for DateTimeUtc {
+    type Err = ParseDateTimeError;
+
+    /// Parse either an RFC 3339 date-time (e.g. "1970-01-01T00:00:00Z") or
+    /// a Unix timestamp in seconds (e.g. "0"). A string of ASCII digits only
+    /// is a Unix timestamp. Because of that, a timestamp before the Unix
+    /// epoch, which would have a leading `-`, is not accepted and must be
+    /// given as an RFC 3339 date-time instead.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_empty() && s.bytes().all(|byte| byte.is_ascii_digit()) {
+            let out_of_range =
+                || ParseDateTimeError::UnixTimestampOutOfRange(s.to_owned());
+            let secs: i64 = s.parse().map_err(|_| out_of_range())?;
+            let utc = Utc
+                .timestamp_opt(secs, 0)
+                .single()
+                .ok_or_else(out_of_range)?;
+            return Ok(Self(utc));
+        }
+        let utc = DateTime::parse_from_rfc3339(s)
+            .map_err(ParseDateTimeError::Rfc3339)?;
+        Ok(Self(utc.into()))
+    }
+}
+
+impl Display for DateTimeUtc {
+    /// Display as an RFC 3339 date-time, the canonical format
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_rfc3339())
+    }
+}
+
 impl Add<DurationSecs> for DateTimeUtc {
     type Output = DateTimeUtc;
 
@@ -248,3 +291,54 @@ impl TryFrom<crate::tendermint::time::Time> for DateTimeUtc {
         Rfc3339String(t.to_rfc3339()).try_into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a Unix timestamp and an RFC 3339 date-time of the same
+    /// instant are parsed to the same value, which is displayed as RFC 3339.
+    #[test]
+    fn test_parse_date_time() {
+        let rfc3339: DateTimeUtc = "2022-11-21T03:06:40Z".parse().unwrap();
+        let timestamp: DateTimeUtc = "1669000000".parse().unwrap();
+        assert_eq!(rfc3339, timestamp);
+        assert_eq!(timestamp.to_string(), "2022-11-21T03:06:40+00:00");
+        assert_eq!(rfc3339.to_string().parse::<DateTimeUtc>(), Ok(rfc3339));
+
+        let epoch: DateTimeUtc = "0".parse().unwrap();
+        assert_eq!(epoch, "1970-01-01T00:00:00Z".parse().unwrap());
+    }
+
+    /// Test that the fractional seconds of an RFC 3339 date-time are kept.
+    #[test]
+    fn test_parse_fractional_seconds() {
+        let fractional: DateTimeUtc = "2022-11-21T03:06:40.5Z".parse().unwrap();
+        let whole: DateTimeUtc = "1669000000".parse().unwrap();
+        assert_eq!(fractional.0 - whole.0, Duration::milliseconds(500));
+        assert_eq!(fractional.to_string(), "2022-11-21T03:06:40.500+00:00");
+    }
+
+    /// Test that timestamps before the Unix epoch, out of range timestamps
+    /// and other malformed strings are rejected.
+    #[test]
+    fn test_parse_invalid_date_time() {
+        assert!(matches!(
+            "-1".parse::<DateTimeUtc>(),
+            Err(ParseDateTimeError::Rfc3339(_))
+        ));
+        assert!(matches!(
+            "99999999999999999999".parse::<DateTimeUtc>(),
+            Err(ParseDateTimeError::UnixTimestampOutOfRange(_))
+        ));
+        for malformed in ["", "1669000000.5", "2022-11-21", " 1669000000"] {
+            assert!(
+                matches!(
+                    malformed.parse::<DateTimeUtc>(),
+                    Err(ParseDateTimeError::Rfc3339(_))
+                ),
+                "{malformed}"
+            );
+        }
+    }
+}
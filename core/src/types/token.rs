@@ -1,5 +1,6 @@
 //! A basic fungible token
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 use std::str::FromStr;
@@ -11,10 +12,14 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::types::address::{masp, Address, DecodeError as AddressError};
-use crate::types::storage::{DbKeySeg, Key, KeySeg};
+use crate::types::storage::{BlockHeight, DbKeySeg, Key, KeySeg, TxIndex};
 
 /// Amount in micro units. For different granularity another representation
 /// might be more appropriate.
+///
+/// The Borsh encoding of an amount is fixed to the 8 little-endian bytes of
+/// its micro units. The amounts are written to storage in this encoding, so
+/// changing it would change the merkle tree and it's consensus-breaking.
 #[derive(
     Clone,
     Copy,
@@ -38,7 +43,8 @@ pub const MAX_DECIMAL_PLACES: u32 = 6;
 /// Decimal scale of token [`Amount`] and [`Change`].
 pub const SCALE: u64 = 1_000_000;
 
-/// A change in tokens amount
+/// A change in tokens amount. Like [`Amount`], its Borsh encoding is fixed to
+/// 16 little-endian bytes in two's complement.
 pub type Change = i128;
 
 impl Amount {
@@ -59,6 +65,99 @@ impl Amount {
         self.micro = self.micro.checked_add(amount.micro).unwrap();
     }
 
+    /// Checked addition. Returns `None` on overflow.
+    pub fn checked_add(&self, amount: &Amount) -> Option<Self> {
+        self.micro
+            .checked_add(amount.micro)
+            .map(|micro| Self { micro })
+    }
+
+    /// Add an amount, clamping the result at [`Amount::max`] instead of
+    /// overflowing.
+    ///
+    /// Only for non-consensus use, such as display or diagnostics. A
+    /// saturated result is not the real sum, so the balance changes validated
+    /// or applied by the protocol must use [`Amount::receive`], which fails
+    /// on overflow.
+    #[must_use]
+    pub fn saturating_add(&self, amount: &Amount) -> Self {
+        Self {
+            micro: self.micro.saturating_add(amount.micro),
+        }
+    }
+
+    /// Subtract an amount, clamping the result at zero instead of
+    /// underflowing.
+    ///
+    /// Only for non-consensus use, such as display or diagnostics. A
+    /// saturated result is not the real difference, so the balance changes
+    /// validated or applied by the protocol must use [`Amount::spend`], which
+    /// fails on underflow.
+    #[must_use]
+    pub fn saturating_sub(&self, amount: &Amount) -> Self {
+        Self {
+            micro: self.micro.saturating_sub(amount.micro),
+        }
+    }
+
+    /// Multiply the amount by a rate, rounding the result down to the nearest
+    /// micro unit.
+    ///
+    /// Returns `None` if the rate is negative or the result overflows.
+    #[must_use]
+    pub fn mul_floor(&self, rate: Decimal) -> Option<Self> {
+        self.mul_rate(rate, false)
+    }
+
+    /// Multiply the amount by a rate, rounding the result up to the nearest
+    /// micro unit.
+    ///
+    /// Returns `None` if the rate is negative or the result overflows.
+    #[must_use]
+    pub fn mul_ceil(&self, rate: Decimal) -> Option<Self> {
+        self.mul_rate(rate, true)
+    }
+
+    /// Multiply the amount by a rate exactly, with the result rounded in the
+    /// given direction. The rate is `mantissa / 10^scale`, so the product of
+    /// the amount and the mantissa, which can take up to 160 bits, is divided
+    /// by the power of ten in steps that fit in a `u64`.
+    fn mul_rate(&self, rate: Decimal, round_up: bool) -> Option<Self> {
+        if rate < Decimal::ZERO {
+            return None;
+        }
+        let micro = self.micro as u128;
+        let mantissa = rate.mantissa().unsigned_abs();
+        // The product as 64-bit limbs, from the most significant one
+        let low = micro * (mantissa as u64 as u128);
+        let high = micro * (mantissa >> 64) + (low >> 64);
+        let mut limbs = [(high >> 64) as u64, high as u64, low as u64];
+
+        let mut scale = rate.scale();
+        let mut exact = true;
+        while scale > 0 {
+            // 10^19 is the largest power of ten that fits in a `u64`
+            let step = scale.min(19);
+            scale -= step;
+            let divisor = 10_u128.pow(step);
+            let mut remainder = 0_u128;
+            for limb in limbs.iter_mut() {
+                let dividend = (remainder << 64) | *limb as u128;
+                *limb = (dividend / divisor) as u64;
+                remainder = dividend % divisor;
+            }
+            exact &= remainder == 0;
+        }
+
+        match limbs {
+            [0, 0, micro] if round_up && !exact => {
+                micro.checked_add(1).map(|micro| Self { micro })
+            }
+            [0, 0, micro] => Some(Self { micro }),
+            _ => None,
+        }
+    }
+
     /// Create a new amount from whole number of tokens
     pub const fn whole(amount: u64) -> Self {
         Self {
@@ -262,6 +361,18 @@ pub const TX_KEY_PREFIX: &str = "tx-";
 pub const CONVERSION_KEY_PREFIX: &str = "conv";
 /// Key segment prefix for pinned shielded transactions
 pub const PIN_KEY_PREFIX: &str = "pin-";
+/// Key segment for the memos of the transfers from an account
+pub const MEMO_STORAGE_KEY: &str = "transfer_memo";
+/// The maximum length in bytes of a transfer's memo
+pub const MAX_MEMO_LENGTH: usize = 256;
+/// Key segment for the tokens of an account locked until a block height
+pub const LOCK_STORAGE_KEY: &str = "token_lock";
+/// The maximum number of blocks after the current block until which tokens
+/// can be locked, about a year with blocks of 5 seconds
+pub const MAX_LOCK_HORIZON_BLOCKS: u64 = 6_307_200;
+/// The maximum number of the locks of a token of an account, so that the
+/// amount that's still locked is computed from a bounded number of locks
+pub const MAX_LOCKS_PER_TOKEN: usize = 32;
 
 /// Obtain a storage key for user's balance.
 pub fn balance_key(token_addr: &Address, owner: &Address) -> Key {
@@ -325,6 +436,81 @@ pub fn is_any_token_balance_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Obtain a storage key for the memo of a transfer from the given account,
+/// applied by the transaction at the given index in the block at the given
+/// height. Each transfer's memo has its own key, so that the memos are not
+/// overwritten by the following transfers.
+pub fn memo_key(
+    owner: &Address,
+    height: BlockHeight,
+    tx_index: TxIndex,
+) -> Key {
+    Key::from(owner.to_db_key())
+        .push(&MEMO_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&height)
+        .expect("Cannot obtain a storage key")
+        .push(&tx_index.0)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a transfer memo key. If it is, returns
+/// the owner.
+pub fn is_memo_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::StringSeg(_height),
+            DbKeySeg::StringSeg(_tx_index),
+        ] if key == MEMO_STORAGE_KEY => Some(owner),
+        _ => None,
+    }
+}
+
+/// Obtain a storage key prefix for the locks of the given token of an account.
+pub fn lock_prefix(owner: &Address, token_addr: &Address) -> Key {
+    Key::from(owner.to_db_key())
+        .push(&LOCK_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&token_addr.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the amount of the given token of an account
+/// locked until the given block height.
+pub fn lock_key(
+    owner: &Address,
+    token_addr: &Address,
+    unlock_height: BlockHeight,
+) -> Key {
+    lock_prefix(owner, token_addr)
+        .push(&unlock_height)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a token lock key. If it is, returns the
+/// owner, the token and the unlock height.
+pub fn is_lock_key(key: &Key) -> Option<(&Address, &Address, BlockHeight)> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(owner),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(token),
+            DbKeySeg::StringSeg(height),
+        ] if key == LOCK_STORAGE_KEY => {
+            let unlock_height = BlockHeight::parse(height.clone()).ok()?;
+            Some((owner, token, unlock_height))
+        }
+        _ => None,
+    }
+}
+
+/// Check that a transfer's memo doesn't exceed [`MAX_MEMO_LENGTH`]
+pub fn is_valid_memo(memo: &str) -> bool {
+    memo.len() <= MAX_MEMO_LENGTH
+}
+
 /// Check if the given storage key is a masp key
 pub fn is_masp_key(key: &Key) -> bool {
     matches!(&key.segments[..],
@@ -386,7 +572,6 @@ fn multitoken_balance_owner(key: &Key) -> Option<(Key, &Address)> {
     Clone,
     PartialEq,
     BorshSerialize,
-    BorshDeserialize,
     BorshSchema,
     Hash,
     Eq,
@@ -409,6 +594,71 @@ pub struct Transfer {
     pub key: Option<String>,
     /// Shielded transaction part
     pub shielded: Option<Transaction>,
+    /// An optional memo, e.g. an identifier for the recipient, of up to
+    /// [`MAX_MEMO_LENGTH`] bytes
+    pub memo: Option<String>,
+}
+
+/// The memo is the last field of a [`Transfer`], so a transfer encoded before
+/// the memo was added ends right after its `shielded` field. Such a transfer
+/// is decoded with no memo, provided that it's at the end of the input, which
+/// is the case for the txs' data and for the transfers recorded in storage.
+impl BorshDeserialize for Transfer {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let source = BorshDeserialize::deserialize(buf)?;
+        let target = BorshDeserialize::deserialize(buf)?;
+        let token = BorshDeserialize::deserialize(buf)?;
+        let sub_prefix = BorshDeserialize::deserialize(buf)?;
+        let amount = BorshDeserialize::deserialize(buf)?;
+        let key = BorshDeserialize::deserialize(buf)?;
+        let shielded = BorshDeserialize::deserialize(buf)?;
+        let memo = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        Ok(Self {
+            source,
+            target,
+            token,
+            sub_prefix,
+            amount,
+            key,
+            shielded,
+            memo,
+        })
+    }
+}
+
+/// A token transfer whose amount is locked in the target's account until a
+/// block height, e.g. for vesting. The target can only spend the locked
+/// amount from the unlock height on, while the rest of its balance is not
+/// restricted.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct TimeLockedTransfer {
+    /// Source address will spend the tokens
+    pub source: Address,
+    /// Target address will receive the tokens
+    pub target: Address,
+    /// Token's address
+    pub token: Address,
+    /// The amount of tokens
+    pub amount: Amount,
+    /// The height of the first block in which the target can spend the
+    /// tokens
+    pub unlock_height: BlockHeight,
 }
 
 #[allow(missing_docs)]
@@ -422,6 +672,103 @@ pub enum TransferError {
     NoToken,
 }
 
+/// An output of a [`MultiTransfer`]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct TransferOutput {
+    /// Target address will receive the tokens
+    pub target: Address,
+    /// Token's address
+    pub token: Address,
+    /// The amount of tokens
+    pub amount: Amount,
+}
+
+/// A transfer of tokens from a single source to many targets, which is
+/// applied atomically
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct MultiTransfer {
+    /// Source address will spend the tokens
+    pub source: Address,
+    /// The outputs of the transfer. The amounts of the outputs with the same
+    /// target and token are summed.
+    pub outputs: Vec<TransferOutput>,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MultiTransferError {
+    #[error("The transfer has no outputs")]
+    NoOutputs,
+    #[error("The output at index {0} has a zero amount")]
+    ZeroAmount(usize),
+    #[error("The total amount of token {0} overflows")]
+    Overflow(Address),
+}
+
+impl MultiTransfer {
+    /// Get the amounts of the outputs by their target and token, with the
+    /// amounts of the duplicate outputs summed. Every output must have a
+    /// non-zero amount.
+    pub fn merged_outputs(
+        &self,
+    ) -> Result<BTreeMap<(Address, Address), Amount>, MultiTransferError> {
+        if self.outputs.is_empty() {
+            return Err(MultiTransferError::NoOutputs);
+        }
+        let mut merged: BTreeMap<(Address, Address), Amount> = BTreeMap::new();
+        for (ix, output) in self.outputs.iter().enumerate() {
+            if output.amount == Amount::default() {
+                return Err(MultiTransferError::ZeroAmount(ix));
+            }
+            let amount = merged
+                .entry((output.target.clone(), output.token.clone()))
+                .or_default();
+            *amount = amount.checked_add(&output.amount).ok_or_else(|| {
+                MultiTransferError::Overflow(output.token.clone())
+            })?;
+        }
+        Ok(merged)
+    }
+
+    /// Get the total amounts spent by the source by token
+    pub fn totals(
+        &self,
+    ) -> Result<BTreeMap<Address, Amount>, MultiTransferError> {
+        let mut totals: BTreeMap<Address, Amount> = BTreeMap::new();
+        for ((_target, token), amount) in self.merged_outputs()? {
+            let total = totals.entry(token.clone()).or_default();
+            *total = total
+                .checked_add(&amount)
+                .ok_or(MultiTransferError::Overflow(token))?;
+        }
+        Ok(totals)
+    }
+}
+
 #[cfg(any(feature = "abciplus", feature = "abcipp"))]
 impl TryFrom<crate::ledger::ibc::data::FungibleTokenPacketData> for Transfer {
     type Error = TransferError;
@@ -447,6 +794,7 @@ impl TryFrom<crate::ledger::ibc::data::FungibleTokenPacketData> for Transfer {
             amount,
             key: None,
             shielded: None,
+            memo: None,
         })
     }
 }
@@ -454,6 +802,7 @@ impl TryFrom<crate::ledger::ibc::data::FungibleTokenPacketData> for Transfer {
 #[cfg(test)]
 mod tests {
     use proptest::prelude::*;
+    use rust_decimal_macros::dec;
 
     use super::*;
 
@@ -470,6 +819,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_memo_key() {
+        let owner = crate::types::address::testing::established_address_1();
+        let key = memo_key(&owner, BlockHeight(1), TxIndex(2));
+        assert_eq!(is_memo_key(&key), Some(&owner));
+        assert_ne!(key, memo_key(&owner, BlockHeight(1), TxIndex(3)));
+        assert_ne!(key, memo_key(&owner, BlockHeight(2), TxIndex(2)));
+        assert_eq!(is_memo_key(&balance_key(&owner, &owner)), None);
+
+        assert!(is_valid_memo(""));
+        assert!(is_valid_memo(&"a".repeat(MAX_MEMO_LENGTH)));
+        assert!(!is_valid_memo(&"a".repeat(MAX_MEMO_LENGTH + 1)));
+    }
+
+    /// Test that a transfer encoded before the memo was added is decoded
+    /// with no memo, and that a transfer with a memo round-trips.
+    #[test]
+    fn test_transfer_without_memo_decoding() {
+        let transfer = Transfer {
+            source: crate::types::address::testing::established_address_1(),
+            target: crate::types::address::testing::established_address_2(),
+            token: crate::types::address::nam(),
+            sub_prefix: None,
+            amount: Amount::from(1),
+            key: None,
+            shielded: None,
+            memo: None,
+        };
+        let mut legacy_bytes = transfer.try_to_vec().unwrap();
+        // Drop the memo's `None` tag
+        assert_eq!(legacy_bytes.pop(), Some(0));
+        assert_eq!(Transfer::try_from_slice(&legacy_bytes).unwrap(), transfer);
+
+        let transfer = Transfer {
+            memo: Some("memo".to_owned()),
+            ..transfer
+        };
+        let bytes = transfer.try_to_vec().unwrap();
+        assert_eq!(Transfer::try_from_slice(&bytes).unwrap(), transfer);
+    }
+
     #[test]
     fn test_token_display() {
         let max = Amount::from(u64::MAX);
@@ -484,6 +874,189 @@ mod tests {
         let zero = Amount::from(0);
         assert_eq!("0", zero.to_string());
     }
+
+    /// Test that the Borsh encoding of the amounts and changes is fixed-width
+    /// and pinned to the exact bytes, as a change of their representation
+    /// would break consensus.
+    #[test]
+    fn test_token_amount_encoding() {
+        let encode = |amount: u64| Amount::from(amount).try_to_vec().unwrap();
+        assert_eq!(encode(0), [0; 8]);
+        assert_eq!(encode(1), [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(encode(0x0102_0304_0506_0708), [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(Amount::max().try_to_vec().unwrap(), [0xff; 8]);
+        for amount in [0, 1, u64::MAX] {
+            let decoded = Amount::try_from_slice(&encode(amount)).unwrap();
+            assert_eq!(decoded, Amount::from(amount));
+        }
+        // Any other width is rejected
+        assert!(Amount::try_from_slice(&[0; 7]).is_err());
+        assert!(Amount::try_from_slice(&[0; 9]).is_err());
+
+        let encode = |change: Change| change.try_to_vec().unwrap();
+        assert_eq!(encode(0), [0; 16]);
+        let mut one = [0; 16];
+        one[0] = 1;
+        assert_eq!(encode(1), one);
+        assert_eq!(encode(-1), [0xff; 16]);
+        let mut max = [0xff; 16];
+        max[15] = 0x7f;
+        assert_eq!(encode(Change::MAX), max);
+        let mut min = [0; 16];
+        min[15] = 0x80;
+        assert_eq!(encode(Change::MIN), min);
+        // The change of an amount has the amount's bytes, zero-extended
+        let mut max_amount = [0; 16];
+        max_amount[..8].copy_from_slice(&[0xff; 8]);
+        assert_eq!(encode(Amount::max().change()), max_amount);
+        for change in [0, 1, -1, Change::MAX, Change::MIN] {
+            let decoded = Change::try_from_slice(&encode(change)).unwrap();
+            assert_eq!(decoded, change);
+        }
+        assert!(Change::try_from_slice(&[0; 15]).is_err());
+        assert!(Change::try_from_slice(&[0; 17]).is_err());
+    }
+
+    #[test]
+    fn test_token_saturating_arithmetic() {
+        let zero = Amount::from(0);
+        let one = Amount::from(1);
+        let max = Amount::max();
+
+        // Clamped at zero
+        assert_eq!(zero.saturating_sub(&one), zero);
+        assert_eq!(one.saturating_sub(&max), zero);
+        assert_eq!(one.saturating_sub(&one), zero);
+        assert_eq!(max.saturating_sub(&one), Amount::from(u64::MAX - 1));
+
+        // Clamped at max
+        assert_eq!(max.saturating_add(&one), max);
+        assert_eq!(max.saturating_add(&max), max);
+        assert_eq!(Amount::from(u64::MAX - 1).saturating_add(&one), max);
+        assert_eq!(zero.saturating_add(&one), one);
+    }
+
+    #[test]
+    fn test_multi_transfer_outputs() {
+        use crate::types::address::testing::{
+            established_address_1, established_address_2, established_address_3,
+        };
+        use crate::types::address::{btc, nam};
+
+        let output =
+            |target: &Address, token: &Address, amount: u64| TransferOutput {
+                target: target.clone(),
+                token: token.clone(),
+                amount: Amount::from(amount),
+            };
+        let source = established_address_1();
+        let alice = established_address_2();
+        let bob = established_address_3();
+        let mut transfer = MultiTransfer {
+            source,
+            outputs: vec![
+                output(&alice, &nam(), 10),
+                output(&bob, &nam(), 20),
+                output(&alice, &btc(), 5),
+                output(&alice, &nam(), 1),
+            ],
+        };
+
+        // The duplicate outputs are summed
+        let merged = transfer.merged_outputs().unwrap();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[&(alice.clone(), nam())], Amount::from(11));
+        assert_eq!(merged[&(bob.clone(), nam())], Amount::from(20));
+        assert_eq!(merged[&(alice.clone(), btc())], Amount::from(5));
+        let totals = transfer.totals().unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[&nam()], Amount::from(31));
+        assert_eq!(totals[&btc()], Amount::from(5));
+
+        // The total must not overflow
+        transfer.outputs.push(output(&bob, &btc(), u64::MAX));
+        assert_eq!(transfer.totals(), Err(MultiTransferError::Overflow(btc())));
+
+        // A zero amount output is rejected
+        transfer.outputs.push(output(&bob, &nam(), 0));
+        assert_eq!(
+            transfer.merged_outputs(),
+            Err(MultiTransferError::ZeroAmount(5))
+        );
+
+        // There must be some outputs
+        transfer.outputs.clear();
+        assert_eq!(
+            transfer.merged_outputs(),
+            Err(MultiTransferError::NoOutputs)
+        );
+    }
+
+    #[test]
+    fn test_token_mul_rate_rounding() {
+        let third = Decimal::ONE / Decimal::from(3);
+
+        // Rounding at the half
+        let amount = Amount::from(3);
+        assert_eq!(amount.mul_floor(dec!(0.5)), Some(Amount::from(1)));
+        assert_eq!(amount.mul_ceil(dec!(0.5)), Some(Amount::from(2)));
+        // An exact result is not rounded
+        let amount = Amount::from(4);
+        assert_eq!(amount.mul_floor(dec!(0.5)), Some(Amount::from(2)));
+        assert_eq!(amount.mul_ceil(dec!(0.5)), Some(Amount::from(2)));
+        // 3 * 0.333... is just below 1
+        let amount = Amount::from(3);
+        assert_eq!(amount.mul_floor(third), Some(Amount::from(0)));
+        assert_eq!(amount.mul_ceil(third), Some(Amount::from(1)));
+        // The smallest fraction is not lost for the largest amount
+        let max = Amount::max();
+        assert_eq!(
+            max.mul_floor(third),
+            Some(Amount::from(6_148_914_691_236_517_204))
+        );
+        assert_eq!(
+            max.mul_ceil(third),
+            Some(Amount::from(6_148_914_691_236_517_205))
+        );
+
+        // The identity and zero rates
+        assert_eq!(max.mul_floor(Decimal::ONE), Some(max));
+        assert_eq!(max.mul_ceil(Decimal::ONE), Some(max));
+        assert_eq!(max.mul_floor(Decimal::ZERO), Some(Amount::from(0)));
+        assert_eq!(max.mul_ceil(Decimal::ZERO), Some(Amount::from(0)));
+
+        // A negative rate
+        assert_eq!(amount.mul_floor(dec!(-0.5)), None);
+        assert_eq!(amount.mul_ceil(dec!(-0.5)), None);
+    }
+
+    #[test]
+    fn test_token_mul_rate_overflow() {
+        let max = Amount::max();
+        assert_eq!(max.mul_floor(dec!(1.5)), None);
+        assert_eq!(max.mul_ceil(dec!(1.5)), None);
+        assert_eq!(Amount::from(2).mul_floor(Decimal::MAX), None);
+
+        // Only rounding up overflows
+        let rate = dec!(1.0000000000000000000000000001);
+        assert_eq!(max.mul_floor(rate), Some(max));
+        assert_eq!(max.mul_ceil(rate), None);
+    }
+
+    /// Test that a lock key is recognized with its owner, token and unlock
+    /// height.
+    #[test]
+    fn test_lock_key() {
+        let owner = crate::types::address::testing::established_address_1();
+        let token = crate::types::address::nam();
+        let key = lock_key(&owner, &token, BlockHeight(42));
+        assert_eq!(is_lock_key(&key), Some((&owner, &token, BlockHeight(42))));
+        assert_eq!(
+            is_lock_key(&memo_key(&owner, BlockHeight(42), TxIndex(0))),
+            None
+        );
+        assert_eq!(is_lock_key(&balance_key(&token, &owner)), None);
+    }
 }
 
 /// Helpers for testing with addresses.
@@ -32,6 +32,7 @@ use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
 use crate::types::key::*;
 use crate::types::storage;
+use crate::types::validity_predicate::{VpEvent, VpRejection};
 
 /// Get the hash of a transaction
 pub fn hash_tx(tx_bytes: &[u8]) -> Hash {
@@ -62,6 +63,36 @@ impl TxResult {
     }
 }
 
+/// The verifiers that a transaction would trigger, as found by a dry run of
+/// the transaction's code without checking its validity predicates
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
+pub struct TxVerifiers {
+    /// The addresses whose validity predicates would be triggered
+    pub verifiers: BTreeSet<Address>,
+    /// Storage keys touched by the transaction
+    pub changed_keys: BTreeSet<storage::Key>,
+}
+
+/// The gas that a transaction would use, as found by a dry run of the
+/// transaction and its validity predicates against the current state
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct GasEstimate {
+    /// Total gas used by the transaction, including the gas used by VPs, or
+    /// the gas used up to its rejection if it was rejected
+    pub gas_used: u64,
+    /// Whether the transaction would be accepted
+    pub outcome: DryRunOutcome,
+}
+
+/// The outcome of a dry run of a transaction
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum DryRunOutcome {
+    /// The transaction would be accepted
+    Accepted,
+    /// The transaction would be rejected for the given reason
+    Rejected(String),
+}
+
 /// Result of checking a transaction with validity predicates
 // TODO derive BorshSchema after <https://github.com/near/borsh-rs/issues/82>
 #[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
@@ -74,6 +105,10 @@ pub struct VpsResult {
     pub gas_used: VpsGas,
     /// Errors occurred in any of the VPs, if any
     pub errors: Vec<(Address, String)>,
+    /// The events emitted by the VPs that accepted the transaction
+    pub events: Vec<(Address, VpEvent)>,
+    /// The details given by the VPs that rejected the transaction, if any
+    pub rejections: Vec<(Address, VpRejection)>,
 }
 
 impl fmt::Display for TxResult {
@@ -97,9 +132,15 @@ impl fmt::Display for VpsResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{}{}{}",
+            "{}{}{}{}",
             iterable_to_string("Accepted", self.accepted_vps.iter()),
             iterable_to_string("Rejected", self.rejected_vps.iter()),
+            iterable_to_string(
+                "Rejections",
+                self.rejections
+                    .iter()
+                    .map(|(addr, rejection)| format!("{} {}", addr, rejection))
+            ),
             iterable_to_string(
                 "Errors",
                 self.errors
@@ -210,6 +251,7 @@ pub mod tx_types {
 
     use super::*;
     use crate::proto::{SignedTxData, Tx};
+    use crate::types::chain::ChainId;
     use crate::types::transaction::protocol::ProtocolTx;
 
     /// Errors relating to decrypting a wrapper tx and its
@@ -277,13 +319,14 @@ pub mod tx_types {
     /// the Tx and verify it is of the appropriate form. This means
     /// 1. The signed Tx data deserializes to a WrapperTx type
     /// 2. The wrapper tx is indeed signed
-    /// 3. The signature is valid
+    /// 3. The signature is valid for the given chain, so that a wrapper signed
+    ///    for another chain cannot be replayed on this one
     ///
     /// We modify the data of the WrapperTx to contain only the signed
     /// data if valid and return it wrapped in a enum variant
     /// indicating it is a wrapper. Otherwise, an error is
     /// returned indicating the signature was not valid
-    pub fn process_tx(tx: Tx) -> Result<TxType, TxError> {
+    pub fn process_tx(tx: Tx, chain_id: &ChainId) -> Result<TxType, TxError> {
         if let Some(Ok(SignedTxData {
             data: Some(data),
             ref sig,
@@ -297,7 +340,7 @@ pub mod tx_types {
                 data: Some(data.clone()),
                 timestamp: tx.timestamp,
             }
-            .hash();
+            .signed_hash(chain_id);
             match TxType::try_from(Tx {
                 code: vec![],
                 data: Some(data),
@@ -339,8 +382,11 @@ pub mod tx_types {
 
     #[cfg(test)]
     mod test_process_tx {
+        use std::str::FromStr;
+
         use super::*;
         use crate::types::address::nam;
+        use crate::types::chain::ChainIdPrefix;
         use crate::types::storage::Epoch;
 
         fn gen_keypair() -> common::SecretKey {
@@ -357,7 +403,9 @@ pub mod tx_types {
         fn test_process_tx_raw_tx_no_data() {
             let tx = Tx::new("wasm code".as_bytes().to_owned(), None);
 
-            match process_tx(tx.clone()).expect("Test failed") {
+            match process_tx(tx.clone(), &ChainId::default())
+                .expect("Test failed")
+            {
                 TxType::Raw(raw) => assert_eq!(tx, raw),
                 _ => panic!("Test failed: Expected Raw Tx"),
             }
@@ -381,7 +429,7 @@ pub mod tx_types {
                 ),
             );
 
-            match process_tx(tx).expect("Test failed") {
+            match process_tx(tx, &ChainId::default()).expect("Test failed") {
                 TxType::Raw(raw) => assert_eq!(inner, raw),
                 _ => panic!("Test failed: Expected Raw Tx"),
             }
@@ -403,9 +451,9 @@ pub mod tx_types {
                         .expect("Test failed"),
                 ),
             )
-            .sign(&gen_keypair());
+            .sign(&gen_keypair(), &ChainId::default());
 
-            match process_tx(tx).expect("Test failed") {
+            match process_tx(tx, &ChainId::default()).expect("Test failed") {
                 TxType::Raw(raw) => assert_eq!(inner, raw),
                 _ => panic!("Test failed: Expected Raw Tx"),
             }
@@ -432,10 +480,11 @@ pub mod tx_types {
                 tx.clone(),
                 Default::default(),
             )
-            .sign(&keypair)
+            .sign(&keypair, &ChainId::default())
             .expect("Test failed");
 
-            match process_tx(wrapper).expect("Test failed") {
+            match process_tx(wrapper, &ChainId::default()).expect("Test failed")
+            {
                 TxType::Wrapper(wrapper) => {
                     let decrypted =
                         wrapper.decrypt(<EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator())
@@ -446,6 +495,38 @@ pub mod tx_types {
             }
         }
 
+        /// Test that process_tx rejects a wrapper tx signed for another chain,
+        /// so that it cannot be replayed
+        #[test]
+        fn test_process_tx_wrapper_tx_other_chain() {
+            let keypair = gen_keypair();
+            let tx = Tx::new(
+                "wasm code".as_bytes().to_owned(),
+                Some("transaction data".as_bytes().to_owned()),
+            );
+            let other_chain = ChainIdPrefix::from_str("other")
+                .expect("Test failed")
+                .temp_chain_id();
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount: 10.into(),
+                    token: nam(),
+                },
+                &keypair,
+                Epoch(0),
+                0.into(),
+                tx,
+                Default::default(),
+            )
+            .sign(&keypair, &other_chain)
+            .expect("Test failed");
+
+            assert!(process_tx(wrapper.clone(), &other_chain).is_ok());
+            let result = process_tx(wrapper, &ChainId::default())
+                .expect_err("Test failed");
+            assert_matches!(result, TxError::SigError(_));
+        }
+
         /// Test that process_tx correctly returns an error on a wrapper tx
         /// with some unsigned data
         #[test]
@@ -474,7 +555,8 @@ pub mod tx_types {
                     TxType::Wrapper(wrapper).try_to_vec().expect("Test failed"),
                 ),
             );
-            let result = process_tx(tx).expect_err("Test failed");
+            let result =
+                process_tx(tx, &ChainId::default()).expect_err("Test failed");
             assert_matches!(result, TxError::Unsigned(_));
         }
     }
@@ -489,7 +571,7 @@ pub mod tx_types {
         );
         let decrypted = DecryptedTx::Decrypted(payload.clone());
         let tx = Tx::from(TxType::Decrypted(decrypted));
-        match process_tx(tx).expect("Test failed") {
+        match process_tx(tx, &ChainId::default()).expect("Test failed") {
             TxType::Decrypted(DecryptedTx::Decrypted(processed)) => {
                 assert_eq!(payload, processed);
             }
@@ -521,7 +603,7 @@ pub mod tx_types {
         // create the tx with signed decrypted data
         let tx =
             Tx::new(vec![], Some(signed.try_to_vec().expect("Test failed")));
-        match process_tx(tx).expect("Test failed") {
+        match process_tx(tx, &ChainId::default()).expect("Test failed") {
             TxType::Decrypted(DecryptedTx::Decrypted(processed)) => {
                 assert_eq!(payload, processed);
             }
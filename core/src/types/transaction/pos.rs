@@ -5,6 +5,7 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use crate::types::address::Address;
+use crate::types::key::common;
 use crate::types::token;
 
 /// A bond is a validator's self-bond or a delegation from non-validator to a
@@ -74,3 +75,22 @@ pub struct CommissionChange {
     /// The new commission rate
     pub new_rate: Decimal,
 }
+
+/// A change to the validator consensus key, which takes effect at the
+/// pipeline offset.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct ConsensusKeyChange {
+    /// Validator address
+    pub validator: Address,
+    /// The new consensus key
+    pub consensus_key: common::PublicKey,
+}
@@ -33,6 +33,7 @@ mod protocol_txs {
 
     use super::*;
     use crate::proto::Tx;
+    use crate::types::chain::ChainId;
     use crate::types::key::*;
     use crate::types::transaction::{EllipticCurve, TxError, TxType};
 
@@ -87,6 +88,7 @@ mod protocol_txs {
             self,
             pk: &common::PublicKey,
             signing_key: &common::SecretKey,
+            chain_id: &ChainId,
         ) -> Tx {
             Tx::new(
                 vec![],
@@ -99,13 +101,14 @@ mod protocol_txs {
                     .expect("Could not serialize ProtocolTx"),
                 ),
             )
-            .sign(signing_key)
+            .sign(signing_key, chain_id)
         }
 
         /// Create a new tx requesting a new DKG session keypair
         pub fn request_new_dkg_keypair<'a, F>(
             data: UpdateDkgSessionKey,
             signing_key: &common::SecretKey,
+            chain_id: &ChainId,
             wasm_dir: &'a Path,
             wasm_loader: F,
         ) -> Self
@@ -126,7 +129,7 @@ mod protocol_txs {
                             .expect("Serializing request should not fail"),
                     ),
                 )
-                .sign(signing_key),
+                .sign(signing_key, chain_id),
             )
         }
     }
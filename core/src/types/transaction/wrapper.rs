@@ -13,6 +13,7 @@ pub mod wrapper_tx {
 
     use crate::proto::Tx;
     use crate::types::address::Address;
+    use crate::types::chain::ChainId;
     use crate::types::key::*;
     use crate::types::storage::Epoch;
     use crate::types::token::Amount;
@@ -149,6 +150,25 @@ pub mod wrapper_tx {
         }
     }
 
+    /// The gas limit and fee of a tx, as reported by a node in its response
+    /// to the tx's mempool validation
+    #[derive(
+        Debug,
+        Clone,
+        PartialEq,
+        BorshSerialize,
+        BorshDeserialize,
+        BorshSchema,
+        Serialize,
+        Deserialize,
+    )]
+    pub struct TxFeeInfo {
+        /// The gas limit of the tx, rounded up to the gas limit resolution
+        pub gas_limit: u64,
+        /// The fee offered for the tx
+        pub fee: Fee,
+    }
+
     /// A transaction with an encrypted payload as well
     /// as some non-encrypted metadata for inclusion
     /// and / or verification purposes
@@ -236,10 +256,12 @@ pub mod wrapper_tx {
             }
         }
 
-        /// Sign the wrapper transaction and convert to a normal Tx type
+        /// Sign the wrapper transaction for the given chain and convert to a
+        /// normal Tx type
         pub fn sign(
             &self,
             keypair: &common::SecretKey,
+            chain_id: &ChainId,
         ) -> Result<Tx, WrapperTxErr> {
             if self.pk != keypair.ref_to() {
                 return Err(WrapperTxErr::InvalidKeyPair);
@@ -252,7 +274,7 @@ pub mod wrapper_tx {
                         .expect("Could not serialize WrapperTx"),
                 ),
             )
-            .sign(keypair))
+            .sign(keypair, chain_id))
         }
 
         /// Validate the signature of a wrapper tx
@@ -426,13 +448,16 @@ pub mod wrapper_tx {
                 tx,
                 Default::default(),
             )
-            .sign(&keypair)
+            .sign(&keypair, &ChainId::default())
             .expect("Test failed");
 
             // we now try to alter the inner tx maliciously
             let mut wrapper = if let TxType::Wrapper(wrapper) =
-                crate::types::transaction::process_tx(tx.clone())
-                    .expect("Test failed")
+                crate::types::transaction::process_tx(
+                    tx.clone(),
+                    &ChainId::default(),
+                )
+                .expect("Test failed")
             {
                 wrapper
             } else {
@@ -472,11 +497,16 @@ pub mod wrapper_tx {
             tx.data = Some(signed_tx_data.try_to_vec().expect("Test failed"));
 
             // check that the signature is not valid
-            tx.verify_sig(&keypair.ref_to(), &signed_tx_data.sig)
-                .expect_err("Test failed");
+            tx.verify_sig(
+                &keypair.ref_to(),
+                &signed_tx_data.sig,
+                &ChainId::default(),
+            )
+            .expect_err("Test failed");
             // check that the try from method also fails
-            let err = crate::types::transaction::process_tx(tx)
-                .expect_err("Test failed");
+            let err =
+                crate::types::transaction::process_tx(tx, &ChainId::default())
+                    .expect_err("Test failed");
             assert_matches!(err, TxError::SigError(_));
         }
     }
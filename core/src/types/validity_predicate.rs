@@ -1,8 +1,13 @@
 //! Types that are used in validity predicates.
 
+use std::collections::BTreeMap;
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
+use crate::types::hash::Hash;
+use crate::types::storage::Key;
+
 /// A validity predicate with an input that is intended to be invoked via `eval`
 /// host function.
 #[derive(
@@ -20,3 +25,105 @@ pub struct EvalVp {
     /// The input for the `eval`ed VP
     pub input: Vec<u8>,
 }
+
+/// The hash of the validity predicate code of an account
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub enum VpCodeHash {
+    /// No VP code is set for the account, e.g. for an implicit account, which
+    /// is validated by the protocol's default VP for its kind of address
+    Default,
+    /// The hash of the VP code set for the account
+    Code(Hash),
+}
+
+impl VpCodeHash {
+    /// Hash the VP code of an account, if any
+    pub fn from_code(vp_code: Option<impl AsRef<[u8]>>) -> Self {
+        match vp_code {
+            Some(vp_code) => Self::Code(Hash::sha256(vp_code)),
+            None => Self::Default,
+        }
+    }
+}
+
+/// A structured event emitted by a validity predicate for off-chain indexers,
+/// e.g. to signal a transfer with a memo. Unlike the free-form VP logs, the
+/// events are attached to the events of the transaction, but only when the
+/// transaction is accepted by all the VPs and committed.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VpEvent {
+    /// The type of the event
+    pub event_type: String,
+    /// The attributes of the event
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl std::fmt::Display for VpEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(k, v)| format!("{}: {};", k, v))
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(
+            f,
+            "Event type: {}, Attributes: {}",
+            self.event_type, attributes
+        )
+    }
+}
+
+/// The details of a rejection of a transaction by a validity predicate,
+/// reported back to the client with the result of the transaction. They are
+/// informative only, the acceptance of the transaction doesn't depend on
+/// them.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VpRejection {
+    /// The storage key whose modification got the transaction rejected
+    pub rejected_key: Option<Key>,
+    /// The reason of the rejection
+    pub reason: Option<String>,
+}
+
+impl std::fmt::Display for VpRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.rejected_key, &self.reason) {
+            (Some(key), Some(reason)) => {
+                write!(f, "rejected modification of key {}: {}", key, reason)
+            }
+            (Some(key), None) => {
+                write!(f, "rejected modification of key {}", key)
+            }
+            (None, Some(reason)) => write!(f, "rejected: {}", reason),
+            (None, None) => write!(f, "rejected"),
+        }
+    }
+}
@@ -29,7 +29,7 @@ use epoched::{
 };
 use namada_core::ledger::storage_api;
 use namada_core::types::address::{self, Address, InternalAddress};
-use namada_core::types::key::common;
+use namada_core::types::key::{common, tm_consensus_key_raw_hash};
 use namada_core::types::storage::Epoch;
 use namada_core::types::token;
 pub use parameters::PosParams;
@@ -69,6 +69,11 @@ pub trait PosReadOnly {
 
     /// Read PoS parameters.
     fn read_pos_params(&self) -> Result<PosParams, storage_api::Error>;
+    /// Read PoS raw hash of validator's consensus key.
+    fn read_validator_address_raw_hash(
+        &self,
+        raw_hash: impl AsRef<str>,
+    ) -> Result<Option<Address>, storage_api::Error>;
     /// Read PoS validator's consensus key (used for signing block votes).
     fn read_validator_consensus_key(
         &self,
@@ -79,6 +84,11 @@ pub trait PosReadOnly {
         &self,
         key: &Address,
     ) -> Result<Option<ValidatorStates>, storage_api::Error>;
+    /// Read the epoch in which a PoS validator has been last jailed.
+    fn read_validator_jailed_epoch(
+        &self,
+        key: &Address,
+    ) -> Result<Option<Epoch>, storage_api::Error>;
     /// Read PoS validator's total deltas of their bonds (validator self-bonds
     /// and delegations).
     fn read_validator_deltas(
@@ -604,6 +614,67 @@ pub trait PosActions: PosReadOnly {
 
         Ok(())
     }
+
+    /// Change the consensus key of a validator. Like the other changes to a
+    /// validator, the new key takes effect at the pipeline offset and the
+    /// current key remains valid until then. When the key is changed again
+    /// before it takes effect, the last change wins.
+    ///
+    /// A key that is or has been used by any validator cannot be used again,
+    /// so that the evidence of misbehavior signed with any key is always
+    /// attributed to its validator.
+    fn change_consensus_key(
+        &mut self,
+        validator: &Address,
+        consensus_key: &common::PublicKey,
+        current_epoch: Epoch,
+    ) -> Result<(), storage_api::Error> {
+        let mut consensus_keys = self
+            .read_validator_consensus_key(validator)?
+            .ok_or_else(|| {
+                ConsensusKeyChangeError::NotAValidator(validator.clone())
+            })?;
+        let raw_hash = tm_consensus_key_raw_hash(consensus_key);
+        if self.read_validator_address_raw_hash(&raw_hash)?.is_some() {
+            return Err(
+                ConsensusKeyChangeError::KeyAlreadyUsed(raw_hash).into()
+            );
+        }
+        let params = self.read_pos_params()?;
+        consensus_keys.set(consensus_key.clone(), current_epoch, &params);
+        self.write_validator_consensus_key(validator, consensus_keys)?;
+        self.write_validator_address_raw_hash(validator, consensus_key)
+    }
+
+    /// Unjail a validator once its jailing period is over. The validator
+    /// becomes a candidate again and it's added back to the validator set at
+    /// the pipeline offset. A validator that has unbonded all of its stake
+    /// cannot be unjailed.
+    fn unjail_validator(
+        &mut self,
+        validator: &Address,
+        current_epoch: Epoch,
+    ) -> Result<(), storage_api::Error> {
+        let params = self.read_pos_params()?;
+        let mut state = self
+            .read_validator_state(validator)?
+            .ok_or_else(|| UnjailError::NotAValidator(validator.clone()))?;
+        let jailed_epoch = self.read_validator_jailed_epoch(validator)?;
+        let deltas = self.read_validator_deltas(validator)?;
+        let mut validator_set = self.read_validator_set()?;
+
+        unjail_validator(
+            &params,
+            validator,
+            &mut state,
+            jailed_epoch,
+            deltas.as_ref(),
+            &mut validator_set,
+            current_epoch,
+        )?;
+        self.write_validator_state(validator, state)?;
+        self.write_validator_set(validator_set)
+    }
 }
 
 /// PoS system base trait for system initialization on genesis block, updating
@@ -630,6 +701,8 @@ pub trait PosBase {
     ) -> Option<ValidatorConsensusKeys>;
     /// Read PoS validator's state.
     fn read_validator_state(&self, key: &Address) -> Option<ValidatorStates>;
+    /// Read the epoch in which a PoS validator has been last jailed.
+    fn read_validator_jailed_epoch(&self, key: &Address) -> Option<Epoch>;
     /// Read PoS validator's total deltas of their bonds (validator self-bonds
     /// and delegations).
     fn read_validator_deltas(&self, key: &Address) -> Option<ValidatorDeltas>;
@@ -664,6 +737,8 @@ pub trait PosBase {
     );
     /// Write PoS validator's state.
     fn write_validator_state(&mut self, key: &Address, value: &ValidatorStates);
+    /// Write the epoch in which a PoS validator has been jailed.
+    fn write_validator_jailed_epoch(&mut self, key: &Address, value: Epoch);
     /// Write PoS validator's total deltas of their bonds (validator self-bonds
     /// and delegations).
     fn write_validator_deltas(
@@ -788,7 +863,56 @@ pub trait PosBase {
         let cur_validators = validators.get(current_epoch).unwrap();
         let prev_validators =
             previous_epoch.and_then(|epoch| validators.get(epoch));
+        // The consensus key of a validator at the given epoch, if set
+        let consensus_key_at = |address: &Address, epoch: Epoch| {
+            self.read_validator_consensus_key(address)
+                .and_then(|keys| keys.get(epoch).cloned())
+        };
+        // The previous consensus key of a validator whose key has been changed
+        // in the current epoch
+        let changed_consensus_key = |address: &Address| {
+            let prev_key = consensus_key_at(address, previous_epoch?)?;
+            (Some(&prev_key)
+                != consensus_key_at(address, current_epoch).as_ref())
+            .then_some(prev_key)
+        };
 
+        // The validators that have been active in the previous epoch, but that
+        // are not in the validator set anymore because they have been jailed,
+        // are removed by their previous consensus key.
+        let removed_validators = prev_validators
+            .into_iter()
+            .flat_map(|prev_set| prev_set.active.iter())
+            .filter_map(|validator: &WeightedValidator| {
+                let is_in_set = cur_validators
+                    .active
+                    .iter()
+                    .chain(cur_validators.inactive.iter())
+                    .any(|cur| cur.address == validator.address);
+                if is_in_set {
+                    return None;
+                }
+                consensus_key_at(&validator.address, previous_epoch?)
+                    .map(ValidatorSetUpdate::Deactivated)
+            });
+        // The validators that have been active in the previous epoch with a
+        // consensus key that has been changed since are removed by their
+        // previous key, to be added back with their new key below.
+        let changed_key_validators = cur_validators.active.iter().filter_map(
+            |validator: &WeightedValidator| {
+                let was_active = prev_validators.map_or(false, |prev_set| {
+                    prev_set
+                        .active
+                        .iter()
+                        .any(|prev| prev.address == validator.address)
+                });
+                if !was_active {
+                    return None;
+                }
+                changed_consensus_key(&validator.address)
+                    .map(ValidatorSetUpdate::Deactivated)
+            },
+        );
         // If the validator has never been active before and it doesn't have
         // more than 0 voting power, we should not tell Tendermint to
         // update it until it does. Tendermint uses 0 voting power as a
@@ -806,7 +930,9 @@ pub trait PosBase {
                 if let (Some(prev_epoch), Some(prev_validators)) =
                     (previous_epoch, prev_validators)
                 {
-                    if prev_validators.active.contains(validator) {
+                    if prev_validators.active.contains(validator)
+                        && changed_consensus_key(&validator.address).is_none()
+                    {
                         println!(
                             "skipping validator update, still the same {}",
                             validator.address
@@ -870,16 +996,43 @@ pub trait PosBase {
                         }
                     }
                 }
-                let consensus_key = self
-                    .read_validator_consensus_key(&validator.address)
-                    .unwrap()
-                    .get(current_epoch)
-                    .unwrap()
-                    .clone();
+                // A validator is deactivated by the consensus key that it had
+                // in the previous epoch, in case it has been changed since
+                let consensus_key = previous_epoch
+                    .and_then(|prev_epoch| {
+                        consensus_key_at(&validator.address, prev_epoch)
+                    })
+                    .or_else(|| {
+                        consensus_key_at(&validator.address, current_epoch)
+                    })
+                    .unwrap();
                 Some(ValidatorSetUpdate::Deactivated(consensus_key))
             },
         );
-        active_validators.chain(inactive_validators).for_each(f)
+        removed_validators
+            .chain(changed_key_validators)
+            .chain(active_validators)
+            .chain(inactive_validators)
+            .for_each(f)
+    }
+
+    /// Re-select the active validators by their bonded stake at the
+    /// beginning of a new epoch, for the validator sets from the pipeline
+    /// offset onward, with the size of the active set limited by the
+    /// `max_validator_slots` parameter. The other validators are queued in
+    /// the inactive set ranked by their stake.
+    fn rebalance_validator_sets(&mut self, current_epoch: Epoch) {
+        let params = self.read_pos_params();
+        let mut validator_sets = self.read_validator_set();
+        validator_sets.update_from_offset(
+            |validator_set, _epoch| {
+                validator_set.rebalance(params.max_validator_slots)
+            },
+            current_epoch,
+            DynEpochOffset::PipelineLen,
+            &params,
+        );
+        self.write_validator_set(&validator_sets);
     }
 
     /// Apply a slash to a byzantine validator for the given evidence.
@@ -938,6 +1091,38 @@ pub trait PosBase {
         );
         Ok(())
     }
+
+    /// Jail a validator for a fault. From the pipeline offset, the validator
+    /// is removed from the validator set, so it doesn't participate in the
+    /// consensus nor receive any rewards, and it may not receive new bonds.
+    /// It may be unjailed after the `jail_len` parameter epochs from the
+    /// current epoch. A validator that's jailed again is jailed for the full
+    /// period from the current epoch.
+    fn jail_validator(&mut self, current_epoch: Epoch, validator: &Address) {
+        let params = self.read_pos_params();
+        let mut state = match self.read_validator_state(validator) {
+            Some(state) => state,
+            None => {
+                tracing::error!(
+                    "Cannot jail {}, it's not a validator",
+                    validator
+                );
+                return;
+            }
+        };
+        let mut validator_set = self.read_validator_set();
+
+        jail_validator(
+            &params,
+            validator,
+            &mut state,
+            &mut validator_set,
+            current_epoch,
+        );
+        self.write_validator_state(validator, &state);
+        self.write_validator_set(&validator_set);
+        self.write_validator_jailed_epoch(validator, current_epoch);
+    }
 }
 
 #[allow(missing_docs)]
@@ -966,6 +1151,8 @@ pub enum BondError {
     SourceMustNotBeAValidator(Address),
     #[error("The given validator address {0} is inactive")]
     InactiveValidator(Address),
+    #[error("The given validator address {0} is jailed")]
+    JailedValidator(Address),
     #[error("Voting power overflow: {0}")]
     VotingPowerOverflow(TryFromIntError),
 }
@@ -1030,6 +1217,31 @@ pub enum CommissionRateChangeError {
     CannotRead(Address),
 }
 
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ConsensusKeyChangeError {
+    #[error("The given address {0} is not a validator")]
+    NotAValidator(Address),
+    #[error(
+        "The consensus key with raw hash {0} is or has already been used by a \
+         validator"
+    )]
+    KeyAlreadyUsed(String),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum UnjailError {
+    #[error("The given address {0} is not a validator")]
+    NotAValidator(Address),
+    #[error("The validator {0} is not jailed")]
+    NotJailed(Address),
+    #[error("The validator {0} may not be unjailed before epoch {1}")]
+    StillJailed(Address, Epoch),
+    #[error("The validator {0} has no bonded stake left")]
+    NoStake(Address),
+}
+
 struct GenesisData<Validators>
 where
     Validators: Iterator<Item = Result<GenesisValidatorData, GenesisError>>,
@@ -1306,6 +1518,17 @@ fn bond_tokens(
                     ));
                 }
             }
+            // Check that it's not jailed at the pipeline offset, from which
+            // the bond would take effect
+            if let Some(ValidatorState::Jailed) = validator_state.get_at_offset(
+                current_epoch,
+                DynEpochOffset::PipelineLen,
+                params,
+            ) {
+                return Err(BondError::JailedValidator(
+                    bond_id.validator.clone(),
+                ));
+            }
         }
     }
 
@@ -1552,10 +1775,7 @@ fn update_validator_set(
                         validator_set.inactive.remove(&validator_pre);
                         validator_set.inactive.insert(validator_post);
                     }
-                } else {
-                    debug_assert!(
-                        validator_set.active.contains(&validator_pre)
-                    );
+                } else if validator_set.active.contains(&validator_pre) {
                     let max_inactive_validator =
                         validator_set.inactive.last_shim();
                     let max_bonded_stake = max_inactive_validator
@@ -1576,6 +1796,9 @@ fn update_validator_set(
                         validator_set.active.insert(validator_post);
                     }
                 }
+                // Otherwise, the validator is jailed and it's not in the
+                // validator set, so it's only added back with its stake
+                // when it's unjailed
             }
         },
         current_epoch,
@@ -1584,6 +1807,87 @@ fn update_validator_set(
     )
 }
 
+/// Jail a validator from the pipeline offset by removing it from the validator
+/// sets, which are then rebalanced.
+fn jail_validator(
+    params: &PosParams,
+    validator: &Address,
+    state: &mut ValidatorStates,
+    validator_set: &mut ValidatorSets,
+    current_epoch: Epoch,
+) {
+    state.set(ValidatorState::Jailed, current_epoch, params);
+    validator_set.update_from_offset(
+        |validator_set, _epoch| {
+            validator_set.active.retain(|v| &v.address != validator);
+            validator_set.inactive.retain(|v| &v.address != validator);
+            validator_set.rebalance(params.max_validator_slots);
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    );
+}
+
+/// Unjail a validator from the pipeline offset by adding it back to the
+/// validator sets with its bonded stake, if its jailing period is over and it
+/// still has some stake bonded.
+fn unjail_validator(
+    params: &PosParams,
+    validator: &Address,
+    state: &mut ValidatorStates,
+    jailed_epoch: Option<Epoch>,
+    deltas: Option<&ValidatorDeltas>,
+    validator_set: &mut ValidatorSets,
+    current_epoch: Epoch,
+) -> Result<(), UnjailError> {
+    let jailed_epoch = match (
+        state.get_at_offset(current_epoch, DynEpochOffset::PipelineLen, params),
+        jailed_epoch,
+    ) {
+        (Some(ValidatorState::Jailed), Some(jailed_epoch)) => jailed_epoch,
+        _ => return Err(UnjailError::NotJailed(validator.clone())),
+    };
+    let unjail_epoch = jailed_epoch + params.jail_len;
+    if current_epoch < unjail_epoch {
+        return Err(UnjailError::StillJailed(validator.clone(), unjail_epoch));
+    }
+    // The stake at the unbonding offset is left after all the pending
+    // unbonds
+    let stake = deltas
+        .and_then(|deltas| {
+            deltas.get_at_offset(
+                current_epoch,
+                DynEpochOffset::UnbondingLen,
+                params,
+            )
+        })
+        .unwrap_or_default();
+    if stake <= token::Change::default() {
+        return Err(UnjailError::NoStake(validator.clone()));
+    }
+
+    state.set(ValidatorState::Candidate, current_epoch, params);
+    validator_set.update_from_offset(
+        |validator_set, epoch| {
+            let bonded_stake = deltas
+                .and_then(|deltas| deltas.get(epoch))
+                .unwrap_or_default();
+            let bonded_stake: u64 =
+                TryFrom::try_from(bonded_stake).unwrap_or_default();
+            validator_set.inactive.insert(WeightedValidator {
+                bonded_stake,
+                address: validator.clone(),
+            });
+            validator_set.rebalance(params.max_validator_slots);
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    );
+    Ok(())
+}
+
 struct WithdrawData {
     pub unbond: Unbonds,
     pub withdrawn: token::Amount,
@@ -1659,3 +1963,15 @@ impl From<CommissionRateChangeError> for storage_api::Error {
         Self::new(err)
     }
 }
+
+impl From<ConsensusKeyChangeError> for storage_api::Error {
+    fn from(err: ConsensusKeyChangeError) -> Self {
+        Self::new(err)
+    }
+}
+
+impl From<UnjailError> for storage_api::Error {
+    fn from(err: UnjailError) -> Self {
+        Self::new(err)
+    }
+}
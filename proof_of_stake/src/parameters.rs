@@ -29,6 +29,8 @@ pub struct PosParams {
     /// Amount of tokens rewarded to each validator that voted on a block
     /// proposal
     pub block_vote_reward: Decimal,
+    /// Minimum staking rewards rate per annum
+    pub min_inflation_rate: Decimal,
     /// Maximum staking rewards rate per annum
     pub max_inflation_rate: Decimal,
     /// Target ratio of staked NAM tokens to total NAM tokens
@@ -39,6 +41,9 @@ pub struct PosParams {
     /// Portion of validator's stake that should be slashed on a light client
     /// attack.
     pub light_client_attack_min_slash_rate: Decimal,
+    /// The number of epochs for which a slashed validator is jailed, after
+    /// which it may be unjailed.
+    pub jail_len: u64,
 }
 
 impl Default for PosParams {
@@ -52,7 +57,8 @@ impl Default for PosParams {
             tm_votes_per_token: dec!(1.0),
             block_proposer_reward: dec!(0.125),
             block_vote_reward: dec!(0.1),
-            // PoS inflation of 10%
+            // PoS inflation between 0% and 10%
+            min_inflation_rate: dec!(0.0),
             max_inflation_rate: dec!(0.1),
             // target staked ratio of 2/3
             target_staked_ratio: dec!(0.6667),
@@ -60,6 +66,7 @@ impl Default for PosParams {
             duplicate_vote_min_slash_rate: dec!(0.001),
             // slash 0.1%
             light_client_attack_min_slash_rate: dec!(0.001),
+            jail_len: 2,
         }
     }
 }
@@ -81,6 +88,15 @@ pub enum ValidationError {
          pipeline: {1}"
     )]
     UnbondingLenTooShort(u64, u64),
+    #[error("Inflation rates must be between 0 and 1, got {0}")]
+    InflationRateOutOfRange(Decimal),
+    #[error(
+        "Minimum inflation rate must be <= maximum inflation rate. Got \
+         minimum: {0}, maximum: {1}"
+    )]
+    MinInflationRateTooHigh(Decimal, Decimal),
+    #[error("Target staked ratio must be between 0 and 1, got {0}")]
+    TargetStakedRatioOutOfRange(Decimal),
 }
 
 /// The number of fundamental units per whole token of the native staking token
@@ -136,6 +152,26 @@ impl PosParams {
             ))
         }
 
+        // Check the bounds of the inflation
+        for rate in [self.min_inflation_rate, self.max_inflation_rate] {
+            if rate < Decimal::ZERO || rate > Decimal::ONE {
+                errors.push(ValidationError::InflationRateOutOfRange(rate))
+            }
+        }
+        if self.min_inflation_rate > self.max_inflation_rate {
+            errors.push(ValidationError::MinInflationRateTooHigh(
+                self.min_inflation_rate,
+                self.max_inflation_rate,
+            ))
+        }
+        if self.target_staked_ratio < Decimal::ZERO
+            || self.target_staked_ratio > Decimal::ONE
+        {
+            errors.push(ValidationError::TargetStakedRatioOutOfRange(
+                self.target_staked_ratio,
+            ))
+        }
+
         errors
     }
 }
@@ -3,7 +3,7 @@
 use namada_core::ledger::storage::types::{decode, encode};
 use namada_core::ledger::storage::{self, Storage, StorageHasher};
 use namada_core::types::address::Address;
-use namada_core::types::storage::{DbKeySeg, Key, KeySeg};
+use namada_core::types::storage::{DbKeySeg, Epoch, Key, KeySeg};
 use namada_core::types::{key, token};
 use rust_decimal::Decimal;
 
@@ -17,6 +17,7 @@ const VALIDATOR_STORAGE_PREFIX: &str = "validator";
 const VALIDATOR_ADDRESS_RAW_HASH: &str = "address_raw_hash";
 const VALIDATOR_CONSENSUS_KEY_STORAGE_KEY: &str = "consensus_key";
 const VALIDATOR_STATE_STORAGE_KEY: &str = "state";
+const VALIDATOR_JAILED_EPOCH_STORAGE_KEY: &str = "jailed_epoch";
 const VALIDATOR_DELTAS_STORAGE_KEY: &str = "validator_deltas";
 const VALIDATOR_COMMISSION_RATE_STORAGE_KEY: &str = "commission_rate";
 const VALIDATOR_MAX_COMMISSION_CHANGE_STORAGE_KEY: &str =
@@ -183,6 +184,31 @@ pub fn is_validator_state_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Storage key for the epoch in which a validator has been jailed.
+pub fn validator_jailed_epoch_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_JAILED_EPOCH_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the epoch in which a validator has been jailed?
+pub fn is_validator_jailed_epoch_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_JAILED_EPOCH_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
 /// Storage key for validator's deltas.
 pub fn validator_deltas_key(validator: &Address) -> Key {
     validator_prefix(validator)
@@ -390,6 +416,15 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_validator_jailed_epoch(
+        &self,
+        key: &namada_core::types::address::Address,
+    ) -> Option<Epoch> {
+        let (value, _gas) =
+            self.read(&validator_jailed_epoch_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_deltas(
         &self,
         key: &namada_core::types::address::Address,
@@ -490,6 +525,15 @@ where
             .unwrap();
     }
 
+    fn write_validator_jailed_epoch(
+        &mut self,
+        key: &namada_core::types::address::Address,
+        value: Epoch,
+    ) {
+        self.write(&validator_jailed_epoch_key(key), encode(&value))
+            .unwrap();
+    }
+
     fn write_validator_deltas(
         &mut self,
         key: &namada_core::types::address::Address,
@@ -620,6 +664,15 @@ macro_rules! impl_pos_read_only {
                 Ok(namada_core::ledger::storage::types::decode(value).unwrap())
             }
 
+            fn read_validator_address_raw_hash(
+                &self,
+                raw_hash: impl AsRef<str>,
+            ) -> namada_core::ledger::storage_api::Result<Option<namada_core::types::address::Address>> {
+                let value =
+                    namada_core::ledger::storage_api::StorageRead::read_bytes(self, &validator_address_raw_hash_key(raw_hash))?;
+                Ok(value.map(|value| namada_core::ledger::storage::types::decode(value).unwrap()))
+            }
+
             fn read_validator_consensus_key(
                 &self,
                 key: &namada_core::types::address::Address,
@@ -655,6 +708,14 @@ macro_rules! impl_pos_read_only {
                 Ok(value.map(|value| namada_core::ledger::storage::types::decode(value).unwrap()))
             }
 
+            fn read_validator_jailed_epoch(
+                &self,
+                key: &namada_core::types::address::Address,
+            ) -> namada_core::ledger::storage_api::Result<Option<namada_core::types::storage::Epoch>> {
+                let value = namada_core::ledger::storage_api::StorageRead::read_bytes(self, &validator_jailed_epoch_key(key))?;
+                Ok(value.map(|value| namada_core::ledger::storage::types::decode(value).unwrap()))
+            }
+
             fn read_validator_deltas(
                 &self,
                 key: &namada_core::types::address::Address,
@@ -14,6 +14,7 @@ use namada_core::types::storage::Epoch;
 use namada_core::types::token;
 use rust_decimal::prelude::{Decimal, ToPrimitive};
 
+use crate::btree_set::BTreeSetShims;
 use crate::epoched::{
     Epoched, EpochedDelta, OffsetPipelineLen, OffsetUnbondingLen,
 };
@@ -152,6 +153,27 @@ pub struct ValidatorSet {
     pub inactive: BTreeSet<WeightedValidator>,
 }
 
+impl ValidatorSet {
+    /// Select the `max_active` validators with the most bonded stake into the
+    /// active set and queue the rest in the inactive set. Validators with
+    /// equal stake are ranked by their address, as in the order of
+    /// [`WeightedValidator`], so the selection at the boundary of the active
+    /// set is deterministic.
+    pub fn rebalance(&mut self, max_active: u64) {
+        let mut inactive = std::mem::take(&mut self.active);
+        inactive.append(&mut self.inactive);
+        while self.active.len() < max_active as usize {
+            match inactive.pop_last_shim() {
+                Some(validator) => {
+                    self.active.insert(validator);
+                }
+                None => break,
+            }
+        }
+        self.inactive = inactive;
+    }
+}
+
 /// Validator's state.
 #[derive(
     Debug,
@@ -171,7 +193,10 @@ pub enum ValidatorState {
     /// A `Candidate` validator may participate in the consensus. It is either
     /// in the active or inactive validator set.
     Candidate,
-    // TODO consider adding `Jailed`
+    /// A `Jailed` validator has been removed from the validator set for a
+    /// fault and it may not participate in the consensus, nor receive any
+    /// rewards, until it's unjailed.
+    Jailed,
 }
 
 /// A bond is either a validator's self-bond or a delegation from a regular
@@ -225,7 +250,9 @@ pub struct Slash {
 pub type Slashes = Vec<Slash>;
 
 /// A type of slashsable event.
-#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, BorshSchema)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, BorshSchema,
+)]
 pub enum SlashType {
     /// Duplicate block vote.
     DuplicateVote,
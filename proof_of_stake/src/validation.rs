@@ -19,7 +19,8 @@ use crate::parameters::PosParams;
 use crate::types::{
     decimal_mult_i128, decimal_mult_u64, BondId, Bonds, CommissionRates, Slash,
     Slashes, TotalDeltas, Unbonds, ValidatorConsensusKeys, ValidatorDeltas,
-    ValidatorSets, ValidatorState, ValidatorStates, WeightedValidator,
+    ValidatorSet, ValidatorSets, ValidatorState, ValidatorStates,
+    WeightedValidator,
 };
 
 #[allow(missing_docs)]
@@ -144,6 +145,17 @@ pub enum Error {
          validator {0}, got {1}."
     )]
     InvalidMaxCommissionRateChange(Address, Decimal),
+    #[error(
+        "Invalid consensus key change of validator {0}, the address raw hash \
+         of the new key must be set."
+    )]
+    InvalidConsensusKeyChange(Address),
+    #[error("Validator {0} may not be unjailed before epoch {1}")]
+    ValidatorStillJailed(Address, u64),
+    #[error("Validator {0} may not be unjailed without any bonded stake")]
+    UnjailedValidatorWithoutStake(Address),
+    #[error("Unjailed validator {0} has not been added to the validator set")]
+    UnjailedValidatorMissingInValidatorSet(Address),
 }
 
 /// An update of PoS data.
@@ -192,8 +204,8 @@ pub enum DataUpdate {
 /// An update of a validator's data.
 #[derive(Clone, Debug)]
 pub enum ValidatorUpdate {
-    /// Validator's state update
-    State(Data<ValidatorStates>),
+    /// Validator's state update, with the validator's jailing, if any
+    State(Data<ValidatorStates>, Option<Jailing>),
     /// Consensus key update
     ConsensusKey(Data<ValidatorConsensusKeys>),
     /// Validator deltas update
@@ -204,6 +216,15 @@ pub enum ValidatorUpdate {
     MaxCommissionRateChange(Data<Decimal>),
 }
 
+/// The jailing of a validator, which is needed to validate its unjailing.
+#[derive(Clone, Debug)]
+pub struct Jailing {
+    /// The epoch in which the validator has been last jailed
+    pub epoch: Epoch,
+    /// The validator's deltas in the posterior state
+    pub deltas: Option<ValidatorDeltas>,
+}
+
 /// Data update with prior and posterior state.
 #[derive(Clone, Debug)]
 pub struct Data<T>
@@ -271,9 +292,24 @@ pub fn validate(
         validator_set_post,
         total_deltas_by_epoch: _,
         bonded_stake_by_epoch,
-        new_validators,
+        mut new_validators,
+        consensus_key_changes,
+        unjailed_validators,
     } = Validate::accumulate_changes(changes, params, &constants, &mut errors);
 
+    // An unjailed validator is added back to the validator sets from the
+    // pipeline epoch with its bonded stake
+    let is_unjailed = |validator: &WeightedValidator, epoch: Epoch| {
+        epoch >= pipeline_epoch
+            && unjailed_validators.get(&validator.address).map_or(
+                false,
+                |deltas| {
+                    let stake = deltas.get(epoch).unwrap_or_default();
+                    u64::try_from(stake).ok() == Some(validator.bonded_stake)
+                },
+            )
+    };
+
     // Check total deltas against bonds
     for (validator, total_delta) in total_deltas.iter() {
         let bond_delta = bond_delta.get(validator).copied().unwrap_or_default();
@@ -360,6 +396,7 @@ pub fn validate(
                                         && !pre.inactive.contains(validator)
                                         && !new_validators
                                             .contains_key(&validator.address)
+                                        && !is_unjailed(validator, epoch)
                                     {
                                         let mut is_valid = false;
 
@@ -429,6 +466,7 @@ pub fn validate(
                                         && !pre.inactive.contains(validator)
                                         && !new_validators
                                             .contains_key(&validator.address)
+                                        && !is_unjailed(validator, epoch)
                                     {
                                         let mut is_valid = false;
 
@@ -491,8 +529,16 @@ pub fn validate(
                                 bonded_stake: (*tokens_at_epoch).into(),
                                 address: validator.clone(),
                             };
+                            // A jailed validator is not in the validator set
+                            let is_jailed = validator_set_pre
+                                .as_ref()
+                                .and_then(|pre| pre.get(epoch))
+                                .map_or(false, |pre| {
+                                    !contains_address(pre, validator)
+                                });
                             if !post.active.contains(&weighted_validator) {
                                 if !post.inactive.contains(&weighted_validator)
+                                    && !is_jailed
                                 {
                                     errors.push(
                                         Error::WeightedValidatorNotFound(
@@ -521,6 +567,31 @@ pub fn validate(
         errors.push(Error::ValidatorSetNotUpdated)
     }
 
+    // Check that the raw hash of a changed consensus key is set to the
+    // validator's address. The raw hash is accumulated with the new
+    // validators, so it's removed from them here.
+    for (address, consensus_key) in consensus_key_changes {
+        let raw_hash = new_validators
+            .remove(&address)
+            .and_then(|validator| validator.has_address_raw_hash);
+        if raw_hash != Some(consensus_key.tm_raw_hash()) {
+            errors.push(Error::InvalidConsensusKeyChange(address))
+        }
+    }
+
+    // Check that the unjailed validators are added back to the validator set
+    for address in unjailed_validators.keys() {
+        let is_in_set = validator_set_post
+            .as_ref()
+            .and_then(|sets| sets.get(pipeline_epoch))
+            .map_or(false, |set| contains_address(set, address));
+        if !is_in_set {
+            errors.push(Error::UnjailedValidatorMissingInValidatorSet(
+                address.clone(),
+            ))
+        }
+    }
+
     // Check new validators are initialized with all the required fields
     if !new_validators.is_empty() {
         match &validator_set_post {
@@ -627,6 +698,20 @@ struct Accumulator {
     validator_set_pre: Option<ValidatorSets>,
     validator_set_post: Option<ValidatorSets>,
     new_validators: HashMap<Address, NewValidator>,
+    /// The new consensus keys of the existing validators at pipeline epoch
+    consensus_key_changes: HashMap<Address, common::PublicKey>,
+    /// The deltas of the unjailed validators
+    unjailed_validators: HashMap<Address, ValidatorDeltas>,
+}
+
+/// Check if a validator is in the active or inactive validator set with any
+/// stake.
+fn contains_address(validator_set: &ValidatorSet, address: &Address) -> bool {
+    validator_set
+        .active
+        .iter()
+        .chain(validator_set.inactive.iter())
+        .any(|validator| &validator.address == address)
 }
 
 /// An empty local type to re-use trait bounds for the functions associated with
@@ -641,7 +726,7 @@ struct Validate {
 impl Validate {
     fn accumulate_changes(
         changes: Vec<DataUpdate>,
-        _params: &PosParams,
+        params: &PosParams,
         constants: &Constants,
         errors: &mut Vec<Error>,
     ) -> Accumulator {
@@ -660,22 +745,28 @@ impl Validate {
             validator_set_pre,
             validator_set_post,
             new_validators,
+            consensus_key_changes,
+            unjailed_validators,
         } = &mut accumulator;
 
         for change in changes {
             match change {
                 Validator { address, update } => match update {
-                    State(data) => Self::validator_state(
+                    State(data, jailing) => Self::validator_state(
+                        params,
                         constants,
                         errors,
                         new_validators,
+                        unjailed_validators,
                         address,
                         data,
+                        jailing,
                     ),
                     ConsensusKey(data) => Self::validator_consensus_key(
                         constants,
                         errors,
                         new_validators,
+                        consensus_key_changes,
                         address,
                         data,
                     ),
@@ -746,12 +837,16 @@ impl Validate {
         accumulator
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn validator_state(
+        params: &PosParams,
         constants: &Constants,
         errors: &mut Vec<Error>,
         new_validators: &mut HashMap<Address, NewValidator>,
+        unjailed_validators: &mut HashMap<Address, ValidatorDeltas>,
         address: Address,
         data: Data<ValidatorStates>,
+        jailing: Option<Jailing>,
     ) {
         match (data.pre, data.post) {
             (None, Some(post)) => {
@@ -809,6 +904,36 @@ impl Validate {
                     (Some(Pending), Some(Candidate) | Some(Inactive))
                     | (Some(Candidate), Some(Inactive))
                     | (Some(Inactive), Some(Candidate) | Some(Pending)) => {}
+                    // A jailed validator may be unjailed once its jailing
+                    // period is over, if it still has some bonded stake
+                    (Some(Jailed), Some(Candidate)) => match jailing {
+                        Some(Jailing {
+                            epoch,
+                            deltas: Some(deltas),
+                        }) => {
+                            let unjail_epoch = epoch + params.jail_len;
+                            if constants.current_epoch < unjail_epoch {
+                                errors.push(Error::ValidatorStillJailed(
+                                    address.clone(),
+                                    unjail_epoch.into(),
+                                ))
+                            }
+                            let stake = deltas
+                                .get(constants.unbonding_epoch)
+                                .unwrap_or_default();
+                            if stake <= token::Change::default() {
+                                errors.push(
+                                    Error::UnjailedValidatorWithoutStake(
+                                        address.clone(),
+                                    ),
+                                )
+                            }
+                            unjailed_validators.insert(address, deltas);
+                        }
+                        _ => errors.push(Error::UnjailedValidatorWithoutStake(
+                            address,
+                        )),
+                    },
                     _ => errors.push(Error::InvalidNewValidatorState(
                         constants.pipeline_epoch.into(),
                     )),
@@ -825,6 +950,7 @@ impl Validate {
         constants: &Constants,
         errors: &mut Vec<Error>,
         new_validators: &mut HashMap<Address, NewValidator>,
+        consensus_key_changes: &mut HashMap<Address, common::PublicKey>,
         address: Address,
         data: Data<ValidatorConsensusKeys>,
     ) {
@@ -868,6 +994,14 @@ impl Validate {
                         ),
                     }
                 }
+                // The key may only change at pipeline epoch
+                let key_pre = pre.get(constants.pipeline_epoch);
+                match post.get(constants.pipeline_epoch) {
+                    Some(key_post) if key_pre != Some(key_post) => {
+                        consensus_key_changes.insert(address, key_post.clone());
+                    }
+                    _ => {}
+                }
             }
             (Some(_), None) => {
                 errors.push(Error::ValidatorConsensusKeyIsRequired(address))
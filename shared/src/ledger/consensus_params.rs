@@ -0,0 +1,43 @@
+//! The consensus parameters enforced by a node, which bound the size and the
+//! gas of the blocks and of the txs that may be included in them.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::ledger::parameters::EpochDuration;
+use crate::types::time::DurationSecs;
+
+// TODO: remove this hard-coded value; Tendermint, and thus
+// Namada uses 20 MiB max block sizes by default; 16 MiB leaves
+// plenty of room for header data, evidence and protobuf serialization
+// overhead
+/// The maximum size of a block proposal in bytes
+pub const MAX_PROPOSAL_SIZE: usize = 16 << 20;
+/// The maximum size in bytes of the wrapper txs included in a block proposal
+pub const HALF_MAX_PROPOSAL_SIZE: usize = MAX_PROPOSAL_SIZE / 2;
+
+/// The consensus parameters currently enforced by a node
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ConsensusParams {
+    /// The maximum size of a block proposal in bytes
+    pub max_proposal_bytes: u64,
+    /// The maximum size in bytes of the wrapper txs included in a block,
+    /// which also bounds the size of a single tx
+    pub max_wrapper_txs_bytes: u64,
+    /// The maximum sum of the gas of the txs in a block. This is the lower
+    /// of the protocol's limit and the `block_gas_limit` parameter, which may
+    /// be changed by governance.
+    pub block_gas_limit: u64,
+    /// The maximum gas of a single tx
+    pub tx_gas_limit: u64,
+    /// The maximum number of verifiers a tx may trigger, if any
+    pub max_verifiers: Option<u64>,
+    /// The number of blocks for which evidence of misbehavior is valid
+    pub evidence_max_age_num_blocks: u64,
+    /// Maximum expected time per block, which may be changed by governance
+    pub max_expected_time_per_block: DurationSecs,
+    /// The epoch duration, which may be changed by governance
+    pub epoch_duration: EpochDuration,
+    /// The maximum size of the data of an IBC packet in bytes, which may be
+    /// changed by governance
+    pub max_ibc_packet_data_bytes: u64,
+}
@@ -1,4 +1,7 @@
 //! Bridge from Ethereum
 
-pub mod storage;
+/// Ethereum bridge storage
+pub use namada_core::ledger::eth_bridge::storage;
+/// Transfers to Ethereum
+pub use namada_core::ledger::eth_bridge::transfer;
 pub mod vp;
@@ -1,17 +1,25 @@
 //! Validity predicate for the Ethereum bridge
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-use crate::ledger::native_vp::{Ctx, NativeVp};
+use borsh::BorshDeserialize;
+/// Internal address for the Ethereum bridge VP
+pub use namada_core::ledger::eth_bridge::ADDRESS;
+
+use super::storage;
+use super::transfer::TransferToEthereum;
+use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::storage as ledger_storage;
 use crate::ledger::storage::StorageHasher;
+use crate::ledger::storage_api::StorageRead;
+use crate::ledger::vp_env::VpEnv;
+use crate::proto::SignedTxData;
 use crate::types::address::{Address, InternalAddress};
+use crate::types::key::{self, common};
 use crate::types::storage::Key;
+use crate::types::token;
 use crate::vm::WasmCacheAccess;
 
-/// Internal address for the Ethereum bridge VP
-pub const ADDRESS: Address = Address::Internal(InternalAddress::EthBridge);
-
 /// Validity predicate for the Ethereum bridge
 pub struct EthBridge<'ctx, DB, H, CA>
 where
@@ -23,11 +31,318 @@ where
     pub ctx: Ctx<'ctx, DB, H, CA>,
 }
 
+/// The type of a storage key modified by a tx that triggers the Ethereum
+/// bridge VP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// The bridge's transfer queue. A user tx may only append well-formed
+    /// transfers to it or cancel pending transfers, which is checked
+    /// separately.
+    Queue,
+    /// Any other key in the bridge's storage. These are reserved for the
+    /// bridge's own protocol txs.
+    Reserved,
+    /// The bridge's balance of a token, which escrows the queued transfers.
+    /// A user tx may only increase it or refund cancelled transfers, which is
+    /// checked separately.
+    Escrow,
+    /// A key outside of the bridge's storage, which this VP doesn't guard
+    Other,
+}
+
+impl From<&Key> for KeyType {
+    fn from(key: &Key) -> Self {
+        if storage::is_queue_key(key) {
+            Self::Queue
+        } else if storage::is_eth_bridge_key(key) {
+            Self::Reserved
+        } else if token::is_any_token_balance_key(key) == Some(&ADDRESS) {
+            Self::Escrow
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl KeyType {
+    /// Check if a user tx may freely modify a key of this type. None of the
+    /// keys in the bridge's storage nor its balances may be, though transfers
+    /// may be appended to the queue and escrowed, or cancelled and refunded.
+    pub fn is_user_writable(&self) -> bool {
+        match self {
+            Self::Queue | Self::Reserved | Self::Escrow => false,
+            Self::Other => true,
+        }
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Internal error")]
     Internal,
+    #[error("Native VP error: {0}")]
+    NativeVpError(#[from] native_vp::Error),
+}
+
+/// Decode the encoded queue, which is empty when absent. Returns `None` if
+/// the queue is malformed.
+fn decode_queue(bytes: Option<&[u8]>) -> Option<Vec<TransferToEthereum>> {
+    match bytes {
+        Some(bytes) => Vec::<TransferToEthereum>::try_from_slice(bytes).ok(),
+        None => Some(vec![]),
+    }
+}
+
+/// Get the transfers appended to the queue by a tx, given the encoded queue
+/// before and after the tx. Returns `None` if the tx changed the queue in any
+/// other way or if the queue is malformed.
+pub fn appended_transfers(
+    pre: Option<&[u8]>,
+    post: Option<&[u8]>,
+) -> Option<Vec<TransferToEthereum>> {
+    let pre = decode_queue(pre)?;
+    let mut post = decode_queue(post)?;
+    if post.len() <= pre.len() || post[..pre.len()] != pre[..] {
+        return None;
+    }
+    Some(post.split_off(pre.len()))
+}
+
+/// Get the transfers cancelled by a tx, which removed them from the queue
+/// while they were pending, given the encoded queue before and after the tx.
+/// Returns `None` if the tx changed the queue in any other way or if the
+/// queue is malformed.
+pub fn cancelled_transfers(
+    pre: Option<&[u8]>,
+    post: Option<&[u8]>,
+) -> Option<Vec<TransferToEthereum>> {
+    let pre = decode_queue(pre)?;
+    let post = decode_queue(post)?;
+    if post.len() >= pre.len() {
+        return None;
+    }
+    // The remaining transfers must keep their order in the queue
+    let mut remaining = post.into_iter().peekable();
+    let mut cancelled = vec![];
+    for transfer in pre {
+        if remaining.peek() == Some(&transfer) {
+            remaining.next();
+        } else {
+            cancelled.push(transfer);
+        }
+    }
+    if remaining.next().is_some() {
+        return None;
+    }
+    Some(cancelled)
+}
+
+/// Get the amounts to refund from the escrow for the given cancelled
+/// transfers, by asset.
+fn refunds_by_asset(
+    cancelled: &[TransferToEthereum],
+) -> BTreeMap<Address, u128> {
+    let mut refunds: BTreeMap<Address, u128> = BTreeMap::new();
+    for transfer in cancelled {
+        *refunds.entry(transfer.asset.clone()).or_default() +=
+            u128::from(transfer.amount) + u128::from(transfer.fee);
+    }
+    refunds
+}
+
+impl<'a, DB, H, CA> EthBridge<'a, DB, H, CA>
+where
+    DB: 'static + ledger_storage::DB + for<'iter> ledger_storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Read the encoded queue before and after the tx
+    fn read_queue_change(
+        &self,
+    ) -> Result<(Option<Vec<u8>>, Option<Vec<u8>>), Error> {
+        let queue_key = storage::queue_key();
+        let pre = self.ctx.pre().read_bytes(&queue_key)?;
+        let post = self.ctx.post().read_bytes(&queue_key)?;
+        Ok((pre, post))
+    }
+
+    /// Check a change of the queue by a tx, which may either append transfers
+    /// or cancel pending transfers.
+    fn check_queue_change(
+        &self,
+        tx_data: &[u8],
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool, Error> {
+        let (pre, post) = self.read_queue_change()?;
+        if let Some(appended) =
+            appended_transfers(pre.as_deref(), post.as_deref())
+        {
+            return self.check_appended_transfers(&appended, verifiers);
+        }
+        if let Some(cancelled) =
+            cancelled_transfers(pre.as_deref(), post.as_deref())
+        {
+            return self.check_cancelled_transfers(&cancelled, tx_data);
+        }
+        tracing::debug!(
+            "Rejecting a change of the queue other than appending or \
+             cancelling transfers",
+        );
+        Ok(false)
+    }
+
+    /// Check the transfers appended to the queue by a tx. They must be
+    /// well-formed, authorized by their senders and their amounts and fees
+    /// must be escrowed in the bridge's balance of their assets.
+    fn check_appended_transfers(
+        &self,
+        appended: &[TransferToEthereum],
+        verifiers: &BTreeSet<Address>,
+    ) -> Result<bool, Error> {
+        let mut required_escrow: BTreeMap<Address, u128> = BTreeMap::new();
+        for transfer in appended {
+            if let Err(err) = transfer.validate() {
+                tracing::debug!(
+                    ?transfer,
+                    %err,
+                    "Rejecting a malformed transfer",
+                );
+                return Ok(false);
+            }
+            if !verifiers.contains(&transfer.sender) {
+                tracing::debug!(
+                    ?transfer,
+                    "Rejecting a transfer not authorized by its sender",
+                );
+                return Ok(false);
+            }
+            *required_escrow.entry(transfer.asset.clone()).or_default() +=
+                u128::from(transfer.amount) + u128::from(transfer.fee);
+        }
+        for (asset, required) in required_escrow {
+            let balance_key = token::balance_key(&asset, &ADDRESS);
+            let pre: token::Amount =
+                self.ctx.pre().read(&balance_key)?.unwrap_or_default();
+            let post: token::Amount =
+                self.ctx.post().read(&balance_key)?.unwrap_or_default();
+            let escrowed = u128::from(post).saturating_sub(u128::from(pre));
+            if escrowed < required {
+                tracing::debug!(
+                    %asset,
+                    escrowed,
+                    required,
+                    "Rejecting transfers that are not escrowed",
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Check the transfers cancelled by a tx. They must be authorized by
+    /// their senders and their amounts and fees must be refunded to them.
+    /// A transfer that is no longer pending cannot be cancelled, as it's not
+    /// in the queue anymore.
+    ///
+    /// Unlike for appended transfers, the senders' VPs don't guard the
+    /// cancellations, as the refunds only credit them, so the tx must be
+    /// signed by each sender.
+    fn check_cancelled_transfers(
+        &self,
+        cancelled: &[TransferToEthereum],
+        tx_data: &[u8],
+    ) -> Result<bool, Error> {
+        let signed = match SignedTxData::try_from_slice(tx_data) {
+            Ok(signed) => signed,
+            Err(_) => {
+                tracing::debug!("Rejecting an unsigned cancellation");
+                return Ok(false);
+            }
+        };
+        let mut refunds: BTreeMap<(Address, Address), u128> = BTreeMap::new();
+        for transfer in cancelled {
+            if !self.is_signed_by(&signed, &transfer.sender)? {
+                tracing::debug!(
+                    ?transfer,
+                    "Rejecting a cancellation not authorized by the sender",
+                );
+                return Ok(false);
+            }
+            *refunds
+                .entry((transfer.sender.clone(), transfer.asset.clone()))
+                .or_default() +=
+                u128::from(transfer.amount) + u128::from(transfer.fee);
+        }
+        for ((sender, asset), refund) in refunds {
+            let balance_key = token::balance_key(&asset, &sender);
+            let pre: token::Amount =
+                self.ctx.pre().read(&balance_key)?.unwrap_or_default();
+            let post: token::Amount =
+                self.ctx.post().read(&balance_key)?.unwrap_or_default();
+            let refunded = u128::from(post).saturating_sub(u128::from(pre));
+            if refunded < refund {
+                tracing::debug!(
+                    %sender,
+                    %asset,
+                    refunded,
+                    refund,
+                    "Rejecting cancelled transfers that are not refunded",
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Check if the tx is signed by the given account's public key
+    fn is_signed_by(
+        &self,
+        signed: &SignedTxData,
+        owner: &Address,
+    ) -> Result<bool, Error> {
+        let pk: Option<common::PublicKey> =
+            self.ctx.pre().read(&key::pk_key(owner))?;
+        match pk {
+            Some(pk) => Ok(self.ctx.verify_tx_signature(&pk, &signed.sig)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Check a change of the bridge's balance of a token. The escrowed tokens
+    /// may not be debited by a user tx, except to refund the transfers it
+    /// cancels.
+    fn check_escrow_change(&self, balance_key: &Key) -> Result<bool, Error> {
+        let pre: token::Amount =
+            self.ctx.pre().read(balance_key)?.unwrap_or_default();
+        let post: token::Amount =
+            self.ctx.post().read(balance_key)?.unwrap_or_default();
+        if post >= pre {
+            return Ok(true);
+        }
+        let debit = u128::from(pre) - u128::from(post);
+        let (queue_pre, queue_post) = self.read_queue_change()?;
+        let cancelled =
+            cancelled_transfers(queue_pre.as_deref(), queue_post.as_deref())
+                .unwrap_or_default();
+        let refund = refunds_by_asset(&cancelled)
+            .into_iter()
+            .find(|(asset, _)| {
+                &token::balance_key(asset, &ADDRESS) == balance_key
+            })
+            .map(|(_, refund)| refund)
+            .unwrap_or_default();
+        if debit > refund {
+            tracing::debug!(
+                %balance_key,
+                debit,
+                refund,
+                "Rejecting a debit of the escrow exceeding the refunds",
+            );
+            return Ok(false);
+        }
+        Ok(true)
+    }
 }
 
 impl<'a, DB, H, CA> NativeVp for EthBridge<'a, DB, H, CA>
@@ -42,16 +357,158 @@ where
 
     fn validate_tx(
         &self,
-        _tx_data: &[u8],
-        _keys_changed: &BTreeSet<Key>,
-        _verifiers: &BTreeSet<Address>,
+        tx_data: &[u8],
+        keys_changed: &BTreeSet<Key>,
+        verifiers: &BTreeSet<Address>,
     ) -> Result<bool, Self::Error> {
         tracing::debug!(
-            tx_data_len = _tx_data.len(),
-            keys_changed_len = _keys_changed.len(),
-            verifiers_len = _verifiers.len(),
+            tx_data_len = tx_data.len(),
+            keys_changed_len = keys_changed.len(),
+            verifiers_len = verifiers.len(),
             "Validity predicate triggered",
         );
-        Ok(false)
+        for key in keys_changed {
+            let key_type = KeyType::from(key);
+            let is_valid = match key_type {
+                KeyType::Queue => {
+                    self.check_queue_change(tx_data, verifiers)?
+                }
+                KeyType::Escrow => self.check_escrow_change(key)?,
+                KeyType::Reserved | KeyType::Other => {
+                    key_type.is_user_writable()
+                }
+            };
+            if !is_valid {
+                tracing::debug!(
+                    %key,
+                    ?key_type,
+                    "Rejecting a modification of a protected key",
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+
+    use super::*;
+    use crate::ledger::eth_bridge::transfer::EthAddress;
+    use crate::types::address;
+    use crate::types::storage::KeySeg;
+
+    /// Test the classification of the keys that a user tx may and may not
+    /// modify.
+    #[test]
+    fn test_key_classification() {
+        let queue = storage::queue_key();
+        assert_eq!(KeyType::from(&queue), KeyType::Queue);
+        assert!(!KeyType::from(&queue).is_user_writable());
+
+        let bridge_key = Key::from(ADDRESS.to_db_key())
+            .push(&"arbitrary".to_owned())
+            .unwrap();
+        assert_eq!(KeyType::from(&bridge_key), KeyType::Reserved);
+        assert!(!KeyType::from(&bridge_key).is_user_writable());
+
+        // A sub-key of the queue is not the queue itself, but it's still
+        // in the bridge's storage
+        let queue_sub_key = queue.push(&"sub".to_owned()).unwrap();
+        assert_eq!(KeyType::from(&queue_sub_key), KeyType::Reserved);
+
+        let bridge_vp_key = Key::validity_predicate(&ADDRESS);
+        assert_eq!(KeyType::from(&bridge_vp_key), KeyType::Reserved);
+
+        let other_key =
+            Key::from(address::testing::established_address_1().to_db_key())
+                .push(&"queue".to_owned())
+                .unwrap();
+        assert_eq!(KeyType::from(&other_key), KeyType::Other);
+        assert!(KeyType::from(&other_key).is_user_writable());
+
+        let escrow_key = token::balance_key(&address::eth(), &ADDRESS);
+        assert_eq!(KeyType::from(&escrow_key), KeyType::Escrow);
+        assert!(!KeyType::from(&escrow_key).is_user_writable());
+    }
+
+    fn transfer(amount: u64) -> TransferToEthereum {
+        TransferToEthereum {
+            sender: address::testing::established_address_1(),
+            recipient: EthAddress([1; 20]),
+            asset: address::eth(),
+            amount: token::Amount::from(amount),
+            fee: token::Amount::default(),
+        }
+    }
+
+    /// Test that only the transfers appended to the queue are accepted as a
+    /// change of the queue.
+    #[test]
+    fn test_appended_transfers() {
+        let encode = |transfers: Vec<TransferToEthereum>| {
+            transfers.try_to_vec().unwrap()
+        };
+        let one = encode(vec![transfer(1)]);
+        let two = encode(vec![transfer(1), transfer(2)]);
+
+        assert_eq!(
+            appended_transfers(None, Some(&one)),
+            Some(vec![transfer(1)])
+        );
+        assert_eq!(
+            appended_transfers(Some(&one), Some(&two)),
+            Some(vec![transfer(2)])
+        );
+
+        // Removing, replacing or not appending transfers is rejected
+        assert_eq!(appended_transfers(Some(&two), Some(&one)), None);
+        assert_eq!(appended_transfers(Some(&one), None), None);
+        assert_eq!(appended_transfers(Some(&one), Some(&one)), None);
+        let replaced = encode(vec![transfer(3), transfer(2)]);
+        assert_eq!(appended_transfers(Some(&one), Some(&replaced)), None);
+
+        // A malformed queue is rejected
+        assert_eq!(appended_transfers(None, Some(b"arbitrary value")), None);
+    }
+
+    /// Test that only the transfers removed from the queue, keeping the
+    /// order of the remaining ones, are accepted as cancelled.
+    #[test]
+    fn test_cancelled_transfers() {
+        let encode = |transfers: Vec<TransferToEthereum>| {
+            transfers.try_to_vec().unwrap()
+        };
+        let one = encode(vec![transfer(1)]);
+        let three = encode(vec![transfer(1), transfer(2), transfer(3)]);
+
+        assert_eq!(
+            cancelled_transfers(Some(&one), None),
+            Some(vec![transfer(1)])
+        );
+        assert_eq!(
+            cancelled_transfers(
+                Some(&three),
+                Some(&encode(vec![transfer(1), transfer(3)]))
+            ),
+            Some(vec![transfer(2)])
+        );
+        assert_eq!(
+            cancelled_transfers(Some(&three), Some(&encode(vec![transfer(2)]))),
+            Some(vec![transfer(1), transfer(3)])
+        );
+
+        // Appending, reordering or not removing transfers is rejected
+        assert_eq!(cancelled_transfers(Some(&one), Some(&three)), None);
+        assert_eq!(cancelled_transfers(Some(&one), Some(&one)), None);
+        let reordered = encode(vec![transfer(3), transfer(1)]);
+        assert_eq!(cancelled_transfers(Some(&three), Some(&reordered)), None);
+        let replaced = encode(vec![transfer(4)]);
+        assert_eq!(cancelled_transfers(Some(&three), Some(&replaced)), None);
+
+        // A malformed queue is rejected
+        assert_eq!(cancelled_transfers(Some(b"arbitrary value"), None), None);
     }
 }
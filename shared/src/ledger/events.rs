@@ -1,5 +1,8 @@
 //! Logic to do with events emitted by the ledger.
+pub mod block_events;
+pub mod ibc_packets;
 pub mod log;
+pub mod tx_results;
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
@@ -10,10 +13,13 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use thiserror::Error;
 
 use crate::ledger::native_vp::governance::utils::ProposalEvent;
+use crate::ledger::pos::types::Slash;
 use crate::tendermint_proto::abci::EventAttribute;
+use crate::types::address::Address;
 use crate::types::ibc::IbcEvent;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::transaction::{hash_tx, TxType};
+use crate::types::validity_predicate::VpEvent;
 
 /// Indicates if an event is emitted do to
 /// an individual Tx or the nature of a finalized block
@@ -49,6 +55,10 @@ pub enum EventType {
     Ibc(String),
     /// The proposal that has been executed
     Proposal,
+    /// An event emitted by a validity predicate of an applied transaction
+    Vp(String),
+    /// A validator that has been slashed for evidence of misbehavior
+    Slash,
 }
 
 impl Display for EventType {
@@ -58,6 +68,8 @@ impl Display for EventType {
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
             EventType::Proposal => write!(f, "proposal"),
+            EventType::Vp(t) => write!(f, "vp_{}", t),
+            EventType::Slash => write!(f, "slash"),
         }?;
         Ok(())
     }
@@ -112,6 +124,47 @@ impl Event {
         event
     }
 
+    /// Creates an event emitted by the VP of the given address for the
+    /// transaction with the given hash. The attributes "vp", "hash" and
+    /// "height" are set by the ledger, overriding the VP's own attributes.
+    pub fn new_vp_event(
+        vp: &Address,
+        event: VpEvent,
+        tx_hash: &str,
+        height: u64,
+    ) -> Self {
+        let mut event = Event {
+            event_type: EventType::Vp(event.event_type),
+            level: EventLevel::Tx,
+            attributes: event.attributes.into_iter().collect(),
+        };
+        event["vp"] = vp.encode();
+        event["hash"] = tx_hash.to_owned();
+        event["height"] = height.to_string();
+        event
+    }
+
+    /// Creates an event for a slash of the given validator applied at the
+    /// given block height
+    pub fn new_slash_event(
+        validator: &Address,
+        slash: &Slash,
+        height: u64,
+    ) -> Self {
+        let mut event = Event {
+            event_type: EventType::Slash,
+            level: EventLevel::Block,
+            attributes: HashMap::new(),
+        };
+        event["validator"] = validator.encode();
+        event["type"] = slash.r#type.to_string();
+        event["rate"] = slash.rate.to_string();
+        event["evidence_epoch"] = slash.epoch.to_string();
+        event["evidence_height"] = slash.block_height.to_string();
+        event["height"] = height.to_string();
+        event
+    }
+
     /// Check if the events keys contains a given string
     pub fn contains_key(&self, key: &str) -> bool {
         self.attributes.contains_key(key)
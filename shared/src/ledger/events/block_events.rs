@@ -0,0 +1,211 @@
+//! A log of the events emitted by the last blocks, to be queried by block
+//! height.
+//!
+//! The events are taken from the `FinalizeBlock` calls, grouped by their
+//! source, and they're retained for a configurable number of blocks.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::ledger::events::{Event, EventLevel, EventType};
+use crate::types::storage::BlockHeight;
+
+/// The default number of blocks for which the events are retained
+pub const DEFAULT_BLOCK_EVENTS_RETENTION_BLOCKS: u64 = 1000;
+
+/// The events emitted by a tx applied in a block
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct TxEvents {
+    /// The hash of the tx
+    pub hash: String,
+    /// The events emitted by the tx, ending with its accepted or applied
+    /// event
+    pub events: Vec<Event>,
+}
+
+/// The events emitted by a block, grouped by their source
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub struct BlockEvents {
+    /// The events emitted at the beginning of the block, before its txs are
+    /// applied, e.g. the slashes and the executed proposals
+    pub begin_block: Vec<Event>,
+    /// The events of the txs applied in the block, in order
+    pub txs: Vec<TxEvents>,
+    /// The events emitted at the end of the block, after its txs are applied
+    pub end_block: Vec<Event>,
+}
+
+impl BlockEvents {
+    /// Group the events emitted by a block by their source. The events of a
+    /// tx are emitted before its accepted or applied event, which carries
+    /// the tx hash. The block-level events emitted before any tx event begin
+    /// the block, while the others end it.
+    pub fn new(events: &[Event]) -> Self {
+        let mut block_events = Self::default();
+        let mut pending_tx_events = vec![];
+        for event in events {
+            match (&event.level, &event.event_type) {
+                (EventLevel::Tx, EventType::Accepted | EventType::Applied) => {
+                    pending_tx_events.push(event.clone());
+                    block_events.txs.push(TxEvents {
+                        hash: event.get("hash").cloned().unwrap_or_default(),
+                        events: std::mem::take(&mut pending_tx_events),
+                    });
+                }
+                (EventLevel::Tx, _) => pending_tx_events.push(event.clone()),
+                (EventLevel::Block, _) => {
+                    if block_events.txs.is_empty()
+                        && pending_tx_events.is_empty()
+                    {
+                        block_events.begin_block.push(event.clone())
+                    } else {
+                        block_events.end_block.push(event.clone())
+                    }
+                }
+            }
+        }
+        if !pending_tx_events.is_empty() {
+            tracing::error!(
+                "{} tx events were emitted without an accepted or applied \
+                 event",
+                pending_tx_events.len()
+            );
+            block_events.txs.push(TxEvents {
+                hash: String::new(),
+                events: pending_tx_events,
+            });
+        }
+        block_events
+    }
+}
+
+/// The events emitted by a block at a queried height
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum BlockEventsResult {
+    /// The events of the block
+    Available(BlockEvents),
+    /// The events of the block are not retained, because it's past the
+    /// retention period or it was finalized before the node started
+    Unavailable,
+}
+
+/// A log of the events emitted by the last blocks
+#[derive(Debug)]
+pub struct BlockEventLog {
+    /// The number of blocks for which the events are retained
+    retention_blocks: u64,
+    /// The events by the height of their block
+    blocks: BTreeMap<BlockHeight, BlockEvents>,
+}
+
+impl Default for BlockEventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_BLOCK_EVENTS_RETENTION_BLOCKS)
+    }
+}
+
+impl BlockEventLog {
+    /// Return a new log retaining the events for the given number of blocks
+    pub fn new(retention_blocks: u64) -> Self {
+        Self {
+            retention_blocks,
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// Log the events emitted by the block at the given height and forget
+    /// the events of the blocks past the retention period
+    pub fn log_block(&mut self, height: BlockHeight, events: &[Event]) {
+        self.blocks.insert(height, BlockEvents::new(events));
+        let oldest_retained =
+            BlockHeight(height.0.saturating_sub(self.retention_blocks));
+        self.blocks = self.blocks.split_off(&(oldest_retained + 1));
+    }
+
+    /// Get the events emitted by the block at the given height, if the block
+    /// is retained
+    pub fn get(&self, height: BlockHeight) -> BlockEventsResult {
+        match self.blocks.get(&height) {
+            Some(events) => BlockEventsResult::Available(events.clone()),
+            None => BlockEventsResult::Unavailable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn event(event_type: EventType, level: EventLevel) -> Event {
+        Event {
+            event_type,
+            level,
+            attributes: HashMap::new(),
+        }
+    }
+
+    fn applied_event(hash: &str) -> Event {
+        let mut event = event(EventType::Applied, EventLevel::Tx);
+        event["hash"] = hash.to_string();
+        event
+    }
+
+    /// Test that the events of a block are grouped by their source and that
+    /// they're forgotten past the retention period.
+    #[test]
+    fn test_block_events_retention() {
+        let mut log = BlockEventLog::new(2);
+        let slash = event(EventType::Slash, EventLevel::Block);
+        let ibc = event(EventType::Ibc("send_packet".into()), EventLevel::Tx);
+        let proposal = event(EventType::Proposal, EventLevel::Block);
+        log.log_block(
+            BlockHeight(1),
+            &[
+                slash.clone(),
+                ibc.clone(),
+                applied_event("a"),
+                applied_event("b"),
+                proposal.clone(),
+            ],
+        );
+
+        assert_eq!(
+            log.get(BlockHeight(1)),
+            BlockEventsResult::Available(BlockEvents {
+                begin_block: vec![slash],
+                txs: vec![
+                    TxEvents {
+                        hash: "a".into(),
+                        events: vec![ibc, applied_event("a")],
+                    },
+                    TxEvents {
+                        hash: "b".into(),
+                        events: vec![applied_event("b")],
+                    },
+                ],
+                end_block: vec![proposal],
+            })
+        );
+
+        // The events are retained until the retention period is over
+        log.log_block(BlockHeight(2), &[]);
+        assert_eq!(
+            log.get(BlockHeight(2)),
+            BlockEventsResult::Available(BlockEvents::default())
+        );
+        assert!(matches!(
+            log.get(BlockHeight(1)),
+            BlockEventsResult::Available(_)
+        ));
+        log.log_block(BlockHeight(3), &[]);
+        assert_eq!(log.get(BlockHeight(1)), BlockEventsResult::Unavailable);
+        assert_eq!(log.get(BlockHeight(4)), BlockEventsResult::Unavailable);
+    }
+}
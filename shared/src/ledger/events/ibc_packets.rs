@@ -0,0 +1,336 @@
+//! A local tracker of the IBC packets sent from this chain that are awaiting
+//! an acknowledgement, derived from the events emitted by `FinalizeBlock`
+//! calls.
+//!
+//! A packet that's still not acknowledged after a configurable bound is only
+//! marked as timed-out locally, so that it can be cleaned up and its timeout
+//! flow started. This is purely local housekeeping: the timeout height and
+//! timestamp of the packet remain the only authority on-chain, so a packet
+//! marked as timed-out locally is still forgotten once it's acknowledged or
+//! timed-out on-chain. A packet marked as timed-out locally is forgotten after
+//! a retention window, so that the tracker doesn't grow without bound.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+use crate::ledger::events::{Event, EventType};
+use crate::types::storage::BlockHeight;
+use crate::types::time::{DateTimeUtc, DurationSecs};
+
+/// The default duration after which a packet awaiting an acknowledgement is
+/// marked as timed-out locally, in seconds
+pub const DEFAULT_IBC_ACK_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+/// The default duration for which a packet marked as timed-out locally is
+/// retained before it's forgotten, in seconds
+pub const DEFAULT_IBC_TIMED_OUT_RETENTION_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// The type of the event emitted when a packet is sent
+const SEND_PACKET_EVENT: &str = "send_packet";
+/// The types of the events emitted when a sent packet is settled on-chain
+const SETTLED_PACKET_EVENTS: [&str; 3] = [
+    "acknowledge_packet",
+    "timeout_packet",
+    "timeout_on_close_packet",
+];
+
+/// The identifier of a packet sent from this chain
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct PacketId {
+    /// The source port of the packet
+    pub port_id: String,
+    /// The source channel of the packet
+    pub channel_id: String,
+    /// The sequence of the packet on its channel
+    pub sequence: u64,
+}
+
+impl PacketId {
+    /// Get the identifier of the packet of an IBC event, if any
+    fn from_event(event: &Event) -> Option<Self> {
+        Some(Self {
+            port_id: event.get("packet_src_port")?.clone(),
+            channel_id: event.get("packet_src_channel")?.clone(),
+            sequence: event.get("packet_sequence")?.parse().ok()?,
+        })
+    }
+}
+
+/// The local status of a sent packet
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub enum PacketStatus {
+    /// The packet is awaiting an acknowledgement
+    AwaitingAck,
+    /// The packet has been awaiting an acknowledgement for longer than the
+    /// configured bound. It may still be acknowledged or timed-out on-chain.
+    TimedOutLocally,
+}
+
+/// A sent packet tracked locally
+#[derive(
+    Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct TrackedPacket {
+    /// The height of the block in which the packet was sent
+    pub height: BlockHeight,
+    /// The time of the block in which the packet was sent
+    pub sent_at: DateTimeUtc,
+    /// The local status of the packet
+    pub status: PacketStatus,
+}
+
+/// A tracker of the packets sent from this chain awaiting an acknowledgement
+#[derive(Debug)]
+pub struct IbcPacketTracker {
+    /// The duration after which a packet awaiting an acknowledgement is
+    /// marked as timed-out locally
+    ack_timeout: DurationSecs,
+    /// The duration after the acknowledgement timeout for which a packet
+    /// marked as timed-out locally is retained
+    retention: DurationSecs,
+    /// The tracked packets
+    packets: BTreeMap<PacketId, TrackedPacket>,
+    /// The packets awaiting an acknowledgement, ordered by the time they were
+    /// sent, so that only those past the bound are visited
+    awaiting_ack: BTreeSet<(DateTimeUtc, PacketId)>,
+    /// The packets marked as timed-out locally, ordered by the time they were
+    /// sent, so that only those past the retention window are visited
+    timed_out_locally: BTreeSet<(DateTimeUtc, PacketId)>,
+}
+
+impl Default for IbcPacketTracker {
+    fn default() -> Self {
+        Self::new(
+            DurationSecs(DEFAULT_IBC_ACK_TIMEOUT_SECS),
+            DurationSecs(DEFAULT_IBC_TIMED_OUT_RETENTION_SECS),
+        )
+    }
+}
+
+impl IbcPacketTracker {
+    /// Return a new tracker marking the packets awaiting an acknowledgement
+    /// for the given duration as timed-out locally and retaining them for
+    /// the given retention
+    pub fn new(ack_timeout: DurationSecs, retention: DurationSecs) -> Self {
+        Self {
+            ack_timeout,
+            retention,
+            packets: BTreeMap::new(),
+            awaiting_ack: BTreeSet::new(),
+            timed_out_locally: BTreeSet::new(),
+        }
+    }
+
+    /// Track the packets sent in the block at the given height and time, from
+    /// the events emitted by the block, and forget the packets that have been
+    /// acknowledged or timed-out on-chain. Then, the packets that have been
+    /// awaiting an acknowledgement for at least the bound are marked as
+    /// timed-out locally and the packets timed-out locally for longer than
+    /// the retention are forgotten.
+    pub fn log_block(
+        &mut self,
+        height: BlockHeight,
+        time: DateTimeUtc,
+        events: &[Event],
+    ) {
+        for event in events {
+            let event_type = match &event.event_type {
+                EventType::Ibc(event_type) => event_type.as_str(),
+                _ => continue,
+            };
+            let packet_id = match PacketId::from_event(event) {
+                Some(packet_id) => packet_id,
+                None => continue,
+            };
+            if event_type == SEND_PACKET_EVENT {
+                self.forget(&packet_id);
+                self.awaiting_ack.insert((time, packet_id.clone()));
+                self.packets.insert(
+                    packet_id,
+                    TrackedPacket {
+                        height,
+                        sent_at: time,
+                        status: PacketStatus::AwaitingAck,
+                    },
+                );
+            } else if SETTLED_PACKET_EVENTS.contains(&event_type) {
+                self.forget(&packet_id);
+            }
+        }
+
+        while let Some((sent_at, packet_id)) =
+            self.awaiting_ack.iter().next().cloned()
+        {
+            if time < sent_at + self.ack_timeout {
+                break;
+            }
+            self.awaiting_ack.remove(&(sent_at, packet_id.clone()));
+            if let Some(packet) = self.packets.get_mut(&packet_id) {
+                tracing::info!(
+                    "IBC packet {}/{}/{} sent at height {} has not been \
+                     acknowledged within {} seconds, marking it as timed-out \
+                     locally",
+                    packet_id.port_id,
+                    packet_id.channel_id,
+                    packet_id.sequence,
+                    packet.height,
+                    self.ack_timeout.0
+                );
+                packet.status = PacketStatus::TimedOutLocally;
+            }
+            self.timed_out_locally.insert((sent_at, packet_id));
+        }
+
+        while let Some((sent_at, packet_id)) =
+            self.timed_out_locally.iter().next().cloned()
+        {
+            if time < sent_at + self.ack_timeout + self.retention {
+                break;
+            }
+            self.timed_out_locally.remove(&(sent_at, packet_id.clone()));
+            self.packets.remove(&packet_id);
+        }
+    }
+
+    /// Stop tracking a packet
+    fn forget(&mut self, packet_id: &PacketId) {
+        if let Some(packet) = self.packets.remove(packet_id) {
+            let key = (packet.sent_at, packet_id.clone());
+            self.awaiting_ack.remove(&key);
+            self.timed_out_locally.remove(&key);
+        }
+    }
+
+    /// Get a tracked packet
+    pub fn get(&self, packet_id: &PacketId) -> Option<&TrackedPacket> {
+        self.packets.get(packet_id)
+    }
+
+    /// Iterate the packets marked as timed-out locally
+    pub fn timed_out(
+        &self,
+    ) -> impl Iterator<Item = (&PacketId, &TrackedPacket)> {
+        self.timed_out_locally
+            .iter()
+            .filter_map(|(_, packet_id)| self.packets.get_key_value(packet_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::ledger::events::EventLevel;
+    use crate::types::time::Duration;
+
+    /// Return an IBC event of the given type for the packet
+    fn packet_event(event_type: &str, packet_id: &PacketId) -> Event {
+        let mut event = Event {
+            event_type: EventType::Ibc(event_type.to_string()),
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        event["packet_src_port"] = packet_id.port_id.clone();
+        event["packet_src_channel"] = packet_id.channel_id.clone();
+        event["packet_sequence"] = packet_id.sequence.to_string();
+        event
+    }
+
+    fn packet_id(sequence: u64) -> PacketId {
+        PacketId {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            sequence,
+        }
+    }
+
+    /// Test that a packet is marked as timed-out locally once the bound has
+    /// elapsed, while it's still forgotten when it's acknowledged or
+    /// timed-out on-chain, which remain authoritative, and that it's
+    /// forgotten after the retention.
+    #[test]
+    fn test_ibc_packet_ack_timeout() {
+        let mut tracker =
+            IbcPacketTracker::new(DurationSecs(100), DurationSecs(50));
+        let start = DateTimeUtc::now();
+        let at = |secs: i64| DateTimeUtc(start.0 + Duration::seconds(secs));
+        let (acked, timed_out, settled_early) =
+            (packet_id(1), packet_id(2), packet_id(3));
+
+        tracker.log_block(
+            BlockHeight(1),
+            at(0),
+            &[
+                packet_event(SEND_PACKET_EVENT, &acked),
+                packet_event(SEND_PACKET_EVENT, &timed_out),
+                packet_event(SEND_PACKET_EVENT, &settled_early),
+            ],
+        );
+        // The packet timed-out on-chain before the bound is forgotten
+        tracker.log_block(
+            BlockHeight(2),
+            at(99),
+            &[packet_event("timeout_packet", &settled_early)],
+        );
+        assert_eq!(tracker.get(&settled_early), None);
+        assert_eq!(
+            tracker.get(&acked).map(|packet| packet.status),
+            Some(PacketStatus::AwaitingAck)
+        );
+        assert_eq!(tracker.timed_out().count(), 0);
+
+        // The packets are timed-out locally from the exact end of the bound
+        tracker.log_block(BlockHeight(3), at(100), &[]);
+        let timed_out_ids: Vec<_> =
+            tracker.timed_out().map(|(id, _)| id.clone()).collect();
+        assert_eq!(timed_out_ids, vec![acked.clone(), timed_out.clone()]);
+        assert_eq!(
+            tracker.get(&timed_out),
+            Some(&TrackedPacket {
+                height: BlockHeight(1),
+                sent_at: at(0),
+                status: PacketStatus::TimedOutLocally,
+            })
+        );
+
+        // A packet timed-out locally may still be acknowledged on-chain
+        tracker.log_block(
+            BlockHeight(4),
+            at(101),
+            &[packet_event("acknowledge_packet", &acked)],
+        );
+        assert_eq!(tracker.get(&acked), None);
+        let timed_out_ids: Vec<_> =
+            tracker.timed_out().map(|(id, _)| id.clone()).collect();
+        assert_eq!(timed_out_ids, vec![timed_out.clone()]);
+
+        // The packet timed-out locally is forgotten from the exact end of the
+        // retention
+        tracker.log_block(BlockHeight(5), at(149), &[]);
+        assert!(tracker.get(&timed_out).is_some());
+        tracker.log_block(BlockHeight(6), at(150), &[]);
+        assert_eq!(tracker.get(&timed_out), None);
+        assert_eq!(tracker.timed_out().count(), 0);
+    }
+}
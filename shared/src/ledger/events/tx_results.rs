@@ -0,0 +1,304 @@
+//! The results of the txs, in the same shape whether they come from the
+//! mempool validation or from the application of the txs in a block, and a
+//! log of the results of the txs applied in the last blocks, to be queried by
+//! tx hash.
+//!
+//! The logged results are derived from the events emitted by `FinalizeBlock`
+//! calls and they're retained for a configurable number of blocks.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::ledger::events::{Event, EventType};
+use crate::types::address::Address;
+use crate::types::hash::Hash;
+use crate::types::storage::{BlockHeight, Key};
+use crate::types::transaction::TxResult as AppliedTx;
+
+/// The default number of blocks for which the tx results are retained
+pub const DEFAULT_TX_RESULTS_RETENTION_BLOCKS: u64 = 1000;
+
+/// Where the result of a tx comes from
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+pub enum TxResultOrigin {
+    /// The mempool validation (`CheckTx`) of the tx. The tx is only
+    /// validated, not executed, and nothing is committed, so the result
+    /// carries no storage changes, verifiers or events.
+    CheckTx,
+    /// The application of the tx in the block at the given height. The
+    /// events of the result were committed with the block.
+    Block(BlockHeight),
+}
+
+/// The result of a tx
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TxResult {
+    /// Where the result comes from
+    pub origin: TxResultOrigin,
+    /// The result code of the tx, which is `0` when the tx was accepted
+    pub code: u32,
+    /// The gas used by the tx
+    pub gas_used: u64,
+    /// The details of the result, e.g. the reason why the tx was rejected
+    pub info: String,
+    /// The storage keys written by the tx, which are only committed if it
+    /// was accepted
+    pub changed_keys: BTreeSet<Key>,
+    /// The verifiers whose VPs were triggered by the tx
+    pub verifiers: BTreeSet<Address>,
+    /// The verifiers whose VPs rejected the tx
+    pub rejected_vps: BTreeSet<Address>,
+    /// The events emitted by the VPs of the tx
+    pub events: Vec<Event>,
+}
+
+impl TxResult {
+    /// Return the result of the mempool validation of a tx, with the given
+    /// code and details
+    pub fn check_tx(code: u32, info: impl Into<String>) -> Self {
+        Self {
+            origin: TxResultOrigin::CheckTx,
+            code,
+            gas_used: 0,
+            info: info.into(),
+            changed_keys: BTreeSet::new(),
+            verifiers: BTreeSet::new(),
+            rejected_vps: BTreeSet::new(),
+            events: vec![],
+        }
+    }
+
+    /// Check if the tx was accepted
+    pub fn is_accepted(&self) -> bool {
+        self.code == 0
+    }
+
+    /// Check if the result, along with its events, was committed in a block
+    pub fn is_committed(&self) -> bool {
+        matches!(self.origin, TxResultOrigin::Block(_))
+    }
+}
+
+/// The storage changes and the verifiers of a tx applied in a block, which
+/// aren't carried by its events
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxChanges {
+    /// The storage keys written by the tx
+    pub changed_keys: BTreeSet<Key>,
+    /// The verifiers whose VPs were triggered by the tx
+    pub verifiers: BTreeSet<Address>,
+    /// The verifiers whose VPs rejected the tx
+    pub rejected_vps: BTreeSet<Address>,
+}
+
+impl From<&AppliedTx> for TxChanges {
+    fn from(tx: &AppliedTx) -> Self {
+        let vps = &tx.vps_result;
+        Self {
+            changed_keys: tx.changed_keys.clone(),
+            verifiers: vps
+                .accepted_vps
+                .union(&vps.rejected_vps)
+                .cloned()
+                .collect(),
+            rejected_vps: vps.rejected_vps.clone(),
+        }
+    }
+}
+
+/// A log of the results of the txs applied in the last blocks
+#[derive(Debug)]
+pub struct TxResultLog {
+    /// The number of blocks for which the results are retained
+    retention_blocks: u64,
+    /// The results by the hash of their tx
+    results: HashMap<Hash, TxResult>,
+    /// The hashes of the txs applied in each retained block
+    hashes_by_height: BTreeMap<BlockHeight, Vec<Hash>>,
+}
+
+impl Default for TxResultLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_TX_RESULTS_RETENTION_BLOCKS)
+    }
+}
+
+impl TxResultLog {
+    /// Return a new log retaining the results for the given number of blocks
+    pub fn new(retention_blocks: u64) -> Self {
+        Self {
+            retention_blocks,
+            results: HashMap::new(),
+            hashes_by_height: BTreeMap::new(),
+        }
+    }
+
+    /// Log the results of the txs applied in the block at the given height,
+    /// from the events emitted by the block, and forget the results of the
+    /// blocks past the retention period. The storage changes and the
+    /// verifiers of a tx are given by the `hash` attribute of its event.
+    pub fn log_block(
+        &mut self,
+        height: BlockHeight,
+        events: &[Event],
+        mut changes: HashMap<String, TxChanges>,
+    ) {
+        let mut hashes = vec![];
+        for event in events {
+            if !matches!(
+                event.event_type,
+                EventType::Accepted | EventType::Applied
+            ) {
+                continue;
+            }
+            let tx_hash = match event.get("hash") {
+                Some(tx_hash) => tx_hash,
+                None => continue,
+            };
+            let hash = match Hash::try_from(tx_hash.as_str()) {
+                Ok(hash) => hash,
+                Err(err) => {
+                    tracing::error!(
+                        "Failed to parse the hash {} of a tx event: {}",
+                        tx_hash,
+                        err
+                    );
+                    continue;
+                }
+            };
+            let vp_events = events
+                .iter()
+                .filter(|vp_event| {
+                    matches!(vp_event.event_type, EventType::Vp(_))
+                        && vp_event.get("hash") == Some(tx_hash)
+                })
+                .cloned()
+                .collect();
+            let TxChanges {
+                changed_keys,
+                verifiers,
+                rejected_vps,
+            } = changes.remove(tx_hash).unwrap_or_default();
+            let result = TxResult {
+                origin: TxResultOrigin::Block(height),
+                code: event
+                    .get("code")
+                    .and_then(|code| code.parse().ok())
+                    .unwrap_or_default(),
+                gas_used: event
+                    .get("gas_used")
+                    .and_then(|gas_used| gas_used.parse().ok())
+                    .unwrap_or_default(),
+                info: event.get("info").cloned().unwrap_or_default(),
+                changed_keys,
+                verifiers,
+                rejected_vps,
+                events: vp_events,
+            };
+            self.results.insert(hash.clone(), result);
+            hashes.push(hash);
+        }
+        if !hashes.is_empty() {
+            self.hashes_by_height.insert(height, hashes);
+        }
+        self.prune(height);
+    }
+
+    /// Get the result of the tx with the given hash, if it was applied in one
+    /// of the retained blocks
+    pub fn get(&self, tx_hash: &Hash) -> Option<&TxResult> {
+        self.results.get(tx_hash)
+    }
+
+    /// Forget the results of the blocks past the retention period, given the
+    /// height of the last block
+    fn prune(&mut self, last_height: BlockHeight) {
+        let oldest_retained =
+            BlockHeight(last_height.0.saturating_sub(self.retention_blocks));
+        let expired: Vec<BlockHeight> = self
+            .hashes_by_height
+            .range(..=oldest_retained)
+            .map(|(height, _)| *height)
+            .collect();
+        for height in expired {
+            let hashes =
+                self.hashes_by_height.remove(&height).unwrap_or_default();
+            for hash in hashes {
+                // The same tx may have been applied again in a later block
+                if matches!(
+                    self.results.get(&hash),
+                    Some(result)
+                        if result.origin == TxResultOrigin::Block(height)
+                ) {
+                    self.results.remove(&hash);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::events::EventLevel;
+
+    /// Return the events of an applied tx with the given hash and code
+    fn tx_events(hash: &Hash, code: u32) -> Vec<Event> {
+        let mut tx_event = Event {
+            event_type: EventType::Applied,
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        tx_event["hash"] = hash.to_string();
+        tx_event["code"] = code.to_string();
+        tx_event["gas_used"] = "10".into();
+        let mut vp_event = Event {
+            event_type: EventType::Vp("transfer".into()),
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        vp_event["hash"] = hash.to_string();
+        vec![tx_event, vp_event]
+    }
+
+    /// Test that the results are logged with the events of their VPs and
+    /// that they're forgotten past the retention period.
+    #[test]
+    fn test_tx_results_retention() {
+        let mut log = TxResultLog::new(2);
+        let hash = Hash([1; 32]);
+        let rejected = BTreeSet::from([
+            crate::types::address::testing::established_address_1(),
+        ]);
+        let changes = TxChanges {
+            changed_keys: BTreeSet::from([Key::parse("key").unwrap()]),
+            verifiers: rejected.clone(),
+            rejected_vps: rejected.clone(),
+        };
+        log.log_block(
+            BlockHeight(1),
+            &tx_events(&hash, 1),
+            HashMap::from([(hash.to_string(), changes.clone())]),
+        );
+
+        let result = log.get(&hash).expect("The result should be logged");
+        assert_eq!(result.origin, TxResultOrigin::Block(BlockHeight(1)));
+        assert!(result.is_committed());
+        assert!(!result.is_accepted());
+        assert_eq!(result.gas_used, 10);
+        assert_eq!(result.changed_keys, changes.changed_keys);
+        assert_eq!(result.verifiers, rejected);
+        assert_eq!(result.rejected_vps, rejected);
+        assert_eq!(result.events.len(), 1);
+        assert!(log.get(&Hash([2; 32])).is_none());
+
+        // The result is retained until the retention period is over
+        log.log_block(BlockHeight(2), &[], HashMap::new());
+        assert!(log.get(&hash).is_some());
+        log.log_block(BlockHeight(3), &[], HashMap::new());
+        assert!(log.get(&hash).is_none());
+    }
+}
@@ -3,8 +3,8 @@ use std::convert::TryInto;
 use std::str::FromStr;
 
 use namada_core::ledger::ibc::actions::{
-    make_create_client_event, make_update_client_event,
-    make_upgrade_client_event,
+    check_client_status, make_create_client_event, make_update_client_event,
+    make_upgrade_client_event, ClientStatusError,
 };
 use thiserror::Error;
 
@@ -61,6 +61,8 @@ pub enum Error {
     InvalidIbcData(IbcDataError),
     #[error("IBC event error: {0}")]
     IbcEvent(String),
+    #[error("Client status error: {0}")]
+    ClientStatus(ClientStatusError),
 }
 
 /// IBC client functions result
@@ -284,6 +286,26 @@ where
             })?;
         // check the prior states
         let prev_client_state = self.client_state_pre(client_id)?;
+        // the client must be active to be updated
+        let prev_height = prev_client_state.latest_height();
+        let prev_consensus_state =
+            ClientReader::maybe_consensus_state(self, client_id, prev_height)
+                .ok()
+                .flatten()
+                .ok_or_else(|| {
+                    Error::InvalidClient(format!(
+                        "The prior consensus state doesn't exist: ID {}, \
+                         Height {}",
+                        client_id, prev_height
+                    ))
+                })?;
+        check_client_status(
+            client_id,
+            &prev_client_state,
+            &prev_consensus_state,
+            ChannelReader::host_timestamp(self),
+        )
+        .map_err(Error::ClientStatus)?;
 
         let client = AnyClient::from_client_type(client_state.client_type());
         let (new_client_state, new_consensus_state) = client
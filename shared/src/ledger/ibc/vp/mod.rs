@@ -317,6 +317,27 @@ mod tests {
     use std::convert::TryFrom;
     use std::str::FromStr;
 
+    use namada_core::ledger::ibc::actions::{
+        self, commitment_prefix, init_connection, make_create_client_event,
+        make_open_ack_channel_event, make_open_ack_connection_event,
+        make_open_confirm_channel_event, make_open_confirm_connection_event,
+        make_open_init_channel_event, make_open_init_connection_event,
+        make_open_try_channel_event, make_open_try_connection_event,
+        make_send_packet_event, make_update_client_event, packet_from_message,
+        try_connection,
+    };
+    use namada_core::ledger::parameters;
+    use prost::Message;
+
+    use super::super::storage::{
+        ack_key, capability_key, channel_key, client_state_key,
+        client_type_key, client_update_height_key, client_update_timestamp_key,
+        commitment_key, connection_key, consensus_state_key,
+        next_sequence_ack_key, next_sequence_recv_key, next_sequence_send_key,
+        port_key, receipt_key,
+    };
+    use super::get_dummy_header;
+    use super::*;
     use crate::ibc::applications::ics20_fungible_token_transfer::msgs::transfer::MsgTransfer;
     use crate::ibc::core::ics02_client::client_consensus::ConsensusState;
     use crate::ibc::core::ics02_client::client_state::ClientState;
@@ -355,37 +376,18 @@ mod tests {
     use crate::ibc::tx_msg::Msg;
     use crate::ibc::Height;
     use crate::ibc_proto::cosmos::base::v1beta1::Coin;
-    use prost::Message;
-    use crate::tendermint::time::Time as TmTime;
-    use crate::tendermint_proto::Protobuf;
-
-    use super::get_dummy_header;
-    use namada_core::ledger::ibc::actions::{
-        self, commitment_prefix, init_connection, make_create_client_event,
-        make_open_ack_channel_event, make_open_ack_connection_event,
-        make_open_confirm_channel_event, make_open_confirm_connection_event,
-        make_open_init_channel_event, make_open_init_connection_event,
-        make_open_try_channel_event, make_open_try_connection_event,
-        make_send_packet_event, make_update_client_event, packet_from_message,
-        try_connection,
-    };
-    use super::super::storage::{
-        ack_key, capability_key, channel_key, client_state_key,
-        client_type_key, client_update_height_key, client_update_timestamp_key,
-        commitment_key, connection_key, consensus_state_key,
-        next_sequence_ack_key, next_sequence_recv_key, next_sequence_send_key,
-        port_key, receipt_key,
-    };
-    use super::*;
-    use crate::types::key::testing::keypair_1;
     use crate::ledger::gas::VpGasMeter;
     use crate::ledger::storage::testing::TestStorage;
     use crate::ledger::storage::write_log::WriteLog;
     use crate::proto::Tx;
+    use crate::tendermint::time::Time as TmTime;
+    use crate::tendermint_proto::Protobuf;
+    use crate::types::chain::ChainId;
     use crate::types::ibc::data::{PacketAck, PacketReceipt};
+    use crate::types::ibc::PacketDataError;
+    use crate::types::key::testing::keypair_1;
+    use crate::types::storage::{BlockHash, BlockHeight, TxIndex};
     use crate::vm::wasm;
-    use crate::types::storage::TxIndex;
-    use crate::types::storage::{BlockHash, BlockHeight};
 
     const ADDRESS: Address = Address::Internal(InternalAddress::Ibc);
 
@@ -569,7 +571,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -609,7 +612,8 @@ mod tests {
         let tx_index = TxIndex::default();
         let tx_code = vec![];
         let tx_data = vec![];
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -690,7 +694,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -749,7 +754,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -805,7 +811,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -887,7 +894,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -975,7 +983,8 @@ mod tests {
         let tx_index = TxIndex::default();
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1050,7 +1059,8 @@ mod tests {
         let tx_index = TxIndex::default();
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1111,7 +1121,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1191,7 +1202,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1279,7 +1291,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1364,7 +1377,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1405,7 +1419,8 @@ mod tests {
         let tx_index = TxIndex::default();
         let tx_code = vec![];
         let tx_data = vec![];
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1447,7 +1462,8 @@ mod tests {
         let tx_index = TxIndex::default();
         let tx_code = vec![];
         let tx_data = vec![];
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1531,7 +1547,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1620,7 +1637,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1714,7 +1732,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1800,7 +1819,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1831,6 +1851,112 @@ mod tests {
         );
     }
 
+    /// Test that a sent packet with data of exactly the maximum size is
+    /// accepted and that it's rejected when the maximum is one byte lower.
+    #[test]
+    fn test_validate_commitment_packet_data_size() {
+        let (mut storage, mut write_log) = insert_init_states();
+        // insert an opened connection
+        let conn_key = connection_key(&get_connection_id());
+        let conn = get_connection(ConnState::Open);
+        let bytes = conn.encode_vec().expect("encoding failed");
+        write_log.write(&conn_key, bytes).expect("write failed");
+        // insert an opened channel
+        set_port(&mut write_log, 0);
+        let channel_key = channel_key(&get_port_channel_id());
+        let channel = get_channel(ChanState::Open, Order::Ordered);
+        let bytes = channel.encode_vec().expect("encoding failed");
+        write_log.write(&channel_key, bytes).expect("write failed");
+        write_log.commit_tx();
+        write_log.commit_block(&mut storage).expect("commit failed");
+
+        // prepare a message
+        let timeout_timestamp =
+            (Timestamp::now() + Duration::from_secs(100)).unwrap();
+        let msg = MsgTransfer {
+            source_port: get_port_id(),
+            source_channel: get_channel_id(),
+            token: Some(Coin {
+                denom: "NAM".to_string(),
+                amount: 100u64.to_string(),
+            }),
+            sender: Signer::new("sender"),
+            receiver: Signer::new("receiver"),
+            timeout_height: Height::new(0, 100),
+            timeout_timestamp,
+        };
+
+        // make a packet
+        let seq_key = next_sequence_send_key(&get_port_channel_id());
+        let sequence = get_next_seq(&storage, &seq_key);
+        let counterparty = get_channel_counterparty();
+        let packet = packet_from_message(&msg, sequence, &counterparty);
+        let data_len = packet.data.len() as u64;
+        // insert a commitment
+        let commitment = actions::commitment(&packet);
+        let commitment_key = commitment_key(
+            &packet.source_port,
+            &packet.source_channel,
+            sequence,
+        );
+        write_log
+            .write(&commitment_key, commitment.into_vec())
+            .expect("write failed");
+        let event = make_send_packet_event(packet);
+        write_log.set_ibc_event(event.try_into().unwrap());
+
+        let tx_index = TxIndex::default();
+        let tx_code = vec![];
+        let mut tx_data = vec![];
+        msg.to_any().encode(&mut tx_data).expect("encoding failed");
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
+
+        let mut keys_changed = BTreeSet::new();
+        keys_changed.insert(commitment_key);
+        let verifiers = BTreeSet::new();
+
+        for (max_bytes, is_valid) in [(data_len, true), (data_len - 1, false)]
+        {
+            parameters::update_max_ibc_packet_data_bytes_parameter(
+                &mut storage,
+                &max_bytes,
+            )
+            .expect("write failed");
+
+            let gas_meter = VpGasMeter::new(0);
+            let (vp_wasm_cache, _vp_cache_dir) =
+                wasm::compilation_cache::common::testing::cache();
+            let ctx = Ctx::new(
+                &ADDRESS,
+                &storage,
+                &write_log,
+                &tx,
+                &tx_index,
+                gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_wasm_cache,
+            );
+            let ibc = Ibc { ctx };
+            let result = ibc.validate_tx(
+                tx.data.as_ref().unwrap(),
+                &keys_changed,
+                &verifiers,
+            );
+            if is_valid {
+                assert!(result.expect("validation failed"));
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(Error::PacketError(packet::Error::PacketData(
+                        PacketDataError::TooLarge { size, max }
+                    ))) if size == data_len && max == max_bytes
+                ));
+            }
+        }
+    }
+
     #[test]
     fn test_validate_receipt() {
         let (mut storage, mut write_log) = insert_init_states();
@@ -1894,7 +2020,8 @@ mod tests {
         let tx_code = vec![];
         let mut tx_data = vec![];
         msg.to_any().encode(&mut tx_data).expect("encoding failed");
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1944,7 +2071,8 @@ mod tests {
         let tx_index = TxIndex::default();
         let tx_code = vec![];
         let tx_data = vec![];
-        let tx = Tx::new(tx_code, Some(tx_data)).sign(&keypair_1());
+        let tx = Tx::new(tx_code, Some(tx_data))
+            .sign(&keypair_1(), &ChainId::default());
         let gas_meter = VpGasMeter::new(0);
         let (vp_wasm_cache, _vp_cache_dir) =
             wasm::compilation_cache::common::testing::cache();
@@ -1,7 +1,8 @@
 //! IBC validity predicate for packets
 
 use namada_core::ledger::ibc::actions::{
-    self, make_send_packet_event, make_timeout_event, packet_from_message,
+    self, check_client_status, make_send_packet_event, make_timeout_event,
+    packet_from_message, ClientStatusError,
 };
 use namada_core::ledger::ibc::data::{
     Error as IbcDataError, FungibleTokenPacketData, IbcMessage,
@@ -10,6 +11,10 @@ use namada_core::ledger::ibc::storage::{
     ibc_denom_key, port_channel_sequence_id, token_hash_from_denom,
     Error as IbcStorageError,
 };
+use namada_core::ledger::parameters::{self, ReadError};
+use namada_core::types::ibc::{
+    check_packet_data_size, PacketDataError, DEFAULT_MAX_PACKET_DATA_BYTES,
+};
 use thiserror::Error;
 
 use super::{Ibc, StateChange};
@@ -70,6 +75,10 @@ pub enum Error {
     Proof(String),
     #[error("IBC denom error: {0}")]
     Denom(String),
+    #[error("Packet data error: {0}")]
+    PacketData(PacketDataError),
+    #[error("Client status error: {0}")]
+    ClientStatus(ClientStatusError),
 }
 
 /// IBC packet functions result
@@ -237,6 +246,7 @@ where
         packet: &Packet,
     ) -> Result<()> {
         self.validate_packet(port_channel_seq_id, packet, Phase::Send)?;
+        self.validate_packet_data_size(packet)?;
 
         self.get_packet_commitment(port_channel_seq_id)
             .map_err(|_| {
@@ -258,6 +268,7 @@ where
         msg: &MsgRecvPacket,
     ) -> Result<()> {
         self.validate_packet(port_channel_seq_id, &msg.packet, Phase::Recv)?;
+        self.validate_packet_data_size(&msg.packet)?;
 
         self.get_packet_receipt(port_channel_seq_id).map_err(|_| {
             Error::InvalidPacket(format!(
@@ -320,6 +331,29 @@ where
         )
     }
 
+    fn validate_packet_data_size(&self, packet: &Packet) -> Result<()> {
+        let max_bytes =
+            match parameters::read_max_ibc_packet_data_bytes_parameter(
+                self.ctx.storage,
+            ) {
+                Ok((max_bytes, gas)) => {
+                    self.ctx
+                        .gas_meter
+                        .borrow_mut()
+                        .add(gas)
+                        .map_err(|e| Error::InvalidPacket(e.to_string()))?;
+                    max_bytes
+                }
+                // The parameter is only missing before it's initialized
+                Err(ReadError::ParametersMissing) => {
+                    DEFAULT_MAX_PACKET_DATA_BYTES
+                }
+                Err(e) => return Err(Error::InvalidPacket(e.to_string())),
+            };
+        check_packet_data_size(&packet.data, max_bytes)
+            .map_err(Error::PacketData)
+    }
+
     fn validate_packet(
         &self,
         port_channel_seq_id: &(PortId, ChannelId, Sequence),
@@ -394,6 +428,31 @@ where
             ));
         }
 
+        // the client of the connection must be active
+        let client_id = connection.client_id();
+        let client_state = self.client_state(client_id).map_err(|_| {
+            Error::InvalidClient(format!(
+                "The client state doesn't exist: ID {}",
+                client_id
+            ))
+        })?;
+        let height = client_state.latest_height();
+        let consensus_state = self
+            .client_consensus_state(client_id, height)
+            .map_err(|_| {
+                Error::InvalidClient(format!(
+                    "The consensus state doesn't exist: ID {}, Height {}",
+                    client_id, height
+                ))
+            })?;
+        check_client_status(
+            client_id,
+            &client_state,
+            &consensus_state,
+            self.host_timestamp(),
+        )
+        .map_err(Error::ClientStatus)?;
+
         // counterparty consistency
         let counterparty = match phase {
             Phase::Send | Phase::Ack => Counterparty::new(
@@ -414,16 +473,6 @@ where
         // check timeout
         match phase {
             Phase::Send => {
-                let client_id = connection.client_id();
-                let height = match self.client_state(client_id) {
-                    Ok(s) => s.latest_height(),
-                    Err(_) => {
-                        return Err(Error::InvalidClient(format!(
-                            "The client state doesn't exist: ID {}",
-                            client_id
-                        )));
-                    }
-                };
                 self.check_timeout(client_id, height, packet)
                     .map_err(|e| Error::InvalidPacket(e.to_string()))?;
             }
@@ -0,0 +1,217 @@
+//! The inflation of the native token, minted at the start of every epoch and
+//! paid to the bonds as staking rewards.
+//!
+//! The amount minted for an epoch is set by a PD controller, which steers the
+//! ratio of the staked tokens to the total supply towards the targeted ratio.
+//! The amount is bounded by the minimum and maximum inflation rates per annum,
+//! applied to the total supply for a single epoch, and it's capped so that the
+//! total supply never overflows.
+
+use rust_decimal::Decimal;
+
+use crate::types::token;
+
+/// The inputs of the inflation controller for an epoch
+#[derive(Clone, Debug)]
+pub struct InflationController {
+    /// The total supply of the native token
+    pub total_supply: token::Amount,
+    /// The amount of the native token bonded
+    pub staked: token::Amount,
+    /// The staked ratio of the last epoch
+    pub last_staked_ratio: Decimal,
+    /// The amount minted in the last epoch
+    pub last_inflation: token::Amount,
+    /// The targeted ratio of the staked tokens to the total supply
+    pub target_staked_ratio: Decimal,
+    /// The minimum inflation rate per annum
+    pub min_inflation_rate: Decimal,
+    /// The maximum inflation rate per annum
+    pub max_inflation_rate: Decimal,
+    /// The P gain factor of the controller
+    pub p_gain: Decimal,
+    /// The D gain factor of the controller
+    pub d_gain: Decimal,
+    /// The expected number of epochs per year
+    pub epochs_per_year: u64,
+}
+
+/// The inflation of an epoch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochInflation {
+    /// The amount to mint
+    pub amount: token::Amount,
+    /// The staked ratio the amount was derived from, to be used as the last
+    /// staked ratio in the next epoch
+    pub staked_ratio: Decimal,
+}
+
+impl InflationController {
+    /// Get the amount to mint for the epoch
+    pub fn run(&self) -> EpochInflation {
+        let staked_ratio = if self.total_supply == token::Amount::default() {
+            Decimal::ZERO
+        } else {
+            (Decimal::from(self.staked) / Decimal::from(self.total_supply))
+                .min(Decimal::ONE)
+        };
+
+        // The supply must not overflow, so the amount is capped by what's left
+        // to the maximum amount
+        let mut headroom = token::Amount::max();
+        headroom.spend(&self.total_supply);
+        let epochs_per_year = Decimal::from(self.epochs_per_year.max(1));
+        let bound = |rate: Decimal| {
+            self.total_supply
+                .mul_floor(rate / epochs_per_year)
+                .map_or(headroom, |amount| amount.min(headroom))
+        };
+        let max_inflation = bound(self.max_inflation_rate);
+        let min_inflation = bound(self.min_inflation_rate).min(max_inflation);
+
+        let max_inflation = Decimal::from(max_inflation);
+        let error = self.target_staked_ratio - staked_ratio;
+        let delta_error = self.last_staked_ratio - staked_ratio;
+        let control = self.p_gain * max_inflation * error
+            + self.d_gain * max_inflation * delta_error;
+        let amount = (Decimal::from(self.last_inflation) + control)
+            .max(Decimal::from(min_inflation))
+            .min(max_inflation);
+
+        EpochInflation {
+            amount: token::Amount::from(amount),
+            staked_ratio,
+        }
+    }
+}
+
+/// Split the inflation of an epoch between the bonds, proportionally to their
+/// amounts. The shares are rounded down, so the rounding remainder of the
+/// split isn't minted.
+pub fn distribute<K>(
+    inflation: token::Amount,
+    bonds: Vec<(K, token::Amount)>,
+) -> Vec<(K, token::Amount)> {
+    let total_bonded = bonds
+        .iter()
+        .fold(Decimal::ZERO, |acc, (_, bond)| acc + Decimal::from(*bond));
+    if total_bonded.is_zero() {
+        return vec![];
+    }
+    let mut remaining = inflation;
+    bonds
+        .into_iter()
+        .map(|(owner, bond)| {
+            let rate = Decimal::from(bond) / total_bonded;
+            let share = inflation.mul_floor(rate).unwrap_or_default();
+            // The rate may be rounded up by the division
+            let share = share.min(remaining);
+            remaining.spend(&share);
+            (owner, share)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> InflationController {
+        InflationController {
+            total_supply: token::Amount::whole(1_000_000),
+            staked: token::Amount::whole(500_000),
+            last_staked_ratio: Decimal::new(5, 1),
+            last_inflation: token::Amount::default(),
+            target_staked_ratio: Decimal::new(5, 1),
+            min_inflation_rate: Decimal::new(5, 2),
+            max_inflation_rate: Decimal::new(1, 1),
+            p_gain: Decimal::new(1, 1),
+            d_gain: Decimal::new(1, 1),
+            epochs_per_year: 100,
+        }
+    }
+
+    /// Test that the inflation is bounded by the minimum and maximum rates.
+    #[test]
+    fn test_inflation_bounds() {
+        // On target, the inflation stays at the minimum
+        let inflation = controller().run();
+        assert_eq!(inflation.amount, token::Amount::whole(500));
+        assert_eq!(inflation.staked_ratio, Decimal::new(5, 1));
+
+        // Far below target, the inflation is capped at the maximum
+        let inflation = InflationController {
+            staked: token::Amount::default(),
+            last_inflation: token::Amount::whole(900),
+            p_gain: Decimal::ONE,
+            ..controller()
+        }
+        .run();
+        assert_eq!(inflation.amount, token::Amount::whole(1_000));
+        assert_eq!(inflation.staked_ratio, Decimal::ZERO);
+
+        // Below target, the inflation rises by the controller's output
+        let inflation = InflationController {
+            staked: token::Amount::whole(400_000),
+            last_staked_ratio: Decimal::new(4, 1),
+            last_inflation: token::Amount::whole(600),
+            ..controller()
+        }
+        .run();
+        // 600 + 0.1 * 1000 * (0.5 - 0.4)
+        assert_eq!(inflation.amount, token::Amount::whole(610));
+    }
+
+    /// Test that the inflation is capped, rather than overflowing, when the
+    /// total supply nears the maximum amount.
+    #[test]
+    fn test_inflation_capped_by_max_amount() {
+        let mut total_supply = token::Amount::max();
+        total_supply.spend(&token::Amount::from(10));
+        let inflation = InflationController {
+            total_supply,
+            staked: total_supply,
+            epochs_per_year: 1,
+            ..controller()
+        }
+        .run();
+        assert_eq!(inflation.amount, token::Amount::from(10));
+    }
+
+    /// Test that the inflation is split by the amounts of the bonds, without
+    /// minting more than the inflation.
+    #[test]
+    fn test_distribute_inflation() {
+        let shares = distribute(
+            token::Amount::from(100),
+            vec![
+                ("a", token::Amount::from(1)),
+                ("b", token::Amount::from(3)),
+                ("c", token::Amount::default()),
+            ],
+        );
+        assert_eq!(
+            shares,
+            vec![
+                ("a", token::Amount::from(25)),
+                ("b", token::Amount::from(75)),
+                ("c", token::Amount::default()),
+            ]
+        );
+
+        let shares = distribute(
+            token::Amount::from(10),
+            vec![("a", token::Amount::from(1)), ("b", token::Amount::from(2))],
+        );
+        assert_eq!(
+            shares,
+            vec![("a", token::Amount::from(3)), ("b", token::Amount::from(6))]
+        );
+        // Nothing is minted without any bonded tokens
+        let shares = distribute(
+            token::Amount::from(10),
+            vec![("a", token::Amount::default())],
+        );
+        assert!(shares.is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 //! The ledger modules
 
+pub mod consensus_params;
 pub mod eth_bridge;
 pub mod events;
 pub mod ibc;
+pub mod inflation;
 pub mod masp;
 pub mod native_vp;
 pub mod pos;
@@ -10,8 +12,9 @@ pub mod pos;
 pub mod protocol;
 pub mod queries;
 pub mod storage;
+pub mod token_supply;
 pub mod vp_host_fns;
 
 pub use namada_core::ledger::{
-    gas, governance, parameters, storage_api, tx_env, vp_env,
+    gas, governance, parameters, storage_api, testnet_faucet, tx_env, vp_env,
 };
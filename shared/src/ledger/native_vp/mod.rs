@@ -469,19 +469,17 @@ where
     ) -> Result<bool, storage_api::Error> {
         #[cfg(feature = "wasm-runtime")]
         {
-            use std::marker::PhantomData;
-
             use crate::vm::host_env::VpCtx;
             use crate::vm::wasm::run::VpEvalWasm;
 
-            let eval_runner = VpEvalWasm {
-                db: PhantomData,
-                hasher: PhantomData,
-                cache_access: PhantomData,
-            };
+            let eval_runner = VpEvalWasm::default();
             let mut iterators: PrefixIterators<'_, DB> =
                 PrefixIterators::default();
             let mut result_buffer: Option<Vec<u8>> = None;
+            // The events and the rejection of a VP evaluated from a native VP
+            // are not attached to the transaction
+            let mut events = vec![];
+            let mut rejection = None;
             let mut vp_wasm_cache = self.vp_wasm_cache.clone();
 
             let ctx = VpCtx::new(
@@ -494,6 +492,8 @@ where
                 &mut iterators,
                 self.verifiers,
                 &mut result_buffer,
+                &mut events,
+                &mut rejection,
                 self.keys_changed,
                 &eval_runner,
                 &mut vp_wasm_cache,
@@ -526,7 +526,7 @@ where
         pk: &crate::types::key::common::PublicKey,
         sig: &crate::types::key::common::Signature,
     ) -> Result<bool, storage_api::Error> {
-        Ok(self.tx.verify_sig(pk, sig).is_ok())
+        Ok(self.tx.verify_sig(pk, sig, &self.storage.chain_id).is_ok())
     }
 
     fn verify_masp(&self, _tx: Vec<u8>) -> Result<bool, storage_api::Error> {
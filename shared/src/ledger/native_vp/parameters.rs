@@ -2,6 +2,7 @@
 
 use std::collections::BTreeSet;
 
+use namada_core::ledger::parameters::{self, FeeDistribution};
 use namada_core::ledger::storage;
 use namada_core::types::address::{Address, InternalAddress};
 use namada_core::types::storage::Key;
@@ -9,6 +10,7 @@ use thiserror::Error;
 
 use super::governance;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
+use crate::ledger::storage_api::StorageRead;
 use crate::vm::WasmCacheAccess;
 
 #[allow(missing_docs)]
@@ -51,11 +53,14 @@ where
         let result = keys_changed.iter().all(|key| {
             let key_type: KeyType = key.into();
             match key_type {
-                KeyType::PARAMETER => governance::utils::is_proposal_accepted(
-                    self.ctx.storage,
-                    tx_data,
-                )
-                .unwrap_or(false),
+                KeyType::PARAMETER => {
+                    governance::utils::is_proposal_accepted(
+                        self.ctx.storage,
+                        tx_data,
+                    )
+                    .unwrap_or(false)
+                        && self.is_valid_value(key)
+                }
                 KeyType::UNKNOWN_PARAMETER => false,
                 KeyType::UNKNOWN => true,
             }
@@ -64,6 +69,25 @@ where
     }
 }
 
+impl<'a, DB, H, CA> ParametersVp<'a, DB, H, CA>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + storage::StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    /// Check the new value of a parameter whose values are constrained. A fee
+    /// distribution must be valid, so that the fees are distributed entirely.
+    fn is_valid_value(&self, key: &Key) -> bool {
+        if !parameters::storage::is_fee_distribution_key(key) {
+            return true;
+        }
+        match self.ctx.post().read::<FeeDistribution>(key) {
+            Ok(Some(fee_distribution)) => fee_distribution.validate().is_ok(),
+            _ => false,
+        }
+    }
+}
+
 impl From<native_vp::Error> for Error {
     fn from(err: native_vp::Error) -> Self {
         Self::NativeVpError(err)
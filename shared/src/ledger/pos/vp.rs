@@ -16,8 +16,9 @@ use thiserror::Error;
 use super::{
     bond_key, is_bond_key, is_params_key, is_total_deltas_key, is_unbond_key,
     is_validator_deltas_key, is_validator_set_key, params_key,
-    total_deltas_key, unbond_key, validator_commission_rate_key,
-    validator_consensus_key_key, validator_deltas_key,
+    total_deltas_key, unbond_key, validator_address_raw_hash_key,
+    validator_commission_rate_key, validator_consensus_key_key,
+    validator_deltas_key, validator_jailed_epoch_key,
     validator_max_commission_rate_change_key, validator_set_key,
     validator_slashes_key, validator_state_key, BondId, Bonds, CommissionRates,
     TotalDeltas, Unbonds, ValidatorConsensusKeys, ValidatorDeltas,
@@ -111,9 +112,9 @@ where
         keys_changed: &BTreeSet<Key>,
         _verifiers: &BTreeSet<Address>,
     ) -> Result<bool> {
-        use validation::Data;
         use validation::DataUpdate::{self, *};
         use validation::ValidatorUpdate::*;
+        use validation::{Data, Jailing};
 
         let addr = Address::Internal(Self::ADDR);
         let mut changes: Vec<DataUpdate> = vec![];
@@ -142,9 +143,26 @@ where
                 let post = self.ctx.post().read_bytes(key)?.and_then(|bytes| {
                     ValidatorStates::try_from_slice(&bytes[..]).ok()
                 });
+                // For a jailed validator, we need to look-up the epoch in
+                // which it has been jailed and its stake to validate its
+                // unjailing
+                let jailing = match self
+                    .ctx
+                    .pre()
+                    .read_validator_jailed_epoch(validator)?
+                {
+                    Some(epoch) => Some(Jailing {
+                        epoch,
+                        deltas: self
+                            .ctx
+                            .post()
+                            .read_validator_deltas(validator)?,
+                    }),
+                    None => None,
+                };
                 changes.push(Validator {
                     address: validator.clone(),
-                    update: State(Data { pre, post }),
+                    update: State(Data { pre, post }, jailing),
                 });
             } else if let Some(validator) = is_validator_consensus_key_key(key)
             {
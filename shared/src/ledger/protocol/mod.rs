@@ -19,7 +19,10 @@ use crate::proto::{self, Tx};
 use crate::types::address::{Address, InternalAddress};
 use crate::types::storage;
 use crate::types::storage::TxIndex;
-use crate::types::transaction::{DecryptedTx, TxResult, TxType, VpsResult};
+use crate::types::transaction::{
+    DecryptedTx, DryRunOutcome, GasEstimate, TxResult, TxType, TxVerifiers,
+    VpsResult,
+};
 use crate::vm::wasm::{TxCache, VpCache};
 use crate::vm::{self, wasm, WasmCacheAccess};
 
@@ -58,6 +61,11 @@ pub enum Error {
     EthBridgeNativeVpError(crate::ledger::eth_bridge::vp::Error),
     #[error("Access to an internal address {0} is forbidden")]
     AccessForbidden(InternalAddress),
+    #[error(
+        "The transaction triggers {verifiers} verifiers, more than the \
+         maximum of {max}"
+    )]
+    TooManyVerifiers { verifiers: usize, max: usize },
 }
 
 /// Result of applying a transaction
@@ -70,6 +78,9 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// If the given tx is a successfully decrypted payload apply the necessary
 /// vps. Otherwise, we include the tx on chain with the gas charge added
 /// but no further validations.
+///
+/// When `max_verifiers` is set, a transaction that triggers more verifiers is
+/// rejected before any of their validity predicates run.
 #[allow(clippy::too_many_arguments)]
 pub fn apply_tx<D, H, CA>(
     tx: TxType,
@@ -80,6 +91,7 @@ pub fn apply_tx<D, H, CA>(
     storage: &Storage<D, H>,
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
+    max_verifiers: Option<usize>,
 ) -> Result<TxResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -111,6 +123,7 @@ where
                 write_log,
                 &verifiers,
                 vp_wasm_cache,
+                max_verifiers,
             )?;
 
             let gas_used = block_gas_meter
@@ -140,6 +153,88 @@ where
     }
 }
 
+/// Dry run a transaction and its validity predicates to estimate the gas that
+/// it would use. When the transaction would be rejected, the estimate is the
+/// gas used up to its rejection, together with the reason. Nothing is
+/// committed to the storage.
+pub fn estimate_gas<D, H, CA>(
+    tx: Tx,
+    tx_length: usize,
+    storage: &Storage<D, H>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    max_verifiers: Option<usize>,
+) -> GasEstimate
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    let mut gas_meter = BlockGasMeter::default();
+    let mut write_log = WriteLog::default();
+    let result = apply_tx(
+        TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+        tx_length,
+        TxIndex(0),
+        &mut gas_meter,
+        &mut write_log,
+        storage,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        max_verifiers,
+    );
+    match result {
+        Ok(result) if result.is_accepted() => GasEstimate {
+            gas_used: result.gas_used,
+            outcome: DryRunOutcome::Accepted,
+        },
+        Ok(result) => GasEstimate {
+            gas_used: result.gas_used,
+            outcome: DryRunOutcome::Rejected(format!(
+                "Rejected by the validity predicates:{}",
+                result.vps_result
+            )),
+        },
+        Err(err) => GasEstimate {
+            gas_used: gas_meter.get_current_transaction_gas(),
+            outcome: DryRunOutcome::Rejected(err.to_string()),
+        },
+    }
+}
+
+/// Execute a transaction code without checking its validity predicates.
+/// Returns the verifiers that the transaction would trigger and the storage
+/// keys that it would change. Nothing is committed to the storage.
+pub fn tx_verifiers<D, H, CA>(
+    tx: &Tx,
+    storage: &Storage<D, H>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+) -> Result<TxVerifiers>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    let mut gas_meter = BlockGasMeter::default();
+    let mut write_log = WriteLog::default();
+    let verifiers_from_tx = execute_tx(
+        tx,
+        &TxIndex(0),
+        storage,
+        &mut gas_meter,
+        &mut write_log,
+        vp_wasm_cache,
+        tx_wasm_cache,
+    )?;
+    let (verifiers, changed_keys) =
+        write_log.verifiers_and_changed_keys(&verifiers_from_tx);
+    Ok(TxVerifiers {
+        verifiers,
+        changed_keys,
+    })
+}
+
 /// Execute a transaction code. Returns verifiers requested by the transaction.
 fn execute_tx<D, H, CA>(
     tx: &Tx,
@@ -173,7 +268,13 @@ where
     .map_err(Error::TxRunnerError)
 }
 
-/// Check the acceptance of a transaction by validity predicates
+/// Check the acceptance of a transaction by validity predicates.
+///
+/// An address may be implicated in a transaction through several paths, e.g.
+/// when it's inserted as a verifier by the transaction and owns some of the
+/// changed keys, or when it's both the source and the target of a transfer.
+/// The verifiers are collected in a set, so its VP still runs exactly once.
+#[allow(clippy::too_many_arguments)]
 fn check_vps<D, H, CA>(
     tx: &Tx,
     tx_index: &TxIndex,
@@ -182,6 +283,7 @@ fn check_vps<D, H, CA>(
     write_log: &WriteLog,
     verifiers_from_tx: &BTreeSet<Address>,
     vp_wasm_cache: &mut VpCache<CA>,
+    max_verifiers: Option<usize>,
 ) -> Result<VpsResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -190,6 +292,24 @@ where
 {
     let (verifiers, keys_changed) =
         write_log.verifiers_and_changed_keys(verifiers_from_tx);
+    if cfg!(debug_assertions) {
+        let untriggered = untriggered_owners(write_log, &verifiers);
+        assert!(
+            untriggered.is_empty(),
+            "The owners {:?} of keys changed by the transaction are not \
+             verifiers",
+            untriggered
+        );
+    }
+
+    if let Some(max) = max_verifiers {
+        if verifiers.len() > max {
+            return Err(Error::TooManyVerifiers {
+                verifiers: verifiers.len(),
+                max,
+            });
+        }
+    }
 
     let initial_gas = gas_meter.get_current_transaction_gas();
 
@@ -212,7 +332,55 @@ where
     Ok(vps_result)
 }
 
-/// Execute verifiers' validity predicates
+/// Get the owners of the keys written or deleted by the transaction that are
+/// not verifiers, other than the accounts initialized by the transaction.
+/// The keys are read from the write log's modifications, independently of
+/// the changed keys passed to the VPs. Only used by the debug checks.
+fn untriggered_owners(
+    write_log: &WriteLog,
+    verifiers: &BTreeSet<Address>,
+) -> BTreeSet<Address> {
+    let initialized_accounts = write_log.get_initialized_accounts();
+    write_log
+        .tx_modifications()
+        .flat_map(|(key, _)| key.find_addresses())
+        .filter(|owner| {
+            !verifiers.contains(owner) && !initialized_accounts.contains(owner)
+        })
+        .collect()
+}
+
+/// Get the keys written or deleted by the transaction that are owned by the
+/// given verifier, but missing from the changed keys passed to its VP. The
+/// keys are read from the write log's modifications, independently of how
+/// the changed keys were collected. Only used by the debug checks.
+fn missing_keys_changed(
+    verifier: &Address,
+    write_log: &WriteLog,
+    keys_changed: &BTreeSet<storage::Key>,
+) -> BTreeSet<storage::Key> {
+    write_log
+        .tx_modifications()
+        .map(|(key, _modification)| key)
+        .filter(|key| {
+            !keys_changed.contains(*key)
+                && key.find_addresses().contains(verifier)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Execute verifiers' validity predicates.
+///
+/// The verifiers are visited in the canonical order of their addresses, as
+/// given by the ordering of the `BTreeSet`. The VPs may run in parallel, but
+/// they only read the state, so the order in which they are scheduled cannot
+/// affect their results. Each VP produces its own result and the results are
+/// then merged in the canonical order, so that the accepted and rejected VPs,
+/// the gas used, the errors, the events and the rejections (sorted by the
+/// verifier's address) are the same on every node, regardless of the number of
+/// threads. Only the events emitted by the VPs that accept the transaction are
+/// kept.
 #[allow(clippy::too_many_arguments)]
 fn execute_vps<D, H, CA>(
     verifiers: BTreeSet<Address>,
@@ -229,10 +397,33 @@ where
     H: 'static + StorageHasher + Sync,
     CA: 'static + WasmCacheAccess + Sync,
 {
-    verifiers
+    // The VPs run on the threads of the Rayon pool, which don't inherit the
+    // current span, so their spans are nested in it explicitly
+    let parent_span = tracing::Span::current();
+    let mut result = verifiers
         .par_iter()
-        .try_fold(VpsResult::default, |mut result, addr| {
+        .map(|addr| -> Result<VpsResult> {
+            let _span = tracing::debug_span!(
+                parent: &parent_span,
+                "vp",
+                verifier = %addr
+            )
+            .entered();
+            if cfg!(debug_assertions) {
+                let missing =
+                    missing_keys_changed(addr, write_log, &keys_changed);
+                assert!(
+                    missing.is_empty(),
+                    "The keys {:?} changed by the transaction are missing \
+                     from the changed keys passed to the VP of {}",
+                    missing,
+                    addr
+                );
+            }
+            let mut result = VpsResult::default();
             let mut gas_meter = VpGasMeter::new(initial_gas);
+            let mut events = vec![];
+            let mut rejection = None;
             let accept = match &addr {
                 Address::Implicit(_) | Address::Established(_) => {
                     let (vp, gas) = storage
@@ -257,6 +448,8 @@ where
                         &keys_changed,
                         &verifiers,
                         vp_wasm_cache.clone(),
+                        &mut events,
+                        &mut rejection,
                     )
                     .map_err(Error::VpRunnerError)
                 }
@@ -384,8 +577,16 @@ where
                 Ok(accepted) => {
                     if !accepted {
                         result.rejected_vps.insert(addr.clone());
+                        result.rejections.extend(
+                            rejection
+                                .map(|rejection| (addr.clone(), rejection)),
+                        );
                     } else {
                         result.accepted_vps.insert(addr.clone());
+                        result.events = events
+                            .into_iter()
+                            .map(|event| (addr.clone(), event))
+                            .collect();
                     }
                     Ok(result)
                 }
@@ -401,7 +602,12 @@ where
         })
         .try_reduce(VpsResult::default, |a, b| {
             merge_vp_results(a, b, initial_gas)
-        })
+        })?;
+    result.errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+    // The sort is stable, so a VP's events keep the order of their emission
+    result.events.sort_by(|(a, _), (b, _)| a.cmp(b));
+    result.rejections.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(result)
 }
 
 /// Merge VP results from parallel runs
@@ -416,6 +622,10 @@ fn merge_vp_results(
     rejected_vps.extend(b.rejected_vps);
     let mut errors = a.errors;
     errors.append(&mut b.errors);
+    let mut events = a.events;
+    events.append(&mut b.events);
+    let mut rejections = a.rejections;
+    rejections.append(&mut b.rejections);
     let mut gas_used = a.gas_used;
 
     // Returning error from here will short-circuit the VP parallel execution.
@@ -431,5 +641,262 @@ fn merge_vp_results(
         rejected_vps,
         gas_used,
         errors,
+        events,
+        rejections,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use borsh::BorshSerialize;
+
+    use super::*;
+    use crate::ledger::storage::testing::TestStorage;
+    use crate::ledger::storage::write_log::StorageModification;
+    use crate::types::storage::{Key, KeySeg};
+    use crate::types::{address, token};
+    use crate::vm::wasm::compilation_cache::common::testing::cache;
+
+    const VP_ALWAYS_TRUE_WASM: &str = "../wasm_for_tests/vp_always_true.wasm";
+    const VP_ALWAYS_FALSE_WASM: &str = "../wasm_for_tests/vp_always_false.wasm";
+
+    /// Test that the result of the VPs doesn't depend on the number of
+    /// threads they are scheduled on, so that every node reaches the same
+    /// decision with the same gas cost.
+    #[test]
+    fn test_vps_result_is_deterministic() {
+        let mut storage = TestStorage::default();
+        let vp_always_true =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        let vp_always_false =
+            std::fs::read(VP_ALWAYS_FALSE_WASM).expect("cannot load wasm");
+
+        // Every third verifier rejects the tx and one verifier doesn't exist
+        let mut verifiers = BTreeSet::new();
+        let mut expected_rejected = BTreeSet::new();
+        for i in 0..12 {
+            let addr = address::gen_established_address(i.to_string());
+            let vp = if i % 3 == 0 {
+                expected_rejected.insert(addr.clone());
+                &vp_always_false
+            } else {
+                &vp_always_true
+            };
+            storage.write(&Key::validity_predicate(&addr), vp).unwrap();
+            verifiers.insert(addr);
+        }
+        let missing = address::gen_established_address("missing");
+        expected_rejected.insert(missing.clone());
+        verifiers.insert(missing.clone());
+
+        let tx = Tx::new(vec![], None);
+        let write_log = WriteLog::default();
+        let (vp_cache, _cache_dir) = cache();
+        let results: Vec<Vec<u8>> = [1, 2, 8]
+            .into_iter()
+            .map(|num_threads| {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .build()
+                    .unwrap();
+                let result = pool
+                    .install(|| {
+                        execute_vps(
+                            verifiers.clone(),
+                            BTreeSet::new(),
+                            &tx,
+                            &TxIndex::default(),
+                            &storage,
+                            &write_log,
+                            0,
+                            &mut vp_cache.clone(),
+                        )
+                    })
+                    .expect("VPs shouldn't run out of gas");
+                assert_eq!(result.rejected_vps, expected_rejected);
+                assert_eq!(result.accepted_vps.len(), 8);
+                assert_eq!(
+                    result
+                        .errors
+                        .iter()
+                        .map(|(addr, _)| addr)
+                        .collect::<Vec<_>>(),
+                    vec![&missing]
+                );
+                result.try_to_vec().unwrap()
+            })
+            .collect();
+        assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    /// Test that a key deleted by a tx, i.e. present before the tx and absent
+    /// after it, is in the changed keys passed to the VPs, and that changed
+    /// keys diverging from the tx's write-set are detected.
+    #[test]
+    fn test_keys_changed_include_deletion() {
+        let mut storage = TestStorage::default();
+        let owner = address::gen_established_address("owner");
+        let other = address::gen_established_address("other");
+        let key = Key::from(owner.to_db_key())
+            .push(&"data".to_owned())
+            .unwrap();
+        storage.write(&key, vec![1]).unwrap();
+
+        let mut write_log = WriteLog::default();
+        write_log.delete(&key).unwrap();
+        assert!(storage.has_key(&key).unwrap().0);
+        assert!(matches!(
+            write_log.read(&key).0,
+            Some(StorageModification::Delete)
+        ));
+
+        let (verifiers, keys_changed) =
+            write_log.verifiers_and_changed_keys(&BTreeSet::new());
+        assert_eq!(keys_changed, BTreeSet::from([key.clone()]));
+        assert!(verifiers.contains(&owner));
+        assert!(
+            missing_keys_changed(&owner, &write_log, &keys_changed).is_empty()
+        );
+        assert!(untriggered_owners(&write_log, &verifiers).is_empty());
+
+        // The changed keys without the deletion diverge from the write-set
+        assert_eq!(
+            missing_keys_changed(&owner, &write_log, &BTreeSet::new()),
+            keys_changed
+        );
+        // Only for the VP of the key's owner
+        assert!(missing_keys_changed(&other, &write_log, &BTreeSet::new())
+            .is_empty());
+
+        // The owner of the deleted key must be a verifier
+        assert_eq!(
+            untriggered_owners(&write_log, &BTreeSet::new()),
+            BTreeSet::from([owner])
+        );
+    }
+
+    /// Test that a tx triggering more verifiers than the maximum is rejected
+    /// before the VPs run, while a tx triggering exactly the maximum is
+    /// checked by its VPs.
+    #[test]
+    fn test_max_verifiers() {
+        const MAX_VERIFIERS: usize = 4;
+        let mut storage = TestStorage::default();
+        let vp_always_true =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        // A tx writing to the storage of `MAX_VERIFIERS + 1` owners
+        let mut write_log = WriteLog::default();
+        let owners: Vec<Address> = (0..=MAX_VERIFIERS)
+            .map(|i| address::gen_established_address(i.to_string()))
+            .collect();
+        for owner in &owners {
+            storage
+                .write(&Key::validity_predicate(owner), &vp_always_true)
+                .unwrap();
+            let key = Key::from(owner.to_db_key())
+                .push(&"data".to_owned())
+                .unwrap();
+            write_log.write(&key, vec![1]).unwrap();
+        }
+
+        let tx = Tx::new(vec![], None);
+        let (mut vp_cache, _cache_dir) = cache();
+        let result = check_vps(
+            &tx,
+            &TxIndex::default(),
+            &storage,
+            &mut BlockGasMeter::default(),
+            &write_log,
+            &BTreeSet::new(),
+            &mut vp_cache,
+            Some(MAX_VERIFIERS),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::TooManyVerifiers {
+                verifiers,
+                max: MAX_VERIFIERS,
+            }) if verifiers == MAX_VERIFIERS + 1
+        ));
+
+        // Exactly at the maximum, the VPs run and accept the tx
+        let mut write_log = WriteLog::default();
+        for owner in &owners[..MAX_VERIFIERS] {
+            let key = Key::from(owner.to_db_key())
+                .push(&"data".to_owned())
+                .unwrap();
+            write_log.write(&key, vec![1]).unwrap();
+        }
+        let result = check_vps(
+            &tx,
+            &TxIndex::default(),
+            &storage,
+            &mut BlockGasMeter::default(),
+            &write_log,
+            &BTreeSet::new(),
+            &mut vp_cache,
+            Some(MAX_VERIFIERS),
+        )
+        .expect("The tx shouldn't be rejected at the maximum");
+        assert_eq!(result.accepted_vps.len(), MAX_VERIFIERS);
+        assert!(result.rejected_vps.is_empty());
+    }
+
+    /// Test that the VP of an address implicated in a self-transfer through
+    /// several paths runs only once: the address is inserted as a verifier
+    /// by the tx and it owns the balance key, which is both debited and
+    /// credited.
+    #[test]
+    fn test_self_transfer_single_vp_run() {
+        let mut storage = TestStorage::default();
+        let vp_always_true =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        let owner = address::testing::established_address_1();
+        let token = address::testing::established_address_2();
+        for addr in [&owner, &token] {
+            storage
+                .write(&Key::validity_predicate(addr), &vp_always_true)
+                .unwrap();
+        }
+
+        // The balance is debited and credited back
+        let balance_key = token::balance_key(&token, &owner);
+        let amount = token::Amount::whole(10).try_to_vec().unwrap();
+        let mut write_log = WriteLog::default();
+        write_log.write(&balance_key, amount.clone()).unwrap();
+        write_log.write(&balance_key, amount).unwrap();
+
+        let tx = Tx::new(vec![], None);
+        let (mut vp_cache, _cache_dir) = cache();
+        let mut check = |verifiers_from_tx: &BTreeSet<Address>| {
+            let mut gas_meter = BlockGasMeter::default();
+            let result = check_vps(
+                &tx,
+                &TxIndex::default(),
+                &storage,
+                &mut gas_meter,
+                &write_log,
+                verifiers_from_tx,
+                &mut vp_cache,
+                None,
+            )
+            .expect("The VPs should run");
+            (result, gas_meter.get_current_transaction_gas())
+        };
+
+        // The owner is only implicated by its balance key
+        let (result, single_run_gas) = check(&BTreeSet::new());
+        assert_eq!(
+            result.accepted_vps,
+            BTreeSet::from([owner.clone(), token.clone()])
+        );
+
+        // The owner is also inserted as a verifier by the tx, as the source of
+        // the transfer
+        let (result, gas) = check(&BTreeSet::from([owner.clone()]));
+        assert_eq!(result.accepted_vps, BTreeSet::from([owner, token]));
+        assert!(result.rejected_vps.is_empty());
+        assert_eq!(gas, single_run_gas);
+    }
+}
@@ -0,0 +1,199 @@
+//! A bounded in-memory LRU cache of the results of the recent queries.
+//!
+//! The committed state at a block height is immutable, so the result of a
+//! query is keyed by the queried height and by the last committed height, as
+//! the handlers that don't support arbitrary heights read the state at the
+//! latter. A cached result is never invalidated, it's just not looked up
+//! anymore once a new block is committed and it's eventually evicted.
+//!
+//! The queries that execute txs or that read the node-local logs, which are
+//! updated before the block is committed, are never cached.
+
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use clru::CLruCache;
+
+use crate::ledger::queries::{EncodedResponseQuery, RequestQuery};
+use crate::ledger::storage_api;
+use crate::types::storage::BlockHeight;
+
+/// The queries of the shell router that are never cached
+const UNCACHED_SHELL_QUERIES: [&str; 9] = [
+    "dry_run_tx",
+    "dry_run_tx_verifiers",
+    "estimate_gas",
+    "results",
+    "accepted",
+    "applied",
+    "tx_result",
+    "total_supply",
+    "block_events",
+];
+
+/// The key of a cached query result
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct QueryCacheKey {
+    path: String,
+    data: Vec<u8>,
+    height: BlockHeight,
+    prove: bool,
+    last_height: BlockHeight,
+}
+
+/// The number of lookups of the cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    /// The number of queries served from the cache
+    pub hits: u64,
+    /// The number of cacheable queries that had to be computed
+    pub misses: u64,
+}
+
+/// The cached results and the lookups, guarded together
+#[derive(Debug)]
+struct Inner {
+    results: CLruCache<QueryCacheKey, EncodedResponseQuery>,
+    stats: QueryCacheStats,
+}
+
+/// A bounded LRU cache of the results of the recent queries
+#[derive(Debug)]
+pub struct QueryCache {
+    inner: Mutex<Inner>,
+}
+
+impl QueryCache {
+    /// Return a new cache retaining the given number of query results
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                results: CLruCache::new(capacity),
+                stats: QueryCacheStats::default(),
+            }),
+        }
+    }
+
+    /// Get the cached result of a query, or compute it with the given
+    /// function and cache it, if the query is cacheable. The errors are not
+    /// cached.
+    pub fn get_or_compute(
+        &self,
+        request: &RequestQuery,
+        last_height: BlockHeight,
+        compute: impl FnOnce() -> storage_api::Result<EncodedResponseQuery>,
+    ) -> storage_api::Result<EncodedResponseQuery> {
+        if !is_cacheable(&request.path) {
+            return compute();
+        }
+        let key = QueryCacheKey {
+            path: request.path.clone(),
+            data: request.data.clone(),
+            height: request.height,
+            prove: request.prove,
+            last_height,
+        };
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(result) = inner.results.get(&key).cloned() {
+                inner.stats.hits += 1;
+                return Ok(result);
+            }
+            inner.stats.misses += 1;
+        }
+        // The lock is not held while the query is computed, so the same
+        // query may be computed concurrently, with the same result
+        let result = compute()?;
+        self.inner.lock().unwrap().results.put(key, result.clone());
+        Ok(result)
+    }
+
+    /// Get the number of lookups of the cache
+    pub fn stats(&self) -> QueryCacheStats {
+        self.inner.lock().unwrap().stats
+    }
+}
+
+/// Check if the result of a query with the given path may be cached
+fn is_cacheable(path: &str) -> bool {
+    let mut segments = path.trim_start_matches('/').split('/');
+    !matches!(
+        (segments.next(), segments.next()),
+        (Some("shell"), Some(query)) if UNCACHED_SHELL_QUERIES.contains(&query)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the queries that execute txs or read the node-local logs are
+    /// not cacheable.
+    #[test]
+    fn test_query_cacheable() {
+        assert!(is_cacheable("/shell/value/key"));
+        assert!(is_cacheable("/shell/epoch"));
+        assert!(is_cacheable("/vp/pos/total_stake"));
+        assert!(!is_cacheable("/shell/dry_run_tx"));
+        assert!(!is_cacheable("/shell/dry_run_tx_verifiers"));
+        assert!(!is_cacheable("/shell/estimate_gas"));
+        assert!(!is_cacheable("/shell/applied/hash"));
+        assert!(!is_cacheable("/shell/block_events/1"));
+    }
+
+    /// Test that a cached result is only served for the same query and
+    /// heights, that the errors are not cached and that the least recently
+    /// used result is evicted.
+    #[test]
+    fn test_query_cache() {
+        let cache = QueryCache::new(NonZeroUsize::new(2).unwrap());
+        let request = |path: &str| RequestQuery {
+            data: vec![],
+            path: path.to_string(),
+            height: BlockHeight(1),
+            prove: false,
+        };
+        let response = |data: u8| EncodedResponseQuery {
+            data: vec![data],
+            ..Default::default()
+        };
+        let mut computed = 0;
+        let mut query = |path: &str, last_height: u64| {
+            cache
+                .get_or_compute(
+                    &request(path),
+                    BlockHeight(last_height),
+                    || {
+                        computed += 1;
+                        Ok(response(computed))
+                    },
+                )
+                .map(|result| result.data[0])
+        };
+
+        assert_eq!(query("/shell/a", 1).unwrap(), 1);
+        assert_eq!(query("/shell/a", 1).unwrap(), 1);
+        // A new committed block is a new key
+        assert_eq!(query("/shell/a", 2).unwrap(), 2);
+        // The least recently used result is evicted
+        assert_eq!(query("/shell/b", 2).unwrap(), 3);
+        assert_eq!(query("/shell/a", 1).unwrap(), 4);
+        // The uncacheable queries are always computed and they're not counted
+        assert_eq!(query("/shell/dry_run_tx", 2).unwrap(), 5);
+        assert_eq!(query("/shell/dry_run_tx", 2).unwrap(), 6);
+        assert_eq!(cache.stats(), QueryCacheStats { hits: 1, misses: 4 });
+
+        // The errors are not cached
+        let error =
+            cache.get_or_compute(&request("/shell/c"), BlockHeight(2), || {
+                Err(storage_api::Error::new_const("error"))
+            });
+        assert!(error.is_err());
+        let result = cache
+            .get_or_compute(&request("/shell/c"), BlockHeight(2), || {
+                Ok(response(7))
+            })
+            .unwrap();
+        assert_eq!(result.data, vec![7]);
+    }
+}
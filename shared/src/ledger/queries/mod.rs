@@ -2,8 +2,8 @@
 //! defined via `router!` macro.
 
 // Re-export to show in rustdoc!
-pub use shell::Shell;
 use shell::SHELL;
+pub use shell::{Shell, MAX_PREFIX_PAGE_LIMIT};
 #[cfg(any(test, feature = "async-client"))]
 pub use types::Client;
 pub use types::{
@@ -11,12 +11,17 @@ pub use types::{
 };
 use vp::VP;
 // Re-export to show in rustdoc!
-pub use vp::{Pos, Vp};
+pub use vp::{ConsensusValidator, Pos, ValidatorSetAtEpoch, Vp};
 
 use super::storage::{DBIter, StorageHasher, DB};
 use super::storage_api;
 use crate::types::storage::BlockHeight;
 
+/// The code of the response to a query that timed out before the node
+/// responded to it, which can be retried
+pub const QUERY_TIMEOUT_CODE: u32 = 2;
+
+pub mod cache;
 #[macro_use]
 mod router;
 mod shell;
@@ -158,7 +163,10 @@ mod testing {
     use tempfile::TempDir;
 
     use super::*;
+    use crate::ledger::events::block_events::BlockEventLog;
+    use crate::ledger::events::ibc_packets::IbcPacketTracker;
     use crate::ledger::events::log::EventLog;
+    use crate::ledger::events::tx_results::TxResultLog;
     use crate::ledger::storage::testing::TestStorage;
     use crate::types::storage::BlockHeight;
     use crate::vm::wasm::{self, TxCache, VpCache};
@@ -175,6 +183,12 @@ mod testing {
         pub storage: TestStorage,
         /// event log
         pub event_log: EventLog,
+        /// tx results log
+        pub tx_results: TxResultLog,
+        /// block events log
+        pub block_events: BlockEventLog,
+        /// IBC packet tracker
+        pub ibc_packets: IbcPacketTracker,
         /// VP wasm compilation cache
         pub vp_wasm_cache: VpCache<WasmCacheRoAccess>,
         /// tx wasm compilation cache
@@ -195,6 +209,9 @@ mod testing {
             // Initialize the `TestClient`
             let storage = TestStorage::default();
             let event_log = EventLog::default();
+            let tx_results = TxResultLog::default();
+            let block_events = BlockEventLog::default();
+            let ibc_packets = IbcPacketTracker::default();
             let (vp_wasm_cache, vp_cache_dir) =
                 wasm::compilation_cache::common::testing::cache();
             let (tx_wasm_cache, tx_cache_dir) =
@@ -203,6 +220,9 @@ mod testing {
                 rpc,
                 storage,
                 event_log,
+                tx_results,
+                block_events,
+                ibc_packets,
                 vp_wasm_cache: vp_wasm_cache.read_only(),
                 tx_wasm_cache: tx_wasm_cache.read_only(),
                 vp_cache_dir,
@@ -238,6 +258,9 @@ mod testing {
             let ctx = RequestCtx {
                 storage: &self.storage,
                 event_log: &self.event_log,
+                tx_results: &self.tx_results,
+                block_events: &self.block_events,
+                ibc_packets: &self.ibc_packets,
                 vp_wasm_cache: self.vp_wasm_cache.clone(),
                 tx_wasm_cache: self.tx_wasm_cache.clone(),
                 storage_read_past_height_limit: None,
@@ -1007,6 +1007,9 @@ mod test {
         };
         let ctx = RequestCtx {
             event_log: &client.event_log,
+            tx_results: &client.tx_results,
+            block_events: &client.block_events,
+            ibc_packets: &client.ibc_packets,
             storage: &client.storage,
             vp_wasm_cache: client.vp_wasm_cache.clone(),
             tx_wasm_cache: client.tx_wasm_cache.clone(),
@@ -1,22 +1,48 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use data_encoding::HEXLOWER;
 use masp_primitives::asset_type::AssetType;
 use masp_primitives::merkle_tree::MerklePath;
 use masp_primitives::sapling::Node;
 use namada_core::types::address::Address;
 use namada_core::types::hash::Hash;
-use namada_core::types::storage::BlockResults;
+use namada_core::types::key::{self, common, AccountPublicKey};
+use namada_core::types::storage::{
+    BlockMetadata, BlockResults, EpochBoundaries, StorageDumpEntry,
+    StorageDumpPage,
+};
+use namada_core::types::validity_predicate::VpCodeHash;
 
+use crate::ledger::consensus_params::{
+    ConsensusParams, HALF_MAX_PROPOSAL_SIZE, MAX_PROPOSAL_SIZE,
+};
+use crate::ledger::events::block_events::BlockEventsResult;
+use crate::ledger::events::ibc_packets::{PacketId, TrackedPacket};
 use crate::ledger::events::log::dumb_queries;
-use crate::ledger::events::Event;
+use crate::ledger::events::{tx_results, Event};
 use crate::ledger::queries::types::{RequestCtx, RequestQuery};
 use crate::ledger::queries::{require_latest_height, EncodedResponseQuery};
 use crate::ledger::storage::traits::StorageHasher;
-use crate::ledger::storage::{DBIter, DB};
+use crate::ledger::storage::{DBIter, DB, EVIDENCE_MAX_AGE_NUM_BLOCKS};
 use crate::ledger::storage_api::{self, ResultExt, StorageRead};
+use crate::ledger::token_supply::{self, EpochSupply};
+use crate::ledger::{gas, parameters};
 use crate::tendermint::merkle::proof::Proof;
-use crate::types::storage::{self, Epoch, PrefixValue};
+use crate::types::storage::{
+    self, BlockHeight, Epoch, PrefixValue, PrefixValuePage,
+};
 #[cfg(any(test, feature = "async-client"))]
-use crate::types::transaction::TxResult;
+use crate::types::transaction::{GasEstimate, TxResult, TxVerifiers};
+
+/// The maximum number of values in a page of a storage prefix iterator
+pub const MAX_PREFIX_PAGE_LIMIT: u64 = 1000;
+
+/// The maximum size of the hex-encoded values in a page of a storage dump, in
+/// bytes. A page is cut short once it's reached, but it always contains at
+/// least one entry.
+pub const MAX_STORAGE_DUMP_PAGE_BYTES: usize = 1024 * 1024;
+
+/// The maximum number of blocks whose metadata can be queried at once
+pub const MAX_LAST_BLOCKS: u64 = 1000;
 
 type Conversion = (
     Address,
@@ -29,6 +55,17 @@ router! {SHELL,
     // Epoch of the last committed block
     ( "epoch" ) -> Epoch = epoch,
 
+    // Epoch of the last committed block and when the next epoch may start
+    ( "epoch_boundaries" ) -> EpochBoundaries = epoch_boundaries,
+
+    // The consensus parameters enforced by the node, including the
+    // parameters currently set by governance
+    ( "consensus_params" ) -> ConsensusParams = consensus_params,
+
+    // The costs per operation charged by the gas meters, with their version
+    // and the effective block gas limit
+    ( "gas_schedule" ) -> gas::GasSchedule = gas_schedule,
+
     // Raw storage access - read value
     ( "value" / [storage_key: storage::Key] )
         -> Vec<u8> = (with_options storage_value),
@@ -36,17 +73,46 @@ router! {SHELL,
     // Dry run a transaction
     ( "dry_run_tx" ) -> TxResult = (with_options dry_run_tx),
 
+    // Dry run a transaction's code to find the verifiers it would trigger
+    ( "dry_run_tx_verifiers" ) -> TxVerifiers =
+        (with_options dry_run_tx_verifiers),
+
+    // Dry run a transaction and its VPs to estimate the gas it would use
+    ( "estimate_gas" ) -> GasEstimate = (with_options estimate_gas),
+
     // Raw storage access - prefix iterator
     ( "prefix" / [storage_key: storage::Key] )
         -> Vec<PrefixValue> = (with_options storage_prefix),
 
+    // Raw storage access - a page of a prefix iterator. The following pages
+    // must be requested at the height given in the response.
+    ( "prefix_page" / [storage_key: storage::Key] / [offset: u64] / [limit: u64] )
+        -> PrefixValuePage = (with_options storage_prefix_page),
+
+    // Diagnostics - a page of a dump of the storage under a prefix, with
+    // hex-encoded values. Not meant for production use, the following pages
+    // must be requested at the height given in the response.
+    ( "dump_prefix" / [storage_key: storage::Key] / [offset: u64] / [limit: u64] )
+        -> StorageDumpPage = (with_options storage_dump_prefix),
+
     // Raw storage access - is given storage key present?
     ( "has_key" / [storage_key: storage::Key] )
         -> bool = storage_has_key,
 
+    // The public key of an account and its signature scheme
+    ( "public_key" / [owner: Address] )
+        -> AccountPublicKey = account_public_key,
+
+    // The hash of the VP code of an account
+    ( "vp_code_hash" / [owner: Address] )
+        -> VpCodeHash = vp_code_hash,
+
     // Block results access - read bit-vec
     ( "results" ) -> Vec<BlockResults> = read_results,
 
+    // Metadata of the last committed blocks, newest first
+    ( "last_blocks" / [num_blocks: u64] ) -> Vec<BlockMetadata> = last_blocks,
+
     // Conversion state access - read conversion
     ( "conv" / [asset_type: AssetType] ) -> Conversion = read_conversion,
 
@@ -56,6 +122,25 @@ router! {SHELL,
     // was the transaction applied?
     ( "applied" / [tx_hash: Hash] ) -> Option<Event> = applied,
 
+    // The result of a tx applied in one of the last blocks, or `None` when
+    // the tx is unknown or its result has expired
+    ( "tx_result" / [tx_hash: Hash] )
+        -> Option<tx_results::TxResult> = tx_result,
+
+    // The total supply of a token at the end of an epoch, or at the last
+    // committed block for the current epoch
+    ( "total_supply" / [token: Address] / [epoch: Epoch] )
+        -> EpochSupply = total_supply,
+
+    // The events emitted by the block at a height, grouped by their source
+    ( "block_events" / [height: BlockHeight] )
+        -> BlockEventsResult = block_events,
+
+    // The IBC packets sent from this chain that are marked as timed-out in
+    // the node's local tracking, oldest first
+    ( "ibc_timed_out_packets" )
+        -> Vec<(PacketId, TrackedPacket)> = ibc_timed_out_packets,
+
 }
 
 // Handlers:
@@ -80,6 +165,7 @@ where
     let mut write_log = WriteLog::default();
     let tx = Tx::try_from(&request.data[..]).into_storage_result()?;
     let tx = TxType::Decrypted(DecryptedTx::Decrypted(tx));
+    let max_verifiers = read_max_verifiers(&ctx)?;
     let data = protocol::apply_tx(
         tx,
         request.data.len(),
@@ -89,6 +175,7 @@ where
         ctx.storage,
         &mut ctx.vp_wasm_cache,
         &mut ctx.tx_wasm_cache,
+        max_verifiers,
     )
     .into_storage_result()?;
     let data = data.try_to_vec().into_storage_result()?;
@@ -99,6 +186,84 @@ where
     })
 }
 
+#[cfg(all(feature = "wasm-runtime", feature = "ferveo-tpke"))]
+fn dry_run_tx_verifiers<D, H>(
+    mut ctx: RequestCtx<'_, D, H>,
+    request: &RequestQuery,
+) -> storage_api::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    use crate::ledger::protocol;
+    use crate::proto::Tx;
+
+    let tx = Tx::try_from(&request.data[..])
+        .map_err(protocol::Error::TxDecodingError)
+        .into_storage_result()?;
+    let data = protocol::tx_verifiers(
+        &tx,
+        ctx.storage,
+        &mut ctx.vp_wasm_cache,
+        &mut ctx.tx_wasm_cache,
+    )
+    .into_storage_result()?;
+    let data = data.try_to_vec().into_storage_result()?;
+    Ok(EncodedResponseQuery {
+        data,
+        proof: None,
+        info: Default::default(),
+    })
+}
+
+#[cfg(all(feature = "wasm-runtime", feature = "ferveo-tpke"))]
+fn estimate_gas<D, H>(
+    mut ctx: RequestCtx<'_, D, H>,
+    request: &RequestQuery,
+) -> storage_api::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    use crate::ledger::protocol;
+    use crate::proto::Tx;
+
+    let tx = Tx::try_from(&request.data[..])
+        .map_err(protocol::Error::TxDecodingError)
+        .into_storage_result()?;
+    let max_verifiers = read_max_verifiers(&ctx)?;
+    let data = protocol::estimate_gas(
+        tx,
+        request.data.len(),
+        ctx.storage,
+        &mut ctx.vp_wasm_cache,
+        &mut ctx.tx_wasm_cache,
+        max_verifiers,
+    );
+    let data = data.try_to_vec().into_storage_result()?;
+    Ok(EncodedResponseQuery {
+        data,
+        proof: None,
+        info: Default::default(),
+    })
+}
+
+/// Read the max verifiers parameter, which caps the verifiers of the dry-run
+/// txs like those applied in a block
+#[cfg(all(feature = "wasm-runtime", feature = "ferveo-tpke"))]
+fn read_max_verifiers<D, H>(
+    ctx: &RequestCtx<'_, D, H>,
+) -> storage_api::Result<Option<usize>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let (max_verifiers, _gas) =
+        parameters::read_max_verifiers_parameter(ctx.storage)
+            .into_storage_result()?;
+    Ok(max_verifiers.map(|max| max as usize))
+}
+
 /// Query to read block results from storage
 pub fn read_results<D, H>(
     ctx: RequestCtx<'_, D, H>,
@@ -121,6 +286,49 @@ where
     Ok(results)
 }
 
+/// Query the metadata of up to `num_blocks` last committed blocks, newest
+/// first. The number of blocks is bounded by [`MAX_LAST_BLOCKS`] and by the
+/// `storage_read_past_height_limit`, if any. When the chain has fewer blocks,
+/// all of them are returned.
+fn last_blocks<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    num_blocks: u64,
+) -> storage_api::Result<Vec<BlockMetadata>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let mut num_blocks = num_blocks.min(MAX_LAST_BLOCKS);
+    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
+        num_blocks = num_blocks.min(past_height_limit + 1);
+    }
+    let mut blocks = Vec::with_capacity(num_blocks as usize);
+    let mut height = ctx.storage.last_height;
+    while (blocks.len() as u64) < num_blocks && height.0 > 0 {
+        let header = ctx
+            .storage
+            .db
+            .read_block_header(height)
+            .into_storage_result()?;
+        let hash = ctx
+            .storage
+            .db
+            .read_block_hash(height)
+            .into_storage_result()?;
+        match (header, hash) {
+            (Some(header), Some(hash)) => blocks.push(BlockMetadata {
+                height,
+                hash,
+                time: header.time,
+            }),
+            // There are no more blocks before this height
+            _ => break,
+        }
+        height = BlockHeight(height.0 - 1);
+    }
+    Ok(blocks)
+}
+
 /// Query to read a conversion from storage
 fn read_conversion<D, H>(
     ctx: RequestCtx<'_, D, H>,
@@ -162,6 +370,30 @@ where
     unimplemented!("Dry running tx requires \"wasm-runtime\" feature.")
 }
 
+#[cfg(not(all(feature = "wasm-runtime", feature = "ferveo-tpke")))]
+fn dry_run_tx_verifiers<D, H>(
+    _ctx: RequestCtx<'_, D, H>,
+    _request: &RequestQuery,
+) -> storage_api::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    unimplemented!("Dry running tx requires \"wasm-runtime\" feature.")
+}
+
+#[cfg(not(all(feature = "wasm-runtime", feature = "ferveo-tpke")))]
+fn estimate_gas<D, H>(
+    _ctx: RequestCtx<'_, D, H>,
+    _request: &RequestQuery,
+) -> storage_api::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    unimplemented!("Dry running tx requires \"wasm-runtime\" feature.")
+}
+
 fn epoch<D, H>(ctx: RequestCtx<'_, D, H>) -> storage_api::Result<Epoch>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -171,10 +403,90 @@ where
     Ok(data)
 }
 
+fn gas_schedule<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+) -> storage_api::Result<gas::GasSchedule>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    // The costs are fixed by the protocol, but the block gas limit is a
+    // protocol parameter, capped like in the consensus parameters
+    let (block_gas_limit, _gas) =
+        parameters::read_block_gas_limit_parameter(ctx.storage)
+            .into_storage_result()?;
+    Ok(gas::GasSchedule {
+        block_gas_limit: block_gas_limit.min(gas::BLOCK_GAS_LIMIT),
+        ..gas::GasSchedule::current()
+    })
+}
+
+fn consensus_params<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+) -> storage_api::Result<ConsensusParams>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    // The parameters are read from the last committed state, so any change
+    // made by governance is reflected once it's committed
+    let (params, _gas) = parameters::read(ctx.storage).into_storage_result()?;
+    Ok(ConsensusParams {
+        max_proposal_bytes: MAX_PROPOSAL_SIZE as u64,
+        max_wrapper_txs_bytes: HALF_MAX_PROPOSAL_SIZE as u64,
+        block_gas_limit: params.block_gas_limit.min(gas::BLOCK_GAS_LIMIT),
+        tx_gas_limit: gas::TRANSACTION_GAS_LIMIT,
+        max_verifiers: params.max_verifiers,
+        evidence_max_age_num_blocks: EVIDENCE_MAX_AGE_NUM_BLOCKS,
+        max_expected_time_per_block: params.max_expected_time_per_block,
+        epoch_duration: params.epoch_duration,
+        max_ibc_packet_data_bytes: params.max_ibc_packet_data_bytes,
+    })
+}
+
+fn epoch_boundaries<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+) -> storage_api::Result<EpochBoundaries>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let current_epoch = ctx.storage.last_epoch;
+    // The block that starts a new epoch is recorded as the first block of
+    // the new epoch, so at a transition boundary this is the last height
+    let first_block_of_current = ctx
+        .storage
+        .block
+        .pred_epochs
+        .get_first_block_height(current_epoch)
+        .ok_or_else(|| {
+            storage_api::Error::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "The first block of the current epoch {} is not known",
+                    current_epoch
+                ),
+            ))
+        })?;
+    Ok(EpochBoundaries {
+        current_epoch,
+        first_block_of_current,
+        next_epoch_min_start_height: ctx.storage.next_epoch_min_start_height,
+        next_epoch_min_start_time: ctx.storage.next_epoch_min_start_time,
+    })
+}
+
 /// Returns data with `vec![]` when the storage key is not found. For all
 /// borsh-encoded types, it is safe to check `data.is_empty()` to see if the
 /// value was found, except for unit - see `fn query_storage_value` in
 /// `apps/src/lib/client/rpc.rs` for unit type handling via `storage_has_key`.
+///
+/// When a proof is requested, it's an inclusion proof of the value or, when
+/// the key is not found, an exclusion proof of the key, which can be verified
+/// against the app hash of the block with
+/// [`crate::ledger::storage::merkle_tree::Proof::verify`]. Exclusion proofs
+/// are only supported for the keys of the IBC sub-tree, because the other
+/// sub-trees only store the hashes of the keys.
 fn storage_value<D, H>(
     ctx: RequestCtx<'_, D, H>,
     request: &RequestQuery,
@@ -184,18 +496,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
-        if request.height.0 + past_height_limit < ctx.storage.last_height.0 {
-            return Err(storage_api::Error::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!(
-                    "Cannot query more than {past_height_limit} blocks in the \
-                     past (configured via \
-                     `shell.storage_read_past_height_limit`)."
-                ),
-            )));
-        }
-    }
+    check_past_height_limit(&ctx, request.height)?;
 
     match ctx
         .storage
@@ -256,9 +557,197 @@ where
         })
         .collect();
     let data = data?;
-    let proof = if request.prove {
+    let proof = prefix_values_proof(&ctx, request, &data)?;
+    let data = data.try_to_vec().into_storage_result()?;
+    Ok(EncodedResponseQuery {
+        data,
+        proof,
+        ..Default::default()
+    })
+}
+
+fn storage_prefix_page<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    request: &RequestQuery,
+    storage_key: storage::Key,
+    offset: u64,
+    limit: u64,
+) -> storage_api::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let height = validate_prefix_page(&ctx, request, limit)?;
+
+    let (kvs, _gas) = ctx
+        .storage
+        .iter_prefix_with_height(&storage_key, height)
+        .into_storage_result()?;
+    // Read one more value than the limit to find out if there's a next page
+    let mut values: Vec<PrefixValue> = kvs
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize + 1)
+        .map(|(key, value)| {
+            let key = storage::Key::parse(key).into_storage_result()?;
+            Ok(PrefixValue { key, value })
+        })
+        .collect::<storage_api::Result<_>>()?;
+    let next_offset = if values.len() as u64 > limit {
+        values.truncate(limit as usize);
+        Some(offset + limit)
+    } else {
+        None
+    };
+    let proof = prefix_values_proof(&ctx, request, &values)?;
+    let data = PrefixValuePage {
+        values,
+        height,
+        next_offset,
+    }
+    .try_to_vec()
+    .into_storage_result()?;
+    Ok(EncodedResponseQuery {
+        data,
+        proof,
+        ..Default::default()
+    })
+}
+
+fn storage_dump_prefix<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    request: &RequestQuery,
+    storage_key: storage::Key,
+    offset: u64,
+    limit: u64,
+) -> storage_api::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let height = validate_prefix_page(&ctx, request, limit)?;
+
+    let (kvs, _gas) = ctx
+        .storage
+        .iter_prefix_with_height(&storage_key, height)
+        .into_storage_result()?;
+    let mut iter = kvs.into_iter().skip(offset as usize).peekable();
+    let mut entries = vec![];
+    let mut page_bytes = 0_usize;
+    while let Some((_key, value)) = iter.peek() {
+        if entries.len() as u64 == limit {
+            break;
+        }
+        let value_bytes = HEXLOWER.encode_len(value.len());
+        if !entries.is_empty()
+            && page_bytes + value_bytes > MAX_STORAGE_DUMP_PAGE_BYTES
+        {
+            break;
+        }
+        let (key, value) = iter.next().expect("peeked above");
+        page_bytes += value_bytes;
+        entries.push(StorageDumpEntry {
+            key: storage::Key::parse(key).into_storage_result()?,
+            value: HEXLOWER.encode(&value),
+        });
+    }
+    let next_offset =
+        iter.peek().is_some().then(|| offset + entries.len() as u64);
+    let data = StorageDumpPage {
+        entries,
+        height,
+        next_offset,
+    }
+    .try_to_vec()
+    .into_storage_result()?;
+    Ok(EncodedResponseQuery {
+        data,
+        ..Default::default()
+    })
+}
+
+/// Check that the given height is not further in the past than the
+/// `storage_read_past_height_limit`, if any.
+fn check_past_height_limit<D, H>(
+    ctx: &RequestCtx<'_, D, H>,
+    height: BlockHeight,
+) -> storage_api::Result<()>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
+        if height.0 + past_height_limit < ctx.storage.last_height.0 {
+            return Err(storage_api::Error::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot query more than {past_height_limit} blocks in the \
+                     past (configured via \
+                     `shell.storage_read_past_height_limit`)."
+                ),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a page of a prefix iterator is requested at a committed height,
+/// or at no particular height for the first page, within the
+/// `storage_read_past_height_limit`, and that its limit is within the
+/// bounds. Reading a page at an older height walks the diffs of every block
+/// since. Returns the height at which the page has to be read, so that
+/// all the pages of a pagination are read from the same state even if new
+/// blocks get committed in between.
+fn validate_prefix_page<D, H>(
+    ctx: &RequestCtx<'_, D, H>,
+    request: &RequestQuery,
+    limit: u64,
+) -> storage_api::Result<BlockHeight>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let height = if request.height == BlockHeight(0) {
+        ctx.storage.last_height
+    } else {
+        request.height
+    };
+    if height > ctx.storage.last_height {
+        return Err(storage_api::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "The height {} requested for this page has not been committed \
+                 yet, the last committed height is {}",
+                height, ctx.storage.last_height
+            ),
+        )));
+    }
+    check_past_height_limit(ctx, height)?;
+    if limit == 0 || limit > MAX_PREFIX_PAGE_LIMIT {
+        return Err(storage_api::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "The page limit must be between 1 and \
+                 {MAX_PREFIX_PAGE_LIMIT}, got {limit}"
+            ),
+        )));
+    }
+    Ok(height)
+}
+
+/// Get the proof of the given prefix values, if requested.
+fn prefix_values_proof<D, H>(
+    ctx: &RequestCtx<'_, D, H>,
+    request: &RequestQuery,
+    data: &[PrefixValue],
+) -> storage_api::Result<Option<Proof>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if request.prove {
         let mut ops = vec![];
-        for PrefixValue { key, value } in &data {
+        for PrefixValue { key, value } in data {
             let mut proof: crate::tendermint::merkle::proof::Proof = ctx
                 .storage
                 .get_existence_proof(key, value, request.height)
@@ -267,16 +756,10 @@ where
         }
         // ops is not empty in this case
         let proof = Proof { ops };
-        Some(proof)
+        Ok(Some(proof))
     } else {
-        None
-    };
-    let data = data.try_to_vec().into_storage_result()?;
-    Ok(EncodedResponseQuery {
-        data,
-        proof,
-        ..Default::default()
-    })
+        Ok(None)
+    }
 }
 
 fn storage_has_key<D, H>(
@@ -291,6 +774,34 @@ where
     Ok(data)
 }
 
+fn account_public_key<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<AccountPublicKey>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let public_key: Option<common::PublicKey> =
+        StorageRead::read(ctx.storage, &key::pk_key(&owner))?;
+    Ok(public_key.into())
+}
+
+fn vp_code_hash<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+) -> storage_api::Result<VpCodeHash>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let vp_code = StorageRead::read_bytes(
+        ctx.storage,
+        &storage::Key::validity_predicate(&owner),
+    )?;
+    Ok(VpCodeHash::from_code(vp_code))
+}
+
 fn accepted<D, H>(
     ctx: RequestCtx<'_, D, H>,
     tx_hash: Hash,
@@ -325,17 +836,103 @@ where
         .cloned())
 }
 
+fn tx_result<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    tx_hash: Hash,
+) -> storage_api::Result<Option<tx_results::TxResult>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    Ok(ctx.tx_results.get(&tx_hash).cloned())
+}
+
+fn total_supply<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    token: Address,
+    epoch: Epoch,
+) -> storage_api::Result<EpochSupply>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let current_epoch = ctx.storage.last_epoch;
+    if epoch > current_epoch {
+        return Err(storage_api::Error::new_const(
+            "The total supply cannot be queried for a future epoch",
+        ));
+    }
+    if epoch == current_epoch {
+        let supply = token_supply::read_total_supply(ctx.storage, &token)?;
+        return Ok(EpochSupply::Available(supply));
+    }
+    token_supply::read_epoch_total_supply(ctx.storage, &token, epoch)
+}
+
+fn block_events<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    height: BlockHeight,
+) -> storage_api::Result<BlockEventsResult>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if height > ctx.storage.last_height {
+        return Err(storage_api::Error::new_const(
+            "The events cannot be queried for a block that's not committed",
+        ));
+    }
+    Ok(ctx.block_events.get(height))
+}
+
+fn ibc_timed_out_packets<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+) -> storage_api::Result<Vec<(PacketId, TrackedPacket)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    Ok(ctx
+        .ibc_packets
+        .timed_out()
+        .map(|(packet_id, packet)| (packet_id.clone(), packet.clone()))
+        .collect())
+}
+
 #[cfg(test)]
 mod test {
-    use borsh::BorshDeserialize;
+    use std::collections::HashMap;
+
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use data_encoding::HEXLOWER;
 
+    use super::MAX_STORAGE_DUMP_PAGE_BYTES;
+    use crate::ledger::events::ibc_packets::{
+        IbcPacketTracker, PacketId, PacketStatus, TrackedPacket,
+    };
+    use crate::ledger::events::{Event, EventLevel, EventType};
     use crate::ledger::queries::testing::TestClient;
-    use crate::ledger::queries::RPC;
-    use crate::ledger::storage_api::{self, StorageWrite};
+    use crate::ledger::queries::types::{RequestCtx, RequestQuery};
+    use crate::ledger::queries::{Router, Rpc, RPC};
+    use crate::ledger::storage::merkle_tree::Proof;
+    use crate::ledger::storage::traits::Sha256Hasher;
+    use crate::ledger::storage_api::{self, StorageRead, StorageWrite};
     use crate::proto::Tx;
+    use crate::types::address::{Address, InternalAddress};
+    use crate::types::hash::Hash;
+    use crate::types::key::{self, AccountPublicKey, RefTo, SchemeType};
+    use crate::types::storage::{
+        BlockHash, BlockHeight, Epoch, Header, Key, KeySeg, StorageDumpEntry,
+    };
+    use crate::types::time::{DateTimeUtc, DurationSecs};
+    use crate::types::transaction::DryRunOutcome;
+    use crate::types::validity_predicate::VpCodeHash;
     use crate::types::{address, token};
 
     const TX_NO_OP_WASM: &str = "../wasm_for_tests/tx_no_op.wasm";
+    const TX_MINT_TOKENS_WASM: &str = "../wasm_for_tests/tx_mint_tokens.wasm";
+    const VP_ALWAYS_TRUE_WASM: &str = "../wasm_for_tests/vp_always_true.wasm";
+    const VP_ALWAYS_FALSE_WASM: &str = "../wasm_for_tests/vp_always_false.wasm";
 
     #[test]
     fn test_shell_queries_router_paths() {
@@ -351,6 +948,9 @@ mod test {
         let path = RPC.shell().dry_run_tx_path();
         assert_eq!("/shell/dry_run_tx", path);
 
+        let path = RPC.shell().dry_run_tx_verifiers_path();
+        assert_eq!("/shell/dry_run_tx_verifiers", path);
+
         let path = RPC.shell().storage_prefix_path(&key);
         assert_eq!(format!("/shell/prefix/{}", key), path);
 
@@ -442,4 +1042,652 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_shell_queries_prefix_pages() -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+
+        // Write some balances
+        let token_addr = address::testing::established_address_1();
+        for i in 0..25_u64 {
+            let owner = address::testing::gen_established_address();
+            let balance_key = token::balance_key(&token_addr, &owner);
+            StorageWrite::write(
+                &mut client.storage,
+                &balance_key,
+                token::Amount::from(i),
+            )?;
+        }
+        let balance_prefix = token::balance_prefix(&token_addr);
+        let all_balances = RPC
+            .shell()
+            .storage_prefix(&client, None, None, false, &balance_prefix)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(all_balances.len(), 25);
+
+        // Read the balances in pages and reassemble them
+        let mut pages = vec![];
+        let mut offset = 0;
+        let mut height = None;
+        loop {
+            let page = RPC
+                .shell()
+                .storage_prefix_page(
+                    &client,
+                    None,
+                    height,
+                    false,
+                    &balance_prefix,
+                    &offset,
+                    &10,
+                )
+                .await
+                .unwrap()
+                .data;
+            assert_eq!(page.height, client.storage.last_height);
+            pages.push(page.values);
+            match page.next_offset {
+                Some(next_offset) => {
+                    offset = next_offset;
+                    height = Some(page.height);
+                }
+                None => break,
+            }
+        }
+        assert_eq!(
+            pages.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![10, 10, 5]
+        );
+        let paged_balances: Vec<_> = pages.into_iter().flatten().collect();
+        assert_eq!(
+            paged_balances.try_to_vec().unwrap(),
+            all_balances.try_to_vec().unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shell_queries_prefix_page_changed_height() {
+        let mut client = TestClient::new(RPC);
+        client.storage.last_height = BlockHeight(2);
+        let ctx = RequestCtx {
+            storage: &client.storage,
+            event_log: &client.event_log,
+            tx_results: &client.tx_results,
+            block_events: &client.block_events,
+            ibc_packets: &client.ibc_packets,
+            vp_wasm_cache: client.vp_wasm_cache.clone(),
+            tx_wasm_cache: client.tx_wasm_cache.clone(),
+            storage_read_past_height_limit: None,
+        };
+        let token_addr = address::testing::established_address_1();
+        let balance_prefix = token::balance_prefix(&token_addr);
+        let path =
+            RPC.shell()
+                .storage_prefix_page_path(&balance_prefix, &0, &10);
+
+        // A page requested at a height that hasn't been committed yet must be
+        // rejected
+        let request = RequestQuery {
+            path,
+            height: BlockHeight(3),
+            ..Default::default()
+        };
+        assert!(RPC.handle(ctx, &request).is_err());
+    }
+
+    /// Test that the pages of a prefix and of a storage dump can't be read
+    /// further in the past than the `storage_read_past_height_limit`.
+    #[test]
+    fn test_shell_queries_prefix_page_past_height_limit() {
+        let mut client = TestClient::new(RPC);
+        client.storage.last_height = BlockHeight(10);
+        let ctx = || RequestCtx {
+            storage: &client.storage,
+            event_log: &client.event_log,
+            tx_results: &client.tx_results,
+            block_events: &client.block_events,
+            ibc_packets: &client.ibc_packets,
+            vp_wasm_cache: client.vp_wasm_cache.clone(),
+            tx_wasm_cache: client.tx_wasm_cache.clone(),
+            storage_read_past_height_limit: Some(5),
+        };
+        let token_addr = address::testing::established_address_1();
+        let balance_prefix = token::balance_prefix(&token_addr);
+        let paths = [
+            RPC.shell()
+                .storage_prefix_page_path(&balance_prefix, &0, &10),
+            RPC.shell()
+                .storage_dump_prefix_path(&balance_prefix, &0, &10),
+        ];
+
+        for path in paths {
+            // A page at the limit is served
+            let request = RequestQuery {
+                path: path.clone(),
+                height: BlockHeight(5),
+                ..Default::default()
+            };
+            assert!(RPC.handle(ctx(), &request).is_ok());
+
+            // A page older than the limit is rejected
+            let request = RequestQuery {
+                path,
+                height: BlockHeight(4),
+                ..Default::default()
+            };
+            assert!(RPC.handle(ctx(), &request).is_err());
+        }
+    }
+
+    /// Dump all the storage under a prefix in pages.
+    async fn dump_prefix(
+        client: &TestClient<Rpc>,
+        prefix: &Key,
+        limit: u64,
+    ) -> Vec<Vec<StorageDumpEntry>> {
+        let mut pages = vec![];
+        let mut offset = 0;
+        let mut height = None;
+        loop {
+            let page = RPC
+                .shell()
+                .storage_dump_prefix(
+                    client, None, height, false, prefix, &offset, &limit,
+                )
+                .await
+                .unwrap()
+                .data;
+            pages.push(page.entries);
+            match page.next_offset {
+                Some(next_offset) => {
+                    offset = next_offset;
+                    height = Some(page.height);
+                }
+                None => break,
+            }
+        }
+        pages
+    }
+
+    /// Test that a dump of the storage under a prefix returns all the keys
+    /// under it with their hex-encoded values, in pages capped in size.
+    #[tokio::test]
+    async fn test_shell_queries_storage_dump_prefix() -> storage_api::Result<()>
+    {
+        let mut client = TestClient::new(RPC);
+        let owner = address::testing::established_address_1();
+        let prefix = Key::from(owner.to_db_key())
+            .push(&"dump".to_string())
+            .unwrap();
+
+        // Write some binary values under the prefix and one next to it
+        let mut expected = vec![];
+        for i in 0..5_u8 {
+            let key = prefix.push(&i.to_string()).unwrap();
+            let value = vec![0xf0 | i; i as usize + 1];
+            StorageWrite::write_bytes(&mut client.storage, &key, &value)?;
+            expected.push(StorageDumpEntry {
+                key,
+                value: HEXLOWER.encode(&value),
+            });
+        }
+        let other_key = Key::from(owner.to_db_key())
+            .push(&"other".to_string())
+            .unwrap();
+        StorageWrite::write_bytes(&mut client.storage, &other_key, [1_u8])?;
+
+        let pages = dump_prefix(&client, &prefix, 2).await;
+        assert_eq!(
+            pages.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        let dumped: Vec<_> = pages.into_iter().flatten().collect();
+        assert_eq!(dumped, expected);
+        assert_eq!(dumped[1].value, "f1f1");
+
+        // The pages are cut short by the size cap, but a value larger than
+        // the cap is still returned
+        let large_prefix = Key::from(owner.to_db_key())
+            .push(&"large".to_string())
+            .unwrap();
+        for i in 0..3_u8 {
+            let key = large_prefix.push(&i.to_string()).unwrap();
+            let len = MAX_STORAGE_DUMP_PAGE_BYTES / 3 + i as usize;
+            StorageWrite::write_bytes(&mut client.storage, &key, vec![i; len])?;
+        }
+        let pages = dump_prefix(&client, &large_prefix, 10).await;
+        assert_eq!(
+            pages.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![1, 1, 1]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shell_queries_last_blocks() -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+
+        // There are no committed blocks yet
+        let blocks = RPC.shell().last_blocks(&client, &5).await.unwrap();
+        assert!(blocks.is_empty());
+
+        // Commit a few blocks
+        let genesis_time = DateTimeUtc::now();
+        for height in 1..=3_u64 {
+            let hash = BlockHash([height as u8; 32]);
+            client
+                .storage
+                .begin_block(hash, BlockHeight(height))
+                .unwrap();
+            client
+                .storage
+                .set_header(Header {
+                    hash: Hash([0; 32]),
+                    time: genesis_time + DurationSecs(height),
+                    next_validators_hash: Hash([0; 32]),
+                })
+                .unwrap();
+            client.storage.commit().unwrap();
+        }
+
+        // When there are fewer blocks than requested, all of them are
+        // returned, newest first
+        let blocks = RPC.shell().last_blocks(&client, &5).await.unwrap();
+        let heights: Vec<_> =
+            blocks.iter().map(|block| block.height.0).collect();
+        assert_eq!(heights, vec![3, 2, 1]);
+        for block in blocks {
+            assert_eq!(block.hash, BlockHash([block.height.0 as u8; 32]));
+            assert_eq!(block.time, genesis_time + DurationSecs(block.height.0));
+        }
+
+        let blocks = RPC.shell().last_blocks(&client, &2).await.unwrap();
+        let heights: Vec<_> =
+            blocks.iter().map(|block| block.height.0).collect();
+        assert_eq!(heights, vec![3, 2]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shell_queries_epoch_boundaries() -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+
+        // Before the first epoch transition
+        let boundaries = RPC.shell().epoch_boundaries(&client).await.unwrap();
+        assert_eq!(boundaries.current_epoch, Epoch(0));
+        assert_eq!(boundaries.first_block_of_current, BlockHeight(0));
+
+        // Commit a block and set the next epoch to start at height 3
+        let genesis_time = DateTimeUtc::now();
+        client
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        client.storage.next_epoch_min_start_height = BlockHeight(3);
+        client.storage.next_epoch_min_start_time = genesis_time;
+        client.storage.commit().unwrap();
+
+        let boundaries = RPC.shell().epoch_boundaries(&client).await.unwrap();
+        assert_eq!(boundaries.current_epoch, Epoch(0));
+        assert_eq!(boundaries.first_block_of_current, BlockHeight(0));
+        assert_eq!(boundaries.next_epoch_min_start_height, BlockHeight(3));
+        assert_eq!(boundaries.next_epoch_min_start_time, genesis_time);
+
+        // Commit the block at the transition boundary, that starts a new epoch
+        client
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(3))
+            .unwrap();
+        client.storage.block.epoch = client.storage.block.epoch.next();
+        client
+            .storage
+            .block
+            .pred_epochs
+            .new_epoch(BlockHeight(3), 1000);
+        client.storage.next_epoch_min_start_height = BlockHeight(6);
+        client.storage.next_epoch_min_start_time =
+            genesis_time + DurationSecs(60);
+        client.storage.commit().unwrap();
+
+        // The boundary block is reported as the first block of the new epoch
+        let boundaries = RPC.shell().epoch_boundaries(&client).await.unwrap();
+        assert_eq!(boundaries.current_epoch, Epoch(1));
+        assert_eq!(boundaries.first_block_of_current, BlockHeight(3));
+        assert_eq!(boundaries.next_epoch_min_start_height, BlockHeight(6));
+        assert_eq!(
+            boundaries.next_epoch_min_start_time,
+            genesis_time + DurationSecs(60)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shell_queries_account_public_key() -> storage_api::Result<()>
+    {
+        let mut client = TestClient::new(RPC);
+        let owner = address::testing::established_address_1();
+
+        // There is no key established for the account yet
+        let public_key = RPC
+            .shell()
+            .account_public_key(&client, &owner)
+            .await
+            .unwrap();
+        assert_eq!(public_key, AccountPublicKey::Uninitialized);
+
+        // Establish a key and check that it's returned with its scheme
+        let pk = key::testing::keypair_1().ref_to();
+        StorageWrite::write(&mut client.storage, &key::pk_key(&owner), &pk)?;
+        let public_key = RPC
+            .shell()
+            .account_public_key(&client, &owner)
+            .await
+            .unwrap();
+        assert_eq!(
+            public_key,
+            AccountPublicKey::Initialized {
+                public_key: pk,
+                scheme: SchemeType::Ed25519,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Test that a dry run of a transfer finds the token and the owner of the
+    /// credited balance as verifiers, and that an undecodable tx is rejected.
+    #[tokio::test]
+    async fn test_shell_queries_dry_run_tx_verifiers() -> storage_api::Result<()>
+    {
+        let client = TestClient::new(RPC);
+
+        let token_addr = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let transfer = token::Transfer {
+            source: address::testing::established_address_3(),
+            target: target.clone(),
+            token: token_addr.clone(),
+            sub_prefix: None,
+            amount: token::Amount::from(100),
+            key: None,
+            shielded: None,
+            memo: None,
+        };
+        let tx_code =
+            std::fs::read(TX_MINT_TOKENS_WASM).expect("cannot load wasm");
+        let tx = Tx::new(tx_code, Some(transfer.try_to_vec().unwrap()))
+            .sign(&key::testing::keypair_1(), &client.storage.chain_id);
+        let result = RPC
+            .shell()
+            .dry_run_tx_verifiers(&client, Some(tx.to_bytes()), None, false)
+            .await
+            .unwrap();
+        assert!(result.data.verifiers.contains(&token_addr));
+        assert!(result.data.verifiers.contains(&target));
+        assert!(result
+            .data
+            .changed_keys
+            .contains(&token::balance_key(&token_addr, &target)));
+
+        // The tx was only dry run, so nothing has been written
+        let balance: Option<token::Amount> = StorageRead::read(
+            &client.storage,
+            &token::balance_key(&token_addr, &target),
+        )?;
+        assert_eq!(balance, None);
+
+        let result = RPC
+            .shell()
+            .dry_run_tx_verifiers(&client, Some(vec![0xff; 8]), None, false)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    /// Test that the gas estimate of a transfer is positive and stable, and
+    /// that the estimate of a rejected transfer reports the gas used and the
+    /// reason of the rejection.
+    #[tokio::test]
+    async fn test_shell_queries_estimate_gas() -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+
+        let token_addr = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let vp_always_true =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        for addr in [&token_addr, &target] {
+            StorageWrite::write_bytes(
+                &mut client.storage,
+                &Key::validity_predicate(addr),
+                &vp_always_true,
+            )?;
+        }
+        let transfer = token::Transfer {
+            source: address::testing::established_address_3(),
+            target: target.clone(),
+            token: token_addr.clone(),
+            sub_prefix: None,
+            amount: token::Amount::from(100),
+            key: None,
+            shielded: None,
+            memo: None,
+        };
+        let tx_code =
+            std::fs::read(TX_MINT_TOKENS_WASM).expect("cannot load wasm");
+        let tx = Tx::new(tx_code, Some(transfer.try_to_vec().unwrap()))
+            .sign(&key::testing::keypair_1(), &client.storage.chain_id);
+
+        let estimate = RPC
+            .shell()
+            .estimate_gas(&client, Some(tx.to_bytes()), None, false)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(estimate.outcome, DryRunOutcome::Accepted);
+        assert!(estimate.gas_used > 0);
+        // The estimate is stable
+        let again = RPC
+            .shell()
+            .estimate_gas(&client, Some(tx.to_bytes()), None, false)
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(again, estimate);
+
+        // The tx was only dry run, so nothing has been written
+        let balance: Option<token::Amount> = StorageRead::read(
+            &client.storage,
+            &token::balance_key(&token_addr, &target),
+        )?;
+        assert_eq!(balance, None);
+
+        // The target's VP rejects the transfer
+        StorageWrite::write_bytes(
+            &mut client.storage,
+            &Key::validity_predicate(&target),
+            std::fs::read(VP_ALWAYS_FALSE_WASM).expect("cannot load wasm"),
+        )?;
+        let rejected = RPC
+            .shell()
+            .estimate_gas(&client, Some(tx.to_bytes()), None, false)
+            .await
+            .unwrap()
+            .data;
+        assert!(rejected.gas_used > 0);
+        match rejected.outcome {
+            DryRunOutcome::Rejected(reason) => {
+                assert!(reason.contains(&target.to_string()), "{}", reason)
+            }
+            DryRunOutcome::Accepted => panic!("The transfer must be rejected"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shell_queries_vp_code_hash() -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+        let owner = address::testing::established_address_1();
+
+        // An implicit account has the default VP
+        let implicit = address::testing::gen_implicit_address();
+        let vp_code_hash =
+            RPC.shell().vp_code_hash(&client, &implicit).await.unwrap();
+        assert_eq!(vp_code_hash, VpCodeHash::Default);
+
+        // There is no VP established for the account yet
+        let vp_code_hash =
+            RPC.shell().vp_code_hash(&client, &owner).await.unwrap();
+        assert_eq!(vp_code_hash, VpCodeHash::Default);
+
+        // Establish a VP and check that its hash is returned
+        let vp_code = std::fs::read(VP_ALWAYS_TRUE_WASM).unwrap();
+        StorageWrite::write_bytes(
+            &mut client.storage,
+            &Key::validity_predicate(&owner),
+            &vp_code,
+        )?;
+        let vp_code_hash =
+            RPC.shell().vp_code_hash(&client, &owner).await.unwrap();
+        assert_eq!(vp_code_hash, VpCodeHash::Code(Hash::sha256(vp_code)));
+
+        Ok(())
+    }
+
+    /// Test that the IBC packets timed-out locally can be queried.
+    #[tokio::test]
+    async fn test_shell_queries_ibc_timed_out_packets(
+    ) -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+        client.ibc_packets =
+            IbcPacketTracker::new(DurationSecs(100), DurationSecs(100));
+        let packet_id = PacketId {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            sequence: 1,
+        };
+        let mut event = Event {
+            event_type: EventType::Ibc("send_packet".to_string()),
+            level: EventLevel::Tx,
+            attributes: HashMap::new(),
+        };
+        event["packet_src_port"] = packet_id.port_id.clone();
+        event["packet_src_channel"] = packet_id.channel_id.clone();
+        event["packet_sequence"] = packet_id.sequence.to_string();
+        let sent_at = DateTimeUtc::now();
+        client
+            .ibc_packets
+            .log_block(BlockHeight(1), sent_at, &[event]);
+
+        // The packet is still awaiting an acknowledgement
+        let packets = RPC.shell().ibc_timed_out_packets(&client).await.unwrap();
+        assert!(packets.is_empty());
+
+        // The packet is timed-out locally once the bound has elapsed
+        client.ibc_packets.log_block(
+            BlockHeight(2),
+            sent_at + DurationSecs(100),
+            &[],
+        );
+        let packets = RPC.shell().ibc_timed_out_packets(&client).await.unwrap();
+        assert_eq!(
+            packets,
+            vec![(
+                packet_id,
+                TrackedPacket {
+                    height: BlockHeight(1),
+                    sent_at,
+                    status: PacketStatus::TimedOutLocally,
+                }
+            )]
+        );
+
+        Ok(())
+    }
+
+    /// Test that the proof of a written key returned with its value verifies
+    /// against the committed root, but not with another value or root.
+    #[tokio::test]
+    async fn test_shell_queries_storage_value_proof() -> storage_api::Result<()>
+    {
+        let mut client = TestClient::new(RPC);
+        let token_addr = address::testing::established_address_1();
+        let owner = address::testing::established_address_2();
+        let balance_key = token::balance_key(&token_addr, &owner);
+        let balance = token::Amount::from(1000);
+        StorageWrite::write(&mut client.storage, &balance_key, balance)?;
+        client.storage.commit().unwrap();
+        let root = client.storage.merkle_root();
+
+        let response = RPC
+            .shell()
+            .storage_value(&client, None, None, true, &balance_key)
+            .await
+            .unwrap();
+        assert_eq!(response.data, balance.try_to_vec().unwrap());
+        let proof = Proof::try_from(response.proof.unwrap()).unwrap();
+        assert_eq!(proof.key, balance_key);
+        assert!(proof.verify::<Sha256Hasher>(Some(&response.data), &root));
+
+        // The proof doesn't prove another value, nor the non-existence of
+        // the key
+        let other = token::Amount::from(1).try_to_vec().unwrap();
+        assert!(!proof.verify::<Sha256Hasher>(Some(&other), &root));
+        assert!(!proof.verify::<Sha256Hasher>(None, &root));
+
+        // The proof doesn't verify against another root
+        StorageWrite::write(
+            &mut client.storage,
+            &balance_key,
+            token::Amount::from(1),
+        )?;
+        let other_root = client.storage.merkle_root();
+        assert!(
+            !proof.verify::<Sha256Hasher>(Some(&response.data), &other_root)
+        );
+
+        Ok(())
+    }
+
+    /// Test that the proof of a missing key is an exclusion proof that
+    /// verifies against the committed root.
+    #[tokio::test]
+    async fn test_shell_queries_storage_value_exclusion_proof(
+    ) -> storage_api::Result<()> {
+        let mut client = TestClient::new(RPC);
+        let ibc_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let key = ibc_prefix.push(&"written".to_string()).unwrap();
+        let missing_key = ibc_prefix.push(&"missing".to_string()).unwrap();
+        StorageWrite::write_bytes(&mut client.storage, &key, [1u8; 8])?;
+        client.storage.commit().unwrap();
+        let root = client.storage.merkle_root();
+
+        let response = RPC
+            .shell()
+            .storage_value(&client, None, None, true, &missing_key)
+            .await
+            .unwrap();
+        assert!(response.data.is_empty());
+        let proof = Proof::try_from(response.proof.unwrap()).unwrap();
+        assert_eq!(proof.key, missing_key);
+        assert!(proof.verify::<Sha256Hasher>(None, &root));
+        // The exclusion proof doesn't prove any value of the key
+        assert!(!proof.verify::<Sha256Hasher>(Some(&[1u8; 8]), &root));
+
+        // Once the key is written, its exclusion proof no longer verifies
+        StorageWrite::write_bytes(&mut client.storage, &missing_key, [2u8; 8])?;
+        let new_root = client.storage.merkle_root();
+        assert!(!proof.verify::<Sha256Hasher>(None, &new_root));
+
+        Ok(())
+    }
 }
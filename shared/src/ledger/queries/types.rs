@@ -1,4 +1,7 @@
+use crate::ledger::events::block_events::BlockEventLog;
+use crate::ledger::events::ibc_packets::IbcPacketTracker;
 use crate::ledger::events::log::EventLog;
+use crate::ledger::events::tx_results::TxResultLog;
 use crate::ledger::storage::{DBIter, Storage, StorageHasher, DB};
 use crate::ledger::storage_api;
 use crate::tendermint::merkle::proof::Proof;
@@ -20,6 +23,12 @@ where
     pub storage: &'shell Storage<D, H>,
     /// Log of events emitted by `FinalizeBlock` ABCI calls.
     pub event_log: &'shell EventLog,
+    /// Log of the results of the txs applied in the last blocks.
+    pub tx_results: &'shell TxResultLog,
+    /// Log of the events emitted by the last blocks.
+    pub block_events: &'shell BlockEventLog,
+    /// The IBC packets sent from this chain awaiting an acknowledgement.
+    pub ibc_packets: &'shell IbcPacketTracker,
     /// Cache of VP wasm compiled artifacts.
     #[cfg(feature = "wasm-runtime")]
     pub vp_wasm_cache: VpCache<WasmCacheRoAccess>,
@@ -1,5 +1,5 @@
 // Re-export to show in rustdoc!
-pub use pos::Pos;
+pub use pos::{ConsensusValidator, Pos, ValidatorSetAtEpoch};
 use pos::POS;
 mod pos;
 
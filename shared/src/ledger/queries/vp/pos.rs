@@ -1,13 +1,18 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada_proof_of_stake::types::WeightedValidator;
 use namada_proof_of_stake::PosReadOnly;
 
-use crate::ledger::pos::{self, BondId};
+use crate::ledger::parameters;
+use crate::ledger::pos::{self, into_tm_voting_power, BondId};
 use crate::ledger::queries::types::RequestCtx;
 use crate::ledger::storage::{DBIter, StorageHasher, DB};
-use crate::ledger::storage_api;
+use crate::ledger::storage_api::{self, ResultExt};
 use crate::types::address::Address;
+use crate::types::key::common;
 use crate::types::storage::Epoch;
+use crate::types::time::{DateTimeUtc, DurationSecs};
 use crate::types::token;
 
 // PoS validity predicate queries
@@ -20,8 +25,25 @@ router! {POS,
 
         ( "stake" / [validator: Address] / [epoch: opt Epoch] )
         -> token::Amount = validator_stake,
+
+        ( "stats" / [validator: Address] )
+        -> ValidatorStats = validator_stats,
     },
 
+    // A page of the stats of all the validators. The following pages are
+    // requested with the `next_offset` of the previous page.
+    ( "validators_stats" / [offset: u64] / [limit: u64] )
+    -> ValidatorStatsPage = validators_stats_page,
+
+    ( "validator_set" / [epoch: Epoch] )
+    -> ValidatorSetAtEpoch = validator_set,
+
+    ( "projected_validator_set" )
+    -> ProjectedValidatorSet = projected_validator_set,
+
+    ( "validator_queue" / [epoch: opt Epoch] )
+    -> ValidatorQueue = validator_queue,
+
     ( "total_stake" / [epoch: opt Epoch] )
     -> token::Amount = total_stake,
 
@@ -30,6 +52,117 @@ router! {POS,
 
     ( "bond_amount" / [owner: Address] / [validator: Address] / [epoch: opt Epoch] )
     -> token::Amount = bond_amount,
+
+    ( "unbonds" / [owner: Address] / [validator: Address] )
+    -> Vec<PendingUnbond> = unbonds,
+}
+
+/// A validator from the active validator set with its voting power and
+/// consensus key at some epoch.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ConsensusValidator {
+    /// Validator's address
+    pub address: Address,
+    /// Validator's voting power in the Tendermint context
+    pub voting_power: u64,
+    /// Validator's consensus key used for signing block votes
+    pub consensus_key: common::PublicKey,
+}
+
+/// The result of a validator set query for a given epoch.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum ValidatorSetAtEpoch {
+    /// The active validator set at the requested epoch
+    Known(Vec<ConsensusValidator>),
+    /// The requested epoch is beyond the pipeline length from the current
+    /// epoch, so its validator set is not yet determined
+    NotYetDetermined,
+    /// The validator set at the requested epoch has already been pruned from
+    /// storage
+    Unavailable,
+}
+
+/// A validator from the projected validator set with its bonded stake and
+/// voting power.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ProjectedValidator {
+    /// Validator's address
+    pub address: Address,
+    /// Validator's projected bonded stake
+    pub bonded_stake: token::Amount,
+    /// Validator's projected voting power in the Tendermint context
+    pub voting_power: u64,
+}
+
+/// The validator set of the next epoch, projected from the bonds and unbonds
+/// pending at the current epoch.
+///
+/// This is only a projection: the bonds, unbonds and slashes applied before
+/// the epoch boundary can still change the validator set of the next epoch.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ProjectedValidatorSet {
+    /// The epoch the validator set is projected for, i.e. the next epoch
+    pub epoch: Epoch,
+    /// The projected active validators, ordered by descending stake
+    pub active: Vec<ProjectedValidator>,
+    /// The projected inactive validators, ordered by descending stake
+    pub inactive: Vec<ProjectedValidator>,
+}
+
+/// The active validators and the queue of the inactive validators at some
+/// epoch.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorQueue {
+    /// The active validators, ordered by descending stake
+    pub active: Vec<WeightedValidator>,
+    /// The inactive validators, ordered by descending stake, i.e. the first
+    /// one is the next to become active
+    pub inactive: Vec<WeightedValidator>,
+}
+
+/// The maximum number of validators in a page of the validators' stats
+pub const MAX_VALIDATOR_STATS_PAGE_LIMIT: u64 = 100;
+
+/// The stake of a validator and its delegations at some epoch.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorStats {
+    /// Validator's address
+    pub address: Address,
+    /// Validator's total bonded stake, which is the sum of its self-bond and
+    /// delegations
+    pub bonded_stake: token::Amount,
+    /// The amount self-bonded by the validator
+    pub self_bond: token::Amount,
+    /// The amount delegated to the validator
+    pub delegated: token::Amount,
+    /// The number of unique delegators with a non-zero delegation to the
+    /// validator
+    pub delegators: u64,
+}
+
+/// A page of the stats of the validators, ordered by their address.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ValidatorStatsPage {
+    /// The stats of the validators in this page
+    pub validators: Vec<ValidatorStats>,
+    /// The epoch at which the stats were read. When the epoch of the
+    /// following pages differs, the pagination must be restarted.
+    pub epoch: Epoch,
+    /// The offset of the next page, if there are any more validators
+    pub next_offset: Option<u64>,
+}
+
+/// An unbond that is waiting to be withdrawn.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PendingUnbond {
+    /// The unbonded amount, before any slashes are applied
+    pub amount: token::Amount,
+    /// The epoch from which the unbonded tokens can be withdrawn
+    pub withdrawable_epoch: Epoch,
+    /// The earliest time at which the withdrawable epoch may start, estimated
+    /// from the chain's epoch schedule. `None` when the withdrawable epoch has
+    /// already started, i.e. the tokens can be withdrawn now.
+    pub withdrawable_time: Option<DateTimeUtc>,
 }
 
 // Handlers that implement the functions via `trait StorageRead`:
@@ -60,6 +193,132 @@ where
     ctx.storage.validator_addresses(epoch)
 }
 
+/// Get the active validator set at the given epoch together with the
+/// validators' voting power and consensus keys.
+fn validator_set<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    epoch: Epoch,
+) -> storage_api::Result<ValidatorSetAtEpoch>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let params = ctx.storage.read_pos_params()?;
+    if epoch > ctx.storage.last_epoch + params.pipeline_len {
+        return Ok(ValidatorSetAtEpoch::NotYetDetermined);
+    }
+    let validator_sets = ctx.storage.read_validator_set()?;
+    // Validator sets before the last update are dropped from the data
+    if epoch < validator_sets.last_update() {
+        return Ok(ValidatorSetAtEpoch::Unavailable);
+    }
+    let validator_set = match validator_sets.get(epoch) {
+        Some(validator_set) => validator_set,
+        None => return Ok(ValidatorSetAtEpoch::Unavailable),
+    };
+    let validators = validator_set
+        .active
+        .iter()
+        .map(|validator| {
+            let consensus_key = ctx
+                .storage
+                .read_validator_consensus_key(&validator.address)?
+                .and_then(|keys| keys.get(epoch).cloned())
+                .ok_or_else(|| {
+                    storage_api::Error::new_const(
+                        "Validator should have a consensus key",
+                    )
+                })?;
+            let voting_power = into_tm_voting_power(
+                params.tm_votes_per_token,
+                validator.bonded_stake,
+            ) as u64;
+            Ok(ConsensusValidator {
+                address: validator.address.clone(),
+                voting_power,
+                consensus_key,
+            })
+        })
+        .collect::<storage_api::Result<Vec<_>>>()?;
+    Ok(ValidatorSetAtEpoch::Known(validators))
+}
+
+/// Project the validator set of the next epoch from the validators' stake at
+/// the next epoch, which includes the bonds and unbonds pending at the current
+/// epoch, without waiting for the epoch transition.
+fn projected_validator_set<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+) -> storage_api::Result<ProjectedValidatorSet>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let params = ctx.storage.read_pos_params()?;
+    let epoch = ctx.storage.last_epoch.next();
+    let validator_sets = ctx.storage.read_validator_set()?;
+    let validator_set = validator_sets.get(epoch).ok_or_else(|| {
+        storage_api::Error::new_const(
+            "The validator set of the next epoch should be known",
+        )
+    })?;
+    // Re-rank the validators by their stake at the next epoch
+    let mut validators = validator_set
+        .active
+        .union(&validator_set.inactive)
+        .map(|validator| {
+            let bonded_stake =
+                ctx.storage.validator_stake(&validator.address, epoch)?;
+            Ok(WeightedValidator {
+                bonded_stake: bonded_stake.into(),
+                address: validator.address.clone(),
+            })
+        })
+        .collect::<storage_api::Result<BTreeSet<_>>>()?
+        .into_iter()
+        .rev()
+        .map(|validator| ProjectedValidator {
+            voting_power: into_tm_voting_power(
+                params.tm_votes_per_token,
+                validator.bonded_stake,
+            ) as u64,
+            bonded_stake: validator.bonded_stake.into(),
+            address: validator.address,
+        });
+    let active = validators
+        .by_ref()
+        .take(params.max_validator_slots as usize)
+        .collect();
+    let inactive = validators.collect();
+    Ok(ProjectedValidatorSet {
+        epoch,
+        active,
+        inactive,
+    })
+}
+
+/// Get the active validators and the ranked queue of the inactive validators
+/// at the given epoch or current when `None`.
+fn validator_queue<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    epoch: Option<Epoch>,
+) -> storage_api::Result<ValidatorQueue>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let epoch = epoch.unwrap_or(ctx.storage.last_epoch);
+    let validator_sets = ctx.storage.read_validator_set()?;
+    let validator_set = validator_sets.get(epoch).ok_or_else(|| {
+        storage_api::Error::new_const(
+            "The validator set should be known at the epoch",
+        )
+    })?;
+    Ok(ValidatorQueue {
+        active: validator_set.active.iter().rev().cloned().collect(),
+        inactive: validator_set.inactive.iter().rev().cloned().collect(),
+    })
+}
+
 /// Get the total stake of a validator at the given epoch or current when
 /// `None`. The total stake is a sum of validator's self-bonds and delegations
 /// to their address.
@@ -76,6 +335,120 @@ where
     ctx.storage.validator_stake(&validator, epoch)
 }
 
+/// Get the stats of a validator at the current epoch. A validator without any
+/// delegations has zero delegated amount and delegators.
+fn validator_stats<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    validator: Address,
+) -> storage_api::Result<ValidatorStats>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if !ctx.storage.is_validator(&validator)? {
+        return Err(storage_api::Error::new_const(
+            "The given address is not a validator",
+        ));
+    }
+    let mut stats = read_validators_stats(ctx, [validator])?;
+    Ok(stats.remove(0))
+}
+
+/// Get a page of the stats of all the validators at the current epoch,
+/// ordered by their address.
+fn validators_stats_page<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    offset: u64,
+    limit: u64,
+) -> storage_api::Result<ValidatorStatsPage>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if limit == 0 || limit > MAX_VALIDATOR_STATS_PAGE_LIMIT {
+        return Err(storage_api::Error::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "The page limit must be between 1 and \
+                 {MAX_VALIDATOR_STATS_PAGE_LIMIT}, got {limit}"
+            ),
+        )));
+    }
+    let epoch = ctx.storage.last_epoch;
+    let validators: BTreeSet<Address> = ctx
+        .storage
+        .validator_addresses(epoch)?
+        .into_iter()
+        .collect();
+    let num_validators = validators.len() as u64;
+    let validators = validators
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize);
+    let end = offset.saturating_add(limit);
+    let next_offset = (end < num_validators).then_some(end);
+    Ok(ValidatorStatsPage {
+        validators: read_validators_stats(ctx, validators)?,
+        epoch,
+        next_offset,
+    })
+}
+
+/// Read the stats of the given validators at the current epoch, in the same
+/// order, by aggregating all the bonds.
+fn read_validators_stats<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    validators: impl IntoIterator<Item = Address>,
+) -> storage_api::Result<Vec<ValidatorStats>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let epoch = ctx.storage.last_epoch;
+    let mut stats = validators
+        .into_iter()
+        .map(|address| {
+            let bonded_stake = ctx.storage.validator_stake(&address, epoch)?;
+            Ok((
+                address.clone(),
+                ValidatorStats {
+                    address,
+                    bonded_stake,
+                    self_bond: token::Amount::default(),
+                    delegated: token::Amount::default(),
+                    delegators: 0,
+                },
+            ))
+        })
+        .collect::<storage_api::Result<Vec<_>>>()?;
+    let index: BTreeMap<Address, usize> = stats
+        .iter()
+        .enumerate()
+        .map(|(ix, (address, _))| (address.clone(), ix))
+        .collect();
+    for iter_result in
+        storage_api::iter_prefix_bytes(ctx.storage, &pos::bonds_prefix())?
+    {
+        let (key, _bonds_bytes) = iter_result?;
+        let bond_id = match pos::is_bond_key(&key) {
+            Some(bond_id) => bond_id,
+            None => continue,
+        };
+        let validator_stats = match index.get(&bond_id.validator) {
+            Some(ix) => &mut stats[*ix].1,
+            None => continue,
+        };
+        let amount = ctx.storage.bond_amount(&bond_id, epoch)?;
+        if bond_id.source == bond_id.validator {
+            validator_stats.self_bond += amount;
+        } else if amount != token::Amount::default() {
+            validator_stats.delegated += amount;
+            validator_stats.delegators += 1;
+        }
+    }
+    Ok(stats.into_iter().map(|(_, stats)| stats).collect())
+}
+
 /// Get the total stake in PoS system at the given epoch or current when `None`.
 fn total_stake<D, H>(
     ctx: RequestCtx<'_, D, H>,
@@ -111,6 +484,61 @@ where
     ctx.storage.bond_amount(&bond_id, epoch)
 }
 
+/// Get the pending unbonds for the given bond ID (this may be delegation or
+/// self-bond when `owner == validator`), ordered by their withdrawable epoch.
+fn unbonds<D, H>(
+    ctx: RequestCtx<'_, D, H>,
+    owner: Address,
+    validator: Address,
+) -> storage_api::Result<Vec<PendingUnbond>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let bond_id = BondId {
+        source: owner,
+        validator,
+    };
+    let unbonds = match ctx.storage.read_unbond(&bond_id)? {
+        Some(unbonds) => unbonds,
+        None => return Ok(vec![]),
+    };
+    let (epoch_duration, _gas) =
+        parameters::read_epoch_duration_parameter(ctx.storage)
+            .into_storage_result()?;
+
+    let current_epoch = ctx.storage.last_epoch;
+    let mut pending_unbonds: Vec<PendingUnbond> = unbonds
+        .iter()
+        .flat_map(|unbond| unbond.deltas.iter())
+        .map(|((_bond_start, unbond_end), amount)| {
+            let withdrawable_epoch = *unbond_end + 1_u64;
+            // The next epoch starts at the earliest at the next epoch's
+            // minimum start time and every epoch after that lasts at least
+            // the minimum duration
+            let withdrawable_time = if withdrawable_epoch <= current_epoch {
+                None
+            } else {
+                let epochs_after_next =
+                    withdrawable_epoch.0 - current_epoch.0 - 1;
+                Some(
+                    ctx.storage.next_epoch_min_start_time
+                        + DurationSecs(
+                            epoch_duration.min_duration.0 * epochs_after_next,
+                        ),
+                )
+            };
+            PendingUnbond {
+                amount: *amount,
+                withdrawable_epoch,
+                withdrawable_time,
+            }
+        })
+        .collect();
+    pending_unbonds.sort_by_key(|unbond| unbond.withdrawable_epoch);
+    Ok(pending_unbonds)
+}
+
 /// Find all the validator addresses to whom the given `owner` address has
 /// some delegation in any epoch
 fn delegations<D, H>(
@@ -138,3 +566,523 @@ where
     }
     Ok(delegations)
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeSet, HashMap};
+
+    use namada_proof_of_stake::types::{
+        Bond, Bonds, Unbond, Unbonds, ValidatorConsensusKeys, ValidatorDeltas,
+        ValidatorSet, ValidatorSets, ValidatorState, ValidatorStates,
+    };
+    use namada_proof_of_stake::PosBase;
+
+    use super::*;
+    use crate::ledger::pos::PosParams;
+    use crate::ledger::queries::testing::TestClient;
+    use crate::ledger::queries::RPC;
+    use crate::ledger::storage_api::StorageWrite;
+    use crate::types::address;
+    use crate::types::key::{self, RefTo};
+
+    fn validator_set(validators: &[(&Address, u64)]) -> ValidatorSet {
+        ValidatorSet {
+            active: validators
+                .iter()
+                .map(|(address, bonded_stake)| WeightedValidator {
+                    bonded_stake: *bonded_stake,
+                    address: (*address).clone(),
+                })
+                .collect(),
+            inactive: BTreeSet::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validator_set_query() {
+        let mut client = TestClient::new(RPC);
+        let params = PosParams {
+            pipeline_len: 1,
+            unbonding_len: 1,
+            ..Default::default()
+        };
+        client.storage.write_pos_params(&params);
+
+        let validator_1 = address::testing::established_address_1();
+        let validator_2 = address::testing::established_address_2();
+        let pk_1 = key::testing::keypair_1().ref_to();
+        let pk_2 = key::testing::keypair_2().ref_to();
+        client.storage.write_validator_consensus_key(
+            &validator_1,
+            &ValidatorConsensusKeys::init_at_genesis(pk_1.clone(), Epoch(0)),
+        );
+        client.storage.write_validator_consensus_key(
+            &validator_2,
+            &ValidatorConsensusKeys::init_at_genesis(pk_2.clone(), Epoch(0)),
+        );
+
+        // Seed a different validator set for epochs 0 and 1
+        let mut validator_sets = ValidatorSets::init_at_genesis(
+            validator_set(&[(&validator_1, 100)]),
+            Epoch(0),
+        );
+        validator_sets.set(
+            validator_set(&[(&validator_1, 100), (&validator_2, 200)]),
+            Epoch(0),
+            &params,
+        );
+        client.storage.write_validator_set(&validator_sets);
+
+        let epoch_0 = RPC
+            .vp()
+            .pos()
+            .validator_set(&client, &Epoch(0))
+            .await
+            .unwrap();
+        assert_eq!(
+            epoch_0,
+            ValidatorSetAtEpoch::Known(vec![ConsensusValidator {
+                address: validator_1.clone(),
+                voting_power: 100,
+                consensus_key: pk_1.clone(),
+            }])
+        );
+        let epoch_1 = RPC
+            .vp()
+            .pos()
+            .validator_set(&client, &Epoch(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            epoch_1,
+            ValidatorSetAtEpoch::Known(vec![
+                ConsensusValidator {
+                    address: validator_1.clone(),
+                    voting_power: 100,
+                    consensus_key: pk_1,
+                },
+                ConsensusValidator {
+                    address: validator_2.clone(),
+                    voting_power: 200,
+                    consensus_key: pk_2,
+                },
+            ])
+        );
+
+        // An epoch beyond the pipeline offset is not yet determined
+        let epoch_2 = RPC
+            .vp()
+            .pos()
+            .validator_set(&client, &Epoch(2))
+            .await
+            .unwrap();
+        assert_eq!(epoch_2, ValidatorSetAtEpoch::NotYetDetermined);
+
+        // After an update in the next epoch, epoch 0 gets pruned
+        client.storage.last_epoch = Epoch(1);
+        validator_sets.set(
+            validator_set(&[(&validator_2, 200)]),
+            Epoch(1),
+            &params,
+        );
+        client.storage.write_validator_set(&validator_sets);
+        let epoch_0 = RPC
+            .vp()
+            .pos()
+            .validator_set(&client, &Epoch(0))
+            .await
+            .unwrap();
+        assert_eq!(epoch_0, ValidatorSetAtEpoch::Unavailable);
+    }
+
+    /// Test that the projected validator set of the next epoch reflects a
+    /// pending unbond that drops a validator out of the active set.
+    #[tokio::test]
+    async fn test_projected_validator_set_query() {
+        let mut client = TestClient::new(RPC);
+        let params = PosParams {
+            max_validator_slots: 2,
+            pipeline_len: 1,
+            unbonding_len: 1,
+            ..Default::default()
+        };
+        client.storage.write_pos_params(&params);
+
+        let validator_1 = address::testing::established_address_1();
+        let validator_2 = address::testing::established_address_2();
+        let validator_3 = address::testing::established_address_3();
+        let stakes = [(&validator_1, 100), (&validator_2, 200)];
+        let mut validator_set = validator_set(&stakes);
+        validator_set.inactive.insert(WeightedValidator {
+            bonded_stake: 50,
+            address: validator_3.clone(),
+        });
+        client
+            .storage
+            .write_validator_set(&ValidatorSets::init_at_genesis(
+                validator_set,
+                Epoch(0),
+            ));
+        for (validator, stake) in stakes.into_iter().chain([(&validator_3, 50)])
+        {
+            client.storage.write_validator_deltas(
+                validator,
+                &ValidatorDeltas::init_at_genesis(
+                    token::Change::from(stake),
+                    Epoch(0),
+                ),
+            );
+        }
+
+        let projected = |voting_powers: &[(&Address, u64)]| {
+            voting_powers
+                .iter()
+                .map(|(address, stake)| ProjectedValidator {
+                    address: (*address).clone(),
+                    bonded_stake: token::Amount::from(*stake),
+                    voting_power: *stake,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Without any pending changes, the projection is the current set
+        let projection = RPC
+            .vp()
+            .pos()
+            .projected_validator_set(&client)
+            .await
+            .unwrap();
+        assert_eq!(
+            projection,
+            ProjectedValidatorSet {
+                epoch: Epoch(1),
+                active: projected(&[(&validator_2, 200), (&validator_1, 100)]),
+                inactive: projected(&[(&validator_3, 50)]),
+            }
+        );
+
+        // A pending unbond of 160 from validator 2 takes effect at the next
+        // epoch and drops it below validator 3, out of the active set
+        let mut deltas =
+            PosBase::read_validator_deltas(&client.storage, &validator_2)
+                .unwrap();
+        deltas.add(-160, Epoch(0), &params);
+        client.storage.write_validator_deltas(&validator_2, &deltas);
+
+        let projection = RPC
+            .vp()
+            .pos()
+            .projected_validator_set(&client)
+            .await
+            .unwrap();
+        assert_eq!(
+            projection,
+            ProjectedValidatorSet {
+                epoch: Epoch(1),
+                active: projected(&[(&validator_1, 100), (&validator_3, 50)]),
+                inactive: projected(&[(&validator_2, 40)]),
+            }
+        );
+    }
+
+    /// Test that at the beginning of an epoch, the active set is re-selected
+    /// as the validators with the most stake and that the validators tied at
+    /// the boundary of the active set are selected by their address.
+    #[tokio::test]
+    async fn test_validator_queue_query() {
+        let mut client = TestClient::new(RPC);
+        let params = PosParams {
+            max_validator_slots: 2,
+            pipeline_len: 1,
+            unbonding_len: 1,
+            ..Default::default()
+        };
+        client.storage.write_pos_params(&params);
+
+        // 4 validators for 2 slots, with 2 of them tied at the boundary
+        let weighted =
+            |address: Address, bonded_stake: u64| WeightedValidator {
+                bonded_stake,
+                address,
+            };
+        let top = weighted(address::testing::established_address_1(), 300);
+        let mut tied = [
+            weighted(address::testing::established_address_2(), 200),
+            weighted(address::testing::established_address_3(), 200),
+        ];
+        tied.sort();
+        let [tied_low, tied_high] = tied;
+        let bottom = weighted(address::testing::established_address_4(), 100);
+
+        // Seed a set where the boundary tie isn't resolved by the address and
+        // a validator with less stake is active
+        let validator_set = ValidatorSet {
+            active: BTreeSet::from([bottom.clone(), tied_low.clone()]),
+            inactive: BTreeSet::from([top.clone(), tied_high.clone()]),
+        };
+        client
+            .storage
+            .write_validator_set(&ValidatorSets::init_at_genesis(
+                validator_set,
+                Epoch(0),
+            ));
+
+        let queue = RPC
+            .vp()
+            .pos()
+            .validator_queue(&client, &Some(Epoch(0)))
+            .await
+            .unwrap();
+        assert_eq!(queue.active, vec![tied_low.clone(), bottom.clone()]);
+        assert_eq!(queue.inactive, vec![top.clone(), tied_high.clone()]);
+
+        // The validator sets from the pipeline offset are re-selected
+        client.storage.rebalance_validator_sets(Epoch(0));
+        let queue = RPC
+            .vp()
+            .pos()
+            .validator_queue(&client, &Some(Epoch(1)))
+            .await
+            .unwrap();
+        assert_eq!(
+            queue,
+            ValidatorQueue {
+                active: vec![top, tied_high],
+                inactive: vec![tied_low, bottom],
+            }
+        );
+        // The selection is deterministic
+        client.storage.rebalance_validator_sets(Epoch(0));
+        let requeued = RPC
+            .vp()
+            .pos()
+            .validator_queue(&client, &Some(Epoch(1)))
+            .await
+            .unwrap();
+        assert_eq!(requeued, queue);
+    }
+
+    /// Test that the pending unbonds are reported with their withdrawable
+    /// epoch and the estimated time at which it starts, and that an unbond
+    /// whose withdrawable epoch has already started is withdrawable now.
+    #[tokio::test]
+    async fn test_unbonds_query() {
+        let mut client = TestClient::new(RPC);
+        let min_duration = DurationSecs(60);
+        parameters::update_epoch_parameter(
+            &mut client.storage,
+            &parameters::EpochDuration {
+                min_num_of_blocks: 10,
+                min_duration,
+            },
+        )
+        .unwrap();
+        client.storage.last_epoch = Epoch(5);
+        let next_epoch_start = client.storage.next_epoch_min_start_time;
+
+        let owner = address::testing::established_address_1();
+        let validator = address::testing::established_address_2();
+        let bond_id = BondId {
+            source: owner.clone(),
+            validator: validator.clone(),
+        };
+
+        // No unbonds yet
+        let unbonds = RPC
+            .vp()
+            .pos()
+            .unbonds(&client, &owner, &validator)
+            .await
+            .unwrap();
+        assert!(unbonds.is_empty());
+
+        // One unbond withdrawable from epoch 4, which has passed, and one
+        // withdrawable from epoch 8
+        let unbond = Unbond {
+            deltas: HashMap::from_iter([
+                ((Epoch(1), Epoch(7)), token::Amount::from(200)),
+                ((Epoch(0), Epoch(3)), token::Amount::from(100)),
+            ]),
+        };
+        StorageWrite::write(
+            &mut client.storage,
+            &pos::unbond_key(&bond_id),
+            Unbonds::init_at_genesis(unbond, Epoch(3)),
+        )
+        .unwrap();
+
+        let unbonds = RPC
+            .vp()
+            .pos()
+            .unbonds(&client, &owner, &validator)
+            .await
+            .unwrap();
+        assert_eq!(
+            unbonds,
+            vec![
+                PendingUnbond {
+                    amount: token::Amount::from(100),
+                    withdrawable_epoch: Epoch(4),
+                    withdrawable_time: None,
+                },
+                PendingUnbond {
+                    amount: token::Amount::from(200),
+                    withdrawable_epoch: Epoch(8),
+                    // Epoch 6 starts at the earliest at the next epoch's
+                    // start time, and epoch 8 two epochs after that
+                    withdrawable_time: Some(
+                        next_epoch_start + DurationSecs(2 * min_duration.0)
+                    ),
+                },
+            ]
+        );
+    }
+
+    /// Test that the stats of the validators aggregate their self-bonds and
+    /// the delegations from multiple delegators, that a fully unbonded
+    /// delegator is not counted and that the stats of all the validators are
+    /// paginated.
+    #[tokio::test]
+    async fn test_validator_stats_query() {
+        let mut client = TestClient::new(RPC);
+        let params = PosParams {
+            pipeline_len: 1,
+            unbonding_len: 1,
+            ..Default::default()
+        };
+        client.storage.write_pos_params(&params);
+
+        let validator_1 = address::testing::established_address_1();
+        let validator_2 = address::testing::established_address_2();
+        let delegator_1 = address::testing::established_address_3();
+        let delegator_2 = address::testing::established_address_4();
+        let stakes = [(&validator_1, 150), (&validator_2, 50)];
+        client
+            .storage
+            .write_validator_set(&ValidatorSets::init_at_genesis(
+                validator_set(&stakes),
+                Epoch(0),
+            ));
+        for (validator, stake) in stakes {
+            client.storage.write_validator_state(
+                validator,
+                &ValidatorStates::init_at_genesis(
+                    ValidatorState::Candidate,
+                    Epoch(0),
+                ),
+            );
+            client.storage.write_validator_deltas(
+                validator,
+                &ValidatorDeltas::init_at_genesis(
+                    token::Change::from(stake),
+                    Epoch(0),
+                ),
+            );
+        }
+        let mut write_bond = |source: &Address,
+                              validator: &Address,
+                              bonded: u64,
+                              unbonded: u64| {
+            let bond = Bond {
+                pos_deltas: HashMap::from_iter([(
+                    Epoch(0),
+                    token::Amount::from(bonded),
+                )]),
+                neg_deltas: token::Amount::from(unbonded),
+            };
+            let bond_id = BondId {
+                source: source.clone(),
+                validator: validator.clone(),
+            };
+            StorageWrite::write(
+                &mut client.storage,
+                &pos::bond_key(&bond_id),
+                Bonds::init_at_genesis(bond, Epoch(0)),
+            )
+            .unwrap();
+        };
+        write_bond(&validator_1, &validator_1, 100, 0);
+        write_bond(&delegator_1, &validator_1, 30, 0);
+        write_bond(&delegator_2, &validator_1, 25, 5);
+        write_bond(&validator_2, &validator_2, 50, 0);
+        // A delegation that has been fully unbonded
+        write_bond(&delegator_1, &validator_2, 10, 10);
+
+        let stats_1 = ValidatorStats {
+            address: validator_1.clone(),
+            bonded_stake: token::Amount::from(150),
+            self_bond: token::Amount::from(100),
+            delegated: token::Amount::from(50),
+            delegators: 2,
+        };
+        let stats_2 = ValidatorStats {
+            address: validator_2.clone(),
+            bonded_stake: token::Amount::from(50),
+            self_bond: token::Amount::from(50),
+            delegated: token::Amount::default(),
+            delegators: 0,
+        };
+        let stats = RPC
+            .vp()
+            .pos()
+            .validator_stats(&client, &validator_1)
+            .await
+            .unwrap();
+        assert_eq!(stats, stats_1);
+        // A validator without any delegators
+        let stats = RPC
+            .vp()
+            .pos()
+            .validator_stats(&client, &validator_2)
+            .await
+            .unwrap();
+        assert_eq!(stats, stats_2);
+        // Not a validator
+        assert!(RPC
+            .vp()
+            .pos()
+            .validator_stats(&client, &delegator_1)
+            .await
+            .is_err());
+
+        // The stats of all the validators are paginated in the order of their
+        // address
+        let mut all_stats = vec![stats_1, stats_2];
+        all_stats.sort_by(|a, b| a.address.cmp(&b.address));
+        let page = RPC
+            .vp()
+            .pos()
+            .validators_stats_page(&client, &0, &1)
+            .await
+            .unwrap();
+        assert_eq!(
+            page,
+            ValidatorStatsPage {
+                validators: all_stats[..1].to_vec(),
+                epoch: Epoch(0),
+                next_offset: Some(1),
+            }
+        );
+        let page = RPC
+            .vp()
+            .pos()
+            .validators_stats_page(&client, &1, &1)
+            .await
+            .unwrap();
+        assert_eq!(
+            page,
+            ValidatorStatsPage {
+                validators: all_stats[1..].to_vec(),
+                epoch: Epoch(0),
+                next_offset: None,
+            }
+        );
+        // The page limit must not be zero
+        assert!(RPC
+            .vp()
+            .pos()
+            .validators_stats_page(&client, &0, &0)
+            .await
+            .is_err());
+    }
+}
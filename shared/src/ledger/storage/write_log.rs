@@ -6,6 +6,7 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 use thiserror::Error;
 
 use crate::ledger;
+use crate::ledger::gas::STORAGE_ACCESS_GAS_PER_BYTE;
 use crate::ledger::storage::{Storage, StorageHasher};
 use crate::types::address::{Address, EstablishedAddressGen};
 use crate::types::ibc::IbcEvent;
@@ -107,9 +108,9 @@ impl WriteLog {
                         key.len() + value.len()
                     }
                 };
-                (Some(v), gas as _)
+                (Some(v), gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE)
             }
-            None => (None, key.len() as _),
+            None => (None, key.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE),
         }
     }
 
@@ -135,9 +136,9 @@ impl WriteLog {
                         key.len() + value.len()
                     }
                 };
-                (Some(v), gas as _)
+                (Some(v), gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE)
             }
-            None => (None, key.len() as _),
+            None => (None, key.len() as u64 * STORAGE_ACCESS_GAS_PER_BYTE),
         }
     }
 
@@ -173,7 +174,7 @@ impl WriteLog {
             // the previous value exists on the storage
             None => len as i64,
         };
-        Ok((gas as _, size_diff))
+        Ok((gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE, size_diff))
     }
 
     /// Write a key and a value and return the gas cost and the size difference
@@ -210,7 +211,7 @@ impl WriteLog {
             // the previous value exists on the storage
             None => len as i64,
         };
-        Ok((gas as _, size_diff))
+        Ok((gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE, size_diff))
     }
 
     /// Delete a key and its value, and return the gas cost and the size
@@ -238,7 +239,7 @@ impl WriteLog {
             None => 0,
         };
         let gas = key.len() + size_diff as usize;
-        Ok((gas as _, -size_diff))
+        Ok((gas as u64 * STORAGE_ACCESS_GAS_PER_BYTE, -size_diff))
     }
 
     /// Initialize a new account and return the gas cost.
@@ -254,7 +255,7 @@ impl WriteLog {
         let addr =
             address_gen.generate_address("TODO more randomness".as_bytes());
         let key = storage::Key::validity_predicate(&addr);
-        let gas = (key.len() + vp.len()) as _;
+        let gas = (key.len() + vp.len()) as u64 * STORAGE_ACCESS_GAS_PER_BYTE;
         self.tx_write_log
             .insert(key, StorageModification::InitAccount { vp });
         (addr, gas)
@@ -277,6 +278,20 @@ impl WriteLog {
         self.tx_write_log.keys().cloned().collect()
     }
 
+    /// Iterate the storage modifications of the current transaction, including
+    /// the deletions, in no particular order
+    pub fn tx_modifications(
+        &self,
+    ) -> impl Iterator<Item = (&storage::Key, &StorageModification)> {
+        self.tx_write_log.iter()
+    }
+
+    /// Get the storage keys changed in the current block by the committed
+    /// transactions
+    pub fn get_block_keys(&self) -> BTreeSet<storage::Key> {
+        self.block_write_log.keys().cloned().collect()
+    }
+
     /// Get the storage keys changed in the current transaction (left) and
     /// the addresses of accounts initialized in the current transaction
     /// (right). The first vector excludes keys of validity predicates of
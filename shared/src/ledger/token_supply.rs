@@ -0,0 +1,163 @@
+//! The total supply of the tokens, to be queried for past epochs.
+//!
+//! The shell keeps the current supply of each known token in the token's
+//! storage. It's updated at the end of every block from the balances changed
+//! in the block and it's recorded for the epoch when an epoch ends. The
+//! supplies of the ended epochs are retained for
+//! [`TOKEN_SUPPLY_RETENTION_EPOCHS`] epochs.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::ledger::storage_api::{self, StorageRead};
+use crate::types::address::Address;
+use crate::types::storage::{Epoch, Key, KeySeg};
+use crate::types::token;
+
+/// The number of epochs for which the supplies of the ended epochs are
+/// retained
+pub const TOKEN_SUPPLY_RETENTION_EPOCHS: u64 = 100;
+
+/// The storage key segment of the total supply of a token
+const TOTAL_SUPPLY_STORAGE_KEY: &str = "total_supply";
+
+/// The total supply of a token at the end of an epoch
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum EpochSupply {
+    /// The supply at the end of the epoch, or at the last committed block
+    /// for the current epoch
+    Available(token::Amount),
+    /// The supply of the epoch is not retained, because it's past the
+    /// retention period or it ended before the supplies were recorded
+    Unavailable,
+}
+
+/// Obtain a storage key for the current total supply of a token.
+pub fn total_supply_key(token: &Address) -> Key {
+    Key::from(token.to_db_key())
+        .push(&TOTAL_SUPPLY_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Obtain a storage key for the total supply of a token at the end of the
+/// given epoch.
+pub fn epoch_total_supply_key(token: &Address, epoch: Epoch) -> Key {
+    total_supply_key(token)
+        .push(&epoch.0)
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the epoch whose supplies are past the retention period once the given
+/// epoch has ended, if any
+pub fn expired_epoch(ended_epoch: Epoch) -> Option<Epoch> {
+    ended_epoch
+        .0
+        .checked_sub(TOKEN_SUPPLY_RETENTION_EPOCHS + 1)
+        .map(Epoch)
+}
+
+/// Read the current total supply of a token. When it's not been recorded
+/// yet, it's summed up from the balances.
+pub fn read_total_supply<S>(
+    storage: &S,
+    token: &Address,
+) -> storage_api::Result<token::Amount>
+where
+    S: for<'iter> StorageRead<'iter>,
+{
+    match storage.read(&total_supply_key(token))? {
+        Some(supply) => Ok(supply),
+        None => sum_balances(storage, token),
+    }
+}
+
+/// Read the total supply of a token at the end of the given ended epoch
+pub fn read_epoch_total_supply<S>(
+    storage: &S,
+    token: &Address,
+    epoch: Epoch,
+) -> storage_api::Result<EpochSupply>
+where
+    S: for<'iter> StorageRead<'iter>,
+{
+    Ok(storage
+        .read(&epoch_total_supply_key(token, epoch))?
+        .map(EpochSupply::Available)
+        .unwrap_or(EpochSupply::Unavailable))
+}
+
+/// Sum up the balances of a token
+fn sum_balances<S>(
+    storage: &S,
+    token: &Address,
+) -> storage_api::Result<token::Amount>
+where
+    S: for<'iter> StorageRead<'iter>,
+{
+    let balances = storage_api::iter_prefix::<token::Amount>(
+        storage,
+        &token::balance_prefix(token),
+    )?;
+    let mut supply = token::Amount::default();
+    for balance in balances {
+        let (_key, balance) = balance?;
+        supply = supply.saturating_add(&balance);
+    }
+    Ok(supply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::storage::testing::TestStorage;
+    use crate::ledger::storage_api::StorageWrite;
+    use crate::types::address;
+
+    /// Test that the total supply is summed up from the balances until it's
+    /// recorded, and that it's not read from the balances once it is.
+    #[test]
+    fn test_read_total_supply() {
+        let mut storage = TestStorage::default();
+        let token = address::nam();
+        let owner = address::testing::established_address_1();
+        storage
+            .write(&token::balance_key(&token, &owner), token::Amount::from(10))
+            .unwrap();
+        assert_eq!(
+            read_total_supply(&storage, &token).unwrap(),
+            token::Amount::from(10)
+        );
+
+        storage
+            .write(&total_supply_key(&token), token::Amount::from(20))
+            .unwrap();
+        assert_eq!(
+            read_total_supply(&storage, &token).unwrap(),
+            token::Amount::from(20)
+        );
+        assert_eq!(
+            read_epoch_total_supply(&storage, &token, Epoch(0)).unwrap(),
+            EpochSupply::Unavailable
+        );
+    }
+
+    /// Test that an epoch's supply expires after the retention period.
+    #[test]
+    fn test_expired_epoch() {
+        assert_eq!(expired_epoch(Epoch(TOKEN_SUPPLY_RETENTION_EPOCHS)), None);
+        assert_eq!(
+            expired_epoch(Epoch(TOKEN_SUPPLY_RETENTION_EPOCHS + 1)),
+            Some(Epoch(0))
+        );
+    }
+
+    /// Test that the total supply keys aren't balance keys.
+    #[test]
+    fn test_total_supply_keys() {
+        let token = address::nam();
+        let key = total_supply_key(&token);
+        assert!(token::is_any_token_balance_key(&key).is_none());
+        let epoch_key = epoch_total_supply_key(&token, Epoch(1));
+        assert!(epoch_key.segments.starts_with(&key.segments));
+        assert!(token::is_any_token_balance_key(&epoch_key).is_none());
+    }
+}
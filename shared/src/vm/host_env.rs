@@ -13,7 +13,10 @@ use super::wasm::TxCache;
 #[cfg(feature = "wasm-runtime")]
 use super::wasm::VpCache;
 use super::WasmCacheAccess;
-use crate::ledger::gas::{self, BlockGasMeter, VpGasMeter, MIN_STORAGE_GAS};
+use crate::ledger::gas::{
+    self, BlockGasMeter, VpGasMeter, MIN_STORAGE_GAS, VERIFY_TX_SIG_GAS_COST,
+    WASM_VALIDATION_GAS_PER_BYTE,
+};
 use crate::ledger::storage::write_log::{self, WriteLog};
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::ledger::vp_host_fns;
@@ -23,15 +26,13 @@ use crate::types::ibc::IbcEvent;
 use crate::types::internal::HostEnvResult;
 use crate::types::key::*;
 use crate::types::storage::{Key, TxIndex};
+use crate::types::validity_predicate::{VpEvent, VpRejection};
 use crate::vm::memory::VmMemory;
 use crate::vm::prefix_iter::{PrefixIteratorId, PrefixIterators};
 use crate::vm::{
     validate_untrusted_wasm, HostRef, MutHostRef, WasmValidationError,
 };
 
-const VERIFY_TX_SIG_GAS_COST: u64 = 1000;
-const WASM_VALIDATION_GAS_PER_BYTE: u64 = 1;
-
 /// These runtime errors will abort tx WASM execution immediately
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -250,6 +251,10 @@ where
     pub eval_runner: HostRef<'a, &'a EVAL>,
     /// Cache for 2-step reads from host environment.
     pub result_buffer: MutHostRef<'a, &'a Option<Vec<u8>>>,
+    /// The events emitted by the VP
+    pub events: MutHostRef<'a, &'a Vec<VpEvent>>,
+    /// The details of the rejection of the tx given by the VP, if any
+    pub rejection: MutHostRef<'a, &'a Option<VpRejection>>,
     /// The storage keys that have been changed. Used for calls to `eval`.
     pub keys_changed: HostRef<'a, &'a BTreeSet<Key>>,
     /// The verifiers whose validity predicates should be triggered. Used for
@@ -314,6 +319,8 @@ where
         iterators: &mut PrefixIterators<'a, DB>,
         verifiers: &BTreeSet<Address>,
         result_buffer: &mut Option<Vec<u8>>,
+        events: &mut Vec<VpEvent>,
+        rejection: &mut Option<VpRejection>,
         keys_changed: &BTreeSet<Key>,
         eval_runner: &EVAL,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
@@ -328,6 +335,8 @@ where
             iterators,
             verifiers,
             result_buffer,
+            events,
+            rejection,
             keys_changed,
             eval_runner,
             #[cfg(feature = "wasm-runtime")]
@@ -379,6 +388,8 @@ where
         iterators: &mut PrefixIterators<'a, DB>,
         verifiers: &BTreeSet<Address>,
         result_buffer: &mut Option<Vec<u8>>,
+        events: &mut Vec<VpEvent>,
+        rejection: &mut Option<VpRejection>,
         keys_changed: &BTreeSet<Key>,
         eval_runner: &EVAL,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
@@ -392,6 +403,8 @@ where
         let gas_meter = unsafe { MutHostRef::new(gas_meter) };
         let verifiers = unsafe { HostRef::new(verifiers) };
         let result_buffer = unsafe { MutHostRef::new(result_buffer) };
+        let events = unsafe { MutHostRef::new(events) };
+        let rejection = unsafe { MutHostRef::new(rejection) };
         let keys_changed = unsafe { HostRef::new(keys_changed) };
         let eval_runner = unsafe { HostRef::new(eval_runner) };
         #[cfg(feature = "wasm-runtime")]
@@ -406,6 +419,8 @@ where
             tx_index,
             eval_runner,
             result_buffer,
+            events,
+            rejection,
             keys_changed,
             verifiers,
             #[cfg(feature = "wasm-runtime")]
@@ -434,6 +449,8 @@ where
             tx_index: self.tx_index.clone(),
             eval_runner: self.eval_runner.clone(),
             result_buffer: self.result_buffer.clone(),
+            events: self.events.clone(),
+            rejection: self.rejection.clone(),
             keys_changed: self.keys_changed.clone(),
             verifiers: self.verifiers.clone(),
             #[cfg(feature = "wasm-runtime")]
@@ -1806,7 +1823,9 @@ where
 
     vp_host_fns::add_gas(gas_meter, VERIFY_TX_SIG_GAS_COST)?;
     let tx = unsafe { env.ctx.tx.get() };
-    Ok(HostEnvResult::from(tx.verify_sig(&pk, &sig).is_ok()).to_i64())
+    let storage = unsafe { env.ctx.storage.get() };
+    let verified = tx.verify_sig(&pk, &sig, &storage.chain_id).is_ok();
+    Ok(HostEnvResult::from(verified).to_i64())
 }
 
 /// Verify a ShieldedTransaction.
@@ -1961,6 +1980,62 @@ where
     Ok(())
 }
 
+/// Emitting an event function exposed to the wasm VM VP environment. The
+/// events are kept only if the VP accepts the transaction.
+pub fn vp_emit_event<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    event_ptr: u64,
+    event_len: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (event, gas) = env
+        .memory
+        .read_bytes(event_ptr, event_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    let event: VpEvent = BorshDeserialize::try_from_slice(&event)
+        .map_err(vp_host_fns::RuntimeError::EncodingError)?;
+    let events = unsafe { env.ctx.events.get() };
+    events.push(event);
+    Ok(())
+}
+
+/// Rejection function exposed to the wasm VM VP environment, giving the
+/// details of the VP's rejection of the transaction. The details are kept
+/// only if the VP rejects the transaction.
+pub fn vp_reject<MEM, DB, H, EVAL, CA>(
+    env: &VpVmEnv<MEM, DB, H, EVAL, CA>,
+    rejection_ptr: u64,
+    rejection_len: u64,
+) -> vp_host_fns::EnvResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (rejection, gas) = env
+        .memory
+        .read_bytes(rejection_ptr, rejection_len as _)
+        .map_err(|e| vp_host_fns::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    let rejection: VpRejection =
+        BorshDeserialize::try_from_slice(&rejection)
+            .map_err(vp_host_fns::RuntimeError::EncodingError)?;
+    let current = unsafe { env.ctx.rejection.get() };
+    *current = Some(rejection);
+    Ok(())
+}
+
 /// A helper module for testing
 #[cfg(feature = "testing")]
 pub mod testing {
@@ -2016,6 +2091,8 @@ pub mod testing {
         tx_index: &TxIndex,
         verifiers: &BTreeSet<Address>,
         result_buffer: &mut Option<Vec<u8>>,
+        events: &mut Vec<VpEvent>,
+        rejection: &mut Option<VpRejection>,
         keys_changed: &BTreeSet<Key>,
         eval_runner: &EVAL,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
@@ -2037,6 +2114,8 @@ pub mod testing {
             iterators,
             verifiers,
             result_buffer,
+            events,
+            rejection,
             keys_changed,
             eval_runner,
             #[cfg(feature = "wasm-runtime")]
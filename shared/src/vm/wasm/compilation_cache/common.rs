@@ -3,6 +3,10 @@
 //! limit and a file system cache of compiled modules (either to dynamic libs
 //! compiled via the `dylib` module, or serialized modules compiled via the
 //! `universal` module).
+//!
+//! The module files are tagged with the version of the engine that compiled
+//! them. A module file compiled by another version, e.g. before a node
+//! upgrade, is evicted and the module is recompiled, rather than loaded.
 
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
@@ -25,6 +29,11 @@ use crate::vm::{WasmCacheAccess, WasmCacheRoAccess};
 /// The size of the [`struct@Hash`]
 const HASH_BYTES: usize = 32;
 
+/// The version of the compilation of the modules, to be bumped whenever it
+/// changes in a way that's incompatible with the previously compiled modules,
+/// even if the version of `wasmer` stays the same
+const COMPILATION_VERSION: u32 = 1;
+
 /// Cache handle. Thread-safe.
 #[derive(Debug, Clone)]
 pub struct Cache<N: CacheName, A: WasmCacheAccess> {
@@ -34,6 +43,9 @@ pub struct Cache<N: CacheName, A: WasmCacheAccess> {
     progress: Arc<RwLock<HashMap<Hash, Compilation>>>,
     /// In-memory LRU cache of compiled modules
     in_memory: Arc<RwLock<MemoryCache>>,
+    /// The maximum number of pages of the memory of the modules run in the
+    /// stores returned from the cache
+    memory_max_pages: u32,
     /// The cache's name
     name: PhantomData<N>,
     /// Cache access level
@@ -44,6 +56,9 @@ pub struct Cache<N: CacheName, A: WasmCacheAccess> {
 pub trait CacheName: Clone + std::fmt::Debug {
     /// Get the name of the cache
     fn name() -> &'static str;
+
+    /// Get the default maximum number of pages of the memory of the modules
+    fn default_memory_max_pages() -> u32;
 }
 
 /// In-memory LRU cache of compiled modules
@@ -96,24 +111,46 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
             dir,
             progress: Default::default(),
             in_memory,
+            memory_max_pages: N::default_memory_max_pages(),
             name: Default::default(),
             access: Default::default(),
         }
     }
 
+    /// Set the maximum number of pages of the memory of the modules run in
+    /// the stores returned from the cache.
+    pub fn with_memory_max_pages(mut self, memory_max_pages: u32) -> Self {
+        self.set_memory_max_pages(memory_max_pages);
+        self
+    }
+
+    /// Set the maximum number of pages of the memory of the modules run in
+    /// the stores returned from the cache from now on.
+    pub fn set_memory_max_pages(&mut self, memory_max_pages: u32) {
+        self.memory_max_pages = memory_max_pages;
+    }
+
+    /// Get the maximum number of pages of the memory of the modules run in
+    /// the stores returned from the cache.
+    pub fn memory_max_pages(&self) -> u32 {
+        self.memory_max_pages
+    }
+
     /// Get a WASM module from LRU cache, from a file or compile it and cache
     /// it. If the cache access is set to [`crate::vm::WasmCacheRwAccess`], it
     /// updates the position in the LRU cache. Otherwise, the compiled
-    /// module will not be be cached, if it's not already.
+    /// module will not be be cached, if it's not already. The returned store
+    /// limits the memory to the cache's maximum number of pages.
     pub fn fetch_or_compile(
         &mut self,
         code: impl AsRef<[u8]>,
     ) -> Result<(Module, Store), wasm::run::Error> {
-        if A::is_read_write() {
+        let (module, _store) = if A::is_read_write() {
             self.get_or_compile(code)
         } else {
             self.peek_or_compile(code)
-        }
+        }?;
+        Ok((module, limited_store(self.memory_max_pages)))
     }
 
     /// Get a WASM module from LRU cache, from a file or compile it and cache
@@ -151,7 +188,17 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                         return Ok((module.clone(), store()));
                     }
 
-                    let (module, store) = file_load_module(&self.dir, &hash);
+                    let (module, store) =
+                        match file_load_module(&self.dir, &hash) {
+                            Some(loaded) => loaded,
+                            None => {
+                                // The file has been evicted, so the module
+                                // gets recompiled
+                                drop(in_memory);
+                                self.progress.write().unwrap().remove(&hash);
+                                continue;
+                            }
+                        };
                     tracing::info!(
                         "{} found {} in file cache.",
                         N::name(),
@@ -177,15 +224,19 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                     progress.insert(hash, Compilation::Compiling);
                     drop(progress);
 
-                    let (module, store) =
-                        if module_file_exists(&self.dir, &hash) {
-                            tracing::info!(
-                                "Loading {} {} from file.",
-                                N::name(),
-                                hash.to_string()
-                            );
-                            file_load_module(&self.dir, &hash)
-                        } else {
+                    let loaded = if module_file_is_current(&self.dir, &hash) {
+                        tracing::info!(
+                            "Loading {} {} from file.",
+                            N::name(),
+                            hash.to_string()
+                        );
+                        file_load_module(&self.dir, &hash)
+                    } else {
+                        None
+                    };
+                    let (module, store) = match loaded {
+                        Some(loaded) => loaded,
+                        None => {
                             tracing::info!(
                                 "Compiling {} {}.",
                                 N::name(),
@@ -228,7 +279,8 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                                     return Err(err);
                                 }
                             }
-                        };
+                        }
+                    };
 
                     // Update progress
                     let mut progress = self.progress.write().unwrap();
@@ -281,13 +333,18 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                         return Ok((module.clone(), store()));
                     }
 
-                    let (module, store) = file_load_module(&self.dir, &hash);
-                    tracing::info!(
-                        "{} found {} in file cache.",
-                        N::name(),
-                        hash.to_string()
-                    );
-                    return Ok((module, store));
+                    if let Some(loaded) = file_load_module(&self.dir, &hash) {
+                        tracing::info!(
+                            "{} found {} in file cache.",
+                            N::name(),
+                            hash.to_string()
+                        );
+                        return Ok(loaded);
+                    }
+                    // The file has been evicted, so the module gets
+                    // recompiled
+                    let code = wasm::run::prepare_wasm_code(code)?;
+                    return compile(code);
                 }
                 Some(Compilation::Compiling) => {
                     drop(progress);
@@ -303,22 +360,24 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                 None => {
                     drop(progress);
 
-                    return if module_file_exists(&self.dir, &hash) {
+                    if module_file_is_current(&self.dir, &hash) {
                         tracing::info!(
                             "Loading {} {} from file.",
                             N::name(),
                             hash.to_string()
                         );
-                        Ok(file_load_module(&self.dir, &hash))
-                    } else {
-                        tracing::info!(
-                            "Compiling {} {}.",
-                            N::name(),
-                            hash.to_string()
-                        );
-                        let code = wasm::run::prepare_wasm_code(code)?;
-                        compile(code)
-                    };
+                        if let Some(loaded) = file_load_module(&self.dir, &hash)
+                        {
+                            return Ok(loaded);
+                        }
+                    }
+                    tracing::info!(
+                        "Compiling {} {}.",
+                        N::name(),
+                        hash.to_string()
+                    );
+                    let code = wasm::run::prepare_wasm_code(code)?;
+                    return compile(code);
                 }
             }
         }
@@ -335,7 +394,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                     // Already known, do nothing
                 }
                 None => {
-                    if module_file_exists(&self.dir, &hash) {
+                    if module_file_is_current(&self.dir, &hash) {
                         progress.insert(hash, Compilation::Done);
                         return;
                     }
@@ -397,6 +456,7 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
             dir: self.dir.clone(),
             progress: self.progress.clone(),
             in_memory: self.in_memory.clone(),
+            memory_max_pages: self.memory_max_pages,
             name: Default::default(),
             access: Default::default(),
         }
@@ -436,18 +496,58 @@ fn store() -> Store {
     universal::store()
 }
 
+fn limited_store(memory_max_pages: u32) -> Store {
+    // This has to be using the store matching the compilation method in the
+    // `fn compile`
+    universal::limited_store(memory_max_pages)
+}
+
+/// Get the version of the engine that compiles the modules, with which the
+/// module files are tagged
+fn engine_version() -> String {
+    format!(
+        "wasmer-{}-{}-{}",
+        wasmer::VERSION,
+        file_ext(),
+        COMPILATION_VERSION
+    )
+}
+
+/// Write the module to a file, tagged with the current engine version
 fn file_write_module(dir: impl AsRef<Path>, module: &Module, hash: &Hash) {
     use wasmer_cache::Cache;
-    let mut fs_cache = fs_cache(dir, hash);
+    let mut fs_cache = fs_cache(&dir, hash);
     fs_cache.store(*hash, module).unwrap();
+    fs::write(module_version_file(dir, hash), engine_version()).unwrap();
 }
 
-fn file_load_module(dir: impl AsRef<Path>, hash: &Hash) -> (Module, Store) {
+/// Load the module from its file. If it cannot be loaded, e.g. when the file
+/// is corrupted, the file is evicted and `None` is returned, so that the
+/// module gets recompiled.
+fn file_load_module(
+    dir: impl AsRef<Path>,
+    hash: &Hash,
+) -> Option<(Module, Store)> {
     use wasmer_cache::Cache;
-    let fs_cache = fs_cache(dir, hash);
+    let fs_cache = fs_cache(&dir, hash);
     let store = store();
-    let module = unsafe { fs_cache.load(&store, *hash) }.unwrap();
-    (module, store)
+    match unsafe { fs_cache.load(&store, *hash) } {
+        Ok(module) => Some((module, store)),
+        Err(err) => {
+            tracing::info!(
+                "Failed to load WASM {} from file with {}, evicting it",
+                hash.to_string(),
+                err
+            );
+            evict_module_file(dir, hash);
+            None
+        }
+    }
+}
+
+/// Evict the module file and its version tag
+fn evict_module_file(dir: impl AsRef<Path>, hash: &Hash) {
+    let _ = fs::remove_dir_all(dir.as_ref().join(hash_to_store_dir(hash)));
 }
 
 fn fs_cache(dir: impl AsRef<Path>, hash: &Hash) -> FileSystemCache {
@@ -457,13 +557,45 @@ fn fs_cache(dir: impl AsRef<Path>, hash: &Hash) -> FileSystemCache {
     fs_cache
 }
 
-fn module_file_exists(dir: impl AsRef<Path>, hash: &Hash) -> bool {
-    let file = dir.as_ref().join(hash_to_store_dir(hash)).join(format!(
+fn module_file(dir: impl AsRef<Path>, hash: &Hash) -> PathBuf {
+    dir.as_ref().join(hash_to_store_dir(hash)).join(format!(
         "{}.{}",
         hash.to_string(),
         file_ext()
-    ));
-    file.exists()
+    ))
+}
+
+fn module_version_file(dir: impl AsRef<Path>, hash: &Hash) -> PathBuf {
+    dir.as_ref()
+        .join(hash_to_store_dir(hash))
+        .join(format!("{}.version", hash.to_string()))
+}
+
+fn module_file_exists(dir: impl AsRef<Path>, hash: &Hash) -> bool {
+    module_file(dir, hash).exists()
+}
+
+/// Check if the module file exists and if it's been compiled by the current
+/// engine version. A module file compiled by another version, or without a
+/// version tag, is evicted, so that the module gets recompiled.
+fn module_file_is_current(dir: impl AsRef<Path>, hash: &Hash) -> bool {
+    if !module_file_exists(&dir, hash) {
+        return false;
+    }
+    let version = fs::read_to_string(module_version_file(&dir, hash)).ok();
+    let current_version = engine_version();
+    if version.as_ref() == Some(&current_version) {
+        return true;
+    }
+    tracing::info!(
+        "Evicting WASM {} compiled by engine version {}, the current version \
+         is {}",
+        hash.to_string(),
+        version.as_deref().unwrap_or("unknown"),
+        current_version
+    );
+    evict_module_file(dir, hash);
+    false
 }
 
 /// A universal engine compilation. The module can be serialized to/from bytes.
@@ -488,6 +620,11 @@ mod universal {
     pub fn store() -> Store {
         untrusted_wasm_store(memory::vp_limit())
     }
+
+    /// Universal WASM store with the given memory limit
+    pub fn limited_store(memory_max_pages: u32) -> Store {
+        untrusted_wasm_store(memory::limit(memory_max_pages))
+    }
 }
 
 /// A dynamic library engine compilation.
@@ -776,6 +913,80 @@ mod test {
         );
     }
 
+    /// Test that a module file compiled by another engine version, or
+    /// without a version tag, as if it was compiled before a node upgrade, is
+    /// evicted and the module is recompiled rather than failing to load. The
+    /// same goes for a corrupted module file.
+    #[test]
+    fn test_fetch_or_compile_stale_module_file() {
+        let tx_no_op = load_wasm(TX_NO_OP);
+        let hash = &tx_no_op.hash;
+        let dir = tempdir().unwrap();
+        // A new cache handle is used for every fetch, as if the node was
+        // restarted, so that the module is not in memory
+        let fetch = || {
+            let mut cache = Cache::<TestCache, WasmCacheRwAccess>::new(
+                dir.path(),
+                50 * 1024 * 1024, // 50 MiB
+            );
+            cache.fetch_or_compile(&tx_no_op.code)
+        };
+        let version =
+            || fs::read_to_string(module_version_file(dir.path(), hash)).ok();
+        // The module file written by another engine, which this engine
+        // cannot load
+        let stale_module = b"stale module".to_vec();
+        let write_stale_module = |stale_version: Option<&str>| {
+            fs::write(module_file(dir.path(), hash), &stale_module).unwrap();
+            match stale_version {
+                Some(stale_version) => fs::write(
+                    module_version_file(dir.path(), hash),
+                    stale_version,
+                )
+                .unwrap(),
+                None => fs::remove_file(module_version_file(dir.path(), hash))
+                    .unwrap(),
+            }
+        };
+        let is_recompiled = || {
+            version() == Some(engine_version())
+                && fs::read(module_file(dir.path(), hash)).unwrap()
+                    != stale_module
+        };
+
+        // The compiled module file is tagged with the engine version
+        fetch().unwrap();
+        assert_eq!(version(), Some(engine_version()));
+
+        // A module file compiled by an older engine version
+        write_stale_module(Some("wasmer-2.1.0-bin-1"));
+        fetch().expect("The stale module must be recompiled");
+        assert!(is_recompiled());
+
+        // A module file written before the files were tagged
+        write_stale_module(None);
+        fetch().expect("The stale module must be recompiled");
+        assert!(is_recompiled());
+
+        // A corrupted module file tagged with the current version
+        write_stale_module(Some(engine_version().as_str()));
+        fetch().expect("The corrupted module must be recompiled");
+        assert!(is_recompiled());
+
+        // With read-only access, the stale module file is evicted, but the
+        // recompiled module is not written
+        write_stale_module(Some("wasmer-2.1.0-bin-1"));
+        Cache::<TestCache, WasmCacheRwAccess>::new(
+            dir.path(),
+            50 * 1024 * 1024,
+        )
+        .read_only()
+        .fetch_or_compile(&tx_no_op.code)
+        .expect("The stale module must be recompiled");
+        assert!(!module_file_exists(dir.path(), hash));
+        assert_eq!(version(), None);
+    }
+
     #[test]
     fn test_pre_compile_valid_wasm() {
         // Load some WASMs and find their hashes and in-memory size
@@ -965,6 +1176,10 @@ mod test {
         fn name() -> &'static str {
             "test"
         }
+
+        fn default_memory_max_pages() -> u32 {
+            memory::VP_MEMORY_MAX_PAGES
+        }
     }
 
     /// A cache with a temp dir for unit tests
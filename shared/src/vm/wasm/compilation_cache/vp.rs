@@ -1,6 +1,7 @@
 //! VP WASM compilation cache
 
 use super::common::{Cache, CacheName};
+use crate::vm::wasm::memory;
 
 /// VP WASM compilation cache handle. Thread-safe.
 pub type VpCache<A> = Cache<Name, A>;
@@ -13,4 +14,8 @@ impl CacheName for Name {
     fn name() -> &'static str {
         "VP"
     }
+
+    fn default_memory_max_pages() -> u32 {
+        memory::VP_MEMORY_MAX_PAGES
+    }
 }
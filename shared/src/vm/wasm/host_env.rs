@@ -124,6 +124,8 @@ where
             "namada_vp_eval" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_eval),
             "namada_vp_get_native_token" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_native_token),
             "namada_vp_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_log_string),
+            "namada_vp_emit_event" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_emit_event),
+            "namada_vp_reject" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_reject),
         },
     }
 }
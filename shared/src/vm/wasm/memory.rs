@@ -41,28 +41,36 @@ pub type Result<T> = std::result::Result<T, Error>;
 // TODO set bounds to accommodate for wasm env size
 /// Initial pages in tx memory
 pub const TX_MEMORY_INIT_PAGES: u32 = 100; // 6.4 MiB
-/// Mamixmum pages in tx memory
+/// Default mamixmum pages in tx memory
 pub const TX_MEMORY_MAX_PAGES: u32 = 200; // 12.8 MiB
 /// Initial pages in VP memory
 pub const VP_MEMORY_INIT_PAGES: u32 = 100; // 6.4 MiB
-/// Mamixmum pages in VP memory
+/// Default mamixmum pages in VP memory
 pub const VP_MEMORY_MAX_PAGES: u32 = 200; // 12.8 MiB
 
-/// Prepare memory for instantiating a transaction module
-pub fn prepare_tx_memory(store: &wasmer::Store) -> Result<wasmer::Memory> {
+/// Prepare memory for instantiating a transaction module, limited to the
+/// given maximum pages
+pub fn prepare_tx_memory(
+    store: &wasmer::Store,
+    max_pages: u32,
+) -> Result<wasmer::Memory> {
     let mem_type = wasmer::MemoryType::new(
-        TX_MEMORY_INIT_PAGES,
-        Some(TX_MEMORY_MAX_PAGES),
+        TX_MEMORY_INIT_PAGES.min(max_pages),
+        Some(max_pages),
         false,
     );
     Memory::new(store, mem_type).map_err(Error::InitMemoryError)
 }
 
-/// Prepare memory for instantiating a validity predicate module
-pub fn prepare_vp_memory(store: &wasmer::Store) -> Result<wasmer::Memory> {
+/// Prepare memory for instantiating a validity predicate module, limited to
+/// the given maximum pages
+pub fn prepare_vp_memory(
+    store: &wasmer::Store,
+    max_pages: u32,
+) -> Result<wasmer::Memory> {
     let mem_type = wasmer::MemoryType::new(
-        VP_MEMORY_INIT_PAGES,
-        Some(VP_MEMORY_MAX_PAGES),
+        VP_MEMORY_INIT_PAGES.min(max_pages),
+        Some(max_pages),
         false,
     );
     let memory =
@@ -296,18 +304,22 @@ pub struct Limit<T: Tunables> {
     base: T,
 }
 
-/// A [`Limit`] with memory limit setup for validity predicate WASM
-/// execution.
-pub fn vp_limit() -> Limit<BaseTunables> {
+/// A [`Limit`] with the given maximum pages.
+pub fn limit(max_pages: u32) -> Limit<BaseTunables> {
     let base = BaseTunables::for_target(&Target::default());
-    let limit = Pages(VP_MEMORY_MAX_PAGES);
+    let limit = Pages(max_pages);
     Limit { limit, base }
 }
-/// A [`Limit`] with memory limit setup for transaction WASM execution.
+
+/// A [`Limit`] with the default memory limit setup for validity predicate
+/// WASM execution.
+pub fn vp_limit() -> Limit<BaseTunables> {
+    limit(VP_MEMORY_MAX_PAGES)
+}
+/// A [`Limit`] with the default memory limit setup for transaction WASM
+/// execution.
 pub fn tx_limit() -> Limit<BaseTunables> {
-    let base = BaseTunables::for_target(&Target::default());
-    let limit = Pages(TX_MEMORY_MAX_PAGES);
-    Limit { limit, base }
+    limit(TX_MEMORY_MAX_PAGES)
 }
 
 impl<T: Tunables> Limit<T> {
@@ -1,5 +1,6 @@
 //! Wasm runners
 
+use std::cell::Cell;
 use std::collections::BTreeSet;
 use std::marker::PhantomData;
 
@@ -10,13 +11,17 @@ use wasmer::BaseTunables;
 
 use super::memory::{Limit, WasmMemory};
 use super::TxCache;
-use crate::ledger::gas::{BlockGasMeter, VpGasMeter};
+use crate::ledger::gas::{
+    BlockGasMeter, VpGasMeter, WASM_INSTRUCTION_GAS,
+    WASM_MEMORY_GROW_GAS_PER_PAGE,
+};
 use crate::ledger::storage::write_log::WriteLog;
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::proto::Tx;
 use crate::types::address::Address;
 use crate::types::internal::HostEnvResult;
 use crate::types::storage::{Key, TxIndex};
+use crate::types::validity_predicate::{VpEvent, VpRejection};
 use crate::vm::host_env::{TxVmEnv, VpCtx, VpEvaluator, VpVmEnv};
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::types::VpInput;
@@ -64,6 +69,8 @@ pub enum Error {
     },
     #[error("Wasm validation error: {0}")]
     ValidationError(WasmValidationError),
+    #[error("Exceeded the maximum nesting depth {0} of the evaluated VPs")]
+    MaxEvalDepthExceeded(u64),
 }
 
 /// Result for functions that may fail
@@ -92,6 +99,7 @@ where
     validate_untrusted_wasm(&tx_code).map_err(Error::ValidationError)?;
 
     let (module, store) = tx_wasm_cache.fetch_or_compile(&tx_code)?;
+    let memory_max_pages = tx_wasm_cache.memory_max_pages();
 
     let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
     let mut verifiers = BTreeSet::new();
@@ -110,8 +118,8 @@ where
         tx_wasm_cache,
     );
 
-    let initial_memory =
-        memory::prepare_tx_memory(&store).map_err(Error::MemoryError)?;
+    let initial_memory = memory::prepare_tx_memory(&store, memory_max_pages)
+        .map_err(Error::MemoryError)?;
     let imports = tx_imports(&store, initial_memory, env);
 
     // Instantiate the wasm module
@@ -154,7 +162,8 @@ where
 
 /// Execute a validity predicate code. Returns whether the validity
 /// predicate accepted storage modifications performed by the transaction
-/// that triggered the execution.
+/// that triggered the execution. The details of a rejection given by the
+/// validity predicate, if any, are written into `rejection`.
 #[allow(clippy::too_many_arguments)]
 pub fn vp<DB, H, CA>(
     vp_code: impl AsRef<[u8]>,
@@ -167,6 +176,8 @@ pub fn vp<DB, H, CA>(
     keys_changed: &BTreeSet<Key>,
     verifiers: &BTreeSet<Address>,
     mut vp_wasm_cache: VpCache<CA>,
+    events: &mut Vec<VpEvent>,
+    rejection: &mut Option<VpRejection>,
 ) -> Result<bool>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -185,14 +196,13 @@ where
 
     // Compile the wasm module
     let (module, store) = vp_wasm_cache.fetch_or_compile(vp_code)?;
+    let memory_max_pages = vp_wasm_cache.memory_max_pages();
 
     let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
     let mut result_buffer: Option<Vec<u8>> = None;
-    let eval_runner = VpEvalWasm {
-        db: PhantomData,
-        hasher: PhantomData,
-        cache_access: PhantomData,
-    };
+    let mut vp_events: Vec<VpEvent> = vec![];
+    let mut vp_rejection: Option<VpRejection> = None;
+    let eval_runner = VpEvalWasm::default();
 
     let env = VpVmEnv::new(
         WasmMemory::default(),
@@ -205,23 +215,33 @@ where
         &mut iterators,
         verifiers,
         &mut result_buffer,
+        &mut vp_events,
+        &mut vp_rejection,
         keys_changed,
         &eval_runner,
         &mut vp_wasm_cache,
     );
 
-    let initial_memory =
-        memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
+    let initial_memory = memory::prepare_vp_memory(&store, memory_max_pages)
+        .map_err(Error::MemoryError)?;
     let imports = vp_imports(&store, initial_memory, env);
 
-    run_vp(
+    let accepted = run_vp(
         module,
         imports,
         input_data,
         address,
         keys_changed,
         verifiers,
-    )
+    )?;
+    // The events of a VP that rejects the tx are discarded, as is the
+    // rejection of a VP that accepts it
+    if accepted {
+        events.append(&mut vp_events);
+    } else {
+        *rejection = vp_rejection;
+    }
+    Ok(accepted)
 }
 
 fn run_vp(
@@ -286,8 +306,11 @@ fn run_vp(
     Ok(is_valid == 1)
 }
 
+/// The default maximum nesting depth of the VPs evaluated with the `eval`
+/// host function. The VP triggered by a tx is at the depth 0.
+pub const DEFAULT_MAX_VP_EVAL_DEPTH: u64 = 8;
+
 /// Validity predicate wasm evaluator for `eval` host function calls.
-#[derive(Default)]
 pub struct VpEvalWasm<DB, H, CA>
 where
     DB: storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -300,6 +323,22 @@ where
     pub hasher: PhantomData<*const H>,
     /// Phantom type for WASM compilation cache access
     pub cache_access: PhantomData<*const CA>,
+    /// The maximum nesting depth of the evaluated VPs. Evaluating a VP any
+    /// deeper fails.
+    pub max_depth: u64,
+    /// The nesting depth of the VP being evaluated, if any
+    depth: Cell<u64>,
+}
+
+impl<DB, H, CA> Default for VpEvalWasm<DB, H, CA>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_VP_EVAL_DEPTH)
+    }
 }
 
 impl<DB, H, CA> VpEvaluator for VpEvalWasm<DB, H, CA>
@@ -335,12 +374,43 @@ where
     H: 'static + StorageHasher,
     CA: WasmCacheAccess,
 {
-    /// Evaluate the given VP.
+    /// Create a new evaluator with the given maximum nesting depth of the
+    /// evaluated VPs
+    pub fn new(max_depth: u64) -> Self {
+        Self {
+            db: PhantomData,
+            hasher: PhantomData,
+            cache_access: PhantomData,
+            max_depth,
+            depth: Cell::new(0),
+        }
+    }
+
+    /// Evaluate the given VP. Fails if the VP would be nested deeper than
+    /// the maximum depth.
     pub fn eval_native_result(
         &self,
         ctx: VpCtx<'static, DB, H, Self, CA>,
         vp_code: Vec<u8>,
         input_data: Vec<u8>,
+    ) -> Result<bool> {
+        let depth = self.depth.get() + 1;
+        if depth > self.max_depth {
+            return Err(Error::MaxEvalDepthExceeded(self.max_depth));
+        }
+        // The VPs evaluated from this one are nested one level deeper
+        self.depth.set(depth);
+        let result = self.eval_at_current_depth(ctx, vp_code, input_data);
+        self.depth.set(depth - 1);
+        result
+    }
+
+    /// Evaluate the given VP at the current nesting depth
+    fn eval_at_current_depth(
+        &self,
+        ctx: VpCtx<'static, DB, H, Self, CA>,
+        vp_code: Vec<u8>,
+        input_data: Vec<u8>,
     ) -> Result<bool> {
         // let wasm_store = untrusted_wasm_store(memory::tx_limit());
 
@@ -350,6 +420,10 @@ where
         let keys_changed = unsafe { ctx.keys_changed.get() };
         let verifiers = unsafe { ctx.verifiers.get() };
         let vp_wasm_cache = unsafe { ctx.vp_wasm_cache.get() };
+        let events = unsafe { ctx.events.get() };
+        let emitted_events = events.len();
+        let rejection = unsafe { ctx.rejection.get() };
+        let prior_rejection = rejection.clone();
         let env = VpVmEnv {
             memory: WasmMemory::default(),
             ctx,
@@ -359,18 +433,26 @@ where
         let (module, store) = vp_wasm_cache.fetch_or_compile(&vp_code)?;
 
         let initial_memory =
-            memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
+            memory::prepare_vp_memory(&store, vp_wasm_cache.memory_max_pages())
+                .map_err(Error::MemoryError)?;
 
         let imports = vp_imports(&store, initial_memory, env);
 
-        run_vp(
+        let result = run_vp(
             module,
             imports,
             &input_data[..],
             address,
             keys_changed,
             verifiers,
-        )
+        );
+        // The events of an evaluated VP that doesn't accept are discarded.
+        // Its rejection is not the evaluating VP's own, so it's discarded too.
+        if !matches!(result, Ok(true)) {
+            events.truncate(emitted_events);
+        }
+        *rejection = prior_rejection;
+        result
     }
 }
 
@@ -399,7 +481,8 @@ pub fn prepare_wasm_code<T: AsRef<[u8]>>(code: T) -> Result<Vec<u8>> {
 
 /// Get the gas rules used to meter wasm operations
 fn get_gas_rules() -> rules::Set {
-    rules::Set::default().with_grow_cost(1)
+    rules::Set::new(WASM_INSTRUCTION_GAS, Default::default())
+        .with_grow_cost(WASM_MEMORY_GROW_GAS_PER_PAGE)
 }
 
 #[cfg(test)]
@@ -562,6 +645,8 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache.clone(),
+            &mut vec![],
+            &mut None,
         )
         .unwrap();
         assert!(passed);
@@ -589,12 +674,72 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            &mut vec![],
+            &mut None,
         )
         .unwrap();
 
         assert!(!passed);
     }
 
+    /// Test that a validity predicate recursively evaluating itself may be
+    /// nested up to the maximum eval depth, while the `eval` nested one
+    /// level deeper fails and hence the VP returns `false`.
+    #[test]
+    fn test_vp_eval_max_depth() {
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let tx_index = TxIndex::default();
+
+        // This code will call `eval` with the VP given in its input
+        let vp_eval = std::fs::read(VP_EVAL_WASM).expect("cannot load wasm");
+        let vp_always_true =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        // The tx data for the VP to evaluate itself until `vp_always_true` is
+        // evaluated at the given depth
+        let nested_tx_data = |depth: u64| {
+            let mut eval_vp = EvalVp {
+                vp_code: vp_always_true.clone(),
+                input: vec![],
+            };
+            for _ in 1..depth {
+                eval_vp = EvalVp {
+                    vp_code: vp_eval.clone(),
+                    input: eval_vp.try_to_vec().unwrap(),
+                };
+            }
+            eval_vp.try_to_vec().unwrap()
+        };
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+
+        for (depth, expected) in [
+            (DEFAULT_MAX_VP_EVAL_DEPTH, true),
+            (DEFAULT_MAX_VP_EVAL_DEPTH + 1, false),
+        ] {
+            let tx = Tx::new(vec![], Some(nested_tx_data(depth)));
+            let passed = vp(
+                vp_eval.clone(),
+                &tx,
+                &tx_index,
+                &addr,
+                &storage,
+                &write_log,
+                &mut gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_cache.clone(),
+                &mut vec![],
+                &mut None,
+            )
+            .unwrap();
+            assert_eq!(passed, expected, "Evaluated at the depth {depth}");
+        }
+    }
+
     /// Test that when a validity predicate wasm goes over the memory limit
     /// inside the wasm execution, the execution is aborted.
     #[test]
@@ -630,6 +775,8 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache.clone(),
+            &mut vec![],
+            &mut None,
         );
         assert!(result.is_ok(), "Expected success, got {:?}", result);
 
@@ -648,12 +795,65 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            &mut vec![],
+            &mut None,
         )
         .expect_err("Expected to run out of memory");
 
         assert_stack_overflow(&error);
     }
 
+    /// Test that the memory limit of transaction wasm configured in the cache
+    /// is enforced, with the limit set lower and higher than the default.
+    #[test]
+    fn test_tx_memory_limit_configured() {
+        // 100 pages, 6.4 MiB limit
+        let max_pages = 100;
+        // Allocating `2^22` (4 MiB) should be below the memory limit
+        let result = allocate_in_tx_wasm(max_pages, 2_usize.pow(22));
+        assert!(result.is_ok(), "Expected success, got {:?}", result);
+        // Allocating `2^23` (8 MiB) should be above the memory limit
+        let error = allocate_in_tx_wasm(max_pages, 2_usize.pow(23))
+            .expect_err("Expected to run out of memory");
+        assert_stack_overflow(&error);
+
+        // 400 pages, 25.6 MiB limit
+        let max_pages = 400;
+        // Allocating `2^24` (16 MiB) should be below the memory limit
+        let result = allocate_in_tx_wasm(max_pages, 2_usize.pow(24));
+        assert!(result.is_ok(), "Expected success, got {:?}", result);
+        // Allocating `2^25` (32 MiB) should be above the memory limit
+        let error = allocate_in_tx_wasm(max_pages, 2_usize.pow(25))
+            .expect_err("Expected to run out of memory");
+        assert_stack_overflow(&error);
+    }
+
+    /// Test that the memory limit of validity predicate wasm configured in the
+    /// cache is enforced, with the limit set lower and higher than the
+    /// default.
+    #[test]
+    fn test_vp_memory_limit_configured() {
+        // 100 pages, 6.4 MiB limit
+        let max_pages = 100;
+        // Allocating `2^22` (4 MiB) should be below the memory limit
+        let result = allocate_in_vp_wasm(max_pages, 2_usize.pow(22));
+        assert!(result.is_ok(), "Expected success, got {:?}", result);
+        // Allocating `2^23` (8 MiB) should be above the memory limit
+        let error = allocate_in_vp_wasm(max_pages, 2_usize.pow(23))
+            .expect_err("Expected to run out of memory");
+        assert_stack_overflow(&error);
+
+        // 400 pages, 25.6 MiB limit
+        let max_pages = 400;
+        // Allocating `2^24` (16 MiB) should be below the memory limit
+        let result = allocate_in_vp_wasm(max_pages, 2_usize.pow(24));
+        assert!(result.is_ok(), "Expected success, got {:?}", result);
+        // Allocating `2^25` (32 MiB) should be above the memory limit
+        let error = allocate_in_vp_wasm(max_pages, 2_usize.pow(25))
+            .expect_err("Expected to run out of memory");
+        assert_stack_overflow(&error);
+    }
+
     /// Test that when a transaction wasm goes over the wasm memory limit in the
     /// host input, the execution fails.
     #[test]
@@ -740,6 +940,8 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            &mut vec![],
+            &mut None,
         );
         // Depending on platform, we get a different error from the running out
         // of memory
@@ -847,6 +1049,8 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            &mut vec![],
+            &mut None,
         )
         .expect_err("Expected to run out of memory");
 
@@ -903,11 +1107,241 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            &mut vec![],
+            &mut None,
         )
         .unwrap();
         assert!(!passed);
     }
 
+    /// Test that running the same validity predicate on the same input
+    /// consumes the same gas, whether its module is compiled or already
+    /// cached. The gas is charged for the executed wasm instructions and the
+    /// host calls only.
+    #[test]
+    fn test_vp_gas_is_deterministic() {
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let tx_index = TxIndex::default();
+
+        let vp_read_key =
+            std::fs::read(VP_READ_STORAGE_KEY_WASM).expect("cannot load wasm");
+
+        // Write a value that the VP reads from the storage
+        let key = Key::parse("key").unwrap();
+        let value: Vec<u8> = vec![6_u8; 1024];
+        storage.write(&key, value.try_to_vec().unwrap()).unwrap();
+        let tx_data = key.try_to_vec().unwrap();
+        let tx = Tx::new(vec![], Some(tx_data));
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+
+        let run = || {
+            let mut gas_meter = VpGasMeter::new(0);
+            let passed = vp(
+                &vp_read_key,
+                &tx,
+                &tx_index,
+                &addr,
+                &storage,
+                &write_log,
+                &mut gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_cache.clone(),
+                &mut vec![],
+                &mut None,
+            )
+            .unwrap();
+            assert!(passed);
+            gas_meter.current_gas
+        };
+        // The first run compiles the module, the second one hits the cache
+        let gas = run();
+        assert!(gas > 0);
+        assert_eq!(gas, run());
+    }
+
+    /// Test that running the same transaction on the same input consumes the
+    /// same gas, whether its module is compiled or already cached.
+    #[test]
+    fn test_tx_gas_is_deterministic() {
+        let mut storage = TestStorage::default();
+        let tx_index = TxIndex::default();
+
+        let tx_read_key =
+            std::fs::read(TX_READ_STORAGE_KEY_WASM).expect("cannot load wasm");
+
+        // Write a value that the tx reads from the storage
+        let key = Key::parse("key").unwrap();
+        let value: Vec<u8> = vec![6_u8; 1024];
+        storage.write(&key, value.try_to_vec().unwrap()).unwrap();
+        let tx_data = key.try_to_vec().unwrap();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+
+        let mut run_tx = || {
+            let mut write_log = WriteLog::default();
+            let mut gas_meter = BlockGasMeter::default();
+            tx(
+                &storage,
+                &mut write_log,
+                &mut gas_meter,
+                &tx_index,
+                &tx_read_key,
+                tx_data.clone(),
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+            .unwrap();
+            gas_meter.get_current_transaction_gas()
+        };
+        // The first run compiles the module, the second one hits the cache
+        let gas = run_tx();
+        assert!(gas > 0);
+        assert_eq!(gas, run_tx());
+    }
+
+    /// Test that the events emitted by a validity predicate are kept only if
+    /// it accepts the transaction.
+    #[test]
+    fn test_vp_events() {
+        let event = VpEvent {
+            event_type: "transfer".to_owned(),
+            attributes: [("amount".to_owned(), "10".to_owned())]
+                .into_iter()
+                .collect(),
+        };
+        let accepted = emit_event_in_vp_wasm(&event, true);
+        assert_eq!(accepted, vec![event.clone()]);
+        let rejected = emit_event_in_vp_wasm(&event, false);
+        assert!(rejected.is_empty());
+    }
+
+    /// Run a validity predicate that emits the event it receives as the tx
+    /// data and then accepts or rejects the tx. Returns the kept events.
+    fn emit_event_in_vp_wasm(event: &VpEvent, accept: bool) -> Vec<VpEvent> {
+        let vp_code = wasmer::wat2wasm(format!(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+                (import "env" "namada_vp_emit_event" (func $emit (param i64 i64)))
+
+                (func $_validate_tx (type 0) (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+                (call $emit (get_local 2) (get_local 3))
+                (i64.const {}))
+
+                (table (;0;) 1 1 funcref)
+                (memory (;0;) 16)
+                (global (;0;) (mut i32) (i32.const 1048576))
+                (export "memory" (memory 0))
+                (export "_validate_tx" (func $_validate_tx)))
+            "#, u8::from(accept)).as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm").into_owned();
+
+        let tx = Tx::new(vec![], Some(event.try_to_vec().unwrap()));
+        let tx_index = TxIndex::default();
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let mut events = vec![];
+        let passed = vp(
+            vp_code,
+            &tx,
+            &tx_index,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+            &mut events,
+            &mut None,
+        )
+        .unwrap();
+        assert_eq!(passed, accept);
+        events
+    }
+
+    /// Test that the details of a rejection given by a validity predicate
+    /// are kept only if it rejects the transaction.
+    #[test]
+    fn test_vp_rejection() {
+        let rejection = VpRejection {
+            rejected_key: Some(Key::parse("key").unwrap()),
+            reason: Some("The key is read-only".to_owned()),
+        };
+        let rejected = reject_in_vp_wasm(&rejection, false);
+        assert_eq!(rejected, Some(rejection.clone()));
+        let accepted = reject_in_vp_wasm(&rejection, true);
+        assert_eq!(accepted, None);
+    }
+
+    /// Run a validity predicate that gives the rejection it receives as the
+    /// tx data and then accepts or rejects the tx. Returns the kept
+    /// rejection.
+    fn reject_in_vp_wasm(
+        rejection: &VpRejection,
+        accept: bool,
+    ) -> Option<VpRejection> {
+        let vp_code = wasmer::wat2wasm(format!(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)))
+                (import "env" "namada_vp_reject" (func $reject (param i64 i64)))
+
+                (func $_validate_tx (type 0) (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64)
+                (call $reject (get_local 2) (get_local 3))
+                (i64.const {}))
+
+                (table (;0;) 1 1 funcref)
+                (memory (;0;) 16)
+                (global (;0;) (mut i32) (i32.const 1048576))
+                (export "memory" (memory 0))
+                (export "_validate_tx" (func $_validate_tx)))
+            "#, u8::from(accept)).as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm").into_owned();
+
+        let tx = Tx::new(vec![], Some(rejection.try_to_vec().unwrap()));
+        let tx_index = TxIndex::default();
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let mut kept_rejection = None;
+        let passed = vp(
+            vp_code,
+            &tx,
+            &tx_index,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+            &mut vec![],
+            &mut kept_rejection,
+        )
+        .unwrap();
+        assert_eq!(passed, accept);
+        kept_rejection
+    }
+
     fn loop_in_tx_wasm(loops: u32) -> Result<BTreeSet<Address>> {
         // A transaction with a recursive loop.
         // The boilerplate code is generated from tx_template.wasm using
@@ -1013,6 +1447,69 @@ mod tests {
             &keys_changed,
             &verifiers,
             vp_cache,
+            &mut vec![],
+            &mut None,
+        )
+    }
+
+    /// Run the tx wasm that allocates memory of the given size, with the
+    /// memory limited to the given maximum pages
+    fn allocate_in_tx_wasm(
+        max_pages: u32,
+        size: usize,
+    ) -> Result<BTreeSet<Address>> {
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = BlockGasMeter::default();
+        let tx_index = TxIndex::default();
+        let tx_code =
+            std::fs::read(TX_MEMORY_LIMIT_WASM).expect("cannot load wasm");
+        let tx_data = size.try_to_vec().unwrap();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (tx_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let mut tx_cache = tx_cache.with_memory_max_pages(max_pages);
+        tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            &tx_index,
+            tx_code,
+            tx_data,
+            &mut vp_cache,
+            &mut tx_cache,
+        )
+    }
+
+    /// Run the VP wasm that allocates memory of the given size, with the
+    /// memory limited to the given maximum pages
+    fn allocate_in_vp_wasm(max_pages: u32, size: usize) -> Result<bool> {
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+        let tx_index = TxIndex::default();
+        let vp_code =
+            std::fs::read(VP_MEMORY_LIMIT_WASM).expect("cannot load wasm");
+        let tx_data = size.try_to_vec().unwrap();
+        let tx = Tx::new(vec![], Some(tx_data));
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let vp_cache = vp_cache.with_memory_max_pages(max_pages);
+        vp(
+            vp_code,
+            &tx,
+            &tx_index,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+            &mut vec![],
+            &mut None,
         )
     }
 
@@ -0,0 +1,91 @@
+//! Benchmarks of the execution of validity predicates, run against a
+//! standard input set with a warm compilation cache to measure the
+//! steady-state execution.
+//!
+//! The user VP is only benchmarked when the WASMs have been built with
+//! `make build-wasm-scripts`.
+//!
+//! Run with `cargo bench --bench vps`.
+
+use std::path::PathBuf;
+
+use borsh::BorshSerialize;
+use criterion::{criterion_group, criterion_main, Criterion};
+use namada::proto::Tx;
+use namada::types::storage::Key;
+use namada::types::{address, token};
+use namada_tests::vp::TestVpEnv;
+use namada_tests::wasms::TestWasms;
+
+/// Set up a VP env with the standard input set of the benchmarks: a tx whose
+/// data is a storage key that's present in storage and a changed balance of
+/// the VP's owner, who is the tx's only verifier.
+fn standard_env() -> TestVpEnv {
+    let mut env = TestVpEnv::default();
+    // The `VpReadStorageKey` reads this key, given in the tx data
+    let key = Key::parse("bench/key").unwrap();
+    env.storage.write(&key, vec![1]).unwrap();
+    env.tx = Tx::new(vec![], Some(key.try_to_vec().unwrap()));
+    let balance_key = token::balance_key(&address::nam(), &env.addr);
+    env.write_log
+        .write(&balance_key, token::Amount::whole(10).try_to_vec().unwrap())
+        .unwrap();
+    env.keys_changed.insert(balance_key);
+    env.verifiers.insert(env.addr.clone());
+    env
+}
+
+/// Read the user VP built in the `wasm` directory, if any
+fn read_vp_user() -> Option<Vec<u8>> {
+    let wasm_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../wasm");
+    let checksums =
+        std::fs::read_to_string(wasm_dir.join("checksums.json")).ok()?;
+    let checksums: serde_json::Value = serde_json::from_str(&checksums).ok()?;
+    let file_name = checksums.get("vp_user.wasm")?.as_str()?;
+    std::fs::read(wasm_dir.join(file_name)).ok()
+}
+
+/// Run the VP once to warm up the compilation cache and return the gas it
+/// used, which is the same in every run
+fn warm_up(env: &mut TestVpEnv, name: &str, vp_code: &[u8]) -> u64 {
+    let result = env.run_vp_wasm(vp_code);
+    let gas = env.gas_meter.current_gas;
+    println!("{}: {:?}, gas used {}", name, result, gas);
+    gas
+}
+
+fn vps(c: &mut Criterion) {
+    let mut env = standard_env();
+    let mut vps = vec![
+        ("vp_always_true", TestWasms::VpAlwaysTrue.read_bytes()),
+        (
+            "vp_read_storage_key",
+            TestWasms::VpReadStorageKey.read_bytes(),
+        ),
+    ];
+    match read_vp_user() {
+        Some(vp_user) => vps.push(("vp_user", vp_user)),
+        None => println!("vp_user: skipped, the WASMs are not built"),
+    }
+
+    let gas: Vec<u64> = vps
+        .iter()
+        .map(|(name, vp_code)| warm_up(&mut env, name, vp_code))
+        .collect();
+    // The baseline: reading storage must cost more than accepting right away
+    assert!(
+        gas[0] < gas[1],
+        "vp_always_true gas {} >= vp_read_storage_key gas {}",
+        gas[0],
+        gas[1]
+    );
+
+    let mut group = c.benchmark_group("vps");
+    for (name, vp_code) in &vps {
+        group.bench_function(*name, |b| b.iter(|| env.run_vp_wasm(vp_code)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, vps);
+criterion_main!(benches);
@@ -1,7 +1,14 @@
-use crate::e2e::helpers::get_actor_rpc;
+use borsh::{BorshDeserialize, BorshSerialize};
+use data_encoding::HEXLOWER;
+use namada::ledger::eth_bridge::transfer::TransferToEthereum;
+use namada::types::address::eth;
+use namada::types::token;
+
+use crate::e2e::helpers::{find_address, get_actor_rpc, query_storage_bytes};
 use crate::e2e::setup;
 use crate::e2e::setup::constants::{
-    wasm_abs_path, ALBERT, TX_WRITE_STORAGE_KEY_WASM,
+    wasm_abs_path, ALBERT, TX_CANCEL_TRANSFER_TO_ETHEREUM_WASM,
+    TX_TRANSFER_TO_ETHEREUM_WASM, TX_WRITE_STORAGE_KEY_WASM,
 };
 use crate::e2e::setup::{Bin, Who};
 use crate::{run, run_as};
@@ -63,6 +70,10 @@ fn everything() {
         &ledger_addr,
     ];
 
+    let queue_before =
+        query_storage_bytes(&test, &ledger_addr, &storage_key("queue"))
+            .unwrap();
+
     for &dry_run in &[true, false] {
         let tx_args = if dry_run {
             vec![tx_args.clone(), vec!["--dry-run"]].concat()
@@ -81,14 +92,156 @@ fn everything() {
             namadac_tx.exp_string("Transaction accepted").unwrap();
             namadac_tx.exp_string("Transaction applied").unwrap();
         }
-        // TODO: we should check here explicitly with the ledger via a
-        //  Tendermint RPC call that the path `value/#EthBridge/queue`
-        //  is unchanged rather than relying solely  on looking at namadac
-        //  stdout.
         namadac_tx.exp_string("Transaction is invalid").unwrap();
         namadac_tx
             .exp_string(&format!("Rejected: {}", ETH_BRIDGE_ADDRESS))
             .unwrap();
         namadac_tx.assert_success();
+
+        // Neither the dry run nor the rejected tx may change the queue
+        let queue_after =
+            query_storage_bytes(&test, &ledger_addr, &storage_key("queue"))
+                .unwrap();
+        assert_eq!(queue_after, queue_before);
     }
+
+    // In contrast, an accepted tx does change the storage. The same tx
+    // writing into the signer's own storage is accepted by their VP.
+    let albert = find_address(&test, ALBERT).unwrap();
+    let albert_key = format!("#{albert}/arbitrary");
+    std::fs::write(&tx_data_path, &albert_key).unwrap();
+    let value_before =
+        query_storage_bytes(&test, &ledger_addr, &albert_key).unwrap();
+    assert_eq!(value_before, None);
+
+    let mut namadac_tx = run!(
+        test,
+        Bin::Client,
+        tx_args,
+        Some(CLIENT_COMMAND_TIMEOUT_SECONDS)
+    )
+    .unwrap();
+    namadac_tx.exp_string("Transaction is valid").unwrap();
+    namadac_tx.assert_success();
+
+    let value_after =
+        query_storage_bytes(&test, &ledger_addr, &albert_key).unwrap();
+    assert!(value_after.is_some());
+}
+
+/// Test that a well-formed transfer to Ethereum whose amount is escrowed can
+/// be added to the bridge's queue, while a transfer of a zero amount cannot,
+/// and that the queued transfer can be cancelled by its sender.
+#[test]
+fn queue_transfer_to_ethereum() {
+    const LEDGER_STARTUP_TIMEOUT_SECONDS: u64 = 30;
+    const CLIENT_COMMAND_TIMEOUT_SECONDS: u64 = 30;
+    const SOLE_VALIDATOR: Who = Who::Validator(0);
+
+    let test = setup::single_node_net().unwrap();
+
+    let mut namadan_ledger = run_as!(
+        test,
+        SOLE_VALIDATOR,
+        Bin::Node,
+        &["ledger"],
+        Some(LEDGER_STARTUP_TIMEOUT_SECONDS)
+    )
+    .unwrap();
+    namadan_ledger
+        .exp_string("Namada ledger node started")
+        .unwrap();
+    namadan_ledger.exp_string("Committed block hash").unwrap();
+    let _bg_ledger = namadan_ledger.background();
+
+    let ledger_addr = get_actor_rpc(&test, &SOLE_VALIDATOR);
+    let albert = find_address(&test, ALBERT).unwrap();
+    let valid = TransferToEthereum::new(
+        albert,
+        "0x6b175474e89094c44da98b954eedeac495271d0f"
+            .parse()
+            .unwrap(),
+        eth(),
+        token::Amount::from(10_000_000),
+        token::Amount::from(1_000_000),
+    )
+    .unwrap();
+    let zero_amount = TransferToEthereum {
+        amount: token::Amount::default(),
+        ..valid.clone()
+    };
+
+    let tx_code_path = wasm_abs_path(TX_TRANSFER_TO_ETHEREUM_WASM);
+    let tx_code_path = tx_code_path.to_string_lossy().to_string();
+    let tx_data_path = test.test_dir.path().join("transfer.data");
+    let tx_data_path = tx_data_path.to_string_lossy().to_string();
+    let tx_args = vec![
+        "tx",
+        "--signer",
+        ALBERT,
+        "--code-path",
+        &tx_code_path,
+        "--data-path",
+        &tx_data_path,
+        "--ledger-address",
+        &ledger_addr,
+    ];
+
+    for (transfer, is_valid) in [(&zero_amount, false), (&valid, true)] {
+        std::fs::write(&tx_data_path, transfer.try_to_vec().unwrap()).unwrap();
+        let mut namadac_tx = run!(
+            test,
+            Bin::Client,
+            &tx_args,
+            Some(CLIENT_COMMAND_TIMEOUT_SECONDS)
+        )
+        .unwrap();
+        if is_valid {
+            namadac_tx.exp_string("Transaction is valid").unwrap();
+        } else {
+            namadac_tx.exp_string("Transaction is invalid").unwrap();
+            namadac_tx
+                .exp_string(&format!("Rejected: {}", ETH_BRIDGE_ADDRESS))
+                .unwrap();
+        }
+        namadac_tx.assert_success();
+    }
+
+    // Only the valid transfer is queued
+    let queue = query_storage_bytes(&test, &ledger_addr, &storage_key("queue"))
+        .unwrap()
+        .expect("The queue should not be empty");
+    let queue = HEXLOWER.decode(queue.as_bytes()).unwrap();
+    let queue = Vec::<TransferToEthereum>::try_from_slice(&queue).unwrap();
+    assert_eq!(queue, vec![valid.clone()]);
+
+    // Cancel the pending transfer
+    let cancel_code_path = wasm_abs_path(TX_CANCEL_TRANSFER_TO_ETHEREUM_WASM);
+    let cancel_code_path = cancel_code_path.to_string_lossy().to_string();
+    let cancel_args = vec![
+        "tx",
+        "--signer",
+        ALBERT,
+        "--code-path",
+        &cancel_code_path,
+        "--data-path",
+        &tx_data_path,
+        "--ledger-address",
+        &ledger_addr,
+    ];
+    std::fs::write(&tx_data_path, valid.try_to_vec().unwrap()).unwrap();
+    let mut namadac_tx = run!(
+        test,
+        Bin::Client,
+        &cancel_args,
+        Some(CLIENT_COMMAND_TIMEOUT_SECONDS)
+    )
+    .unwrap();
+    namadac_tx.exp_string("Transaction is valid").unwrap();
+    namadac_tx.assert_success();
+
+    // The queue is empty
+    let queue = query_storage_bytes(&test, &ledger_addr, &storage_key("queue"))
+        .unwrap();
+    assert!(queue.is_none());
 }
@@ -48,7 +48,7 @@ pub fn find_address(test: &Test, alias: impl AsRef<str>) -> Result<Address> {
 pub fn get_actor_rpc(test: &Test, who: &Who) -> String {
     let base_dir = test.get_base_dir(who);
     let tendermint_mode = match who {
-        Who::NonValidator => TendermintMode::Full,
+        Who::NonValidator | Who::Fullnode(_) => TendermintMode::Full,
         Who::Validator(_) => TendermintMode::Validator,
     };
     let config =
@@ -59,7 +59,7 @@ pub fn get_actor_rpc(test: &Test, who: &Who) -> String {
 /// Get the public key of the validator
 pub fn get_validator_pk(test: &Test, who: &Who) -> Option<common::PublicKey> {
     let index = match who {
-        Who::NonValidator => return None,
+        Who::NonValidator | Who::Fullnode(_) => return None,
         Who::Validator(i) => i,
     };
     let file = format!("{}.toml", test.net.chain_id.as_str());
@@ -204,6 +204,34 @@ pub fn get_height(test: &Test, ledger_address: &str) -> Result<u64> {
     })
 }
 
+/// Query the raw bytes stored at the given storage key. Returns the
+/// hex-encoded bytes, or `None` if there's no value at the key.
+pub fn query_storage_bytes(
+    test: &Test,
+    ledger_address: &str,
+    storage_key: &str,
+) -> Result<Option<String>> {
+    let mut query = run!(
+        test,
+        Bin::Client,
+        &[
+            "query-bytes",
+            "--storage-key",
+            storage_key,
+            "--ledger-address",
+            ledger_address
+        ],
+        Some(10)
+    )?;
+    let (_unread, matched) =
+        query.exp_regex("(Found data: 0x[0-9a-f]*|No data found for key)")?;
+    query.assert_success();
+    Ok(strip_trailing_newline(&matched)
+        .trim()
+        .strip_prefix("Found data: 0x")
+        .map(ToOwned::to_owned))
+}
+
 /// Sleep until the given height is reached or panic when time out is reached
 /// before the height
 pub fn wait_for_block_height(
@@ -226,6 +254,28 @@ pub fn wait_for_block_height(
     }
 }
 
+/// Sleep until the given epoch is reached or panic when time out is reached
+/// before the epoch
+pub fn wait_for_epoch(
+    test: &Test,
+    ledger_address: &str,
+    epoch: Epoch,
+    timeout_secs: u64,
+) -> Result<Epoch> {
+    let start = Instant::now();
+    let loop_timeout = Duration::new(timeout_secs, 0);
+    loop {
+        let current = get_epoch(test, ledger_address)?;
+        if current >= epoch {
+            break Ok(current);
+        }
+        if Instant::now().duration_since(start) > loop_timeout {
+            panic!("Timed out waiting for epoch {epoch}, current {current}");
+        }
+        sleep(1);
+    }
+}
+
 /// Are the E2E tests be running in debug mode?
 pub fn is_debug_mode() -> bool {
     match env::var(ENV_VAR_DEBUG) {
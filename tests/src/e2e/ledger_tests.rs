@@ -19,8 +19,13 @@ use std::time::{Duration, Instant};
 use borsh::BorshSerialize;
 use color_eyre::eyre::Result;
 use data_encoding::HEXLOWER;
+use namada::ledger::governance::storage as gov_storage;
+use namada::proto::Tx;
 use namada::types::address::{btc, eth, masp_rewards, Address};
+use namada::types::hash::Hash;
+use namada::types::storage::Epoch;
 use namada::types::token;
+use namada::types::transaction::{Fee, GasLimit, WrapperTx};
 use namada_apps::client::tx::ShieldedContext;
 use namada_apps::config::genesis::genesis_config::{
     GenesisConfig, ParametersConfig, PosParamsConfig,
@@ -28,10 +33,13 @@ use namada_apps::config::genesis::genesis_config::{
 use serde_json::json;
 use setup::constants::*;
 
-use super::helpers::{get_height, is_debug_mode, wait_for_block_height};
+use super::helpers::{
+    get_height, is_debug_mode, wait_for_block_height, wait_for_epoch,
+};
 use super::setup::get_all_wasms_hashes;
 use crate::e2e::helpers::{
-    epoch_sleep, find_address, find_bonded_stake, get_actor_rpc, get_epoch,
+    epoch_sleep, find_address, find_bonded_stake, find_keypair, get_actor_rpc,
+    get_epoch,
 };
 use crate::e2e::setup::{self, default_port_offset, sleep, Bin, Who};
 use crate::{run, run_as};
@@ -165,6 +173,56 @@ fn test_node_connectivity_and_consensus() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Run a genesis validator node and let it produce some blocks
+/// 2. Run a full node peered to the validator
+/// 3. Check that the full node catches up with the validator
+/// 4. Query the full node's state
+#[test]
+fn test_fullnode_catches_up() -> Result<()> {
+    let test = setup::single_node_net()?;
+    setup::add_fullnodes(&test, 1)?;
+
+    // 1. Run a genesis validator node and let it produce some blocks
+    let args = ["ledger"];
+    let mut validator_0 =
+        run_as!(test, Who::Validator(0), Bin::Node, args, Some(40))?;
+    validator_0.exp_string("Namada ledger node started")?;
+    validator_0.exp_string("This node is a validator")?;
+    let _bg_validator_0 = validator_0.background();
+
+    let validator_0_rpc = get_actor_rpc(&test, &Who::Validator(0));
+    wait_for_block_height(&test, &validator_0_rpc, 5, 30)?;
+
+    // 2. Run a full node peered to the validator
+    let mut fullnode =
+        run_as!(test, Who::Fullnode(0), Bin::Node, args, Some(40))?;
+    fullnode.exp_string("Namada ledger node started")?;
+    fullnode.exp_string("This node is not a validator")?;
+    let _bg_fullnode = fullnode.background();
+
+    // 3. Check that the full node catches up with the validator
+    let fullnode_rpc = get_actor_rpc(&test, &Who::Fullnode(0));
+    let validator_height = get_height(&test, &validator_0_rpc)?;
+    wait_for_block_height(&test, &fullnode_rpc, validator_height, 30)?;
+
+    // 4. Query the full node's state
+    let query_balance_args = [
+        "balance",
+        "--owner",
+        ALBERT,
+        "--token",
+        NAM,
+        "--ledger-address",
+        &fullnode_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_balance_args, Some(40))?;
+    client.exp_string("NAM: 1000000")?;
+    client.assert_success();
+
+    Ok(())
+}
+
 /// In this test we:
 /// 1. Start up the ledger
 /// 2. Kill the tendermint process
@@ -459,6 +517,110 @@ fn ledger_txs_and_queries() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Run the ledger node with a VP whitelist
+/// 2. Initialize an account with the user VP
+/// 3. Query the VP hash of the account back
+/// 4. Attempt to initialize an account with a VP that's not whitelisted, which
+///    is rejected before it's submitted
+#[test]
+fn init_account_with_vp() -> Result<()> {
+    let working_dir = setup::working_dir();
+    let test = setup::network(
+        |genesis| {
+            let parameters = ParametersConfig {
+                vp_whitelist: Some(get_all_wasms_hashes(
+                    &working_dir,
+                    Some("vp_"),
+                )),
+                ..genesis.parameters
+            };
+            GenesisConfig {
+                parameters,
+                ..genesis
+            }
+        },
+        None,
+    )?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Namada ledger node started")?;
+    let _bg_ledger = ledger.background();
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+    let vp_user = wasm_abs_path(VP_USER_WASM);
+    let vp_user_hash = Hash::sha256(std::fs::read(&vp_user)?).to_string();
+    let vp_user = vp_user.to_string_lossy();
+
+    // 2. Initialize an account with the user VP
+    let tx_args = vec![
+        "init-account",
+        "--source",
+        BERTHA,
+        "--public-key",
+        // Value obtained from
+        // `namada::types::key::ed25519::tests::gen_keypair`
+        "001be519a321e29020fa3cbfbfd01bd5e92db134305609270b71dace25b5a21168",
+        "--code-path",
+        &vp_user,
+        "--alias",
+        "Test-Account",
+        "--gas-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--gas-token",
+        NAM,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 3. Query the VP hash of the account back
+    let query_args = vec![
+        "query-account-vp",
+        "--address",
+        "Test-Account",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string(&vp_user_hash)?;
+    client.assert_success();
+
+    // 4. Attempt to initialize an account with a VP that's not whitelisted
+    let vp_always_true = wasm_abs_path(VP_ALWAYS_TRUE_WASM);
+    let vp_always_true = vp_always_true.to_string_lossy();
+    let tx_args = vec![
+        "init-account",
+        "--source",
+        BERTHA,
+        "--public-key",
+        "001be519a321e29020fa3cbfbfd01bd5e92db134305609270b71dace25b5a21168",
+        "--code-path",
+        &vp_always_true,
+        "--alias",
+        "Test-Account-2",
+        "--gas-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--gas-token",
+        NAM,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("is not in the VP whitelist of the chain")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
 /// In this test we:
 /// 1. Run the ledger node
 /// 2. Attempt to spend 10 BTC at SK(A) to PA(B)
@@ -1646,6 +1808,7 @@ fn invalid_transactions() -> Result<()> {
         amount: token::Amount::whole(1),
         key: None,
         shielded: None,
+        memo: None,
     };
     let data = transfer
         .try_to_vec()
@@ -1746,6 +1909,89 @@ fn invalid_transactions() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Sign a transfer outside of the client and write it to a file
+/// 3. Submit the signed transaction from the file
+/// 4. Submit a file that doesn't contain a signed wrapper transaction
+#[test]
+fn ledger_submit_signed_tx() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Namada ledger node started")?;
+    let _bg_ledger = ledger.background();
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Sign a transfer with Albert's key, as an offline wallet would
+    let albert_key = find_keypair(&test, ALBERT_KEY)?;
+    let transfer = token::Transfer {
+        source: find_address(&test, ALBERT)?,
+        target: find_address(&test, BERTHA)?,
+        token: find_address(&test, NAM)?,
+        sub_prefix: None,
+        amount: token::Amount::whole(10),
+        key: None,
+        shielded: None,
+        memo: None,
+    };
+    let tx_code = std::fs::read(wasm_abs_path(TX_TRANSFER_WASM))?;
+    let inner_tx = Tx::new(tx_code, Some(transfer.try_to_vec()?))
+        .sign(&albert_key, &test.net.chain_id);
+    let epoch = get_epoch(&test, &validator_one_rpc)?;
+    let wrapper_tx = WrapperTx::new(
+        Fee {
+            amount: token::Amount::from(0),
+            token: find_address(&test, NAM)?,
+        },
+        &albert_key,
+        epoch,
+        GasLimit::from(0),
+        inner_tx.clone(),
+        Default::default(),
+    )
+    .sign(&albert_key, &test.net.chain_id)
+    .expect("Signing the wrapper tx shouldn't fail");
+    let tx_path = test.test_dir.path().join("signed.tx");
+    std::fs::write(&tx_path, wrapper_tx.try_to_vec()?)?;
+    let tx_path = tx_path.to_string_lossy();
+
+    // 3. Submit the signed transaction from the file
+    let tx_args = vec![
+        "signed-tx",
+        "--tx-path",
+        &tx_path,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Transaction accepted")?;
+    client.exp_string("Transaction applied")?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. The inner tx is signed, but without a wrapper it must be rejected
+    // before it gets broadcast
+    let inner_tx_path = test.test_dir.path().join("inner.tx");
+    std::fs::write(&inner_tx_path, inner_tx.try_to_vec()?)?;
+    let inner_tx_path = inner_tx_path.to_string_lossy();
+    let tx_args = vec![
+        "signed-tx",
+        "--tx-path",
+        &inner_tx_path,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Invalid signed transaction")?;
+    client.assert_failure();
+
+    Ok(())
+}
+
 /// PoS bonding, unbonding and withdrawal tests. In this test we:
 ///
 /// 1. Run the ledger node with shorter epochs for faster progression
@@ -2010,8 +2256,8 @@ fn pos_init_validator() -> Result<()> {
     client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 3. Submit a delegation to the new validator
-    //    First, transfer some tokens to the validator's key for fees:
+    // 3. Submit a delegation to the new validator First, transfer some tokens
+    //    to the validator's key for fees:
     let tx_args = vec![
         "transfer",
         "--source",
@@ -2697,6 +2943,132 @@ fn proposal_offline() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit a proposal, vote it through and wait for its grace epoch
+/// 3. Check that the proposal code has been executed
+#[test]
+fn proposal_executes_at_grace_epoch() -> Result<()> {
+    let test = setup::network(fast_epochs_genesis, None)?;
+
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Namada ledger node started")?;
+    let _bg_ledger = ledger.background();
+
+    let executed = submit_proposal_until_grace_epoch(&test, &[(0, "yay")])?;
+    assert!(executed, "The accepted proposal must be executed");
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Submit a proposal, vote against it and wait for its grace epoch
+/// 3. Check that the proposal code has not been executed
+#[test]
+fn proposal_without_quorum_is_not_executed() -> Result<()> {
+    let test = setup::network(fast_epochs_genesis, None)?;
+
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Namada ledger node started")?;
+    let _bg_ledger = ledger.background();
+
+    let executed = submit_proposal_until_grace_epoch(&test, &[(0, "nay")])?;
+    assert!(!executed, "The rejected proposal must not be executed");
+
+    Ok(())
+}
+
+/// Set the epochs to last for 1 second and at least 1 block.
+fn fast_epochs_genesis(genesis: GenesisConfig) -> GenesisConfig {
+    let parameters = ParametersConfig {
+        epochs_per_year: epochs_per_year_from_min_duration(1),
+        min_num_of_blocks: 1,
+        max_expected_time_per_block: 1,
+        ..genesis.parameters
+    };
+    GenesisConfig {
+        parameters,
+        ..genesis
+    }
+}
+
+/// Submit the proposal from [`prepare_proposal_data`], cast the given votes
+/// of validators by their index, wait until the proposal's grace epoch and
+/// return whether the proposal's code has been executed. The execution is
+/// checked by querying the storage value written by the `TX_PROPOSAL_CODE`.
+/// The ledger of validator 0 must be running.
+fn submit_proposal_until_grace_epoch(
+    test: &setup::Test,
+    votes: &[(u64, &str)],
+) -> Result<bool> {
+    let validator_one_rpc = get_actor_rpc(test, &Who::Validator(0));
+
+    // Submit the proposal
+    let albert = find_address(test, ALBERT)?;
+    let proposal_json_path = prepare_proposal_data(test, albert);
+    let submit_proposal_args = vec![
+        "init-proposal",
+        "--data-path",
+        proposal_json_path.to_str().unwrap(),
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, submit_proposal_args, Some(40))?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // Vote once the voting period has started
+    wait_for_epoch(
+        test,
+        &validator_one_rpc,
+        Epoch(PROPOSAL_VOTING_START_EPOCH + 1),
+        240,
+    )?;
+    for (validator, vote) in votes {
+        let signer = format!("validator-{}", validator);
+        let vote_args = vec![
+            "vote-proposal",
+            "--proposal-id",
+            "0",
+            "--vote",
+            *vote,
+            "--signer",
+            &signer,
+            "--ledger-address",
+            &validator_one_rpc,
+        ];
+        let mut client = run_as!(
+            test,
+            Who::Validator(*validator),
+            Bin::Client,
+            vote_args,
+            Some(40)
+        )?;
+        client.exp_string("Transaction is valid.")?;
+        client.assert_success();
+    }
+
+    // The proposal is executed at the beginning of its grace epoch
+    wait_for_epoch(test, &validator_one_rpc, Epoch(PROPOSAL_GRACE_EPOCH), 240)?;
+    let executed_value = HEXLOWER.encode(&9_u64.try_to_vec().unwrap());
+    let storage_key =
+        gov_storage::get_min_proposal_grace_epoch_key().to_string();
+    let query_args = vec![
+        "query-bytes",
+        "--storage-key",
+        &storage_key,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    let (_unread, matched) = client.exp_regex("Found data: 0x[0-9a-f]*")?;
+    client.assert_success();
+    Ok(matched.trim() == format!("Found data: 0x{}", executed_value))
+}
+
 fn generate_proposal_json_file(
     proposal_path: &std::path::Path,
     proposal_content: &serde_json::Value,
@@ -3243,13 +3615,12 @@ fn double_signing_gets_slashed() -> Result<()> {
 
 /// In this test we:
 /// 1. Run the ledger node
-/// 2. For some transactions that need signature authorization:
-///    2a. Generate a new key for an implicit account.
-///    2b. Send some funds to the implicit account.
-///    2c. Submit the tx with the implicit account as the source, that
-///        requires that the account has revealed its PK. This should be done
-///        by the client automatically.
-///    2d. Submit same tx again, this time the client shouldn't reveal again.
+/// 2. For some transactions that need signature authorization: 2a. Generate a
+///    new key for an implicit account. 2b. Send some funds to the implicit
+///    account. 2c. Submit the tx with the implicit account as the source, that
+///    requires that the account has revealed its PK. This should be done by the
+///    client automatically. 2d. Submit same tx again, this time the client
+///    shouldn't reveal again.
 #[test]
 fn implicit_account_reveal_pk() -> Result<()> {
     let test = setup::network(|genesis| genesis, None)?;
@@ -3367,6 +3738,13 @@ fn implicit_account_reveal_pk() -> Result<()> {
     Ok(())
 }
 
+/// The voting start epoch of the proposal from [`prepare_proposal_data`]
+const PROPOSAL_VOTING_START_EPOCH: u64 = 12;
+/// The voting end epoch of the proposal from [`prepare_proposal_data`]
+const PROPOSAL_VOTING_END_EPOCH: u64 = 24;
+/// The grace epoch of the proposal from [`prepare_proposal_data`]
+const PROPOSAL_GRACE_EPOCH: u64 = 30;
+
 /// Prepare proposal data in the test's temp dir from the given source address.
 /// This can be submitted with "init-proposal" command.
 fn prepare_proposal_data(test: &setup::Test, source: Address) -> PathBuf {
@@ -3385,9 +3763,9 @@ fn prepare_proposal_data(test: &setup::Test, source: Address) -> PathBuf {
                 "requires": "2"
             },
             "author": source,
-            "voting_start_epoch": 12_u64,
-            "voting_end_epoch": 24_u64,
-            "grace_epoch": 30_u64,
+            "voting_start_epoch": PROPOSAL_VOTING_START_EPOCH,
+            "voting_end_epoch": PROPOSAL_VOTING_END_EPOCH,
+            "grace_epoch": PROPOSAL_GRACE_EPOCH,
             "proposal_code_path": proposal_code.to_str().unwrap()
         }
     );
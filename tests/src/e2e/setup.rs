@@ -22,6 +22,8 @@ use itertools::{Either, Itertools};
 use namada::types::chain::ChainId;
 use namada_apps::client::utils;
 use namada_apps::config::genesis::genesis_config::{self, GenesisConfig};
+use namada_apps::config::global::GlobalConfig;
+use namada_apps::config::TendermintMode;
 use namada_apps::{config, wallet};
 use rand::Rng;
 use serde_json;
@@ -60,6 +62,10 @@ pub struct Network {
 /// Offset the ports used in the network configuration to avoid shared resources
 pub const ANOTHER_CHAIN_PORT_OFFSET: u16 = 1000;
 
+/// Offset the ports used by the full nodes from the ports of the genesis
+/// validators
+pub const FULLNODE_PORT_OFFSET: u16 = 2000;
+
 /// Default functions for offsetting ports when
 /// adding multiple validators to a network
 pub fn default_port_offset(ix: u8) -> u16 {
@@ -211,6 +217,63 @@ pub fn network(
     })
 }
 
+/// Provision `num` full nodes, i.e. non-validator nodes that are peered to the
+/// genesis validators, to be run with [`Who::Fullnode`]. Unlike
+/// [`Who::NonValidator`], whose base dir is shared with the client, every full
+/// node has its own base dir and ports.
+pub fn add_fullnodes(test: &Test, num: u8) -> Result<()> {
+    let chain_id = &test.net.chain_id;
+    let source_dir = test.test_dir.path();
+    let source_chain_dir = source_dir.join(chain_id.as_str());
+    let genesis_file = format!("{}.toml", chain_id.as_str());
+    let validator_0 = test.genesis.validator.get("validator-0").unwrap();
+    let net_address_0 =
+        SocketAddr::from_str(validator_0.net_address.as_ref().unwrap())
+            .unwrap();
+    for ix in 0..num {
+        let base_dir = test.get_base_dir(&Who::Fullnode(ix as u64));
+        let chain_dir = base_dir.join(chain_id.as_str());
+        fs::create_dir_all(&chain_dir)?;
+
+        // Copy the global config, the genesis, the wallet and the WASMs
+        fs::copy(
+            GlobalConfig::file_path(source_dir),
+            GlobalConfig::file_path(&base_dir),
+        )?;
+        fs::copy(source_dir.join(&genesis_file), base_dir.join(&genesis_file))?;
+        fs::copy(
+            wallet::wallet_file(&source_chain_dir),
+            wallet::wallet_file(&chain_dir),
+        )?;
+        fs_extra::dir::copy(
+            source_chain_dir.join(config::DEFAULT_WASM_DIR),
+            &chain_dir,
+            &fs_extra::dir::CopyOptions::new(),
+        )?;
+
+        // The ledger config has the validators as persistent peers, but the
+        // ports must be distinct
+        let mut config = config::Config::load(
+            source_dir,
+            chain_id,
+            Some(TendermintMode::Full),
+        );
+        config.ledger.shell.base_dir = base_dir.clone();
+        let first_port = net_address_0.port()
+            + FULLNODE_PORT_OFFSET
+            + default_port_offset(ix);
+        config.ledger.tendermint.p2p_address.set_port(first_port);
+        config
+            .ledger
+            .tendermint
+            .rpc_address
+            .set_port(first_port + 1);
+        config.ledger.shell.ledger_address.set_port(first_port + 2);
+        config.write(&base_dir, chain_id, true)?;
+    }
+    Ok(())
+}
+
 /// Namada binaries
 #[derive(Debug)]
 pub enum Bin {
@@ -349,6 +412,9 @@ pub enum Who {
     NonValidator,
     // Genesis validator with a given index, starting from `0`
     Validator(u64),
+    // A full node with a given index, starting from `0`, provisioned with
+    // `add_fullnodes`
+    Fullnode(u64),
 }
 
 impl Test {
@@ -392,7 +458,7 @@ impl Test {
     {
         let base_dir = self.get_base_dir(&who);
         let mode = match &who {
-            Who::NonValidator => "full",
+            Who::NonValidator | Who::Fullnode(_) => "full",
             Who::Validator(_) => "validator",
         };
         run_cmd(
@@ -416,6 +482,11 @@ impl Test {
                 .join(utils::NET_ACCOUNTS_DIR)
                 .join(format!("validator-{}", index))
                 .join(config::DEFAULT_BASE_DIR),
+            Who::Fullnode(index) => self
+                .test_dir
+                .path()
+                .join(format!("fullnode-{}", index))
+                .join(config::DEFAULT_BASE_DIR),
         }
     }
 }
@@ -778,7 +849,9 @@ pub fn sleep(seconds: u64) {
 #[allow(dead_code)]
 pub mod constants {
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::sync::Mutex;
 
     // User addresses aliases
     pub const ALBERT: &str = "Albert";
@@ -831,6 +904,10 @@ pub mod constants {
     pub const TX_INIT_PROPOSAL: &str = "wasm_for_tests/tx_init_proposal.wasm";
     pub const TX_WRITE_STORAGE_KEY_WASM: &str =
         "wasm_for_tests/tx_write_storage_key.wasm";
+    pub const TX_TRANSFER_TO_ETHEREUM_WASM: &str =
+        "wasm_for_tests/tx_transfer_to_ethereum.wasm";
+    pub const TX_CANCEL_TRANSFER_TO_ETHEREUM_WASM: &str =
+        "wasm_for_tests/tx_cancel_transfer_to_ethereum.wasm";
     pub const TX_IBC_WASM: &str = "wasm/tx_ibc.wasm";
     pub const VP_ALWAYS_TRUE_WASM: &str = "wasm_for_tests/vp_always_true.wasm";
     pub const VP_ALWAYS_FALSE_WASM: &str =
@@ -838,10 +915,49 @@ pub mod constants {
     pub const TX_MINT_TOKENS_WASM: &str = "wasm_for_tests/tx_mint_tokens.wasm";
     pub const TX_PROPOSAL_CODE: &str = "wasm_for_tests/tx_proposal_code.wasm";
 
-    /// Find the absolute path to one of the WASM files above
+    /// The directory with the sources of the test WASMs
+    const WASM_FOR_TESTS_SOURCE_DIR: &str = "wasm_for_tests/wasm_source";
+
+    /// Serializes the builds of the missing test WASMs
+    static BUILD_TEST_WASM: Mutex<()> = Mutex::new(());
+
+    /// Find the absolute path to one of the WASM files above. A test WASM
+    /// that hasn't been built yet is built from its source.
     pub fn wasm_abs_path(file_name: &str) -> PathBuf {
         let working_dir = fs::canonicalize("..").unwrap();
-        working_dir.join(file_name)
+        let path = working_dir.join(file_name);
+        if file_name.starts_with("wasm_for_tests/") {
+            build_test_wasm(&working_dir, &path);
+        }
+        path
+    }
+
+    /// Build the test WASM at the given path with the `wasm_for_tests`
+    /// Makefile, unless it already exists
+    fn build_test_wasm(working_dir: &Path, path: &Path) {
+        let _guard = BUILD_TEST_WASM
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if path.exists() {
+            return;
+        }
+        let wasm = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .expect("A test WASM path must have a file name");
+        let status = Command::new("make")
+            .arg("-C")
+            .arg(working_dir.join(WASM_FOR_TESTS_SOURCE_DIR))
+            .arg(wasm)
+            .status()
+            .expect("Failed to run make to build a test WASM");
+        assert!(
+            status.success() && path.exists(),
+            "Failed to build the test WASM {}. Build it with `make -C {} {}`.",
+            path.to_string_lossy(),
+            WASM_FOR_TESTS_SOURCE_DIR,
+            wasm,
+        );
     }
 }
 
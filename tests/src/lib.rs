@@ -13,6 +13,9 @@ pub mod native_vp;
 pub mod storage;
 #[cfg(test)]
 mod storage_api;
+#[cfg(feature = "wasm-runtime")]
+pub mod vp_replay;
+pub mod wasms;
 
 /// Using this import requires `tracing` and `tracing-subscriber` dependencies.
 /// Set env var `RUST_LOG=info` to see the logs from a test run (and
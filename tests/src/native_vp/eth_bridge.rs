@@ -0,0 +1,172 @@
+use namada::ledger::eth_bridge::storage;
+use namada::ledger::eth_bridge::transfer::{EthAddress, TransferToEthereum};
+use namada::ledger::eth_bridge::vp::{EthBridge, ADDRESS};
+use namada::proto::Tx;
+use namada::types::address::{self, Address};
+use namada::types::key::{common, RefTo};
+use namada::types::token;
+use namada_tx_prelude::{BorshSerialize, StorageRead};
+
+use crate::native_vp::TestNativeVpEnv;
+use crate::tx::tx_host_env;
+
+/// The sender's balance before queueing a transfer
+const INITIAL_BALANCE: u64 = 1_000;
+
+fn transfer(sender: &Address) -> TransferToEthereum {
+    TransferToEthereum::new(
+        sender.clone(),
+        EthAddress([1; 20]),
+        address::eth(),
+        token::Amount::from(100),
+        token::Amount::from(10),
+    )
+    .unwrap()
+}
+
+fn sender_keypair() -> common::SecretKey {
+    namada::types::key::testing::keypair_1()
+}
+
+/// Initialize the tx env with a funded sender and queue the given transfer
+/// in a committed block.
+fn init_queued_transfer(transfer: &TransferToEthereum) {
+    tx_host_env::init();
+    tx_host_env::with(|tx_env| {
+        tx_env.spawn_accounts([&transfer.sender]);
+        tx_env.write_public_key(&transfer.sender, &sender_keypair().ref_to());
+        tx_env.credit_tokens(
+            &transfer.sender,
+            &transfer.asset,
+            None,
+            token::Amount::from(INITIAL_BALANCE),
+        );
+    });
+    escrow(transfer);
+    storage::push_to_queue(tx_host_env::ctx(), transfer.clone()).unwrap();
+    tx_host_env::commit_tx_and_block();
+}
+
+/// Transfer the amount and fee of a transfer to Ethereum between its sender
+/// and the bridge's escrow.
+fn transfer_escrow(
+    src: &Address,
+    dest: &Address,
+    transfer: &TransferToEthereum,
+) {
+    namada_tx_prelude::token::transfer(
+        tx_host_env::ctx(),
+        src,
+        dest,
+        &transfer.asset,
+        None,
+        transfer.amount + transfer.fee,
+        &None,
+        &None,
+    )
+    .unwrap();
+}
+
+fn escrow(transfer: &TransferToEthereum) {
+    transfer_escrow(&transfer.sender, &ADDRESS, transfer)
+}
+
+fn refund(transfer: &TransferToEthereum) {
+    transfer_escrow(&ADDRESS, &transfer.sender, transfer)
+}
+
+/// Validate the changes of the current tx, which cancels the given transfer
+/// and is signed with the given key, with the Ethereum bridge VP
+fn validate_cancellation(
+    transfer: &TransferToEthereum,
+    signer: &common::SecretKey,
+) -> bool {
+    tx_host_env::with(|tx_env| {
+        let tx_data = transfer.try_to_vec().unwrap();
+        tx_env.tx = Tx::new(vec![], Some(tx_data))
+            .sign(signer, &tx_env.storage.chain_id);
+    });
+    let tx_env = tx_host_env::take();
+    let vp_env = TestNativeVpEnv::from_tx_env(tx_env, ADDRESS);
+    let result = vp_env.validate_tx(|ctx| EthBridge { ctx });
+    tx_host_env::set(vp_env.tx_env);
+    result.expect("Validation must not fail")
+}
+
+fn balance(owner: &Address) -> token::Amount {
+    let key = token::balance_key(&address::eth(), owner);
+    tx_host_env::ctx().read(&key).unwrap().unwrap_or_default()
+}
+
+/// Test that a pending transfer to Ethereum can be cancelled by its sender,
+/// who is refunded its amount and fee.
+#[test]
+fn test_cancel_pending_transfer() {
+    let sender = address::testing::established_address_1();
+    let transfer = transfer(&sender);
+    init_queued_transfer(&transfer);
+    assert_eq!(balance(&sender), token::Amount::from(INITIAL_BALANCE - 110));
+
+    assert!(storage::remove_from_queue(tx_host_env::ctx(), &transfer).unwrap());
+    refund(&transfer);
+    assert!(validate_cancellation(&transfer, &sender_keypair()));
+
+    tx_host_env::commit_tx_and_block();
+    assert_eq!(storage::read_queue(tx_host_env::ctx()).unwrap(), vec![]);
+    assert_eq!(balance(&sender), token::Amount::from(INITIAL_BALANCE));
+    assert_eq!(balance(&ADDRESS), token::Amount::default());
+}
+
+/// Test that the cancellation of a transfer to Ethereum is rejected when it's
+/// not signed by the sender or when it's not fully refunded.
+#[test]
+fn test_cancel_transfer_rejected() {
+    let sender = address::testing::established_address_1();
+    let other = address::testing::established_address_2();
+    let other_keypair = namada::types::key::testing::keypair_2();
+    let transfer = transfer(&sender);
+
+    // Not signed by the sender
+    init_queued_transfer(&transfer);
+    storage::remove_from_queue(tx_host_env::ctx(), &transfer).unwrap();
+    refund(&transfer);
+    assert!(!validate_cancellation(&transfer, &other_keypair));
+
+    // Refunded to someone else
+    init_queued_transfer(&transfer);
+    tx_host_env::with(|tx_env| tx_env.spawn_accounts([&other]));
+    storage::remove_from_queue(tx_host_env::ctx(), &transfer).unwrap();
+    transfer_escrow(&ADDRESS, &other, &transfer);
+    assert!(!validate_cancellation(&transfer, &sender_keypair()));
+
+    // Cancelled without a refund
+    init_queued_transfer(&transfer);
+    storage::remove_from_queue(tx_host_env::ctx(), &transfer).unwrap();
+    assert!(!validate_cancellation(&transfer, &sender_keypair()));
+}
+
+/// Test that a transfer to Ethereum can no longer be cancelled once it has
+/// been taken from the queue to be relayed, even if the cancellation was
+/// submitted before.
+#[test]
+fn test_cancel_relayed_transfer() {
+    let sender = address::testing::established_address_1();
+    let transfer = transfer(&sender);
+    init_queued_transfer(&transfer);
+
+    // The transfer is taken from the queue before the cancellation is applied
+    tx_host_env::with(|tx_env| {
+        assert_eq!(
+            storage::pop_from_queue(&mut tx_env.storage).unwrap(),
+            Some(transfer.clone())
+        );
+    });
+
+    // The transfer is no longer pending, so it cannot be removed
+    let removed =
+        storage::remove_from_queue(tx_host_env::ctx(), &transfer).unwrap();
+    assert!(!removed);
+    // The escrow cannot be refunded without cancelling a pending transfer
+    refund(&transfer);
+    assert!(!validate_cancellation(&transfer, &sender_keypair()));
+}
@@ -1,3 +1,5 @@
+#[cfg(test)]
+mod eth_bridge;
 pub mod pos;
 
 use std::collections::BTreeSet;
@@ -30,6 +30,7 @@ mod tests {
     use namada::ledger::tx_env::TxEnv;
     use namada::proto::{SignedTxData, Tx};
     use namada::tendermint_proto::Protobuf;
+    use namada::types::chain::ChainId;
     use namada::types::key::*;
     use namada::types::storage::{self, BlockHash, BlockHeight, Key, KeySeg};
     use namada::types::time::DateTimeUtc;
@@ -45,6 +46,7 @@ mod tests {
     use super::{ibc, tx, vp};
     use crate::tx::{tx_host_env, TestTxEnv};
     use crate::vp::{vp_host_env, TestVpEnv};
+    use crate::wasms::TestWasms;
 
     // paths to the WASMs used for tests
     const VP_ALWAYS_TRUE_WASM: &str = "../wasm_for_tests/vp_always_true.wasm";
@@ -134,13 +136,11 @@ mod tests {
 
         // Trying to delete a validity predicate should fail
         let key = storage::Key::validity_predicate(&test_account);
-        assert!(
-            panic::catch_unwind(|| { tx::ctx().delete(&key).unwrap() })
-                .err()
-                .map(|a| a.downcast_ref::<String>().cloned().unwrap())
-                .unwrap()
-                .contains("CannotDeleteVp")
-        );
+        assert!(panic::catch_unwind(|| { tx::ctx().delete(&key).unwrap() })
+            .err()
+            .map(|a| a.downcast_ref::<String>().cloned().unwrap())
+            .unwrap()
+            .contains("CannotDeleteVp"));
     }
 
     #[test]
@@ -469,28 +469,25 @@ mod tests {
             None,
         ] {
             let signed_tx_data = vp_host_env::with(|env| {
-                env.tx = Tx::new(code.clone(), data.clone()).sign(&keypair);
+                env.tx = Tx::new(code.clone(), data.clone())
+                    .sign(&keypair, &env.storage.chain_id);
                 let tx_data = env.tx.data.as_ref().expect("data should exist");
 
                 SignedTxData::try_from_slice(&tx_data[..])
                     .expect("decoding signed data we just signed")
             });
             assert_eq!(&signed_tx_data.data, data);
-            assert!(
-                vp::CTX
-                    .verify_tx_signature(&pk, &signed_tx_data.sig)
-                    .unwrap()
-            );
+            assert!(vp::CTX
+                .verify_tx_signature(&pk, &signed_tx_data.sig)
+                .unwrap());
 
             let other_keypair = key::testing::keypair_2();
-            assert!(
-                !vp::CTX
-                    .verify_tx_signature(
-                        &other_keypair.ref_to(),
-                        &signed_tx_data.sig
-                    )
-                    .unwrap()
-            );
+            assert!(!vp::CTX
+                .verify_tx_signature(
+                    &other_keypair.ref_to(),
+                    &signed_tx_data.sig
+                )
+                .unwrap());
         }
     }
 
@@ -548,6 +545,37 @@ mod tests {
         assert!(!result);
     }
 
+    /// Test that a VP that reads storage costs more gas than a VP that
+    /// always accepts, which is the baseline of the VP benchmarks.
+    #[cfg(feature = "wasm-runtime")]
+    #[test]
+    fn test_vp_wasm_gas_baseline() {
+        let mut env = TestVpEnv::default();
+        // The VP reads this key, given in the tx data
+        let key = storage::Key::parse("key").unwrap();
+        env.storage.write(&key, vec![1]).unwrap();
+        env.tx = Tx::new(vec![], Some(key.try_to_vec().unwrap()));
+
+        let accepted = env
+            .run_vp_wasm(TestWasms::VpAlwaysTrue.read_bytes())
+            .unwrap();
+        assert!(accepted);
+        let always_true_gas = env.gas_meter.current_gas;
+
+        let accepted = env
+            .run_vp_wasm(TestWasms::VpReadStorageKey.read_bytes())
+            .unwrap();
+        assert!(accepted);
+        let read_storage_key_gas = env.gas_meter.current_gas;
+
+        assert!(
+            always_true_gas < read_storage_key_gas,
+            "{} >= {}",
+            always_true_gas,
+            read_storage_key_gas
+        );
+    }
+
     #[test]
     fn test_ibc_client() {
         // The environment must be initialized first
@@ -567,7 +595,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // get and increment the connection counter
         let counter_key = ibc::client_counter_key();
         let counter = tx::ctx()
@@ -604,7 +632,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
 
         // create a client with the message
         tx::ctx()
@@ -637,7 +665,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // get and update the client without a header
         let client_id = msg.client_id.clone();
         // update the client with the same state
@@ -682,7 +710,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // update the client with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -711,7 +739,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // upgrade the client with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -749,7 +777,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // get and increment the connection counter
         let counter_key = ibc::connection_counter_key();
         let counter = tx::ctx()
@@ -786,7 +814,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // init a connection with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -812,7 +840,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // open the connection with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -848,7 +876,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // open try a connection with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -875,7 +903,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // open the connection with the mssage
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -916,7 +944,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // not bind a port
         // get and increment the channel counter
         let counter_key = ibc::channel_counter_key();
@@ -957,7 +985,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // bind a port
         tx::ctx()
             .bind_port(&port_id)
@@ -1001,7 +1029,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // init a channel with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1025,7 +1053,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // open the channle with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1063,7 +1091,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // try open a channel with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1088,7 +1116,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // open a channel with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1128,7 +1156,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // close the channel with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1168,7 +1196,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
 
         // close the channel with the message
         tx::ctx()
@@ -1213,7 +1241,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // send the token and a packet with the data
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1253,7 +1281,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // ack the packet with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1302,7 +1330,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // send the token and a packet with the data
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1367,7 +1395,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // receive a packet with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1444,7 +1472,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // receive a packet with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1491,7 +1519,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // send a packet with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1520,7 +1548,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // ack the packet with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1571,7 +1599,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
         // receive a packet with the message
         tx::ctx()
             .dispatch_ibc_action(&tx_data)
@@ -1633,7 +1661,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
 
         // close the channel with the message
         tx::ctx()
@@ -1705,7 +1733,7 @@ mod tests {
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
         }
-        .sign(&key::testing::keypair_1());
+        .sign(&key::testing::keypair_1(), &ChainId::default());
 
         // close the channel with the message
         tx::ctx()
@@ -1,5 +1,6 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
+use borsh::BorshSerialize;
 use namada::ledger::gas::VpGasMeter;
 use namada::ledger::storage::mockdb::MockDB;
 use namada::ledger::storage::testing::TestStorage;
@@ -7,6 +8,8 @@ use namada::ledger::storage::write_log::WriteLog;
 use namada::proto::Tx;
 use namada::types::address::{self, Address};
 use namada::types::storage::{self, Key, TxIndex};
+use namada::types::validity_predicate::{VpEvent, VpRejection};
+use namada::types::{key, token};
 use namada::vm::prefix_iter::PrefixIterators;
 use namada::vm::wasm::{self, VpCache};
 use namada::vm::{self, WasmCacheRwAccess};
@@ -47,6 +50,8 @@ pub struct TestVpEnv {
     pub verifiers: BTreeSet<Address>,
     pub eval_runner: native_vp_host_env::VpEval,
     pub result_buffer: Option<Vec<u8>>,
+    pub events: Vec<VpEvent>,
+    pub rejection: Option<VpRejection>,
     pub vp_wasm_cache: VpCache<WasmCacheRwAccess>,
     pub vp_cache_dir: TempDir,
 }
@@ -73,6 +78,8 @@ impl Default for TestVpEnv {
             verifiers: BTreeSet::default(),
             eval_runner,
             result_buffer: None,
+            events: vec![],
+            rejection: None,
             vp_wasm_cache,
             vp_cache_dir,
         }
@@ -80,6 +87,18 @@ impl Default for TestVpEnv {
 }
 
 impl TestVpEnv {
+    /// Start building a VP environment for the given address with
+    /// pre-populated storage. See [`TestVpEnvBuilder`].
+    pub fn builder(addr: Address) -> TestVpEnvBuilder {
+        TestVpEnvBuilder {
+            addr,
+            pre: BTreeMap::default(),
+            post: BTreeMap::default(),
+            verifiers: BTreeSet::default(),
+            tx: Tx::new(vec![], None),
+        }
+    }
+
     pub fn all_touched_storage_keys(&self) -> BTreeSet<Key> {
         self.write_log.get_keys()
     }
@@ -87,6 +106,142 @@ impl TestVpEnv {
     pub fn get_verifiers(&self) -> BTreeSet<Address> {
         self.write_log.verifiers_and_changed_keys(&self.verifiers).0
     }
+
+    /// Run the given VP wasm code for the env's address against the env's
+    /// tx and state. The gas used by the VP is metered from zero in
+    /// [`TestVpEnv::gas_meter`] and the details of its rejection, if any, are
+    /// set in [`TestVpEnv::rejection`]. The modules compiled in the env's
+    /// cache are reused by the following runs.
+    #[cfg(feature = "wasm-runtime")]
+    pub fn run_vp_wasm(
+        &mut self,
+        vp_code: impl AsRef<[u8]>,
+    ) -> Result<bool, wasm::run::Error> {
+        self.gas_meter = VpGasMeter::new(0);
+        self.rejection = None;
+        wasm::run::vp(
+            vp_code,
+            &self.tx,
+            &self.tx_index,
+            &self.addr,
+            &self.storage,
+            &self.write_log,
+            &mut self.gas_meter,
+            &self.keys_changed,
+            &self.verifiers,
+            self.vp_wasm_cache.clone(),
+            &mut self.events,
+            &mut self.rejection,
+        )
+    }
+}
+
+/// A builder of a [`TestVpEnv`] that declares the storage before the tx (pre)
+/// and the tx's storage modifications (post). The changed keys and the
+/// verifiers are derived from the modifications when the env is built, like
+/// they are for a tx applied in the ledger.
+pub struct TestVpEnvBuilder {
+    addr: Address,
+    /// The values in storage before the tx
+    pre: BTreeMap<Key, Vec<u8>>,
+    /// The values written by the tx, or `None` for the deleted keys
+    post: BTreeMap<Key, Option<Vec<u8>>>,
+    /// The verifiers inserted by the tx
+    verifiers: BTreeSet<Address>,
+    tx: Tx,
+}
+
+impl TestVpEnvBuilder {
+    /// Set the value of a key in storage before the tx
+    pub fn pre(mut self, key: Key, value: impl BorshSerialize) -> Self {
+        self.pre.insert(key, value.try_to_vec().unwrap());
+        self
+    }
+
+    /// Write the value of a key in the tx
+    pub fn post(mut self, key: Key, value: impl BorshSerialize) -> Self {
+        self.post.insert(key, Some(value.try_to_vec().unwrap()));
+        self
+    }
+
+    /// Delete a key in the tx
+    pub fn delete(mut self, key: Key) -> Self {
+        self.post.insert(key, None);
+        self
+    }
+
+    /// Set the balance of the env's address in the given token before the tx
+    /// and after it. A `None` pre balance is not in storage, while a `None`
+    /// post balance is deleted by the tx, if there was a pre balance.
+    pub fn balance(
+        self,
+        token: &Address,
+        pre: Option<token::Amount>,
+        post: Option<token::Amount>,
+    ) -> Self {
+        let key = token::balance_key(token, &self.addr);
+        let builder = match pre {
+            Some(pre) => self.pre(key.clone(), pre),
+            None => self,
+        };
+        match (pre, post) {
+            (_, Some(post)) => builder.post(key, post),
+            (Some(_), None) => builder.delete(key),
+            (None, None) => builder,
+        }
+    }
+
+    /// Set the public key of the env's address in storage before the tx
+    pub fn public_key(self, public_key: &key::common::PublicKey) -> Self {
+        let key = key::pk_key(&self.addr);
+        self.pre(key, public_key)
+    }
+
+    /// Insert a verifier of the tx, in addition to the owners of the changed
+    /// keys
+    pub fn verifier(mut self, verifier: Address) -> Self {
+        self.verifiers.insert(verifier);
+        self
+    }
+
+    /// Set the tx
+    pub fn tx(mut self, tx: Tx) -> Self {
+        self.tx = tx;
+        self
+    }
+
+    /// Build the env with the storage before the tx committed and the tx's
+    /// modifications in the write log
+    pub fn build(self) -> TestVpEnv {
+        let mut storage = TestStorage::default();
+        // Write an empty validity predicate for the address, because it's
+        // used to check if the address exists
+        let vp_key = Key::validity_predicate(&self.addr);
+        storage.write(&vp_key, vec![]).unwrap();
+        for (key, value) in self.pre {
+            storage.write(&key, value).unwrap();
+        }
+
+        let mut write_log = WriteLog::default();
+        for (key, value) in self.post {
+            match value {
+                Some(value) => write_log.write(&key, value).unwrap(),
+                None => write_log.delete(&key).unwrap(),
+            };
+        }
+        let (verifiers, keys_changed) =
+            write_log.verifiers_and_changed_keys(&self.verifiers);
+
+        TestVpEnv {
+            addr: self.addr,
+            storage,
+            write_log,
+            tx: self.tx,
+            keys_changed,
+            verifiers,
+            ..Default::default()
+        }
+    }
 }
 
 /// This module allows to test code with vp host environment functions.
@@ -252,6 +407,8 @@ mod native_vp_host_env {
                                 verifiers,
                                 eval_runner,
                                 result_buffer,
+                                events,
+                                rejection,
                                 vp_wasm_cache,
                                 vp_cache_dir: _,
                             }: &mut TestVpEnv| {
@@ -266,6 +423,8 @@ mod native_vp_host_env {
                                 tx_index,
                                 verifiers,
                                 result_buffer,
+                                events,
+                                rejection,
                                 keys_changed,
                                 eval_runner,
                                 vp_wasm_cache,
@@ -296,6 +455,8 @@ mod native_vp_host_env {
                                 verifiers,
                                 eval_runner,
                                 result_buffer,
+                                events,
+                                rejection,
                                 vp_wasm_cache,
                                 vp_cache_dir: _,
                             }: &mut TestVpEnv| {
@@ -310,6 +471,8 @@ mod native_vp_host_env {
                                 tx_index,
                                 verifiers,
                                 result_buffer,
+                                events,
+                                rejection,
                                 keys_changed,
                                 eval_runner,
                                 vp_wasm_cache,
@@ -355,4 +518,6 @@ mod native_vp_host_env {
             input_data_len: u64,
         ) -> i64);
     native_host_fn!(vp_log_string(str_ptr: u64, str_len: u64));
+    native_host_fn!(vp_emit_event(event_ptr: u64, event_len: u64));
+    native_host_fn!(vp_reject(rejection_ptr: u64, rejection_len: u64));
 }
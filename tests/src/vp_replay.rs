@@ -0,0 +1,195 @@
+//! Replay of a validity predicate run in isolation against recorded storage,
+//! useful for debugging a VP's verdict outside of a running ledger.
+//!
+//! A [`VpReplay`] records everything that a VP can observe: the tx, the
+//! storage state before the tx was applied, the tx's modifications and the
+//! sets of changed keys and verifiers. It can be serialized with Borsh, so
+//! that it can be captured once and replayed as many times as needed with
+//! [`VpReplay::run`].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada::ledger::gas::VpGasMeter;
+use namada::ledger::storage::testing::TestStorage;
+use namada::ledger::storage::write_log::WriteLog;
+use namada::proto::Tx;
+use namada::types::address::Address;
+use namada::types::storage::{Key, TxIndex};
+use namada::types::validity_predicate::{VpEvent, VpRejection};
+use namada::vm::wasm;
+
+/// The recorded inputs of a VP run.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct VpReplay {
+    /// The address whose VP is being run
+    pub addr: Address,
+    /// The transaction that triggered the VP
+    pub tx: Tx,
+    /// The index of the transaction in its block
+    pub tx_index: TxIndex,
+    /// The storage values before the transaction was applied
+    pub pre_state: BTreeMap<Key, Vec<u8>>,
+    /// The storage modifications made by the transaction, with `None` for
+    /// a deleted key
+    pub modifications: BTreeMap<Key, Option<Vec<u8>>>,
+    /// The keys changed by the transaction
+    pub keys_changed: BTreeSet<Key>,
+    /// The verifiers of the transaction
+    pub verifiers: BTreeSet<Address>,
+}
+
+/// The outcome of a replayed VP run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VpReplayOutput {
+    /// The VP's verdict, or the error that aborted its run
+    pub result: Result<bool, String>,
+    /// The gas consumed by the VP
+    pub gas_used: u64,
+    /// The log lines emitted during the run, including the VP's own
+    /// `log_string` calls
+    pub logs: Vec<String>,
+    /// The events emitted by the VP, which are only kept when it accepts
+    pub events: Vec<VpEvent>,
+    /// The details of the VP's rejection, which are only kept when it rejects
+    pub rejection: Option<VpRejection>,
+}
+
+impl VpReplay {
+    /// Run the given VP wasm code against the recorded inputs.
+    pub fn run(&self, vp_code: impl AsRef<[u8]>) -> VpReplayOutput {
+        let mut storage = TestStorage::default();
+        for (key, value) in &self.pre_state {
+            storage.write(key, value).expect("cannot write pre state");
+        }
+        let mut write_log = WriteLog::default();
+        for (key, modification) in &self.modifications {
+            match modification {
+                Some(value) => write_log.write(key, value.clone()),
+                None => write_log.delete(key),
+            }
+            .expect("cannot write modification");
+        }
+
+        let (vp_cache, _cache_dir) =
+            wasm::compilation_cache::common::testing::cache();
+        let mut gas_meter = VpGasMeter::new(0);
+        let mut events = vec![];
+        let mut rejection = None;
+
+        let buffer = LogBuffer::default();
+        let subscriber = {
+            let buffer = buffer.clone();
+            tracing_subscriber::fmt()
+                .with_writer(move || buffer.clone())
+                .with_max_level(tracing::Level::TRACE)
+                .with_target(false)
+                .without_time()
+                .finish()
+        };
+        let result = tracing::subscriber::with_default(subscriber, || {
+            wasm::run::vp(
+                vp_code,
+                &self.tx,
+                &self.tx_index,
+                &self.addr,
+                &storage,
+                &write_log,
+                &mut gas_meter,
+                &self.keys_changed,
+                &self.verifiers,
+                vp_cache,
+                &mut events,
+                &mut rejection,
+            )
+        });
+
+        VpReplayOutput {
+            result: result.map_err(|err| err.to_string()),
+            gas_used: gas_meter.current_gas,
+            logs: buffer.lines(),
+            events,
+            rejection,
+        }
+    }
+}
+
+/// A shared buffer that collects the formatted log output.
+#[derive(Clone, Debug, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl LogBuffer {
+    fn lines(&self) -> Vec<String> {
+        let buffer = self.0.lock().unwrap();
+        String::from_utf8_lossy(&buffer)
+            .lines()
+            .map(|line| line.trim().to_owned())
+            .collect()
+    }
+}
+
+impl Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada::types::address;
+
+    use super::*;
+
+    const VP_READ_STORAGE_KEY_WASM: &str =
+        "../wasm_for_tests/vp_read_storage_key.wasm";
+
+    /// Test that a recorded rejection is reproduced deterministically from
+    /// its serialized form, together with the VP's logs.
+    #[test]
+    fn test_replay_reproduces_rejection() {
+        let vp_code =
+            std::fs::read(VP_READ_STORAGE_KEY_WASM).expect("cannot load wasm");
+        let addr = address::testing::established_address_1();
+        // The VP reads this key from the pre state and fails when it's
+        // missing
+        let key = Key::parse("replay/key").unwrap();
+        let tx = Tx::new(vec![], Some(key.try_to_vec().unwrap()));
+        let replay = VpReplay {
+            addr: addr.clone(),
+            tx,
+            tx_index: TxIndex::default(),
+            pre_state: BTreeMap::default(),
+            modifications: BTreeMap::default(),
+            keys_changed: BTreeSet::default(),
+            verifiers: BTreeSet::from([addr]),
+        };
+        let recorded = replay.try_to_vec().unwrap();
+
+        let replay = VpReplay::try_from_slice(&recorded).unwrap();
+        let output = replay.run(&vp_code);
+        assert!(output.result.is_err(), "{:#?}", output);
+        let expected_log = format!("WASM Validity predicate log: key {}", key);
+        assert!(
+            output.logs.iter().any(|line| line.ends_with(&expected_log)),
+            "{:#?}",
+            output.logs
+        );
+        // Replaying again gives the same output
+        assert_eq!(output, replay.run(&vp_code));
+
+        // With the key present in the pre state the VP accepts. The VP decodes
+        // the value as a `Vec<u8>`, so it must be Borsh encoded as such.
+        let mut replay = replay;
+        replay
+            .pre_state
+            .insert(key, vec![1_u8].try_to_vec().unwrap());
+        let output = replay.run(&vp_code);
+        assert_eq!(output.result, Ok(true), "{:#?}", output);
+    }
+}
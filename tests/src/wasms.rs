@@ -0,0 +1,54 @@
+//! The pre-built WASMs from the `wasm_for_tests` directory, which are used as
+//! fixtures in tests and benchmarks.
+
+use std::path::PathBuf;
+
+/// The WASMs in the `wasm_for_tests` directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestWasms {
+    TxMemoryLimit,
+    TxMintTokens,
+    TxNoOp,
+    TxProposalCode,
+    TxReadStorageKey,
+    TxWriteStorageKey,
+    VpAlwaysFalse,
+    VpAlwaysTrue,
+    VpEval,
+    VpMemoryLimit,
+    VpReadStorageKey,
+}
+
+impl TestWasms {
+    /// Get the file name of the WASM
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            TestWasms::TxMemoryLimit => "tx_memory_limit.wasm",
+            TestWasms::TxMintTokens => "tx_mint_tokens.wasm",
+            TestWasms::TxNoOp => "tx_no_op.wasm",
+            TestWasms::TxProposalCode => "tx_proposal_code.wasm",
+            TestWasms::TxReadStorageKey => "tx_read_storage_key.wasm",
+            TestWasms::TxWriteStorageKey => "tx_write_storage_key.wasm",
+            TestWasms::VpAlwaysFalse => "vp_always_false.wasm",
+            TestWasms::VpAlwaysTrue => "vp_always_true.wasm",
+            TestWasms::VpEval => "vp_eval.wasm",
+            TestWasms::VpMemoryLimit => "vp_memory_limit.wasm",
+            TestWasms::VpReadStorageKey => "vp_read_storage_key.wasm",
+        }
+    }
+
+    /// Get the absolute path to the WASM
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../wasm_for_tests")
+            .join(self.file_name())
+    }
+
+    /// Read the bytes of the WASM
+    pub fn read_bytes(&self) -> Vec<u8> {
+        let path = self.path();
+        std::fs::read(&path).unwrap_or_else(|err| {
+            panic!("cannot load wasm {}: {}", path.to_string_lossy(), err)
+        })
+    }
+}
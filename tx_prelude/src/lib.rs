@@ -26,6 +26,7 @@ pub use namada_core::ledger::storage_api::{
     StorageWrite,
 };
 pub use namada_core::ledger::tx_env::TxEnv;
+pub use namada_core::ledger::{eth_bridge, testnet_faucet};
 pub use namada_core::proto::{Signed, SignedTxData};
 pub use namada_core::types::address::Address;
 use namada_core::types::chain::CHAIN_ID_LENGTH;
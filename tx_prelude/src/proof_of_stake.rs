@@ -8,9 +8,10 @@ use namada_proof_of_stake::storage::{
     bond_key, params_key, total_deltas_key, unbond_key,
     validator_address_raw_hash_key, validator_commission_rate_key,
     validator_consensus_key_key, validator_deltas_key,
-    validator_max_commission_rate_change_key, validator_set_key,
-    validator_slashes_key, validator_state_key, BondId, Bonds, TotalDeltas,
-    Unbonds, ValidatorConsensusKeys, ValidatorDeltas, ValidatorSets,
+    validator_jailed_epoch_key, validator_max_commission_rate_change_key,
+    validator_set_key, validator_slashes_key, validator_state_key, BondId,
+    Bonds, TotalDeltas, Unbonds, ValidatorConsensusKeys, ValidatorDeltas,
+    ValidatorSets,
 };
 use namada_proof_of_stake::types::{CommissionRates, ValidatorStates};
 pub use namada_proof_of_stake::{
@@ -91,6 +92,33 @@ impl Ctx {
         )
     }
 
+    /// Change validator consensus key. The new key takes effect at the
+    /// pipeline offset.
+    pub fn change_validator_consensus_key(
+        &mut self,
+        validator: &Address,
+        consensus_key: &common::PublicKey,
+    ) -> TxResult {
+        let current_epoch = self.get_block_epoch()?;
+        namada_proof_of_stake::PosActions::change_consensus_key(
+            self,
+            validator,
+            consensus_key,
+            current_epoch,
+        )
+    }
+
+    /// Unjail a validator once its jailing period is over. The validator is
+    /// added back to the validator set at the pipeline offset.
+    pub fn unjail_validator(&mut self, validator: &Address) -> TxResult {
+        let current_epoch = self.get_block_epoch()?;
+        namada_proof_of_stake::PosActions::unjail_validator(
+            self,
+            validator,
+            current_epoch,
+        )
+    }
+
     /// Attempt to initialize a validator account. On success, returns the
     /// initialized validator account's address.
     pub fn init_validator(
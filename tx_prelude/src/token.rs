@@ -109,6 +109,7 @@ pub fn transfer(
             amount,
             key: key.clone(),
             shielded: Some(shielded.clone()),
+            memo: None,
         };
         ctx.write(
             &current_tx_key,
@@ -131,6 +132,72 @@ pub fn transfer(
     Ok(())
 }
 
+/// A token transfer from a single source to many targets that can be used in
+/// a transaction. The source's balances are checked against the totals of
+/// the outputs before any of them is applied, so that the transfer is
+/// rejected as a whole when the source cannot cover it.
+pub fn multi_transfer(ctx: &mut Ctx, multi: &MultiTransfer) -> TxResult {
+    let src = &multi.source;
+    let outputs = multi.merged_outputs().into_storage_result()?;
+    for (token, total) in multi.totals().into_storage_result()? {
+        let src_bal: Amount = ctx
+            .read(&token::balance_key(&token, src))?
+            .unwrap_or_default();
+        if src_bal < total {
+            return Err(Error::new_const(
+                "The source's balance is insufficient for the transfer",
+            ));
+        }
+    }
+    for ((dest, token), amount) in outputs {
+        transfer(ctx, src, &dest, &token, None, amount, &None, &None)?;
+    }
+    Ok(())
+}
+
+/// A token transfer whose amount is locked in the target's account until the
+/// unlock height, that can be used in a transaction. The amounts locked
+/// until the same height are summed.
+pub fn time_locked_transfer(
+    ctx: &mut Ctx,
+    locked_transfer: &TimeLockedTransfer,
+) -> TxResult {
+    let TimeLockedTransfer {
+        source,
+        target,
+        token,
+        amount,
+        unlock_height,
+    } = locked_transfer;
+    if *amount == Amount::default() {
+        return Err(Error::new_const("The locked amount must not be zero"));
+    }
+    let current_height = ctx.get_block_height()?;
+    if *unlock_height <= current_height {
+        return Err(Error::new_const(
+            "The unlock height must be after the current block height",
+        ));
+    }
+    if unlock_height.0 - current_height.0 > token::MAX_LOCK_HORIZON_BLOCKS {
+        return Err(Error::new_const(
+            "The unlock height must not be further than the maximum lock \
+             horizon from the current block height",
+        ));
+    }
+    if source == target {
+        return Err(Error::new_const(
+            "The source and the target of a locked transfer must differ",
+        ));
+    }
+    transfer(ctx, source, target, token, None, *amount, &None, &None)?;
+    let lock_key = token::lock_key(target, token, *unlock_height);
+    let locked: Amount = ctx.read(&lock_key)?.unwrap_or_default();
+    let locked = locked
+        .checked_add(amount)
+        .ok_or_else(|| Error::new_const("The locked amount overflows"))?;
+    ctx.write(&lock_key, locked)
+}
+
 /// A token transfer with storage keys that can be used in a transaction.
 pub fn transfer_with_keys(
     ctx: &mut Ctx,
@@ -203,6 +203,13 @@ pub mod vp {
         // Requires a node running with "Info" log level
         pub fn namada_vp_log_string(str_ptr: u64, str_len: u64);
 
+        // Emit an event, which is kept only if the VP accepts the tx
+        pub fn namada_vp_emit_event(event_ptr: u64, event_len: u64);
+
+        // Give the details of a rejection, which are kept only if the VP
+        // rejects the tx
+        pub fn namada_vp_reject(rejection_ptr: u64, rejection_len: u64);
+
         pub fn namada_vp_eval(
             vp_code_ptr: u64,
             vp_code_len: u64,
@@ -18,12 +18,12 @@ use std::marker::PhantomData;
 
 pub use borsh::{BorshDeserialize, BorshSerialize};
 pub use namada_core::ledger::governance::storage as gov_storage;
-pub use namada_core::ledger::parameters;
 pub use namada_core::ledger::storage_api::{
     self, iter_prefix, iter_prefix_bytes, rev_iter_prefix,
     rev_iter_prefix_bytes, Error, OptionExt, ResultExt, StorageRead,
 };
 pub use namada_core::ledger::vp_env::VpEnv;
+pub use namada_core::ledger::{parameters, testnet_faucet};
 pub use namada_core::proto::{Signed, SignedTxData};
 pub use namada_core::types::address::Address;
 use namada_core::types::chain::CHAIN_ID_LENGTH;
@@ -69,6 +69,16 @@ pub fn log_string<T: AsRef<str>>(msg: T) {
     }
 }
 
+/// Emit a structured event for off-chain indexers. The event is attached to
+/// the transaction's events only if this VP accepts the transaction and the
+/// transaction is committed.
+pub fn emit_event(event: &validity_predicate::VpEvent) {
+    let event = BorshSerialize::try_to_vec(event).unwrap();
+    unsafe {
+        namada_vp_emit_event(event.as_ptr() as _, event.len() as _);
+    }
+}
+
 /// Checks if a proposal id is being executed
 pub fn is_proposal_accepted(ctx: &Ctx, proposal_id: u64) -> VpResult {
     let proposal_execution_key =
@@ -152,6 +162,66 @@ impl Ctx {
     pub fn post(&self) -> CtxPostStorageRead<'_> {
         CtxPostStorageRead { _ctx: self }
     }
+
+    /// Storage read prior state Borsh encoded value (before tx execution).
+    /// Unlike [`VpEnv::read_pre`], a value that is present but cannot be
+    /// decoded is reported as a [`DecodeError`], so that it can be told apart
+    /// from an absent value.
+    pub fn read_pre_typed<T: BorshDeserialize>(
+        &self,
+        key: &storage::Key,
+    ) -> Result<Option<T>, DecodeError> {
+        let key_str = key.to_string();
+        let read_result = unsafe {
+            namada_vp_read_pre(key_str.as_ptr() as _, key_str.len() as _)
+        };
+        decode_typed(
+            key,
+            read_from_buffer(read_result, namada_vp_result_buffer),
+        )
+    }
+
+    /// Storage read posterior state Borsh encoded value (after tx execution).
+    /// Unlike [`VpEnv::read_post`], a value that is present but cannot be
+    /// decoded is reported as a [`DecodeError`], so that it can be told apart
+    /// from an absent value.
+    pub fn read_post_typed<T: BorshDeserialize>(
+        &self,
+        key: &storage::Key,
+    ) -> Result<Option<T>, DecodeError> {
+        let key_str = key.to_string();
+        let read_result = unsafe {
+            namada_vp_read_post(key_str.as_ptr() as _, key_str.len() as _)
+        };
+        decode_typed(
+            key,
+            read_from_buffer(read_result, namada_vp_result_buffer),
+        )
+    }
+}
+
+/// A storage value is present, but it cannot be decoded into the expected
+/// type
+#[derive(thiserror::Error, Debug)]
+#[error("Cannot decode the value of storage key {key}: {error}")]
+pub struct DecodeError {
+    /// The key of the value
+    pub key: storage::Key,
+    /// The decoding error
+    pub error: std::io::Error,
+}
+
+fn decode_typed<T: BorshDeserialize>(
+    key: &storage::Key,
+    bytes: Option<Vec<u8>>,
+) -> Result<Option<T>, DecodeError> {
+    bytes
+        .map(|bytes| T::try_from_slice(&bytes[..]))
+        .transpose()
+        .map_err(|error| DecodeError {
+            key: key.clone(),
+            error,
+        })
 }
 
 /// Read access to the prior storage (state before tx execution) via
@@ -184,6 +254,64 @@ pub fn reject() -> VpResult {
     Ok(false)
 }
 
+/// The verdict of a validity predicate with the details of a rejection. Only
+/// `accepted` matters for consensus, the rest is informative and reported
+/// back to the client with the result of the transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VpVerdict {
+    /// Is the transaction accepted?
+    pub accepted: bool,
+    /// The storage key whose modification got the transaction rejected
+    pub rejected_key: Option<storage::Key>,
+    /// The reason of the rejection
+    pub reason: Option<String>,
+}
+
+impl VpVerdict {
+    /// Accept a transaction
+    pub fn accept() -> Self {
+        Self {
+            accepted: true,
+            rejected_key: None,
+            reason: None,
+        }
+    }
+
+    /// Reject a transaction for the given reason
+    pub fn reject(reason: impl Into<String>) -> Self {
+        Self {
+            accepted: false,
+            rejected_key: None,
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// Reject a transaction because of the modification of the given key
+    pub fn reject_key(key: storage::Key, reason: impl Into<String>) -> Self {
+        Self {
+            accepted: false,
+            rejected_key: Some(key),
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// Give the details of a rejection, if any, to the host and convert into
+    /// a [`VpResult`].
+    pub fn into_vp_result(self) -> VpResult {
+        if !self.accepted {
+            let rejection = validity_predicate::VpRejection {
+                rejected_key: self.rejected_key,
+                reason: self.reason,
+            };
+            let rejection = BorshSerialize::try_to_vec(&rejection).unwrap();
+            unsafe {
+                namada_vp_reject(rejection.as_ptr() as _, rejection.len() as _);
+            }
+        }
+        Ok(self.accepted)
+    }
+}
+
 #[derive(Debug)]
 pub struct KeyValIterator<T>(pub u64, pub PhantomData<T>);
 
@@ -18,6 +18,17 @@ pub fn vp(
     keys_changed: &BTreeSet<Key>,
     verifiers: &BTreeSet<Address>,
 ) -> VpResult {
+    verdict(ctx, token, keys_changed, verifiers)?.into_vp_result()
+}
+
+/// The verdict of the token validity predicate, giving the failing key and
+/// reason on rejection.
+pub fn verdict(
+    ctx: &Ctx,
+    token: &Address,
+    keys_changed: &BTreeSet<Key>,
+    verifiers: &BTreeSet<Address>,
+) -> EnvResult<VpVerdict> {
     let mut change: Change = 0;
     for key in keys_changed.iter() {
         let owner: Option<&Address> =
@@ -30,7 +41,11 @@ pub fn vp(
                 // Unknown changes to this address space are disallowed, but
                 // unknown changes anywhere else are permitted
                 if key.segments.get(0) == Some(&token.to_db_key()) {
-                    return reject();
+                    return Ok(VpVerdict::reject_key(
+                        key.clone(),
+                        "Unknown changes to the token's address space are not \
+                         allowed",
+                    ));
                 }
             }
             Some(owner) => {
@@ -59,10 +74,113 @@ pub fn vp(
                 if this_change < 0
                     && !(verifiers.contains(owner) || *owner == address::masp())
                 {
-                    return reject();
+                    return Ok(VpVerdict::reject_key(
+                        key.clone(),
+                        "A debit must be approved by the owner of the balance",
+                    ));
                 }
             }
         }
     }
-    Ok(change == 0)
+    if change == 0 {
+        Ok(VpVerdict::accept())
+    } else {
+        Ok(VpVerdict::reject(
+            "The total supply of the token must not change",
+        ))
+    }
+}
+
+/// Get the amount of the given token of an account that is still locked in
+/// the current block, as written by the tx. The tokens locked until a height
+/// can be spent from that height on.
+pub fn locked_amount(
+    ctx: &Ctx,
+    owner: &Address,
+    token: &Address,
+) -> EnvResult<Amount> {
+    let current_height = ctx.get_block_height()?;
+    let prefix = token::lock_prefix(owner, token);
+    let mut locked = Amount::default();
+    for lock in storage_api::iter_prefix::<Amount>(&ctx.post(), &prefix)? {
+        let (key, amount) = lock?;
+        match token::is_lock_key(&key) {
+            Some((_, _, unlock_height)) if unlock_height > current_height => {
+                locked.receive(&amount)
+            }
+            _ => {}
+        }
+    }
+    Ok(locked)
+}
+
+/// Check if the post balance of a debited balance key of an account is below
+/// the amount of the token that's still locked in the account. The locks only
+/// apply to the balances without a sub-prefix.
+pub fn spends_locked(
+    ctx: &Ctx,
+    owner: &Address,
+    key: &Key,
+    post: Amount,
+) -> EnvResult<bool> {
+    match key.segments.first() {
+        Some(storage::DbKeySeg::AddressSeg(token))
+            if token::is_balance_key(token, key).is_some() =>
+        {
+            Ok(post < locked_amount(ctx, owner, token)?)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Check that the increase of the locks of the given token of an account in
+/// the tx doesn't exceed the amount credited to the account by the tx
+pub fn is_lock_credited(
+    ctx: &Ctx,
+    owner: &Address,
+    token: &Address,
+    keys_changed: &BTreeSet<Key>,
+) -> EnvResult<bool> {
+    let mut locked_change: Change = 0;
+    for key in keys_changed {
+        match token::is_lock_key(key) {
+            Some((lock_owner, lock_token, _))
+                if lock_owner == owner && lock_token == token =>
+            {
+                let pre: Amount = ctx.read_pre(key)?.unwrap_or_default();
+                let post: Amount = ctx.read_post(key)?.unwrap_or_default();
+                locked_change += post.change() - pre.change();
+            }
+            _ => {}
+        }
+    }
+    let balance_key = token::balance_key(token, owner);
+    let pre: Amount = ctx.read_pre(&balance_key)?.unwrap_or_default();
+    let post: Amount = ctx.read_post(&balance_key)?.unwrap_or_default();
+    let credited = post.change() - pre.change();
+    Ok(locked_change <= credited)
+}
+
+/// Check that a lock of the given token of an account, credited by the tx,
+/// unlocks within [`MAX_LOCK_HORIZON_BLOCKS`] from the current block and that
+/// the account has no more than [`MAX_LOCKS_PER_TOKEN`] locks of the token
+pub fn is_lock_within_limits(
+    ctx: &Ctx,
+    owner: &Address,
+    token: &Address,
+    unlock_height: storage::BlockHeight,
+) -> EnvResult<bool> {
+    let current_height = ctx.get_block_height()?;
+    let max_unlock_height =
+        current_height.0.saturating_add(MAX_LOCK_HORIZON_BLOCKS);
+    if unlock_height.0 > max_unlock_height {
+        return Ok(false);
+    }
+    let prefix = token::lock_prefix(owner, token);
+    let mut num_of_locks = 0;
+    for lock in storage_api::iter_prefix_bytes(&ctx.post(), &prefix)? {
+        lock?;
+        num_of_locks += 1;
+    }
+    Ok(num_of_locks <= MAX_LOCKS_PER_TOKEN)
 }
@@ -1,7 +1,11 @@
 #[cfg(feature = "tx_bond")]
 pub mod tx_bond;
+#[cfg(feature = "tx_change_consensus_key")]
+pub mod tx_change_consensus_key;
 #[cfg(feature = "tx_change_validator_commission")]
 pub mod tx_change_validator_commission;
+#[cfg(feature = "tx_faucet_withdrawal")]
+pub mod tx_faucet_withdrawal;
 #[cfg(feature = "tx_ibc")]
 pub mod tx_ibc;
 #[cfg(feature = "tx_init_account")]
@@ -10,12 +14,18 @@ pub mod tx_init_account;
 pub mod tx_init_proposal;
 #[cfg(feature = "tx_init_validator")]
 pub mod tx_init_validator;
+#[cfg(feature = "tx_multi_transfer")]
+pub mod tx_multi_transfer;
 #[cfg(feature = "tx_reveal_pk")]
 pub mod tx_reveal_pk;
+#[cfg(feature = "tx_time_locked_transfer")]
+pub mod tx_time_locked_transfer;
 #[cfg(feature = "tx_transfer")]
 pub mod tx_transfer;
 #[cfg(feature = "tx_unbond")]
 pub mod tx_unbond;
+#[cfg(feature = "tx_unjail_validator")]
+pub mod tx_unjail_validator;
 #[cfg(feature = "tx_update_vp")]
 pub mod tx_update_vp;
 #[cfg(feature = "tx_vote_proposal")]
@@ -20,6 +20,7 @@ mod tests {
     use namada::ledger::pos::{BondId, GenesisValidator, PosParams, PosVP};
     use namada::proof_of_stake::types::Bond;
     use namada::proto::Tx;
+    use namada::types::chain::ChainId;
     use namada::types::storage::Epoch;
     use namada_tests::log::test;
     use namada_tests::native_vp::pos::init_pos;
@@ -93,7 +94,7 @@ mod tests {
         let tx_code = vec![];
         let tx_data = bond.try_to_vec().unwrap();
         let tx = Tx::new(tx_code, Some(tx_data));
-        let signed_tx = tx.sign(&key);
+        let signed_tx = tx.sign(&key, &ChainId::default());
         let tx_data = signed_tx.data.unwrap();
 
         // Ensure that the initial stake of the sole validator is equal to the
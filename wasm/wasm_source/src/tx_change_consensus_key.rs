@@ -0,0 +1,160 @@
+//! A tx for a validator to change their consensus key.
+
+use namada_tx_prelude::transaction::pos::ConsensusKeyChange;
+use namada_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+    let signed = SignedTxData::try_from_slice(&tx_data[..])
+        .wrap_err("failed to decode SignedTxData")?;
+    let data = signed.data.ok_or_err_msg("Missing data")?;
+    let ConsensusKeyChange {
+        validator,
+        consensus_key,
+    } = transaction::pos::ConsensusKeyChange::try_from_slice(&data[..])
+        .wrap_err("failed to decode ConsensusKeyChange")?;
+    ctx.change_validator_consensus_key(&validator, &consensus_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use namada::ledger::pos::{PosParams, PosVP};
+    use namada::proto::Tx;
+    use namada::types::chain::ChainId;
+    use namada::types::storage::Epoch;
+    use namada_tests::log::test;
+    use namada_tests::native_vp::pos::init_pos;
+    use namada_tests::native_vp::TestNativeVpEnv;
+    use namada_tests::tx::*;
+    use namada_tx_prelude::address::testing::arb_established_address;
+    use namada_tx_prelude::key::testing::arb_common_keypair;
+    use namada_tx_prelude::key::RefTo;
+    use namada_tx_prelude::proof_of_stake::parameters::testing::arb_pos_params;
+    use namada_tx_prelude::token;
+    use namada_vp_prelude::proof_of_stake::GenesisValidator;
+    use proptest::prelude::*;
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    proptest! {
+        /// In this test we setup the ledger and PoS system with an arbitrary
+        /// initial state with 1 genesis validator and arbitrary PoS
+        /// parameters. We then change the validator's consensus key.
+        ///
+        /// After we apply the change, we check that the new key is only
+        /// used from the pipeline offset and then we also check that this
+        /// transaction is accepted by the PoS validity predicate.
+        #[test]
+        fn test_tx_change_consensus_key(
+            validator in arb_established_address(),
+            // A key to sign the transaction
+            key in arb_common_keypair(),
+            pos_params in arb_pos_params()) {
+            test_tx_change_consensus_key_aux(
+                Address::Established(validator),
+                key,
+                pos_params,
+            ).unwrap()
+        }
+    }
+
+    fn test_tx_change_consensus_key_aux(
+        validator: Address,
+        key: key::common::SecretKey,
+        pos_params: PosParams,
+    ) -> TxResult {
+        let consensus_key = key::testing::keypair_1().ref_to();
+        let new_consensus_key = key::testing::keypair_2().ref_to();
+        let genesis_validators = [GenesisValidator {
+            address: validator.clone(),
+            tokens: token::Amount::from(1_000_000),
+            consensus_key: consensus_key.clone(),
+            commission_rate: Decimal::new(5, 2),
+            max_commission_rate_change: Decimal::new(1, 2),
+        }];
+
+        init_pos(&genesis_validators[..], &pos_params, Epoch(0));
+
+        let tx_code = vec![];
+        let tx_data = ConsensusKeyChange {
+            validator: validator.clone(),
+            consensus_key: new_consensus_key.clone(),
+        }
+        .try_to_vec()
+        .unwrap();
+        let tx = Tx::new(tx_code, Some(tx_data));
+        let signed_tx = tx.sign(&key, &ChainId::default());
+        let tx_data = signed_tx.data.unwrap();
+
+        // Read the data before the tx is executed
+        let consensus_keys_pre = ctx()
+            .read_validator_consensus_key(&validator)?
+            .expect("PoS validator must have consensus keys");
+
+        apply_tx(ctx(), tx_data)?;
+
+        // Read the data after the tx is executed
+
+        // The following storage keys should be updated:
+
+        //     - `#{PoS}/validator/#{validator}/consensus_key`
+        //     - `#{PoS}/validator/address_raw_hash/#{raw_hash}`
+
+        let consensus_keys_post =
+            ctx().read_validator_consensus_key(&validator)?.unwrap();
+
+        // Before pipeline, the consensus key should not change
+        for epoch in 0..pos_params.pipeline_len {
+            assert_eq!(
+                consensus_keys_pre.get(epoch),
+                consensus_keys_post.get(epoch),
+                "The consensus key before the pipeline offset must not change \
+                 - checking in epoch: {epoch}"
+            );
+            assert_eq!(
+                Some(&consensus_key),
+                consensus_keys_post.get(epoch),
+                "The consensus key before the pipeline offset must not change \
+                 - checking in epoch: {epoch}"
+            );
+        }
+
+        // After pipeline, the consensus key should have changed
+        for epoch in pos_params.pipeline_len..=pos_params.unbonding_len {
+            assert_eq!(
+                Some(&new_consensus_key),
+                consensus_keys_post.get(epoch),
+                "The consensus key after the pipeline offset must be the new \
+                 key - checking in epoch: {epoch}"
+            );
+        }
+
+        // Both the old and the new key are associated with the validator
+        for pk in [&consensus_key, &new_consensus_key] {
+            let raw_hash = key::tm_consensus_key_raw_hash(pk);
+            assert_eq!(
+                ctx().read_validator_address_raw_hash(raw_hash)?,
+                Some(validator.clone())
+            );
+        }
+
+        // The same key cannot be used again
+        assert!(ctx()
+            .change_validator_consensus_key(&validator, &consensus_key)
+            .is_err());
+
+        // Use the tx_env to run PoS VP
+        let tx_env = tx_host_env::take();
+        let vp_env = TestNativeVpEnv::from_tx_env(tx_env, address::POS);
+        let result = vp_env.validate_tx(PosVP::new);
+        let result =
+            result.expect("Validation of valid changes must not fail!");
+        assert!(
+            result,
+            "PoS Validity predicate must accept this transaction"
+        );
+
+        Ok(())
+    }
+}
@@ -20,6 +20,7 @@ fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
 mod tests {
     use namada::ledger::pos::{PosParams, PosVP};
     use namada::proto::Tx;
+    use namada::types::chain::ChainId;
     use namada::types::storage::Epoch;
     use namada_tests::log::test;
     use namada_tests::native_vp::pos::init_pos;
@@ -78,7 +79,7 @@ mod tests {
         let tx_code = vec![];
         let tx_data = commission_change.try_to_vec().unwrap();
         let tx = Tx::new(tx_code, Some(tx_data));
-        let signed_tx = tx.sign(&key);
+        let signed_tx = tx.sign(&key, &ChainId::default());
         let tx_data = signed_tx.data.unwrap();
 
         // Read the data before the tx is executed
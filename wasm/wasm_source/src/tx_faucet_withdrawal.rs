@@ -0,0 +1,29 @@
+//! A tx to withdraw tokens from a testnet faucet.
+//! This tx uses `testnet_faucet::Withdrawal` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use namada_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+    let signed = SignedTxData::try_from_slice(&tx_data[..])
+        .wrap_err("failed to decode SignedTxData")?;
+    let data = signed.data.ok_or_err_msg("Missing data")?;
+    let withdrawal = testnet_faucet::Withdrawal::try_from_slice(&data[..])
+        .wrap_err("failed to decode testnet_faucet::Withdrawal")?;
+    debug_log!("apply_tx called with faucet withdrawal: {:#?}", withdrawal);
+    let testnet_faucet::Withdrawal {
+        faucet,
+        target,
+        token,
+        amount,
+    } = withdrawal;
+    // Record the epoch of the withdrawal, for the faucet's VP to limit the
+    // rate of the withdrawals
+    let epoch = ctx.get_block_epoch()?;
+    ctx.write(
+        &testnet_faucet::last_withdrawal_epoch_key(&faucet, &target),
+        epoch,
+    )?;
+    token::transfer(ctx, &faucet, &target, &token, None, amount, &None, &None)
+}
@@ -0,0 +1,16 @@
+//! A tx for a token transfer from a single source to many targets.
+//! This tx uses `token::MultiTransfer` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use namada_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+    let signed = SignedTxData::try_from_slice(&tx_data[..])
+        .wrap_err("failed to decode SignedTxData")?;
+    let data = signed.data.ok_or_err_msg("Missing data")?;
+    let transfer = token::MultiTransfer::try_from_slice(&data[..])
+        .wrap_err("failed to decode token::MultiTransfer")?;
+    debug_log!("apply_tx called with transfer: {:#?}", transfer);
+    token::multi_transfer(ctx, &transfer)
+}
@@ -0,0 +1,17 @@
+//! A tx for a token transfer whose amount is locked in the target's account
+//! until a block height.
+//! This tx uses `token::TimeLockedTransfer` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use namada_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+    let signed = SignedTxData::try_from_slice(&tx_data[..])
+        .wrap_err("failed to decode SignedTxData")?;
+    let data = signed.data.ok_or_err_msg("Missing data")?;
+    let transfer = token::TimeLockedTransfer::try_from_slice(&data[..])
+        .wrap_err("failed to decode token::TimeLockedTransfer")?;
+    debug_log!("apply_tx called with transfer: {:#?}", transfer);
+    token::time_locked_transfer(ctx, &transfer)
+}
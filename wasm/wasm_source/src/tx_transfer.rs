@@ -20,8 +20,22 @@ fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
         amount,
         key,
         shielded,
+        memo,
     } = transfer;
     token::transfer(
         ctx, &source, &target, &token, sub_prefix, amount, &key, &shielded,
-    )
+    )?;
+    // Record the memo, if any, for clients to read it back
+    if let Some(memo) = memo {
+        if !token::is_valid_memo(&memo) {
+            return Err(Error::new_const("The transfer's memo is too long"));
+        }
+        let memo_key = token::memo_key(
+            &source,
+            ctx.get_block_height()?,
+            ctx.get_tx_index()?,
+        );
+        ctx.write(&memo_key, memo)?;
+    }
+    Ok(())
 }
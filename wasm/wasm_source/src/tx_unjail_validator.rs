@@ -0,0 +1,207 @@
+//! A tx for a jailed validator to be unjailed once its jailing period is over.
+
+use namada_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+    let signed = SignedTxData::try_from_slice(&tx_data[..])
+        .wrap_err("failed to decode SignedTxData")?;
+    let data = signed.data.ok_or_err_msg("Missing data")?;
+    let validator = Address::try_from_slice(&data[..])
+        .wrap_err("failed to decode an Address")?;
+    ctx.unjail_validator(&validator)
+}
+
+#[cfg(test)]
+mod tests {
+    use namada::ledger::pos::namada_proof_of_stake::PosBase;
+    use namada::ledger::pos::{PosParams, PosVP};
+    use namada::proto::Tx;
+    use namada::types::chain::ChainId;
+    use namada::types::storage::Epoch;
+    use namada_tests::log::test;
+    use namada_tests::native_vp::pos::init_pos;
+    use namada_tests::native_vp::TestNativeVpEnv;
+    use namada_tests::tx::*;
+    use namada_tx_prelude::address::testing::arb_established_address;
+    use namada_tx_prelude::key::testing::arb_common_keypair;
+    use namada_tx_prelude::key::RefTo;
+    use namada_tx_prelude::proof_of_stake::parameters::testing::arb_pos_params;
+    use namada_tx_prelude::proof_of_stake::types::ValidatorState;
+    use namada_tx_prelude::token;
+    use namada_vp_prelude::proof_of_stake::GenesisValidator;
+    use proptest::prelude::*;
+    use rust_decimal::Decimal;
+
+    use super::*;
+
+    proptest! {
+        /// In this test we setup the ledger and PoS system with an arbitrary
+        /// initial state with 1 genesis validator and arbitrary PoS
+        /// parameters. We then jail the validator and try to unjail it.
+        ///
+        /// We check that the validator is removed from the validator set at
+        /// the pipeline offset when it's jailed, that it cannot be unjailed
+        /// before its jailing period is over and that it's added back to the
+        /// validator set at the pipeline offset when it's unjailed after the
+        /// period. Then we also check that this transaction is accepted by
+        /// the PoS validity predicate.
+        #[test]
+        fn test_tx_unjail_validator(
+            validator in arb_established_address(),
+            // A key to sign the transaction
+            key in arb_common_keypair(),
+            pos_params in arb_pos_params()) {
+            test_tx_unjail_validator_aux(
+                Address::Established(validator),
+                key,
+                pos_params,
+            ).unwrap()
+        }
+
+        /// In this test we check that a jailed validator that has unbonded
+        /// all of its stake cannot be unjailed.
+        #[test]
+        fn test_tx_unjail_unbonded_validator(
+            validator in arb_established_address(),
+            // A key to sign the transaction
+            key in arb_common_keypair(),
+            pos_params in arb_pos_params()) {
+            test_tx_unjail_unbonded_validator_aux(
+                Address::Established(validator),
+                key,
+                pos_params,
+            ).unwrap()
+        }
+    }
+
+    /// Initialize PoS with the genesis validator and jail it in epoch 0.
+    /// Returns the signed tx data to unjail it.
+    fn init_jailed_validator(
+        validator: &Address,
+        key: &key::common::SecretKey,
+        pos_params: &PosParams,
+    ) -> Vec<u8> {
+        let genesis_validators = [GenesisValidator {
+            address: validator.clone(),
+            tokens: token::Amount::from(1_000_000),
+            consensus_key: key::testing::keypair_1().ref_to(),
+            commission_rate: Decimal::new(5, 2),
+            max_commission_rate_change: Decimal::new(1, 2),
+        }];
+
+        init_pos(&genesis_validators[..], pos_params, Epoch(0));
+        tx_host_env::with(|env| {
+            env.storage.jail_validator(Epoch(0), validator);
+        });
+
+        let tx_code = vec![];
+        let tx_data = validator.try_to_vec().unwrap();
+        let tx = Tx::new(tx_code, Some(tx_data));
+        let signed_tx = tx.sign(key, &ChainId::default());
+        signed_tx.data.unwrap()
+    }
+
+    /// Fast forward to the given epoch
+    fn advance_to_epoch(epoch: Epoch) {
+        tx_host_env::with(|env| {
+            env.storage.block.epoch = epoch;
+        });
+    }
+
+    /// Check if the validator is in the validator set in the given epoch
+    fn is_in_validator_set(
+        validator: &Address,
+        epoch: Epoch,
+    ) -> storage_api::Result<bool> {
+        let validator_sets = ctx().read_validator_set()?;
+        let validator_set = validator_sets.get(epoch).unwrap();
+        Ok(validator_set
+            .active
+            .iter()
+            .chain(validator_set.inactive.iter())
+            .any(|weighted| &weighted.address == validator))
+    }
+
+    fn test_tx_unjail_validator_aux(
+        validator: Address,
+        key: key::common::SecretKey,
+        pos_params: PosParams,
+    ) -> TxResult {
+        let tx_data = init_jailed_validator(&validator, &key, &pos_params);
+
+        // The validator is jailed, and removed from the validator set, only
+        // from the pipeline offset
+        let states = ctx().read_validator_state(&validator)?.unwrap();
+        for epoch in 0..pos_params.pipeline_len {
+            assert_eq!(states.get(epoch), Some(&ValidatorState::Candidate));
+            assert!(is_in_validator_set(&validator, Epoch(epoch))?);
+        }
+        let pipeline_epoch = Epoch(pos_params.pipeline_len);
+        assert_eq!(states.get(pipeline_epoch), Some(&ValidatorState::Jailed));
+        assert!(!is_in_validator_set(&validator, pipeline_epoch)?);
+
+        // The validator cannot be unjailed before the jailing period is over
+        let unjail_epoch = Epoch(pos_params.jail_len);
+        advance_to_epoch(unjail_epoch.prev());
+        assert!(apply_tx(ctx(), tx_data.clone()).is_err());
+        // The jailed validator cannot receive new bonds
+        assert!(ctx()
+            .bond_tokens(None, &validator, token::Amount::from(1))
+            .is_err());
+
+        advance_to_epoch(unjail_epoch);
+        apply_tx(ctx(), tx_data)?;
+
+        // The validator is a candidate again in the validator set from the
+        // pipeline offset, with its bonded stake
+        let pipeline_epoch = unjail_epoch + pos_params.pipeline_len;
+        let states = ctx().read_validator_state(&validator)?.unwrap();
+        assert_eq!(
+            states.get(pipeline_epoch),
+            Some(&ValidatorState::Candidate)
+        );
+        assert!(is_in_validator_set(&validator, pipeline_epoch)?);
+        assert_eq!(
+            ctx().validator_stake(&validator, pipeline_epoch)?,
+            token::Amount::from(1_000_000)
+        );
+
+        // A validator that's not jailed cannot be unjailed
+        assert!(ctx().unjail_validator(&validator).is_err());
+
+        // Use the tx_env to run PoS VP
+        let tx_env = tx_host_env::take();
+        let vp_env = TestNativeVpEnv::from_tx_env(tx_env, address::POS);
+        let result = vp_env.validate_tx(PosVP::new);
+        let result =
+            result.expect("Validation of valid changes must not fail!");
+        assert!(
+            result,
+            "PoS Validity predicate must accept this transaction"
+        );
+
+        Ok(())
+    }
+
+    fn test_tx_unjail_unbonded_validator_aux(
+        validator: Address,
+        key: key::common::SecretKey,
+        pos_params: PosParams,
+    ) -> TxResult {
+        let tx_data = init_jailed_validator(&validator, &key, &pos_params);
+
+        // Unbond all of the validator's stake while it's jailed
+        ctx().unbond_tokens(
+            None,
+            &validator,
+            token::Amount::from(1_000_000),
+        )?;
+        tx_host_env::commit_tx_and_block();
+
+        advance_to_epoch(Epoch(pos_params.jail_len));
+        assert!(apply_tx(ctx(), tx_data).is_err());
+
+        Ok(())
+    }
+}
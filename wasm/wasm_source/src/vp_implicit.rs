@@ -6,8 +6,9 @@
 //! It allows to reveal a PK, as long as its address matches with the address
 //! that can be derived from the PK.
 //!
-//! It allows to bond, unbond and withdraw tokens to and from PoS system with a
-//! valid signature.
+//! It allows to bond, unbond and withdraw tokens to and from PoS system and to
+//! change the consensus key of, or unjail, a validator account with a valid
+//! signature.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
@@ -21,6 +22,9 @@ enum KeyType<'a> {
     Token(&'a Address),
     PoS,
     GovernanceVote(&'a Address),
+    /// Governance keys other than the votes, validated by the governance VP
+    Governance,
+    TokenLock(&'a Address, &'a Address, storage::BlockHeight),
     Unknown,
 }
 
@@ -43,12 +47,46 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
             } else {
                 Self::Unknown
             }
+        } else if gov_storage::is_governance_key(key) {
+            Self::Governance
+        } else if let Some((owner, token, unlock_height)) =
+            token::is_lock_key(key)
+        {
+            Self::TokenLock(owner, token, unlock_height)
         } else {
             Self::Unknown
         }
     }
 }
 
+impl<'a> KeyType<'a> {
+    /// The reason of a rejected modification of a key of this type
+    fn rejection_reason(&self) -> &'static str {
+        match self {
+            Self::Pk(_) => {
+                "A PK can only be revealed once, with the address derived from \
+                 it"
+            }
+            Self::Token(_) => "A debit requires a valid signature",
+            Self::PoS => {
+                "A bond, an unbond or a change of a validator's consensus key \
+                 or state requires a valid signature"
+            }
+            Self::GovernanceVote(_) => "A vote requires a valid signature",
+            Self::TokenLock(..) => {
+                "A lock must be credited in the same tx, within the maximum \
+                 horizon and number of locks, and it can only be removed with \
+                 a valid signature from its unlock height"
+            }
+            Self::Governance => "A modification requires a valid signature",
+            Self::Unknown => {
+                "A modification requires a valid signature and must not be in \
+                 a namespace reserved for the protocol"
+            }
+        }
+    }
+}
+
 #[validity_predicate]
 fn validate_tx(
     ctx: &Ctx,
@@ -64,6 +102,17 @@ fn validate_tx(
         verifiers
     );
 
+    check_tx(ctx, tx_data, addr, keys_changed)?.into_vp_result()
+}
+
+/// Check the tx's storage modifications, giving the failing key and reason
+/// on rejection.
+fn check_tx(
+    ctx: &Ctx,
+    tx_data: Vec<u8>,
+    addr: Address,
+    keys_changed: BTreeSet<storage::Key>,
+) -> EnvResult<VpVerdict> {
     let signed_tx_data =
         Lazy::new(|| SignedTxData::try_from_slice(&tx_data[..]));
 
@@ -84,7 +133,7 @@ fn validate_tx(
     });
 
     if !is_valid_tx(ctx, &tx_data)? {
-        return reject();
+        return Ok(VpVerdict::reject("The tx is not whitelisted"));
     }
 
     for key in keys_changed.iter() {
@@ -94,7 +143,10 @@ fn validate_tx(
                 if owner == &addr {
                     if ctx.has_key_pre(key)? {
                         // If the PK is already reveal, reject the tx
-                        return reject();
+                        return Ok(VpVerdict::reject_key(
+                            key.clone(),
+                            "The PK is already revealed",
+                        ));
                     }
                     let post: Option<key::common::PublicKey> =
                         ctx.read_post(key)?;
@@ -104,12 +156,19 @@ fn validate_tx(
                             // Check that address matches with the address
                             // derived from the PK
                             if addr_from_pk != addr {
-                                return reject();
+                                return Ok(VpVerdict::reject_key(
+                                    key.clone(),
+                                    "The address derived from the PK doesn't \
+                                     match",
+                                ));
                             }
                         }
                         None => {
                             // Revealed PK cannot be deleted
-                            return reject();
+                            return Ok(VpVerdict::reject_key(
+                                key.clone(),
+                                "A revealed PK cannot be deleted",
+                            ));
                         }
                     }
                 }
@@ -122,6 +181,16 @@ fn validate_tx(
                     let post: token::Amount =
                         ctx.read_post(key)?.unwrap_or_default();
                     let change = post.change() - pre.change();
+                    // The tokens that are still locked cannot be debited
+                    if change < 0
+                        && token::spends_locked(ctx, &addr, key, post)?
+                    {
+                        return Ok(VpVerdict::reject_key(
+                            key.clone(),
+                            "A debit must not spend the tokens locked until a \
+                             later block height",
+                        ));
+                    }
                     // debit has to signed, credit doesn't
                     let valid = change >= 0 || *valid_sig;
                     debug_log!(
@@ -149,7 +218,7 @@ fn validate_tx(
                 // Allow the account to be used in PoS
                 let bond_id = proof_of_stake::is_bond_key(key)
                     .or_else(|| proof_of_stake::is_unbond_key(key));
-                let valid = match bond_id {
+                let valid_bond_or_unbond_change = match bond_id {
                     Some(bond_id) => {
                         // Bonds and unbonds changes for this address
                         // must be signed
@@ -160,6 +229,33 @@ fn validate_tx(
                         true
                     }
                 };
+                let consensus_key =
+                    proof_of_stake::is_validator_consensus_key_key(key);
+                // Validator's consensus key change must be signed
+                let valid_consensus_key_change = match consensus_key {
+                    Some(source) => *source != addr || *valid_sig,
+                    None => true,
+                };
+                // The look-up of a validator's new consensus key must be signed
+                let valid_raw_hash_change =
+                    if proof_of_stake::is_validator_address_raw_hash_key(key)
+                        .is_some()
+                    {
+                        let validator: Option<Address> = ctx.read_post(key)?;
+                        validator.as_ref() != Some(&addr) || *valid_sig
+                    } else {
+                        true
+                    };
+                let state = proof_of_stake::is_validator_state_key(key);
+                // Validator's state change (unjailing) must be signed
+                let valid_state_change = match state {
+                    Some(source) => *source != addr || *valid_sig,
+                    None => true,
+                };
+                let valid = valid_bond_or_unbond_change
+                    && valid_consensus_key_change
+                    && valid_raw_hash_change
+                    && valid_state_change;
                 debug_log!(
                     "PoS key {} {}",
                     key,
@@ -174,8 +270,44 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::Governance => *valid_sig,
+            KeyType::TokenLock(owner, token, unlock_height) => {
+                if owner == &addr {
+                    let pre: token::Amount =
+                        ctx.read_pre(key)?.unwrap_or_default();
+                    let post: token::Amount =
+                        ctx.read_post(key)?.unwrap_or_default();
+                    if post >= pre {
+                        // A new lock must not restrict the tokens that the
+                        // account already owns, nor exceed the limits of the
+                        // locks
+                        let credited = token::is_lock_credited(
+                            ctx,
+                            &addr,
+                            token,
+                            &keys_changed,
+                        )?;
+                        credited
+                            && token::is_lock_within_limits(
+                                ctx,
+                                &addr,
+                                token,
+                                unlock_height,
+                            )?
+                    } else {
+                        // An expired lock may be removed by the owner
+                        *valid_sig && ctx.get_block_height()? >= unlock_height
+                    }
+                } else {
+                    true
+                }
+            }
             KeyType::Unknown => {
-                if key.segments.get(0) == Some(&addr.to_db_key()) {
+                if key.is_reserved() {
+                    // The namespaces reserved for the protocol may only be
+                    // written by the protocol
+                    false
+                } else if key.segments.get(0) == Some(&addr.to_db_key()) {
                     // Unknown changes to this address space require a valid
                     // signature
                     *valid_sig
@@ -187,11 +319,14 @@ fn validate_tx(
         };
         if !is_valid {
             debug_log!("key {} modification failed vp", key);
-            return reject();
+            return Ok(VpVerdict::reject_key(
+                key.clone(),
+                key_type.rejection_reason(),
+            ));
         }
     }
 
-    accept()
+    Ok(VpVerdict::accept())
 }
 
 #[cfg(test)]
@@ -285,7 +420,7 @@ mod tests {
     }
 
     /// Test that a revealed PK that doesn't correspond to the account's address
-    /// is rejected.
+    /// is rejected with the PK key.
     #[test]
     fn test_reveal_wrong_pk_rejected() {
         // The SK to be used for the implicit account
@@ -315,9 +450,18 @@ mod tests {
         vp_host_env::set(vp_env);
 
         assert!(
-            !validate_tx(&CTX, tx_data, addr, keys_changed, verifiers).unwrap(),
+            !validate_tx(&CTX, tx_data, addr.clone(), keys_changed, verifiers)
+                .unwrap(),
             "Mismatching PK must be rejected"
         );
+        // The rejected PK key is given to the host
+        let rejection = vp_host_env::with(|env| env.rejection.clone())
+            .expect("The rejection must be given");
+        assert_eq!(
+            rejection.rejected_key,
+            Some(namada_tx_prelude::key::pk_key(&addr))
+        );
+        assert!(rejection.reason.is_some());
     }
 
     /// Test that a credit transfer is accepted.
@@ -456,7 +600,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&secret_key);
+        let signed_tx = tx.sign(&secret_key, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -469,6 +613,66 @@ mod tests {
         );
     }
 
+    /// Test that a debit transfer of the tokens locked until a later block
+    /// height is rejected, even with a valid signature.
+    #[test]
+    fn test_signed_locked_debit_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let secret_key = key::testing::keypair_1();
+        let public_key = secret_key.ref_to();
+        let vp_owner: Address = (&public_key).into();
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner and lock them until a later block
+        // height
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+        let lock_key =
+            token::lock_key(&vp_owner, &token, storage::BlockHeight(10));
+        tx_env
+            .storage
+            .write(&lock_key, amount.try_to_vec().unwrap())
+            .unwrap();
+
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                None,
+                amount,
+                &None,
+                &None,
+            )
+            .unwrap();
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&secret_key, &vp_env.storage.chain_id);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(
+            !validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
+                .unwrap()
+        );
+    }
+
     /// Test that a transfer on with accounts other than self is accepted.
     #[test]
     fn test_transfer_between_other_parties_accepted() {
@@ -521,8 +725,8 @@ mod tests {
 
     /// Generates a keypair, derive an implicit address from it and generate
     /// a storage key inside its storage.
-    fn arb_account_storage_subspace_key()
-    -> impl Strategy<Value = (key::common::SecretKey, Address, Key)> {
+    fn arb_account_storage_subspace_key(
+    ) -> impl Strategy<Value = (key::common::SecretKey, Address, Key)> {
         // Generate a keypair
         key::testing::arb_common_keypair().prop_flat_map(|sk| {
             let pk = sk.ref_to();
@@ -604,7 +808,7 @@ mod tests {
 
             let mut vp_env = vp_host_env::take();
             let tx = vp_env.tx.clone();
-            let signed_tx = tx.sign(&secret_key);
+            let signed_tx = tx.sign(&secret_key, &vp_env.storage.chain_id);
             let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
             vp_env.tx = signed_tx;
             let keys_changed: BTreeSet<storage::Key> =
@@ -685,7 +889,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&secret_key);
+        let signed_tx = tx.sign(&secret_key, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -727,7 +931,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&secret_key);
+        let signed_tx = tx.sign(&secret_key, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -1,7 +1,9 @@
 //! A "faucet" account for testnet.
 //!
 //! This VP allows anyone to withdraw up to [`MAX_FREE_DEBIT`] tokens without
-//! the faucet's signature.
+//! the faucet's signature, at most once per epoch for each target account.
+//! The epoch of the last withdrawal to a target is recorded in the faucet's
+//! storage by the tx.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
@@ -50,6 +52,20 @@ fn validate_tx(
         return reject();
     }
 
+    let current_epoch = ctx.get_block_epoch()?;
+    // The targets of the withdrawals recorded by the tx
+    let withdrawal_targets: Vec<&Address> = keys_changed
+        .iter()
+        .filter_map(|key| {
+            testnet_faucet::is_last_withdrawal_epoch_key(&addr, key)
+        })
+        .collect();
+    // The number of the faucet's token balances changed by the tx
+    let changed_balances = keys_changed
+        .iter()
+        .filter(|key| token::is_any_token_balance_key(key) == Some(&addr))
+        .count();
+
     for key in keys_changed.iter() {
         let is_valid = if let Some(owner) = token::is_any_token_balance_key(key)
         {
@@ -58,12 +74,31 @@ fn validate_tx(
                 let post: token::Amount =
                     ctx.read_post(key)?.unwrap_or_default();
                 let change = post.change() - pre.change();
-                // Debit over `MAX_FREE_DEBIT` has to signed, credit doesn't
-                change >= -MAX_FREE_DEBIT || change >= 0 || *valid_sig
+                // Debit over `MAX_FREE_DEBIT` has to be signed, credit doesn't.
+                // An unsigned debit must be a withdrawal of a single token.
+                change >= 0
+                    || *valid_sig
+                    || (change >= -MAX_FREE_DEBIT
+                        && changed_balances == 1
+                        && is_valid_withdrawal(
+                            ctx,
+                            key,
+                            -change,
+                            &withdrawal_targets,
+                        )?)
             } else {
                 // If this is not the owner, allow any change
                 true
             }
+        } else if testnet_faucet::is_last_withdrawal_epoch_key(&addr, key)
+            .is_some()
+        {
+            // A withdrawal to a target may be recorded at most once per epoch
+            let pre: Option<storage::Epoch> = ctx.read_pre(key)?;
+            let post: Option<storage::Epoch> = ctx.read_post(key)?;
+            *valid_sig
+                || (post == Some(current_epoch)
+                    && pre.map(|pre| pre < current_epoch).unwrap_or(true))
         } else if let Some(owner) = key.is_validity_predicate() {
             let has_post: bool = ctx.has_key_post(key)?;
             if owner == &addr {
@@ -89,6 +124,29 @@ fn validate_tx(
     accept()
 }
 
+/// Check that an unsigned debit of the faucet's token balance under the given
+/// key is a withdrawal recorded for a single target that is credited the
+/// debited amount.
+fn is_valid_withdrawal(
+    ctx: &Ctx,
+    balance_key: &storage::Key,
+    debit: token::Change,
+    withdrawal_targets: &[&Address],
+) -> VpResult {
+    let target = match withdrawal_targets {
+        [target] => *target,
+        _ => return reject(),
+    };
+    let token = match balance_key.segments.first() {
+        Some(storage::DbKeySeg::AddressSeg(token)) => token,
+        _ => return reject(),
+    };
+    let target_key = token::balance_key(token, target);
+    let pre: token::Amount = ctx.read_pre(&target_key)?.unwrap_or_default();
+    let post: token::Amount = ctx.read_post(&target_key)?.unwrap_or_default();
+    Ok(post.change() - pre.change() == debit)
+}
+
 #[cfg(test)]
 mod tests {
     use address::testing::arb_non_internal_address;
@@ -231,7 +289,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -260,17 +318,20 @@ mod tests {
         }
     }
 
-    proptest! {
-    /// Test that a debit of more than [`MAX_FREE_DEBIT`] tokens without a valid signature is rejected.
-    #[test]
-    fn test_unsigned_debit_over_limit_rejected(amount in (MAX_FREE_DEBIT as u64 + 1..)) {
+    /// Initialize a VP environment from a tx that withdraws the given amount
+    /// from the faucet, optionally recording the withdrawal. The target's
+    /// last withdrawal may be set to the current epoch before the tx.
+    fn init_withdrawal(
+        amount: token::Amount,
+        record: bool,
+        withdrawn_in_epoch: bool,
+    ) {
         // Initialize a tx environment
         let mut tx_env = TestTxEnv::default();
 
         let vp_owner = address::testing::established_address_1();
         let target = address::testing::established_address_2();
         let token = address::nam();
-        let amount = token::Amount::from(amount);
 
         // Spawn the accounts to be able to modify their storage
         tx_env.spawn_accounts([&vp_owner, &target, &token]);
@@ -279,52 +340,104 @@ mod tests {
         // be able to transfer from it
         tx_env.credit_tokens(&vp_owner, &token, None, amount);
 
+        if withdrawn_in_epoch {
+            let epoch = tx_env.storage.block.epoch;
+            tx_env
+                .storage
+                .write(
+                    &testnet_faucet::last_withdrawal_epoch_key(
+                        &vp_owner, &target,
+                    ),
+                    epoch.try_to_vec().unwrap(),
+                )
+                .unwrap();
+        }
+
         // Initialize VP environment from a transaction
-        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
-        // Apply transfer in a transaction
-        tx_host_env::token::transfer(tx::ctx(), address, &target, &token, None, amount, &None, &None).unwrap();
+        vp_host_env::init_from_tx(vp_owner, tx_env, |address| {
+            // Apply the withdrawal in a transaction
+            if record {
+                let epoch = tx::ctx().get_block_epoch().unwrap();
+                tx::ctx()
+                    .write(
+                        &testnet_faucet::last_withdrawal_epoch_key(
+                            address, &target,
+                        ),
+                        epoch,
+                    )
+                    .unwrap();
+            }
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                None,
+                amount,
+                &None,
+                &None,
+            )
+            .unwrap();
         });
+    }
 
+    /// Run the faucet's VP on the unsigned tx in the VP environment
+    fn validate_unsigned_tx() -> bool {
+        let vp_owner = address::testing::established_address_1();
         let vp_env = vp_host_env::take();
         let tx_data: Vec<u8> = vec![];
         let keys_changed: BTreeSet<storage::Key> =
-        vp_env.all_touched_storage_keys();
+            vp_env.all_touched_storage_keys();
         let verifiers: BTreeSet<Address> = BTreeSet::default();
         vp_host_env::set(vp_env);
-        assert!(!validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers).unwrap());
+        validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers).unwrap()
     }
 
-    /// Test that a debit of less than or equal to [`MAX_FREE_DEBIT`] tokens without a valid signature is accepted.
+    /// Test that an unsigned debit that isn't recorded as a withdrawal is
+    /// rejected.
     #[test]
-    fn test_unsigned_debit_under_limit_accepted(amount in (..MAX_FREE_DEBIT as u64 + 1)) {
-        // Initialize a tx environment
-        let mut tx_env = TestTxEnv::default();
+    fn test_unrecorded_debit_rejected() {
+        init_withdrawal(token::Amount::from(1), false, false);
+        assert!(!validate_unsigned_tx());
+    }
 
-        let vp_owner = address::testing::established_address_1();
-        let target = address::testing::established_address_2();
-        let token = address::nam();
-        let amount = token::Amount::from(amount);
+    /// Test that a second withdrawal to the same target in an epoch is
+    /// rejected.
+    #[test]
+    fn test_second_withdrawal_in_epoch_rejected() {
+        init_withdrawal(testnet_faucet::MAX_WITHDRAWAL, true, false);
+        assert!(validate_unsigned_tx());
 
-        // Spawn the accounts to be able to modify their storage
-        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+        init_withdrawal(token::Amount::from(1), true, true);
+        assert!(!validate_unsigned_tx());
+    }
 
-        // Credit the tokens to the VP owner before running the transaction to
-        // be able to transfer from it
-        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+    /// Test that the limit of the unsigned debits is the limit of the
+    /// withdrawals that the clients make.
+    #[test]
+    fn test_max_free_debit_is_max_withdrawal() {
+        assert_eq!(testnet_faucet::MAX_WITHDRAWAL.change(), MAX_FREE_DEBIT);
+    }
 
-        // Initialize VP environment from a transaction
-        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
-        // Apply transfer in a transaction
-        tx_host_env::token::transfer(tx::ctx(), address, &target, &token, None, amount, &None, &None).unwrap();
-        });
+    proptest! {
+    /// Test that a withdrawal of more than [`MAX_FREE_DEBIT`] tokens without a
+    /// valid signature is rejected.
+    #[test]
+    fn test_unsigned_debit_over_limit_rejected(
+        amount in (MAX_FREE_DEBIT as u64 + 1..)
+    ) {
+        init_withdrawal(token::Amount::from(amount), true, false);
+        assert!(!validate_unsigned_tx());
+    }
 
-        let vp_env = vp_host_env::take();
-        let tx_data: Vec<u8> = vec![];
-        let keys_changed: BTreeSet<storage::Key> =
-        vp_env.all_touched_storage_keys();
-        let verifiers: BTreeSet<Address> = BTreeSet::default();
-        vp_host_env::set(vp_env);
-        assert!(validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers).unwrap());
+    /// Test that a withdrawal of less than or equal to [`MAX_FREE_DEBIT`]
+    /// tokens without a valid signature is accepted.
+    #[test]
+    fn test_unsigned_debit_under_limit_accepted(
+        amount in (..MAX_FREE_DEBIT as u64 + 1)
+    ) {
+        init_withdrawal(token::Amount::from(amount), true, false);
+        assert!(validate_unsigned_tx());
     }
 
         /// Test that a signed tx that performs arbitrary storage writes or
@@ -360,7 +473,7 @@ mod tests {
 
             let mut vp_env = vp_host_env::take();
             let tx = vp_env.tx.clone();
-            let signed_tx = tx.sign(&keypair);
+            let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
             let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
             vp_env.tx = signed_tx;
             let keys_changed: BTreeSet<storage::Key> =
@@ -19,18 +19,33 @@ fn validate_tx(
         verifiers
     );
 
+    check_tx(ctx, tx_data, addr, keys_changed, verifiers)?.into_vp_result()
+}
+
+/// Check the tx's storage modifications, giving the failing key and reason
+/// on rejection.
+fn check_tx(
+    ctx: &Ctx,
+    tx_data: Vec<u8>,
+    addr: Address,
+    keys_changed: BTreeSet<storage::Key>,
+    verifiers: BTreeSet<Address>,
+) -> EnvResult<VpVerdict> {
     if !is_valid_tx(ctx, &tx_data)? {
-        return reject();
+        return Ok(VpVerdict::reject("The tx is not whitelisted"));
     }
 
     for key in keys_changed.iter() {
         if key.is_validity_predicate().is_some() {
             let vp: Vec<u8> = ctx.read_bytes_post(key)?.unwrap();
             if !is_vp_whitelisted(ctx, &vp)? {
-                return reject();
+                return Ok(VpVerdict::reject_key(
+                    key.clone(),
+                    "A VP update requires a whitelisted VP",
+                ));
             }
         }
     }
 
-    token::vp(ctx, &addr, &keys_changed, &verifiers)
+    token::verdict(ctx, &addr, &keys_changed, &verifiers)
 }
@@ -3,8 +3,9 @@
 //! This VP currently provides a signature verification against a public key for
 //! sending tokens (receiving tokens is permissive).
 //!
-//! It allows to bond, unbond and withdraw tokens to and from PoS system with a
-//! valid signature.
+//! It allows to bond, unbond and withdraw tokens to and from PoS system and to
+//! change the consensus key of, or unjail, a validator account with a valid
+//! signature.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
@@ -19,6 +20,10 @@ enum KeyType<'a> {
     Vp(&'a Address),
     Masp,
     GovernanceVote(&'a Address),
+    /// Governance keys other than the votes, validated by the governance VP
+    Governance,
+    Memo(&'a Address),
+    TokenLock(&'a Address, &'a Address, storage::BlockHeight),
     Unknown,
 }
 
@@ -39,6 +44,14 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
             } else {
                 Self::Unknown
             }
+        } else if gov_storage::is_governance_key(key) {
+            Self::Governance
+        } else if let Some(address) = token::is_memo_key(key) {
+            Self::Memo(address)
+        } else if let Some((owner, token, unlock_height)) =
+            token::is_lock_key(key)
+        {
+            Self::TokenLock(owner, token, unlock_height)
         } else if let Some(address) = key.is_validity_predicate() {
             Self::Vp(address)
         } else if token::is_masp_key(key) {
@@ -49,6 +62,39 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
     }
 }
 
+impl<'a> KeyType<'a> {
+    /// The reason of a rejected modification of a key of this type
+    fn rejection_reason(&self) -> &'static str {
+        match self {
+            Self::Token(_) => "A debit requires a valid signature",
+            Self::PoS => {
+                "A bond, an unbond or a change of a validator's consensus key \
+                 or state requires a valid signature"
+            }
+            Self::GovernanceVote(_) => "A vote requires a valid signature",
+            Self::Memo(_) => {
+                "A memo requires a valid signature and must not exceed the \
+                 maximum length"
+            }
+            Self::TokenLock(..) => {
+                "A lock must be credited in the same tx, within the maximum \
+                 horizon and number of locks, and it can only be removed with \
+                 a valid signature from its unlock height"
+            }
+            Self::Vp(_) => {
+                "A VP update requires a valid signature and a whitelisted VP"
+            }
+            Self::Masp | Self::Governance => {
+                "A modification requires a valid signature"
+            }
+            Self::Unknown => {
+                "A modification requires a valid signature and must not be in \
+                 a namespace reserved for the protocol"
+            }
+        }
+    }
+}
+
 #[validity_predicate]
 fn validate_tx(
     ctx: &Ctx,
@@ -64,6 +110,17 @@ fn validate_tx(
         verifiers
     );
 
+    check_tx(ctx, tx_data, addr, keys_changed)?.into_vp_result()
+}
+
+/// Check the tx's storage modifications, giving the failing key and reason
+/// on rejection.
+fn check_tx(
+    ctx: &Ctx,
+    tx_data: Vec<u8>,
+    addr: Address,
+    keys_changed: BTreeSet<storage::Key>,
+) -> EnvResult<VpVerdict> {
     let signed_tx_data =
         Lazy::new(|| SignedTxData::try_from_slice(&tx_data[..]));
 
@@ -84,7 +141,7 @@ fn validate_tx(
     });
 
     if !is_valid_tx(ctx, &tx_data)? {
-        return reject();
+        return Ok(VpVerdict::reject("The tx is not whitelisted"));
     }
 
     for key in keys_changed.iter() {
@@ -92,11 +149,35 @@ fn validate_tx(
         let is_valid = match key_type {
             KeyType::Token(owner) => {
                 if owner == &addr {
-                    let pre: token::Amount =
-                        ctx.read_pre(key)?.unwrap_or_default();
-                    let post: token::Amount =
-                        ctx.read_post(key)?.unwrap_or_default();
+                    let pre: token::Amount = match ctx.read_pre_typed(key) {
+                        Ok(pre) => pre.unwrap_or_default(),
+                        Err(err) => {
+                            return Ok(VpVerdict::reject_key(
+                                key.clone(),
+                                err.to_string(),
+                            ));
+                        }
+                    };
+                    let post: token::Amount = match ctx.read_post_typed(key) {
+                        Ok(post) => post.unwrap_or_default(),
+                        Err(err) => {
+                            return Ok(VpVerdict::reject_key(
+                                key.clone(),
+                                err.to_string(),
+                            ));
+                        }
+                    };
                     let change = post.change() - pre.change();
+                    // The tokens that are still locked cannot be debited
+                    if change < 0
+                        && token::spends_locked(ctx, &addr, key, post)?
+                    {
+                        return Ok(VpVerdict::reject_key(
+                            key.clone(),
+                            "A debit must not spend the tokens locked until a \
+                             later block height",
+                        ));
+                    }
                     // debit has to signed, credit doesn't
                     let valid = change >= 0 || addr == masp() || *valid_sig;
                     debug_log!(
@@ -124,7 +205,7 @@ fn validate_tx(
                 // Allow the account to be used in PoS
                 let bond_id = proof_of_stake::is_bond_key(key)
                     .or_else(|| proof_of_stake::is_unbond_key(key));
-                let valid = match bond_id {
+                let valid_bond_or_unbond_change = match bond_id {
                     Some(bond_id) => {
                         // Bonds and unbonds changes for this address
                         // must be signed
@@ -135,6 +216,33 @@ fn validate_tx(
                         true
                     }
                 };
+                let consensus_key =
+                    proof_of_stake::is_validator_consensus_key_key(key);
+                // Validator's consensus key change must be signed
+                let valid_consensus_key_change = match consensus_key {
+                    Some(source) => *source != addr || *valid_sig,
+                    None => true,
+                };
+                // The look-up of a validator's new consensus key must be signed
+                let valid_raw_hash_change =
+                    if proof_of_stake::is_validator_address_raw_hash_key(key)
+                        .is_some()
+                    {
+                        let validator: Option<Address> = ctx.read_post(key)?;
+                        validator.as_ref() != Some(&addr) || *valid_sig
+                    } else {
+                        true
+                    };
+                let state = proof_of_stake::is_validator_state_key(key);
+                // Validator's state change (unjailing) must be signed
+                let valid_state_change = match state {
+                    Some(source) => *source != addr || *valid_sig,
+                    None => true,
+                };
+                let valid = valid_bond_or_unbond_change
+                    && valid_consensus_key_change
+                    && valid_raw_hash_change
+                    && valid_state_change;
                 debug_log!(
                     "PoS key {} {}",
                     key,
@@ -149,6 +257,7 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::Governance => *valid_sig,
             KeyType::Vp(owner) => {
                 let has_post: bool = ctx.has_key_post(key)?;
                 if owner == &addr {
@@ -163,9 +272,63 @@ fn validate_tx(
                     is_vp_whitelisted(ctx, &vp)?
                 }
             }
+            KeyType::Memo(owner) => {
+                if owner == &addr {
+                    // A memo is recorded by a transfer, which is already
+                    // signed, so only its length is checked in addition
+                    let memo: Option<String> = match ctx.read_post_typed(key) {
+                        Ok(memo) => memo,
+                        Err(err) => {
+                            return Ok(VpVerdict::reject_key(
+                                key.clone(),
+                                err.to_string(),
+                            ));
+                        }
+                    };
+                    *valid_sig
+                        && memo.map_or(true, |memo| token::is_valid_memo(&memo))
+                } else {
+                    true
+                }
+            }
+            KeyType::TokenLock(owner, token, unlock_height) => {
+                if owner == &addr {
+                    let pre: token::Amount =
+                        ctx.read_pre(key)?.unwrap_or_default();
+                    let post: token::Amount =
+                        ctx.read_post(key)?.unwrap_or_default();
+                    if post >= pre {
+                        // A new lock must not restrict the tokens that the
+                        // account already owns, nor exceed the limits of the
+                        // locks
+                        let credited = token::is_lock_credited(
+                            ctx,
+                            &addr,
+                            token,
+                            &keys_changed,
+                        )?;
+                        credited
+                            && token::is_lock_within_limits(
+                                ctx,
+                                &addr,
+                                token,
+                                unlock_height,
+                            )?
+                    } else {
+                        // An expired lock may be removed by the owner
+                        *valid_sig && ctx.get_block_height()? >= unlock_height
+                    }
+                } else {
+                    true
+                }
+            }
             KeyType::Masp => true,
             KeyType::Unknown => {
-                if key.segments.get(0) == Some(&addr.to_db_key()) {
+                if key.is_reserved() {
+                    // The namespaces reserved for the protocol may only be
+                    // written by the protocol
+                    false
+                } else if key.segments.get(0) == Some(&addr.to_db_key()) {
                     // Unknown changes to this address space require a valid
                     // signature
                     *valid_sig
@@ -177,16 +340,21 @@ fn validate_tx(
         };
         if !is_valid {
             debug_log!("key {} modification failed vp", key);
-            return reject();
+            return Ok(VpVerdict::reject_key(
+                key.clone(),
+                key_type.rejection_reason(),
+            ));
         }
     }
 
-    accept()
+    Ok(VpVerdict::accept())
 }
 
 #[cfg(test)]
 mod tests {
     use address::testing::arb_non_internal_address;
+    use namada::proto::Tx;
+    use namada::types::chain::ChainId;
     // Use this as `#[test]` annotation to enable logging
     use namada_tests::log::test;
     use namada_tests::tx::{self, tx_host_env, TestTxEnv};
@@ -310,6 +478,128 @@ mod tests {
         );
     }
 
+    /// Test that the verdict of a rejected debit transfer without a valid
+    /// signature carries the debited balance key.
+    #[test]
+    fn test_unsigned_debit_transfer_rejected_key() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                None,
+                amount,
+                &None,
+                &None,
+            )
+            .unwrap();
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        vp_host_env::set(vp_env);
+        let verdict =
+            check_tx(&CTX, tx_data, vp_owner.clone(), keys_changed).unwrap();
+        assert!(!verdict.accepted);
+        assert_eq!(
+            verdict.rejected_key,
+            Some(token::balance_key(&token, &vp_owner))
+        );
+        assert!(verdict.reason.is_some());
+    }
+
+    /// Test that a malformed token balance of the VP owner in the prior state
+    /// is rejected, rather than read as a zero balance.
+    #[test]
+    fn test_malformed_pre_balance_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let token = address::nam();
+        let balance_key = token::balance_key(&token, &vp_owner);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &token]);
+
+        // Store a value that cannot be decoded as a token amount
+        tx_env.storage.write(&balance_key, vec![1_u8]).unwrap();
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+            // Overwrite the balance with a valid amount in a transaction
+            tx::ctx()
+                .write(&balance_key, token::Amount::from(10_098_123))
+                .unwrap();
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        vp_host_env::set(vp_env);
+        let verdict =
+            check_tx(&CTX, tx_data, vp_owner.clone(), keys_changed).unwrap();
+        assert!(!verdict.accepted);
+        assert_eq!(verdict.rejected_key, Some(balance_key));
+    }
+
+    /// Test that a malformed token balance of the VP owner written by a
+    /// transaction is rejected, rather than read as a zero balance.
+    #[test]
+    fn test_malformed_post_balance_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let token = address::nam();
+        let balance_key = token::balance_key(&token, &vp_owner);
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &token]);
+
+        // Credit the tokens to the VP owner before running the transaction
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+            // Write a value that cannot be decoded as a token amount
+            tx::ctx().write_bytes(&balance_key, [1_u8]).unwrap();
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(
+            !validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
+                .unwrap()
+        );
+    }
+
     /// Test that a debit transfer with a valid signature is accepted.
     #[test]
     fn test_signed_debit_transfer_accepted() {
@@ -350,7 +640,75 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(
+            validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
+                .unwrap()
+        );
+    }
+
+    /// Test that a signed transfer from the VP owner to many targets, with
+    /// duplicate outputs, is applied and accepted.
+    #[test]
+    fn test_signed_multi_transfer_accepted() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let alice = address::testing::established_address_2();
+        let bob = address::testing::established_address_3();
+        let token = address::nam();
+        let amount = token::Amount::from(100);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &alice, &bob, &token]);
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        let output = |target: &Address, amount: u64| token::TransferOutput {
+            target: target.clone(),
+            token: token.clone(),
+            amount: token::Amount::from(amount),
+        };
+        let transfer = token::MultiTransfer {
+            source: vp_owner.clone(),
+            outputs: vec![
+                output(&alice, 10),
+                output(&bob, 20),
+                output(&alice, 30),
+            ],
+        };
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+            // Apply the transfer in a transaction
+            tx_host_env::token::multi_transfer(tx::ctx(), &transfer).unwrap();
+        });
+
+        let balance = |owner: &Address| -> token::Amount {
+            CTX.read_post(&token::balance_key(&token, owner))
+                .unwrap()
+                .unwrap_or_default()
+        };
+        assert_eq!(balance(&vp_owner), token::Amount::from(40));
+        assert_eq!(balance(&alice), token::Amount::from(40));
+        assert_eq!(balance(&bob), token::Amount::from(20));
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -363,6 +721,456 @@ mod tests {
         );
     }
 
+    /// Test that a transfer to many targets whose total in one of the tokens
+    /// exceeds the source's balance is rejected without applying any of the
+    /// outputs.
+    #[test]
+    fn test_over_balance_multi_transfer_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let alice = address::testing::established_address_2();
+        let bob = address::testing::established_address_3();
+        let nam = address::nam();
+        let btc = address::btc();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &alice, &bob, &nam, &btc]);
+
+        // The balance of NAM covers the transfer, but the balance of BTC
+        // doesn't
+        tx_env.credit_tokens(&vp_owner, &nam, None, token::Amount::from(100));
+        tx_env.credit_tokens(&vp_owner, &btc, None, token::Amount::from(20));
+
+        let output = |target: &Address, token: &Address, amount: u64| {
+            token::TransferOutput {
+                target: target.clone(),
+                token: token.clone(),
+                amount: token::Amount::from(amount),
+            }
+        };
+        let transfer = token::MultiTransfer {
+            source: vp_owner,
+            outputs: vec![
+                output(&alice, &nam, 10),
+                output(&bob, &btc, 15),
+                output(&alice, &btc, 15),
+            ],
+        };
+
+        // The transfer is rejected
+        tx_host_env::set(tx_env);
+        assert!(
+            tx_host_env::token::multi_transfer(tx::ctx(), &transfer).is_err()
+        );
+
+        // None of the outputs has been applied
+        let tx_env = tx_host_env::take();
+        assert!(tx_env.all_touched_storage_keys().is_empty());
+    }
+
+    /// The height until which the tokens are locked in the lock tests
+    const UNLOCK_HEIGHT: u64 = 10;
+
+    /// Apply a signed debit transfer of `spent` tokens from the VP owner,
+    /// whose balance of `balance` tokens has `locked` tokens locked until
+    /// [`UNLOCK_HEIGHT`], in a block at the given height, and check it with
+    /// the VP
+    fn check_spend_with_lock(
+        balance: u64,
+        locked: u64,
+        spent: u64,
+        height: u64,
+    ) -> bool {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner and lock a part of them
+        tx_env.credit_tokens(
+            &vp_owner,
+            &token,
+            None,
+            token::Amount::from(balance),
+        );
+        let lock_key = token::lock_key(
+            &vp_owner,
+            &token,
+            storage::BlockHeight(UNLOCK_HEIGHT),
+        );
+        tx_env
+            .storage
+            .write(&lock_key, token::Amount::from(locked).try_to_vec().unwrap())
+            .unwrap();
+        tx_env.storage.block.height = storage::BlockHeight(height);
+
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                None,
+                token::Amount::from(spent),
+                &None,
+                &None,
+            )
+            .unwrap();
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers).unwrap()
+    }
+
+    /// Test that the locked tokens cannot be spent before the unlock height,
+    /// even with a valid signature.
+    #[test]
+    fn test_locked_spend_rejected_before_unlock() {
+        assert!(!check_spend_with_lock(100, 100, 1, 0));
+        assert!(!check_spend_with_lock(100, 100, 100, UNLOCK_HEIGHT - 1));
+    }
+
+    /// Test that the locked tokens can be spent from the unlock height on.
+    #[test]
+    fn test_locked_spend_accepted_from_unlock() {
+        assert!(check_spend_with_lock(100, 100, 100, UNLOCK_HEIGHT));
+        assert!(check_spend_with_lock(100, 100, 100, UNLOCK_HEIGHT + 1));
+    }
+
+    /// Test that only the locked portion of a balance is restricted before the
+    /// unlock height.
+    #[test]
+    fn test_partially_locked_spend() {
+        assert!(check_spend_with_lock(100, 60, 40, UNLOCK_HEIGHT - 1));
+        assert!(!check_spend_with_lock(100, 60, 41, UNLOCK_HEIGHT - 1));
+    }
+
+    /// Test that a time-locked transfer to the VP owner is applied and
+    /// accepted without its signature, and that the transferred tokens are
+    /// locked.
+    #[test]
+    fn test_time_locked_transfer_accepted() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let source = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from(100);
+        let unlock_height = storage::BlockHeight(UNLOCK_HEIGHT);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &source, &token]);
+
+        // Credit the tokens to the source before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&source, &token, None, amount);
+
+        let transfer = token::TimeLockedTransfer {
+            source,
+            target: vp_owner.clone(),
+            token: token.clone(),
+            amount,
+            unlock_height,
+        };
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+            // Apply the transfer in a transaction
+            tx_host_env::token::time_locked_transfer(tx::ctx(), &transfer)
+                .unwrap();
+        });
+
+        let locked: Option<token::Amount> = CTX
+            .read_post(&token::lock_key(&vp_owner, &token, unlock_height))
+            .unwrap();
+        assert_eq!(locked, Some(amount));
+        assert_eq!(
+            token::locked_amount(&CTX, &vp_owner, &token).unwrap(),
+            amount
+        );
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(
+            validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
+                .unwrap()
+        );
+    }
+
+    /// Test that a lock of the tokens that the VP owner already owns, which
+    /// are not credited by the tx, is rejected.
+    #[test]
+    fn test_uncredited_lock_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let token = address::nam();
+        let amount = token::Amount::from(100);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &token]);
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Lock the owner's tokens in a transaction
+            let lock_key = token::lock_key(
+                address,
+                &token,
+                storage::BlockHeight(UNLOCK_HEIGHT),
+            );
+            tx::ctx().write(&lock_key, amount).unwrap();
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(
+            !validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
+                .unwrap()
+        );
+    }
+
+    /// Apply a transfer of a token to the VP owner which locks it until the
+    /// given height, with the given number of other locks of the token already
+    /// in the VP owner's account, and check it with the VP
+    fn check_credited_lock(num_of_locks: usize, unlock_height: u64) -> bool {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let source = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from(1);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &source, &token]);
+        tx_env.credit_tokens(&source, &token, None, amount);
+        for i in 0..num_of_locks as u64 {
+            let lock_key = token::lock_key(
+                &vp_owner,
+                &token,
+                storage::BlockHeight(UNLOCK_HEIGHT + 1 + i),
+            );
+            tx_env
+                .storage
+                .write(&lock_key, amount.try_to_vec().unwrap())
+                .unwrap();
+        }
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply the locked transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                &source,
+                address,
+                &token,
+                None,
+                amount,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let lock_key = token::lock_key(
+                address,
+                &token,
+                storage::BlockHeight(unlock_height),
+            );
+            tx::ctx().write(&lock_key, amount).unwrap();
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers).unwrap()
+    }
+
+    /// Test that a lock is rejected beyond the maximum horizon, which bounds
+    /// how long a lock remains in the VP owner's account.
+    #[test]
+    fn test_lock_horizon() {
+        assert!(check_credited_lock(0, UNLOCK_HEIGHT));
+        assert!(check_credited_lock(0, token::MAX_LOCK_HORIZON_BLOCKS));
+        assert!(!check_credited_lock(0, token::MAX_LOCK_HORIZON_BLOCKS + 1));
+    }
+
+    /// Test that a new lock is rejected once the VP owner's account has the
+    /// maximum number of locks of the token.
+    #[test]
+    fn test_max_locks_per_token() {
+        assert!(check_credited_lock(
+            token::MAX_LOCKS_PER_TOKEN - 1,
+            UNLOCK_HEIGHT
+        ));
+        assert!(!check_credited_lock(
+            token::MAX_LOCKS_PER_TOKEN,
+            UNLOCK_HEIGHT
+        ));
+    }
+
+    /// Apply a signed debit transfer from the VP owner which records the given
+    /// memo, and check it with the VP
+    fn check_signed_transfer_with_memo(memo: String) -> VpVerdict {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner before running the transaction to
+        // be able to transfer from it
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction and record its memo
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                None,
+                amount,
+                &None,
+                &None,
+            )
+            .unwrap();
+            let memo_key = token::memo_key(
+                address,
+                tx::ctx().get_block_height().unwrap(),
+                tx::ctx().get_tx_index().unwrap(),
+            );
+            tx::ctx().write(&memo_key, memo).unwrap();
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        vp_host_env::set(vp_env);
+        check_tx(&CTX, tx_data, vp_owner, keys_changed).unwrap()
+    }
+
+    /// Test that a signed transfer with a memo of the maximum length is
+    /// accepted.
+    #[test]
+    fn test_signed_transfer_with_memo_accepted() {
+        let verdict =
+            check_signed_transfer_with_memo("a".repeat(token::MAX_MEMO_LENGTH));
+        assert!(verdict.accepted, "{:?}", verdict.reason);
+    }
+
+    /// Test that a signed transfer with a memo exceeding the maximum length
+    /// is rejected on the memo's key.
+    #[test]
+    fn test_signed_transfer_with_too_long_memo_rejected() {
+        let verdict = check_signed_transfer_with_memo(
+            "a".repeat(token::MAX_MEMO_LENGTH + 1),
+        );
+        assert!(!verdict.accepted);
+        assert_eq!(
+            verdict.rejected_key.as_ref().and_then(token::is_memo_key),
+            Some(&address::testing::established_address_1())
+        );
+    }
+
+    /// Test the VP against envs built with balance changes of the VP owner: a
+    /// credit is accepted without a signature, while a debit that deletes the
+    /// balance requires a valid signature.
+    #[test]
+    fn test_built_env_balance_changes() {
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let token = address::nam();
+        let amount = token::Amount::from(10_098_123);
+
+        // A credit
+        let vp_env = TestVpEnv::builder(vp_owner.clone())
+            .balance(&token, None, Some(amount))
+            .build();
+        let keys_changed = vp_env.keys_changed.clone();
+        assert!(vp_env.verifiers.contains(&vp_owner));
+        vp_host_env::set(vp_env);
+        let verdict =
+            check_tx(&CTX, vec![], vp_owner.clone(), keys_changed).unwrap();
+        assert!(verdict.accepted);
+
+        // An unsigned debit of the whole balance, which is deleted
+        let balance_key = token::balance_key(&token, &vp_owner);
+        let vp_env = TestVpEnv::builder(vp_owner.clone())
+            .balance(&token, Some(amount), None)
+            .public_key(&public_key)
+            .build();
+        let keys_changed = vp_env.keys_changed.clone();
+        assert!(keys_changed.contains(&balance_key));
+        vp_host_env::set(vp_env);
+        let verdict =
+            check_tx(&CTX, vec![], vp_owner.clone(), keys_changed).unwrap();
+        assert!(!verdict.accepted);
+        assert_eq!(verdict.rejected_key, Some(balance_key));
+
+        // The same debit, signed
+        let signed_tx =
+            Tx::new(vec![], None).sign(&keypair, &ChainId::default());
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        let vp_env = TestVpEnv::builder(vp_owner.clone())
+            .balance(&token, Some(amount), None)
+            .public_key(&public_key)
+            .tx(signed_tx)
+            .build();
+        let keys_changed = vp_env.keys_changed.clone();
+        vp_host_env::set(vp_env);
+        let verdict = check_tx(&CTX, tx_data, vp_owner, keys_changed).unwrap();
+        assert!(verdict.accepted);
+    }
+
     /// Test that a transfer on with accounts other than self is accepted.
     #[test]
     fn test_transfer_between_other_parties_accepted() {
@@ -498,7 +1306,7 @@ mod tests {
 
             let mut vp_env = vp_host_env::take();
             let tx = vp_env.tx.clone();
-            let signed_tx = tx.sign(&keypair);
+            let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
             let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
             vp_env.tx = signed_tx;
             let keys_changed: BTreeSet<storage::Key> =
@@ -509,6 +1317,136 @@ mod tests {
         }
     }
 
+    /// Test that a write into a namespace reserved for the protocol is
+    /// rejected even with a valid signature, while a write to another
+    /// account's key is accepted without a signature.
+    #[test]
+    fn test_reserved_storage_write() {
+        let vp_owner = address::testing::established_address_1();
+        let other = address::testing::established_address_2();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let parameters =
+            Address::Internal(address::InternalAddress::Parameters);
+        let reserved_key = Key::from(parameters.to_db_key())
+            .push(&"data".to_owned())
+            .unwrap();
+        let other_key = Key::from(other.to_db_key())
+            .push(&"data".to_owned())
+            .unwrap();
+
+        let validate = |key: &Key, sign: bool| {
+            // Initialize a tx environment
+            let mut tx_env = TestTxEnv::default();
+            tx_env.spawn_accounts([&vp_owner, &other]);
+            tx_env.write_public_key(&vp_owner, &public_key);
+
+            // Initialize VP environment from a transaction
+            vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+                tx::ctx().write(key, 1_u64).unwrap();
+            });
+
+            let mut vp_env = vp_host_env::take();
+            let tx_data: Vec<u8> = if sign {
+                let signed_tx =
+                    vp_env.tx.clone().sign(&keypair, &vp_env.storage.chain_id);
+                let tx_data = signed_tx.data.as_ref().cloned().unwrap();
+                vp_env.tx = signed_tx;
+                tx_data
+            } else {
+                vec![]
+            };
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = BTreeSet::default();
+            vp_host_env::set(vp_env);
+            validate_tx(
+                &CTX,
+                tx_data,
+                vp_owner.clone(),
+                keys_changed,
+                verifiers,
+            )
+            .unwrap()
+        };
+
+        assert!(!validate(&reserved_key, false));
+        assert!(!validate(&reserved_key, true));
+        assert!(validate(&other_key, false));
+    }
+
+    /// Test that a change of the consensus key or of the state (to unjail it)
+    /// of a validator account is accepted only with a valid signature.
+    #[test]
+    fn test_consensus_key_and_state_change() {
+        let vp_owner = address::testing::established_address_1();
+        let other = address::testing::established_address_2();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let consensus_key = key::testing::keypair_2().ref_to();
+        let consensus_key_key =
+            proof_of_stake::validator_consensus_key_key(&vp_owner);
+        let raw_hash_key = proof_of_stake::validator_address_raw_hash_key(
+            key::tm_consensus_key_raw_hash(&consensus_key),
+        );
+        let state_key = proof_of_stake::validator_state_key(&vp_owner);
+        let other_state_key = proof_of_stake::validator_state_key(&other);
+
+        let validate = |key: &Key, value: Vec<u8>, sign: bool| {
+            // Initialize a tx environment
+            let mut tx_env = TestTxEnv::default();
+            tx_env.spawn_accounts([&vp_owner, &other]);
+            tx_env.write_public_key(&vp_owner, &public_key);
+
+            // Initialize VP environment from a transaction
+            vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+                tx::ctx().write_bytes(key, &value).unwrap();
+            });
+
+            let mut vp_env = vp_host_env::take();
+            let tx_data: Vec<u8> = if sign {
+                let signed_tx =
+                    vp_env.tx.clone().sign(&keypair, &vp_env.storage.chain_id);
+                let tx_data = signed_tx.data.as_ref().cloned().unwrap();
+                vp_env.tx = signed_tx;
+                tx_data
+            } else {
+                vec![]
+            };
+            let keys_changed: BTreeSet<storage::Key> =
+                vp_env.all_touched_storage_keys();
+            let verifiers: BTreeSet<Address> = BTreeSet::default();
+            vp_host_env::set(vp_env);
+            validate_tx(
+                &CTX,
+                tx_data,
+                vp_owner.clone(),
+                keys_changed,
+                verifiers,
+            )
+            .unwrap()
+        };
+
+        let consensus_key_value = consensus_key.try_to_vec().unwrap();
+        assert!(!validate(
+            &consensus_key_key,
+            consensus_key_value.clone(),
+            false
+        ));
+        assert!(validate(&consensus_key_key, consensus_key_value, true));
+
+        // The look-up of the key is only guarded for the VP's owner
+        let owner_value = vp_owner.try_to_vec().unwrap();
+        assert!(!validate(&raw_hash_key, owner_value.clone(), false));
+        assert!(validate(&raw_hash_key, owner_value, true));
+        assert!(validate(&raw_hash_key, other.try_to_vec().unwrap(), false));
+
+        // The value of a validator's state isn't checked by this VP
+        assert!(!validate(&state_key, vec![], false));
+        assert!(validate(&state_key, vec![], true));
+        assert!(validate(&other_state_key, vec![], false));
+    }
+
     /// Test that a validity predicate update without a valid signature is
     /// rejected.
     #[test]
@@ -572,7 +1510,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -585,6 +1523,76 @@ mod tests {
         );
     }
 
+    /// Test that a validity predicate update signed with a secp256k1 key is
+    /// accepted when the account's public key is also secp256k1.
+    #[test]
+    fn test_signed_vp_update_secp256k1_accepted() {
+        let keypair = gen_secp256k1_keypair();
+        let public_key = keypair.ref_to();
+        assert!(signed_vp_update(&public_key, &keypair));
+    }
+
+    /// Test that a validity predicate update is rejected when the scheme of
+    /// the signature doesn't match the scheme of the account's public key.
+    #[test]
+    fn test_signed_vp_update_mismatched_scheme_rejected() {
+        // An ed25519 signature for a secp256k1 account
+        let public_key = gen_secp256k1_keypair().ref_to();
+        let keypair = key::testing::keypair_1();
+        assert!(!signed_vp_update(&public_key, &keypair));
+
+        // A secp256k1 signature for an ed25519 account
+        let public_key = key::testing::keypair_1().ref_to();
+        let keypair = gen_secp256k1_keypair();
+        assert!(!signed_vp_update(&public_key, &keypair));
+    }
+
+    fn gen_secp256k1_keypair() -> key::common::SecretKey {
+        use namada_vp_prelude::key::SecretKey;
+        key::testing::gen_keypair::<key::secp256k1::SigScheme>()
+            .try_to_sk()
+            .unwrap()
+    }
+
+    /// Run the VP on a validity predicate update of an account with the given
+    /// public key, signed with the given keypair.
+    fn signed_vp_update(
+        public_key: &key::common::PublicKey,
+        keypair: &key::common::SecretKey,
+    ) -> bool {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+        tx_env.init_parameters(None, None, None);
+
+        let vp_owner = address::testing::established_address_1();
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+
+        tx_env.write_public_key(&vp_owner, public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Update VP in a transaction
+            tx::ctx()
+                .update_validity_predicate(address, &vp_code)
+                .unwrap();
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(keypair, &vp_env.storage.chain_id);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers).unwrap()
+    }
+
     /// Test that a validity predicate update is rejected if not whitelisted
     #[test]
     fn test_signed_vp_update_not_whitelisted_rejected() {
@@ -613,7 +1621,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -656,7 +1664,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -703,7 +1711,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -745,7 +1753,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -7,8 +7,9 @@
 //! valid signature.
 //!
 //! Currently, the only difference with respect to the user VP is for a tx to
-//! change a validator's commission rate: we require a valid signature only from
-//! the validator whose commission rate is being changed.
+//! change a validator's commission rate, consensus key or state (to unjail
+//! it): we require a valid signature only from the validator whose commission
+//! rate, consensus key or state is being changed.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
@@ -21,6 +22,9 @@ enum KeyType<'a> {
     PoS,
     Vp(&'a Address),
     GovernanceVote(&'a Address),
+    /// Governance keys other than the votes, validated by the governance VP
+    Governance,
+    TokenLock(&'a Address, &'a Address, storage::BlockHeight),
     Unknown,
 }
 
@@ -41,6 +45,12 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
             } else {
                 Self::Unknown
             }
+        } else if gov_storage::is_governance_key(key) {
+            Self::Governance
+        } else if let Some((owner, token, unlock_height)) =
+            token::is_lock_key(key)
+        {
+            Self::TokenLock(owner, token, unlock_height)
         } else if let Some(address) = key.is_validity_predicate() {
             Self::Vp(address)
         } else {
@@ -49,6 +59,33 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
     }
 }
 
+impl<'a> KeyType<'a> {
+    /// The reason of a rejected modification of a key of this type
+    fn rejection_reason(&self) -> &'static str {
+        match self {
+            Self::Token(_) => "A debit requires a valid signature",
+            Self::PoS => {
+                "A bond, an unbond or a change of a validator's commission \
+                 rate, consensus key or state requires a valid signature"
+            }
+            Self::GovernanceVote(_) => "A vote requires a valid signature",
+            Self::TokenLock(..) => {
+                "A lock must be credited in the same tx, within the maximum \
+                 horizon and number of locks, and it can only be removed with \
+                 a valid signature from its unlock height"
+            }
+            Self::Vp(_) => {
+                "A VP update requires a valid signature and a whitelisted VP"
+            }
+            Self::Governance => "A modification requires a valid signature",
+            Self::Unknown => {
+                "A modification requires a valid signature and must not be in \
+                 a namespace reserved for the protocol"
+            }
+        }
+    }
+}
+
 #[validity_predicate]
 fn validate_tx(
     ctx: &Ctx,
@@ -64,6 +101,17 @@ fn validate_tx(
         verifiers
     );
 
+    check_tx(ctx, tx_data, addr, keys_changed)?.into_vp_result()
+}
+
+/// Check the tx's storage modifications, giving the failing key and reason
+/// on rejection.
+fn check_tx(
+    ctx: &Ctx,
+    tx_data: Vec<u8>,
+    addr: Address,
+    keys_changed: BTreeSet<storage::Key>,
+) -> EnvResult<VpVerdict> {
     let signed_tx_data =
         Lazy::new(|| SignedTxData::try_from_slice(&tx_data[..]));
 
@@ -84,7 +132,7 @@ fn validate_tx(
     });
 
     if !is_valid_tx(ctx, &tx_data)? {
-        return reject();
+        return Ok(VpVerdict::reject("The tx is not whitelisted"));
     }
 
     for key in keys_changed.iter() {
@@ -97,6 +145,16 @@ fn validate_tx(
                     let post: token::Amount =
                         ctx.read_post(key)?.unwrap_or_default();
                     let change = post.change() - pre.change();
+                    // The tokens that are still locked cannot be debited
+                    if change < 0
+                        && token::spends_locked(ctx, &addr, key, post)?
+                    {
+                        return Ok(VpVerdict::reject_key(
+                            key.clone(),
+                            "A debit must not spend the tokens locked until a \
+                             later block height",
+                        ));
+                    }
                     // debit has to signed, credit doesn't
                     let valid = change >= 0 || *valid_sig;
                     debug_log!(
@@ -142,8 +200,34 @@ fn validate_tx(
                     Some(source) => *source != addr || *valid_sig,
                     None => true,
                 };
-                let valid =
-                    valid_bond_or_unbond_change && valid_commission_rate_change;
+                let consensus_key =
+                    proof_of_stake::is_validator_consensus_key_key(key);
+                // Validator's consensus key change must be signed
+                let valid_consensus_key_change = match consensus_key {
+                    Some(source) => *source != addr || *valid_sig,
+                    None => true,
+                };
+                // The look-up of a validator's new consensus key must be signed
+                let valid_raw_hash_change =
+                    if proof_of_stake::is_validator_address_raw_hash_key(key)
+                        .is_some()
+                    {
+                        let validator: Option<Address> = ctx.read_post(key)?;
+                        validator.as_ref() != Some(&addr) || *valid_sig
+                    } else {
+                        true
+                    };
+                let state = proof_of_stake::is_validator_state_key(key);
+                // Validator's state change (unjailing) must be signed
+                let valid_state_change = match state {
+                    Some(source) => *source != addr || *valid_sig,
+                    None => true,
+                };
+                let valid = valid_bond_or_unbond_change
+                    && valid_commission_rate_change
+                    && valid_consensus_key_change
+                    && valid_raw_hash_change
+                    && valid_state_change;
                 debug_log!(
                     "PoS key {} {}",
                     key,
@@ -158,6 +242,7 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::Governance => *valid_sig,
             KeyType::Vp(owner) => {
                 let has_post: bool = ctx.has_key_post(key)?;
                 if owner == &addr {
@@ -172,8 +257,43 @@ fn validate_tx(
                     is_vp_whitelisted(ctx, &vp)?
                 }
             }
+            KeyType::TokenLock(owner, token, unlock_height) => {
+                if owner == &addr {
+                    let pre: token::Amount =
+                        ctx.read_pre(key)?.unwrap_or_default();
+                    let post: token::Amount =
+                        ctx.read_post(key)?.unwrap_or_default();
+                    if post >= pre {
+                        // A new lock must not restrict the tokens that the
+                        // account already owns, nor exceed the limits of the
+                        // locks
+                        let credited = token::is_lock_credited(
+                            ctx,
+                            &addr,
+                            token,
+                            &keys_changed,
+                        )?;
+                        credited
+                            && token::is_lock_within_limits(
+                                ctx,
+                                &addr,
+                                token,
+                                unlock_height,
+                            )?
+                    } else {
+                        // An expired lock may be removed by the owner
+                        *valid_sig && ctx.get_block_height()? >= unlock_height
+                    }
+                } else {
+                    true
+                }
+            }
             KeyType::Unknown => {
-                if key.segments.get(0) == Some(&addr.to_db_key()) {
+                if key.is_reserved() {
+                    // The namespaces reserved for the protocol may only be
+                    // written by the protocol
+                    false
+                } else if key.segments.get(0) == Some(&addr.to_db_key()) {
                     // Unknown changes to this address space require a valid
                     // signature
                     *valid_sig
@@ -185,11 +305,14 @@ fn validate_tx(
         };
         if !is_valid {
             debug_log!("key {} modification failed vp", key);
-            return reject();
+            return Ok(VpVerdict::reject_key(
+                key.clone(),
+                key_type.rejection_reason(),
+            ));
         }
     }
 
-    accept()
+    Ok(VpVerdict::accept())
 }
 
 #[cfg(test)]
@@ -272,7 +395,8 @@ mod tests {
         );
     }
 
-    /// Test that a debit transfer without a valid signature is rejected.
+    /// Test that a debit transfer without a valid signature is rejected with
+    /// the debited balance key.
     #[test]
     fn test_unsigned_debit_transfer_rejected() {
         // Initialize a tx environment
@@ -312,10 +436,22 @@ mod tests {
             vp_env.all_touched_storage_keys();
         let verifiers: BTreeSet<Address> = BTreeSet::default();
         vp_host_env::set(vp_env);
-        assert!(
-            !validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
-                .unwrap()
+        assert!(!validate_tx(
+            &CTX,
+            tx_data,
+            vp_owner.clone(),
+            keys_changed,
+            verifiers
+        )
+        .unwrap());
+        // The rejected balance key is given to the host
+        let rejection = vp_host_env::with(|env| env.rejection.clone())
+            .expect("The rejection must be given");
+        assert_eq!(
+            rejection.rejected_key,
+            Some(token::balance_key(&token, &vp_owner))
         );
+        assert!(rejection.reason.is_some());
     }
 
     /// Test that a debit transfer with a valid signature is accepted.
@@ -358,7 +494,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -371,6 +507,66 @@ mod tests {
         );
     }
 
+    /// Test that a debit transfer of the tokens locked until a later block
+    /// height is rejected, even with a valid signature.
+    #[test]
+    fn test_signed_locked_debit_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let target = address::testing::established_address_2();
+        let token = address::nam();
+        let amount = token::Amount::from(10_098_123);
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner, &target, &token]);
+
+        // Credit the tokens to the VP owner and lock them until a later block
+        // height
+        tx_env.credit_tokens(&vp_owner, &token, None, amount);
+        let lock_key =
+            token::lock_key(&vp_owner, &token, storage::BlockHeight(10));
+        tx_env
+            .storage
+            .write(&lock_key, amount.try_to_vec().unwrap())
+            .unwrap();
+
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Apply transfer in a transaction
+            tx_host_env::token::transfer(
+                tx::ctx(),
+                address,
+                &target,
+                &token,
+                None,
+                amount,
+                &None,
+                &None,
+            )
+            .unwrap();
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx = vp_env.tx.clone();
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(
+            !validate_tx(&CTX, tx_data, vp_owner, keys_changed, verifiers)
+                .unwrap()
+        );
+    }
+
     /// Test that a transfer on with accounts other than self is accepted.
     #[test]
     fn test_transfer_between_other_parties_accepted() {
@@ -506,7 +702,7 @@ mod tests {
 
             let mut vp_env = vp_host_env::take();
             let tx = vp_env.tx.clone();
-            let signed_tx = tx.sign(&keypair);
+            let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
             let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
             vp_env.tx = signed_tx;
             let keys_changed: BTreeSet<storage::Key> =
@@ -580,7 +776,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -621,7 +817,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -664,7 +860,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -711,7 +907,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -753,7 +949,7 @@ mod tests {
 
         let mut vp_env = vp_host_env::take();
         let tx = vp_env.tx.clone();
-        let signed_tx = tx.sign(&keypair);
+        let signed_tx = tx.sign(&keypair, &vp_env.storage.chain_id);
         let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
         vp_env.tx = signed_tx;
         let keys_changed: BTreeSet<storage::Key> =
@@ -142,6 +142,7 @@ pub mod main {
             amount,
             key: _,
             shielded: _,
+            memo: _,
         } = transfer;
         let target_key = token::balance_key(&token, &target);
         let mut target_bal: token::Amount =
@@ -152,6 +153,66 @@ pub mod main {
     }
 }
 
+/// A tx that escrows the amount and fee of a transfer to Ethereum from its
+/// sender and adds the transfer to the Ethereum bridge's queue.
+#[cfg(feature = "tx_transfer_to_ethereum")]
+pub mod main {
+    use eth_bridge::transfer::TransferToEthereum;
+    use namada_tx_prelude::*;
+
+    #[transaction]
+    fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+        let signed = SignedTxData::try_from_slice(&tx_data[..])
+            .wrap_err("failed to decode SignedTxData")?;
+        let data = signed.data.ok_or_err_msg("Missing data")?;
+        let transfer = TransferToEthereum::try_from_slice(&data[..])
+            .wrap_err("failed to decode TransferToEthereum")?;
+        log_string(format!("apply_tx called with transfer: {:#?}", transfer));
+        token::transfer(
+            ctx,
+            &transfer.sender,
+            &eth_bridge::ADDRESS,
+            &transfer.asset,
+            None,
+            transfer.amount + transfer.fee,
+            &None,
+            &None,
+        )?;
+        eth_bridge::storage::push_to_queue(ctx, transfer)
+    }
+}
+
+/// A tx that cancels a pending transfer to Ethereum by removing it from the
+/// Ethereum bridge's queue and refunds its amount and fee to its sender.
+#[cfg(feature = "tx_cancel_transfer_to_ethereum")]
+pub mod main {
+    use eth_bridge::transfer::TransferToEthereum;
+    use namada_tx_prelude::*;
+
+    #[transaction]
+    fn apply_tx(ctx: &mut Ctx, tx_data: Vec<u8>) -> TxResult {
+        let signed = SignedTxData::try_from_slice(&tx_data[..])
+            .wrap_err("failed to decode SignedTxData")?;
+        let data = signed.data.ok_or_err_msg("Missing data")?;
+        let transfer = TransferToEthereum::try_from_slice(&data[..])
+            .wrap_err("failed to decode TransferToEthereum")?;
+        log_string(format!("apply_tx called with transfer: {:#?}", transfer));
+        if !eth_bridge::storage::remove_from_queue(ctx, &transfer)? {
+            return Err(Error::new_const("The transfer is no longer pending"));
+        }
+        token::transfer(
+            ctx,
+            &eth_bridge::ADDRESS,
+            &transfer.sender,
+            &transfer.asset,
+            None,
+            transfer.amount + transfer.fee,
+            &None,
+            &None,
+        )
+    }
+}
+
 /// A VP that always returns `true`.
 #[cfg(feature = "vp_always_true")]
 pub mod main {